@@ -0,0 +1,94 @@
+use crate::storage::{AtomicPageId, PageId};
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+
+/// Allocate page ids for [`super::buffer_pool_manager::BufferPoolManager`]
+pub trait PageAllocator: Send + Sync {
+    fn allocate(&self) -> PageId;
+
+    /// Give back a page id which is no longer used
+    fn free(&self, page_id: PageId);
+}
+
+/// Hand out increasing page ids, freed page ids are never reused
+#[derive(Debug, Default)]
+pub struct BumpAllocator {
+    next_page_id: AtomicPageId,
+}
+
+impl BumpAllocator {
+    pub fn new(next_page_id: PageId) -> Self {
+        Self {
+            next_page_id: AtomicPageId::new(next_page_id),
+        }
+    }
+}
+
+impl PageAllocator for BumpAllocator {
+    fn allocate(&self) -> PageId {
+        self.next_page_id.fetch_add(1, Ordering::AcqRel)
+    }
+
+    fn free(&self, _page_id: PageId) {}
+}
+
+/// Reuse freed page ids first, the smallest freed page id is handed out first
+#[derive(Debug, Default)]
+pub struct FreeListAllocator {
+    bump: BumpAllocator,
+    free_list: Mutex<Vec<PageId>>,
+}
+
+impl FreeListAllocator {
+    pub fn new(next_page_id: PageId) -> Self {
+        Self {
+            bump: BumpAllocator::new(next_page_id),
+            free_list: Default::default(),
+        }
+    }
+}
+
+impl PageAllocator for FreeListAllocator {
+    fn allocate(&self) -> PageId {
+        let mut free_list = self.free_list.lock().unwrap();
+        match free_list.pop() {
+            Some(page_id) => page_id,
+            None => self.bump.allocate(),
+        }
+    }
+
+    fn free(&self, page_id: PageId) {
+        let mut free_list = self.free_list.lock().unwrap();
+        if let Err(index) = free_list.binary_search_by(|id| page_id.cmp(id)) {
+            free_list.insert(index, page_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bump_allocator() {
+        let allocator = BumpAllocator::new(3);
+        assert_eq!(allocator.allocate(), 3);
+        assert_eq!(allocator.allocate(), 4);
+        allocator.free(3);
+        assert_eq!(allocator.allocate(), 5);
+    }
+
+    #[test]
+    fn free_list_allocator() {
+        let allocator = FreeListAllocator::new(0);
+        for page_id in 0..5 {
+            assert_eq!(allocator.allocate(), page_id);
+        }
+        allocator.free(3);
+        allocator.free(1);
+        allocator.free(1);
+        assert_eq!(allocator.allocate(), 1);
+        assert_eq!(allocator.allocate(), 3);
+        assert_eq!(allocator.allocate(), 5);
+    }
+}
@@ -1,35 +1,132 @@
+use crate::buffer::allocator::{BumpAllocator, PageAllocator};
 use crate::buffer::lru_k_replacer::LruKReplacer;
 use crate::buffer::{Error, FrameId};
 use crate::encoding::{Decoder, Encoder};
-use crate::storage::disk::disk_manager::DiskManager;
+use crate::storage::disk::disk_manager::{DiskManager, DurabilityMode};
 use crate::storage::page::index::Node;
 use crate::storage::page::table::{Table, TableNode};
-use crate::storage::page::{Page, PageTrait};
-use crate::storage::{PageId, PAGE_SIZE};
-use std::collections::{HashMap, VecDeque};
+use crate::storage::page::{self, Page, PageTrait};
+use crate::storage::{PageId, CHECKSUM_SIZE, PAGE_SIZE};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::future::Future;
 use std::ops::{Deref, DerefMut};
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use tokio::sync::{
-    OwnedRwLockReadGuard, OwnedRwLockWriteGuard, RwLock, RwLockReadGuard, RwLockWriteGuard,
+    Notify, OwnedRwLockReadGuard, OwnedRwLockWriteGuard, RwLock, RwLockReadGuard, RwLockWriteGuard,
 };
 
 pub struct BufferPoolManager {
     inner: RwLock<Inner>,
     disk_manager: DiskManager,
-    next_page_id: AtomicUsize,
+    allocator: Box<dyn PageAllocator>,
     pool_size: usize,
+    page_size: usize,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+    eviction_hook: std::sync::RwLock<Option<Arc<EvictionHook>>>,
+    quarantine: std::sync::RwLock<HashSet<PageId>>,
+}
+
+/// A page whose data is latched to be flushed
+type LatchedPage<'a> = (&'a Arc<Page>, OwnedRwLockWriteGuard<Box<[u8]>>);
+
+/// Called with every page evicted from the buffer pool
+pub type EvictionHook = Box<dyn Fn(&EvictedPage) + Send + Sync>;
+
+/// A page evicted from the buffer pool, its data is taken before the frame is reused
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvictedPage {
+    pub page_id: PageId,
+    pub data: Box<[u8]>,
+}
+
+/// The counters of page fetches since the buffer pool is created
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BufferPoolStats {
+    /// The fetches served from the buffer pool
+    pub hits: usize,
+    /// The fetches which read the page from disk
+    pub misses: usize,
+}
+
+impl BufferPoolStats {
+    /// The number of page fetches
+    pub fn accesses(&self) -> usize {
+        self.hits + self.misses
+    }
+}
+
+/// The state of a frame of the buffer pool, see [`BufferPoolManager::frame_snapshot`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameInfo {
+    pub frame_id: FrameId,
+    /// The page held by the frame, `None` if the frame is free
+    pub page_id: Option<PageId>,
+    pub pin_count: u32,
+    pub dirty: bool,
+    /// Whether the replacer may evict the frame
+    pub evictable: bool,
+}
+
+/// Counts the unpin tasks spawned by dropped [`PageRef`]s,
+/// so [`BufferPoolManager::shutdown`] can wait for them
+#[derive(Default)]
+struct Tasks {
+    pending: AtomicUsize,
+    idle: Notify,
+}
+
+impl Tasks {
+    fn spawn(self: &Arc<Self>, task: impl Future<Output = ()> + Send + 'static) {
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        let tasks = self.clone();
+        tokio::spawn(async move {
+            task.await;
+            if tasks.pending.fetch_sub(1, Ordering::SeqCst) == 1 {
+                tasks.idle.notify_waiters();
+            }
+        });
+    }
+
+    async fn join(&self) {
+        loop {
+            // the notification must be registered before checking, or a wakeup may be missed
+            let idle = self.idle.notified();
+            if self.pending.load(Ordering::SeqCst) == 0 {
+                return;
+            }
+            idle.await;
+        }
+    }
 }
 
 struct Inner {
     pages: Vec<Arc<Page>>,
     replacer: Arc<RwLock<LruKReplacer>>,
+    tasks: Arc<Tasks>,
     page_table: HashMap<PageId, FrameId>,
     free_list: VecDeque<FrameId>,
 }
 
 impl BufferPoolManager {
     pub async fn new(pool_size: usize, k: usize, disk_manager: DiskManager) -> Result<Self, Error> {
+        Self::with_page_size(pool_size, k, disk_manager, PAGE_SIZE).await
+    }
+
+    /// Create a buffer pool whose pages are `page_size` bytes rather than [`PAGE_SIZE`],
+    /// a database file must always be opened with the page size it's created with.
+    /// Fails with [`Error::PageSize`] if the page can't hold more than its checksum.
+    pub async fn with_page_size(
+        pool_size: usize,
+        k: usize,
+        disk_manager: DiskManager,
+        page_size: usize,
+    ) -> Result<Self, Error> {
+        if page_size <= CHECKSUM_SIZE {
+            return Err(Error::PageSize(page_size));
+        }
+        let disk_manager = disk_manager.with_page_size(page_size);
         let replacer = Arc::new(RwLock::new(LruKReplacer::new(pool_size, k)));
         let mut free_list = VecDeque::with_capacity(pool_size);
         for frame_id in 0..pool_size {
@@ -37,48 +134,78 @@ impl BufferPoolManager {
         }
         let pages = {
             let mut v = Vec::with_capacity(pool_size);
-            (0..pool_size).for_each(|_| v.push(Arc::new(Page::new(0))));
+            (0..pool_size).for_each(|_| v.push(Arc::new(Page::new(0, page_size))));
             v
         };
         let inner = Inner {
             pages,
             replacer,
+            tasks: Default::default(),
             page_table: HashMap::with_capacity(pool_size),
             free_list,
         };
+        // Never hand out a page id which is already used by an existing database file
+        let next_page_id = disk_manager.num_pages().await?;
         Ok(Self {
             inner: RwLock::new(inner),
             disk_manager,
-            next_page_id: AtomicUsize::new(0),
+            allocator: Box::new(BumpAllocator::new(next_page_id)),
             pool_size,
+            page_size,
+            hits: Default::default(),
+            misses: Default::default(),
+            eviction_hook: Default::default(),
+            quarantine: Default::default(),
         })
     }
 
+    /// Replace the default [`BumpAllocator`], the allocator must not hand out page ids
+    /// which are already used by the database file
+    pub fn with_allocator(mut self, allocator: impl PageAllocator + 'static) -> Self {
+        self.allocator = Box::new(allocator);
+        self
+    }
+
+    /// Register a hook which is called with every evicted page. It's called after the pool
+    /// lock is released, with the data the page had when it was evicted.
+    pub fn set_eviction_hook(&self, hook: EvictionHook) {
+        *self.eviction_hook.write().unwrap() = Some(Arc::new(hook));
+    }
+
     pub async fn new_page_ref(&self) -> Result<Option<PageRef>, Error> {
         let mut inner = self.inner.write().await;
-        if let Some(frame_id) = self.available_frame(&mut inner).await? {
+        if let Some((frame_id, evicted)) = self.available_frame(&mut inner).await? {
             let page_id = self.allocate_page();
-            let page = Arc::new(Page::new(page_id));
+            let page = Arc::new(Page::new(page_id, self.page_size));
             page.pin_count.store(1, Ordering::Relaxed);
             inner.pages[frame_id] = page.clone();
             inner.page_table.insert(page_id, frame_id);
             let mut replacer = inner.replacer.write().await;
             replacer.record_access(frame_id);
             replacer.set_evictable(frame_id, false);
-            return Ok(Some(PageRef::new(
+            drop(replacer);
+            let page_ref = PageRef::new(
                 page.clone(),
                 frame_id,
                 inner.replacer.clone(),
-            )));
+                inner.tasks.clone(),
+            );
+            drop(inner);
+            self.notify_eviction(evicted);
+            return Ok(Some(page_ref));
         }
         Ok(None)
     }
 
     pub async fn fetch_page_ref(&self, page_id: PageId) -> Result<Option<PageRef>, Error> {
+        if self.is_quarantined(page_id) {
+            return Err(Error::Quarantined(page_id));
+        }
         let mut inner = self.inner.write().await;
         // fetch page from cache
         if let Some(frame_id) = inner.page_table.get(&page_id).cloned() {
             // we can't take lock guard when we fetch from page; or it will be deadlock
+            self.hits.fetch_add(1, Ordering::Relaxed);
             let page = inner.pages[frame_id].clone();
             page.pin_count.fetch_add(1, Ordering::Relaxed);
             let mut replacer = inner.replacer.write().await;
@@ -88,17 +215,29 @@ impl BufferPoolManager {
                 page.clone(),
                 frame_id,
                 inner.replacer.clone(),
+                inner.tasks.clone(),
             )));
         }
         // fetch page from disk
         let frame_id = self.available_frame(&mut inner).await?;
-        if let Some(frame_id) = frame_id {
+        if let Some((frame_id, evicted)) = frame_id {
+            self.misses.fetch_add(1, Ordering::Relaxed);
             let page = inner.pages[frame_id].clone();
             let page_data = page.data();
             let mut page_data = page_data.write().await;
             self.disk_manager
                 .read_page(page_id, page_data.as_mut())
                 .await?;
+            // a corrupt page isn't cached, the frame is freed and the page is quarantined
+            if !page::verify(&page_data) {
+                drop(page_data);
+                page.reset().await;
+                inner.free_list.push_back(frame_id);
+                drop(inner);
+                self.quarantine.write().unwrap().insert(page_id);
+                self.notify_eviction(evicted);
+                return Err(Error::Checksum { page_id });
+            }
             drop(page_data);
             page.set_page_id(page_id);
             page.pin_count.store(1, Ordering::Relaxed);
@@ -106,15 +245,112 @@ impl BufferPoolManager {
             let mut replacer = inner.replacer.write().await;
             replacer.record_access(frame_id);
             replacer.set_evictable(frame_id, false);
-            return Ok(Some(PageRef::new(
+            drop(replacer);
+            let page_ref = PageRef::new(
                 page.clone(),
                 frame_id,
                 inner.replacer.clone(),
-            )));
+                inner.tasks.clone(),
+            );
+            drop(inner);
+            self.notify_eviction(evicted);
+            return Ok(Some(page_ref));
         }
         Ok(None)
     }
 
+    pub fn stats(&self) -> BufferPoolStats {
+        BufferPoolStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Fetch and pin all the pages, they stay resident until the returned guard is dropped.
+    /// Returns [`Error::BufferInsufficient`] and pins nothing if the pool can't hold them all.
+    pub async fn pin_pages(&self, page_ids: &[PageId]) -> Result<PinnedPages, Error> {
+        let mut pages = Vec::with_capacity(page_ids.len());
+        for page_id in page_ids {
+            match self.fetch_page_ref(*page_id).await? {
+                Some(page) => pages.push(page),
+                None => return Err(Error::BufferInsufficient),
+            }
+        }
+        Ok(PinnedPages { pages })
+    }
+
+    /// Release the pages pinned by [`BufferPoolManager::pin_pages`], same as dropping the guard
+    pub fn unpin_pages(&self, pinned: PinnedPages) {
+        drop(pinned);
+    }
+
+    /// Whether the page is in the buffer pool, so fetching it won't read the disk
+    pub async fn is_resident(&self, page_id: PageId) -> bool {
+        self.inner.read().await.page_table.contains_key(&page_id)
+    }
+
+    /// The state of every frame, read under the pool lock so the frames are consistent
+    /// with each other. Pins released by dropped pages are applied asynchronously,
+    /// so they may not be reflected yet
+    pub async fn frame_snapshot(&self) -> Vec<FrameInfo> {
+        let inner = self.inner.read().await;
+        let replacer = inner.replacer.read().await;
+        let mut frames = (0..self.pool_size)
+            .map(|frame_id| FrameInfo {
+                frame_id,
+                page_id: None,
+                pin_count: 0,
+                dirty: false,
+                evictable: false,
+            })
+            .collect::<Vec<_>>();
+        for (page_id, frame_id) in inner.page_table.iter() {
+            let page = &inner.pages[*frame_id];
+            frames[*frame_id] = FrameInfo {
+                frame_id: *frame_id,
+                page_id: Some(*page_id),
+                pin_count: page.pin_count.load(Ordering::Relaxed),
+                dirty: page.is_dirty(),
+                evictable: replacer.is_evictable(*frame_id),
+            };
+        }
+        frames
+    }
+
+    /// Whether the page failed to decode or verify, fetching it fails with
+    /// [`Error::Quarantined`] until [`BufferPoolManager::clear_quarantine`] is called
+    pub fn is_quarantined(&self, page_id: PageId) -> bool {
+        self.quarantine.read().unwrap().contains(&page_id)
+    }
+
+    /// Release the page from quarantine once it's repaired, the cached copy is discarded
+    /// if it's not pinned, so the next fetch reads the page from disk again.
+    /// Returns whether the page was quarantined
+    pub async fn clear_quarantine(&self, page_id: PageId) -> Result<bool, Error> {
+        if !self.quarantine.write().unwrap().remove(&page_id) {
+            return Ok(false);
+        }
+        let mut inner = self.inner.write().await;
+        if let Some(frame_id) = inner.page_table.get(&page_id).cloned() {
+            let page = inner.pages[frame_id].clone();
+            if page.pin_count.load(Ordering::Relaxed) == 0 {
+                page.reset().await;
+                inner.replacer.write().await.remove(frame_id)?;
+                inner.free_list.push_back(frame_id);
+                inner.page_table.remove(&page_id);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Quarantine the page if the error is a decoding failure of its content
+    fn check_decoded<T>(&self, page_id: PageId, result: Result<T, Error>) -> Result<T, Error> {
+        if let Err(Error::Encoding(_)) = result {
+            self.quarantine.write().unwrap().insert(page_id);
+        }
+        result
+    }
+
     pub async fn flush_page(&self, page_id: PageId) -> Result<(), Error> {
         let inner = self.inner.write().await;
         if let Some(frame_id) = inner.page_table.get(&page_id).cloned() {
@@ -122,6 +358,7 @@ impl BufferPoolManager {
             let page_data = page.data();
             let mut page_data = page_data.write().await;
             if page.is_dirty() {
+                page::seal(&mut page_data);
                 self.disk_manager
                     .write_page(page.page_id(), page_data.as_mut())
                     .await?;
@@ -131,17 +368,78 @@ impl BufferPoolManager {
         Ok(())
     }
 
+    /// Make the pages modified so far durable as the [`DurabilityMode`] of the disk manager
+    /// requires, it's called when a transaction commits
+    pub async fn sync_commit(&self) -> Result<(), Error> {
+        match self.disk_manager.durability() {
+            DurabilityMode::None => {}
+            DurabilityMode::PerCommit => {
+                self.flush_page_all().await?;
+                self.disk_manager.sync().await?;
+            }
+            // every page write is synced already
+            DurabilityMode::PerWrite => self.flush_page_all().await?,
+        }
+        Ok(())
+    }
+
+    /// The size of every page of the buffer pool
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    pub fn disk_manager(&self) -> &DiskManager {
+        &self.disk_manager
+    }
+
+    /// Close the buffer pool: wait for the unpin tasks of dropped pages, flush every dirty page
+    /// and sync the database file, the disk manager is only dropped after that.
+    /// The pages still referenced elsewhere must not be written anymore.
+    pub async fn shutdown(self) -> Result<(), Error> {
+        let tasks = self.inner.read().await.tasks.clone();
+        tasks.join().await;
+        self.flush_page_all().await?;
+        self.disk_manager.sync().await?;
+        Ok(())
+    }
+
+    /// Flush all the dirty pages, contiguous dirty pages are written with a single write
     pub async fn flush_page_all(&self) -> Result<(), Error> {
         let inner = self.inner.write().await;
-        for page in inner.pages.iter() {
-            let page_data = page.data();
-            let mut page_data = page_data.write().await;
-            if page.is_dirty() {
-                self.disk_manager
-                    .write_page(page.page_id(), page_data.as_mut())
-                    .await?;
-                page.set_dirty(false);
+        let mut dirty = inner
+            .pages
+            .iter()
+            .filter(|page| page.is_dirty())
+            .collect::<Vec<_>>();
+        dirty.sort_by_key(|page| page.page_id());
+        let mut run: Vec<LatchedPage> = Vec::new();
+        for page in dirty {
+            if let Some((last, _)) = run.last() {
+                if last.page_id() + 1 != page.page_id() {
+                    self.flush_run(&mut run).await?;
+                }
             }
+            let page_data = page.data().write_owned().await;
+            run.push((page, page_data));
+        }
+        self.flush_run(&mut run).await
+    }
+
+    /// Write the latched pages of contiguous page ids, and mark them clean
+    async fn flush_run(&self, run: &mut Vec<LatchedPage<'_>>) -> Result<(), Error> {
+        let Some(first) = run.first().map(|(page, _)| page.page_id()) else {
+            return Ok(());
+        };
+        let mut buf = Vec::with_capacity(run.len() * self.page_size);
+        for (_, page_data) in run.iter_mut() {
+            page::seal(page_data);
+            buf.extend_from_slice(page_data.as_ref());
+        }
+        self.disk_manager
+            .write_pages(first, run.len(), &buf)
+            .await?;
+        for (page, _) in run.drain(..) {
+            page.set_dirty(false);
         }
         Ok(())
     }
@@ -156,6 +454,7 @@ impl BufferPoolManager {
             let page_data = page.data();
             let mut page_data = page_data.write().await;
             if page.is_dirty() {
+                page::seal(&mut page_data);
                 self.disk_manager
                     .write_page(page.page_id(), page_data.as_mut())
                     .await?;
@@ -166,36 +465,85 @@ impl BufferPoolManager {
             inner.replacer.write().await.remove(frame_id)?;
             inner.free_list.push_back(frame_id);
             inner.page_table.remove(&page_id);
+            self.allocator.free(page_id);
             return Ok(Some(page_id));
         }
         Ok(None)
     }
+
+    /// Returns a frame which can be reused, and the page evicted from it if any.
+    /// The evicted page is only returned when an eviction hook is registered.
     async fn available_frame(
         &self,
         inner: &mut RwLockWriteGuard<'_, Inner>,
-    ) -> Result<Option<FrameId>, Error> {
+    ) -> Result<Option<(FrameId, Option<EvictedPage>)>, Error> {
         if let Some(frame_id) = inner.free_list.pop_front() {
-            return Ok(Some(frame_id));
+            return Ok(Some((frame_id, None)));
         }
-        let frame_id = inner.replacer.write().await.evict();
-        if let Some(frame_id) = frame_id {
+        // a dirty victim is only repurposed once it's written, the victims failed to write
+        // stay in the buffer pool and become evictable again
+        let mut failed = Vec::new();
+        let mut error = None;
+        let mut available = None;
+        loop {
+            let Some(frame_id) = inner.replacer.write().await.evict() else {
+                break;
+            };
             let page = inner.pages[frame_id].clone();
             let page_data = page.data();
             let mut page_data = page_data.write().await;
             if page.is_dirty() {
-                self.disk_manager
+                page::seal(&mut page_data);
+                if let Err(err) = self
+                    .disk_manager
                     .write_page(page.page_id(), page_data.as_mut())
-                    .await?;
+                    .await
+                {
+                    failed.push(frame_id);
+                    error = Some(err);
+                    continue;
+                }
                 page.set_dirty(false);
             }
+            let evicted = self
+                .eviction_hook
+                .read()
+                .unwrap()
+                .is_some()
+                .then(|| EvictedPage {
+                    page_id: page.page_id(),
+                    data: page_data.clone(),
+                });
             drop(page_data);
             inner.page_table.remove(&page.page_id());
-            return Ok(Some(frame_id));
+            available = Some((frame_id, evicted));
+            break;
+        }
+        if !failed.is_empty() {
+            let mut replacer = inner.replacer.write().await;
+            for frame_id in failed {
+                replacer.record_access(frame_id);
+                replacer.set_evictable(frame_id, true);
+            }
+        }
+        match (available, error) {
+            (None, Some(err)) => Err(err.into()),
+            (available, _) => Ok(available),
         }
-        Ok(None)
     }
+
+    fn notify_eviction(&self, evicted: Option<EvictedPage>) {
+        let Some(evicted) = evicted else {
+            return;
+        };
+        let hook = self.eviction_hook.read().unwrap().clone();
+        if let Some(hook) = hook {
+            hook(&evicted);
+        }
+    }
+
     fn allocate_page(&self) -> PageId {
-        self.next_page_id.fetch_add(1, Ordering::AcqRel)
+        self.allocator.allocate()
     }
 }
 
@@ -263,7 +611,7 @@ impl BufferPoolManager {
             .fetch_page_ref(page_id)
             .await?
             .ok_or(Error::BufferInsufficient)?;
-        let node = page.page.node().await?;
+        let node = self.check_decoded(page_id, page.page.node().await)?;
         Ok((page, node))
     }
 
@@ -296,7 +644,7 @@ impl BufferPoolManager {
             .fetch_page_ref(page_id)
             .await?
             .ok_or(Error::BufferInsufficient)?;
-        let table = page.page.table().await?;
+        let table = self.check_decoded(page_id, page.page.table().await)?;
         Ok((page, table))
     }
 
@@ -318,7 +666,7 @@ impl BufferPoolManager {
             .fetch_page_ref(page_id)
             .await?
             .ok_or(Error::BufferInsufficient)?;
-        let table_node = page.page.table_node().await?;
+        let table_node = self.check_decoded(page_id, page.page.table_node().await)?;
         Ok((page, table_node))
     }
 }
@@ -326,26 +674,43 @@ pub struct PageRef {
     page: Arc<Page>,
     frame_id: FrameId,
     replacer: Arc<RwLock<LruKReplacer>>,
+    tasks: Arc<Tasks>,
+}
+
+/// A batch of pages pinned by [`BufferPoolManager::pin_pages`], which can't be evicted
+/// until it's dropped
+pub struct PinnedPages {
+    pages: Vec<PageRef>,
+}
+
+impl PinnedPages {
+    pub fn page_ids(&self) -> Vec<PageId> {
+        self.pages.iter().map(PageRef::page_id).collect()
+    }
+
+    pub fn get(&self, page_id: PageId) -> Option<&PageRef> {
+        self.pages.iter().find(|page| page.page_id() == page_id)
+    }
 }
 
 pub struct PageDataWriteGuard<'a> {
-    guard: RwLockWriteGuard<'a, [u8; PAGE_SIZE]>,
+    guard: RwLockWriteGuard<'a, Box<[u8]>>,
     page_id: PageId,
     is_dirty: &'a AtomicBool,
 }
 
 pub struct PageDataReadGuard<'a> {
-    guard: RwLockReadGuard<'a, [u8; PAGE_SIZE]>,
+    guard: RwLockReadGuard<'a, Box<[u8]>>,
     page_id: PageId,
 }
 
 pub struct OwnedPageDataWriteGuard {
-    guard: OwnedRwLockWriteGuard<[u8; PAGE_SIZE]>,
+    guard: OwnedRwLockWriteGuard<Box<[u8]>>,
     page_ref: PageRef,
 }
 
 pub struct OwnedPageDataReadGuard {
-    guard: OwnedRwLockReadGuard<[u8; PAGE_SIZE]>,
+    guard: OwnedRwLockReadGuard<Box<[u8]>>,
     page_ref: PageRef,
 }
 
@@ -353,6 +718,12 @@ impl PageDataWriteGuard<'_> {
     pub fn page_id(&self) -> PageId {
         self.page_id
     }
+
+    /// The page is marked dirty once it's mutably accessed,
+    /// call this only if the page is modified in other ways
+    pub fn mark_dirty(&self) {
+        self.is_dirty.store(true, Ordering::Relaxed);
+    }
 }
 
 impl PageDataReadGuard<'_> {
@@ -365,6 +736,12 @@ impl OwnedPageDataWriteGuard {
     pub fn page_id(&self) -> PageId {
         self.page_ref.page_id()
     }
+
+    /// The page is marked dirty once it's mutably accessed,
+    /// call this only if the page is modified in other ways
+    pub fn mark_dirty(&self) {
+        self.page_ref.page.set_dirty(true);
+    }
 }
 
 impl OwnedPageDataReadGuard {
@@ -378,7 +755,7 @@ impl Drop for PageRef {
         let page = self.page.clone();
         let frame_id = self.frame_id;
         let replacer = self.replacer.clone();
-        tokio::spawn(async move {
+        self.tasks.spawn(async move {
             let prev = page.pin_count.fetch_sub(1, Ordering::Relaxed);
             if prev == 1 {
                 replacer.write().await.set_evictable(frame_id, true);
@@ -388,67 +765,65 @@ impl Drop for PageRef {
 }
 
 impl Deref for PageDataWriteGuard<'_> {
-    type Target = [u8; PAGE_SIZE];
+    type Target = [u8];
 
     fn deref(&self) -> &Self::Target {
-        self.guard.deref()
+        &self.guard[..]
     }
 }
 
+/// Only mutable access makes the page dirty, so reading through a write latch won't flush the page
 impl DerefMut for PageDataWriteGuard<'_> {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        self.guard.deref_mut()
-    }
-}
-
-impl Drop for PageDataWriteGuard<'_> {
-    fn drop(&mut self) {
-        self.is_dirty.store(true, Ordering::Relaxed);
+        self.mark_dirty();
+        &mut self.guard[..]
     }
 }
 
 impl Deref for PageDataReadGuard<'_> {
-    type Target = [u8; PAGE_SIZE];
+    type Target = [u8];
 
     fn deref(&self) -> &Self::Target {
-        self.guard.deref()
+        &self.guard[..]
     }
 }
 
 impl Deref for OwnedPageDataWriteGuard {
-    type Target = [u8; PAGE_SIZE];
+    type Target = [u8];
 
     fn deref(&self) -> &Self::Target {
-        self.guard.deref()
+        &self.guard[..]
     }
 }
 
+/// Only mutable access makes the page dirty, so reading through a write latch won't flush the page
 impl DerefMut for OwnedPageDataWriteGuard {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        self.guard.deref_mut()
-    }
-}
-
-impl Drop for OwnedPageDataWriteGuard {
-    fn drop(&mut self) {
-        self.page_ref.page.set_dirty(true);
+        self.mark_dirty();
+        &mut self.guard[..]
     }
 }
 
 impl Deref for OwnedPageDataReadGuard {
-    type Target = [u8; PAGE_SIZE];
+    type Target = [u8];
 
     fn deref(&self) -> &Self::Target {
-        self.guard.deref()
+        &self.guard[..]
     }
 }
 
 impl PageRef {
-    pub fn new(page: Arc<Page>, frame_id: FrameId, replacer: Arc<RwLock<LruKReplacer>>) -> Self {
+    fn new(
+        page: Arc<Page>,
+        frame_id: FrameId,
+        replacer: Arc<RwLock<LruKReplacer>>,
+        tasks: Arc<Tasks>,
+    ) -> Self {
         Self {
             page,
             frame_id,
             replacer,
+            tasks,
         }
     }
 
@@ -503,6 +878,10 @@ impl PageRef {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::buffer::allocator::FreeListAllocator;
+    use crate::sql::types::Value;
+    use crate::storage::page::table::Tuple;
+    use crate::storage::{CHECKSUM_SIZE, PAGE_PAYLOAD_SIZE};
     use std::io::Write;
     use std::time::Duration;
 
@@ -569,7 +948,11 @@ mod tests {
         let page0 = bpm.fetch_page_ref(0).await?;
         assert!(page0.is_some());
         let page0 = page0.unwrap();
-        assert_eq!(page0.data_read().await.as_ref(), &random_data);
+        // the first bytes of the page are replaced by its checksum when it's flushed
+        assert_eq!(
+            page0.data_read().await[CHECKSUM_SIZE..],
+            random_data[CHECKSUM_SIZE..]
+        );
 
         // Shutdown the disk manager and remove the temporary file we created.
 
@@ -595,7 +978,9 @@ mod tests {
 
         // Scenario: Once we have a page, we should be able to read and write content.
         let data = "Hello".as_bytes();
-        page0.data_write().await.as_mut().write_all(data)?;
+        page0.data_write().await[CHECKSUM_SIZE..]
+            .as_mut()
+            .write_all(data)?;
 
         // Scenario: We should be able to create new pages until we fill up the buffer pool.
         let mut pages = Vec::new();
@@ -637,9 +1022,12 @@ mod tests {
         assert!(page0.is_some());
         let page0 = page0.unwrap();
         let mut data = [0u8; PAGE_SIZE];
-        let mut data_slice = &mut data[..];
+        let mut data_slice = &mut data[CHECKSUM_SIZE..];
         data_slice.write_all("Hello".as_bytes())?;
-        assert_eq!(page0.data_read().await.as_ref(), data);
+        assert_eq!(
+            page0.data_read().await[CHECKSUM_SIZE..],
+            data[CHECKSUM_SIZE..]
+        );
 
         // Scenario: If we unpin page 0 and then make a new page, all the buffer pages should
         // now be pinned. Fetching page 0 again should fail.
@@ -657,4 +1045,415 @@ mod tests {
 
         Ok(())
     }
+
+    /// Create pages, write them, delete the first page and create a new one,
+    /// returns the page id of the new page
+    async fn allocate_pages(bpm: &BufferPoolManager) -> Result<PageId, Error> {
+        let mut page_ids = Vec::new();
+        for i in 0..5 {
+            let page = bpm.new_page_ref().await?.unwrap();
+            page.data_write().await.fill(i);
+            page_ids.push(page.page_id());
+        }
+        assert_eq!(page_ids, vec![0, 1, 2, 3, 4]);
+        // wait until page unpin
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        bpm.flush_page_all().await?;
+        for (i, page_id) in page_ids.iter().enumerate() {
+            let page = bpm.fetch_page_ref(*page_id).await?.unwrap();
+            assert_eq!(
+                page.data_read().await[CHECKSUM_SIZE..],
+                [i as u8; PAGE_PAYLOAD_SIZE]
+            );
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(bpm.delete_page(0).await?, Some(0));
+        Ok(bpm.new_page_ref().await?.unwrap().page_id())
+    }
+
+    #[tokio::test]
+    async fn allocator() -> Result<(), Error> {
+        let file = tempfile::NamedTempFile::new()?;
+        let bpm = BufferPoolManager::new(10, 2, DiskManager::new(file.path()).await?).await?;
+        assert_eq!(allocate_pages(&bpm).await?, 5);
+
+        let file = tempfile::NamedTempFile::new()?;
+        let bpm = BufferPoolManager::new(10, 2, DiskManager::new(file.path()).await?)
+            .await?
+            .with_allocator(FreeListAllocator::new(0));
+        assert_eq!(allocate_pages(&bpm).await?, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_through_write_latch() -> Result<(), Error> {
+        let file = tempfile::NamedTempFile::new()?;
+        let bpm = BufferPoolManager::new(10, 2, DiskManager::new(file.path()).await?).await?;
+        let page = bpm.new_page_ref().await?.unwrap();
+        let page_id = page.page_id();
+        page.data_write().await.fill(1);
+        assert!(page.page().is_dirty());
+        bpm.flush_page(page_id).await?;
+        assert!(!page.page().is_dirty());
+        {
+            let guard = page.data_write().await;
+            assert_eq!(guard[CHECKSUM_SIZE], 1);
+        }
+        assert!(!page.page().is_dirty());
+        drop(page);
+
+        let guard = bpm.fetch_page_write_owned(page_id).await?;
+        assert_eq!(guard[CHECKSUM_SIZE], 1);
+        drop(guard);
+        let page = bpm.fetch_page_ref(page_id).await?.unwrap();
+        assert!(!page.page().is_dirty());
+        let guard = page.data_write_owned().await;
+        guard.mark_dirty();
+        assert!(bpm
+            .fetch_page_ref(page_id)
+            .await?
+            .unwrap()
+            .page()
+            .is_dirty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn pin_pages() -> Result<(), Error> {
+        let file = tempfile::NamedTempFile::new()?;
+        let bpm = BufferPoolManager::new(10, 2, DiskManager::new(file.path()).await?).await?;
+        for i in 0..10 {
+            bpm.new_page_ref()
+                .await?
+                .unwrap()
+                .data_write()
+                .await
+                .fill(i);
+        }
+        // wait until page unpin
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let pinned = bpm.pin_pages(&[0, 1, 2]).await?;
+        assert_eq!(pinned.page_ids(), vec![0, 1, 2]);
+        assert!(bpm.pin_pages(&(3..20).collect::<Vec<_>>()).await.is_err());
+        // wait until page unpin
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        for _ in 0..3 {
+            let mut refs = Vec::new();
+            for _ in 0..7 {
+                refs.push(bpm.new_page_ref().await?.unwrap());
+            }
+            assert!(bpm.new_page_ref().await?.is_none());
+            drop(refs);
+            // wait until page unpin
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            for page_id in 0..3 {
+                assert!(bpm.is_resident(page_id).await);
+            }
+        }
+        assert_eq!(pinned.get(1).unwrap().data_read().await[0], 1);
+        bpm.unpin_pages(pinned);
+        // wait until page unpin
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        for _ in 0..10 {
+            bpm.new_page_ref().await?.unwrap();
+        }
+        assert!(!bpm.is_resident(0).await);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn eviction_hook() -> Result<(), Error> {
+        let file = tempfile::NamedTempFile::new()?;
+        let bpm = BufferPoolManager::new(3, 2, DiskManager::new(file.path()).await?).await?;
+        let evicted = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let hooked = evicted.clone();
+        bpm.set_eviction_hook(Box::new(move |page| {
+            hooked.lock().unwrap().push(page.clone())
+        }));
+        for i in 0..3 {
+            bpm.new_page_ref()
+                .await?
+                .unwrap()
+                .data_write()
+                .await
+                .fill(i);
+        }
+        // wait until page unpin
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(evicted.lock().unwrap().is_empty());
+
+        let page = bpm.new_page_ref().await?.unwrap();
+        assert_eq!(page.page_id(), 3);
+        // the hook gets the data of the evicted page, not of the page reusing the frame
+        page.data_write().await.fill(0xff);
+        let evicted_page = evicted.lock().unwrap()[0].clone();
+        assert_eq!(evicted.lock().unwrap().len(), 1);
+        assert!(!bpm.is_resident(evicted_page.page_id).await);
+        assert!(page::verify(&evicted_page.data));
+        assert_eq!(evicted_page.data[CHECKSUM_SIZE], evicted_page.page_id as u8);
+        // the dirty page is flushed before it's evicted
+        drop(page);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let page = bpm.fetch_page_ref(evicted_page.page_id).await?.unwrap();
+        assert_eq!(*page.data_read().await, *evicted_page.data);
+        assert_eq!(evicted.lock().unwrap().len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn quarantine() -> Result<(), Error> {
+        let file = tempfile::NamedTempFile::new()?;
+        let bpm = BufferPoolManager::new(4, 2, DiskManager::new(file.path()).await?).await?;
+        let page_id = {
+            let page = bpm.new_page_ref().await?.unwrap();
+            let tuple = Tuple::new(vec![Value::Null], 0);
+            let mut data = Vec::new();
+            TableNode::new(page.page_id(), vec![tuple]).encode(&mut data)?;
+            // the tag of the last value isn't a valid type
+            *data.last_mut().unwrap() = 0xee;
+            page.data_write().await[CHECKSUM_SIZE..][..data.len()].copy_from_slice(&data);
+            page.page_id()
+        };
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        bpm.flush_page(page_id).await?;
+
+        assert!(matches!(
+            bpm.fetch_page_table_node(page_id).await,
+            Err(Error::Encoding(_))
+        ));
+        assert!(bpm.is_quarantined(page_id));
+        // the page isn't read again while it's quarantined
+        let stats = bpm.stats();
+        assert!(matches!(
+            bpm.fetch_page_table_node(page_id).await,
+            Err(Error::Quarantined(id)) if id == page_id
+        ));
+        assert!(matches!(
+            bpm.fetch_page_ref(page_id).await,
+            Err(Error::Quarantined(_))
+        ));
+        assert_eq!(bpm.stats(), stats);
+
+        // repair the page on disk, then the next fetch reads it again
+        let mut data = [0; PAGE_SIZE];
+        TableNode::new(page_id, Vec::new()).encode(&mut &mut data[CHECKSUM_SIZE..])?;
+        page::seal(&mut data);
+        bpm.disk_manager.write_page(page_id, &data).await?;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(bpm.clear_quarantine(page_id).await?);
+        assert!(!bpm.clear_quarantine(page_id).await?);
+        let (_, table_node) = bpm.fetch_page_table_node(page_id).await?;
+        assert_eq!(table_node.page_id(), page_id);
+        assert_eq!(bpm.stats().misses, stats.misses + 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn checksum() -> Result<(), Error> {
+        let file = tempfile::NamedTempFile::new()?;
+        let bpm = BufferPoolManager::new(2, 2, DiskManager::new(file.path()).await?).await?;
+        let page = bpm.new_page_ref().await?.unwrap();
+        let page_id = page.page_id();
+        page.data_write().await.fill(1);
+        drop(page);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        bpm.flush_page(page_id).await?;
+
+        // flip a byte of the payload on disk, then evict the page so it's read again
+        let mut data = [0; PAGE_SIZE];
+        bpm.disk_manager.read_page(page_id, &mut data).await?;
+        assert!(page::verify(&data));
+        data[PAGE_SIZE - 1] ^= 0xff;
+        assert!(!page::verify(&data));
+        bpm.disk_manager.write_page(page_id, &data).await?;
+        let _pages = (
+            bpm.new_page_ref().await?.unwrap(),
+            bpm.new_page_ref().await?.unwrap(),
+        );
+        assert!(!bpm.is_resident(page_id).await);
+        drop(_pages);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(matches!(
+            bpm.fetch_page_ref(page_id).await,
+            Err(Error::Checksum { page_id: id }) if id == page_id
+        ));
+        // the frame of the corrupt page is reused
+        assert!(!bpm.is_resident(page_id).await);
+        assert!(bpm.new_page_ref().await?.is_some());
+
+        // the page isn't read from disk again while it's quarantined
+        assert!(bpm.is_quarantined(page_id));
+        let stats = bpm.stats();
+        assert!(matches!(
+            bpm.fetch_page_ref(page_id).await,
+            Err(Error::Quarantined(id)) if id == page_id
+        ));
+        assert_eq!(bpm.stats(), stats);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn invalid_page_size() -> Result<(), Error> {
+        let file = tempfile::NamedTempFile::new()?;
+        for page_size in [0, CHECKSUM_SIZE] {
+            let disk_manager = DiskManager::new(file.path()).await?;
+            assert!(matches!(
+                BufferPoolManager::with_page_size(2, 2, disk_manager, page_size).await,
+                Err(Error::PageSize(size)) if size == page_size
+            ));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn shutdown() -> Result<(), Error> {
+        let file = tempfile::NamedTempFile::new()?;
+        let bpm =
+            Arc::new(BufferPoolManager::new(8, 2, DiskManager::new(file.path()).await?).await?);
+        let pages = 64;
+        for i in 0..pages {
+            let page = loop {
+                match bpm.new_page_ref().await? {
+                    Some(page) => break page,
+                    // the frames are released by the unpin tasks
+                    None => tokio::task::yield_now().await,
+                }
+            };
+            page.data_write().await.fill(i as u8);
+        }
+        // read the pages concurrently, every fetch spawns an unpin task when it's dropped
+        let readers = (0..4)
+            .map(|reader| {
+                let bpm = bpm.clone();
+                tokio::spawn(async move {
+                    for page_id in (reader..pages).step_by(4) {
+                        let mut page = loop {
+                            match bpm.fetch_page_write_owned(page_id).await {
+                                Err(Error::BufferInsufficient) => tokio::task::yield_now().await,
+                                page => break page?,
+                            }
+                        };
+                        page[CHECKSUM_SIZE] = page_id as u8 + 1;
+                    }
+                    Ok::<_, Error>(())
+                })
+            })
+            .collect::<Vec<_>>();
+        for reader in readers {
+            reader.await.unwrap()?;
+        }
+        let bpm = Arc::into_inner(bpm).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), bpm.shutdown())
+            .await
+            .expect("the unpin tasks must finish")?;
+
+        let disk_manager = DiskManager::new(file.path()).await?;
+        let mut data = [0; PAGE_SIZE];
+        for page_id in 0..pages {
+            disk_manager.read_page(page_id, &mut data).await?;
+            assert!(page::verify(&data));
+            assert_eq!(data[CHECKSUM_SIZE], page_id as u8 + 1);
+            assert_eq!(data[PAGE_SIZE - 1], page_id as u8);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn frame_snapshot() -> Result<(), Error> {
+        let file = tempfile::NamedTempFile::new()?;
+        let bpm = BufferPoolManager::new(4, 2, DiskManager::new(file.path()).await?).await?;
+        let page0 = bpm.new_page_ref().await?.unwrap();
+        let page1 = bpm.new_page_ref().await?.unwrap();
+        page1.data_write().await.fill(1);
+        let page0_again = bpm.fetch_page_ref(page0.page_id()).await?.unwrap();
+
+        let frames = bpm.frame_snapshot().await;
+        assert_eq!(frames.len(), 4);
+        let frame = |page_id| {
+            *frames
+                .iter()
+                .find(|frame| frame.page_id == Some(page_id))
+                .unwrap()
+        };
+        assert_eq!(frame(page0.page_id()).pin_count, 2);
+        assert!(!frame(page0.page_id()).dirty);
+        assert!(!frame(page0.page_id()).evictable);
+        assert_eq!(frame(page1.page_id()).pin_count, 1);
+        assert!(frame(page1.page_id()).dirty);
+        assert_eq!(
+            frames
+                .iter()
+                .filter(|frame| frame.page_id.is_none())
+                .count(),
+            2
+        );
+
+        let (page0_id, page1_id) = (page0.page_id(), page1.page_id());
+        drop(page0);
+        drop(page0_again);
+        drop(page1);
+        let tasks = bpm.inner.read().await.tasks.clone();
+        tasks.join().await;
+        bpm.flush_page(page1_id).await?;
+        let frames = bpm.frame_snapshot().await;
+        for page_id in [page0_id, page1_id] {
+            let frame = frames
+                .iter()
+                .find(|frame| frame.page_id == Some(page_id))
+                .unwrap();
+            assert_eq!(frame.pin_count, 0);
+            assert!(!frame.dirty);
+            assert!(frame.evictable);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn evict_write_failure() -> Result<(), Error> {
+        let file = tempfile::NamedTempFile::new()?;
+        let bpm = BufferPoolManager::new(2, 2, DiskManager::new(file.path()).await?).await?;
+        let tasks = bpm.inner.read().await.tasks.clone();
+        let dirty = bpm.new_page_ref().await?.unwrap();
+        dirty.data_write().await[CHECKSUM_SIZE] = 1;
+        let clean = bpm.new_page_ref().await?.unwrap();
+        let (dirty_id, clean_id) = (dirty.page_id(), clean.page_id());
+        drop(dirty);
+        drop(clean);
+        tasks.join().await;
+
+        // the dirty victim fails to write, so the clean one is evicted instead
+        bpm.disk_manager.fail_writes(1);
+        let page = bpm.new_page_ref().await?.unwrap();
+        assert!(bpm.is_resident(dirty_id).await);
+        assert!(!bpm.is_resident(clean_id).await);
+        page.data_write().await[CHECKSUM_SIZE] = 2;
+        let page_id = page.page_id();
+        drop(page);
+        tasks.join().await;
+
+        // every victim fails to write, the error is returned and both pages are kept
+        bpm.disk_manager.fail_writes(2);
+        assert!(matches!(bpm.new_page_ref().await, Err(Error::IO(_))));
+        assert!(bpm.is_resident(dirty_id).await);
+        assert!(bpm.is_resident(page_id).await);
+        assert!(bpm
+            .frame_snapshot()
+            .await
+            .iter()
+            .all(|frame| frame.dirty && frame.evictable));
+
+        // both frames can be repurposed once the writes succeed, and the data is on disk
+        let _pages = (
+            bpm.new_page_ref().await?.unwrap(),
+            bpm.new_page_ref().await?.unwrap(),
+        );
+        assert!(!bpm.is_resident(dirty_id).await);
+        assert!(!bpm.is_resident(page_id).await);
+        let mut data = [0; PAGE_SIZE];
+        bpm.disk_manager.read_page(dirty_id, &mut data).await?;
+        assert_eq!(data[CHECKSUM_SIZE], 1);
+        bpm.disk_manager.read_page(page_id, &mut data).await?;
+        assert_eq!(data[CHECKSUM_SIZE], 2);
+        Ok(())
+    }
 }
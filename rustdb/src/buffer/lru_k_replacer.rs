@@ -70,6 +70,10 @@ impl LruKReplacer {
 
         replacer
     }
+    /// Evict the evictable frame with the largest backward k-distance.
+    /// The frames accessed fewer than k times all have an infinite distance, the tie is broken
+    /// by evicting the one whose first access is the earliest. The other frames are evicted
+    /// in the order of their last access
     pub fn evict(&mut self) -> Option<FrameId> {
         if self.current_size.load(Ordering::SeqCst) == 0 {
             return None;
@@ -112,10 +116,11 @@ impl LruKReplacer {
         if let Some(node) = self.node_store.get(KeyWrapper::from_ref(&frame_id)) {
             let node_ptr = node.as_ptr();
             unsafe {
-                if (*node_ptr).access_count >= self.k - 1 {
+                (*node_ptr).access_count += 1;
+                // a frame in the history list keeps its place until it's accessed k times
+                if (*node_ptr).access_count >= self.k {
                     Self::detach(node_ptr);
                     Self::attach(self.cache_list_head, node_ptr);
-                    (*node_ptr).access_count += 1;
                 }
             }
         } else {
@@ -147,6 +152,13 @@ impl LruKReplacer {
         }
     }
 
+    /// Whether the frame is tracked and can be evicted
+    pub fn is_evictable(&self, frame_id: FrameId) -> bool {
+        self.node_store
+            .get(KeyWrapper::from_ref(&frame_id))
+            .is_some_and(|node| unsafe { (*node.as_ptr()).is_evictable })
+    }
+
     pub fn size(&self) -> usize {
         self.current_size.load(Ordering::SeqCst)
     }
@@ -155,11 +167,12 @@ impl LruKReplacer {
         assert!(frame_id.lt(&(self.replacer_size)));
         if let Some(node) = self.node_store.get(KeyWrapper::from_ref(&frame_id)) {
             let node_ptr = node.as_ptr();
-            if unsafe { (*node_ptr).is_evictable } {
+            if unsafe { !(*node_ptr).is_evictable } {
                 return Err(Error::UnEvictableFrame(frame_id));
             }
             Self::detach(node_ptr);
             self.node_store.remove(KeyWrapper::from_ref(&frame_id));
+            self.current_size.fetch_sub(1, Ordering::SeqCst);
         }
         Ok(())
     }
@@ -256,4 +269,53 @@ mod tests {
         assert!(lru_replacer.evict().is_none());
         assert_eq!(0, lru_replacer.size());
     }
+
+    #[test]
+    fn tie_breaker() {
+        let mut lru_replacer = LruKReplacer::new(4, 3);
+        for frame_id in 0..4 {
+            lru_replacer.record_access(frame_id);
+        }
+        // frame 0 is accessed again but fewer than k times, it still has the earliest first
+        // access among the frames of infinite distance
+        lru_replacer.record_access(0);
+        // frame 1 reaches k accesses, so it has a finite distance
+        lru_replacer.record_access(1);
+        lru_replacer.record_access(1);
+        assert_eq!(4, lru_replacer.size());
+        assert_eq!(Some(0), lru_replacer.evict());
+        assert_eq!(Some(2), lru_replacer.evict());
+        assert_eq!(Some(3), lru_replacer.evict());
+        assert_eq!(Some(1), lru_replacer.evict());
+        assert_eq!(None, lru_replacer.evict());
+    }
+
+    #[test]
+    fn remove() {
+        let mut lru_replacer = LruKReplacer::new(4, 2);
+        for frame_id in 0..3 {
+            lru_replacer.record_access(frame_id);
+            lru_replacer.set_evictable(frame_id, true);
+        }
+        lru_replacer.set_evictable(2, false);
+        assert_eq!(2, lru_replacer.size());
+
+        // a pinned frame can't be removed and stays tracked
+        assert!(matches!(
+            lru_replacer.remove(2),
+            Err(Error::UnEvictableFrame(2))
+        ));
+        assert_eq!(2, lru_replacer.size());
+        // removing a frame which isn't tracked does nothing
+        assert!(lru_replacer.remove(3).is_ok());
+        assert_eq!(2, lru_replacer.size());
+
+        assert!(lru_replacer.remove(0).is_ok());
+        assert_eq!(1, lru_replacer.size());
+        assert!(!lru_replacer.is_evictable(0));
+        assert_eq!(Some(1), lru_replacer.evict());
+        assert_eq!(None, lru_replacer.evict());
+        lru_replacer.set_evictable(2, true);
+        assert_eq!(Some(2), lru_replacer.evict());
+    }
 }
@@ -1,8 +1,10 @@
 use crate::encoding;
+use crate::storage::PageId;
 use std::borrow::Borrow;
 use std::hash::{Hash, Hasher};
 use thiserror::Error;
 
+pub mod allocator;
 pub mod buffer_pool_manager;
 mod lru_k_replacer;
 mod lru_replacer;
@@ -73,4 +75,12 @@ pub enum Error {
     TryLock(#[from] tokio::sync::TryLockError),
     #[error("io error: {0}")]
     IO(#[from] std::io::Error),
+    #[error("page {0} is quarantined since it failed to decode or verify")]
+    Quarantined(PageId),
+    #[error("page {page_id} doesn't match its checksum")]
+    Checksum { page_id: PageId },
+    #[error("{size} bytes don't fit in the page payload of {capacity} bytes")]
+    PageOverflow { size: usize, capacity: usize },
+    #[error("page size {0} leaves no room after the checksum")]
+    PageSize(usize),
 }
@@ -1,5 +1,6 @@
 use crate::catalog::{ColumnId, TableId};
 use crate::sql::types::{DataType, Value};
+use crate::storage::page;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -8,6 +9,7 @@ pub struct ColumnCatalog {
     pub name: String,
     pub datatype: DataType,
     pub primary_key: bool,
+    pub auto_increment: bool,
     pub nullable: Option<bool>,
     pub default: Option<Value>,
     pub unique: bool,
@@ -22,6 +24,7 @@ impl ColumnCatalog {
             name: name.into(),
             datatype,
             primary_key: false,
+            auto_increment: false,
             nullable: None,
             default: None,
             unique: false,
@@ -56,6 +59,11 @@ impl ColumnCatalog {
         self
     }
 
+    pub fn with_auto_increment(mut self, auto_increment: bool) -> Self {
+        self.auto_increment = auto_increment;
+        self
+    }
+
     pub fn with_nullable(mut self, nullable: bool) -> Self {
         self.nullable = Some(nullable);
         self
@@ -81,3 +89,21 @@ impl ColumnCatalog {
         self
     }
 }
+
+/// The id is assigned when the column is added into [`crate::catalog::table::TableCatalog`]
+impl From<page::column::Column> for ColumnCatalog {
+    fn from(column: page::column::Column) -> Self {
+        Self {
+            id: 0,
+            name: column.name,
+            datatype: column.datatype,
+            primary_key: column.primary_key,
+            auto_increment: column.auto_increment,
+            nullable: column.nullable,
+            default: column.default,
+            unique: column.unique,
+            index: column.index,
+            references: column.references,
+        }
+    }
+}
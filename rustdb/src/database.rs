@@ -0,0 +1,2188 @@
+use crate::buffer::buffer_pool_manager::BufferPoolManager;
+use crate::encoding::{Decoder, Encoder};
+use crate::sql::engine::{CommitObserver, Engine, StorageEngine, TransactionId};
+use crate::sql::execution::{Executor, ResultSet};
+use crate::sql::parser::ast;
+use crate::sql::plan::Planner;
+use crate::sql::transaction::Transaction;
+use crate::sql::types::{CastMode, Row};
+use crate::sql::{parser, Error, SqlResult};
+use crate::storage;
+use crate::storage::disk::disk_manager::DiskManager;
+use crate::storage::page::column::Column;
+use crate::storage::page::table::{TableIndex, Tuple};
+use crate::storage::{Storage, PAGE_SIZE};
+use bytes::{Buf, BufMut};
+use futures::{pin_mut, StreamExt};
+use std::io::ErrorKind;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+/// The number of frames in the buffer pool
+pub const DEFAULT_POOL_SIZE: usize = 1024;
+/// The k of lru-k replacer
+pub const DEFAULT_REPLACER_K: usize = 2;
+
+/// The first bytes of a dump written by [`Database::dump`]
+const DUMP_MAGIC: &[u8] = b"RUSTDUMP";
+/// The number of rows [`Database::restore`] inserts at a time
+pub const RESTORE_BATCH: usize = 256;
+
+/// The entry of rustdb, which ties the disk, buffer pool, catalog and sql engine together
+pub struct Database {
+    engine: StorageEngine,
+}
+
+impl Database {
+    /// Create a new database in the given file, the file must be empty or not exist
+    pub async fn create(path: impl AsRef<Path>) -> SqlResult<Self> {
+        Self::create_with(Self::buffer_pool(path).await?).await
+    }
+
+    /// Create a new database on the buffer pool, whose file must be empty or not exist.
+    /// The pages of the database have the page size of the buffer pool
+    pub async fn create_with(buffer_pool: BufferPoolManager) -> SqlResult<Self> {
+        Ok(Self {
+            engine: StorageEngine::new(Arc::new(
+                storage::engine::Engine::new(Arc::new(buffer_pool)).await?,
+            )),
+        })
+    }
+
+    /// Open an existing database file, and reload the catalog from its metadata page
+    pub async fn open(path: impl AsRef<Path>) -> SqlResult<Self> {
+        let buffer_pool = Self::buffer_pool(path).await?;
+        Ok(Self {
+            engine: StorageEngine::new(Arc::new(
+                storage::engine::Engine::open(Arc::new(buffer_pool)).await?,
+            )),
+        })
+    }
+
+    /// Create a new database in the given file from a dump written by [`Database::dump`]
+    pub async fn restore(
+        path: impl AsRef<Path>,
+        reader: impl AsyncRead + Unpin,
+    ) -> SqlResult<Self> {
+        Self::restore_with(Self::buffer_pool(path).await?, reader).await
+    }
+
+    /// Create a new database on the buffer pool from a dump written by [`Database::dump`],
+    /// the page size of the buffer pool may differ from the dumped database's.
+    /// The dump is decoded as it's read, and the rows are inserted [`RESTORE_BATCH`] at a time
+    pub async fn restore_with(
+        buffer_pool: BufferPoolManager,
+        reader: impl AsyncRead + Unpin,
+    ) -> SqlResult<Self> {
+        let mut reader = BufReader::new(reader);
+        let mut magic = [0; DUMP_MAGIC.len()];
+        if reader.read_exact(&mut magic).await.is_err() || magic != DUMP_MAGIC {
+            return Err(Error::Value("Not a dump of rustdb".to_string()));
+        }
+        let db = Self::create_with(buffer_pool).await?;
+        let storage = db.engine.storage();
+        for _ in 0..decode::<u32>(&mut reader).await? {
+            let name = decode::<String>(&mut reader).await?;
+            let sequence = decode::<i64>(&mut reader).await?;
+            let table = storage
+                .create_table(name.clone(), decode::<Vec<Column>>(&mut reader).await?)
+                .await?;
+            let mut tuples = Vec::new();
+            while decode::<bool>(&mut reader).await? {
+                tuples.push(Tuple::new(decode::<Row>(&mut reader).await?, 0));
+                if tuples.len() == RESTORE_BATCH {
+                    storage.insert(&name, std::mem::take(&mut tuples)).await?;
+                }
+            }
+            storage.insert(&name, tuples).await?;
+            table.advance_sequence(sequence).await?;
+            for index in decode::<Vec<TableIndex>>(&mut reader).await? {
+                storage
+                    .create_index(&name, index.name(), index.columns().to_vec())
+                    .await?;
+            }
+        }
+        let left = reader
+            .read(&mut [0; 1])
+            .await
+            .map_err(storage::Error::from)?;
+        if left != 0 {
+            return Err(Error::Value("Bytes are left after the dump".to_string()));
+        }
+        Ok(db)
+    }
+
+    /// Write the schema, rows and indexes of every table into the writer.
+    /// The dump doesn't depend on the page layout, it can be restored with another page size.
+    pub async fn dump(&self, mut writer: impl AsyncWrite + Unpin) -> SqlResult<()> {
+        let storage = self.engine.storage();
+        let names = storage.table_names().await;
+        let mut buf = DUMP_MAGIC.to_vec();
+        encode(&(names.len() as u32), &mut buf)?;
+        for name in names {
+            let table = storage
+                .read_table(&name)
+                .await?
+                .ok_or(Error::NotFound("table", name.clone()))?;
+            encode(&name, &mut buf)?;
+            encode(&table.sequence().await?, &mut buf)?;
+            encode(&table.columns().await?, &mut buf)?;
+            let tuples = storage.scan(&name, ..).await?;
+            pin_mut!(tuples);
+            while let Some(tuple) = tuples.next().await {
+                encode(&true, &mut buf)?;
+                encode(&tuple?.values, &mut buf)?;
+                if buf.len() >= PAGE_SIZE {
+                    writer.write_all(&buf).await.map_err(storage::Error::from)?;
+                    buf.clear();
+                }
+            }
+            encode(&false, &mut buf)?;
+            encode(&table.indexes().await?, &mut buf)?;
+        }
+        writer.write_all(&buf).await.map_err(storage::Error::from)?;
+        writer.flush().await.map_err(storage::Error::from)?;
+        Ok(())
+    }
+
+    /// Parse, plan and execute a sql statement in its own transaction
+    pub async fn execute(&self, sql: &str) -> SqlResult<ResultSet> {
+        self.autocommit(parser::parse(sql)?).await
+    }
+
+    /// Execute a statement in its own transaction, which is rolled back if the statement fails
+    async fn autocommit(&self, statement: ast::Statement) -> SqlResult<ResultSet> {
+        let txn = self.engine.begin().await?;
+        let result = match Planner::new(&txn).build_statement(statement).await {
+            Ok(node) => node.execute(&txn).await,
+            Err(err) => Err(err),
+        };
+        match result {
+            Ok(result) => {
+                txn.commit().await?;
+                Ok(result)
+            }
+            Err(err) => {
+                txn.rollback().await?;
+                Err(err)
+            }
+        }
+    }
+
+    /// Parse and execute a script of statements, returns the result of every statement.
+    /// Each statement runs in its own transaction, unless it's inside a block of
+    /// `BEGIN TRANSACTION` and `COMMIT` or `ROLLBACK`, whose statements run in one transaction.
+    /// A failed statement stops the script, and its block is rolled back as a whole.
+    /// A block left open at the end of the script is rolled back too.
+    pub async fn execute_script(&self, sql: &str) -> SqlResult<Vec<ResultSet>> {
+        let mut results = Vec::new();
+        let mut block = None;
+        for statement in parser::parse_many(sql)? {
+            let result = match (statement, block.take()) {
+                (ast::Statement::Begin(_), None) => {
+                    block = Some(self.engine.begin().await?);
+                    ResultSet::Begin
+                }
+                (ast::Statement::Commit, Some(txn)) => {
+                    txn.commit().await?;
+                    ResultSet::Commit
+                }
+                (ast::Statement::Rollback, Some(txn)) => {
+                    txn.rollback().await?;
+                    ResultSet::Rollback
+                }
+                (ast::Statement::Begin(_), Some(txn)) => {
+                    txn.rollback().await?;
+                    return Err(Error::Value("The transaction is already begun".to_string()));
+                }
+                (ast::Statement::Commit | ast::Statement::Rollback, None) => {
+                    return Err(Error::Value("No transaction is begun".to_string()))
+                }
+                (statement, None) => self.autocommit(statement).await?,
+                (statement, Some(txn)) => {
+                    let result = match Planner::new(&txn).build_statement(statement).await {
+                        Ok(node) => node.execute(&txn).await,
+                        Err(err) => Err(err),
+                    };
+                    match result {
+                        Ok(result) => {
+                            block = Some(txn);
+                            result
+                        }
+                        Err(err) => {
+                            txn.rollback().await?;
+                            return Err(err);
+                        }
+                    }
+                }
+            };
+            results.push(result);
+        }
+        if let Some(txn) = block {
+            txn.rollback().await?;
+            return Err(Error::Value("The transaction isn't committed".to_string()));
+        }
+        Ok(results)
+    }
+
+    /// See [`StorageEngine::with_memory_budget`]
+    pub fn with_memory_budget(mut self, memory_budget: usize) -> Self {
+        self.engine = self.engine.with_memory_budget(memory_budget);
+        self
+    }
+
+    /// See [`StorageEngine::with_cast_mode`]
+    pub fn with_cast_mode(mut self, cast_mode: CastMode) -> Self {
+        self.engine = self.engine.with_cast_mode(cast_mode);
+        self
+    }
+
+    /// Register an observer which is notified with the writes of every committed transaction
+    pub fn on_commit(&self, observer: CommitObserver) {
+        self.engine.on_commit(observer);
+    }
+
+    /// The ids of the transactions in progress
+    pub fn active_transactions(&self) -> Vec<TransactionId> {
+        self.engine.active_transactions()
+    }
+
+    /// Abort a transaction in progress, see [`StorageEngine::abort`]
+    pub async fn abort(&self, id: TransactionId) -> SqlResult<()> {
+        self.engine.abort(id).await
+    }
+
+    /// Flush all the modified pages to disk
+    pub async fn close(self) -> SqlResult<()> {
+        self.engine.storage().flush().await?;
+        Ok(())
+    }
+
+    async fn buffer_pool(path: impl AsRef<Path>) -> SqlResult<BufferPoolManager> {
+        let disk_manager = DiskManager::new(path).await.map_err(storage::Error::from)?;
+        BufferPoolManager::new(DEFAULT_POOL_SIZE, DEFAULT_REPLACER_K, disk_manager)
+            .await
+            .map_err(|err| Error::Storage(err.into()))
+    }
+}
+
+/// Every entry of a dump is prefixed by its length, so a truncated dump is detected
+/// before it reaches the decoder
+fn encode(value: &impl Encoder, buf: &mut Vec<u8>) -> SqlResult<()> {
+    let mut entry = Vec::new();
+    value.encode(&mut entry).map_err(storage::Error::from)?;
+    buf.put_u32(entry.len() as u32);
+    buf.put_slice(&entry);
+    Ok(())
+}
+
+async fn decode<T: Decoder>(reader: &mut (impl AsyncRead + Unpin)) -> SqlResult<T> {
+    let read = |err: std::io::Error| match err.kind() {
+        ErrorKind::UnexpectedEof => Error::Value("The dump is truncated".to_string()),
+        _ => Error::Storage(err.into()),
+    };
+    let len = reader.read_u32().await.map_err(read)? as usize;
+    let mut entry = vec![0; len];
+    reader.read_exact(&mut entry).await.map_err(read)?;
+    let mut entry = entry.as_slice();
+    let value = T::decode(&mut entry).map_err(storage::Error::from)?;
+    match entry.has_remaining() {
+        true => Err(Error::Value("The dump is corrupted".to_string())),
+        false => Ok(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sql::catalog::{Catalog, Table, ROWID};
+    use crate::sql::parser::dql::Order;
+    use crate::sql::plan::node::{Lookup, Node};
+    use crate::sql::types::expression::Expression;
+    use crate::sql::types::{DataType, Value};
+    use crate::storage::disk::disk_manager::DurabilityMode;
+    use crate::storage::engine::TableStats;
+    use crate::storage::page::table::TableNode;
+    use crate::storage::CHECKSUM_SIZE;
+    use ordered_float::OrderedFloat;
+    use std::ops::Bound;
+
+    fn column(name: &str, datatype: DataType) -> (String, Option<DataType>) {
+        (name.to_string(), Some(datatype))
+    }
+
+    #[tokio::test]
+    async fn reopen() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        assert_eq!(
+            db.execute("CREATE TABLE user (id INTEGER PRIMARY, name STRING, age INTEGER);")
+                .await?,
+            ResultSet::CreateTable {
+                name: "user".to_string()
+            }
+        );
+        for id in 0..100 {
+            db.execute(&format!(
+                "INSERT INTO user VALUES ({}, 'name{}', {});",
+                id,
+                id,
+                id % 10
+            ))
+            .await?;
+        }
+        assert_eq!(
+            db.execute("DELETE FROM user WHERE age = 0;").await?,
+            ResultSet::Delete { count: 10 }
+        );
+        let query = "SELECT id, name FROM user WHERE age = 3 ORDER BY id DESC LIMIT 2;";
+        let expected = ResultSet::Query {
+            columns: vec![
+                column("id", DataType::Integer),
+                column("name", DataType::String),
+            ],
+            rows: vec![
+                vec![Value::Integer(93), Value::String("name93".to_string())],
+                vec![Value::Integer(83), Value::String("name83".to_string())],
+            ],
+        };
+        assert_eq!(db.execute(query).await?, expected);
+        db.close().await?;
+
+        let db = Database::open(f.path()).await?;
+        assert_eq!(db.execute(query).await?, expected);
+        let (_, rows) = db
+            .execute("SELECT * FROM user;")
+            .await?
+            .into_query()
+            .unwrap();
+        assert_eq!(rows.len(), 90);
+        assert!(Database::create(f.path()).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rename_column() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        db.execute("CREATE TABLE user (id INTEGER PRIMARY, name STRING);")
+            .await?;
+        db.execute("INSERT INTO user VALUES (1, 'alice');").await?;
+        assert_eq!(
+            db.execute("ALTER TABLE user RENAME COLUMN name TO nickname;")
+                .await?,
+            ResultSet::AlterTable {
+                name: "user".to_string()
+            }
+        );
+        assert!(db.execute("SELECT name FROM user;").await.is_err());
+        assert!(db
+            .execute("ALTER TABLE user RENAME COLUMN nickname TO id;")
+            .await
+            .is_err());
+        assert_eq!(
+            db.execute("SELECT nickname FROM user WHERE id = 1;")
+                .await?,
+            ResultSet::Query {
+                columns: vec![column("nickname", DataType::String)],
+                rows: vec![vec![Value::String("alice".to_string())]],
+            }
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn function() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        db.execute("CREATE TABLE user (id INTEGER PRIMARY, name STRING);")
+            .await?;
+        db.execute("INSERT INTO user VALUES (1, 'alice');").await?;
+        db.execute("INSERT INTO user VALUES (2, NULL);").await?;
+        assert_eq!(
+            db.execute("SELECT upper(name), length(coalesce(name, 'unknown')) FROM user;")
+                .await?
+                .into_query()
+                .unwrap()
+                .1,
+            vec![
+                vec![Value::String("ALICE".to_string()), Value::Integer(5)],
+                vec![Value::Null, Value::Integer(7)],
+            ]
+        );
+        assert!(matches!(
+            db.execute("SELECT reverse(name) FROM user;").await,
+            Err(Error::NotFound("function", _))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn integer_range() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        db.execute("CREATE TABLE t (id INTEGER PRIMARY, a TINYINT, b SMALLINT);")
+            .await?;
+        db.execute("INSERT INTO t VALUES (1, 127, 32767);").await?;
+        db.execute("INSERT INTO t VALUES (2, -128, -32768);")
+            .await?;
+        assert!(db
+            .execute("INSERT INTO t VALUES (3, 128, 0);")
+            .await
+            .is_err());
+        assert!(db
+            .execute("INSERT INTO t VALUES (4, -129, 0);")
+            .await
+            .is_err());
+        assert!(db
+            .execute("INSERT INTO t VALUES (5, 0, 32768);")
+            .await
+            .is_err());
+        assert!(db
+            .execute("UPDATE t SET a = 200 WHERE id = 1;")
+            .await
+            .is_err());
+        assert_eq!(
+            db.execute("SELECT a, b FROM t;")
+                .await?
+                .into_query()
+                .unwrap()
+                .1,
+            vec![
+                vec![Value::Tinyint(127), Value::Smallint(32767)],
+                vec![Value::Tinyint(-128), Value::Smallint(-32768)],
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn on_commit() -> SqlResult<()> {
+        use crate::sql::engine::{CommitEvent, Write};
+        use std::sync::Mutex;
+
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        let events = Arc::new(Mutex::new(Vec::<CommitEvent>::new()));
+        let observed = events.clone();
+        db.on_commit(Box::new(move |event| {
+            observed.lock().unwrap().push(event.clone())
+        }));
+        db.execute("CREATE TABLE user (id INTEGER PRIMARY, name STRING);")
+            .await?;
+        db.execute("INSERT INTO user VALUES (1, 'alice');").await?;
+        db.execute("SELECT * FROM user;").await?;
+        db.execute("UPDATE user SET name = 'bob' WHERE id = 1;")
+            .await?;
+        db.execute("DELETE FROM user WHERE id = 1;").await?;
+        let row = |name: &str| vec![Value::Integer(1), Value::String(name.to_string())];
+        let write = |write: Write| CommitEvent {
+            writes: vec![write],
+        };
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                write(Write::Insert {
+                    table: "user".to_string(),
+                    row: row("alice"),
+                }),
+                write(Write::Update {
+                    table: "user".to_string(),
+                    row: row("bob"),
+                }),
+                write(Write::Delete {
+                    table: "user".to_string(),
+                    row: row("bob"),
+                }),
+            ]
+        );
+        assert_eq!(
+            events.lock().unwrap()[0]
+                .tables()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec!["user"]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn case() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        db.execute("CREATE TABLE user (id INTEGER PRIMARY, age INTEGER);")
+            .await?;
+        for (id, age) in [(1, 10), (2, 30), (3, 70)] {
+            db.execute(&format!("INSERT INTO user VALUES ({}, {});", id, age))
+                .await?;
+        }
+        let query =
+            "SELECT CASE WHEN age < 18 THEN 'young' WHEN age < 60 THEN 'adult' END AS stage \
+                     FROM user;";
+        let string = |s: &str| vec![Value::String(s.to_string())];
+        assert_eq!(
+            db.execute(query).await?,
+            ResultSet::Query {
+                columns: vec![column("stage", DataType::String)],
+                rows: vec![string("young"), string("adult"), vec![Value::Null]],
+            }
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn qualified_field() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        db.execute("CREATE TABLE user (id INTEGER PRIMARY, `a.b` INTEGER);")
+            .await?;
+        db.execute("INSERT INTO user VALUES (1, 2);").await?;
+        assert!(db
+            .execute("SELECT u.id, `a.b` FROM user AS u WHERE user.id = 1;")
+            .await
+            .is_err());
+        assert_eq!(
+            db.execute("SELECT u.id, u.`a.b` FROM user AS u WHERE u.id = 1;")
+                .await?,
+            ResultSet::Query {
+                columns: vec![
+                    column("id", DataType::Integer),
+                    column("a.b", DataType::Integer)
+                ],
+                rows: vec![vec![Value::Integer(1), Value::Integer(2)]],
+            }
+        );
+        assert!(matches!(
+            db.execute("SELECT a.b FROM user;").await,
+            Err(Error::NotFound("column", _))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn column_types() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        db.execute("CREATE TABLE t (id INTEGER PRIMARY, price INTEGER);")
+            .await?;
+        db.execute("INSERT INTO t VALUES (1, 21);").await?;
+        assert_eq!(
+            db.execute("SELECT id, price * 2 FROM t;").await?,
+            ResultSet::Query {
+                columns: vec![
+                    column("id", DataType::Integer),
+                    column("?column?", DataType::Integer)
+                ],
+                rows: vec![vec![Value::Integer(1), Value::Integer(42)]],
+            }
+        );
+        let (columns, _) = db
+            .execute("SELECT price / 2.0, price > 1, NULL FROM t;")
+            .await?
+            .into_query()
+            .unwrap();
+        assert_eq!(
+            columns,
+            vec![
+                column("?column?", DataType::Float),
+                column("?column?", DataType::Boolean),
+                ("?column?".to_string(), None),
+            ]
+        );
+        // an overflowing integer power is a double, the type of the column isn't known upfront
+        assert_eq!(
+            db.execute("SELECT 2 ^ 200, price ^ 2 FROM t;").await?,
+            ResultSet::Query {
+                columns: vec![
+                    ("?column?".to_string(), None),
+                    ("?column?".to_string(), None)
+                ],
+                rows: vec![vec![
+                    Value::Double(OrderedFloat(2f64.powi(200))),
+                    Value::Integer(441)
+                ]],
+            }
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn auto_increment() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Arc::new(Database::create(f.path()).await?);
+        db.execute("CREATE TABLE t (id INTEGER PRIMARY AUTOINCREMENT, task INTEGER, n INTEGER);")
+            .await?;
+        let (tasks, inserts) = (4, 25);
+        let handles = (0..tasks)
+            .map(|task| {
+                let db = db.clone();
+                tokio::spawn(async move {
+                    for n in 0..inserts {
+                        db.execute(&format!(
+                            "INSERT INTO t (task, n) VALUES ({}, {});",
+                            task, n
+                        ))
+                        .await?;
+                    }
+                    SqlResult::Ok(())
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.await.unwrap()?;
+        }
+        let (_, rows) = db
+            .execute("SELECT id, task, n FROM t;")
+            .await?
+            .into_query()
+            .unwrap();
+        // the rows are scanned in the order of id, so the ids are unique if they are increasing
+        let ids = rows.iter().map(|row| row[0].clone()).collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            (1..=tasks * inserts)
+                .map(Value::Integer)
+                .collect::<Vec<_>>()
+        );
+        // the ids of every task increase in the order of its inserts
+        for task in 0..tasks {
+            let ns = rows
+                .iter()
+                .filter(|row| row[1] == Value::Integer(task))
+                .map(|row| row[2].clone())
+                .collect::<Vec<_>>();
+            assert_eq!(ns, (0..inserts).map(Value::Integer).collect::<Vec<_>>());
+        }
+
+        db.execute("INSERT INTO t VALUES (200, 0, 0);").await?;
+        Arc::into_inner(db).unwrap().close().await?;
+        let db = Database::open(f.path()).await?;
+        db.execute("INSERT INTO t (task, n) VALUES (0, 0);").await?;
+        assert_eq!(
+            db.execute("SELECT id FROM t WHERE id > 200;")
+                .await?
+                .into_query()
+                .unwrap()
+                .1,
+            vec![vec![Value::Integer(201)]]
+        );
+        assert!(db
+            .execute("CREATE TABLE u (id STRING PRIMARY AUTOINCREMENT);")
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn dump_restore() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        db.execute(
+            "CREATE TABLE user (id INTEGER PRIMARY AUTOINCREMENT, name STRING, score DOUBLE);",
+        )
+        .await?;
+        db.execute("CREATE TABLE empty (id BIGINT PRIMARY);")
+            .await?;
+        for i in 0..300 {
+            let name = match i % 7 {
+                0 => "NULL".to_string(),
+                _ => format!("'user{}'", i),
+            };
+            db.execute(&format!(
+                "INSERT INTO user (name, score) VALUES ({}, {}.5);",
+                name, i
+            ))
+            .await?;
+        }
+        db.execute("CREATE INDEX name_score ON user (name, score);")
+            .await?;
+        let mut dump = Vec::new();
+        db.dump(&mut dump).await?;
+
+        let restored_file = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let restored = Database::restore(restored_file.path(), dump.as_slice()).await?;
+        for sql in ["SELECT * FROM user;", "SELECT * FROM empty;"] {
+            assert_eq!(
+                restored.execute(sql).await?.into_query(),
+                db.execute(sql).await?.into_query()
+            );
+        }
+        // the indexes are restored with the tables
+        assert!(restored
+            .execute("CREATE INDEX name_score ON user (name, score);")
+            .await
+            .is_err());
+        let lookup = "SELECT id FROM user WHERE name = 'user12' AND score = 12.5;";
+        assert_eq!(
+            restored.execute(lookup).await?.into_query().unwrap().1,
+            vec![vec![Value::Integer(13)]]
+        );
+
+        // a dump can be restored with another page size
+        let paged_file = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let disk_manager = DiskManager::new(paged_file.path())
+            .await
+            .map_err(storage::Error::from)?;
+        let buffer_pool = BufferPoolManager::with_page_size(
+            DEFAULT_POOL_SIZE,
+            DEFAULT_REPLACER_K,
+            disk_manager,
+            PAGE_SIZE * 2,
+        )
+        .await
+        .map_err(|err| Error::Storage(err.into()))?;
+        let paged = Database::restore_with(buffer_pool, dump.as_slice()).await?;
+        assert_eq!(
+            paged.engine.storage().buffer_pool().page_size(),
+            PAGE_SIZE * 2
+        );
+        for sql in ["SELECT * FROM user;", "SELECT * FROM empty;", lookup] {
+            assert_eq!(
+                paged.execute(sql).await?.into_query(),
+                db.execute(sql).await?.into_query()
+            );
+        }
+        assert!(paged
+            .execute("CREATE INDEX name_score ON user (name, score);")
+            .await
+            .is_err());
+        // the sequence continues after the restored rows
+        restored
+            .execute("INSERT INTO user (name, score) VALUES ('new', 0.0);")
+            .await?;
+        assert_eq!(
+            restored
+                .execute("SELECT id FROM user WHERE id > 300;")
+                .await?
+                .into_query()
+                .unwrap()
+                .1,
+            vec![vec![Value::Integer(301)]]
+        );
+
+        let other = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        assert!(Database::restore(other.path(), &b"not a dump"[..])
+            .await
+            .is_err());
+        assert!(Database::restore(other.path(), &dump[..dump.len() - 1])
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reindex_analyze() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        db.execute("CREATE TABLE user (id INTEGER PRIMARY, name STRING INDEX, age INTEGER);")
+            .await?;
+        for i in 0..50 {
+            db.execute(&format!(
+                "INSERT INTO user VALUES ({}, 'name{}', {});",
+                i,
+                i,
+                i % 5
+            ))
+            .await?;
+        }
+        db.execute("CREATE INDEX age_name ON user (age, name);")
+            .await?;
+        let analyze = |rows: usize| ResultSet::Analyze {
+            name: "user".to_string(),
+            stats: TableStats { rows, pages: 1 },
+        };
+        assert_eq!(db.engine.storage().stats("user").await, None);
+        assert_eq!(db.execute("ANALYZE user;").await?, analyze(50));
+        db.execute("DELETE FROM user WHERE id < 10;").await?;
+        // the stats are only refreshed by analyze
+        assert_eq!(
+            db.engine.storage().stats("user").await,
+            Some(TableStats { rows: 50, pages: 1 })
+        );
+        assert_eq!(db.execute("ANALYZE user;").await?, analyze(40));
+        assert_eq!(
+            db.engine.storage().stats("user").await,
+            Some(TableStats { rows: 40, pages: 1 })
+        );
+
+        assert_eq!(
+            db.execute("REINDEX TABLE user;").await?,
+            ResultSet::Reindex {
+                name: "user".to_string(),
+                count: 40
+            }
+        );
+        assert_eq!(
+            db.execute("SELECT name FROM user WHERE id = 42;")
+                .await?
+                .into_query()
+                .unwrap()
+                .1,
+            vec![vec![Value::String("name42".to_string())]]
+        );
+        // the secondary and composite indexes are rebuilt too and still take writes
+        db.execute("INSERT INTO user VALUES (50, 'name50', 2);")
+            .await?;
+        let ids = |sql: &'static str| {
+            let db = &db;
+            async move {
+                let (_, rows) = db.execute(sql).await?.into_query().unwrap();
+                SqlResult::Ok(rows)
+            }
+        };
+        let id = |ids: &[i64]| {
+            ids.iter()
+                .map(|id| vec![Value::Integer(*id)])
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            ids("SELECT id FROM user WHERE name = 'name42';").await?,
+            id(&[42])
+        );
+        assert!(ids("SELECT id FROM user WHERE name = 'name7';")
+            .await?
+            .is_empty());
+        assert_eq!(
+            ids("SELECT id FROM user WHERE name = 'name50';").await?,
+            id(&[50])
+        );
+        assert_eq!(
+            ids("SELECT id FROM user WHERE age = 2 AND name = 'name12';").await?,
+            id(&[12])
+        );
+        assert_eq!(
+            ids("SELECT id FROM user WHERE age = 2 AND name = 'name50';").await?,
+            id(&[50])
+        );
+        let name42 = db
+            .engine
+            .storage()
+            .search_index("user", 1, &Value::String("name42".to_string()))
+            .await?;
+        assert_eq!(name42.map(|record_ids| record_ids.len()), Some(1));
+        assert!(db.execute("ANALYZE pet;").await.is_err());
+        assert!(db.execute("REINDEX TABLE pet;").await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn index_intersection() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        db.execute("CREATE TABLE t (id INTEGER PRIMARY, a INTEGER INDEX, b INTEGER INDEX);")
+            .await?;
+        for chunk in (0..400).collect::<Vec<_>>().chunks(100) {
+            let values = chunk
+                .iter()
+                .map(|i| format!("({}, {}, {})", i, i % 20, i % 21))
+                .collect::<Vec<_>>()
+                .join(", ");
+            db.execute(&format!("INSERT INTO t VALUES {};", values))
+                .await?;
+        }
+        let ids = |sql: &'static str| {
+            let db = &db;
+            async move {
+                let stats = db.engine.storage().buffer_pool().stats();
+                let (_, rows) = db.execute(sql).await?.into_query().unwrap();
+                let accesses =
+                    db.engine.storage().buffer_pool().stats().accesses() - stats.accesses();
+                Ok::<_, Error>((rows, accesses))
+            }
+        };
+        let id = |i: i64| vec![Value::Integer(i)];
+
+        let (rows, intersected) = ids("SELECT id FROM t WHERE a = 3 AND b = 5;").await?;
+        assert_eq!(rows, vec![id(383)]);
+        // only one index applies, the other equality is a residual filter
+        let (rows, single) = ids("SELECT id FROM t WHERE a = 3 AND b + 0 = 5;").await?;
+        assert_eq!(rows, vec![id(383)]);
+        let (rows, scanned) = ids("SELECT id FROM t WHERE a + 0 = 3 AND b + 0 = 5;").await?;
+        assert_eq!(rows, vec![id(383)]);
+        assert!(intersected < single && single < scanned);
+
+        let (rows, _) = ids("SELECT id FROM t WHERE 3 = a AND id > 300;").await?;
+        assert_eq!(rows, vec![id(303), id(323), id(343), id(363), id(383)]);
+        let (rows, _) = ids("SELECT id FROM t WHERE a = 3 AND b = 4;").await?;
+        assert!(rows.is_empty());
+
+        db.execute("UPDATE t SET b = 4 WHERE id = 383;").await?;
+        let (rows, _) = ids("SELECT id FROM t WHERE a = 3 AND b = 5;").await?;
+        assert!(rows.is_empty());
+        let (rows, _) = ids("SELECT id FROM t WHERE a = 3 AND b = 4;").await?;
+        assert_eq!(rows, vec![id(383)]);
+        db.execute("DELETE FROM t WHERE a = 3 AND b = 4;").await?;
+        let (rows, _) = ids("SELECT id FROM t WHERE a = 3 AND b = 4;").await?;
+        assert!(rows.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn like_prefix() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        db.execute("CREATE TABLE user (id INTEGER PRIMARY, name VARCHAR INDEX);")
+            .await?;
+        db.execute(
+            "INSERT INTO user VALUES (1, 'John'), (2, 'Jp'), (3, 'Jo'), (4, 'jo'), \
+             (5, 'Jonathan'), (6, 'J'), (7, NULL), (8, 'Mike'), (9, 'Joe');",
+        )
+        .await?;
+        let txn = db.engine.begin().await?;
+        let node = Planner::new(&txn)
+            .build_statement(parser::parse("SELECT * FROM user WHERE name LIKE 'Jo%';")?)
+            .await?;
+        let Node::IndexScan {
+            lookups, filter, ..
+        } = node
+        else {
+            panic!("expected an index scan, got {:?}", node);
+        };
+        let string = |s: &str| Value::String(s.to_string());
+        assert_eq!(
+            lookups,
+            vec![(
+                1,
+                Lookup::Range(Bound::Included(string("Jo")), Bound::Excluded(string("Jp")))
+            )]
+        );
+        assert!(filter.is_none());
+        txn.commit().await?;
+
+        let ids = |sql: &'static str| {
+            let db = &db;
+            async move {
+                let (_, rows) = db.execute(sql).await?.into_query().unwrap();
+                Ok::<_, Error>(
+                    rows.into_iter()
+                        .map(|row| row[0].clone())
+                        .collect::<Vec<_>>(),
+                )
+            }
+        };
+        let id = |ids: &[i64]| ids.iter().map(|id| Value::Integer(*id)).collect::<Vec<_>>();
+        assert_eq!(
+            ids("SELECT id FROM user WHERE name LIKE 'Jo%';").await?,
+            id(&[1, 3, 5, 9])
+        );
+        assert_eq!(
+            ids("SELECT id FROM user WHERE name LIKE 'Jo%' AND id > 3;").await?,
+            id(&[5, 9])
+        );
+        assert!(ids("SELECT id FROM user WHERE name LIKE 'Ja%';")
+            .await?
+            .is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn storage_error() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        db.execute("CREATE TABLE t (id INTEGER PRIMARY, name VARCHAR);")
+            .await?;
+        db.execute("INSERT INTO t VALUES (1, 'a'), (2, 'b');")
+            .await?;
+        let record_ids = db
+            .engine
+            .storage()
+            .read_primary("t")
+            .await
+            .unwrap()
+            .search_range(..)
+            .await?;
+        let corrupt = record_ids[0].page_id;
+        {
+            // the deleted flag of the first tuple follows the header of the node and the timestamp
+            let mut header = Vec::new();
+            TableNode::new(corrupt, Vec::new())
+                .encode(&mut header)
+                .map_err(storage::Error::from)?;
+            let mut page = db
+                .engine
+                .storage()
+                .buffer_pool()
+                .fetch_page_write_owned(corrupt)
+                .await
+                .map_err(storage::Error::from)?;
+            page[CHECKSUM_SIZE + header.len() + 8] = 2;
+        }
+        // the scan executor propagates the decoding failure with `?`
+        let err = db.execute("SELECT * FROM t;").await.unwrap_err();
+        assert!(
+            matches!(err, Error::Storage(storage::Error::Buffer(_))),
+            "{:?}",
+            err
+        );
+        let err = db.execute("DELETE FROM t;").await.unwrap_err();
+        assert!(matches!(err, Error::Storage(_)), "{:?}", err);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn delete_range() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        db.execute("CREATE TABLE t (id INTEGER PRIMARY, a INTEGER);")
+            .await?;
+        for chunk in (0..400).collect::<Vec<_>>().chunks(100) {
+            let values = chunk
+                .iter()
+                .map(|i| format!("({}, {})", i, i % 7))
+                .collect::<Vec<_>>()
+                .join(", ");
+            db.execute(&format!("INSERT INTO t VALUES {};", values))
+                .await?;
+        }
+        let delete = |sql: &'static str| {
+            let db = &db;
+            async move {
+                let stats = db.engine.storage().buffer_pool().stats();
+                let result = db.execute(sql).await?;
+                let accesses =
+                    db.engine.storage().buffer_pool().stats().accesses() - stats.accesses();
+                Ok::<_, Error>((result, accesses))
+            }
+        };
+        let (result, ranged) = delete("DELETE FROM t WHERE id BETWEEN 100 AND 199;").await?;
+        assert_eq!(result, ResultSet::Delete { count: 100 });
+        // the same rows, but the filter isn't a range of the key, so every row is deleted alone
+        let (result, scanned) = delete("DELETE FROM t WHERE id + 0 BETWEEN 200 AND 299;").await?;
+        assert_eq!(result, ResultSet::Delete { count: 100 });
+        assert!(ranged < scanned, "{} {}", ranged, scanned);
+
+        let (result, _) = delete("DELETE FROM t WHERE id > 380 AND id <= 390;").await?;
+        assert_eq!(result, ResultSet::Delete { count: 10 });
+        let (result, _) = delete("DELETE FROM t WHERE id BETWEEN 50 AND 10;").await?;
+        assert_eq!(result, ResultSet::Delete { count: 0 });
+        let (result, _) = delete("DELETE FROM t WHERE id BETWEEN 150 AND 250;").await?;
+        assert_eq!(result, ResultSet::Delete { count: 0 });
+
+        let (_, rows) = db.execute("SELECT id FROM t;").await?.into_query().unwrap();
+        let expected = (0..100)
+            .chain(300..381)
+            .chain(391..400)
+            .map(|i| vec![Value::Integer(i)])
+            .collect::<Vec<_>>();
+        assert_eq!(rows, expected);
+        // the deleted keys can be inserted again
+        db.execute("INSERT INTO t VALUES (150, 0);").await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn import_csv() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        db.execute(
+            "CREATE TABLE user (id INTEGER PRIMARY AUTOINCREMENT, name STRING NOT NULL, age TINYINT, score DOUBLE);",
+        )
+        .await?;
+        let csv = "name,age,id,score\nMike,18,1,1.5\n\"Lee, Bob\",,2,\nJohn,30,,-2\n";
+        assert_eq!(db.engine.import_csv("user", csv.as_bytes()).await?, 3);
+        let (_, rows) = db
+            .execute("SELECT * FROM user;")
+            .await?
+            .into_query()
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                vec![
+                    Value::Integer(1),
+                    Value::String("Mike".to_string()),
+                    Value::Tinyint(18),
+                    Value::Double(1.5.into())
+                ],
+                vec![
+                    Value::Integer(2),
+                    Value::String("Lee, Bob".to_string()),
+                    Value::Null,
+                    Value::Null
+                ],
+                vec![
+                    Value::Integer(3),
+                    Value::String("John".to_string()),
+                    Value::Tinyint(30),
+                    Value::Double((-2.0).into())
+                ],
+            ]
+        );
+
+        let error = |csv: &'static str| {
+            let db = &db;
+            async move {
+                db.engine
+                    .import_csv("user", csv.as_bytes())
+                    .await
+                    .unwrap_err()
+                    .to_string()
+            }
+        };
+        assert!(error("name,age\nAmy,1\nBen,1000\n")
+            .await
+            .starts_with("line 3:"));
+        assert!(error("name,age\nCat,1,2\n").await.starts_with("line 2:"));
+        assert!(error("name,id\nDan,1\n").await.starts_with("line 2:"));
+        assert!(error("name,id\n,10\n").await.starts_with("line 2:"));
+        assert!(error("name,unknown\n").await.contains("unknown"));
+        // the batch of a malformed row isn't inserted
+        let (_, rows) = db
+            .execute("SELECT * FROM user;")
+            .await?
+            .into_query()
+            .unwrap();
+        assert_eq!(rows.len(), 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn min_max() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        db.execute("CREATE TABLE t (id INTEGER PRIMARY, a INTEGER INDEX, b INTEGER);")
+            .await?;
+        let query = |sql: &'static str| {
+            let db = &db;
+            async move {
+                let stats = db.engine.storage().buffer_pool().stats();
+                let result = db.execute(sql).await?;
+                let accesses =
+                    db.engine.storage().buffer_pool().stats().accesses() - stats.accesses();
+                Ok::<_, Error>((result.into_query().unwrap(), accesses))
+            }
+        };
+        let ((columns, rows), _) = query("SELECT MIN(id), MAX(a) AS top, COUNT(b) FROM t;").await?;
+        assert_eq!(
+            columns,
+            vec![
+                column("min", DataType::Integer),
+                column("top", DataType::Integer),
+                column("count", DataType::Integer)
+            ]
+        );
+        assert_eq!(
+            rows,
+            vec![vec![Value::Null, Value::Null, Value::Integer(0)]]
+        );
+        assert_eq!(
+            query("SELECT MAX(id) FROM t;").await?.0 .1,
+            vec![vec![Value::Null]]
+        );
+
+        for chunk in (0..3000).collect::<Vec<_>>().chunks(500) {
+            let values = chunk
+                .iter()
+                .map(|i| match i % 7 {
+                    0 => format!("({}, NULL, NULL)", i),
+                    _ => format!("({}, {}, {})", i, 5000 - i, i % 100),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            db.execute(&format!("INSERT INTO t VALUES {};", values))
+                .await?;
+        }
+        let ((columns, rows), min_id) = query("SELECT MIN(id) AS lowest FROM t;").await?;
+        assert_eq!(columns, vec![column("lowest", DataType::Integer)]);
+        assert_eq!(rows, vec![vec![Value::Integer(0)]]);
+        let ((_, rows), scanned) = query("SELECT MIN(id + 0) FROM t;").await?;
+        assert_eq!(rows, vec![vec![Value::Integer(0)]]);
+        // the path from root to the first leaf, instead of every leaf and tuple
+        assert!(min_id <= 10, "{} accesses", min_id);
+        assert!(scanned > 3000);
+
+        for (sql, expected) in [
+            ("SELECT MAX(id) FROM t;", Value::Integer(2999)),
+            ("SELECT MIN(a) FROM t;", Value::Integer(2001)),
+            ("SELECT MAX(a) FROM t;", Value::Integer(4999)),
+            ("SELECT MAX(b) FROM t;", Value::Integer(99)),
+            ("SELECT COUNT(a) FROM t;", Value::Integer(2571)),
+            ("SELECT MAX(a) FROM t WHERE id > 10;", Value::Integer(4989)),
+        ] {
+            assert_eq!(query(sql).await?.0 .1, vec![vec![expected]], "{}", sql);
+        }
+        db.execute("DELETE FROM t WHERE id = 0;").await?;
+        assert_eq!(
+            query("SELECT MIN(id) FROM t;").await?.0 .1,
+            vec![vec![Value::Integer(1)]]
+        );
+        assert!(db.execute("SELECT id, MIN(a) FROM t;").await.is_err());
+        assert!(db.execute("SELECT MIN(a, b) FROM t;").await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn group_by_nulls() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        db.execute("CREATE TABLE t (id INTEGER PRIMARY, k INTEGER, v INTEGER);")
+            .await?;
+        db.execute(
+            "INSERT INTO t VALUES (1, 1, 10), (2, NULL, 20), (3, 2, 30), (4, NULL, NULL), \
+             (5, 1, 50), (6, NULL, 60);",
+        )
+        .await?;
+        let (columns, rows) = db
+            .execute("SELECT COUNT(id) AS n, k, MAX(v), COUNT(v) FROM t GROUP BY k;")
+            .await?
+            .into_query()
+            .unwrap();
+        assert_eq!(
+            columns,
+            vec![
+                column("n", DataType::Integer),
+                column("k", DataType::Integer),
+                column("max", DataType::Integer),
+                column("count", DataType::Integer)
+            ]
+        );
+        // the NULL keys form a single group, in the order the groups are first seen
+        let row = |n, k, max, count| vec![Value::Integer(n), k, max, Value::Integer(count)];
+        assert_eq!(
+            rows,
+            vec![
+                row(2, Value::Integer(1), Value::Integer(50), 2),
+                row(3, Value::Null, Value::Integer(60), 2),
+                row(1, Value::Integer(2), Value::Integer(30), 1),
+            ]
+        );
+        let (_, rows) = db
+            .execute("SELECT k FROM t WHERE id > 1 GROUP BY k;")
+            .await?
+            .into_query()
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                vec![Value::Null],
+                vec![Value::Integer(2)],
+                vec![Value::Integer(1)]
+            ]
+        );
+        let (_, rows) = db
+            .execute("SELECT MIN(v) FROM t WHERE id > 6 GROUP BY k;")
+            .await?
+            .into_query()
+            .unwrap();
+        assert!(rows.is_empty());
+        assert!(db
+            .execute("SELECT v, COUNT(id) FROM t GROUP BY k;")
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn null_comparison() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        db.execute("CREATE TABLE t (id INTEGER PRIMARY, x INTEGER);")
+            .await?;
+        db.execute("INSERT INTO t VALUES (1, 1), (2, NULL), (3, 3), (4, 2);")
+            .await?;
+        // a comparison with NULL is NULL, so the row matches neither the filter nor its negation
+        for (sql, expected) in [
+            ("SELECT id FROM t WHERE x > 2;", vec![3]),
+            ("SELECT id FROM t WHERE NOT (x > 2);", vec![1, 4]),
+            ("SELECT id FROM t WHERE x = 2;", vec![4]),
+            ("SELECT id FROM t WHERE NOT (x < 2);", vec![3, 4]),
+        ] {
+            let (_, rows) = db.execute(sql).await?.into_query().unwrap();
+            assert_eq!(
+                rows,
+                expected
+                    .into_iter()
+                    .map(|id| vec![Value::Integer(id)])
+                    .collect::<Vec<_>>()
+            );
+        }
+        let (_, rows) = db
+            .execute("SELECT x > 2, x = NULL FROM t WHERE id = 2;")
+            .await?
+            .into_query()
+            .unwrap();
+        assert_eq!(rows, vec![vec![Value::Null, Value::Null]]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn schema_cache() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        db.execute("CREATE TABLE t (id INTEGER PRIMARY, a INTEGER);")
+            .await?;
+        let buffer_pool = db.engine.storage().buffer_pool().clone();
+        let names = |table: Table| {
+            table
+                .columns()
+                .iter()
+                .map(|column| column.name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let txn = db.engine.begin().await?;
+        txn.read_table("t").await?;
+        let stats = buffer_pool.stats();
+        for _ in 0..10 {
+            assert_eq!(names(txn.read_table("t").await?.unwrap()), vec!["id", "a"]);
+        }
+        // other transactions share the cached schema
+        let other = db.engine.begin().await?;
+        other.read_table("t").await?;
+        assert_eq!(buffer_pool.stats(), stats);
+
+        db.execute("ALTER TABLE t RENAME COLUMN a TO b;").await?;
+        assert_eq!(names(txn.read_table("t").await?.unwrap()), vec!["id", "b"]);
+        let stats = buffer_pool.stats();
+        for _ in 0..10 {
+            txn.read_table("t").await?;
+        }
+        assert_eq!(buffer_pool.stats(), stats);
+
+        db.execute("DROP TABLE t;").await?;
+        assert!(txn.read_table("t").await?.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn memory_budget() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?.with_memory_budget(4096);
+        db.execute("CREATE TABLE t (id INTEGER PRIMARY, name STRING);")
+            .await?;
+        let values = (0..500)
+            .map(|i| format!("({}, 'name{}')", i, i))
+            .collect::<Vec<_>>()
+            .join(", ");
+        db.execute(&format!("INSERT INTO t VALUES {};", values))
+            .await?;
+
+        assert!(matches!(
+            db.execute("SELECT * FROM t ORDER BY name;").await,
+            Err(Error::MemoryBudget("sort", 4096))
+        ));
+        assert!(matches!(
+            db.execute("SELECT name, COUNT(id) FROM t GROUP BY name;")
+                .await,
+            Err(Error::MemoryBudget("aggregate", 4096))
+        ));
+        // the last row fails the filter, so the budget is exceeded before it's read
+        assert!(db
+            .execute("SELECT * FROM t WHERE 1 / (id - 499) < 1 ORDER BY name;")
+            .await
+            .is_err_and(|err| matches!(err, Error::MemoryBudget("sort", 4096))));
+        assert!(db
+            .execute("SELECT name, COUNT(id) FROM t WHERE 1 / (id - 499) < 1 GROUP BY name;")
+            .await
+            .is_err_and(|err| matches!(err, Error::MemoryBudget("aggregate", 4096))));
+        // a single group stays within the budget, so the scan reaches the last row
+        assert!(db
+            .execute("SELECT COUNT(id) FROM t WHERE 1 / (id - 499) < 1;")
+            .await
+            .is_err_and(|err| !matches!(err, Error::MemoryBudget(..))));
+        // the operators which don't buffer rows aren't limited
+        let (_, rows) = db.execute("SELECT * FROM t;").await?.into_query().unwrap();
+        assert_eq!(rows.len(), 500);
+        let (_, rows) = db
+            .execute("SELECT * FROM t WHERE id < 10 ORDER BY name DESC;")
+            .await?
+            .into_query()
+            .unwrap();
+        assert_eq!(rows.len(), 10);
+        assert_eq!(rows[0][1], Value::String("name9".to_string()));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn non_finite_floats() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        db.execute("CREATE TABLE t (id INTEGER PRIMARY, f FLOAT, d DOUBLE);")
+            .await?;
+        for csv in [
+            "id,d\n1,NaN\n",
+            "id,d\n1,inf\n",
+            "id,f\n1,-inf\n",
+            "id,f\n1,1e300\n",
+        ] {
+            assert!(
+                db.engine.import_csv("t", csv.as_bytes()).await.is_err(),
+                "{}",
+                csv
+            );
+        }
+        db.engine
+            .import_csv("t", "id,f,d\n1,1.5,1e300\n".as_bytes())
+            .await?;
+        assert!(db
+            .execute("UPDATE t SET d = d * d WHERE id = 1;")
+            .await
+            .is_err());
+        let (_, rows) = db.execute("SELECT * FROM t;").await?.into_query().unwrap();
+        assert_eq!(
+            rows,
+            vec![vec![
+                Value::Integer(1),
+                Value::Float(OrderedFloat(1.5)),
+                Value::Double(OrderedFloat(1e300))
+            ]]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn abort() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        db.execute("CREATE TABLE user (id INTEGER PRIMARY, name STRING);")
+            .await?;
+        let txn = db.engine.begin().await?;
+        let id = txn.id();
+        assert_eq!(db.active_transactions(), vec![id]);
+        let row = vec![Value::Integer(1), Value::String("alice".to_string())];
+        txn.insert("user", row.clone()).await?;
+        assert!(txn.read("user", &Value::Integer(1)).await?.is_some());
+
+        let engine = db.engine.clone();
+        tokio::spawn(async move { engine.abort(id).await })
+            .await
+            .unwrap()?;
+        assert!(db.active_transactions().is_empty());
+        assert!(matches!(db.abort(id).await, Err(Error::Aborted(_))));
+        // the insert is undone by the abort
+        let (_, rows) = db
+            .execute("SELECT * FROM user;")
+            .await?
+            .into_query()
+            .unwrap();
+        assert!(rows.is_empty());
+        assert!(matches!(
+            txn.insert("user", row).await,
+            Err(Error::Aborted(_))
+        ));
+        assert!(matches!(txn.commit().await, Err(Error::Aborted(_))));
+        assert!(db.abort(id).await.is_err());
+
+        let txn = db.engine.begin().await?;
+        let id = txn.id();
+        txn.commit().await?;
+        assert!(db.active_transactions().is_empty());
+        assert!(db.abort(id).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn commit_failure() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        db.execute("CREATE TABLE user (id INTEGER PRIMARY, name STRING);")
+            .await?;
+        db.execute("CREATE TABLE pet (id INTEGER PRIMARY);").await?;
+        db.execute("INSERT INTO user VALUES (1, 'alice');").await?;
+        let storage = db.engine.storage();
+        let pet = storage.read_table("pet").await?.unwrap().page_id();
+        storage
+            .buffer_pool()
+            .flush_page(pet)
+            .await
+            .map_err(storage::Error::from)?;
+        // the commit records the index roots in every table page, so it fails to read the pet page
+        let saved = {
+            let mut page = storage
+                .buffer_pool()
+                .fetch_page_write_owned(pet)
+                .await
+                .map_err(storage::Error::from)?;
+            let saved = page.to_vec();
+            // the name of the table follows its length, 0xff is never valid utf-8
+            page[CHECKSUM_SIZE + 4] = 0xff;
+            saved
+        };
+        let txn = db.engine.begin().await?;
+        let id = txn.id();
+        txn.insert(
+            "user",
+            vec![Value::Integer(2), Value::String("bob".to_string())],
+        )
+        .await?;
+        txn.delete("user", &Value::Integer(1)).await?;
+        assert!(txn.commit().await.is_err());
+        // the page is quarantined once it fails to decode
+        assert!(storage
+            .buffer_pool()
+            .clear_quarantine(pet)
+            .await
+            .map_err(storage::Error::from)?);
+        storage
+            .buffer_pool()
+            .fetch_page_write_owned(pet)
+            .await
+            .map_err(storage::Error::from)?
+            .copy_from_slice(&saved);
+
+        // the failed commit is rolled back instead of being left half applied
+        assert!(db.active_transactions().is_empty());
+        assert!(db.abort(id).await.is_err());
+        let (_, rows) = db
+            .execute("SELECT * FROM user;")
+            .await?
+            .into_query()
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![vec![Value::Integer(1), Value::String("alice".to_string())]]
+        );
+        db.execute("INSERT INTO user VALUES (2, 'bob');").await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn update_returning() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        db.execute("CREATE TABLE t (id INTEGER PRIMARY, a INTEGER, b VARCHAR);")
+            .await?;
+        db.execute("INSERT INTO t VALUES (1, 10, 'x'), (2, 20, 'y'), (3, 30, 'z');")
+            .await?;
+        let (columns, rows) = db
+            .execute("UPDATE t SET a = a + 1, b = 'w' WHERE id > 1 RETURNING *;")
+            .await?
+            .into_query()
+            .unwrap();
+        assert_eq!(
+            columns,
+            vec![
+                column("id", DataType::Integer),
+                column("a", DataType::Integer),
+                column("b", DataType::String)
+            ]
+        );
+        let row = |id, a| {
+            vec![
+                Value::Integer(id),
+                Value::Integer(a),
+                Value::String("w".to_string()),
+            ]
+        };
+        assert_eq!(rows, vec![row(2, 21), row(3, 31)]);
+        let (columns, rows) = db
+            .execute("UPDATE t SET a = a * 2 WHERE id = 1 RETURNING a AS doubled, a - 10;")
+            .await?
+            .into_query()
+            .unwrap();
+        assert_eq!(
+            columns,
+            vec![
+                column("doubled", DataType::Integer),
+                column("?column?", DataType::Integer)
+            ]
+        );
+        assert_eq!(rows, vec![vec![Value::Integer(20), Value::Integer(10)]]);
+        assert_eq!(
+            db.execute("UPDATE t SET a = 0 WHERE id = 4;").await?,
+            ResultSet::Update { count: 0 }
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn select_without_from() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        let (columns, rows) = db.execute("SELECT 1 + 1;").await?.into_query().unwrap();
+        assert_eq!(columns.len(), 1);
+        assert_eq!(rows, vec![vec![Value::Tinyint(2)]]);
+        let (columns, rows) = db
+            .execute("SELECT 2 * 3 AS six, 'a';")
+            .await?
+            .into_query()
+            .unwrap();
+        assert_eq!(columns[0].0, "six");
+        assert_eq!(
+            rows,
+            vec![vec![Value::Tinyint(6), Value::String("a".to_string())]]
+        );
+        assert!(db.execute("SELECT id;").await.is_err());
+        assert!(db.execute("SELECT *;").await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cast_mode() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        assert!(db.execute("SELECT '1' + 1;").await.is_err());
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path())
+            .await?
+            .with_cast_mode(CastMode::Lenient);
+        let (_, rows) = db.execute("SELECT '1' + 1;").await?.into_query().unwrap();
+        assert_eq!(rows, vec![vec![Value::Tinyint(2)]]);
+        assert!(db.execute("SELECT 'a' + 1;").await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cast() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        db.execute("CREATE TABLE t (id INTEGER PRIMARY, code STRING);")
+            .await?;
+        db.execute("INSERT INTO t VALUES (1, '10'), (2, '9'), (3, NULL);")
+            .await?;
+        let (_, rows) = db
+            .execute("SELECT CAST(code AS INTEGER), CAST(id AS STRING) FROM t ORDER BY id;")
+            .await?
+            .into_query()
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                vec![Value::Integer(10), Value::String("1".to_string())],
+                vec![Value::Integer(9), Value::String("2".to_string())],
+                vec![Value::Null, Value::String("3".to_string())],
+            ]
+        );
+        assert!(matches!(
+            db.execute("SELECT CAST(300 AS TINYINT);").await,
+            Err(Error::OutOfBound("Tinyint", "overflow"))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn qualified_wildcard() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        db.execute("CREATE TABLE user (id INTEGER PRIMARY, name VARCHAR, age INTEGER);")
+            .await?;
+        db.execute("INSERT INTO user VALUES (1, 'alice', 30);")
+            .await?;
+        let txn = db.engine.begin().await?;
+        let node = Planner::new(&txn)
+            .build_statement(parser::parse("SELECT u.* FROM user AS u;")?)
+            .await?;
+        let Node::Projection { expressions, .. } = node else {
+            panic!("expected a projection, got {:?}", node);
+        };
+        assert_eq!(
+            expressions,
+            (0..3)
+                .map(|index| (Expression::Column(index), None))
+                .collect::<Vec<_>>()
+        );
+        assert!(Planner::new(&txn)
+            .build_statement(parser::parse("SELECT user.* FROM user AS u;")?)
+            .await
+            .is_err());
+        txn.commit().await?;
+
+        let (columns, rows) = db
+            .execute("SELECT u.* FROM user AS u;")
+            .await?
+            .into_query()
+            .unwrap();
+        assert_eq!(
+            columns,
+            vec![
+                column("id", DataType::Integer),
+                column("name", DataType::String),
+                column("age", DataType::Integer)
+            ]
+        );
+        assert_eq!(
+            rows,
+            vec![vec![
+                Value::Integer(1),
+                Value::String("alice".to_string()),
+                Value::Integer(30)
+            ]]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn insert_select() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        db.execute("CREATE TABLE user (id INTEGER PRIMARY, name VARCHAR, grade INTEGER);")
+            .await?;
+        db.execute("CREATE TABLE archive (id BIGINT PRIMARY, name VARCHAR, grade INTEGER);")
+            .await?;
+        db.execute("INSERT INTO user VALUES (1, 'alice', 3), (2, 'bob', 5), (3, 'carol', 4);")
+            .await?;
+        assert_eq!(
+            db.execute("INSERT INTO archive SELECT * FROM user WHERE grade > 3;")
+                .await?,
+            ResultSet::Insert { count: 2 }
+        );
+        // the values are cast to the datatypes of the destination
+        let (_, rows) = db
+            .execute("SELECT * FROM archive;")
+            .await?
+            .into_query()
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                vec![
+                    Value::Bigint(2),
+                    Value::String("bob".to_string()),
+                    Value::Integer(5)
+                ],
+                vec![
+                    Value::Bigint(3),
+                    Value::String("carol".to_string()),
+                    Value::Integer(4)
+                ],
+            ]
+        );
+        db.execute("INSERT INTO archive (id, name) SELECT id + 10, name FROM user WHERE id = 1;")
+            .await?;
+        let (_, rows) = db
+            .execute("SELECT grade FROM archive WHERE id = 11;")
+            .await?
+            .into_query()
+            .unwrap();
+        assert_eq!(rows, vec![vec![Value::Null]]);
+        // the width of the query must match the destination
+        assert!(db
+            .execute("INSERT INTO archive SELECT id, name FROM user;")
+            .await
+            .is_err());
+        assert!(db
+            .execute("INSERT INTO archive (id) SELECT id, name FROM user;")
+            .await
+            .is_err());
+        // a violated constraint fails the whole statement
+        assert!(db
+            .execute("INSERT INTO user SELECT * FROM user;")
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn durability() -> SqlResult<()> {
+        for durability in [
+            DurabilityMode::None,
+            DurabilityMode::PerCommit,
+            DurabilityMode::PerWrite,
+        ] {
+            let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+            let disk_manager = DiskManager::new(f.path())
+                .await
+                .map_err(storage::Error::from)?
+                .with_durability(durability);
+            let buffer_pool = BufferPoolManager::new(DEFAULT_POOL_SIZE, 2, disk_manager)
+                .await
+                .map_err(|err| Error::Storage(err.into()))?;
+            let db = Database {
+                engine: StorageEngine::new(Arc::new(
+                    storage::engine::Engine::new(Arc::new(buffer_pool)).await?,
+                )),
+            };
+            let disk_manager = db.engine.storage().buffer_pool().disk_manager();
+            let before = disk_manager.syncs();
+            // a commit of only ddl is synced too
+            db.execute("CREATE TABLE t (id INTEGER PRIMARY);").await?;
+            let syncs = disk_manager.syncs() - before;
+            match durability {
+                DurabilityMode::None => assert_eq!(syncs, 0),
+                DurabilityMode::PerCommit => assert_eq!(syncs, 1),
+                DurabilityMode::PerWrite => assert!(syncs >= 1),
+            }
+            let before = disk_manager.syncs();
+            for i in 0..3 {
+                db.execute(&format!("INSERT INTO t VALUES ({});", i))
+                    .await?;
+            }
+            // a read only transaction has nothing to sync
+            db.execute("SELECT * FROM t;").await?;
+            let syncs = disk_manager.syncs() - before;
+            match durability {
+                DurabilityMode::None => assert_eq!(syncs, 0),
+                DurabilityMode::PerCommit => assert_eq!(syncs, 3),
+                // every commit writes at least the modified table page
+                DurabilityMode::PerWrite => assert!(syncs >= 3),
+            }
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rowid() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        db.execute("CREATE TABLE log (message VARCHAR, level INTEGER);")
+            .await?;
+        db.execute("INSERT INTO log VALUES ('start', 1), ('stop', 2);")
+            .await?;
+        db.execute("INSERT INTO log (level, message) VALUES (3, 'crash');")
+            .await?;
+        assert!(db
+            .execute("INSERT INTO log VALUES ('done', 1, 9);")
+            .await
+            .is_err());
+        assert!(db
+            .execute("CREATE TABLE bad (rowid INTEGER, name VARCHAR);")
+            .await
+            .is_err());
+
+        // `*` leaves the rowid out
+        let (columns, rows) = db
+            .execute("SELECT * FROM log;")
+            .await?
+            .into_query()
+            .unwrap();
+        assert_eq!(
+            columns,
+            vec![
+                column("message", DataType::String),
+                column("level", DataType::Integer)
+            ]
+        );
+        assert_eq!(rows.len(), 3);
+
+        let (columns, rows) = db
+            .execute("SELECT rowid, message FROM log;")
+            .await?
+            .into_query()
+            .unwrap();
+        assert_eq!(columns[0], column(ROWID, DataType::Bigint));
+        let rowids = rows.iter().map(|row| row[0].clone()).collect::<Vec<_>>();
+        assert!(rowids.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(
+            rows.into_iter()
+                .map(|row| row[1].clone())
+                .collect::<Vec<_>>(),
+            vec![
+                Value::String("start".to_string()),
+                Value::String("stop".to_string()),
+                Value::String("crash".to_string())
+            ]
+        );
+
+        let (_, rows) = db
+            .execute(&format!(
+                "SELECT message FROM log WHERE rowid = {};",
+                rowids[1]
+            ))
+            .await?
+            .into_query()
+            .unwrap();
+        assert_eq!(rows, vec![vec![Value::String("stop".to_string())]]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn execute_script() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        let results = db
+            .execute_script(
+                "CREATE TABLE user (id INTEGER PRIMARY, name VARCHAR);
+                 INSERT INTO user VALUES (1, 'alice'), (2, 'bob');",
+            )
+            .await?;
+        assert_eq!(
+            results,
+            vec![
+                ResultSet::CreateTable {
+                    name: "user".to_string()
+                },
+                ResultSet::Insert { count: 2 }
+            ]
+        );
+        let rows = || async {
+            let (_, rows) = db
+                .execute("SELECT * FROM user;")
+                .await?
+                .into_query()
+                .unwrap();
+            Ok::<_, Error>(rows)
+        };
+        let before = rows().await?;
+
+        // the duplicate key fails the block, so the insert and update before it are undone
+        assert!(db
+            .execute_script(
+                "BEGIN TRANSACTION;
+                 INSERT INTO user VALUES (3, 'carol');
+                 UPDATE user SET name = 'eve' WHERE id = 1;
+                 INSERT INTO user VALUES (2, 'dave');
+                 COMMIT;",
+            )
+            .await
+            .is_err());
+        assert_eq!(rows().await?, before);
+
+        let results = db
+            .execute_script(
+                "INSERT INTO user VALUES (3, 'carol');
+                 BEGIN TRANSACTION;
+                 DELETE FROM user WHERE id = 3;
+                 ROLLBACK;
+                 BEGIN TRANSACTION;
+                 DELETE FROM user WHERE id = 1;
+                 COMMIT;",
+            )
+            .await?;
+        assert_eq!(results.len(), 7);
+        assert_eq!(results[3], ResultSet::Rollback);
+        let ids = rows()
+            .await?
+            .into_iter()
+            .map(|row| row[0].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![Value::Integer(2), Value::Integer(3)]);
+
+        assert!(db.execute_script("COMMIT;").await.is_err());
+        assert!(db
+            .execute_script("BEGIN TRANSACTION; DELETE FROM user WHERE id = 2;")
+            .await
+            .is_err());
+        assert_eq!(rows().await?.len(), 2);
+
+        // a statement outside of a block is rolled back as a whole too
+        let before = rows().await?;
+        assert!(db
+            .execute("INSERT INTO user VALUES (4, 'dave'), (5, 'erin'), (2, 'frank');")
+            .await
+            .is_err());
+        assert_eq!(rows().await?, before);
+        assert!(db
+            .execute_script(
+                "INSERT INTO user VALUES (4, 'dave');
+                 INSERT INTO user VALUES (5, 'erin'), (3, 'frank');",
+            )
+            .await
+            .is_err());
+        let ids = rows()
+            .await?
+            .into_iter()
+            .map(|row| row[0].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            vec![Value::Integer(2), Value::Integer(3), Value::Integer(4)]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn positional_order_group() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        db.execute("CREATE TABLE t (id INTEGER PRIMARY, a INTEGER, b VARCHAR);")
+            .await?;
+        db.execute("INSERT INTO t VALUES (1, 3, 'y'), (2, 1, 'z'), (3, 1, 'x');")
+            .await?;
+        let txn = db.engine.begin().await?;
+        let node = Planner::new(&txn)
+            .build_statement(parser::parse("SELECT a, b FROM t ORDER BY 2 DESC;")?)
+            .await?;
+        let Node::Projection { source, .. } = node else {
+            panic!("expected a projection, got {:?}", node);
+        };
+        let Node::Order { orders, .. } = *source else {
+            panic!("expected an order, got {:?}", source);
+        };
+        assert_eq!(orders, vec![(Expression::Column(2), Order::Descending)]);
+        for sql in [
+            "SELECT a, b FROM t ORDER BY 3;",
+            "SELECT a, b FROM t ORDER BY 0;",
+            "SELECT a, COUNT(id) FROM t GROUP BY 3;",
+        ] {
+            assert!(
+                Planner::new(&txn)
+                    .build_statement(parser::parse(sql)?)
+                    .await
+                    .is_err(),
+                "{}",
+                sql
+            );
+        }
+        txn.commit().await?;
+
+        let query = |sql: &'static str| {
+            let db = &db;
+            async move {
+                let (_, rows) = db.execute(sql).await?.into_query().unwrap();
+                Ok::<_, Error>(rows)
+            }
+        };
+        let string = |s: &str| Value::String(s.to_string());
+        assert_eq!(
+            query("SELECT * FROM t ORDER BY 3;").await?,
+            vec![
+                vec![Value::Integer(3), Value::Integer(1), string("x")],
+                vec![Value::Integer(1), Value::Integer(3), string("y")],
+                vec![Value::Integer(2), Value::Integer(1), string("z")],
+            ]
+        );
+        let rows = query("SELECT a, COUNT(id) FROM t GROUP BY 1;").await?;
+        assert_eq!(rows.len(), 2);
+        assert!(rows.contains(&vec![Value::Integer(1), Value::Integer(2)]));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn composite_index() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        db.execute(
+            "CREATE TABLE pet (id INTEGER PRIMARY, owner INTEGER, kind STRING, age INTEGER);",
+        )
+        .await?;
+        for i in 0..30 {
+            let kind = if i % 2 == 0 { "cat" } else { "dog" };
+            db.execute(&format!(
+                "INSERT INTO pet VALUES ({}, {}, '{}', {});",
+                i,
+                i % 3,
+                kind,
+                i
+            ))
+            .await?;
+        }
+        assert_eq!(
+            db.execute("CREATE INDEX owner_kind ON pet (owner, kind);")
+                .await?,
+            ResultSet::CreateIndex {
+                name: "owner_kind".to_string()
+            }
+        );
+        assert!(db
+            .execute("CREATE INDEX owner_kind ON pet (age);")
+            .await
+            .is_err());
+        assert!(db
+            .execute("CREATE INDEX name ON pet (name);")
+            .await
+            .is_err());
+
+        let plan = |sql: &'static str| {
+            let db = &db;
+            async move {
+                let txn = db.engine.begin().await?;
+                let node = Planner::new(&txn)
+                    .build_statement(parser::parse(sql)?)
+                    .await?;
+                txn.commit().await?;
+                Ok::<_, Error>(node)
+            }
+        };
+        let prefix = |key: Vec<Value>| vec![(1, Lookup::Prefix("owner_kind".to_string(), key))];
+        let cat = || Value::String("cat".to_string());
+        let Node::IndexScan {
+            lookups, filter, ..
+        } = plan("SELECT * FROM pet WHERE owner = 1;").await?
+        else {
+            panic!("expected an index scan");
+        };
+        assert_eq!(lookups, prefix(vec![Value::Integer(1)]));
+        assert!(filter.is_none());
+        let Node::IndexScan {
+            lookups, filter, ..
+        } = plan("SELECT * FROM pet WHERE kind = 'cat' AND age > 10 AND owner = 1;").await?
+        else {
+            panic!("expected an index scan");
+        };
+        assert_eq!(lookups, prefix(vec![Value::Integer(1), cat()]));
+        assert!(filter.is_some());
+        // the leading column isn't constrained
+        assert!(matches!(
+            plan("SELECT * FROM pet WHERE kind = 'cat';").await?,
+            Node::Scan { .. }
+        ));
+
+        let ids = |sql: &'static str| {
+            let db = &db;
+            async move {
+                let (_, rows) = db.execute(sql).await?.into_query().unwrap();
+                Ok::<_, Error>(
+                    rows.into_iter()
+                        .map(|row| row[0].clone())
+                        .collect::<Vec<_>>(),
+                )
+            }
+        };
+        let id = |ids: &[i64]| ids.iter().map(|id| Value::Integer(*id)).collect::<Vec<_>>();
+        let owner = "SELECT id FROM pet WHERE owner = 1;";
+        let owner_kind = "SELECT id FROM pet WHERE owner = 1 AND kind = 'cat';";
+        assert_eq!(
+            ids(owner).await?,
+            id(&[1, 4, 7, 10, 13, 16, 19, 22, 25, 28])
+        );
+        assert_eq!(ids(owner_kind).await?, id(&[4, 10, 16, 22, 28]));
+
+        // the index follows the writes after it's created
+        db.execute("INSERT INTO pet VALUES (30, 1, 'cat', 30);")
+            .await?;
+        db.execute("UPDATE pet SET kind = 'dog' WHERE id = 4;")
+            .await?;
+        db.execute("DELETE FROM pet WHERE id = 10;").await?;
+        assert_eq!(ids(owner_kind).await?, id(&[16, 22, 28, 30]));
+
+        // the index is rebuilt when the database is opened again
+        db.close().await?;
+        let db = Database::open(f.path()).await?;
+        let (_, rows) = db.execute(owner_kind).await?.into_query().unwrap();
+        assert_eq!(
+            rows.into_iter()
+                .map(|row| row[0].clone())
+                .collect::<Vec<_>>(),
+            id(&[16, 22, 28, 30])
+        );
+        let txn = db.engine.begin().await?;
+        let node = Planner::new(&txn)
+            .build_statement(parser::parse(owner_kind)?)
+            .await?;
+        txn.commit().await?;
+        let Node::Projection { source, .. } = node else {
+            panic!("expected a projection, got {:?}", node);
+        };
+        assert!(matches!(*source, Node::IndexScan { .. }));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn timestamp() -> SqlResult<()> {
+        let f = tempfile::NamedTempFile::new().map_err(storage::Error::from)?;
+        let db = Database::create(f.path()).await?;
+        db.execute("CREATE TABLE event (id INTEGER PRIMARY, at TIMESTAMP);")
+            .await?;
+        db.execute(
+            "INSERT INTO event VALUES (1, TIMESTAMP '2024-01-01 00:00:00'), \
+             (2, '2023-06-15 12:30:00.25');",
+        )
+        .await?;
+        db.close().await?;
+
+        let db = Database::open(f.path()).await?;
+        let (_, rows) = db
+            .execute("SELECT id, at FROM event WHERE at < TIMESTAMP '2024-01-01' ORDER BY at;")
+            .await?
+            .into_query()
+            .unwrap();
+        assert_eq!(
+            rows.iter()
+                .map(|row| (row[0].clone(), row[1].to_string()))
+                .collect::<Vec<_>>(),
+            vec![(Value::Integer(2), "2023-06-15T12:30:00.250000".to_string())]
+        );
+        let (_, rows) = db
+            .execute("SELECT id FROM event WHERE at = TIMESTAMP '2024-01-01';")
+            .await?
+            .into_query()
+            .unwrap();
+        assert_eq!(rows, vec![vec![Value::Integer(1)]]);
+        assert!(db
+            .execute("INSERT INTO event VALUES (4, '2024-02-30');")
+            .await
+            .is_err());
+        Ok(())
+    }
+}
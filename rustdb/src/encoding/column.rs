@@ -14,6 +14,7 @@ impl Decoder for Column {
             name: String::decode(buf)?,
             datatype: DataType::decode(buf)?,
             primary_key: bool::decode(buf)?,
+            auto_increment: bool::decode(buf)?,
             nullable: Option::<bool>::decode(buf)?,
             default: Option::<Value>::decode(buf)?,
             unique: bool::decode(buf)?,
@@ -30,6 +31,7 @@ impl Encoder for Column {
         self.name.encode(buf)?;
         self.datatype.encode(buf)?;
         self.primary_key.encode(buf)?;
+        self.auto_increment.encode(buf)?;
         self.nullable.encode(buf)?;
         self.default.encode(buf)?;
         self.unique.encode(buf)?;
@@ -44,6 +46,7 @@ impl EncodedSize for Column {
         self.name.encoded_size()
             + self.datatype.encoded_size()
             + self.primary_key.encoded_size()
+            + self.auto_increment.encoded_size()
             + self.nullable.encoded_size()
             + self.default.encoded_size()
             + self.unique.encoded_size()
@@ -1,6 +1,7 @@
 use crate::encoding::encoded_size::EncodedSize;
 use crate::encoding::error::Error;
 use crate::encoding::{Decoder, Encoder};
+use crate::sql::types::decimal::Decimal;
 use crate::sql::types::{DataType, Value};
 use bytes::{Buf, BufMut};
 use ordered_float::OrderedFloat;
@@ -15,6 +16,8 @@ mod basetype {
     pub const FLOAT: u8 = 5;
     pub const DOUBLE: u8 = 6;
     pub const STRING: u8 = 7;
+    pub const DECIMAL: u8 = 8;
+    pub const TIMESTAMP: u8 = 9;
 }
 
 mod basevalue {
@@ -29,6 +32,8 @@ mod basevalue {
     pub const FLOAT: u8 = 5;
     pub const DOUBLE: u8 = 6;
     pub const STRING: u8 = 7;
+    pub const DECIMAL: u8 = 8;
+    pub const TIMESTAMP: u8 = 9;
 
     pub const NONE_VALUE: u8 = u8::MAX;
     pub const SOME_VALUE: u8 = 1;
@@ -47,6 +52,8 @@ impl DataType {
             DataType::Float => basetype::FLOAT,
             DataType::Double => basetype::DOUBLE,
             DataType::String => basetype::STRING,
+            DataType::Decimal => basetype::DECIMAL,
+            DataType::Timestamp => basetype::TIMESTAMP,
         }
     }
 
@@ -60,6 +67,8 @@ impl DataType {
             basetype::FLOAT => DataType::Float,
             basetype::DOUBLE => DataType::Double,
             basetype::STRING => DataType::String,
+            basetype::DECIMAL => DataType::Decimal,
+            basetype::TIMESTAMP => DataType::Timestamp,
             other => return Err(Error::Decode(format!("Can't decode {} as datatype", other))),
         })
     }
@@ -105,6 +114,18 @@ impl Decoder for Value {
             basevalue::FLOAT => Value::Float(OrderedFloat::<f32>::decode(buf)?),
             basevalue::DOUBLE => Value::Double(OrderedFloat::<f64>::decode(buf)?),
             basevalue::STRING => Value::String(String::decode(buf)?),
+            basevalue::DECIMAL => {
+                let mantissa = i128::decode(buf)?;
+                let scale = u32::decode(buf)?;
+                if scale > Decimal::MAX_SCALE {
+                    return Err(Error::Decode(format!(
+                        "Can't decode {} as decimal scale",
+                        scale
+                    )));
+                }
+                Value::Decimal(Decimal::new(mantissa, scale))
+            }
+            basevalue::TIMESTAMP => Value::Timestamp(i64::decode(buf)?),
             other => return Err(Error::Decode(format!("Can't decode {} as value", other))),
         })
     }
@@ -149,6 +170,15 @@ impl Encoder for Value {
                 basevalue::STRING.encode(buf)?;
                 str.encode(buf)
             }
+            Value::Decimal(decimal) => {
+                basevalue::DECIMAL.encode(buf)?;
+                decimal.mantissa().encode(buf)?;
+                decimal.scale().encode(buf)
+            }
+            Value::Timestamp(micros) => {
+                basevalue::TIMESTAMP.encode(buf)?;
+                micros.encode(buf)
+            }
         }
     }
 }
@@ -166,6 +196,10 @@ impl EncodedSize for Value {
                 Value::Float(float) => float.encoded_size(),
                 Value::Double(double) => double.encoded_size(),
                 Value::String(str) => str.encoded_size(),
+                Value::Decimal(decimal) => {
+                    decimal.mantissa().encoded_size() + decimal.scale().encoded_size()
+                }
+                Value::Timestamp(micros) => micros.encoded_size(),
             }
     }
 }
@@ -210,11 +244,11 @@ impl EncodedSize for Option<Value> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::storage::PAGE_SIZE;
+    use crate::storage::PAGE_PAYLOAD_SIZE;
 
     #[test]
     fn datatype_encode_decode() {
-        let mut buffer = [0; PAGE_SIZE];
+        let mut buffer = [0; PAGE_PAYLOAD_SIZE];
         let ty = DataType::Tinyint;
         ty.encode(&mut buffer.as_mut()).unwrap();
         let decoded = DataType::decode(&mut buffer[..ty.encoded_size()].as_ref()).unwrap();
@@ -224,21 +258,21 @@ mod tests {
     #[test]
     fn value_encode_decode() {
         {
-            let mut buffer = [0; PAGE_SIZE];
+            let mut buffer = [0; PAGE_PAYLOAD_SIZE];
             let ty = Value::Bigint(128);
             ty.encode(&mut buffer.as_mut()).unwrap();
             let decoded = Value::decode(&mut buffer[..ty.encoded_size()].as_ref()).unwrap();
             assert_eq!(decoded, ty)
         }
         {
-            let mut buffer = [0; PAGE_SIZE];
+            let mut buffer = [0; PAGE_PAYLOAD_SIZE];
             let ty = Value::Double(2.0.into());
             ty.encode(&mut buffer.as_mut()).unwrap();
             let decoded = Value::decode(&mut buffer[..ty.encoded_size()].as_ref()).unwrap();
             assert_eq!(decoded, ty)
         }
         {
-            let mut buffer = [0; PAGE_SIZE];
+            let mut buffer = [0; PAGE_PAYLOAD_SIZE];
             let ty = Some(Value::Double(2.0.into()));
             ty.encode(&mut buffer.as_mut()).unwrap();
             let decoded =
@@ -246,7 +280,26 @@ mod tests {
             assert_eq!(decoded, ty)
         }
         {
-            let mut buffer = [0; PAGE_SIZE];
+            let mut buffer = [0; PAGE_PAYLOAD_SIZE];
+            let ty = Value::Decimal(Decimal::new(-150, 2));
+            ty.encode(&mut buffer.as_mut()).unwrap();
+            let decoded = Value::decode(&mut buffer[..ty.encoded_size()].as_ref()).unwrap();
+            assert_eq!(decoded.to_string(), "-1.50");
+        }
+        for ty in [
+            Value::Timestamp(-1),
+            Value::Timestamp(1_704_067_200_000_000),
+            Value::Timestamp(i64::MAX),
+        ] {
+            let mut buffer = [0; PAGE_PAYLOAD_SIZE];
+            let ty = Some(ty);
+            ty.encode(&mut buffer.as_mut()).unwrap();
+            let decoded =
+                Option::<Value>::decode(&mut buffer[..ty.encoded_size()].as_ref()).unwrap();
+            assert_eq!(decoded, ty)
+        }
+        {
+            let mut buffer = [0; PAGE_PAYLOAD_SIZE];
             let ty = Value::String("Hello world".into());
             ty.encode(&mut buffer.as_mut()).unwrap();
             let decoded = Value::decode(&mut buffer[..ty.encoded_size()].as_ref()).unwrap();
@@ -1,11 +1,11 @@
 use crate::encoding::EncoderVecLen;
-use crate::storage::PAGE_SIZE;
+use crate::storage::PAGE_PAYLOAD_SIZE;
 
 pub trait EncodedSize {
     fn encoded_size(&self) -> usize;
 
     fn total_size(&self) -> usize {
-        PAGE_SIZE
+        PAGE_PAYLOAD_SIZE
     }
 }
 
@@ -98,7 +98,7 @@ mod tests {
 
     #[test]
     fn encode_size() {
-        let mut buffer = [0; PAGE_SIZE];
+        let mut buffer = [0; PAGE_PAYLOAD_SIZE];
         let str = String::from("Hello world");
         str.encode(&mut buffer.as_mut()).unwrap();
         assert_eq!(
@@ -1,3 +1,4 @@
+use crate::encoding::encoded_size::EncodedSize;
 use crate::encoding::error::Error;
 use crate::encoding::{Decoder, Encoder};
 use crate::storage::page::index::{Header, Internal, Leaf, Node};
@@ -145,3 +146,53 @@ where
         Ok(())
     }
 }
+
+impl<K> EncodedSize for Node<K>
+where
+    K: EncodedSize,
+{
+    fn encoded_size(&self) -> usize {
+        LEAF_TYPE.encoded_size()
+            + match self {
+                Node::Internal(internal) => internal.encoded_size(),
+                Node::Leaf(leaf) => leaf.encoded_size(),
+            }
+    }
+}
+
+impl EncodedSize for Header {
+    fn encoded_size(&self) -> usize {
+        self.size.encoded_size()
+            + self.max_size.encoded_size()
+            + self.parent.encoded_size()
+            + self.page_id.encoded_size()
+            + self.next.encoded_size()
+            + self.prev.encoded_size()
+    }
+}
+
+impl<K> EncodedSize for Internal<K>
+where
+    K: EncodedSize,
+{
+    fn encoded_size(&self) -> usize {
+        self.kv
+            .iter()
+            .fold(self.header.encoded_size(), |size, (k, v)| {
+                size + k.encoded_size() + v.encoded_size()
+            })
+    }
+}
+
+impl<K> EncodedSize for Leaf<K>
+where
+    K: EncodedSize,
+{
+    fn encoded_size(&self) -> usize {
+        self.kv
+            .iter()
+            .fold(self.header.encoded_size(), |size, (k, v)| {
+                size + k.encoded_size() + v.encoded_size()
+            })
+    }
+}
@@ -0,0 +1,62 @@
+use crate::encoding::encoded_size::EncodedSize;
+use crate::encoding::error::Error;
+use crate::encoding::{Decoder, Encoder};
+use crate::storage::page::meta::Meta;
+use crate::storage::PageId;
+use bytes::{Buf, BufMut};
+
+impl Decoder for Meta {
+    fn decode<B>(buf: &mut B) -> Result<Self, Error>
+    where
+        B: Buf,
+    {
+        Ok(Self {
+            page_id: PageId::decode(buf)?,
+            page_size: usize::decode(buf)?,
+            tables: Vec::<PageId>::decode(buf)?,
+        })
+    }
+}
+
+impl Encoder for Meta {
+    fn encode<B>(&self, buf: &mut B) -> Result<(), Error>
+    where
+        B: BufMut,
+    {
+        self.page_id.encode(buf)?;
+        self.page_size.encode(buf)?;
+        self.tables.encode(buf)?;
+        Ok(())
+    }
+}
+
+impl EncodedSize for Meta {
+    fn encoded_size(&self) -> usize {
+        self.page_id.encoded_size() + self.page_size.encoded_size() + self.tables.encoded_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::PAGE_PAYLOAD_SIZE;
+
+    #[test]
+    fn encode_decode_meta() {
+        let mut buffer = [0; PAGE_PAYLOAD_SIZE];
+        let meta = Meta {
+            page_id: 0,
+            page_size: 4096,
+            tables: vec![1, 3, 5],
+        };
+        meta.encode(&mut buffer.as_mut()).unwrap();
+        assert_eq!(
+            Meta::decode(&mut buffer[..meta.encoded_size()].as_ref()).unwrap(),
+            meta
+        );
+        assert_eq!(
+            meta.page_id.encoded_size() + meta.page_size.encoded_size(),
+            Meta::PREFIX_SIZE
+        )
+    }
+}
@@ -8,11 +8,28 @@ mod column;
 mod datatype;
 pub mod encoded_size;
 pub mod error;
+mod meta;
 mod record_id;
 mod table;
 
 pub type EncoderVecLen = u32;
 
+/// Encode the length of a string or a vector. [`EncoderVecLen::MAX`] is the NULL of an optional
+/// string, so a length from it on fails rather than being truncated
+pub(crate) fn encode_len<B>(len: usize, buf: &mut B) -> Result<(), Error>
+where
+    B: BufMut,
+{
+    match EncoderVecLen::try_from(len) {
+        Ok(len) if len < EncoderVecLen::MAX => len.encode(buf),
+        _ => Err(Error::Encode(format!(
+            "length {} exceeds the max length {}",
+            len,
+            EncoderVecLen::MAX - 1
+        ))),
+    }
+}
+
 pub trait Encoder: Sized {
     fn encode<B>(&self, buf: &mut B) -> Result<(), Error>
     where
@@ -181,7 +198,7 @@ impl Encoder for String {
     where
         B: BufMut,
     {
-        (self.as_bytes().len() as u32).encode(buf)?;
+        encode_len(self.len(), buf)?;
         buf.put_slice(self.as_bytes());
         Ok(())
     }
@@ -345,7 +362,7 @@ where
     where
         B: BufMut,
     {
-        (self.len() as EncoderVecLen).encode(buf)?;
+        encode_len(self.len(), buf)?;
         for data in self {
             data.encode(buf)?;
         }
@@ -355,39 +372,55 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::storage::PAGE_SIZE;
+    use crate::storage::PAGE_PAYLOAD_SIZE;
 
     #[test]
     fn encode_decode() {
         {
-            let mut buffer = [0; PAGE_SIZE];
+            let mut buffer = [0; PAGE_PAYLOAD_SIZE];
             let str = String::from("Hello world");
             str.encode(&mut buffer.as_mut()).unwrap();
             assert_eq!(String::decode(&mut buffer.as_ref()).unwrap(), str);
         }
         {
-            let mut buffer = [0; PAGE_SIZE];
+            let mut buffer = [0; PAGE_PAYLOAD_SIZE];
             let val: u32 = 256;
             val.encode(&mut buffer.as_mut()).unwrap();
             assert_eq!(u32::decode(&mut buffer.as_ref()).unwrap(), val);
         }
         {
-            let mut buffer = [0; PAGE_SIZE];
+            let mut buffer = [0; PAGE_PAYLOAD_SIZE];
             let val: Option<u32> = Some(256);
             val.encode(&mut buffer.as_mut()).unwrap();
             assert_eq!(Option::<u32>::decode(&mut buffer.as_ref()).unwrap(), val);
         }
         {
-            let mut buffer = [0; PAGE_SIZE];
+            let mut buffer = [0; PAGE_PAYLOAD_SIZE];
             let val: Option<u32> = None;
             val.encode(&mut buffer.as_mut()).unwrap();
             assert_eq!(Option::<u32>::decode(&mut buffer.as_ref()).unwrap(), val);
         }
         {
-            let mut buffer = [0; PAGE_SIZE];
+            let mut buffer = [0; PAGE_PAYLOAD_SIZE];
             let val: Option<bool> = None;
             u8::MAX.encode(&mut buffer.as_mut()).unwrap();
             assert_eq!(Option::<bool>::decode(&mut buffer.as_ref()).unwrap(), val);
         }
     }
+
+    #[test]
+    fn encode_len_overflow() {
+        // a string of 4 GiB is too large to allocate in a test, so the guard is called directly
+        let mut buffer = Vec::new();
+        encode_len(EncoderVecLen::MAX as usize - 1, &mut buffer).unwrap();
+        assert_eq!(buffer, (EncoderVecLen::MAX - 1).to_be_bytes());
+        for len in [EncoderVecLen::MAX as usize, usize::MAX] {
+            let mut buffer = Vec::new();
+            assert!(matches!(
+                encode_len(len, &mut buffer),
+                Err(Error::Encode(_))
+            ));
+            assert!(buffer.is_empty());
+        }
+    }
 }
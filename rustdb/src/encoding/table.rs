@@ -3,7 +3,7 @@ use crate::encoding::error::Error;
 use crate::encoding::{Decoder, Encoder};
 use crate::sql::types::Value;
 use crate::storage::page::column::Column;
-use crate::storage::page::table::{Table, TableNode, Tuple};
+use crate::storage::page::table::{Table, TableIndex, TableNode, Tuple};
 use crate::storage::{PageId, TimeStamp};
 use bytes::{Buf, BufMut};
 
@@ -78,7 +78,12 @@ impl Decoder for Table {
             page_id: PageId::decode(buf)?,
             start: PageId::decode(buf)?,
             end: PageId::decode(buf)?,
+            sequence: i64::decode(buf)?,
+            primary: PageId::decode(buf)?,
+            secondaries: Vec::<PageId>::decode(buf)?,
+            composites: Vec::<PageId>::decode(buf)?,
             columns: Vec::<Column>::decode(buf)?,
+            indexes: Vec::<TableIndex>::decode(buf)?,
         })
     }
 }
@@ -92,7 +97,12 @@ impl Encoder for Table {
         self.page_id.encode(buf)?;
         self.start.encode(buf)?;
         self.end.encode(buf)?;
+        self.sequence.encode(buf)?;
+        self.primary.encode(buf)?;
+        self.secondaries.encode(buf)?;
+        self.composites.encode(buf)?;
         self.columns.encode(buf)?;
+        self.indexes.encode(buf)?;
         Ok(())
     }
 }
@@ -103,7 +113,41 @@ impl EncodedSize for Table {
             + self.page_id.encoded_size()
             + self.start.encoded_size()
             + self.end.encoded_size()
+            + self.sequence.encoded_size()
+            + self.primary.encoded_size()
+            + self.secondaries.encoded_size()
+            + self.composites.encoded_size()
             + self.columns.encoded_size()
+            + self.indexes.encoded_size()
+    }
+}
+
+impl Decoder for TableIndex {
+    fn decode<B>(buf: &mut B) -> Result<Self, Error>
+    where
+        B: Buf,
+    {
+        Ok(Self {
+            name: String::decode(buf)?,
+            columns: Vec::<usize>::decode(buf)?,
+        })
+    }
+}
+
+impl Encoder for TableIndex {
+    fn encode<B>(&self, buf: &mut B) -> Result<(), Error>
+    where
+        B: BufMut,
+    {
+        self.name.encode(buf)?;
+        self.columns.encode(buf)?;
+        Ok(())
+    }
+}
+
+impl EncodedSize for TableIndex {
+    fn encoded_size(&self) -> usize {
+        self.name.encoded_size() + self.columns.encoded_size()
     }
 }
 
@@ -113,12 +157,12 @@ mod tests {
     use ordered_float::OrderedFloat;
 
     use crate::sql::types::DataType;
-    use crate::storage::PAGE_SIZE;
+    use crate::storage::PAGE_PAYLOAD_SIZE;
 
     #[test]
     fn encode_decode_table() {
-        let mut buffer = [0; PAGE_SIZE];
-        let table = Table::new(
+        let mut buffer = [0; PAGE_PAYLOAD_SIZE];
+        let mut table = Table::new(
             "table_1",
             1,
             1,
@@ -130,18 +174,22 @@ mod tests {
                 .with_index(true)
                 .with_references("table_2")],
         );
+        table.push_index(TableIndex::new("idx", vec![0, 2]));
+        table.set_primary(3);
+        assert!(table.set_index_roots(vec![4], vec![5]));
+        assert!(!table.set_index_roots(vec![4], vec![5]));
         table.encode(&mut buffer.as_mut()).unwrap();
         let decoded = Table::decode(&mut buffer[..table.encoded_size()].as_ref()).unwrap();
         assert_eq!(
             buffer[table.encoded_size()..],
-            [0; PAGE_SIZE][table.encoded_size()..]
+            [0; PAGE_PAYLOAD_SIZE][table.encoded_size()..]
         );
         assert_eq!(decoded, table)
     }
 
     #[test]
     fn encode_decode_table_node() {
-        let mut buffer = [0; PAGE_SIZE];
+        let mut buffer = [0; PAGE_PAYLOAD_SIZE];
         let table_node = TableNode {
             page_id: 256,
             next: None,
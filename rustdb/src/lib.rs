@@ -2,7 +2,8 @@ extern crate core;
 
 pub mod buffer;
 pub mod catalog;
+pub mod database;
 pub mod encoding;
 mod error;
-mod sql;
+pub mod sql;
 pub mod storage;
@@ -1,13 +1,28 @@
 use crate::sql::types::{DataType, Value};
-use crate::sql::SqlResult;
+use crate::sql::{Error, SqlResult};
+use crate::storage::page;
 use std::future::Future;
 
+/// The name of the hidden primary key which is added to a table declared without one
+pub const ROWID: &str = "rowid";
+
 pub trait Catalog {
     fn create_table(&self, table: Table) -> impl Future<Output = SqlResult<()>>;
 
     fn drop_table(&self, name: &str) -> impl Future<Output = SqlResult<Option<Table>>>;
 
     fn read_table(&self, name: &str) -> impl Future<Output = SqlResult<Option<Table>>>;
+
+    /// Create an index on several columns of table, the rows of the table are indexed
+    fn create_index(&self, table: &str, index: Index) -> impl Future<Output = SqlResult<()>>;
+
+    /// Rename the column of table, the position of the column is unchanged
+    fn rename_column(
+        &self,
+        table: &str,
+        from: &str,
+        to: &str,
+    ) -> impl Future<Output = SqlResult<()>>;
 }
 
 /// Logical table.
@@ -18,6 +33,8 @@ pub struct Table {
     name: String,
     /// Columns
     columns: Vec<Column>,
+    /// The indexes of several columns, created by `CREATE INDEX`
+    indexes: Vec<Index>,
 }
 
 impl Table {
@@ -25,6 +42,73 @@ impl Table {
         Self {
             name: name.into(),
             columns,
+            indexes: Vec::new(),
+        }
+    }
+
+    pub fn with_indexes(mut self, indexes: Vec<Index>) -> Self {
+        self.indexes = indexes;
+        self
+    }
+
+    pub fn indexes(&self) -> &[Index] {
+        &self.indexes
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    /// Returns the position of the column
+    pub fn column_index(&self, name: &str) -> SqlResult<usize> {
+        self.columns
+            .iter()
+            .position(|column| column.name == name)
+            .ok_or(Error::NotFound("column", format!("{}.{}", self.name, name)))
+    }
+
+    /// Returns the position of the primary key
+    pub fn primary_key_index(&self) -> SqlResult<usize> {
+        self.columns
+            .iter()
+            .position(|column| column.constraints.is_primary_key())
+            .ok_or(Error::NotFound("primary key", self.name.clone()))
+    }
+
+    /// Returns the positions of the columns which `*` expands to, the hidden rowid is left out
+    pub fn wildcard(&self) -> Vec<usize> {
+        self.columns
+            .iter()
+            .enumerate()
+            .filter(|(_, column)| !column.is_rowid())
+            .map(|(position, _)| position)
+            .collect()
+    }
+}
+
+/// An index of several columns, whose key is the values of the columns in order
+#[derive(Clone, Debug, PartialEq)]
+pub struct Index {
+    pub name: String,
+    /// The positions of the indexed columns
+    pub columns: Vec<usize>,
+}
+
+impl From<Index> for page::table::TableIndex {
+    fn from(index: Index) -> Self {
+        page::table::TableIndex::new(index.name, index.columns)
+    }
+}
+
+impl From<page::table::TableIndex> for Index {
+    fn from(index: page::table::TableIndex) -> Self {
+        Self {
+            name: index.name,
+            columns: index.columns,
         }
     }
 }
@@ -37,8 +121,17 @@ pub struct Column {
     pub name: String,
     /// Column datatype
     pub datatype: DataType,
+    /// The constraints declared on the column
+    pub constraints: Constraints,
+}
+
+/// The constraints of a column, which are set by the builder methods of [`Column`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Constraints {
     /// Whether the column is a primary key
     pub primary_key: bool,
+    /// Whether the column is filled from the table's sequence when its value is omitted
+    pub auto_increment: bool,
     /// Whether the column allows null values
     pub nullable: bool,
     /// The default value of the column
@@ -51,47 +144,194 @@ pub struct Column {
     pub index: bool,
 }
 
+impl Constraints {
+    pub fn is_primary_key(&self) -> bool {
+        self.primary_key
+    }
+
+    pub fn is_auto_increment(&self) -> bool {
+        self.auto_increment
+    }
+
+    /// A primary key never takes null values
+    pub fn is_nullable(&self) -> bool {
+        self.nullable && !self.primary_key
+    }
+
+    /// Whether the values of the column must be unique, which a primary key implies
+    pub fn is_unique(&self) -> bool {
+        self.unique || self.primary_key
+    }
+
+    /// Whether the column can be searched through an index, the primary key is always indexed
+    pub fn is_indexed(&self) -> bool {
+        self.index || self.primary_key
+    }
+
+    pub fn default_value(&self) -> Option<&Value> {
+        self.default.as_ref()
+    }
+
+    pub fn references(&self) -> Option<&str> {
+        self.references.as_deref()
+    }
+}
+
 impl Column {
     pub fn new(name: impl Into<String>, datatype: DataType) -> Self {
         Self {
             name: name.into(),
             datatype,
-            primary_key: false,
-            nullable: false,
-            default: None,
-            unique: false,
-            references: None,
-            index: false,
+            constraints: Constraints::default(),
         }
     }
 
     pub fn with_primary(mut self, primary: bool) -> Self {
-        self.primary_key = primary;
+        self.constraints.primary_key = primary;
+        self
+    }
+
+    pub fn with_auto_increment(mut self, auto_increment: bool) -> Self {
+        self.constraints.auto_increment = auto_increment;
         self
     }
 
     pub fn with_nullable(mut self, nullable: bool) -> Self {
-        self.nullable = nullable;
+        self.constraints.nullable = nullable;
         self
     }
 
     pub fn with_default(mut self, default: Value) -> Self {
-        self.default = Some(default);
+        self.constraints.default = Some(default);
         self
     }
 
     pub fn with_unique(mut self, unique: bool) -> Self {
-        self.unique = unique;
+        self.constraints.unique = unique;
         self
     }
 
     pub fn with_references(mut self, references: String) -> Self {
-        self.references = Some(references);
+        self.constraints.references = Some(references);
         self
     }
 
     pub fn with_index(mut self, index: bool) -> Self {
-        self.index = index;
+        self.constraints.index = index;
         self
     }
+
+    pub fn constraints(&self) -> &Constraints {
+        &self.constraints
+    }
+
+    /// The hidden primary key of a table declared without one, see [`ROWID`]
+    pub fn is_rowid(&self) -> bool {
+        self.name == ROWID
+            && self.constraints.is_primary_key()
+            && self.constraints.is_auto_increment()
+    }
+
+    /// Cast the value into the column's datatype and check its nullability.
+    /// NaN and infinite floats are rejected, so a stored float is always a finite number
+    pub fn check_value(&self, value: Value) -> SqlResult<Value> {
+        if value == Value::Null && !self.constraints.nullable {
+            return Err(Error::Value(format!(
+                "NULL value not allowed for column {}",
+                self.name
+            )));
+        }
+        let value = value.cast(&self.datatype)?;
+        let finite = match &value {
+            Value::Float(float) => float.is_finite(),
+            Value::Double(double) => double.is_finite(),
+            _ => true,
+        };
+        if !finite {
+            return Err(Error::Value(format!(
+                "{} value not allowed for column {}",
+                value, self.name
+            )));
+        }
+        Ok(value)
+    }
+}
+
+impl From<Column> for page::column::Column {
+    fn from(column: Column) -> Self {
+        let constraints = column.constraints;
+        let mut physical = page::column::Column::new(column.name, column.datatype)
+            .with_primary(constraints.primary_key)
+            .with_auto_increment(constraints.auto_increment)
+            .with_nullable(constraints.nullable)
+            .with_unique(constraints.unique)
+            .with_index(constraints.index);
+        if let Some(default) = constraints.default {
+            physical = physical.with_default(default);
+        }
+        if let Some(references) = constraints.references {
+            physical = physical.with_references(references);
+        }
+        physical
+    }
+}
+
+impl From<page::column::Column> for Column {
+    fn from(column: page::column::Column) -> Self {
+        Self {
+            name: column.name,
+            datatype: column.datatype,
+            constraints: Constraints {
+                primary_key: column.primary_key,
+                auto_increment: column.auto_increment,
+                nullable: column.nullable.unwrap_or_default(),
+                default: column.default,
+                unique: column.unique,
+                references: column.references,
+                index: column.index,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constraints() {
+        let column = Column::new("id", DataType::Integer)
+            .with_primary(true)
+            .with_nullable(true)
+            .with_auto_increment(true);
+        let constraints = column.constraints();
+        assert!(constraints.is_primary_key());
+        assert!(constraints.is_unique());
+        assert!(constraints.is_indexed());
+        assert!(!constraints.is_nullable());
+        assert!(constraints.is_auto_increment());
+        assert_eq!(constraints.default_value(), None);
+
+        let column = Column::new("owner", DataType::Integer)
+            .with_nullable(true)
+            .with_unique(true)
+            .with_default(Value::Integer(0))
+            .with_references("user".to_string());
+        let constraints = column.constraints();
+        assert!(!constraints.is_primary_key());
+        assert!(constraints.is_unique());
+        assert!(!constraints.is_indexed());
+        assert!(constraints.is_nullable());
+        assert_eq!(constraints.default_value(), Some(&Value::Integer(0)));
+        assert_eq!(constraints.references(), Some("user"));
+        assert!(column.check_value(Value::Null).is_ok());
+
+        let column = Column::new("name", DataType::String).with_index(true);
+        assert!(column.constraints().is_indexed());
+        assert!(!column.constraints().is_unique());
+        assert!(column.check_value(Value::Null).is_err());
+        // the physical column keeps every constraint
+        let physical: page::column::Column = column.clone().into();
+        assert_eq!(Column::from(physical), column);
+    }
 }
@@ -0,0 +1,185 @@
+use crate::sql::types::{timestamp, DataType, Value};
+use crate::sql::{Error, SqlResult};
+use crate::storage;
+use ordered_float::OrderedFloat;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+
+/// A field of CSV record, an empty unquoted field is `None`
+pub type Field = Option<String>;
+
+/// Reads the records of CSV one by one, a quoted field may contain commas, line breaks
+/// and doubled quotes
+pub struct CsvReader<R> {
+    reader: BufReader<R>,
+    line: usize,
+}
+
+impl<R: AsyncRead + Unpin> CsvReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader: BufReader::new(reader),
+            line: 0,
+        }
+    }
+
+    /// Read the next record and the line it starts at, blank lines are skipped
+    pub async fn next_record(&mut self) -> SqlResult<Option<(usize, Vec<Field>)>> {
+        let mut text = String::new();
+        let mut start = self.line + 1;
+        loop {
+            let mut line = String::new();
+            let read = self.reader.read_line(&mut line).await;
+            if read.map_err(storage::Error::from)? == 0 {
+                return match text.is_empty() {
+                    true => Ok(None),
+                    false => Err(Error::Value(format!(
+                        "line {}: the quoted field isn't closed",
+                        start
+                    ))),
+                };
+            }
+            self.line += 1;
+            let line = line.strip_suffix('\n').unwrap_or(&line);
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            if text.is_empty() && line.is_empty() {
+                start = self.line + 1;
+                continue;
+            }
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            text.push_str(line);
+            match parse_record(&text) {
+                Ok(Some(fields)) => return Ok(Some((start, fields))),
+                Ok(None) => continue,
+                Err(err) => return Err(Error::Value(format!("line {}: {}", start, err))),
+            }
+        }
+    }
+}
+
+/// Split the text into fields, returns `None` if a quoted field isn't closed yet
+fn parse_record(text: &str) -> Result<Option<Vec<Field>>, String> {
+    let mut fields = Vec::new();
+    let mut chars = text.chars().peekable();
+    loop {
+        let mut field = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            loop {
+                match chars.next() {
+                    None => return Ok(None),
+                    Some('"') if chars.next_if_eq(&'"').is_some() => field.push('"'),
+                    Some('"') => break,
+                    Some(c) => field.push(c),
+                }
+            }
+            fields.push(Some(field));
+            match chars.next() {
+                None => return Ok(Some(fields)),
+                Some(',') => continue,
+                Some(c) => return Err(format!("unexpected {} after a quoted field", c)),
+            }
+        }
+        loop {
+            match chars.next() {
+                None => {
+                    fields.push((!field.is_empty()).then_some(field));
+                    return Ok(Some(fields));
+                }
+                Some(',') => break,
+                Some('"') => return Err("unexpected quote in an unquoted field".to_string()),
+                Some(c) => field.push(c),
+            }
+        }
+        fields.push((!field.is_empty()).then_some(field));
+    }
+}
+
+/// Parse the text of a field into a value of the datatype, an empty field is NULL
+pub fn parse_field(field: Field, datatype: &DataType) -> SqlResult<Value> {
+    let Some(text) = field else {
+        return Ok(Value::Null);
+    };
+    let invalid = || Error::ValueNotMatch("parse", format!("{} as {}", text, datatype));
+    let value = match datatype {
+        DataType::Boolean => match text.to_ascii_lowercase().as_str() {
+            "true" => Value::Boolean(true),
+            "false" => Value::Boolean(false),
+            _ => return Err(invalid()),
+        },
+        DataType::Tinyint | DataType::Smallint | DataType::Integer | DataType::Bigint => {
+            Value::Bigint(text.trim().parse().map_err(|_| invalid())?)
+        }
+        DataType::Float | DataType::Double => {
+            Value::Double(OrderedFloat(text.trim().parse().map_err(|_| invalid())?))
+        }
+        DataType::Decimal => Value::Decimal(text.trim().parse().map_err(|_| invalid())?),
+        DataType::String => return Ok(Value::String(text)),
+        DataType::Timestamp => {
+            Value::Timestamp(timestamp::parse(text.trim()).map_err(|_| invalid())?)
+        }
+    };
+    value.cast(datatype)
+}
+
+/// Render the value as a CSV field, NULL is an empty field
+pub fn format_field(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => escape(s),
+        value => value.to_string(),
+    }
+}
+
+/// Quote the text if it can't be read back as it is, an empty text is quoted to differ from NULL
+pub fn escape(text: &str) -> String {
+    if text.is_empty() || text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn read_records() -> SqlResult<()> {
+        let text = "a,b,c\n1,,\"x, y\"\r\n\n\"multi\nline\",\"say \"\"hi\"\"\",\"\"\n";
+        let mut reader = CsvReader::new(text.as_bytes());
+        let some = |s: &str| Some(s.to_string());
+        assert_eq!(
+            reader.next_record().await?,
+            Some((1, vec![some("a"), some("b"), some("c")]))
+        );
+        assert_eq!(
+            reader.next_record().await?,
+            Some((2, vec![some("1"), None, some("x, y")]))
+        );
+        assert_eq!(
+            reader.next_record().await?,
+            Some((4, vec![some("multi\nline"), some("say \"hi\""), some("")]))
+        );
+        assert_eq!(reader.next_record().await?, None);
+
+        let mut reader = CsvReader::new("a\nb\"c\n".as_bytes());
+        reader.next_record().await?;
+        assert!(reader.next_record().await.is_err());
+        let mut reader = CsvReader::new("\"open\n".as_bytes());
+        assert!(reader.next_record().await.is_err());
+
+        assert_eq!(
+            parse_field(some("12"), &DataType::Smallint)?,
+            Value::Smallint(12)
+        );
+        assert_eq!(
+            parse_field(some("1.5"), &DataType::Float)?,
+            Value::Float(OrderedFloat(1.5))
+        );
+        assert_eq!(parse_field(None, &DataType::Integer)?, Value::Null);
+        assert!(parse_field(some("1000"), &DataType::Tinyint).is_err());
+        assert!(parse_field(some("yes"), &DataType::Boolean).is_err());
+        Ok(())
+    }
+}
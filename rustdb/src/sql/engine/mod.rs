@@ -2,6 +2,12 @@ use crate::sql::transaction::Transaction;
 use crate::sql::SqlResult;
 use std::future::Future;
 
+mod storage;
+
+pub use storage::{
+    CommitEvent, CommitObserver, StorageEngine, StorageTransaction, TransactionId, Write,
+};
+
 /// A sql engine trait
 pub trait Engine {
     type Transaction: Transaction;
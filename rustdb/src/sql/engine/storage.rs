@@ -0,0 +1,782 @@
+use crate::sql::catalog::{Catalog, Column, Index, Table};
+use crate::sql::csv::{self, CsvReader};
+use crate::sql::engine::Engine;
+use crate::sql::transaction::Transaction;
+use crate::sql::types::{CastMode, Row, Value};
+use crate::sql::{Error, SqlResult};
+use crate::storage::engine;
+use crate::storage::engine::TableStats;
+use crate::storage::page::table::Tuple;
+use crate::storage::{self, RecordId, Storage};
+use futures::{Stream, StreamExt};
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Bound;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::io::AsyncRead;
+
+/// A row written by a transaction
+#[derive(Debug, Clone, PartialEq)]
+pub enum Write {
+    Insert { table: String, row: Row },
+    Update { table: String, row: Row },
+    Delete { table: String, row: Row },
+}
+
+impl Write {
+    pub fn table(&self) -> &str {
+        match self {
+            Write::Insert { table, .. }
+            | Write::Update { table, .. }
+            | Write::Delete { table, .. } => table,
+        }
+    }
+}
+
+/// The writes of a committed transaction, in the order they were made
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommitEvent {
+    pub writes: Vec<Write>,
+}
+
+impl CommitEvent {
+    /// Returns the names of the written tables
+    pub fn tables(&self) -> BTreeSet<&str> {
+        self.writes.iter().map(Write::table).collect()
+    }
+}
+
+/// How to undo a write of a transaction, see [`StorageTransaction::rollback`]
+#[derive(Debug)]
+enum Undo {
+    /// Delete the inserted row by its primary key
+    Delete { table: String, key: Value },
+    /// Insert the deleted row back
+    Insert { table: String, row: Row },
+    /// Write back the row as it was before the update
+    Update { table: String, row: Row },
+}
+
+pub type CommitObserver = Box<dyn Fn(&CommitEvent) + Send + Sync>;
+
+type Observers = Arc<RwLock<Vec<Arc<CommitObserver>>>>;
+
+pub type TransactionId = u64;
+
+/// The number of rows [`StorageEngine::import_csv`] inserts at a time
+pub const IMPORT_BATCH_SIZE: usize = 1024;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+enum Status {
+    #[default]
+    Active,
+    Committed,
+    Aborted,
+}
+
+/// The state of a transaction shared with [`StorageEngine::abort`]
+#[derive(Default)]
+struct TransactionState {
+    status: Mutex<Status>,
+    /// How to undo the writes, in the order they were made
+    undo: Mutex<Vec<Undo>>,
+    /// Held by every write, commit, rollback and abort, so an abort can't interleave with
+    /// a write which already checked the transaction is active
+    latch: tokio::sync::Mutex<()>,
+}
+
+impl TransactionState {
+    /// Mark the active transaction as aborted and undo its writes
+    async fn abort(&self, id: TransactionId, storage: &engine::Engine) -> SqlResult<()> {
+        {
+            let mut status = self.status.lock().unwrap();
+            match *status {
+                Status::Active => *status = Status::Aborted,
+                Status::Committed => return Err(Error::Committed(id)),
+                Status::Aborted => return Err(Error::Aborted(id)),
+            }
+        }
+        self.undo(storage).await
+    }
+
+    /// Undo the writes in reverse order
+    async fn undo(&self, storage: &engine::Engine) -> SqlResult<()> {
+        let undo = std::mem::take(&mut *self.undo.lock().unwrap());
+        if undo.is_empty() {
+            return Ok(());
+        }
+        for undo in undo.into_iter().rev() {
+            match undo {
+                Undo::Delete { table, key } => {
+                    storage.delete(&table, &key).await?;
+                }
+                Undo::Insert { table, row } => {
+                    storage.insert(&table, vec![Tuple::new(row, 0)]).await?;
+                }
+                Undo::Update { table, row } => {
+                    storage.update(&table, Tuple::new(row, 0)).await?;
+                }
+            }
+        }
+        storage.commit().await?;
+        Ok(())
+    }
+}
+
+/// The state of every transaction which isn't dropped yet
+type Transactions = Arc<Mutex<BTreeMap<TransactionId, Arc<TransactionState>>>>;
+
+/// The schemas of the tables read by transactions, so resolving a name doesn't read the table
+/// page from the buffer pool. The schema of a table is invalidated by DDL on it.
+#[derive(Default)]
+struct SchemaCache {
+    tables: RwLock<BTreeMap<String, Table>>,
+    /// Bumped by every invalidation, a schema read before an invalidation isn't cached
+    version: AtomicU64,
+}
+
+impl SchemaCache {
+    fn get(&self, name: &str) -> Option<Table> {
+        self.tables.read().unwrap().get(name).cloned()
+    }
+
+    fn version(&self) -> u64 {
+        self.version.load(Ordering::SeqCst)
+    }
+
+    /// Cache the schema read from storage, unless an invalidation happened since `version`
+    fn insert(&self, version: u64, table: Table) {
+        let mut tables = self.tables.write().unwrap();
+        if self.version() == version {
+            tables.insert(table.name().to_string(), table);
+        }
+    }
+
+    fn invalidate(&self, name: &str) {
+        let mut tables = self.tables.write().unwrap();
+        self.version.fetch_add(1, Ordering::SeqCst);
+        tables.remove(name);
+    }
+}
+
+/// A sql engine on top of the storage [`engine::Engine`]
+#[derive(Clone)]
+pub struct StorageEngine {
+    storage: Arc<engine::Engine>,
+    schemas: Arc<SchemaCache>,
+    memory_budget: Option<usize>,
+    cast_mode: CastMode,
+    observers: Observers,
+    transactions: Transactions,
+    next_transaction_id: Arc<AtomicU64>,
+}
+
+impl StorageEngine {
+    pub fn new(storage: Arc<engine::Engine>) -> Self {
+        Self {
+            storage,
+            schemas: Default::default(),
+            memory_budget: None,
+            cast_mode: Default::default(),
+            observers: Default::default(),
+            transactions: Default::default(),
+            next_transaction_id: Default::default(),
+        }
+    }
+
+    /// Limit the bytes of rows each buffering operator of a query may hold,
+    /// the query fails with [`Error::MemoryBudget`] once an operator exceeds it
+    pub fn with_memory_budget(mut self, memory_budget: usize) -> Self {
+        self.memory_budget = Some(memory_budget);
+        self
+    }
+
+    /// How the queries of every transaction convert between values, strict by default
+    pub fn with_cast_mode(mut self, cast_mode: CastMode) -> Self {
+        self.cast_mode = cast_mode;
+        self
+    }
+
+    pub fn storage(&self) -> &Arc<engine::Engine> {
+        &self.storage
+    }
+
+    /// Import the rows of CSV into table, the first record is a header of the column names.
+    /// Every row is checked like `INSERT`, the columns missing from the header take their
+    /// default or sequence values. The rows are inserted in batches of [`IMPORT_BATCH_SIZE`],
+    /// so the CSV is never held in memory, and the batches before a malformed row are kept.
+    /// Returns the number of imported rows, they bypass transactions and commit observers.
+    pub async fn import_csv(
+        &self,
+        table: &str,
+        reader: impl AsyncRead + Unpin,
+    ) -> SqlResult<usize> {
+        let heap = self
+            .storage
+            .read_table(table)
+            .await?
+            .ok_or(Error::NotFound("table", table.to_string()))?;
+        let columns: Vec<Column> = heap.columns().await?.into_iter().map(Into::into).collect();
+        let schema = Table::new(table, columns);
+        let primary_key = schema.primary_key_index()?;
+        let mut reader = CsvReader::new(reader);
+        let (_, header) = reader
+            .next_record()
+            .await?
+            .ok_or(Error::Value("The CSV has no header".to_string()))?;
+        let positions = header
+            .into_iter()
+            .map(|name| schema.column_index(&name.unwrap_or_default()))
+            .collect::<SqlResult<Vec<_>>>()?;
+        let mut count = 0;
+        let mut batch = Vec::with_capacity(IMPORT_BATCH_SIZE);
+        let mut keys = BTreeSet::new();
+        loop {
+            let record = reader.next_record().await?;
+            let done = record.is_none();
+            if let Some((line, fields)) = record {
+                let row = self
+                    .import_row(&heap, &schema, &positions, fields)
+                    .await
+                    .map_err(|err| Error::Value(format!("line {}: {}", line, err)))?;
+                let key = &row[primary_key];
+                if !keys.insert(key.clone()) || self.storage.contains(table, key).await? {
+                    return Err(Error::Value(format!(
+                        "line {}: Primary key {} already exists in table {}",
+                        line, key, table
+                    )));
+                }
+                batch.push(Tuple::new(row, 0));
+            }
+            if batch.len() == IMPORT_BATCH_SIZE || (done && !batch.is_empty()) {
+                count += self
+                    .storage
+                    .insert(table, std::mem::take(&mut batch))
+                    .await?;
+                keys.clear();
+            }
+            if done {
+                return Ok(count);
+            }
+        }
+    }
+
+    /// Build a full row of table from the fields of a CSV record. The sequence is advanced
+    /// past the given auto-increment values now, since the rows are inserted later.
+    async fn import_row(
+        &self,
+        heap: &storage::table::Table,
+        schema: &Table,
+        positions: &[usize],
+        fields: Vec<csv::Field>,
+    ) -> SqlResult<Row> {
+        if fields.len() != positions.len() {
+            return Err(Error::Value(format!(
+                "{} columns but {} fields were supplied",
+                positions.len(),
+                fields.len()
+            )));
+        }
+        let mut row: Vec<Option<Value>> = vec![None; schema.columns().len()];
+        for (position, field) in positions.iter().zip(fields) {
+            row[*position] = Some(csv::parse_field(
+                field,
+                &schema.columns()[*position].datatype,
+            )?);
+        }
+        let mut built = Vec::with_capacity(row.len());
+        for (value, column) in row.into_iter().zip(schema.columns()) {
+            let value = match value {
+                None | Some(Value::Null) if column.constraints.is_auto_increment() => {
+                    Value::Bigint(heap.next_sequence().await?.into())
+                }
+                Some(value) => value,
+                None => column.constraints.default.clone().ok_or_else(|| {
+                    Error::Value(format!("No value given for column {}", column.name))
+                })?,
+            };
+            let value = column.check_value(value)?;
+            if let Some(value) = value
+                .as_i64()
+                .filter(|_| column.constraints.is_auto_increment())
+            {
+                heap.advance_sequence(value).await?;
+            }
+            built.push(value);
+        }
+        Ok(built)
+    }
+
+    /// Register an observer which is called after a transaction with writes commits
+    pub fn on_commit(&self, observer: CommitObserver) {
+        self.observers.write().unwrap().push(Arc::new(observer));
+    }
+
+    /// The ids of the transactions which are neither committed nor aborted
+    pub fn active_transactions(&self) -> Vec<TransactionId> {
+        self.transactions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, state)| *state.status.lock().unwrap() == Status::Active)
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    /// Abort an active transaction and undo its writes, its next operation and its commit fail
+    /// with [`Error::Aborted`]. A write in progress finishes before the abort, and whichever of
+    /// abort and commit comes first wins, aborting a committed transaction fails with
+    /// [`Error::Committed`].
+    pub async fn abort(&self, id: TransactionId) -> SqlResult<()> {
+        let state = self
+            .transactions
+            .lock()
+            .unwrap()
+            .get(&id)
+            .cloned()
+            .ok_or(Error::NotFound("transaction", id.to_string()))?;
+        let _latch = state.latch.lock().await;
+        state.abort(id, &self.storage).await
+    }
+}
+
+impl Engine for StorageEngine {
+    type Transaction = StorageTransaction;
+
+    async fn begin(&self) -> SqlResult<Self::Transaction> {
+        let id = self.next_transaction_id.fetch_add(1, Ordering::Relaxed);
+        let txn = StorageTransaction::new(self.storage.clone())
+            .with_schemas(self.schemas.clone())
+            .with_memory_budget(self.memory_budget)
+            .with_cast_mode(self.cast_mode)
+            .with_observers(self.observers.clone())
+            .with_id(id, self.transactions.clone());
+        self.transactions
+            .lock()
+            .unwrap()
+            .insert(id, txn.state.clone());
+        Ok(txn)
+    }
+}
+
+/// Every write of the transaction is applied to storage immediately, so commit is a no-op
+/// and rollback undoes the writes in reverse order. DDL and sequences aren't rolled back.
+/// An aborted transaction can't go on, its writes are undone and never passed to the observers.
+pub struct StorageTransaction {
+    id: TransactionId,
+    storage: Arc<engine::Engine>,
+    schemas: Arc<SchemaCache>,
+    memory_budget: Option<usize>,
+    cast_mode: CastMode,
+    writes: Mutex<Vec<Write>>,
+    /// Whether the transaction wrote rows, schemas or sequences, so its commit syncs storage
+    modified: AtomicBool,
+    observers: Observers,
+    state: Arc<TransactionState>,
+    transactions: Transactions,
+}
+
+impl StorageTransaction {
+    pub fn new(storage: Arc<engine::Engine>) -> Self {
+        Self {
+            id: 0,
+            storage,
+            schemas: Default::default(),
+            memory_budget: None,
+            cast_mode: Default::default(),
+            writes: Default::default(),
+            modified: Default::default(),
+            observers: Default::default(),
+            state: Default::default(),
+            transactions: Default::default(),
+        }
+    }
+
+    fn with_schemas(mut self, schemas: Arc<SchemaCache>) -> Self {
+        self.schemas = schemas;
+        self
+    }
+
+    fn with_memory_budget(mut self, memory_budget: Option<usize>) -> Self {
+        self.memory_budget = memory_budget;
+        self
+    }
+
+    fn with_cast_mode(mut self, cast_mode: CastMode) -> Self {
+        self.cast_mode = cast_mode;
+        self
+    }
+
+    fn with_observers(mut self, observers: Observers) -> Self {
+        self.observers = observers;
+        self
+    }
+
+    fn with_id(mut self, id: TransactionId, transactions: Transactions) -> Self {
+        self.id = id;
+        self.transactions = transactions;
+        self
+    }
+
+    pub fn id(&self) -> TransactionId {
+        self.id
+    }
+
+    /// Fails if the transaction has been aborted
+    fn check_active(&self) -> SqlResult<()> {
+        match *self.state.status.lock().unwrap() {
+            Status::Aborted => Err(Error::Aborted(self.id)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Wait for the latch of the transaction before a modification, then fail if it has been
+    /// aborted. The transaction can't be aborted until the returned guard is dropped.
+    async fn latch_active(&self) -> SqlResult<tokio::sync::MutexGuard<'_, ()>> {
+        let latch = self.state.latch.lock().await;
+        self.check_active()?;
+        self.modified.store(true, Ordering::Relaxed);
+        Ok(latch)
+    }
+
+    /// Record a write for the observers and how to undo it for rollback
+    fn record(&self, write: Write, undo: Undo) {
+        self.writes.lock().unwrap().push(write);
+        self.state.undo.lock().unwrap().push(undo);
+    }
+
+    async fn must_read_table(&self, name: &str) -> SqlResult<Table> {
+        self.read_table(name)
+            .await?
+            .ok_or(Error::NotFound("table", name.to_string()))
+    }
+}
+
+impl Catalog for StorageTransaction {
+    async fn create_table(&self, table: Table) -> SqlResult<()> {
+        let _latch = self.latch_active().await?;
+        self.storage
+            .create_table(
+                table.name(),
+                table.columns().iter().cloned().map(Into::into).collect(),
+            )
+            .await?;
+        self.schemas.invalidate(table.name());
+        Ok(())
+    }
+
+    async fn drop_table(&self, name: &str) -> SqlResult<Option<Table>> {
+        let _latch = self.latch_active().await?;
+        let table = self.read_table(name).await?;
+        if table.is_some() {
+            self.storage.drop_table(name).await?;
+            self.schemas.invalidate(name);
+        }
+        Ok(table)
+    }
+
+    async fn read_table(&self, name: &str) -> SqlResult<Option<Table>> {
+        self.check_active()?;
+        if let Some(table) = self.schemas.get(name) {
+            return Ok(Some(table));
+        }
+        let version = self.schemas.version();
+        let Some(table) = self.storage.read_table(name).await? else {
+            return Ok(None);
+        };
+        let table = Table::new(
+            name,
+            table.columns().await?.into_iter().map(Into::into).collect(),
+        )
+        .with_indexes(table.indexes().await?.into_iter().map(Into::into).collect());
+        self.schemas.insert(version, table.clone());
+        Ok(Some(table))
+    }
+
+    async fn create_index(&self, table: &str, index: Index) -> SqlResult<()> {
+        let _latch = self.latch_active().await?;
+        self.storage
+            .create_index(table, &index.name, index.columns)
+            .await?;
+        self.schemas.invalidate(table);
+        Ok(())
+    }
+
+    async fn rename_column(&self, table: &str, from: &str, to: &str) -> SqlResult<()> {
+        let _latch = self.latch_active().await?;
+        let schema = self.must_read_table(table).await?;
+        schema.column_index(from)?;
+        if schema.column_index(to).is_ok() {
+            return Err(Error::Value(format!(
+                "Column {} already exists in table {}",
+                to, table
+            )));
+        }
+        self.storage
+            .read_table(table)
+            .await?
+            .ok_or(Error::NotFound("table", table.to_string()))?
+            .rename_column(from, to)
+            .await?;
+        self.schemas.invalidate(table);
+        Ok(())
+    }
+}
+
+impl Drop for StorageTransaction {
+    fn drop(&mut self) {
+        self.transactions.lock().unwrap().remove(&self.id);
+    }
+}
+
+impl Transaction for StorageTransaction {
+    async fn commit(self) -> SqlResult<()> {
+        let _latch = self.state.latch.lock().await;
+        match *self.state.status.lock().unwrap() {
+            Status::Active => {}
+            Status::Committed => return Err(Error::Committed(self.id)),
+            Status::Aborted => return Err(Error::Aborted(self.id)),
+        }
+        if self.modified.load(Ordering::Relaxed) {
+            if let Err(err) = self.storage.commit().await {
+                // the writes which can't be committed are undone like an abort
+                *self.state.status.lock().unwrap() = Status::Aborted;
+                self.state.undo(&self.storage).await?;
+                return Err(err.into());
+            }
+        }
+        *self.state.status.lock().unwrap() = Status::Committed;
+        let writes = std::mem::take(&mut *self.writes.lock().unwrap());
+        if writes.is_empty() {
+            return Ok(());
+        }
+        let event = CommitEvent { writes };
+        // Call the observers without holding the lock, so they can register other observers
+        let observers = self.observers.read().unwrap().clone();
+        for observer in observers {
+            observer(&event);
+        }
+        Ok(())
+    }
+
+    /// Undo the writes in reverse order, an aborted transaction can be rolled back too
+    async fn rollback(self) -> SqlResult<()> {
+        let _latch = self.state.latch.lock().await;
+        {
+            let mut status = self.state.status.lock().unwrap();
+            if *status == Status::Committed {
+                return Err(Error::Committed(self.id));
+            }
+            *status = Status::Aborted;
+        }
+        self.writes.lock().unwrap().clear();
+        self.state.undo(&self.storage).await
+    }
+
+    async fn insert(&self, table: &str, row: Row) -> SqlResult<()> {
+        let _latch = self.latch_active().await?;
+        let schema = self.must_read_table(table).await?;
+        let key = row
+            .get(schema.primary_key_index()?)
+            .ok_or(Error::NotFound("primary key", table.to_string()))?;
+        if self.storage.contains(table, key).await? {
+            return Err(Error::Value(format!(
+                "Primary key {} already exists in table {}",
+                key, table
+            )));
+        }
+        let undo = Undo::Delete {
+            table: table.to_string(),
+            key: key.clone(),
+        };
+        self.storage
+            .insert(table, vec![Tuple::new(row.clone(), 0)])
+            .await?;
+        self.record(
+            Write::Insert {
+                table: table.to_string(),
+                row,
+            },
+            undo,
+        );
+        Ok(())
+    }
+
+    async fn read(&self, table: &str, key: &Value) -> SqlResult<Option<Row>> {
+        self.check_active()?;
+        Ok(self
+            .storage
+            .read(table, key)
+            .await?
+            .map(|tuple| tuple.values))
+    }
+
+    async fn delete(&self, table: &str, key: &Value) -> SqlResult<Option<Row>> {
+        let _latch = self.latch_active().await?;
+        let row = self
+            .storage
+            .delete(table, key)
+            .await?
+            .map(|tuple| tuple.values);
+        if let Some(row) = &row {
+            self.record(
+                Write::Delete {
+                    table: table.to_string(),
+                    row: row.clone(),
+                },
+                Undo::Insert {
+                    table: table.to_string(),
+                    row: row.clone(),
+                },
+            );
+        }
+        Ok(row)
+    }
+
+    async fn delete_range(
+        &self,
+        table: &str,
+        start: Bound<&Value>,
+        end: Bound<&Value>,
+    ) -> SqlResult<Vec<Row>> {
+        let _latch = self.latch_active().await?;
+        let rows = self
+            .storage
+            .delete_range(table, start, end)
+            .await?
+            .into_iter()
+            .map(|tuple| tuple.values)
+            .collect::<Vec<_>>();
+        for row in rows.iter() {
+            self.record(
+                Write::Delete {
+                    table: table.to_string(),
+                    row: row.clone(),
+                },
+                Undo::Insert {
+                    table: table.to_string(),
+                    row: row.clone(),
+                },
+            );
+        }
+        Ok(rows)
+    }
+
+    async fn update(&self, table: &str, row: Row) -> SqlResult<Option<()>> {
+        let _latch = self.latch_active().await?;
+        let schema = self.must_read_table(table).await?;
+        let key = row
+            .get(schema.primary_key_index()?)
+            .ok_or(Error::NotFound("primary key", table.to_string()))?;
+        let Some(old) = self.storage.read(table, key).await? else {
+            return Ok(None);
+        };
+        let updated = self
+            .storage
+            .update(table, Tuple::new(row.clone(), 0))
+            .await?;
+        if updated.is_some() {
+            self.record(
+                Write::Update {
+                    table: table.to_string(),
+                    row,
+                },
+                Undo::Update {
+                    table: table.to_string(),
+                    row: old.values,
+                },
+            );
+        }
+        Ok(updated)
+    }
+
+    async fn next_sequence(&self, table: &str) -> SqlResult<i64> {
+        let _latch = self.latch_active().await?;
+        Ok(self.storage.next_sequence(table).await?)
+    }
+
+    async fn reindex(&self, table: &str) -> SqlResult<usize> {
+        let _latch = self.latch_active().await?;
+        Ok(self.storage.reindex(table).await?)
+    }
+
+    async fn analyze(&self, table: &str) -> SqlResult<TableStats> {
+        self.check_active()?;
+        Ok(self.storage.analyze(table).await?)
+    }
+
+    async fn search_index(
+        &self,
+        table: &str,
+        column: usize,
+        key: &Value,
+    ) -> SqlResult<Option<Vec<RecordId>>> {
+        self.check_active()?;
+        Ok(self.storage.search_index(table, column, key).await?)
+    }
+
+    async fn search_composite_index(
+        &self,
+        table: &str,
+        index: &str,
+        prefix: &[Value],
+    ) -> SqlResult<Option<Vec<RecordId>>> {
+        self.check_active()?;
+        Ok(self
+            .storage
+            .search_composite_index(table, index, prefix)
+            .await?)
+    }
+
+    async fn search_index_range(
+        &self,
+        table: &str,
+        column: usize,
+        start: Bound<&Value>,
+        end: Bound<&Value>,
+    ) -> SqlResult<Option<Vec<RecordId>>> {
+        self.check_active()?;
+        Ok(self
+            .storage
+            .search_index_range(table, column, start, end)
+            .await?)
+    }
+
+    async fn index_extreme(
+        &self,
+        table: &str,
+        column: usize,
+        largest: bool,
+    ) -> SqlResult<Option<Value>> {
+        self.check_active()?;
+        Ok(self.storage.index_extreme(table, column, largest).await?)
+    }
+
+    async fn read_records(&self, table: &str, record_ids: &[RecordId]) -> SqlResult<Vec<Row>> {
+        self.check_active()?;
+        Ok(self
+            .storage
+            .read_records(table, record_ids)
+            .await?
+            .into_iter()
+            .map(|tuple| tuple.values)
+            .collect())
+    }
+
+    fn memory_budget(&self) -> Option<usize> {
+        self.memory_budget
+    }
+
+    fn cast_mode(&self) -> CastMode {
+        self.cast_mode
+    }
+
+    async fn scan(&self, table: &str) -> SqlResult<impl Stream<Item = SqlResult<Row>>> {
+        self.check_active()?;
+        Ok(self
+            .storage
+            .scan(table, ..)
+            .await?
+            .map(|tuple| tuple.map(|tuple| tuple.values).map_err(Into::into)))
+    }
+}
@@ -1,11 +1,368 @@
+use crate::sql::csv;
+use crate::sql::plan::node::Node;
 use crate::sql::transaction::Transaction;
-use crate::sql::SqlResult;
+use crate::sql::types::Value;
+use crate::sql::types::{DataType, Row};
+use crate::sql::{Error, SqlResult};
+use crate::storage;
+use crate::storage::engine::TableStats;
+use std::fmt::Formatter;
 use std::future::Future;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+mod mutation;
+mod query;
+mod schema;
 
 /// Executor execute the physical plan
 pub trait Executor<T: Transaction> {
     fn execute(self, txn: &T) -> impl Future<Output = SqlResult<ResultSet>>;
 }
 
-#[derive(Debug, Clone)]
-pub enum ResultSet {}
+/// The name and datatype of an output column, the datatype is `None` when it's unknown
+pub type OutputColumn = (String, Option<DataType>);
+
+/// The result of executing a statement
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResultSet {
+    AlterTable {
+        name: String,
+    },
+    Analyze {
+        name: String,
+        stats: TableStats,
+    },
+    Begin,
+    Commit,
+    Rollback,
+    CreateTable {
+        name: String,
+    },
+    CreateIndex {
+        name: String,
+    },
+    DropTable {
+        name: String,
+    },
+    Insert {
+        count: usize,
+    },
+    /// The number of entries in the rebuilt index
+    Reindex {
+        name: String,
+        count: usize,
+    },
+    Update {
+        count: usize,
+    },
+    Delete {
+        count: usize,
+    },
+    Query {
+        columns: Vec<OutputColumn>,
+        rows: Vec<Row>,
+    },
+}
+
+impl<T: Transaction> Executor<T> for Node {
+    async fn execute(self, txn: &T) -> SqlResult<ResultSet> {
+        match self {
+            Node::Analyze { table } => schema::Analyze::new(table).execute(txn).await,
+            Node::Reindex { table } => schema::Reindex::new(table).execute(txn).await,
+            Node::AlterTable { table, action } => {
+                schema::AlterTable::new(table, action).execute(txn).await
+            }
+            Node::CreateTable { schema } => schema::CreateTable::new(schema).execute(txn).await,
+            Node::CreateIndex { table, index } => {
+                schema::CreateIndex::new(table, index).execute(txn).await
+            }
+            Node::DropTable { table, if_exists } => {
+                schema::DropTable::new(table, if_exists).execute(txn).await
+            }
+            Node::Insert {
+                table,
+                columns,
+                source,
+            } => {
+                mutation::Insert::new(table, columns, *source)
+                    .execute(txn)
+                    .await
+            }
+            Node::Delete { table, source } => {
+                mutation::Delete::new(table, *source).execute(txn).await
+            }
+            Node::Update {
+                table,
+                source,
+                expressions,
+                returning,
+            } => {
+                mutation::Update::new(table, *source, expressions, returning)
+                    .execute(txn)
+                    .await
+            }
+            Node::Scan { table, filter, .. } => query::Scan::new(table, filter).execute(txn).await,
+            Node::IndexScan {
+                table,
+                lookups,
+                filter,
+                ..
+            } => {
+                query::IndexScan::new(table, lookups, filter)
+                    .execute(txn)
+                    .await
+            }
+            Node::Aggregate {
+                source,
+                group_by,
+                aggregates,
+            } => {
+                query::Aggregation::new(*source, group_by, aggregates)
+                    .execute(txn)
+                    .await
+            }
+            Node::IndexExtreme {
+                table,
+                column,
+                aggregate,
+                label,
+            } => {
+                query::IndexExtreme::new(table, column, aggregate, label)
+                    .execute(txn)
+                    .await
+            }
+            Node::Order { source, orders } => query::Order::new(*source, orders).execute(txn).await,
+            Node::Offset { source, offset } => {
+                query::Offset::new(*source, offset).execute(txn).await
+            }
+            Node::Limit { source, limit } => query::Limit::new(*source, limit).execute(txn).await,
+            Node::Projection {
+                source,
+                expressions,
+            } => {
+                query::Projection::new(*source, expressions)
+                    .execute(txn)
+                    .await
+            }
+            Node::Values { rows } => query::Values::new(rows).execute(txn).await,
+        }
+    }
+}
+
+impl ResultSet {
+    /// Returns the columns and rows of a query result
+    pub fn into_query(self) -> Option<(Vec<OutputColumn>, Vec<Row>)> {
+        match self {
+            ResultSet::Query { columns, rows } => Some((columns, rows)),
+            _ => None,
+        }
+    }
+
+    fn query(&self) -> SqlResult<(&[OutputColumn], &[Row])> {
+        match self {
+            ResultSet::Query { columns, rows } => Ok((columns, rows)),
+            _ => Err(Error::Value(
+                "Only the result of a query can be exported".to_string(),
+            )),
+        }
+    }
+
+    /// Write the query result as CSV with a header of the column names, NULL is an empty field.
+    /// Every row is written as soon as it's rendered.
+    pub async fn write_csv(&self, mut writer: impl AsyncWrite + Unpin) -> SqlResult<()> {
+        let (columns, rows) = self.query()?;
+        let header = columns.iter().map(|(name, _)| csv::escape(name));
+        write_line(&mut writer, header.collect::<Vec<_>>().join(",")).await?;
+        for row in rows {
+            let fields = row.iter().map(csv::format_field);
+            write_line(&mut writer, fields.collect::<Vec<_>>().join(",")).await?;
+        }
+        writer.flush().await.map_err(storage::Error::from)?;
+        Ok(())
+    }
+
+    /// Write every row of the query result as a JSON object keyed by the column names,
+    /// one object per line. NULL and the floats JSON can't represent are `null`.
+    pub async fn write_ndjson(&self, mut writer: impl AsyncWrite + Unpin) -> SqlResult<()> {
+        let (columns, rows) = self.query()?;
+        for row in rows {
+            let fields = columns
+                .iter()
+                .zip(row)
+                .map(|((name, _), value)| format!("{}:{}", json_string(name), json_value(value)));
+            write_line(
+                &mut writer,
+                format!("{{{}}}", fields.collect::<Vec<_>>().join(",")),
+            )
+            .await?;
+        }
+        writer.flush().await.map_err(storage::Error::from)?;
+        Ok(())
+    }
+}
+
+/// A one-line status of the result, like `INSERT 3`. A query is summarized by its number of rows
+impl std::fmt::Display for ResultSet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResultSet::AlterTable { name } => write!(f, "ALTER TABLE {}", name),
+            ResultSet::Analyze { name, stats } => {
+                write!(f, "ANALYZE {} {} {}", name, stats.rows, stats.pages)
+            }
+            ResultSet::Begin => write!(f, "BEGIN"),
+            ResultSet::Commit => write!(f, "COMMIT"),
+            ResultSet::Rollback => write!(f, "ROLLBACK"),
+            ResultSet::CreateTable { name } => write!(f, "CREATE TABLE {}", name),
+            ResultSet::CreateIndex { name } => write!(f, "CREATE INDEX {}", name),
+            ResultSet::DropTable { name } => write!(f, "DROP TABLE {}", name),
+            ResultSet::Insert { count } => write!(f, "INSERT {}", count),
+            ResultSet::Reindex { name, count } => write!(f, "REINDEX {} {}", name, count),
+            ResultSet::Update { count } => write!(f, "UPDATE {}", count),
+            ResultSet::Delete { count } => write!(f, "DELETE {}", count),
+            ResultSet::Query { rows, .. } => write!(f, "SELECT {}", rows.len()),
+        }
+    }
+}
+
+async fn write_line(writer: &mut (impl AsyncWrite + Unpin), mut line: String) -> SqlResult<()> {
+    line.push('\n');
+    writer
+        .write_all(line.as_bytes())
+        .await
+        .map_err(storage::Error::from)?;
+    Ok(())
+}
+
+fn json_value(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Boolean(b) => b.to_string(),
+        Value::Float(f) if f.is_finite() => f.to_string(),
+        Value::Double(f) if f.is_finite() => f.to_string(),
+        Value::Float(_) | Value::Double(_) => "null".to_string(),
+        Value::String(s) => json_string(s),
+        value => value.to_string(),
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut output = String::with_capacity(s.len() + 2);
+    output.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ordered_float::OrderedFloat;
+
+    fn result() -> ResultSet {
+        ResultSet::Query {
+            columns: vec![
+                ("id".to_string(), Some(DataType::Integer)),
+                ("name".to_string(), Some(DataType::String)),
+                ("score".to_string(), Some(DataType::Double)),
+                ("ok".to_string(), None),
+            ],
+            rows: vec![
+                vec![
+                    Value::Integer(1),
+                    Value::String("Mike".to_string()),
+                    Value::Double(OrderedFloat(1.5)),
+                    Value::Boolean(true),
+                ],
+                vec![
+                    Value::Integer(2),
+                    Value::String("say \"hi\", bye\n".to_string()),
+                    Value::Double(OrderedFloat(f64::NAN)),
+                    Value::Null,
+                ],
+                vec![
+                    Value::Integer(3),
+                    Value::String(String::new()),
+                    Value::Null,
+                    Value::Boolean(false),
+                ],
+            ],
+        }
+    }
+
+    #[tokio::test]
+    async fn export() -> SqlResult<()> {
+        let mut output = Vec::new();
+        result().write_csv(&mut output).await?;
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "id,name,score,ok\n\
+             1,Mike,1.5,TRUE\n\
+             2,\"say \"\"hi\"\", bye\n\",NaN,\n\
+             3,\"\",,FALSE\n"
+        );
+
+        let mut output = Vec::new();
+        result().write_ndjson(&mut output).await?;
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "{\"id\":1,\"name\":\"Mike\",\"score\":1.5,\"ok\":true}\n\
+             {\"id\":2,\"name\":\"say \\\"hi\\\", bye\\n\",\"score\":null,\"ok\":null}\n\
+             {\"id\":3,\"name\":\"\",\"score\":null,\"ok\":false}\n"
+        );
+
+        assert!(ResultSet::Insert { count: 1 }
+            .write_csv(&mut Vec::new())
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn display() {
+        let name = || "user".to_string();
+        for (result, status) in [
+            (ResultSet::AlterTable { name: name() }, "ALTER TABLE user"),
+            (
+                ResultSet::Analyze {
+                    name: name(),
+                    stats: TableStats { rows: 10, pages: 2 },
+                },
+                "ANALYZE user 10 2",
+            ),
+            (ResultSet::Begin, "BEGIN"),
+            (ResultSet::Commit, "COMMIT"),
+            (ResultSet::Rollback, "ROLLBACK"),
+            (ResultSet::CreateTable { name: name() }, "CREATE TABLE user"),
+            (
+                ResultSet::CreateIndex {
+                    name: "user_name".to_string(),
+                },
+                "CREATE INDEX user_name",
+            ),
+            (ResultSet::DropTable { name: name() }, "DROP TABLE user"),
+            (ResultSet::Insert { count: 3 }, "INSERT 3"),
+            (
+                ResultSet::Reindex {
+                    name: name(),
+                    count: 5,
+                },
+                "REINDEX user 5",
+            ),
+            (ResultSet::Update { count: 0 }, "UPDATE 0"),
+            (ResultSet::Delete { count: 1 }, "DELETE 1"),
+            (result(), "SELECT 3"),
+        ] {
+            assert_eq!(result.to_string(), status);
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use crate::sql::catalog::Table;
+use crate::sql::execution::query::{project, source_rows, table_columns};
+use crate::sql::execution::{Executor, ResultSet};
+use crate::sql::plan::node::{Node, ProjectionItem};
+use crate::sql::transaction::Transaction;
+use crate::sql::types::expression::Expression;
+use crate::sql::types::{Row, Value};
+use crate::sql::{Error, SqlResult};
+use std::ops::Bound;
+
+pub struct Insert {
+    table: String,
+    columns: Vec<String>,
+    source: Node,
+}
+
+impl Insert {
+    pub fn new(table: String, columns: Vec<String>, source: Node) -> Self {
+        Self {
+            table,
+            columns,
+            source,
+        }
+    }
+
+    /// Build a full row of table from the values of the given columns, missing columns are
+    /// filled with their default value, or the next value of the sequence if they are
+    /// auto-increment. Without the given columns, the values are taken in the table's order,
+    /// skipping the hidden rowid
+    async fn build_row<T: Transaction>(
+        txn: &T,
+        schema: &Table,
+        given: &[String],
+        values: Vec<Value>,
+    ) -> SqlResult<Row> {
+        let columns = schema.columns();
+        let wildcard = schema.wildcard();
+        if values.len() > wildcard.len() {
+            return Err(Error::Value(format!(
+                "Table {} has {} columns but {} values were supplied",
+                schema.name(),
+                wildcard.len(),
+                values.len()
+            )));
+        }
+        let mut row: Vec<Option<Value>> = vec![None; columns.len()];
+        if given.is_empty() {
+            for (position, value) in wildcard.into_iter().zip(values) {
+                row[position] = Some(value);
+            }
+        } else {
+            if given.len() != values.len() {
+                return Err(Error::Value(format!(
+                    "{} columns but {} values were supplied",
+                    given.len(),
+                    values.len()
+                )));
+            }
+            for (column, value) in given.iter().zip(values) {
+                row[schema.column_index(column)?] = Some(value);
+            }
+        }
+        let mut built = Vec::with_capacity(columns.len());
+        for (value, column) in row.into_iter().zip(columns) {
+            let value = match value {
+                None | Some(Value::Null) if column.constraints.is_auto_increment() => {
+                    Value::Bigint(txn.next_sequence(schema.name()).await?.into())
+                }
+                Some(value) => value,
+                None => column.constraints.default.clone().ok_or_else(|| {
+                    Error::Value(format!("No value given for column {}", column.name))
+                })?,
+            };
+            built.push(column.check_value(value)?);
+        }
+        Ok(built)
+    }
+}
+
+impl<T: Transaction> Executor<T> for Insert {
+    async fn execute(self, txn: &T) -> SqlResult<ResultSet> {
+        let schema = txn
+            .read_table(&self.table)
+            .await?
+            .ok_or(Error::NotFound("table", self.table.clone()))?;
+        let (source_columns, rows) = source_rows(self.source, txn).await?;
+        // only the rows of VALUES have no columns, they may omit the trailing columns
+        let width = schema.wildcard().len();
+        if !source_columns.is_empty() && self.columns.is_empty() && source_columns.len() != width {
+            return Err(Error::Value(format!(
+                "Table {} has {} columns but the query returns {}",
+                schema.name(),
+                width,
+                source_columns.len()
+            )));
+        }
+        let mut count = 0;
+        for values in rows {
+            let row = Self::build_row(txn, &schema, &self.columns, values).await?;
+            txn.insert(&self.table, row).await?;
+            count += 1;
+        }
+        Ok(ResultSet::Insert { count })
+    }
+}
+
+pub struct Delete {
+    table: String,
+    source: Node,
+}
+
+impl Delete {
+    pub fn new(table: String, source: Node) -> Self {
+        Self { table, source }
+    }
+
+    /// The range of primary keys which the source selects, if it's a scan filtered only by
+    /// comparisons between the primary key and constants, like `id BETWEEN 1 AND 9`.
+    /// The constants must be represented exactly by the datatype of the key.
+    fn key_range(&self, schema: &Table) -> SqlResult<Option<(Bound<Value>, Bound<Value>)>> {
+        let Node::Scan {
+            filter: Some(filter),
+            ..
+        } = &self.source
+        else {
+            return Ok(None);
+        };
+        let primary_key = schema.primary_key_index()?;
+        let Some((start, end)) = filter.as_index_bound(primary_key) else {
+            return Ok(None);
+        };
+        let datatype = &schema.columns()[primary_key].datatype;
+        // a lossy cast would move the bound to another value
+        let exact = |value: Value| {
+            let key = value.clone().cast(datatype).ok()?;
+            match Expression::Equal(
+                Box::new(Expression::Const(key.clone())),
+                Box::new(Expression::Const(value)),
+            )
+            .evaluate(None)
+            {
+                Ok(Value::Boolean(true)) => Some(key),
+                _ => None,
+            }
+        };
+        let cast = |bound: Bound<Value>| match bound {
+            Bound::Included(value) => exact(value).map(Bound::Included),
+            Bound::Excluded(value) => exact(value).map(Bound::Excluded),
+            Bound::Unbounded => Some(Bound::Unbounded),
+        };
+        Ok(cast(start).zip(cast(end)))
+    }
+}
+
+impl<T: Transaction> Executor<T> for Delete {
+    async fn execute(self, txn: &T) -> SqlResult<ResultSet> {
+        let schema = txn
+            .read_table(&self.table)
+            .await?
+            .ok_or(Error::NotFound("table", self.table.clone()))?;
+        if let Some((start, end)) = self.key_range(&schema)? {
+            let rows = txn
+                .delete_range(&self.table, start.as_ref(), end.as_ref())
+                .await?;
+            return Ok(ResultSet::Delete { count: rows.len() });
+        }
+        let primary_key = schema.primary_key_index()?;
+        let (_, rows) = source_rows(self.source, txn).await?;
+        let mut count = 0;
+        for row in rows {
+            if txn.delete(&self.table, &row[primary_key]).await?.is_some() {
+                count += 1;
+            }
+        }
+        Ok(ResultSet::Delete { count })
+    }
+}
+
+pub struct Update {
+    table: String,
+    source: Node,
+    expressions: Vec<(usize, Option<String>, Expression)>,
+    returning: Option<Vec<ProjectionItem>>,
+}
+
+impl Update {
+    pub fn new(
+        table: String,
+        source: Node,
+        expressions: Vec<(usize, Option<String>, Expression)>,
+        returning: Option<Vec<ProjectionItem>>,
+    ) -> Self {
+        Self {
+            table,
+            source,
+            expressions,
+            returning,
+        }
+    }
+}
+
+impl<T: Transaction> Executor<T> for Update {
+    async fn execute(self, txn: &T) -> SqlResult<ResultSet> {
+        let schema = txn
+            .read_table(&self.table)
+            .await?
+            .ok_or(Error::NotFound("table", self.table.clone()))?;
+        let primary_key = schema.primary_key_index()?;
+        // Reject ill-typed expressions even when no row matches
+        let mode = txn.cast_mode();
+        for (_, _, expr) in &self.expressions {
+            expr.result_type_with(schema.columns(), mode)?;
+        }
+        let (_, rows) = source_rows(self.source, txn).await?;
+        let mut count = 0;
+        let mut returned = Vec::new();
+        for row in rows {
+            let mut updated = row.clone();
+            for (index, _, expr) in &self.expressions {
+                updated[*index] =
+                    schema.columns()[*index].check_value(expr.evaluate_with(Some(&row), mode)?)?;
+            }
+            if self.returning.is_some() {
+                returned.push(updated.clone());
+            }
+            if updated[primary_key] != row[primary_key] {
+                txn.delete(&self.table, &row[primary_key]).await?;
+                txn.insert(&self.table, updated).await?;
+            } else {
+                txn.update(&self.table, updated).await?;
+            }
+            count += 1;
+        }
+        match self.returning {
+            Some(returning) => project(&table_columns(&schema), returned, &returning, mode),
+            None => Ok(ResultSet::Update { count }),
+        }
+    }
+}
@@ -0,0 +1,590 @@
+use crate::sql::catalog::Table;
+use crate::sql::execution::{Executor, OutputColumn, ResultSet};
+use crate::sql::parser::dql;
+use crate::sql::plan::node::{Aggregate, AggregateItem, Lookup, Node};
+use crate::sql::transaction::Transaction;
+use crate::sql::types::expression::Expression;
+use crate::sql::types::{CastMode, DataType, Project, Row, Value};
+use crate::sql::{Error, SqlResult};
+use futures::{pin_mut, StreamExt};
+use indexmap::IndexMap;
+use std::collections::BTreeSet;
+
+/// Execute the source node and returns its columns and rows
+pub(super) async fn source_rows<T: Transaction>(
+    source: Node,
+    txn: &T,
+) -> SqlResult<(Vec<OutputColumn>, Vec<Row>)> {
+    Box::pin(source.execute(txn))
+        .await?
+        .into_query()
+        .ok_or(Error::Value(
+            "The source of plan must be a query".to_string(),
+        ))
+}
+
+/// Execute the source node and hand its rows to `f` one at a time, the rows of a scan are handed
+/// over as they're read instead of being collected first. Returns the columns of the source
+async fn for_each_source_row<T: Transaction>(
+    source: Node,
+    txn: &T,
+    mut f: impl FnMut(Row) -> SqlResult<()>,
+) -> SqlResult<Vec<OutputColumn>> {
+    match source {
+        Node::Scan { table, filter, .. } => Scan::new(table, filter).for_each(txn, f).await,
+        source => {
+            let (columns, rows) = source_rows(source, txn).await?;
+            for row in rows {
+                f(row)?;
+            }
+            Ok(columns)
+        }
+    }
+}
+
+/// The bytes of rows buffered by an operator, which fails once they exceed the memory budget of
+/// the transaction
+struct MemoryBudget {
+    operator: &'static str,
+    limit: Option<usize>,
+    used: usize,
+}
+
+impl MemoryBudget {
+    fn new<T: Transaction>(operator: &'static str, txn: &T) -> Self {
+        Self {
+            operator,
+            limit: txn.memory_budget(),
+            used: 0,
+        }
+    }
+
+    /// Account the values about to be buffered
+    fn reserve(&mut self, values: &[Value]) -> SqlResult<()> {
+        self.used += values
+            .iter()
+            .map(|value| match value {
+                Value::String(s) => std::mem::size_of::<Value>() + s.len(),
+                _ => std::mem::size_of::<Value>(),
+            })
+            .sum::<usize>();
+        match self.limit {
+            Some(limit) if self.used > limit => Err(Error::MemoryBudget(self.operator, limit)),
+            _ => Ok(()),
+        }
+    }
+}
+
+pub struct Scan {
+    table: String,
+    filter: Option<Expression>,
+}
+
+impl Scan {
+    pub fn new(table: String, filter: Option<Expression>) -> Self {
+        Self {
+            table,
+            filter: filter.map(Expression::precompute),
+        }
+    }
+
+    /// Hand the rows passing the filter to `f` as they're read, returns the columns of the table
+    async fn for_each<T: Transaction>(
+        self,
+        txn: &T,
+        mut f: impl FnMut(Row) -> SqlResult<()>,
+    ) -> SqlResult<Vec<OutputColumn>> {
+        let schema = txn
+            .read_table(&self.table)
+            .await?
+            .ok_or(Error::NotFound("table", self.table.clone()))?;
+        let stream = txn.scan(&self.table).await?;
+        pin_mut!(stream);
+        while let Some(row) = stream.next().await {
+            let row = row?;
+            if matches(&self.filter, &row, txn.cast_mode())? {
+                f(row)?;
+            }
+        }
+        Ok(table_columns(&schema))
+    }
+}
+
+impl<T: Transaction> Executor<T> for Scan {
+    async fn execute(self, txn: &T) -> SqlResult<ResultSet> {
+        let mut rows = Vec::new();
+        let columns = self
+            .for_each(txn, |row| {
+                rows.push(row);
+                Ok(())
+            })
+            .await?;
+        Ok(ResultSet::Query { columns, rows })
+    }
+}
+
+/// Whether the row passes the filter, NULL doesn't pass
+fn matches(filter: &Option<Expression>, row: &Row, mode: CastMode) -> SqlResult<bool> {
+    Ok(match filter {
+        None => true,
+        Some(filter) => match filter.evaluate_with(Some(row), mode)? {
+            Value::Boolean(b) => b,
+            Value::Null => false,
+            value => return Err(Error::ValueNotMatch("filter", value.to_string())),
+        },
+    })
+}
+
+pub(super) fn table_columns(schema: &Table) -> Vec<OutputColumn> {
+    schema
+        .columns()
+        .iter()
+        .map(|column| (column.name.clone(), Some(column.datatype.clone())))
+        .collect()
+}
+
+pub struct IndexScan {
+    table: String,
+    lookups: Vec<(usize, Lookup)>,
+    filter: Option<Expression>,
+}
+
+impl IndexScan {
+    pub fn new(table: String, lookups: Vec<(usize, Lookup)>, filter: Option<Expression>) -> Self {
+        Self {
+            table,
+            lookups,
+            filter: filter.map(Expression::precompute),
+        }
+    }
+}
+
+impl<T: Transaction> Executor<T> for IndexScan {
+    async fn execute(self, txn: &T) -> SqlResult<ResultSet> {
+        let schema = txn
+            .read_table(&self.table)
+            .await?
+            .ok_or(Error::NotFound("table", self.table.clone()))?;
+        let mut sets = Vec::with_capacity(self.lookups.len());
+        for (column, lookup) in self.lookups.iter() {
+            let record_ids = match lookup {
+                Lookup::Equal(key) => txn.search_index(&self.table, *column, key).await?,
+                Lookup::Range(start, end) => {
+                    txn.search_index_range(&self.table, *column, start.as_ref(), end.as_ref())
+                        .await?
+                }
+                Lookup::Prefix(index, key) => {
+                    txn.search_composite_index(&self.table, index, key).await?
+                }
+            };
+            let record_ids = record_ids.ok_or_else(|| {
+                let name = match lookup {
+                    Lookup::Prefix(index, _) => index.clone(),
+                    _ => schema.columns()[*column].name.clone(),
+                };
+                Error::NotFound("index", name)
+            })?;
+            sets.push(record_ids.into_iter().collect::<BTreeSet<_>>());
+        }
+        // intersect from the smallest set, so the heap is only read for the records in all sets
+        sets.sort_by_key(BTreeSet::len);
+        let mut sets = sets.into_iter();
+        let mut record_ids = sets.next().unwrap_or_default();
+        for set in sets {
+            if record_ids.is_empty() {
+                break;
+            }
+            record_ids.retain(|record_id| set.contains(record_id));
+        }
+        let record_ids = record_ids.into_iter().collect::<Vec<_>>();
+        let mut rows = txn
+            .read_records(&self.table, &record_ids)
+            .await?
+            .into_iter()
+            .filter_map(|row| match matches(&self.filter, &row, txn.cast_mode()) {
+                Ok(true) => Some(Ok(row)),
+                Ok(false) => None,
+                Err(err) => Some(Err(err)),
+            })
+            .collect::<SqlResult<Vec<_>>>()?;
+        // return the rows in primary key order like a full scan
+        let primary = schema.primary_key_index()?;
+        rows.sort_by(|a, b| a.get(primary).cmp(&b.get(primary)));
+        Ok(ResultSet::Query {
+            columns: table_columns(&schema),
+            rows,
+        })
+    }
+}
+
+pub struct Aggregation {
+    source: Node,
+    group_by: Vec<Expression>,
+    aggregates: Vec<AggregateItem>,
+}
+
+impl Aggregation {
+    pub fn new(source: Node, group_by: Vec<Expression>, aggregates: Vec<AggregateItem>) -> Self {
+        Self {
+            source,
+            group_by,
+            aggregates,
+        }
+    }
+
+    fn initial(aggregates: &[AggregateItem]) -> Vec<Value> {
+        aggregates
+            .iter()
+            .map(|(aggregate, _, _)| match aggregate {
+                Aggregate::Count => Value::Integer(0),
+                Aggregate::Max | Aggregate::Min => Value::Null,
+            })
+            .collect()
+    }
+
+    fn fold(
+        aggregates: &[AggregateItem],
+        values: &mut [Value],
+        row: &Row,
+        mode: CastMode,
+    ) -> SqlResult<()> {
+        for ((aggregate, expr, _), folded) in aggregates.iter().zip(values.iter_mut()) {
+            let value = expr.evaluate_with(Some(row), mode)?;
+            if value == Value::Null {
+                continue;
+            }
+            match (aggregate, &folded) {
+                (Aggregate::Count, Value::Integer(count)) => *folded = Value::Integer(count + 1),
+                (Aggregate::Max, Value::Null) | (Aggregate::Min, Value::Null) => *folded = value,
+                (Aggregate::Max, _) if value > *folded => *folded = value,
+                (Aggregate::Min, _) if value < *folded => *folded = value,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: Transaction> Executor<T> for Aggregation {
+    async fn execute(self, txn: &T) -> SqlResult<ResultSet> {
+        let Self {
+            source,
+            group_by,
+            aggregates,
+        } = self;
+        let mode = txn.cast_mode();
+        // Groups are told apart by the structural equality of their keys,
+        // so all the NULL keys fall into one group
+        let mut groups: IndexMap<Vec<Value>, Vec<Value>> = IndexMap::new();
+        let mut budget = MemoryBudget::new("aggregate", txn);
+        if group_by.is_empty() {
+            groups.insert(Vec::new(), Self::initial(&aggregates));
+        }
+        // the rows are folded as they're read, only the groups are buffered
+        let source_columns = for_each_source_row(source, txn, |row| {
+            let key = group_by
+                .iter()
+                .map(|expr| expr.evaluate_with(Some(&row), mode))
+                .collect::<SqlResult<Vec<_>>>()?;
+            if !groups.contains_key(&key) {
+                budget.reserve(&key)?;
+                budget.reserve(&Self::initial(&aggregates))?;
+            }
+            let values = groups
+                .entry(key)
+                .or_insert_with(|| Self::initial(&aggregates));
+            Self::fold(&aggregates, values, &row, mode)
+        })
+        .await?;
+        let types = source_columns
+            .iter()
+            .map(|(_, datatype)| datatype.clone())
+            .collect::<Vec<_>>();
+        let mut columns = group_by
+            .iter()
+            .map(|expr| {
+                let name = match expr {
+                    Expression::Column(index) => source_columns
+                        .get(*index)
+                        .map(|(name, _)| name.clone())
+                        .unwrap_or_else(|| "?column?".to_string()),
+                    _ => "?column?".to_string(),
+                };
+                Ok((name, expr.infer_type_with(&types, mode)?))
+            })
+            .collect::<SqlResult<Vec<_>>>()?;
+        for (aggregate, expr, label) in aggregates.iter() {
+            let name = label
+                .clone()
+                .unwrap_or_else(|| aggregate.name().to_string());
+            columns.push(match aggregate {
+                Aggregate::Count => (name, Some(DataType::Integer)),
+                Aggregate::Max | Aggregate::Min => (name, expr.infer_type_with(&types, mode)?),
+            });
+        }
+        Ok(ResultSet::Query {
+            columns,
+            rows: groups
+                .into_iter()
+                .map(|(mut key, values)| {
+                    key.extend(values);
+                    key
+                })
+                .collect(),
+        })
+    }
+}
+
+pub struct IndexExtreme {
+    table: String,
+    column: usize,
+    aggregate: Aggregate,
+    label: Option<String>,
+}
+
+impl IndexExtreme {
+    pub fn new(table: String, column: usize, aggregate: Aggregate, label: Option<String>) -> Self {
+        Self {
+            table,
+            column,
+            aggregate,
+            label,
+        }
+    }
+}
+
+impl<T: Transaction> Executor<T> for IndexExtreme {
+    async fn execute(self, txn: &T) -> SqlResult<ResultSet> {
+        let largest = match self.aggregate {
+            Aggregate::Max => true,
+            Aggregate::Min => false,
+            Aggregate::Count => {
+                return Err(Error::Value(
+                    "COUNT can't be read from an index".to_string(),
+                ))
+            }
+        };
+        match txn.index_extreme(&self.table, self.column, largest).await? {
+            Some(value) => {
+                let schema = txn
+                    .read_table(&self.table)
+                    .await?
+                    .ok_or(Error::NotFound("table", self.table.clone()))?;
+                let column = &schema.columns()[self.column];
+                let name = self
+                    .label
+                    .unwrap_or_else(|| self.aggregate.name().to_string());
+                Ok(ResultSet::Query {
+                    columns: vec![(name, Some(column.datatype.clone()))],
+                    rows: vec![vec![value]],
+                })
+            }
+            // the index can't tell, fall back to aggregating every row
+            None => {
+                let source = Node::Scan {
+                    table: self.table,
+                    alias: None,
+                    filter: None,
+                };
+                let aggregates =
+                    vec![(self.aggregate, Expression::Column(self.column), self.label)];
+                Aggregation::new(source, Vec::new(), aggregates)
+                    .execute(txn)
+                    .await
+            }
+        }
+    }
+}
+
+pub struct Order {
+    source: Node,
+    orders: Vec<(Expression, dql::Order)>,
+}
+
+impl Order {
+    pub fn new(source: Node, orders: Vec<(Expression, dql::Order)>) -> Self {
+        Self { source, orders }
+    }
+}
+
+impl<T: Transaction> Executor<T> for Order {
+    async fn execute(self, txn: &T) -> SqlResult<ResultSet> {
+        let mut budget = MemoryBudget::new("sort", txn);
+        let mut keyed = Vec::new();
+        // the budget is checked as every row is buffered, before the rest of the input is read
+        let columns = for_each_source_row(self.source, txn, |row| {
+            let keys = self
+                .orders
+                .iter()
+                .map(|(expr, _)| expr.evaluate_with(Some(&row), txn.cast_mode()))
+                .collect::<SqlResult<Vec<_>>>()?;
+            budget.reserve(&row)?;
+            budget.reserve(&keys)?;
+            keyed.push((keys, row));
+            Ok(())
+        })
+        .await?;
+        keyed.sort_by(|(a, _), (b, _)| {
+            a.iter()
+                .zip(b)
+                .zip(&self.orders)
+                .map(|((a, b), (_, order))| match order {
+                    dql::Order::Ascending => a.cmp(b),
+                    dql::Order::Descending => b.cmp(a),
+                })
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        Ok(ResultSet::Query {
+            columns,
+            rows: keyed.into_iter().map(|(_, row)| row).collect(),
+        })
+    }
+}
+
+pub struct Offset {
+    source: Node,
+    offset: usize,
+}
+
+impl Offset {
+    pub fn new(source: Node, offset: usize) -> Self {
+        Self { source, offset }
+    }
+}
+
+impl<T: Transaction> Executor<T> for Offset {
+    async fn execute(self, txn: &T) -> SqlResult<ResultSet> {
+        let (columns, rows) = source_rows(self.source, txn).await?;
+        Ok(ResultSet::Query {
+            columns,
+            rows: rows.into_iter().skip(self.offset).collect(),
+        })
+    }
+}
+
+pub struct Limit {
+    source: Node,
+    limit: usize,
+}
+
+impl Limit {
+    pub fn new(source: Node, limit: usize) -> Self {
+        Self { source, limit }
+    }
+}
+
+impl<T: Transaction> Executor<T> for Limit {
+    async fn execute(self, txn: &T) -> SqlResult<ResultSet> {
+        let (columns, rows) = source_rows(self.source, txn).await?;
+        Ok(ResultSet::Query {
+            columns,
+            rows: rows.into_iter().take(self.limit).collect(),
+        })
+    }
+}
+
+pub struct Values {
+    rows: Vec<Vec<Expression>>,
+}
+
+impl Values {
+    pub fn new(rows: Vec<Vec<Expression>>) -> Self {
+        Self { rows }
+    }
+}
+
+impl<T: Transaction> Executor<T> for Values {
+    async fn execute(self, txn: &T) -> SqlResult<ResultSet> {
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|expr| expr.evaluate_with(None, txn.cast_mode()))
+                    .collect()
+            })
+            .collect::<SqlResult<_>>()?;
+        Ok(ResultSet::Query {
+            columns: Vec::new(),
+            rows,
+        })
+    }
+}
+
+pub struct Projection {
+    source: Node,
+    expressions: Vec<(Expression, Option<String>)>,
+}
+
+impl Projection {
+    pub fn new(source: Node, expressions: Vec<(Expression, Option<String>)>) -> Self {
+        Self {
+            source,
+            expressions,
+        }
+    }
+}
+
+impl<T: Transaction> Executor<T> for Projection {
+    async fn execute(self, txn: &T) -> SqlResult<ResultSet> {
+        let (source_columns, rows) = source_rows(self.source, txn).await?;
+        project(&source_columns, rows, &self.expressions, txn.cast_mode())
+    }
+}
+
+/// Evaluate the expressions on every row, unlabeled columns keep the name of the source column
+pub(super) fn project(
+    source_columns: &[OutputColumn],
+    rows: Vec<Row>,
+    expressions: &[(Expression, Option<String>)],
+    mode: CastMode,
+) -> SqlResult<ResultSet> {
+    let types = source_columns
+        .iter()
+        .map(|(_, datatype)| datatype.clone())
+        .collect::<Vec<_>>();
+    let columns = expressions
+        .iter()
+        .map(|(expr, label)| {
+            let name = match (label, expr) {
+                (Some(label), _) => label.clone(),
+                (None, Expression::Column(index)) => source_columns
+                    .get(*index)
+                    .map(|(name, _)| name.clone())
+                    .unwrap_or_else(|| "?column?".to_string()),
+                (None, _) => "?column?".to_string(),
+            };
+            Ok((name, expr.infer_type_with(&types, mode)?))
+        })
+        .collect::<SqlResult<_>>()?;
+    // Reorder values directly when only columns are selected
+    let indices = expressions
+        .iter()
+        .map(|(expr, _)| match expr {
+            Expression::Column(index) => Some(*index),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>();
+    let rows = match indices {
+        Some(indices) => rows
+            .iter()
+            .map(
+                |row| match indices.iter().find(|index| **index >= row.len()) {
+                    Some(index) => Err(Error::NotFound("column", index.to_string())),
+                    None => Ok(row.project(&indices)),
+                },
+            )
+            .collect::<SqlResult<_>>()?,
+        None => rows
+            .iter()
+            .map(|row| {
+                expressions
+                    .iter()
+                    .map(|(expr, _)| expr.evaluate_with(Some(row), mode))
+                    .collect()
+            })
+            .collect::<SqlResult<_>>()?,
+    };
+    Ok(ResultSet::Query { columns, rows })
+}
@@ -0,0 +1,127 @@
+use crate::sql::catalog::{Index, Table, ROWID};
+use crate::sql::execution::{Executor, ResultSet};
+use crate::sql::parser::ddl::AlterAction;
+use crate::sql::transaction::Transaction;
+use crate::sql::{Error, SqlResult};
+
+pub struct CreateTable {
+    schema: Table,
+}
+
+impl CreateTable {
+    pub fn new(schema: Table) -> Self {
+        Self { schema }
+    }
+}
+
+impl<T: Transaction> Executor<T> for CreateTable {
+    async fn execute(self, txn: &T) -> SqlResult<ResultSet> {
+        let name = self.schema.name().to_string();
+        txn.create_table(self.schema).await?;
+        Ok(ResultSet::CreateTable { name })
+    }
+}
+
+pub struct CreateIndex {
+    table: String,
+    index: Index,
+}
+
+impl CreateIndex {
+    pub fn new(table: String, index: Index) -> Self {
+        Self { table, index }
+    }
+}
+
+impl<T: Transaction> Executor<T> for CreateIndex {
+    async fn execute(self, txn: &T) -> SqlResult<ResultSet> {
+        let name = self.index.name.clone();
+        txn.create_index(&self.table, self.index).await?;
+        Ok(ResultSet::CreateIndex { name })
+    }
+}
+
+pub struct DropTable {
+    table: String,
+    if_exists: bool,
+}
+
+impl DropTable {
+    pub fn new(table: String, if_exists: bool) -> Self {
+        Self { table, if_exists }
+    }
+}
+
+impl<T: Transaction> Executor<T> for DropTable {
+    async fn execute(self, txn: &T) -> SqlResult<ResultSet> {
+        if txn.drop_table(&self.table).await?.is_none() && !self.if_exists {
+            return Err(Error::NotFound("table", self.table));
+        }
+        Ok(ResultSet::DropTable { name: self.table })
+    }
+}
+
+pub struct AlterTable {
+    table: String,
+    action: AlterAction,
+}
+
+impl AlterTable {
+    pub fn new(table: String, action: AlterAction) -> Self {
+        Self { table, action }
+    }
+}
+
+impl<T: Transaction> Executor<T> for AlterTable {
+    async fn execute(self, txn: &T) -> SqlResult<ResultSet> {
+        match self.action {
+            AlterAction::RenameColumn { from, to } => {
+                if from == ROWID || to == ROWID {
+                    return Err(Error::Value(format!("Column name {} is reserved", ROWID)));
+                }
+                txn.rename_column(&self.table, &from, &to).await?
+            }
+        }
+        Ok(ResultSet::AlterTable { name: self.table })
+    }
+}
+
+pub struct Reindex {
+    table: String,
+}
+
+impl Reindex {
+    pub fn new(table: String) -> Self {
+        Self { table }
+    }
+}
+
+impl<T: Transaction> Executor<T> for Reindex {
+    async fn execute(self, txn: &T) -> SqlResult<ResultSet> {
+        let count = txn.reindex(&self.table).await?;
+        Ok(ResultSet::Reindex {
+            name: self.table,
+            count,
+        })
+    }
+}
+
+pub struct Analyze {
+    table: String,
+}
+
+impl Analyze {
+    pub fn new(table: String) -> Self {
+        Self { table }
+    }
+}
+
+impl<T: Transaction> Executor<T> for Analyze {
+    async fn execute(self, txn: &T) -> SqlResult<ResultSet> {
+        let stats = txn.analyze(&self.table).await?;
+        Ok(ResultSet::Analyze {
+            name: self.table,
+            stats,
+        })
+    }
+}
@@ -1,11 +1,13 @@
+use crate::storage;
 use thiserror::Error;
 
-mod catalog;
-mod engine;
-mod execution;
+pub mod catalog;
+pub mod csv;
+pub mod engine;
+pub mod execution;
 pub mod parser;
-mod plan;
-mod transaction;
+pub mod plan;
+pub mod transaction;
 pub mod types;
 
 pub type SqlResult<T> = Result<T, Error>;
@@ -19,4 +21,22 @@ pub enum Error {
     ValueNotMatch(&'static str, String),
     #[error("can't {0} {1}")]
     OutOfBound(&'static str, &'static str),
+    #[error("{0} {1} not found")]
+    NotFound(&'static str, String),
+    #[error("transaction {0} is aborted")]
+    Aborted(engine::TransactionId),
+    #[error("transaction {0} is already committed")]
+    Committed(engine::TransactionId),
+    #[error("{0} is not supported")]
+    NotSupported(String),
+    #[error("{0} operator exceeded the memory budget of {1} bytes")]
+    MemoryBudget(&'static str, usize),
+    #[error("{0}")]
+    Value(String),
+    #[error("{0}")]
+    Parse(#[from] parser::Error),
+    #[error("storage error: {0}")]
+    Storage(#[from] storage::Error),
+    #[error("catalog error: {0}")]
+    Catalog(#[from] crate::catalog::error::Error),
 }
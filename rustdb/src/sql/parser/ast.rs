@@ -1,4 +1,4 @@
-use crate::sql::parser::ddl::{CreateTable, DropTable};
+use crate::sql::parser::ddl::{AlterTable, CreateIndex, CreateTable, DropTable};
 use crate::sql::parser::dml::{Delete, Insert, Update};
 use crate::sql::parser::dql::Select;
 use crate::sql::parser::tcl::Begin;
@@ -11,11 +11,17 @@ pub enum Statement {
     Explain(Box<Statement>),
 
     CreateTable(CreateTable),
+    CreateIndex(CreateIndex),
     DropTable(DropTable),
+    AlterTable(AlterTable),
+    /// `REINDEX TABLE name`
+    Reindex(String),
+    /// `ANALYZE name`
+    Analyze(String),
 
     Delete(Delete),
     Insert(Insert),
     Update(Update),
 
-    Select(Select),
+    Select(Box<Select>),
 }
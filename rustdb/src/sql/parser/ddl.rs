@@ -17,17 +17,38 @@ pub struct CreateTable {
     pub columns: Vec<Column>,
 }
 
+/// `CREATE INDEX name ON table (column, ...)`
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreateIndex {
+    pub name: String,
+    pub table: String,
+    pub columns: Vec<String>,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct DropTable {
     pub name: String,
     pub if_exists: bool,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlterTable {
+    pub name: String,
+    pub action: AlterAction,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AlterAction {
+    /// `RENAME COLUMN from TO to`
+    RenameColumn { from: String, to: String },
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Column {
     pub name: String,
     pub datatype: DataType,
     pub primary_key: bool,
+    pub auto_increment: bool,
     pub nullable: Option<bool>,
     pub default: Option<Expression>,
     pub unique: bool,
@@ -53,6 +74,9 @@ impl std::fmt::Display for Column {
         if self.primary_key {
             write!(f, "PRIMARY ")?;
         }
+        if self.auto_increment {
+            write!(f, "AUTOINCREMENT ")?;
+        }
         if self.nullable.unwrap_or_default() {
             write!(f, "NOT NULL ")?;
         }
@@ -95,6 +119,33 @@ pub fn create(i: &str) -> IResult<&str, CreateTable> {
     )(i)
 }
 
+/// Parse `CREATE INDEX name ON table (column, ...);`
+pub fn create_index(i: &str) -> IResult<&str, CreateIndex> {
+    context(
+        "create index",
+        map(
+            tuple((
+                preceded(multispace0, tag_no_case(Keyword::Create.to_str())),
+                preceded(multispace1, tag_no_case(Keyword::Index.to_str())),
+                preceded(multispace1, identifier),
+                preceded(multispace1, tag_no_case(Keyword::On.to_str())),
+                preceded(multispace1, identifier),
+                delimited(
+                    space_open_paren,
+                    separated_list1(space_comma, identifier),
+                    space_close_paren,
+                ),
+                preceded(multispace0, tag(";")),
+            )),
+            |(_, _, name, _, table, columns, _)| CreateIndex {
+                name: name.to_string(),
+                table: table.to_string(),
+                columns: columns.into_iter().map(str::to_string).collect(),
+            },
+        ),
+    )(i)
+}
+
 fn column(i: &str) -> IResult<&str, Column> {
     context(
         "column",
@@ -103,16 +154,28 @@ fn column(i: &str) -> IResult<&str, Column> {
                 preceded(multispace0, identifier),
                 preceded(multispace1, datatype),
                 opt(preceded(multispace1, primary_key)),
+                opt(preceded(multispace1, auto_increment)),
                 opt(preceded(multispace1, nullable)),
                 opt(preceded(multispace1, default)),
                 opt(preceded(multispace1, unique)),
                 opt(preceded(multispace1, index)),
                 opt(preceded(multispace1, references)),
             )),
-            |(name, datatype, primary, null, default, unique, index, references)| Column {
+            |(
+                name,
+                datatype,
+                primary,
+                auto_increment,
+                null,
+                default,
+                unique,
+                index,
+                references,
+            )| Column {
                 name: name.to_string(),
                 datatype,
                 primary_key: primary.is_some(),
+                auto_increment: auto_increment.is_some(),
                 nullable: null,
                 default,
                 unique: unique.is_some(),
@@ -148,10 +211,88 @@ pub fn drop_table(i: &str) -> IResult<&str, DropTable> {
     )(i)
 }
 
+/// Parse `REINDEX TABLE name;`, returns the name of table
+pub fn reindex(i: &str) -> IResult<&str, String> {
+    context(
+        "reindex",
+        map(
+            preceded(
+                tuple((
+                    preceded(multispace0, tag_no_case(Keyword::Reindex.to_str())),
+                    preceded(multispace1, tag_no_case(Keyword::Table.to_str())),
+                )),
+                preceded(multispace1, identifier),
+            ),
+            |name| name.to_string(),
+        ),
+    )(i)
+}
+
+/// Parse `ANALYZE name;`, returns the name of table
+pub fn analyze(i: &str) -> IResult<&str, String> {
+    context(
+        "analyze",
+        map(
+            preceded(
+                preceded(multispace0, tag_no_case(Keyword::Analyze.to_str())),
+                preceded(multispace1, identifier),
+            ),
+            |name| name.to_string(),
+        ),
+    )(i)
+}
+
+/// Parse `ALTER TABLE name RENAME COLUMN from TO to;`
+pub fn alter_table(i: &str) -> IResult<&str, AlterTable> {
+    context(
+        "alter table",
+        map(
+            tuple((
+                preceded(
+                    tuple((
+                        preceded(multispace0, tag_no_case(Keyword::Alter.to_str())),
+                        preceded(multispace1, tag_no_case(Keyword::Table.to_str())),
+                    )),
+                    preceded(multispace1, identifier),
+                ),
+                preceded(multispace1, alter_action),
+                preceded(multispace0, tag(";")),
+            )),
+            |(name, action, _)| AlterTable {
+                name: name.to_string(),
+                action,
+            },
+        ),
+    )(i)
+}
+
+fn alter_action(i: &str) -> IResult<&str, AlterAction> {
+    context(
+        "alter action",
+        map(
+            tuple((
+                tag_no_case(Keyword::Rename.to_str()),
+                preceded(multispace1, tag_no_case(Keyword::Column.to_str())),
+                preceded(multispace1, identifier),
+                preceded(multispace1, tag_no_case(Keyword::To.to_str())),
+                preceded(multispace1, identifier),
+            )),
+            |(_, _, from, _, to)| AlterAction::RenameColumn {
+                from: from.to_string(),
+                to: to.to_string(),
+            },
+        ),
+    )(i)
+}
+
 fn primary_key(i: &str) -> IResult<&str, bool> {
     tag_no_case(Keyword::Primary.to_str())(i).map(|(remaining, _primary)| (remaining, true))
 }
 
+fn auto_increment(i: &str) -> IResult<&str, bool> {
+    tag_no_case(Keyword::Autoincrement.to_str())(i).map(|(remaining, _)| (remaining, true))
+}
+
 fn nullable(i: &str) -> IResult<&str, bool> {
     tuple((
         tag_no_case(Keyword::Not.to_str()),
@@ -183,11 +324,14 @@ fn references(i: &str) -> IResult<&str, String> {
         .map(|(remaining, references)| (remaining, references.1.to_string()))
 }
 
+/// Parse a datatype by its name or one of the aliases `INT`, `VARCHAR`, `TEXT`, `REAL`,
+/// `DOUBLE PRECISION` and `BOOL`. A longer name is tried before its prefixes
 pub fn datatype(i: &str) -> IResult<&str, DataType> {
     alt((
         map(tag_no_case(DataType::Boolean.as_str()), |_| {
             DataType::Boolean
         }),
+        map(tag_no_case("BOOL"), |_| DataType::Boolean),
         map(tag_no_case(DataType::Tinyint.as_str()), |_| {
             DataType::Tinyint
         }),
@@ -197,10 +341,29 @@ pub fn datatype(i: &str) -> IResult<&str, DataType> {
         map(tag_no_case(DataType::Integer.as_str()), |_| {
             DataType::Integer
         }),
+        map(tag_no_case("INT"), |_| DataType::Integer),
         map(tag_no_case(DataType::Bigint.as_str()), |_| DataType::Bigint),
         map(tag_no_case(DataType::Float.as_str()), |_| DataType::Float),
+        map(
+            tuple((
+                tag_no_case(DataType::Double.as_str()),
+                multispace1,
+                tag_no_case("PRECISION"),
+            )),
+            |_| DataType::Double,
+        ),
         map(tag_no_case(DataType::Double.as_str()), |_| DataType::Double),
+        map(tag_no_case("REAL"), |_| DataType::Double),
         map(tag_no_case(DataType::String.as_str()), |_| DataType::String),
+        map(tag_no_case("VARCHAR"), |_| DataType::String),
+        map(tag_no_case("TEXT"), |_| DataType::String),
+        map(tag_no_case(DataType::Decimal.as_str()), |_| {
+            DataType::Decimal
+        }),
+        map(tag_no_case("NUMERIC"), |_| DataType::Decimal),
+        map(tag_no_case(DataType::Timestamp.as_str()), |_| {
+            DataType::Timestamp
+        }),
     ))(i)
 }
 
@@ -218,7 +381,9 @@ pub(crate) fn space_close_paren(i: &str) -> IResult<&str, &str> {
 
 #[cfg(test)]
 mod tests {
-    use crate::sql::parser::ddl::{create, Column, CreateTable, DropTable};
+    use crate::sql::parser::ddl::{
+        alter_table, create, AlterAction, AlterTable, Column, CreateIndex, CreateTable, DropTable,
+    };
     use crate::sql::parser::expression::{Expression, Literal};
     use crate::sql::types::DataType;
 
@@ -226,7 +391,7 @@ mod tests {
 
     #[test]
     fn column() {
-        let input = " EmployeeID INTEGER PRIMARY NOT NULL DEFAULT 1 UNIQUE INDEX";
+        let input = " EmployeeID INTEGER PRIMARY AUTOINCREMENT NOT NULL DEFAULT 1 UNIQUE INDEX";
         let column = super::column(input).finish().unwrap().1;
         assert_eq!(
             column,
@@ -234,6 +399,7 @@ mod tests {
                 name: "EmployeeID".to_string(),
                 datatype: DataType::Integer,
                 primary_key: true,
+                auto_increment: true,
                 nullable: Some(true),
                 default: Some(Expression::Literal(Literal::Tinyint(1))),
                 unique: true,
@@ -255,6 +421,7 @@ mod tests {
                         name: "EmployeeID".to_string(),
                         datatype: DataType::Integer,
                         primary_key: true,
+                        auto_increment: false,
                         nullable: None,
                         default: None,
                         unique: false,
@@ -265,6 +432,7 @@ mod tests {
                         name: "FirstName".to_string(),
                         datatype: DataType::String,
                         primary_key: false,
+                        auto_increment: false,
                         nullable: None,
                         default: None,
                         unique: false,
@@ -275,6 +443,7 @@ mod tests {
                         name: "LastName".to_string(),
                         datatype: DataType::String,
                         primary_key: false,
+                        auto_increment: false,
                         nullable: None,
                         default: None,
                         unique: false,
@@ -285,6 +454,7 @@ mod tests {
                         name: "Department".to_string(),
                         datatype: DataType::String,
                         primary_key: false,
+                        auto_increment: false,
                         nullable: None,
                         default: None,
                         unique: false,
@@ -295,6 +465,7 @@ mod tests {
                         name: "Salary".to_string(),
                         datatype: DataType::Double,
                         primary_key: false,
+                        auto_increment: false,
                         nullable: Some(true),
                         default: Some(Expression::Literal(Literal::Float(1.0))),
                         unique: false,
@@ -306,6 +477,41 @@ mod tests {
         )
     }
 
+    #[test]
+    fn datatype_aliases() {
+        let table =
+            create("CREATE TABLE t(a INT, b VARCHAR, c BOOL, d REAL, e text, f double precision, g timestamp);")
+                .unwrap()
+                .1;
+        assert_eq!(
+            table
+                .columns
+                .into_iter()
+                .map(|column| column.datatype)
+                .collect::<Vec<_>>(),
+            vec![
+                DataType::Integer,
+                DataType::String,
+                DataType::Boolean,
+                DataType::Double,
+                DataType::String,
+                DataType::Double,
+                DataType::Timestamp
+            ]
+        );
+        for (name, datatype) in [
+            ("int", DataType::Integer),
+            ("VarChar", DataType::String),
+            ("TEXT", DataType::String),
+            ("real", DataType::Double),
+            ("DOUBLE PRECISION", DataType::Double),
+            ("bool", DataType::Boolean),
+            ("numeric", DataType::Decimal),
+        ] {
+            assert_eq!(name.parse::<DataType>().unwrap(), datatype);
+        }
+    }
+
     #[test]
     fn drop_table() {
         assert_eq!(
@@ -316,4 +522,55 @@ mod tests {
             }
         )
     }
+
+    #[test]
+    fn reindex_analyze() {
+        assert_eq!(
+            super::reindex("REINDEX TABLE user;").unwrap().1,
+            "user".to_string()
+        );
+        assert!(super::reindex("REINDEX user;").is_err());
+        assert_eq!(
+            super::analyze(" analyze user;").unwrap().1,
+            "user".to_string()
+        );
+        assert!(super::analyze("ANALYZE;").is_err());
+        assert_eq!(
+            crate::sql::parser::parse("ANALYZE user;").unwrap(),
+            crate::sql::parser::ast::Statement::Analyze("user".to_string())
+        );
+    }
+
+    #[test]
+    fn create_index() {
+        assert_eq!(
+            super::create_index("CREATE INDEX idx ON user (name, age);")
+                .unwrap()
+                .1,
+            CreateIndex {
+                name: "idx".to_string(),
+                table: "user".to_string(),
+                columns: vec!["name".to_string(), "age".to_string()],
+            }
+        );
+        assert!(super::create_index("CREATE INDEX idx ON user ();").is_err());
+        assert!(super::create_index("CREATE INDEX ON user (name);").is_err());
+    }
+
+    #[test]
+    fn rename_column() {
+        assert_eq!(
+            alter_table("ALTER TABLE user RENAME COLUMN name TO nickname;")
+                .unwrap()
+                .1,
+            AlterTable {
+                name: "user".to_string(),
+                action: AlterAction::RenameColumn {
+                    from: "name".to_string(),
+                    to: "nickname".to_string(),
+                },
+            }
+        );
+        assert!(alter_table("ALTER TABLE user RENAME COLUMN name;").is_err());
+    }
 }
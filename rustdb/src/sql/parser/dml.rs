@@ -1,6 +1,8 @@
+use crate::sql::parser::dql::{query, returning, Select, SelectItem};
 use crate::sql::parser::expression::{expression, Expression};
 use crate::sql::parser::keyword::Keyword;
 use crate::sql::parser::{ast, identifier, IResult};
+use nom::branch::alt;
 use nom::bytes::complete::{tag, tag_no_case};
 use nom::character::complete::{multispace0, multispace1};
 use nom::combinator::{map, not, opt, peek};
@@ -19,14 +21,23 @@ pub struct Delete {
 pub struct Insert {
     pub table: String,
     pub columns: Option<Vec<String>>,
-    pub values: Vec<Vec<Expression>>,
+    pub source: InsertSource,
+}
+
+/// The rows to insert
+#[derive(Debug, Clone, PartialEq)]
+pub enum InsertSource {
+    Values(Vec<Vec<Expression>>),
+    Select(Box<Select>),
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Update {
-    table: String,
-    set: BTreeMap<String, Expression>,
-    r#where: Option<Expression>,
+    pub table: String,
+    pub set: BTreeMap<String, Expression>,
+    pub r#where: Option<Expression>,
+    /// The columns of the updated rows to return
+    pub returning: Option<SelectItem>,
 }
 
 pub fn insert(i: &str) -> IResult<&str, Insert> {
@@ -43,12 +54,15 @@ pub fn insert(i: &str) -> IResult<&str, Insert> {
                         preceded(multispace1, identifier),
                     ),
                     opt(columns),
-                    values,
+                    alt((
+                        map(values, InsertSource::Values),
+                        map(query, |select| InsertSource::Select(Box::new(select))),
+                    )),
                 )),
-                |(name, columns, values)| Insert {
+                |(name, columns, source)| Insert {
                     table: name.to_string(),
                     columns,
-                    values,
+                    source,
                 },
             ),
             preceded(multispace0, tag(";")),
@@ -93,11 +107,13 @@ pub fn update(i: &str) -> IResult<&str, Update> {
                     ),
                     set,
                     opt(r#where),
+                    opt(returning),
                 )),
-                |(name, set, r#where)| Update {
+                |(name, set, r#where, returning)| Update {
                     table: name.to_string(),
                     set,
                     r#where,
+                    returning,
                 },
             ),
             preceded(multispace0, tag(";")),
@@ -228,7 +244,7 @@ mod tests {
                     "name".to_string(),
                     "grade".to_string()
                 ]),
-                values: vec![
+                source: InsertSource::Values(vec![
                     vec![
                         Expression::Literal(Literal::Tinyint(1)),
                         Expression::Literal(Literal::String("John".to_string())),
@@ -239,11 +255,33 @@ mod tests {
                         Expression::Literal(Literal::String("Mike".to_string())),
                         Expression::Literal(Literal::Float(3.8))
                     ]
-                ],
+                ]),
             }
         );
         let sql = "INSERT INTO user values( 1, 'John',3.0),(2, 'Mike',3.8) ;";
         assert!(super::insert(sql).unwrap().1.columns.is_none());
+        let sql = "INSERT INTO archive (id, name) SELECT id, name FROM user WHERE grade > 3.0;";
+        let insert = super::insert(sql).unwrap().1;
+        assert_eq!(
+            insert.columns,
+            Some(vec!["id".to_string(), "name".to_string()])
+        );
+        let InsertSource::Select(select) = insert.source else {
+            panic!("expected a select source");
+        };
+        assert_eq!(
+            select.select,
+            SelectItem::Part(vec![
+                (Expression::Field(None, "id".to_string()), None),
+                (Expression::Field(None, "name".to_string()), None),
+            ])
+        );
+        assert!(select.r#where.is_some());
+        let sql = "INSERT INTO archive SELECT * FROM user;";
+        assert!(matches!(
+            super::insert(sql).unwrap().1.source,
+            InsertSource::Select(_)
+        ));
     }
 
     #[test]
@@ -285,9 +323,29 @@ mod tests {
                     Box::new(Expression::Field(None, "id".to_string())),
                     Box::new(Expression::Literal(Literal::Tinyint(2)))
                 ))),
+                returning: None,
             }
         );
         let sql = "UPDATE user set grade = grade + 1.0, name = 'John' ;";
         assert!(super::update(sql).unwrap().1.r#where.is_none());
+        let sql = "UPDATE user set grade = 1.0 where id = 2 RETURNING * ;";
+        assert_eq!(
+            super::update(sql).unwrap().1.returning,
+            Some(SelectItem::All)
+        );
+        let sql = "UPDATE user set grade = 1.0 RETURNING id, grade + 1.0 as next ;";
+        assert_eq!(
+            super::update(sql).unwrap().1.returning,
+            Some(SelectItem::Part(vec![
+                (Expression::Field(None, "id".to_string()), None),
+                (
+                    Expression::Operation(Operation::Add(
+                        Box::new(Expression::Field(None, "grade".to_string())),
+                        Box::new(Expression::Literal(Literal::Float(1.0))),
+                    )),
+                    Some("next".to_string())
+                ),
+            ]))
+        );
     }
 }
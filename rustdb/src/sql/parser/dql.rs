@@ -26,6 +26,8 @@ pub struct Select {
 #[derive(Clone, Debug, PartialEq)]
 pub enum SelectItem {
     All,
+    /// `relation.*`, all the columns of one relation
+    Qualified(String),
     Part(Vec<(Expression, Option<String>)>),
 }
 
@@ -64,38 +66,41 @@ pub enum Order {
 }
 
 pub fn select(i: &str) -> IResult<&str, Select> {
+    context("select", terminated(query, preceded(multispace0, tag(";"))))(i)
+}
+
+/// Parse a select statement without the terminating `;`, so it can be nested in other statements
+pub fn query(i: &str) -> IResult<&str, Select> {
     context(
-        "select",
-        terminated(
-            map(
-                tuple((
-                    preceded(multispace0, select_item),
-                    preceded(multispace1, from),
-                    opt(preceded(multispace1, r#where)),
-                    opt(preceded(multispace1, group_by)),
-                    opt(preceded(multispace1, having)),
-                    opt(preceded(multispace1, order)),
-                    opt(preceded(multispace1, offset)),
-                    opt(preceded(multispace1, limit)),
-                )),
-                |(select_item, from, r#where, group_by, having, order, offset, limit)| Select {
-                    select: select_item,
-                    from,
-                    r#where,
-                    group_by,
-                    having,
-                    order,
-                    offset,
-                    limit,
-                },
-            ),
-            preceded(multispace0, tag(";")),
+        "query",
+        map(
+            tuple((
+                preceded(multispace0, select_item),
+                opt(preceded(multispace1, from)),
+                opt(preceded(multispace1, r#where)),
+                opt(preceded(multispace1, group_by)),
+                opt(preceded(multispace1, having)),
+                opt(preceded(multispace1, order)),
+                opt(preceded(multispace1, offset)),
+                opt(preceded(multispace1, limit)),
+            )),
+            |(select_item, from, r#where, group_by, having, order, offset, limit)| Select {
+                select: select_item,
+                from: from.unwrap_or_default(),
+                r#where,
+                group_by,
+                having,
+                order,
+                offset,
+                limit,
+            },
         ),
     )(i)
 }
 
 /// Parse `Select field1 as f1, field2 as f2`
 /// Parse `Select *`
+/// Parse `Select relation.*`
 fn select_item(i: &str) -> IResult<&str, SelectItem> {
     context(
         "select item",
@@ -105,6 +110,9 @@ fn select_item(i: &str) -> IResult<&str, SelectItem> {
                 multispace1,
                 alt((
                     map(tag("*"), |_| SelectItem::All),
+                    map(terminated(identifier, tag(".*")), |relation| {
+                        SelectItem::Qualified(relation.to_string())
+                    }),
                     map(
                         separated_list1(
                             delimited(multispace0, tag(","), multispace0),
@@ -118,6 +126,24 @@ fn select_item(i: &str) -> IResult<&str, SelectItem> {
     )(i)
 }
 
+/// Parse `RETURNING field1 as f1, field2 as f2`
+/// Parse `RETURNING *`
+pub fn returning(i: &str) -> IResult<&str, SelectItem> {
+    context(
+        "returning",
+        preceded(
+            tuple((multispace0, tag_no_case(Keyword::Returning.to_str()))),
+            preceded(
+                multispace1,
+                alt((
+                    map(tag("*"), |_| SelectItem::All),
+                    map(select_clauses, SelectItem::Part),
+                )),
+            ),
+        ),
+    )(i)
+}
+
 /// Parse `field1 as f1, field2 as f2`
 fn select_clauses(i: &str) -> IResult<&str, Vec<(Expression, Option<String>)>> {
     context(
@@ -160,20 +186,8 @@ fn from(i: &str) -> IResult<&str, Vec<FromItem>> {
 
 fn from_item(i: &str) -> IResult<&str, FromItem> {
     let (i, table) = context("from item", preceded(multispace0, from_table))(i)?;
-    let item = context(
-        "from item",
-        map(
-            opt(preceded(
-                multispace1,
-                from_join(FromItem::Table(table.clone())),
-            )),
-            |join| match join {
-                None => FromItem::Table(table.clone()),
-                Some(join) => join,
-            },
-        ),
-    )(i)?;
-    Ok(item)
+    // `from_join` returns the table itself without consuming any input when there's no join
+    context("from item", from_join(FromItem::Table(table)))(i)
 }
 
 fn from_table(i: &str) -> IResult<&str, FromTable> {
@@ -359,7 +373,7 @@ where a.attendance>=75;";
         let parsed = super::select(sql).unwrap().1;
         let select_item = SelectItem::Part(vec![
             (
-                Expression::Field(Some("id".into()), "s".into()),
+                Expression::Field(Some("s".into()), "id".into()),
                 Some("i".to_string()),
             ),
             (Expression::Field(None, "name".into()), None),
@@ -383,8 +397,8 @@ where a.attendance>=75;";
                     })),
                     r#type: JoinType::Inner,
                     predicate: Some(Expression::Operation(Operation::Equal(
-                        Box::new(Expression::Field(Some("id".to_string()), "s".to_string())),
-                        Box::new(Expression::Field(Some("id".to_string()), "m".to_string())),
+                        Box::new(Expression::Field(Some("s".to_string()), "id".to_string())),
+                        Box::new(Expression::Field(Some("m".to_string()), "id".to_string())),
                     ))),
                 })),
                 right: Box::new(FromItem::Table(FromTable {
@@ -397,8 +411,8 @@ where a.attendance>=75;";
         ];
         let r#where = Some(Expression::Operation(Operation::GreaterThanOrEqual(
             Box::new(Expression::Field(
-                Some("attendance".to_string()),
-                "a".to_string(),
+                Some("a".to_string()),
+                "attendance".to_string(),
             )),
             Box::new(Expression::Literal(Literal::Tinyint(75))),
         )));
@@ -406,4 +420,38 @@ where a.attendance>=75;";
         assert_eq!(parsed.from, from);
         assert_eq!(parsed.r#where, r#where);
     }
+
+    #[test]
+    fn select_without_from() {
+        let parsed = super::select("SELECT 1 + 1;").unwrap().1;
+        assert_eq!(
+            parsed.select,
+            SelectItem::Part(vec![(
+                Expression::Operation(Operation::Add(
+                    Box::new(Expression::Literal(Literal::Tinyint(1))),
+                    Box::new(Expression::Literal(Literal::Tinyint(1))),
+                )),
+                None
+            )])
+        );
+        assert!(parsed.from.is_empty());
+        assert!(parsed.r#where.is_none());
+    }
+
+    #[test]
+    fn qualified_wildcard() {
+        let parsed = super::select("SELECT a.* FROM a JOIN b ON a.id = b.id;")
+            .unwrap()
+            .1;
+        assert_eq!(parsed.select, SelectItem::Qualified("a".to_string()));
+        assert!(matches!(parsed.from.as_slice(), [FromItem::Join(_)]));
+        let parsed = super::select("SELECT a.id FROM a;").unwrap().1;
+        assert_eq!(
+            parsed.select,
+            SelectItem::Part(vec![(
+                Expression::Field(Some("a".into()), "id".into()),
+                None
+            )])
+        );
+    }
 }
@@ -1,10 +1,13 @@
+use crate::sql::parser::ddl::{datatype, space_comma, space_open_paren};
 use crate::sql::parser::keyword::Keyword;
 use crate::sql::parser::{identifier, IResult};
+use crate::sql::types::{timestamp, DataType};
 use nom::branch::alt;
-use nom::bytes::complete::{tag, tag_no_case};
-use nom::character::complete::{alphanumeric1, i128, i16, i32, i64, multispace0, multispace1};
-use nom::combinator::{map, not, opt, peek};
+use nom::bytes::complete::{is_not, tag, tag_no_case};
+use nom::character::complete::{i128, i16, i64, i8, multispace0, multispace1};
+use nom::combinator::{map, map_res, not, opt, peek};
 use nom::error::context;
+use nom::multi::{many1, separated_list0};
 use nom::number::complete::{double, float};
 use nom::sequence::{delimited, preceded, terminated, tuple};
 use std::fmt::{Debug, Formatter};
@@ -14,6 +17,15 @@ pub enum Expression {
     Literal(Literal),
     Field(Option<String>, String),
     Column(usize),
+    /// A scalar function call with its name and arguments
+    Function(String, Vec<Expression>),
+    /// `CASE WHEN condition THEN value ... [ELSE default] END`
+    Case {
+        branches: Vec<(Expression, Expression)>,
+        default: Option<Box<Expression>>,
+    },
+    /// `CAST(expression AS datatype)`
+    Cast(Box<Expression>, DataType),
     Operation(Operation),
 }
 
@@ -36,6 +48,8 @@ pub enum Literal {
     Float(f32),
     Double(f64),
     String(String),
+    /// `TIMESTAMP '2024-01-01 00:00:00'`, the microseconds since `1970-01-01 00:00:00`
+    Timestamp(i64),
 }
 
 impl std::fmt::Display for Literal {
@@ -50,6 +64,7 @@ impl std::fmt::Display for Literal {
             Literal::String(s) => write!(f, "{}", s),
             Literal::Null => write!(f, "NULL"),
             Literal::Boolean(bool) => write!(f, "{}", bool),
+            Literal::Timestamp(micros) => write!(f, "TIMESTAMP '{}'", timestamp::format(*micros)),
         }
     }
 }
@@ -62,9 +77,13 @@ pub enum Operation {
     Or(Box<Expression>, Box<Expression>),
 
     // Comparison operators
+    /// `lhs BETWEEN low AND high`
+    Between(Box<Expression>, Box<Expression>, Box<Expression>),
     Equal(Box<Expression>, Box<Expression>),
     GreaterThan(Box<Expression>, Box<Expression>),
     GreaterThanOrEqual(Box<Expression>, Box<Expression>),
+    IsDistinctFrom(Box<Expression>, Box<Expression>),
+    IsNotDistinctFrom(Box<Expression>, Box<Expression>),
     IsNull(Box<Expression>),
     LessThan(Box<Expression>, Box<Expression>),
     LessThanOrEqual(Box<Expression>, Box<Expression>),
@@ -135,11 +154,15 @@ impl Operator for PrefixOperator {
 enum InfixOperator {
     Add,
     And,
+    /// Takes two operands after the lhs, so it's built by [`expression`] instead of `build`
+    Between,
     Divide,
     Equal,
     Exponentiate,
     GreaterThan,
     GreaterThanOrEqual,
+    IsDistinctFrom,
+    IsNotDistinctFrom,
     LessThan,
     LessThanOrEqual,
     Like,
@@ -162,9 +185,12 @@ impl InfixOperator {
             InfixOperator::Subtract => Operation::Subtract(lhs, rhs),
             InfixOperator::Modulo => Operation::Modulo(lhs, rhs),
             InfixOperator::And => Operation::And(lhs, rhs),
+            InfixOperator::Between => unreachable!("BETWEEN is built with both of its bounds"),
             InfixOperator::Equal => Operation::Equal(lhs, rhs),
             InfixOperator::GreaterThan => Operation::GreaterThan(lhs, rhs),
             InfixOperator::GreaterThanOrEqual => Operation::GreaterThanOrEqual(lhs, rhs),
+            InfixOperator::IsDistinctFrom => Operation::IsDistinctFrom(lhs, rhs),
+            InfixOperator::IsNotDistinctFrom => Operation::IsNotDistinctFrom(lhs, rhs),
             InfixOperator::LessThan => Operation::LessThan(lhs, rhs),
             InfixOperator::LessThanOrEqual => Operation::LessThanOrEqual(lhs, rhs),
             InfixOperator::Like => Operation::Like(lhs, rhs),
@@ -187,7 +213,12 @@ impl Operator for InfixOperator {
         match self {
             Self::Or => 1,
             Self::And => 2,
-            Self::Equal | Self::NotEqual | Self::Like => 3,
+            Self::Between
+            | Self::Equal
+            | Self::NotEqual
+            | Self::Like
+            | Self::IsDistinctFrom
+            | Self::IsNotDistinctFrom => 3,
             Self::GreaterThan
             | Self::GreaterThanOrEqual
             | Self::LessThan
@@ -247,7 +278,17 @@ pub fn expression(prec_min: u8) -> impl FnMut(&str) -> IResult<&str, Expression>
         let mut rhs = Expression::default();
         loop {
             (i, infix) = min_prec_infix_operator(prec_min)(i)?;
-            if let Some(infix) = infix {
+            if let Some(InfixOperator::Between) = infix {
+                // the lower bound ends before `AND`, which separates it from the upper bound
+                let (remaining, low) = expression(InfixOperator::And.prec() + 1)(i)?;
+                let (remaining, _) = preceded(
+                    multispace0,
+                    terminated(tag_no_case(Keyword::And.to_str()), multispace1),
+                )(remaining)?;
+                let between = InfixOperator::Between;
+                (i, rhs) = expression(between.prec() + between.assoc())(remaining)?;
+                lhs = Operation::Between(Box::new(lhs), Box::new(low), Box::new(rhs)).into();
+            } else if let Some(infix) = infix {
                 (i, rhs) = expression(infix.prec() + infix.assoc())(i)?;
                 lhs = infix.build(lhs, rhs);
             } else {
@@ -264,15 +305,28 @@ fn expression_atom(i: &str) -> IResult<&str, Expression> {
         preceded(
             multispace0,
             alt((
+                case,
+                cast,
                 map(literal, Expression::Literal),
                 delimited(tag("("), expression(0), tag(")")),
+                map(
+                    tuple((
+                        identifier,
+                        delimited(
+                            space_open_paren,
+                            separated_list0(space_comma, expression(0)),
+                            preceded(multispace0, tag(")")),
+                        ),
+                    )),
+                    |(name, args)| Expression::Function(name.to_string(), args),
+                ),
                 map(
                     tuple((identifier, opt(preceded(tag("."), identifier)))),
-                    |(field, relation)| {
-                        Expression::Field(
-                            relation.map(|relation| relation.to_string()),
-                            field.to_string(),
-                        )
+                    |(first, second)| match second {
+                        Some(field) => {
+                            Expression::Field(Some(first.to_string()), field.to_string())
+                        }
+                        None => Expression::Field(None, first.to_string()),
                     },
                 ),
             )),
@@ -280,17 +334,64 @@ fn expression_atom(i: &str) -> IResult<&str, Expression> {
     )(i)
 }
 
+fn case(i: &str) -> IResult<&str, Expression> {
+    let keyword = |keyword: Keyword| {
+        move |i| {
+            preceded(
+                multispace0,
+                terminated(tag_no_case(keyword.to_str()), multispace1),
+            )(i)
+        }
+    };
+    context(
+        "case",
+        map(
+            tuple((
+                keyword(Keyword::Case),
+                many1(tuple((
+                    preceded(keyword(Keyword::When), expression(0)),
+                    preceded(keyword(Keyword::Then), expression(0)),
+                ))),
+                opt(preceded(keyword(Keyword::Else), expression(0))),
+                preceded(multispace0, tag_no_case(Keyword::End.to_str())),
+            )),
+            |(_, branches, default, _)| Expression::Case {
+                branches,
+                default: default.map(Box::new),
+            },
+        ),
+    )(i)
+}
+
+fn cast(i: &str) -> IResult<&str, Expression> {
+    context(
+        "cast",
+        map(
+            tuple((
+                tag_no_case(Keyword::Cast.to_str()),
+                space_open_paren,
+                expression(0),
+                delimited(multispace0, tag_no_case(Keyword::As.to_str()), multispace1),
+                datatype,
+                preceded(multispace0, tag(")")),
+            )),
+            |(_, _, expr, _, datatype, _)| Expression::Cast(Box::new(expr), datatype),
+        ),
+    )(i)
+}
+
 fn literal(i: &str) -> IResult<&str, Literal> {
     context(
         "literal",
         alt((
+            // an integer is parsed into the narrowest type which can hold it
             map(
-                tuple((i16, not(alt((tag("."), tag_no_case("e")))))),
-                |(integer, _)| Literal::Tinyint(integer),
+                tuple((i8, not(alt((tag("."), tag_no_case("e")))))),
+                |(integer, _)| Literal::Tinyint(integer as i16),
             ),
             map(
-                tuple((i32, not(alt((tag("."), tag_no_case("e")))))),
-                |(integer, _)| Literal::Smallint(integer),
+                tuple((i16, not(alt((tag("."), tag_no_case("e")))))),
+                |(integer, _)| Literal::Smallint(integer as i32),
             ),
             map(
                 tuple((i64, not(alt((tag("."), tag_no_case("e")))))),
@@ -302,9 +403,16 @@ fn literal(i: &str) -> IResult<&str, Literal> {
             ),
             map(float, Literal::Float),
             map(double, Literal::Double),
-            map(delimited(tag("'"), alphanumeric1, tag("'")), |s: &str| {
+            map(delimited(tag("'"), is_not("'"), tag("'")), |s: &str| {
                 Literal::String(s.to_string())
             }),
+            map_res(
+                preceded(
+                    tuple((tag_no_case(DataType::Timestamp.as_str()), multispace0)),
+                    delimited(tag("'"), is_not("'"), tag("'")),
+                ),
+                |s: &str| timestamp::parse(s).map(Literal::Timestamp),
+            ),
             map(tag_no_case(Keyword::Null.to_str()), |_| Literal::Null),
             map(tag_no_case(Keyword::False.to_str()), |_| {
                 Literal::Boolean(false)
@@ -334,15 +442,33 @@ fn pre_operator(i: &str) -> IResult<&str, PrefixOperator> {
 }
 
 fn infix_operator(i: &str) -> IResult<&str, InfixOperator> {
+    let keyword =
+        |keyword: Keyword| move |i| terminated(tag_no_case(keyword.to_str()), multispace1)(i);
     context(
         "infix operator",
         preceded(
             multispace0,
             alt((
+                map(
+                    tuple((
+                        keyword(Keyword::Is),
+                        opt(keyword(Keyword::Not)),
+                        keyword(Keyword::Distinct),
+                        keyword(Keyword::From),
+                    )),
+                    |(_, not, _, _)| match not {
+                        Some(_) => InfixOperator::IsNotDistinctFrom,
+                        None => InfixOperator::IsDistinctFrom,
+                    },
+                ),
                 map(
                     terminated(tag_no_case(Keyword::Like.to_str()), multispace1),
                     |_| InfixOperator::Like,
                 ),
+                map(
+                    terminated(tag_no_case(Keyword::Between.to_str()), multispace1),
+                    |_| InfixOperator::Between,
+                ),
                 map(
                     terminated(tag_no_case(Keyword::And.to_str()), multispace1),
                     |_| InfixOperator::And,
@@ -352,8 +478,8 @@ fn infix_operator(i: &str) -> IResult<&str, InfixOperator> {
                     |_| InfixOperator::Or,
                 ),
                 map(tag_no_case(">="), |_| InfixOperator::GreaterThanOrEqual),
-                map(tag_no_case("<"), |_| InfixOperator::LessThan),
                 map(tag_no_case("<="), |_| InfixOperator::LessThanOrEqual),
+                map(tag_no_case("<"), |_| InfixOperator::LessThan),
                 map(tag_no_case("!="), |_| InfixOperator::NotEqual),
                 map(tag_no_case("+"), |_| InfixOperator::Add),
                 map(tag_no_case("/"), |_| InfixOperator::Divide),
@@ -439,6 +565,18 @@ mod tests {
     fn literal() {
         assert_eq!(super::literal("1.0").unwrap().1, Literal::Float(1.0));
         assert_eq!(super::literal("1").unwrap().1, Literal::Tinyint(1));
+        assert_eq!(super::literal("-128").unwrap().1, Literal::Tinyint(-128));
+        assert_eq!(super::literal("128").unwrap().1, Literal::Smallint(128));
+        assert_eq!(super::literal("40000").unwrap().1, Literal::Integer(40000));
+        assert_eq!(
+            super::literal("'Jo%'").unwrap().1,
+            Literal::String("Jo%".to_string())
+        );
+        assert_eq!(
+            super::literal("timestamp '1970-01-01 00:00:01'").unwrap().1,
+            Literal::Timestamp(1_000_000)
+        );
+        assert!(super::literal("TIMESTAMP '1970-13-01'").is_err());
     }
     #[test]
     fn arith_expression() {
@@ -479,4 +617,149 @@ mod tests {
             expression("(a.user = 'John') and (b.id = 2)"),
         );
     }
+
+    #[test]
+    fn case() {
+        let field = |name: &str| Box::new(Expression::Field(None, name.to_string()));
+        let literal = |i: i16| Box::new(Expression::Literal(Literal::Tinyint(i)));
+        assert_eq!(
+            expression("CASE WHEN a = 1 THEN b WHEN a = 2 THEN 3 ELSE 4 END + 1")
+                .unwrap()
+                .1,
+            Expression::Operation(Operation::Add(
+                Box::new(Expression::Case {
+                    branches: vec![
+                        (
+                            Expression::Operation(Operation::Equal(field("a"), literal(1))),
+                            *field("b"),
+                        ),
+                        (
+                            Expression::Operation(Operation::Equal(field("a"), literal(2))),
+                            *literal(3),
+                        ),
+                    ],
+                    default: Some(literal(4)),
+                }),
+                literal(1),
+            ))
+        );
+        assert_eq!(
+            expression("case when a then 1 end").unwrap().1,
+            Expression::Case {
+                branches: vec![(*field("a"), *literal(1))],
+                default: None,
+            }
+        );
+        assert!(super::case("CASE ELSE 1 END").is_err());
+    }
+
+    #[test]
+    fn cast() {
+        assert_eq!(
+            expression("CAST(a + 1 AS bigint) * 2").unwrap().1,
+            Expression::Operation(Operation::Multiply(
+                Box::new(Expression::Cast(
+                    Box::new(Expression::Operation(Operation::Add(
+                        Box::new(Expression::Field(None, "a".to_string())),
+                        Box::new(Expression::Literal(Literal::Tinyint(1))),
+                    ))),
+                    DataType::Bigint,
+                )),
+                Box::new(Expression::Literal(Literal::Tinyint(2))),
+            ))
+        );
+        assert_eq!(
+            expression("cast ( '1' as DOUBLE PRECISION )").unwrap().1,
+            Expression::Cast(
+                Box::new(Expression::Literal(Literal::String("1".to_string()))),
+                DataType::Double,
+            )
+        );
+        assert!(super::cast("CAST(1 AS BLOB)").is_err());
+        assert!(super::cast("CAST(1)").is_err());
+    }
+
+    #[test]
+    fn function() {
+        assert_eq!(
+            expression("coalesce(name, 'John') = upper( 'john' )")
+                .unwrap()
+                .1,
+            Expression::Operation(Operation::Equal(
+                Box::new(Expression::Function(
+                    "coalesce".to_string(),
+                    vec![
+                        Expression::Field(None, "name".to_string()),
+                        Expression::Literal(Literal::String("John".to_string())),
+                    ]
+                )),
+                Box::new(Expression::Function(
+                    "upper".to_string(),
+                    vec![Expression::Literal(Literal::String("john".to_string()))]
+                )),
+            ))
+        );
+        assert_eq!(
+            expression("length()").unwrap().1,
+            Expression::Function("length".to_string(), vec![])
+        );
+    }
+
+    #[test]
+    fn distinct_from() {
+        let field = |name: &str| Box::new(Expression::Field(None, name.to_string()));
+        assert_eq!(
+            expression("a IS DISTINCT FROM b AND a is not distinct from NULL")
+                .unwrap()
+                .1,
+            Expression::Operation(Operation::And(
+                Box::new(Expression::Operation(Operation::IsDistinctFrom(
+                    field("a"),
+                    field("b")
+                ))),
+                Box::new(Expression::Operation(Operation::IsNotDistinctFrom(
+                    field("a"),
+                    Box::new(Expression::Literal(Literal::Null))
+                ))),
+            ))
+        );
+    }
+
+    #[test]
+    fn between() {
+        let field = |name: &str| Box::new(Expression::Field(None, name.to_string()));
+        let int = |i: i16| Box::new(Expression::Literal(Literal::Tinyint(i)));
+        assert_eq!(
+            expression("a BETWEEN 1 + 1 AND 5 AND b = 2").unwrap().1,
+            Expression::Operation(Operation::And(
+                Box::new(Expression::Operation(Operation::Between(
+                    field("a"),
+                    Box::new(Expression::Operation(Operation::Add(int(1), int(1)))),
+                    int(5),
+                ))),
+                Box::new(Expression::Operation(Operation::Equal(field("b"), int(2)))),
+            ))
+        );
+        assert!(expression("a BETWEEN 1").is_err());
+    }
+
+    #[test]
+    fn field() {
+        assert_eq!(
+            expression("`a.b`").unwrap().1,
+            Expression::Field(None, "a.b".to_string())
+        );
+        assert_eq!(
+            expression("[a.b]").unwrap().1,
+            Expression::Field(None, "a.b".to_string())
+        );
+        assert_eq!(
+            expression("a.b").unwrap().1,
+            Expression::Field(Some("a".to_string()), "b".to_string())
+        );
+        assert_eq!(
+            expression("t.`a.b`").unwrap().1,
+            Expression::Field(Some("t".to_string()), "a.b".to_string())
+        );
+    }
 }
@@ -1,27 +1,36 @@
-use crate::sql::parser::IResult;
-use nom::branch::alt;
-use nom::bytes::complete::tag_no_case;
-use nom::combinator::map;
+use crate::sql::parser::{is_identifier, IResult};
+use nom::bytes::complete::take_while1;
+use nom::combinator::map_opt;
 use nom::error::context;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Keyword {
+    Alter,
+    Analyze,
     And,
     As,
     Asc,
+    Autoincrement,
     Begin,
+    Between,
     Bool,
     Boolean,
     By,
+    Case,
+    Cast,
     Char,
+    Column,
     Commit,
     Create,
     Cross,
     Default,
     Delete,
     Desc,
+    Distinct,
     Double,
     Drop,
+    Else,
+    End,
     Exists,
     Explain,
     False,
@@ -56,6 +65,9 @@ pub enum Keyword {
     Primary,
     Read,
     References,
+    Reindex,
+    Rename,
+    Returning,
     Right,
     Rollback,
     Select,
@@ -64,13 +76,16 @@ pub enum Keyword {
     System,
     Table,
     Text,
+    Then,
     Time,
+    To,
     Transaction,
     True,
     Unique,
     Update,
     Values,
     Varchar,
+    When,
     Where,
     Write,
 }
@@ -81,20 +96,30 @@ impl Keyword {
         Some(match ident.to_uppercase().as_ref() {
             "AS" => Self::As,
             "ASC" => Self::Asc,
+            "AUTOINCREMENT" => Self::Autoincrement,
+            "ALTER" => Self::Alter,
+            "ANALYZE" => Self::Analyze,
             "AND" => Self::And,
             "BEGIN" => Self::Begin,
+            "BETWEEN" => Self::Between,
             "BOOL" => Self::Bool,
             "BOOLEAN" => Self::Boolean,
             "BY" => Self::By,
+            "CASE" => Self::Case,
+            "CAST" => Self::Cast,
             "CHAR" => Self::Char,
+            "COLUMN" => Self::Column,
             "COMMIT" => Self::Commit,
             "CREATE" => Self::Create,
             "CROSS" => Self::Cross,
             "DEFAULT" => Self::Default,
             "DELETE" => Self::Delete,
             "DESC" => Self::Desc,
+            "DISTINCT" => Self::Distinct,
             "DOUBLE" => Self::Double,
             "DROP" => Self::Drop,
+            "ELSE" => Self::Else,
+            "END" => Self::End,
             "EXISTS" => Self::Exists,
             "EXPLAIN" => Self::Explain,
             "FALSE" => Self::False,
@@ -129,6 +154,9 @@ impl Keyword {
             "PRIMARY" => Self::Primary,
             "READ" => Self::Read,
             "REFERENCES" => Self::References,
+            "REINDEX" => Self::Reindex,
+            "RENAME" => Self::Rename,
+            "RETURNING" => Self::Returning,
             "RIGHT" => Self::Right,
             "ROLLBACK" => Self::Rollback,
             "SELECT" => Self::Select,
@@ -137,13 +165,16 @@ impl Keyword {
             "SYSTEM" => Self::System,
             "TABLE" => Self::Table,
             "TEXT" => Self::Text,
+            "THEN" => Self::Then,
             "TIME" => Self::Time,
+            "TO" => Self::To,
             "TRANSACTION" => Self::Transaction,
             "TRUE" => Self::True,
             "UNIQUE" => Self::Unique,
             "UPDATE" => Self::Update,
             "VALUES" => Self::Values,
             "VARCHAR" => Self::Varchar,
+            "WHEN" => Self::When,
             "WHERE" => Self::Where,
             "WRITE" => Self::Write,
             _ => return None,
@@ -154,20 +185,30 @@ impl Keyword {
         match self {
             Self::As => "AS",
             Self::Asc => "ASC",
+            Self::Autoincrement => "AUTOINCREMENT",
+            Self::Alter => "ALTER",
+            Self::Analyze => "ANALYZE",
             Self::And => "AND",
             Self::Begin => "BEGIN",
+            Self::Between => "BETWEEN",
             Self::Bool => "BOOL",
             Self::Boolean => "BOOLEAN",
             Self::By => "BY",
+            Self::Case => "CASE",
+            Self::Cast => "CAST",
             Self::Char => "CHAR",
+            Self::Column => "COLUMN",
             Self::Commit => "COMMIT",
             Self::Create => "CREATE",
             Self::Cross => "CROSS",
             Self::Default => "DEFAULT",
             Self::Delete => "DELETE",
             Self::Desc => "DESC",
+            Self::Distinct => "DISTINCT",
             Self::Double => "DOUBLE",
             Self::Drop => "DROP",
+            Self::Else => "ELSE",
+            Self::End => "END",
             Self::Exists => "EXISTS",
             Self::Explain => "EXPLAIN",
             Self::False => "FALSE",
@@ -202,6 +243,9 @@ impl Keyword {
             Self::Primary => "PRIMARY",
             Self::Read => "READ",
             Self::References => "REFERENCES",
+            Self::Reindex => "REINDEX",
+            Self::Rename => "RENAME",
+            Self::Returning => "RETURNING",
             Self::Right => "RIGHT",
             Self::Rollback => "ROLLBACK",
             Self::Select => "SELECT",
@@ -210,139 +254,55 @@ impl Keyword {
             Self::System => "SYSTEM",
             Self::Table => "TABLE",
             Self::Text => "TEXT",
+            Self::Then => "THEN",
             Self::Time => "TIME",
+            Self::To => "TO",
             Self::Transaction => "TRANSACTION",
             Self::True => "TRUE",
             Self::Unique => "UNIQUE",
             Self::Update => "UPDATE",
             Self::Values => "VALUES",
             Self::Varchar => "VARCHAR",
+            Self::When => "WHEN",
             Self::Where => "WHERE",
             Self::Write => "WRITE",
         }
     }
 }
 
+/// Parse a whole word as keyword, so identifiers which only start with a keyword such as
+/// `order_id` are not treated as keywords
 pub fn keyword(i: &str) -> IResult<&str, Keyword> {
     context(
         "keyword",
-        alt((
-            keyword_a_to_d,
-            keyword_e_to_g,
-            keyword_h_to_k,
-            keyword_l_to_n,
-            keyword_o_to_q,
-            keyword_r_to_t,
-            keyword_u_to_z,
-        )),
+        map_opt(take_while1(is_identifier), Keyword::from_str),
     )(i)
 }
 
-fn keyword_a_to_d(i: &str) -> IResult<&str, Keyword> {
-    alt((
-        map(tag_no_case(Keyword::And.to_str()), |_| Keyword::And),
-        map(tag_no_case(Keyword::As.to_str()), |_| Keyword::As),
-        map(tag_no_case(Keyword::Asc.to_str()), |_| Keyword::Asc),
-        map(tag_no_case(Keyword::Boolean.to_str()), |_| Keyword::Boolean),
-        map(tag_no_case(Keyword::Begin.to_str()), |_| Keyword::Begin),
-        map(tag_no_case(Keyword::By.to_str()), |_| Keyword::By),
-        map(tag_no_case(Keyword::Bool.to_str()), |_| Keyword::Bool),
-        map(tag_no_case(Keyword::Char.to_str()), |_| Keyword::Char),
-        map(tag_no_case(Keyword::Commit.to_str()), |_| Keyword::Commit),
-        map(tag_no_case(Keyword::Create.to_str()), |_| Keyword::Create),
-        map(tag_no_case(Keyword::Cross.to_str()), |_| Keyword::Cross),
-        map(tag_no_case(Keyword::Default.to_str()), |_| Keyword::Default),
-        map(tag_no_case(Keyword::Delete.to_str()), |_| Keyword::Delete),
-        map(tag_no_case(Keyword::Desc.to_str()), |_| Keyword::Desc),
-        map(tag_no_case(Keyword::Drop.to_str()), |_| Keyword::Drop),
-        map(tag_no_case(Keyword::Double.to_str()), |_| Keyword::Double),
-    ))(i)
-}
-
-fn keyword_e_to_g(i: &str) -> IResult<&str, Keyword> {
-    alt((
-        map(tag_no_case(Keyword::Exists.to_str()), |_| Keyword::Exists),
-        map(tag_no_case(Keyword::Explain.to_str()), |_| Keyword::Explain),
-        map(tag_no_case(Keyword::False.to_str()), |_| Keyword::False),
-        map(tag_no_case(Keyword::Float.to_str()), |_| Keyword::Float),
-        map(tag_no_case(Keyword::From.to_str()), |_| Keyword::From),
-        map(tag_no_case(Keyword::Group.to_str()), |_| Keyword::Group),
-    ))(i)
-}
-
-fn keyword_h_to_k(i: &str) -> IResult<&str, Keyword> {
-    alt((
-        map(tag_no_case(Keyword::Having.to_str()), |_| Keyword::Having),
-        map(tag_no_case(Keyword::If.to_str()), |_| Keyword::If),
-        map(tag_no_case(Keyword::Index.to_str()), |_| Keyword::Index),
-        map(tag_no_case(Keyword::Infinity.to_str()), |_| {
-            Keyword::Infinity
-        }),
-        map(tag_no_case(Keyword::Inner.to_str()), |_| Keyword::Inner),
-        map(tag_no_case(Keyword::Insert.to_str()), |_| Keyword::Insert),
-        map(tag_no_case(Keyword::Int.to_str()), |_| Keyword::Int),
-        map(tag_no_case(Keyword::Integer.to_str()), |_| Keyword::Integer),
-        map(tag_no_case(Keyword::Into.to_str()), |_| Keyword::Into),
-        map(tag_no_case(Keyword::Is.to_str()), |_| Keyword::Is),
-        map(tag_no_case(Keyword::Join.to_str()), |_| Keyword::Join),
-    ))(i)
-}
-
-fn keyword_l_to_n(i: &str) -> IResult<&str, Keyword> {
-    alt((
-        map(tag_no_case(Keyword::Left.to_str()), |_| Keyword::Left),
-        map(tag_no_case(Keyword::Like.to_str()), |_| Keyword::Like),
-        map(tag_no_case(Keyword::Limit.to_str()), |_| Keyword::Limit),
-        map(tag_no_case(Keyword::NaN.to_str()), |_| Keyword::NaN),
-        map(tag_no_case(Keyword::Not.to_str()), |_| Keyword::Not),
-        map(tag_no_case(Keyword::Null.to_str()), |_| Keyword::Null),
-    ))(i)
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sql::parser::identifier;
 
-fn keyword_o_to_q(i: &str) -> IResult<&str, Keyword> {
-    alt((
-        map(tag_no_case(Keyword::Of.to_str()), |_| Keyword::Of),
-        map(tag_no_case(Keyword::Offset.to_str()), |_| Keyword::Offset),
-        map(tag_no_case(Keyword::On.to_str()), |_| Keyword::On),
-        map(tag_no_case(Keyword::Only.to_str()), |_| Keyword::Only),
-        map(tag_no_case(Keyword::Or.to_str()), |_| Keyword::Or),
-        map(tag_no_case(Keyword::Order.to_str()), |_| Keyword::Order),
-        map(tag_no_case(Keyword::Outer.to_str()), |_| Keyword::Outer),
-        map(tag_no_case(Keyword::Primary.to_str()), |_| Keyword::Primary),
-    ))(i)
-}
-
-fn keyword_r_to_t(i: &str) -> IResult<&str, Keyword> {
-    alt((
-        map(tag_no_case(Keyword::Read.to_str()), |_| Keyword::Of),
-        map(tag_no_case(Keyword::References.to_str()), |_| {
-            Keyword::References
-        }),
-        map(tag_no_case(Keyword::Right.to_str()), |_| Keyword::Right),
-        map(tag_no_case(Keyword::Rollback.to_str()), |_| {
-            Keyword::Rollback
-        }),
-        map(tag_no_case(Keyword::Select.to_str()), |_| Keyword::Select),
-        map(tag_no_case(Keyword::Set.to_str()), |_| Keyword::Set),
-        map(tag_no_case(Keyword::System.to_str()), |_| Keyword::System),
-        map(tag_no_case(Keyword::String.to_str()), |_| Keyword::String),
-        map(tag_no_case(Keyword::Table.to_str()), |_| Keyword::Table),
-        map(tag_no_case(Keyword::Text.to_str()), |_| Keyword::Text),
-        map(tag_no_case(Keyword::Time.to_str()), |_| Keyword::Time),
-        map(tag_no_case(Keyword::Transaction.to_str()), |_| {
-            Keyword::Transaction
-        }),
-        map(tag_no_case(Keyword::True.to_str()), |_| Keyword::True),
-    ))(i)
-}
-
-fn keyword_u_to_z(i: &str) -> IResult<&str, Keyword> {
-    alt((
-        map(tag_no_case(Keyword::Unique.to_str()), |_| Keyword::Unique),
-        map(tag_no_case(Keyword::Update.to_str()), |_| Keyword::Update),
-        map(tag_no_case(Keyword::Values.to_str()), |_| Keyword::Values),
-        map(tag_no_case(Keyword::Varchar.to_str()), |_| Keyword::Varchar),
-        map(tag_no_case(Keyword::Where.to_str()), |_| Keyword::Where),
-        map(tag_no_case(Keyword::Write.to_str()), |_| Keyword::Write),
-    ))(i)
+    #[test]
+    fn whole_word() {
+        assert_eq!(keyword("asc, id"), Ok((", id", Keyword::Asc)));
+        assert_eq!(keyword("Order BY"), Ok((" BY", Keyword::Order)));
+        assert_eq!(keyword("to;"), Ok((";", Keyword::To)));
+        assert!(keyword("ascending").is_err());
+        for ident in [
+            "order_id",
+            "total",
+            "top",
+            "doubled",
+            "timestamp",
+            "endpoint",
+            "is_active",
+            "settings",
+        ] {
+            assert_eq!(keyword(ident).ok(), None);
+            assert_eq!(identifier(ident), Ok(("", ident)));
+        }
+        assert!(identifier("order").is_err());
+    }
 }
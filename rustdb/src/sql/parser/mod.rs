@@ -30,6 +30,23 @@ pub fn parse(sql: &str) -> Result<ast::Statement, Error> {
         Err(err) => Err(Error::Parse(convert_error(sql, err))),
     }
 }
+
+/// Parse a script of statements, every statement ends with `;`
+pub fn parse_many(sql: &str) -> Result<Vec<ast::Statement>, Error> {
+    let mut statements = Vec::new();
+    let mut rest = sql.trim_start();
+    while !rest.is_empty() {
+        match statement(rest).finish() {
+            Ok((remaining, statement)) => {
+                statements.push(statement);
+                rest = remaining.trim_start();
+            }
+            Err(err) => return Err(Error::Parse(convert_error(rest, err))),
+        }
+    }
+    Ok(statements)
+}
+
 pub fn statement(i: &str) -> IResult<&str, ast::Statement> {
     context(
         "parse sql statement",
@@ -39,13 +56,19 @@ pub fn statement(i: &str) -> IResult<&str, ast::Statement> {
             map(ddl::create, |create_table| {
                 ast::Statement::CreateTable(create_table)
             }),
+            map(ddl::create_index, ast::Statement::CreateIndex),
             map(ddl::drop_table, |drop_table| {
                 ast::Statement::DropTable(drop_table)
             }),
+            map(ddl::alter_table, ast::Statement::AlterTable),
+            map(ddl::reindex, ast::Statement::Reindex),
+            map(ddl::analyze, ast::Statement::Analyze),
             map(dml::delete, ast::Statement::Delete),
             map(dml::insert, ast::Statement::Insert),
             map(dml::update, ast::Statement::Update),
-            map(dql::select, ast::Statement::Select),
+            map(dql::select, |select| {
+                ast::Statement::Select(Box::new(select))
+            }),
         )),
     )(i)
 }
@@ -58,14 +81,15 @@ pub fn identifier(i: &str) -> IResult<&str, &str> {
                 not(keyword),
                 tuple((peek(alpha1), take_while1(is_identifier))),
             ),
+            // A quoted identifier may contain dots, which are part of the name
             delimited(
                 tag("`"),
-                tuple((peek(alpha1), take_while1(is_identifier))),
+                tuple((peek(alpha1), take_while1(is_quoted_identifier))),
                 tag("`"),
             ),
             delimited(
                 tag("["),
-                tuple((peek(alpha1), take_while1(is_identifier))),
+                tuple((peek(alpha1), take_while1(is_quoted_identifier))),
                 tag("]"),
             ),
         )),
@@ -73,10 +97,14 @@ pub fn identifier(i: &str) -> IResult<&str, &str> {
     .map(|(remaining, ident)| (remaining, ident.1))
 }
 
-fn is_identifier(c: char) -> bool {
+pub(crate) fn is_identifier(c: char) -> bool {
     c.is_alphanumeric() || c == '_' || c == '@'
 }
 
+fn is_quoted_identifier(c: char) -> bool {
+    is_identifier(c) || c == '.'
+}
+
 #[cfg(test)]
 mod tests {
     use crate::sql::parser::ast;
@@ -89,4 +117,23 @@ mod tests {
             ast::Statement::Insert(_)
         ))
     }
+
+    #[test]
+    fn parse_many() {
+        let statements = super::parse_many(
+            " BEGIN TRANSACTION;\n\tInsert into user values(1);SELECT * FROM user;\nCOMMIT; ",
+        )
+        .unwrap();
+        assert!(matches!(
+            statements.as_slice(),
+            [
+                ast::Statement::Begin(_),
+                ast::Statement::Insert(_),
+                ast::Statement::Select(_),
+                ast::Statement::Commit
+            ]
+        ));
+        assert!(super::parse_many("  ").unwrap().is_empty());
+        assert!(super::parse_many("COMMIT; SELECT").is_err());
+    }
 }
@@ -3,196 +3,944 @@ use super::{
     types::expression,
     SqlResult,
 };
-use crate::sql::catalog::{Column, Table};
+use crate::sql::catalog::{Catalog, Column, Index, Table, ROWID};
 use crate::sql::parser::ast;
-use crate::sql::parser::ddl::{CreateTable, DropTable};
-use crate::sql::parser::dml::{Delete, Insert};
-use crate::sql::plan::node::Node;
-use crate::sql::types::Value;
-use ordered_float::OrderedFloat;
+use crate::sql::parser::ddl::{AlterTable, CreateIndex, CreateTable, DropTable};
+use crate::sql::parser::dml::{Delete, Insert, InsertSource, Update};
+use crate::sql::parser::dql::{FromItem, FromTable, Select, SelectItem};
+use crate::sql::plan::node::{Aggregate, AggregateItem, Lookup, Node, ProjectionItem};
+use crate::sql::types::{function, DataType, Value};
+use crate::sql::Error;
+use std::collections::HashMap;
+use std::ops::Bound;
 
-mod node;
+pub mod node;
 
-pub struct Planner {}
+pub struct Planner<'a, C: Catalog> {
+    catalog: &'a C,
+}
 
-impl Planner {
-    pub fn new() -> Self {
-        Self {}
+impl<'a, C: Catalog> Planner<'a, C> {
+    pub fn new(catalog: &'a C) -> Self {
+        Self { catalog }
     }
 
-    pub fn build_statement(&self, statement: ast::Statement) -> SqlResult<Node> {
+    pub async fn build_statement(&self, statement: ast::Statement) -> SqlResult<Node> {
         match statement {
-            ast::Statement::CreateTable(CreateTable { name, columns }) => Ok(Node::CreateTable {
-                schema: Table::new(
-                    name,
-                    columns
-                        .into_iter()
-                        .map(|c| {
-                            let mut column = Column::new(c.name, c.datatype)
-                                .with_primary(c.primary_key)
-                                .with_unique(c.unique)
-                                .with_index(c.index);
-                            if let Some(nullable) = c.nullable {
-                                column = column.with_nullable(nullable);
-                            }
-                            if let Some(default) = c.default {
-                                column = column
-                                    .with_default(self.build_expression(default)?.evaluate()?);
-                            }
-                            if let Some(references) = c.references {
-                                column = column.with_references(references)
-                            }
-                            Ok(column)
-                        })
-                        .collect::<SqlResult<_>>()?,
-                ),
-            }),
+            ast::Statement::CreateTable(CreateTable { name, columns }) => {
+                let mut columns = columns
+                    .into_iter()
+                    .map(|c| {
+                        // The parser's nullable means `NOT NULL`, and primary key can't be null
+                        let nullable = !c.primary_key && !c.nullable.unwrap_or_default();
+                        let mut column = Column::new(c.name, c.datatype)
+                            .with_primary(c.primary_key)
+                            .with_auto_increment(c.auto_increment)
+                            .with_unique(c.unique || c.primary_key)
+                            .with_index(c.index)
+                            .with_nullable(nullable);
+                        if let Some(default) = c.default {
+                            column = column.with_default(
+                                self.build_expression(default, &Scope::default())?
+                                    .evaluate(None)?,
+                            );
+                        } else if nullable {
+                            column = column.with_default(Value::Null);
+                        }
+                        if let Some(references) = c.references {
+                            column = column.with_references(references)
+                        }
+                        Ok(column)
+                    })
+                    .collect::<SqlResult<Vec<_>>>()?;
+                if columns.iter().any(|column| column.name == ROWID) {
+                    return Err(Error::Value(format!("Column name {} is reserved", ROWID)));
+                }
+                // Without a primary key, the rows are keyed by a hidden auto-increment rowid
+                if !columns
+                    .iter()
+                    .any(|column| column.constraints.is_primary_key())
+                {
+                    columns.push(
+                        Column::new(ROWID, DataType::Bigint)
+                            .with_primary(true)
+                            .with_auto_increment(true)
+                            .with_unique(true),
+                    );
+                }
+                Ok(Node::CreateTable {
+                    schema: Table::new(name, columns),
+                })
+            }
+            ast::Statement::CreateIndex(CreateIndex {
+                name,
+                table,
+                columns,
+            }) => {
+                let schema = self.read_table(&table).await?;
+                let columns = columns
+                    .iter()
+                    .map(|column| schema.column_index(column))
+                    .collect::<SqlResult<Vec<_>>>()?;
+                Ok(Node::CreateIndex {
+                    table,
+                    index: Index { name, columns },
+                })
+            }
             ast::Statement::DropTable(DropTable { name, if_exists }) => Ok(Node::DropTable {
                 table: name,
                 if_exists,
             }),
+            ast::Statement::AlterTable(AlterTable { name, action }) => Ok(Node::AlterTable {
+                table: name,
+                action,
+            }),
+            ast::Statement::Reindex(table) => Ok(Node::Reindex { table }),
+            ast::Statement::Analyze(table) => Ok(Node::Analyze { table }),
             ast::Statement::Insert(Insert {
                 table,
                 columns,
-                values,
+                source,
             }) => Ok(Node::Insert {
                 table,
                 columns: columns.unwrap_or_default(),
-                values: values
-                    .into_iter()
-                    .map(|value| {
-                        value
+                source: Box::new(match source {
+                    InsertSource::Values(values) => Node::Values {
+                        rows: values
                             .into_iter()
-                            .map(|expr| self.build_expression(expr))
-                            .collect::<SqlResult<Vec<_>>>()
-                    })
-                    .collect::<SqlResult<Vec<_>>>()?,
+                            .map(|value| {
+                                value
+                                    .into_iter()
+                                    .map(|expr| self.build_expression(expr, &Scope::default()))
+                                    .collect::<SqlResult<Vec<_>>>()
+                            })
+                            .collect::<SqlResult<Vec<_>>>()?,
+                    },
+                    InsertSource::Select(select) => self.build_select(*select).await?,
+                }),
             }),
-            ast::Statement::Delete(Delete { table, r#where }) => Ok(Node::Delete {
-                table: table.clone(),
-                source: Node::Scan {
-                    table,
-                    alias: None,
-                    filter: r#where
-                        .map(|expr| self.build_expression(expr))
+            ast::Statement::Delete(Delete { table, r#where }) => {
+                let schema = self.read_table(&table).await?;
+                let scope = Scope::from_table(&schema, None);
+                let filter = self.build_filter(r#where, &scope)?;
+                Ok(Node::Delete {
+                    table: table.clone(),
+                    source: Self::build_scan(&schema, table, None, filter).into(),
+                })
+            }
+            ast::Statement::Update(Update {
+                table,
+                set,
+                r#where,
+                returning,
+            }) => {
+                let schema = self.read_table(&table).await?;
+                let scope = Scope::from_table(&schema, None);
+                let filter = self.build_filter(r#where, &scope)?;
+                Ok(Node::Update {
+                    table: table.clone(),
+                    source: Self::build_scan(&schema, table.clone(), None, filter).into(),
+                    expressions: set
+                        .into_iter()
+                        .map(|(column, expr)| {
+                            Ok((
+                                schema.column_index(&column)?,
+                                Some(column),
+                                self.build_expression(expr, &scope)?,
+                            ))
+                        })
+                        .collect::<SqlResult<_>>()?,
+                    returning: returning
+                        .map(|returning| match returning {
+                            SelectItem::All => Ok(schema
+                                .wildcard()
+                                .into_iter()
+                                .map(|index| (expression::Expression::Column(index), None))
+                                .collect()),
+                            SelectItem::Qualified(relation) => Ok(scope
+                                .expand(&relation)?
+                                .into_iter()
+                                .filter(|index| !schema.columns()[*index].is_rowid())
+                                .map(|index| (expression::Expression::Column(index), None))
+                                .collect()),
+                            SelectItem::Part(items) => items
+                                .into_iter()
+                                .map(|(expr, label)| {
+                                    Ok((self.build_expression(expr, &scope)?, label))
+                                })
+                                .collect::<SqlResult<_>>(),
+                        })
                         .transpose()?,
+                })
+            }
+            ast::Statement::Select(select) => self.build_select(*select).await,
+            statement => Err(Error::NotSupported(format!("{:?}", statement))),
+        }
+    }
+
+    async fn build_select(&self, select: Select) -> SqlResult<Node> {
+        let Select {
+            select,
+            from,
+            r#where,
+            group_by,
+            having,
+            order,
+            offset,
+            limit,
+        } = select;
+        if having.is_some() {
+            return Err(Error::NotSupported("having".to_string()));
+        }
+        if from.is_empty() {
+            let items = match select {
+                SelectItem::Part(items)
+                    if r#where.is_none()
+                        && group_by.is_none()
+                        && order.is_none()
+                        && offset.is_none()
+                        && limit.is_none() =>
+                {
+                    items
                 }
-                .into(),
-            }),
-            _ => unimplemented!(),
+                _ => return Err(Error::NotSupported("select without from".to_string())),
+            };
+            // the expressions are evaluated once on an empty row
+            return Ok(Node::Projection {
+                source: Box::new(Node::Values {
+                    rows: vec![Vec::new()],
+                }),
+                expressions: items
+                    .into_iter()
+                    .map(|(expr, label)| {
+                        Ok((self.build_expression(expr, &Scope::default())?, label))
+                    })
+                    .collect::<SqlResult<_>>()?,
+            });
+        }
+        let (table, alias) = match from.as_slice() {
+            [FromItem::Table(FromTable { name, alias })] => (name.clone(), alias.clone()),
+            _ => return Err(Error::NotSupported("join".to_string())),
+        };
+        let schema = self.read_table(&table).await?;
+        let scope = Scope::from_table(&schema, alias.as_deref());
+        let filter = self.build_filter(r#where, &scope)?;
+        let mut node = Self::build_scan(&schema, table, alias, filter);
+        let group_by = group_by
+            .unwrap_or_default()
+            .into_iter()
+            .map(|expr| {
+                let expr = Self::resolve_position(expr, &select, &schema, &scope)?;
+                self.build_expression(expr, &scope)
+            })
+            .collect::<SqlResult<Vec<_>>>()?;
+        let aggregation = match &select {
+            SelectItem::Part(items) => self.build_aggregates(items, &group_by, &scope)?,
+            SelectItem::All | SelectItem::Qualified(_) if group_by.is_empty() => None,
+            SelectItem::All | SelectItem::Qualified(_) => {
+                return Err(Error::NotSupported("* with group by".to_string()))
+            }
+        };
+        let aggregated = aggregation.is_some();
+        let mut projection = None;
+        if let Some((aggregates, expressions)) = aggregation {
+            if order.is_some() {
+                return Err(Error::NotSupported("order by with aggregates".to_string()));
+            }
+            if !group_by.is_empty() {
+                projection = Some(expressions);
+            }
+            node = Self::build_aggregate(&schema, node, group_by, aggregates);
+        }
+        if let Some(order) = order {
+            node = Node::Order {
+                source: Box::new(node),
+                orders: order
+                    .into_iter()
+                    .map(|(expr, order)| {
+                        let expr = Self::resolve_position(expr, &select, &schema, &scope)?;
+                        Ok((self.build_expression(expr, &scope)?, order))
+                    })
+                    .collect::<SqlResult<_>>()?,
+            }
+        }
+        if let Some(offset) = offset {
+            node = Node::Offset {
+                source: Box::new(node),
+                offset: self.build_count(offset)?,
+            }
+        }
+        if let Some(limit) = limit {
+            node = Node::Limit {
+                source: Box::new(node),
+                limit: self.build_count(limit)?,
+            }
+        }
+        if let Some(expressions) = projection {
+            node = Node::Projection {
+                source: Box::new(node),
+                expressions,
+            }
+        } else {
+            let expressions = match (select, aggregated) {
+                (SelectItem::Part(items), false) => Some(
+                    items
+                        .into_iter()
+                        .map(|(expr, label)| Ok((self.build_expression(expr, &scope)?, label)))
+                        .collect::<SqlResult<_>>()?,
+                ),
+                (SelectItem::Qualified(relation), _) => Some(
+                    scope
+                        .expand(&relation)?
+                        .into_iter()
+                        .filter(|index| !schema.columns()[*index].is_rowid())
+                        .map(|index| (expression::Expression::Column(index), None))
+                        .collect(),
+                ),
+                // the hidden rowid is only selected by its name
+                (SelectItem::All, _) if schema.columns().iter().any(Column::is_rowid) => Some(
+                    schema
+                        .wildcard()
+                        .into_iter()
+                        .map(|index| (expression::Expression::Column(index), None))
+                        .collect(),
+                ),
+                _ => None,
+            };
+            if let Some(expressions) = expressions {
+                node = Node::Projection {
+                    source: Box::new(node),
+                    expressions,
+                }
+            }
+        }
+        Ok(node)
+    }
+
+    /// Resolve an integer literal of `ORDER BY` or `GROUP BY`, like `ORDER BY 2`, into the
+    /// expression of the select item at that 1-based position. Other expressions are unchanged.
+    fn resolve_position(
+        expr: parser::expression::Expression,
+        select: &SelectItem,
+        schema: &Table,
+        scope: &Scope,
+    ) -> SqlResult<parser::expression::Expression> {
+        let position = match &expr {
+            parser::expression::Expression::Literal(literal) => {
+                match Value::from(literal.clone()).as_i64() {
+                    Some(position) => position,
+                    None => return Ok(expr),
+                }
+            }
+            _ => return Ok(expr),
+        };
+        let mut items = match select {
+            SelectItem::Part(items) => items.iter().map(|(expr, _)| expr.clone()).collect(),
+            SelectItem::All => schema
+                .wildcard()
+                .into_iter()
+                .map(parser::expression::Expression::Column)
+                .collect(),
+            SelectItem::Qualified(relation) => scope
+                .expand(relation)?
+                .into_iter()
+                .filter(|index| !schema.columns()[*index].is_rowid())
+                .map(parser::expression::Expression::Column)
+                .collect::<Vec<_>>(),
+        };
+        let len = items.len();
+        usize::try_from(position)
+            .ok()
+            .and_then(|position| position.checked_sub(1))
+            .filter(|index| *index < len)
+            .map(|index| items.swap_remove(index))
+            .ok_or_else(|| {
+                Error::Value(format!(
+                    "Position {} is out of the {} select items",
+                    position, len
+                ))
+            })
+    }
+
+    /// Build the aggregates of the select items and the projection of the items over the
+    /// aggregated rows, `None` if there is neither an aggregate nor `GROUP BY`.
+    /// Every item which isn't an aggregate must be one of the `GROUP BY` expressions.
+    fn build_aggregates(
+        &self,
+        items: &[(parser::expression::Expression, Option<String>)],
+        group_by: &[expression::Expression],
+        scope: &Scope,
+    ) -> SqlResult<Option<(Vec<AggregateItem>, Vec<ProjectionItem>)>> {
+        let found = items
+            .iter()
+            .map(|(expr, label)| match expr {
+                parser::expression::Expression::Function(name, args) => Aggregate::lookup(name)
+                    .map(|aggregate| (aggregate, name, args.as_slice(), label)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if group_by.is_empty() && found.iter().all(Option::is_none) {
+            return Ok(None);
+        }
+        let mut aggregates = Vec::new();
+        let mut expressions = Vec::new();
+        for ((expr, label), aggregate) in items.iter().zip(found) {
+            match aggregate {
+                Some((aggregate, _, [arg], label)) => {
+                    expressions.push((
+                        expression::Expression::Column(group_by.len() + aggregates.len()),
+                        label.clone(),
+                    ));
+                    aggregates.push((
+                        aggregate,
+                        self.build_expression(arg.clone(), scope)?,
+                        label.clone(),
+                    ));
+                }
+                Some((_, name, args, _)) => {
+                    return Err(Error::Value(format!(
+                        "{} expects 1 argument, but got {}",
+                        name,
+                        args.len()
+                    )))
+                }
+                None if group_by.is_empty() => {
+                    return Err(Error::NotSupported(
+                        "columns mixed with aggregates".to_string(),
+                    ))
+                }
+                None => {
+                    let expr = self.build_expression(expr.clone(), scope)?;
+                    let position =
+                        group_by
+                            .iter()
+                            .position(|key| key == &expr)
+                            .ok_or_else(|| {
+                                Error::Value(format!(
+                                    "{:?} must appear in group by or be used in an aggregate",
+                                    expr
+                                ))
+                            })?;
+                    expressions.push((expression::Expression::Column(position), label.clone()));
+                }
+            }
+        }
+        Ok(Some((aggregates, expressions)))
+    }
+
+    /// Build the aggregation of source, a lone ungrouped `MIN` or `MAX` of an indexed column over
+    /// the whole table is read from one end of the index instead
+    fn build_aggregate(
+        schema: &Table,
+        source: Node,
+        group_by: Vec<expression::Expression>,
+        mut aggregates: Vec<AggregateItem>,
+    ) -> Node {
+        if let (
+            Node::Scan {
+                table,
+                filter: None,
+                ..
+            },
+            [(
+                aggregate @ (Aggregate::Max | Aggregate::Min),
+                expression::Expression::Column(column),
+                _,
+            )],
+            [],
+        ) = (&source, aggregates.as_slice(), group_by.as_slice())
+        {
+            let (table, column, aggregate) = (table.clone(), *column, *aggregate);
+            let indexed = schema.columns()[column].constraints.is_indexed();
+            if indexed {
+                let (_, _, label) = aggregates.pop().unwrap();
+                return Node::IndexExtreme {
+                    table,
+                    column,
+                    aggregate,
+                    label,
+                };
+            }
+        }
+        Node::Aggregate {
+            source: Box::new(source),
+            group_by,
+            aggregates,
+        }
+    }
+
+    /// Build the scan of table. The equalities between an indexed column and a constant, and the
+    /// prefix patterns of `LIKE` on an indexed column, in the conjuncts of the filter are looked
+    /// up in the indexes, and the rest of the filter is applied to the found rows. The equalities
+    /// on the leading columns of an index of several columns are looked up in it together.
+    /// Without such conjuncts the whole table is scanned.
+    fn build_scan(
+        schema: &Table,
+        table: String,
+        alias: Option<String>,
+        filter: Option<expression::Expression>,
+    ) -> Node {
+        let Some(filter) = filter else {
+            return Node::Scan {
+                table,
+                alias,
+                filter: None,
+            };
+        };
+        let mut conjuncts = filter.conjuncts();
+        let mut lookups = Vec::new();
+        if let Some((lookup, used)) = Self::build_prefix_lookup(schema, &conjuncts) {
+            lookups.push(lookup);
+            conjuncts = conjuncts
+                .into_iter()
+                .enumerate()
+                .filter(|(position, _)| !used.contains(position))
+                .map(|(_, conjunct)| conjunct)
+                .collect();
+        }
+        let mut residual = Vec::new();
+        for conjunct in conjuncts {
+            match Self::build_lookup(schema, &conjunct) {
+                Some(lookup) => lookups.push(lookup),
+                None => residual.push(conjunct),
+            }
+        }
+        let filter = residual
+            .into_iter()
+            .reduce(|lhs, rhs| expression::Expression::And(Box::new(lhs), Box::new(rhs)));
+        if lookups.is_empty() {
+            return Node::Scan {
+                table,
+                alias,
+                filter,
+            };
+        }
+        Node::IndexScan {
+            table,
+            alias,
+            lookups,
+            filter,
+        }
+    }
+
+    /// The lookup of the index of several columns which has the most leading columns equal to
+    /// constants in the conjuncts, and the positions of those conjuncts
+    fn build_prefix_lookup(
+        schema: &Table,
+        conjuncts: &[expression::Expression],
+    ) -> Option<((usize, Lookup), Vec<usize>)> {
+        let equalities = conjuncts
+            .iter()
+            .enumerate()
+            .filter_map(|(position, conjunct)| {
+                let (column, key) = Self::build_equality(schema, conjunct)?;
+                Some((column, (position, key)))
+            })
+            .collect::<HashMap<_, _>>();
+        let (index, prefix) = schema
+            .indexes()
+            .iter()
+            .map(|index| {
+                let prefix = index
+                    .columns
+                    .iter()
+                    .map_while(|column| equalities.get(column).cloned())
+                    .collect::<Vec<_>>();
+                (index, prefix)
+            })
+            .filter(|(_, prefix)| !prefix.is_empty())
+            .max_by_key(|(_, prefix)| prefix.len())?;
+        let (used, key) = prefix.into_iter().unzip();
+        Some((
+            (index.columns[0], Lookup::Prefix(index.name.clone(), key)),
+            used,
+        ))
+    }
+
+    /// The column position and its lookup, if the expression is an equality between an indexed
+    /// column and a constant, see [`Planner::build_equality`], or an indexed string column
+    /// `LIKE` a prefix pattern
+    fn build_lookup(
+        schema: &Table,
+        expression: &expression::Expression,
+    ) -> Option<(usize, Lookup)> {
+        use expression::Expression::{Column, Const, Like};
+        if let Like(lhs, rhs) = expression {
+            let (Column(index), Const(Value::String(pattern))) = (lhs.as_ref(), rhs.as_ref())
+            else {
+                return None;
+            };
+            let column = schema.columns().get(*index)?;
+            if !column.constraints.index
+                || column.constraints.primary_key
+                || column.datatype != DataType::String
+            {
+                return None;
+            }
+            let (start, end) = like_prefix_range(pattern)?;
+            return Some((*index, Lookup::Range(start, end)));
+        }
+        let (index, key) = Self::build_equality(schema, expression)?;
+        let column = &schema.columns()[index];
+        if !column.constraints.index || column.constraints.primary_key {
+            return None;
         }
+        Some((index, Lookup::Equal(key)))
+    }
+
+    /// The column position and the key, if the expression is an equality between a column and
+    /// a non-null constant which the column's datatype represents exactly
+    fn build_equality(
+        schema: &Table,
+        expression: &expression::Expression,
+    ) -> Option<(usize, Value)> {
+        use expression::Expression::{Column, Const, Equal};
+        let (index, value) = match expression {
+            Equal(lhs, rhs) => match (lhs.as_ref(), rhs.as_ref()) {
+                (Column(index), Const(value)) | (Const(value), Column(index)) => (*index, value),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        let column = schema.columns().get(index)?;
+        if value == &Value::Null {
+            return None;
+        }
+        let key = value.clone().cast(&column.datatype).ok()?;
+        // a lossy cast would find the rows of another value
+        match Equal(Box::new(Const(key.clone())), Box::new(Const(value.clone()))).evaluate(None) {
+            Ok(Value::Boolean(true)) => Some((index, key)),
+            _ => None,
+        }
+    }
+
+    /// Build the constant non-negative count of `LIMIT` and `OFFSET`
+    fn build_count(&self, expression: parser::expression::Expression) -> SqlResult<usize> {
+        let value = self
+            .build_expression(expression, &Scope::default())?
+            .evaluate(None)?;
+        match value.clone().cast(&super::types::DataType::Bigint)? {
+            Value::Bigint(count) => {
+                usize::try_from(count).map_err(|_| Error::ValueNotMatch("count", value.to_string()))
+            }
+            _ => Err(Error::ValueNotMatch("count", value.to_string())),
+        }
+    }
+
+    /// Build the `WHERE` clause, negations are normalized away where possible
+    fn build_filter(
+        &self,
+        expression: Option<parser::expression::Expression>,
+        scope: &Scope,
+    ) -> SqlResult<Option<expression::Expression>> {
+        expression
+            .map(|expr| Ok(self.build_expression(expr, scope)?.normalize()))
+            .transpose()
+    }
+
+    async fn read_table(&self, name: &str) -> SqlResult<Table> {
+        self.catalog
+            .read_table(name)
+            .await?
+            .ok_or(Error::NotFound("table", name.to_string()))
     }
 
     pub fn build_expression(
         &self,
         expression: parser::expression::Expression,
+        scope: &Scope,
     ) -> SqlResult<expression::Expression> {
         use super::types::expression::*;
         Ok(match expression {
-            parser::expression::Expression::Literal(literal) => Expression::Const(match literal {
-                parser::expression::Literal::Null => Value::Null,
-                parser::expression::Literal::Boolean(boolean) => Value::Boolean(boolean),
-                parser::expression::Literal::Tinyint(integer) => Value::Tinyint(integer),
-                parser::expression::Literal::Smallint(integer) => Value::Smallint(integer),
-                parser::expression::Literal::Integer(integer) => Value::Integer(integer),
-                parser::expression::Literal::Bigint(integer) => Value::Bigint(integer),
-                parser::expression::Literal::Float(float) => Value::Float(OrderedFloat(float)),
-                parser::expression::Literal::Double(float) => Value::Double(OrderedFloat(float)),
-                parser::expression::Literal::String(string) => Value::String(string),
-            }),
-            parser::expression::Expression::Field(_, _) => todo!(),
-            parser::expression::Expression::Column(_) => todo!(),
+            parser::expression::Expression::Literal(literal) => {
+                Expression::Const(Value::from(literal))
+            }
+            parser::expression::Expression::Field(relation, name) => {
+                Expression::Column(scope.resolve(relation.as_deref(), &name)?)
+            }
+            parser::expression::Expression::Column(index) => Expression::Column(index),
+            parser::expression::Expression::Case { branches, default } => Expression::Case {
+                branches: branches
+                    .into_iter()
+                    .map(|(condition, value)| {
+                        Ok((
+                            self.build_expression(condition, scope)?,
+                            self.build_expression(value, scope)?,
+                        ))
+                    })
+                    .collect::<SqlResult<_>>()?,
+                default: match default {
+                    Some(default) => Some(Box::new(self.build_expression(*default, scope)?)),
+                    None => None,
+                },
+            },
+            parser::expression::Expression::Cast(expr, datatype) => {
+                Expression::Cast(Box::new(self.build_expression(*expr, scope)?), datatype)
+            }
+            parser::expression::Expression::Function(name, args) => {
+                if function::lookup(&name).is_none() {
+                    return Err(Error::NotFound("function", name));
+                }
+                Expression::Function(
+                    name,
+                    args.into_iter()
+                        .map(|arg| self.build_expression(arg, scope))
+                        .collect::<SqlResult<_>>()?,
+                )
+            }
             parser::expression::Expression::Operation(operation) => match operation {
                 parser::expression::Operation::And(lhs, rhs) => Expression::And(
-                    Box::new(self.build_expression(*lhs)?),
-                    Box::new(self.build_expression(*rhs)?),
+                    Box::new(self.build_expression(*lhs, scope)?),
+                    Box::new(self.build_expression(*rhs, scope)?),
                 ),
                 parser::expression::Operation::Not(expr) => {
-                    Expression::Not(Box::new(self.build_expression(*expr)?))
+                    Expression::Not(Box::new(self.build_expression(*expr, scope)?))
                 }
                 parser::expression::Operation::Or(lhs, rhs) => Expression::Or(
-                    Box::new(self.build_expression(*lhs)?),
-                    Box::new(self.build_expression(*rhs)?),
+                    Box::new(self.build_expression(*lhs, scope)?),
+                    Box::new(self.build_expression(*rhs, scope)?),
+                ),
+                // `a BETWEEN b AND c` is `a >= b AND a <= c`
+                parser::expression::Operation::Between(lhs, low, high) => Expression::And(
+                    Box::new(self.build_expression(
+                        parser::expression::Operation::GreaterThanOrEqual(lhs.clone(), low).into(),
+                        scope,
+                    )?),
+                    Box::new(self.build_expression(
+                        parser::expression::Operation::LessThanOrEqual(lhs, high).into(),
+                        scope,
+                    )?),
                 ),
                 parser::expression::Operation::Equal(lhs, rhs) => Expression::Equal(
-                    Box::new(self.build_expression(*lhs)?),
-                    Box::new(self.build_expression(*rhs)?),
+                    Box::new(self.build_expression(*lhs, scope)?),
+                    Box::new(self.build_expression(*rhs, scope)?),
                 ),
                 parser::expression::Operation::GreaterThan(lhs, rhs) => Expression::GreaterThan(
-                    Box::new(self.build_expression(*lhs)?),
-                    Box::new(self.build_expression(*rhs)?),
+                    Box::new(self.build_expression(*lhs, scope)?),
+                    Box::new(self.build_expression(*rhs, scope)?),
                 ),
                 parser::expression::Operation::GreaterThanOrEqual(lhs, rhs) => Expression::Or(
                     Box::new(Expression::Equal(
-                        Box::new(self.build_expression(*lhs.clone())?),
-                        Box::new(self.build_expression(*rhs.clone())?),
+                        Box::new(self.build_expression(*lhs.clone(), scope)?),
+                        Box::new(self.build_expression(*rhs.clone(), scope)?),
                     )),
                     Box::new(Expression::GreaterThan(
-                        Box::new(self.build_expression(*lhs)?),
-                        Box::new(self.build_expression(*rhs)?),
+                        Box::new(self.build_expression(*lhs, scope)?),
+                        Box::new(self.build_expression(*rhs, scope)?),
                     )),
                 ),
+                parser::expression::Operation::IsDistinctFrom(lhs, rhs) => {
+                    Expression::IsDistinctFrom(
+                        Box::new(self.build_expression(*lhs, scope)?),
+                        Box::new(self.build_expression(*rhs, scope)?),
+                    )
+                }
+                parser::expression::Operation::IsNotDistinctFrom(lhs, rhs) => {
+                    Expression::Not(Box::new(Expression::IsDistinctFrom(
+                        Box::new(self.build_expression(*lhs, scope)?),
+                        Box::new(self.build_expression(*rhs, scope)?),
+                    )))
+                }
                 parser::expression::Operation::IsNull(expr) => {
-                    Expression::IsNull(Box::new(self.build_expression(*expr)?))
+                    Expression::IsNull(Box::new(self.build_expression(*expr, scope)?))
                 }
                 parser::expression::Operation::LessThan(lhs, rhs) => Expression::LessThan(
-                    Box::new(self.build_expression(*lhs)?),
-                    Box::new(self.build_expression(*rhs)?),
+                    Box::new(self.build_expression(*lhs, scope)?),
+                    Box::new(self.build_expression(*rhs, scope)?),
                 ),
                 parser::expression::Operation::LessThanOrEqual(lhs, rhs) => Expression::Or(
                     Box::new(Expression::Equal(
-                        Box::new(self.build_expression(*lhs.clone())?),
-                        Box::new(self.build_expression(*rhs.clone())?),
+                        Box::new(self.build_expression(*lhs.clone(), scope)?),
+                        Box::new(self.build_expression(*rhs.clone(), scope)?),
                     )),
                     Box::new(Expression::LessThan(
-                        Box::new(self.build_expression(*lhs)?),
-                        Box::new(self.build_expression(*rhs)?),
+                        Box::new(self.build_expression(*lhs, scope)?),
+                        Box::new(self.build_expression(*rhs, scope)?),
                     )),
                 ),
                 parser::expression::Operation::NotEqual(lhs, rhs) => {
                     Expression::Not(Box::new(Expression::Equal(
-                        Box::new(self.build_expression(*lhs)?),
-                        Box::new(self.build_expression(*rhs)?),
+                        Box::new(self.build_expression(*lhs, scope)?),
+                        Box::new(self.build_expression(*rhs, scope)?),
                     )))
                 }
                 parser::expression::Operation::Add(lhs, rhs) => Expression::Add(
-                    Box::new(self.build_expression(*lhs)?),
-                    Box::new(self.build_expression(*rhs)?),
+                    Box::new(self.build_expression(*lhs, scope)?),
+                    Box::new(self.build_expression(*rhs, scope)?),
                 ),
                 parser::expression::Operation::Assert(expr) => {
-                    Expression::Assert(Box::new(self.build_expression(*expr)?))
+                    Expression::Assert(Box::new(self.build_expression(*expr, scope)?))
                 }
                 parser::expression::Operation::Divide(lhs, rhs) => Expression::Divide(
-                    Box::new(self.build_expression(*lhs)?),
-                    Box::new(self.build_expression(*rhs)?),
+                    Box::new(self.build_expression(*lhs, scope)?),
+                    Box::new(self.build_expression(*rhs, scope)?),
                 ),
                 parser::expression::Operation::Exponentiate(lhs, rhs) => Expression::Exponentiate(
-                    Box::new(self.build_expression(*lhs)?),
-                    Box::new(self.build_expression(*rhs)?),
+                    Box::new(self.build_expression(*lhs, scope)?),
+                    Box::new(self.build_expression(*rhs, scope)?),
                 ),
                 parser::expression::Operation::Factorial(expr) => {
-                    Expression::Factorial(Box::new(self.build_expression(*expr)?))
+                    Expression::Factorial(Box::new(self.build_expression(*expr, scope)?))
                 }
                 parser::expression::Operation::Modulo(lhs, rhs) => Expression::Modulo(
-                    Box::new(self.build_expression(*lhs)?),
-                    Box::new(self.build_expression(*rhs)?),
+                    Box::new(self.build_expression(*lhs, scope)?),
+                    Box::new(self.build_expression(*rhs, scope)?),
                 ),
                 parser::expression::Operation::Multiply(lhs, rhs) => Expression::Multiply(
-                    Box::new(self.build_expression(*lhs)?),
-                    Box::new(self.build_expression(*rhs)?),
+                    Box::new(self.build_expression(*lhs, scope)?),
+                    Box::new(self.build_expression(*rhs, scope)?),
                 ),
                 parser::expression::Operation::Negate(expr) => {
-                    Expression::Negate(Box::new(self.build_expression(*expr)?))
+                    Expression::Negate(Box::new(self.build_expression(*expr, scope)?))
                 }
                 parser::expression::Operation::Subtract(lhs, rhs) => Expression::Subtract(
-                    Box::new(self.build_expression(*lhs)?),
-                    Box::new(self.build_expression(*rhs)?),
+                    Box::new(self.build_expression(*lhs, scope)?),
+                    Box::new(self.build_expression(*rhs, scope)?),
                 ),
                 parser::expression::Operation::Like(lhs, rhs) => Expression::Like(
-                    Box::new(self.build_expression(*lhs)?),
-                    Box::new(self.build_expression(*rhs)?),
+                    Box::new(self.build_expression(*lhs, scope)?),
+                    Box::new(self.build_expression(*rhs, scope)?),
                 ),
             },
         })
     }
 }
+
+/// The range of the strings which match a `LIKE` pattern of a non-empty prefix followed by a
+/// single `%`, `None` for any other pattern. The prefix must not contain `%`, `_` or the
+/// escape character `\`. The range ends before the smallest string greater than every string
+/// of the prefix, which is the prefix with its last char incremented.
+fn like_prefix_range(pattern: &str) -> Option<(Bound<Value>, Bound<Value>)> {
+    let prefix = pattern.strip_suffix('%')?;
+    if prefix.is_empty() || prefix.contains(['%', '_', '\\']) {
+        return None;
+    }
+    let mut chars = prefix.chars().collect::<Vec<_>>();
+    let mut end = Bound::Unbounded;
+    while let Some(last) = chars.pop() {
+        // skip the surrogates, which aren't chars
+        if let Some(next) = (last as u32 + 1..=char::MAX as u32).find_map(char::from_u32) {
+            chars.push(next);
+            end = Bound::Excluded(Value::String(chars.into_iter().collect()));
+            break;
+        }
+    }
+    Some((Bound::Included(Value::String(prefix.to_string())), end))
+}
+
+/// The columns which expressions can refer to, it resolves field names into row positions
+#[derive(Debug, Default)]
+pub struct Scope {
+    /// The (relation, column name) of every position
+    columns: Vec<(Option<String>, String)>,
+}
+
+impl Scope {
+    /// A scope of table's columns, the columns can be qualified by the alias or table name
+    pub fn from_table(table: &Table, alias: Option<&str>) -> Self {
+        let relation = alias.unwrap_or(table.name());
+        Self {
+            columns: table
+                .columns()
+                .iter()
+                .map(|column| (Some(relation.to_string()), column.name.clone()))
+                .collect(),
+        }
+    }
+
+    /// Returns the positions of all the columns of relation, for `relation.*`
+    pub fn expand(&self, relation: &str) -> SqlResult<Vec<usize>> {
+        let positions = self
+            .columns
+            .iter()
+            .enumerate()
+            .filter(|(_, (r, _))| r.as_deref() == Some(relation))
+            .map(|(position, _)| position)
+            .collect::<Vec<_>>();
+        match positions.as_slice() {
+            [] => Err(Error::NotFound("relation", relation.to_string())),
+            // the columns of a relation are adjacent, so a gap means two relations share the name
+            [first, .., last] if last - first + 1 != positions.len() => {
+                Err(Error::Value(format!("Ambiguous relation {}", relation)))
+            }
+            _ => Ok(positions),
+        }
+    }
+
+    /// Returns the position of field, an unqualified field must be unambiguous
+    pub fn resolve(&self, relation: Option<&str>, name: &str) -> SqlResult<usize> {
+        let mut positions = self
+            .columns
+            .iter()
+            .enumerate()
+            .filter(|(_, (r, n))| n == name && (relation.is_none() || r.as_deref() == relation))
+            .map(|(position, _)| position);
+        let field = match relation {
+            None => name.to_string(),
+            Some(relation) => format!("{}.{}", relation, name),
+        };
+        match (positions.next(), positions.next()) {
+            (Some(position), None) => Ok(position),
+            (Some(_), Some(_)) => Err(Error::Value(format!("Ambiguous column {}", field))),
+            (None, _) => Err(Error::NotFound("column", field)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand() {
+        let table = Table::new(
+            "a",
+            vec![
+                Column::new("id", DataType::Integer),
+                Column::new("name", DataType::String),
+            ],
+        );
+        let mut scope = Scope::from_table(&table, None);
+        assert_eq!(scope.expand("a").unwrap(), vec![0, 1]);
+        assert!(scope.expand("b").is_err());
+        // the same relation name twice, as in a self join without aliases
+        scope
+            .columns
+            .extend(Scope::from_table(&Table::new("b", table.columns().to_vec()), None).columns);
+        scope
+            .columns
+            .extend(Scope::from_table(&table, None).columns);
+        assert_eq!(scope.expand("b").unwrap(), vec![2, 3]);
+        assert!(matches!(scope.expand("a"), Err(Error::Value(_))));
+    }
+
+    #[test]
+    fn like_prefix_range() {
+        let string = |s: &str| Value::String(s.to_string());
+        assert_eq!(
+            super::like_prefix_range("Jo%"),
+            Some((Bound::Included(string("Jo")), Bound::Excluded(string("Jp"))))
+        );
+        assert_eq!(
+            super::like_prefix_range("a\u{D7FF}%"),
+            Some((
+                Bound::Included(string("a\u{D7FF}")),
+                Bound::Excluded(string("a\u{E000}"))
+            ))
+        );
+        assert_eq!(
+            super::like_prefix_range("a\u{10FFFF}%"),
+            Some((
+                Bound::Included(string("a\u{10FFFF}")),
+                Bound::Excluded(string("b"))
+            ))
+        );
+        assert_eq!(
+            super::like_prefix_range("\u{10FFFF}%"),
+            Some((Bound::Included(string("\u{10FFFF}")), Bound::Unbounded))
+        );
+        for pattern in ["Jo", "%", "%Jo%", "J_%", "Jo%%", "J%o", "Jo\\%", "J\\\\%"] {
+            assert_eq!(super::like_prefix_range(pattern), None, "{}", pattern);
+        }
+    }
+}
@@ -1,8 +1,78 @@
-use crate::sql::catalog::Table;
+use crate::sql::catalog::{Index, Table};
+use crate::sql::parser::ddl::AlterAction;
+use crate::sql::parser::dql::Order;
 use crate::sql::types::expression::Expression;
+use crate::sql::types::Value;
+use std::ops::Bound;
+
+/// An aggregate function, which folds the values of all rows into one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    Count,
+    Max,
+    Min,
+}
+
+impl Aggregate {
+    /// Find the aggregate function by its name, the name is matched case-insensitively
+    pub fn lookup(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "COUNT" => Some(Aggregate::Count),
+            "MAX" => Some(Aggregate::Max),
+            "MIN" => Some(Aggregate::Min),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Aggregate::Count => "count",
+            Aggregate::Max => "max",
+            Aggregate::Min => "min",
+        }
+    }
+}
+
+/// An aggregate with its argument and its label
+pub type AggregateItem = (Aggregate, Expression, Option<String>);
+
+/// How [`Node::IndexScan`] looks up the index of a column
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lookup {
+    /// The records whose value equals the key
+    Equal(Value),
+    /// The records whose value is within the range
+    Range(Bound<Value>, Bound<Value>),
+    /// The records whose leading columns equal the key, looked up in the index of several
+    /// columns by its name. The column of the lookup is the first column of the index
+    Prefix(String, Vec<Value>),
+}
+
+/// A projected expression with its label
+pub type ProjectionItem = (Expression, Option<String>);
 
 #[derive(Debug)]
 pub enum Node {
+    /// Fold the rows of source into one row per group, NULLs are ignored by every aggregate.
+    /// The output rows are the values of `group_by` followed by the aggregates, without
+    /// `group_by` all the rows form one group
+    Aggregate {
+        source: Box<Node>,
+        group_by: Vec<Expression>,
+        aggregates: Vec<AggregateItem>,
+    },
+    Analyze {
+        table: String,
+    },
+    AlterTable {
+        table: String,
+        action: AlterAction,
+    },
+    /// Create the index on the columns of table
+    CreateIndex {
+        table: String,
+        index: Index,
+    },
     CreateTable {
         schema: Table,
     },
@@ -14,10 +84,46 @@ pub enum Node {
         table: String,
         if_exists: bool,
     },
+    /// Scan the rows found by the lookups of indexed columns,
+    /// then filter them by the rest of `WHERE`
+    IndexScan {
+        table: String,
+        alias: Option<String>,
+        /// The column positions and their lookups, the rows must match all of them
+        lookups: Vec<(usize, Lookup)>,
+        filter: Option<Expression>,
+    },
+    /// `MIN` or `MAX` of an indexed column, read from one end of its index
+    IndexExtreme {
+        table: String,
+        column: usize,
+        aggregate: Aggregate,
+        label: Option<String>,
+    },
+    /// Insert the rows of source, which is either [`Node::Values`] or a query
     Insert {
         table: String,
         columns: Vec<String>,
-        values: Vec<Vec<Expression>>,
+        source: Box<Node>,
+    },
+    Limit {
+        source: Box<Node>,
+        limit: usize,
+    },
+    Offset {
+        source: Box<Node>,
+        offset: usize,
+    },
+    Order {
+        source: Box<Node>,
+        orders: Vec<(Expression, Order)>,
+    },
+    Projection {
+        source: Box<Node>,
+        expressions: Vec<ProjectionItem>,
+    },
+    Reindex {
+        table: String,
     },
     Scan {
         table: String,
@@ -28,5 +134,12 @@ pub enum Node {
         table: String,
         source: Box<Node>,
         expressions: Vec<(usize, Option<String>, Expression)>,
+        /// Evaluated on the updated rows, which are returned instead of the count
+        returning: Option<Vec<ProjectionItem>>,
+    },
+    /// Rows of constant expressions without columns,
+    /// the source of `INSERT ... VALUES` and of a select without `FROM`
+    Values {
+        rows: Vec<Vec<Expression>>,
     },
 }
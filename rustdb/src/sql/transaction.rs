@@ -1,7 +1,11 @@
 use crate::sql::catalog::Catalog;
-use crate::sql::types::Row;
+use crate::sql::types::{CastMode, Row, Value};
 use crate::sql::SqlResult;
+use crate::storage::engine::TableStats;
+use crate::storage::RecordId;
+use futures::Stream;
 use std::future::Future;
+use std::ops::Bound;
 
 /// Transaction trait
 pub trait Transaction: Catalog {
@@ -11,9 +15,83 @@ pub trait Transaction: Catalog {
 
     fn insert(&self, table: &str, row: Row) -> impl Future<Output = SqlResult<()>>;
 
-    fn read(&self, table: &str, key: &Row) -> impl Future<Output = SqlResult<Option<Row>>>;
+    fn read(&self, table: &str, key: &Value) -> impl Future<Output = SqlResult<Option<Row>>>;
 
-    fn delete(&self, table: &str, key: &Row) -> impl Future<Output = SqlResult<Option<Row>>>;
+    fn delete(&self, table: &str, key: &Value) -> impl Future<Output = SqlResult<Option<Row>>>;
 
-    fn update(&self, table: &str, row: Row) -> impl Future<Output = SqlResult<Option<Row>>>;
+    /// Replace the row which has the same primary key
+    fn update(&self, table: &str, row: Row) -> impl Future<Output = SqlResult<Option<()>>>;
+
+    /// Hand out the next value of the table's auto-increment sequence
+    fn next_sequence(&self, table: &str) -> impl Future<Output = SqlResult<i64>>;
+
+    /// Rebuild the primary index of table, returns the number of indexed rows
+    fn reindex(&self, table: &str) -> impl Future<Output = SqlResult<usize>>;
+
+    /// Refresh the statistics of table
+    fn analyze(&self, table: &str) -> impl Future<Output = SqlResult<TableStats>>;
+
+    /// Look up the records whose column equals the key in the index of the column,
+    /// `None` if the column isn't indexed
+    fn search_index(
+        &self,
+        table: &str,
+        column: usize,
+        key: &Value,
+    ) -> impl Future<Output = SqlResult<Option<Vec<RecordId>>>>;
+
+    /// Look up the records whose leading columns of the index equal the prefix,
+    /// `None` if the table has no such index
+    fn search_composite_index(
+        &self,
+        table: &str,
+        index: &str,
+        prefix: &[Value],
+    ) -> impl Future<Output = SqlResult<Option<Vec<RecordId>>>>;
+
+    /// Delete the rows whose primary key is within the range, returns the deleted rows
+    fn delete_range(
+        &self,
+        table: &str,
+        start: Bound<&Value>,
+        end: Bound<&Value>,
+    ) -> impl Future<Output = SqlResult<Vec<Row>>>;
+
+    /// Look up the records whose column is within the range in the index of the column,
+    /// `None` if the column isn't indexed
+    fn search_index_range(
+        &self,
+        table: &str,
+        column: usize,
+        start: Bound<&Value>,
+        end: Bound<&Value>,
+    ) -> impl Future<Output = SqlResult<Option<Vec<RecordId>>>>;
+
+    /// The smallest or largest non-null value of the column read from its index,
+    /// `None` if the index can't tell and the table must be scanned
+    fn index_extreme(
+        &self,
+        table: &str,
+        column: usize,
+        largest: bool,
+    ) -> impl Future<Output = SqlResult<Option<Value>>>;
+
+    /// Read the rows of the records, the deleted ones are skipped
+    fn read_records(
+        &self,
+        table: &str,
+        record_ids: &[RecordId],
+    ) -> impl Future<Output = SqlResult<Vec<Row>>>;
+
+    /// The bytes of rows each buffering operator, like sort, may hold, `None` if unlimited
+    fn memory_budget(&self) -> Option<usize>;
+
+    /// How the expressions of queries convert between values
+    fn cast_mode(&self) -> CastMode;
+
+    /// Scan all rows of table in primary key order
+    fn scan(
+        &self,
+        table: &str,
+    ) -> impl Future<Output = SqlResult<impl Stream<Item = SqlResult<Row>>>>;
 }
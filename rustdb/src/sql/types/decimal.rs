@@ -0,0 +1,274 @@
+use crate::sql::Error;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt::Formatter;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+/// An exact decimal number, whose value is `mantissa / 10^scale`.
+/// Decimals of different scales compare by value, so `1.50` equals `1.5`,
+/// but each keeps its own scale when it's displayed.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u32,
+}
+
+impl Decimal {
+    /// The largest scale, `10^MAX_SCALE` still fits in i128
+    pub const MAX_SCALE: u32 = 38;
+
+    pub fn new(mantissa: i128, scale: u32) -> Self {
+        assert!(
+            scale <= Self::MAX_SCALE,
+            "decimal scale {} too large",
+            scale
+        );
+        Self { mantissa, scale }
+    }
+
+    pub fn mantissa(&self) -> i128 {
+        self.mantissa
+    }
+
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    /// The mantissa at a scale not less than the decimal's, `None` if it overflows
+    fn rescale(&self, scale: u32) -> Option<i128> {
+        10i128
+            .checked_pow(scale.checked_sub(self.scale)?)?
+            .checked_mul(self.mantissa)
+    }
+
+    /// The mantissas of both decimals at the larger of their scales, `None` if either overflows
+    fn align(&self, other: &Decimal) -> Option<(i128, i128, u32)> {
+        let scale = self.scale.max(other.scale);
+        Some((self.rescale(scale)?, other.rescale(scale)?, scale))
+    }
+
+    /// The same value at the smallest scale, without the trailing zeros of the fraction
+    fn normalize(&self) -> Decimal {
+        let mut decimal = *self;
+        while decimal.scale > 0 && decimal.mantissa % 10 == 0 {
+            decimal.mantissa /= 10;
+            decimal.scale -= 1;
+        }
+        decimal
+    }
+
+    /// Convert into the nearest f64, which is lossy for most decimals
+    pub fn to_f64(&self) -> f64 {
+        self.mantissa as f64 / 10f64.powi(self.scale as i32)
+    }
+
+    /// The nearest integer, halves are rounded away from zero
+    pub fn round(&self) -> i128 {
+        if self.scale == 0 {
+            return self.mantissa;
+        }
+        let unit = 10i128.pow(self.scale);
+        let (quotient, remainder) = (self.mantissa / unit, self.mantissa % unit);
+        if remainder.unsigned_abs() * 2 >= unit.unsigned_abs() {
+            quotient + self.mantissa.signum()
+        } else {
+            quotient
+        }
+    }
+
+    /// The sum at the larger scale of the two, `None` if it overflows
+    pub fn checked_add(&self, other: &Decimal) -> Option<Decimal> {
+        let (lhs, rhs, scale) = self.align(other)?;
+        Some(Decimal::new(lhs.checked_add(rhs)?, scale))
+    }
+
+    /// The difference at the larger scale of the two, `None` if it overflows
+    pub fn checked_sub(&self, other: &Decimal) -> Option<Decimal> {
+        let (lhs, rhs, scale) = self.align(other)?;
+        Some(Decimal::new(lhs.checked_sub(rhs)?, scale))
+    }
+
+    /// The negation at the same scale, `None` if it overflows
+    pub fn checked_neg(&self) -> Option<Decimal> {
+        Some(Decimal::new(self.mantissa.checked_neg()?, self.scale))
+    }
+
+    /// The product at the sum of both scales, `None` if it overflows
+    pub fn checked_mul(&self, other: &Decimal) -> Option<Decimal> {
+        let (lhs, rhs) = (self.normalize(), other.normalize());
+        let scale = lhs.scale + rhs.scale;
+        if scale > Self::MAX_SCALE {
+            return None;
+        }
+        Some(Decimal::new(lhs.mantissa.checked_mul(rhs.mantissa)?, scale))
+    }
+}
+
+impl From<i128> for Decimal {
+    fn from(integer: i128) -> Self {
+        Decimal::new(integer, 0)
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (lhs, rhs) = (self.normalize(), other.normalize());
+        match lhs.align(&rhs) {
+            Some((lhs, rhs, _)) => lhs.cmp(&rhs),
+            // only the decimal of the smaller scale is scaled up, and it overflows
+            // because its magnitude is larger than the other's
+            None if lhs.scale < rhs.scale => lhs.mantissa.signum().cmp(&0),
+            None => 0.cmp(&rhs.mantissa.signum()),
+        }
+    }
+}
+
+impl Hash for Decimal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let decimal = self.normalize();
+        decimal.mantissa.hash(state);
+        decimal.scale.hash(state);
+    }
+}
+
+impl std::fmt::Display for Decimal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        let magnitude = self.mantissa.unsigned_abs();
+        if self.scale == 0 {
+            return write!(f, "{}{}", sign, magnitude);
+        }
+        let unit = 10u128.pow(self.scale);
+        write!(
+            f,
+            "{}{}.{:0width$}",
+            sign,
+            magnitude / unit,
+            magnitude % unit,
+            width = self.scale as usize
+        )
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = Error;
+
+    /// Parse a decimal like `-12.50`, the scale is the number of the fraction digits
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::FromStr(format!("Can't convert {} to Decimal", s));
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        if integer.is_empty() && fraction.is_empty() {
+            return Err(invalid());
+        }
+        let scale = u32::try_from(fraction.len()).map_err(|_| invalid())?;
+        if scale > Self::MAX_SCALE {
+            return Err(invalid());
+        }
+        let mut mantissa: i128 = 0;
+        for char in integer.chars().chain(fraction.chars()) {
+            let digit = char.to_digit(10).ok_or_else(invalid)?;
+            mantissa = mantissa
+                .checked_mul(10)
+                .and_then(|mantissa| mantissa.checked_add(digit as i128))
+                .ok_or_else(invalid)?;
+        }
+        Ok(Decimal::new(
+            if negative { -mantissa } else { mantissa },
+            scale,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decimal(s: &str) -> Decimal {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_display() {
+        for s in [
+            "0",
+            "1.50",
+            "-12.05",
+            "0.001",
+            "170141183460469231731687303715884105727",
+        ] {
+            assert_eq!(decimal(s).to_string(), s);
+        }
+        assert_eq!(decimal("+.5").to_string(), "0.5");
+        assert_eq!(decimal("-0.5"), Decimal::new(-5, 1));
+        for s in [
+            "",
+            ".",
+            "1.2.3",
+            "1e5",
+            "abc",
+            "1.000000000000000000000000000000000000001",
+        ] {
+            assert!(s.parse::<Decimal>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn compare() {
+        assert_eq!(decimal("1.50"), decimal("1.5"));
+        assert!(decimal("2.00") > decimal("1"));
+        assert!(decimal("-1.5") < decimal("-1.49"));
+        // aligning the scales overflows, but the order is still known
+        let huge = Decimal::new(i128::MAX, 0);
+        let tiny = Decimal::new(1, 30);
+        assert!(huge > tiny);
+        assert!(tiny < huge);
+        assert!(Decimal::new(i128::MIN, 0) < tiny);
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(
+            decimal("1.5").checked_add(&decimal("0.25")).unwrap(),
+            decimal("1.75")
+        );
+        assert_eq!(
+            decimal("1")
+                .checked_sub(&decimal("0.01"))
+                .unwrap()
+                .to_string(),
+            "0.99"
+        );
+        assert_eq!(
+            decimal("1.50").checked_mul(&decimal("2.0")).unwrap(),
+            decimal("3")
+        );
+        assert!(Decimal::new(i128::MAX, 0)
+            .checked_add(&Decimal::from(1))
+            .is_none());
+        assert_eq!(decimal("0.5").to_f64(), 0.5);
+        assert_eq!(decimal("2.5").round(), 3);
+        assert_eq!(decimal("-2.5").round(), -3);
+        assert_eq!(decimal("2.49").round(), 2);
+        assert_eq!(decimal("-0.4").round(), 0);
+        assert_eq!(Decimal::new(i128::MAX, 38).round(), 2);
+    }
+}
@@ -1,11 +1,26 @@
-use crate::sql::types::Value;
+use crate::sql::catalog::Column;
+use crate::sql::types::decimal::Decimal;
+use crate::sql::types::{function, CastMode, DataType, Row, Value};
 use crate::sql::{Error, SqlResult};
 use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::ops::Bound;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Expression {
     Const(Value),
+    /// The value at the given position of the row which is being evaluated
+    Column(usize),
+    /// A scalar function call, the name must be registered in [`function::lookup`]
+    Function(String, Vec<Expression>),
+    /// The value of the first branch whose condition is true, or the default
+    Case {
+        branches: Vec<(Expression, Expression)>,
+        default: Option<Box<Expression>>,
+    },
+    /// `CAST(expression AS datatype)`, see [`Value::cast_explicit`]
+    Cast(Box<Expression>, DataType),
 
     And(Box<Expression>, Box<Expression>),
     Or(Box<Expression>, Box<Expression>),
@@ -13,6 +28,8 @@ pub enum Expression {
 
     Equal(Box<Expression>, Box<Expression>),
     GreaterThan(Box<Expression>, Box<Expression>),
+    /// Like `!=`, but two NULLs are equal and NULL differs from any other value
+    IsDistinctFrom(Box<Expression>, Box<Expression>),
     IsNull(Box<Expression>),
     LessThan(Box<Expression>, Box<Expression>),
 
@@ -30,283 +47,882 @@ pub enum Expression {
 }
 
 impl Expression {
-    // TODO cast integer
-    pub fn evaluate(&self) -> SqlResult<Value> {
+    /// Evaluate the expression, the row is required when the expression contains [`Expression::Column`]
+    pub fn evaluate(&self, row: Option<&Row>) -> SqlResult<Value> {
+        self.evaluate_with(row, CastMode::Strict)
+    }
+
+    /// Split the expression into the operands of its top-level `AND`s
+    pub fn conjuncts(self) -> Vec<Expression> {
+        match self {
+            Expression::And(lhs, rhs) => {
+                let mut conjuncts = lhs.conjuncts();
+                conjuncts.extend(rhs.conjuncts());
+                conjuncts
+            }
+            expr => vec![expr],
+        }
+    }
+
+    /// The range of the key column which the predicate restricts it to, it recognizes
+    /// `=`, `>`, `>=`, `<`, `<=` between the column and a constant in either order,
+    /// and `BETWEEN`, which is planned as `>=` and `<=` joined by `AND`.
+    /// Returns `None` for any other predicate, or if the constant is NULL
+    pub fn as_index_bound(&self, key_column: usize) -> Option<(Bound<Value>, Bound<Value>)> {
+        use Bound::{Excluded, Included, Unbounded};
+        // the constant of a comparison with the key column, and whether the column is the lhs
+        let operands = |lhs: &Expression, rhs: &Expression| {
+            match (lhs, rhs) {
+                (Expression::Column(index), constant) if *index == key_column => {
+                    Some((constant.evaluate(None).ok()?, true))
+                }
+                (constant, Expression::Column(index)) if *index == key_column => {
+                    Some((constant.evaluate(None).ok()?, false))
+                }
+                _ => None,
+            }
+            .filter(|(value, _)| value != &Value::Null)
+        };
+        match self {
+            Expression::Equal(lhs, rhs) => {
+                let (value, _) = operands(lhs, rhs)?;
+                Some((Included(value.clone()), Included(value)))
+            }
+            Expression::GreaterThan(lhs, rhs) => match operands(lhs, rhs)? {
+                (value, true) => Some((Excluded(value), Unbounded)),
+                (value, false) => Some((Unbounded, Excluded(value))),
+            },
+            Expression::LessThan(lhs, rhs) => match operands(lhs, rhs)? {
+                (value, true) => Some((Unbounded, Excluded(value))),
+                (value, false) => Some((Excluded(value), Unbounded)),
+            },
+            // `a >= b` and `a <= b` are planned as `a = b OR a > b` and `a = b OR a < b`
+            Expression::Or(lhs, rhs) => match (lhs.as_ref(), rhs.as_ref()) {
+                (
+                    Expression::Equal(a, b),
+                    Expression::GreaterThan(c, d) | Expression::LessThan(c, d),
+                ) if a == c && b == d => {
+                    let included = |bound| match bound {
+                        Excluded(value) => Included(value),
+                        bound => bound,
+                    };
+                    let (start, end) = rhs.as_index_bound(key_column)?;
+                    Some((included(start), included(end)))
+                }
+                _ => None,
+            },
+            Expression::And(lhs, rhs) => {
+                let (lhs_start, lhs_end) = lhs.as_index_bound(key_column)?;
+                let (rhs_start, rhs_end) = rhs.as_index_bound(key_column)?;
+                Some((
+                    tighter(lhs_start, rhs_start, Ordering::Greater),
+                    tighter(lhs_end, rhs_end, Ordering::Less),
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Push [`Expression::Not`] down through `AND`, `OR` and comparisons, and remove double
+    /// negations. The normalized expression evaluates to the same value, NULL included,
+    /// as long as the negated operands are booleans.
+    pub fn normalize(self) -> Expression {
+        match self {
+            Expression::Not(expr) => expr.negate(),
+            Expression::And(lhs, rhs) => {
+                Expression::And(Box::new(lhs.normalize()), Box::new(rhs.normalize()))
+            }
+            Expression::Or(lhs, rhs) => {
+                Expression::Or(Box::new(lhs.normalize()), Box::new(rhs.normalize()))
+            }
+            expr => expr,
+        }
+    }
+
+    /// The normalized form of `NOT self`
+    fn negate(self) -> Expression {
+        match self {
+            Expression::Not(expr) => expr.normalize(),
+            Expression::And(lhs, rhs) => {
+                Expression::Or(Box::new(lhs.negate()), Box::new(rhs.negate()))
+            }
+            // `a >= b` and `a <= b` are planned as `a = b OR a > b` and `a = b OR a < b`
+            Expression::Or(lhs, rhs) => match (*lhs, *rhs) {
+                (Expression::Equal(a, b), Expression::GreaterThan(c, d)) if a == c && b == d => {
+                    Expression::LessThan(a, b)
+                }
+                (Expression::Equal(a, b), Expression::LessThan(c, d)) if a == c && b == d => {
+                    Expression::GreaterThan(a, b)
+                }
+                (lhs, rhs) => Expression::And(Box::new(lhs.negate()), Box::new(rhs.negate())),
+            },
+            Expression::GreaterThan(lhs, rhs) => Expression::Or(
+                Box::new(Expression::Equal(lhs.clone(), rhs.clone())),
+                Box::new(Expression::LessThan(lhs, rhs)),
+            ),
+            Expression::LessThan(lhs, rhs) => Expression::Or(
+                Box::new(Expression::Equal(lhs.clone(), rhs.clone())),
+                Box::new(Expression::GreaterThan(lhs, rhs)),
+            ),
+            expr => Expression::Not(Box::new(expr.normalize())),
+        }
+    }
+
+    /// Evaluate the subtrees which don't depend on the row once and cache them as
+    /// [`Expression::Const`], so only the nodes reading columns are evaluated per row.
+    /// A subtree failing to evaluate is kept, the error is raised when a row is evaluated.
+    pub fn precompute(self) -> Expression {
+        let unary = |expr: Box<Expression>, build: fn(Box<Expression>) -> Expression| {
+            build(Box::new(expr.precompute()))
+        };
+        let binary =
+            |lhs: Box<Expression>,
+             rhs: Box<Expression>,
+             build: fn(Box<Expression>, Box<Expression>) -> Expression| {
+                build(Box::new(lhs.precompute()), Box::new(rhs.precompute()))
+            };
+        let expr = match self {
+            Expression::Const(_) | Expression::Column(_) => return self,
+            Expression::Function(name, args) => {
+                Expression::Function(name, args.into_iter().map(Expression::precompute).collect())
+            }
+            Expression::Case { branches, default } => Expression::Case {
+                branches: branches
+                    .into_iter()
+                    .map(|(condition, value)| (condition.precompute(), value.precompute()))
+                    .collect(),
+                default: default.map(|default| Box::new(default.precompute())),
+            },
+            Expression::Cast(expr, datatype) => {
+                Expression::Cast(Box::new(expr.precompute()), datatype)
+            }
+            Expression::And(lhs, rhs) => binary(lhs, rhs, Expression::And),
+            Expression::Or(lhs, rhs) => binary(lhs, rhs, Expression::Or),
+            Expression::Not(expr) => unary(expr, Expression::Not),
+            Expression::Equal(lhs, rhs) => binary(lhs, rhs, Expression::Equal),
+            Expression::GreaterThan(lhs, rhs) => binary(lhs, rhs, Expression::GreaterThan),
+            Expression::IsDistinctFrom(lhs, rhs) => binary(lhs, rhs, Expression::IsDistinctFrom),
+            Expression::IsNull(expr) => unary(expr, Expression::IsNull),
+            Expression::LessThan(lhs, rhs) => binary(lhs, rhs, Expression::LessThan),
+            Expression::Add(lhs, rhs) => binary(lhs, rhs, Expression::Add),
+            Expression::Assert(expr) => unary(expr, Expression::Assert),
+            Expression::Factorial(expr) => unary(expr, Expression::Factorial),
+            Expression::Modulo(lhs, rhs) => binary(lhs, rhs, Expression::Modulo),
+            Expression::Subtract(lhs, rhs) => binary(lhs, rhs, Expression::Subtract),
+            Expression::Multiply(lhs, rhs) => binary(lhs, rhs, Expression::Multiply),
+            Expression::Divide(lhs, rhs) => binary(lhs, rhs, Expression::Divide),
+            Expression::Exponentiate(lhs, rhs) => binary(lhs, rhs, Expression::Exponentiate),
+            Expression::Negate(expr) => unary(expr, Expression::Negate),
+            Expression::Like(lhs, rhs) => binary(lhs, rhs, Expression::Like),
+        };
+        if !expr
+            .children()
+            .iter()
+            .all(|child| matches!(child, Expression::Const(_)))
+        {
+            return expr;
+        }
+        match expr.evaluate(None) {
+            Ok(value) => Expression::Const(value),
+            Err(_) => expr,
+        }
+    }
+
+    /// The direct sub-expressions
+    fn children(&self) -> Vec<&Expression> {
+        match self {
+            Expression::Const(_) | Expression::Column(_) => vec![],
+            Expression::Function(_, args) => args.iter().collect(),
+            Expression::Case { branches, default } => branches
+                .iter()
+                .flat_map(|(condition, value)| [condition, value])
+                .chain(default.as_deref())
+                .collect(),
+            Expression::Cast(expr, _)
+            | Expression::Not(expr)
+            | Expression::IsNull(expr)
+            | Expression::Assert(expr)
+            | Expression::Factorial(expr)
+            | Expression::Negate(expr) => vec![expr],
+            Expression::And(lhs, rhs)
+            | Expression::Or(lhs, rhs)
+            | Expression::Equal(lhs, rhs)
+            | Expression::GreaterThan(lhs, rhs)
+            | Expression::IsDistinctFrom(lhs, rhs)
+            | Expression::LessThan(lhs, rhs)
+            | Expression::Add(lhs, rhs)
+            | Expression::Modulo(lhs, rhs)
+            | Expression::Subtract(lhs, rhs)
+            | Expression::Multiply(lhs, rhs)
+            | Expression::Divide(lhs, rhs)
+            | Expression::Exponentiate(lhs, rhs)
+            | Expression::Like(lhs, rhs) => vec![lhs, rhs],
+        }
+    }
+
+    /// Statically infer the datatype of the evaluated value from the schema of the input row.
+    /// `None` means the type is unknown, e.g. the expression is a NULL constant.
+    pub fn result_type(&self, columns: &[Column]) -> SqlResult<Option<DataType>> {
+        self.result_type_with(columns, CastMode::Strict)
+    }
+
+    /// Like [`Expression::result_type`], see [`Expression::infer_type_with`] for the mode
+    pub fn result_type_with(
+        &self,
+        columns: &[Column],
+        mode: CastMode,
+    ) -> SqlResult<Option<DataType>> {
+        self.infer_type_with(
+            &columns
+                .iter()
+                .map(|column| Some(column.datatype.clone()))
+                .collect::<Vec<_>>(),
+            mode,
+        )
+    }
+
+    /// Like [`Expression::result_type`], but the datatypes of input columns may be unknown
+    pub fn infer_type(&self, columns: &[Option<DataType>]) -> SqlResult<Option<DataType>> {
+        self.infer_type_with(columns, CastMode::Strict)
+    }
+
+    /// Like [`Expression::infer_type`], the mode decides whether strings and booleans are
+    /// operands of arithmetic
+    pub fn infer_type_with(
+        &self,
+        columns: &[Option<DataType>],
+        mode: CastMode,
+    ) -> SqlResult<Option<DataType>> {
+        Ok(match self {
+            Expression::Const(value) => value.datatype(),
+            Expression::Column(index) => columns
+                .get(*index)
+                .ok_or(Error::NotFound("column", index.to_string()))?
+                .clone(),
+            Expression::Function(name, args) => {
+                function::lookup(name).ok_or(Error::NotFound("function", name.clone()))?;
+                function::result_type(
+                    name,
+                    &args
+                        .iter()
+                        .map(|arg| arg.infer_type_with(columns, mode))
+                        .collect::<SqlResult<Vec<_>>>()?,
+                )
+            }
+            Expression::Case { branches, default } => {
+                let mut datatype = None;
+                for (condition, value) in branches {
+                    condition.infer_type_with(columns, mode)?;
+                    datatype = datatype.or(value.infer_type_with(columns, mode)?);
+                }
+                match default {
+                    Some(default) => datatype.or(default.infer_type_with(columns, mode)?),
+                    None => datatype,
+                }
+            }
+            Expression::Cast(expr, datatype) => {
+                expr.infer_type_with(columns, mode)?;
+                Some(datatype.clone())
+            }
+            Expression::And(lhs, rhs)
+            | Expression::Or(lhs, rhs)
+            | Expression::Equal(lhs, rhs)
+            | Expression::GreaterThan(lhs, rhs)
+            | Expression::IsDistinctFrom(lhs, rhs)
+            | Expression::LessThan(lhs, rhs)
+            | Expression::Like(lhs, rhs) => {
+                lhs.infer_type_with(columns, mode)?;
+                rhs.infer_type_with(columns, mode)?;
+                Some(DataType::Boolean)
+            }
+            Expression::Not(expr) | Expression::IsNull(expr) => {
+                expr.infer_type_with(columns, mode)?;
+                Some(DataType::Boolean)
+            }
+            Expression::Add(lhs, rhs) => widen("add", lhs, rhs, columns, mode)?,
+            Expression::Subtract(lhs, rhs) => widen("subtract", lhs, rhs, columns, mode)?,
+            Expression::Multiply(lhs, rhs) => widen("multiply", lhs, rhs, columns, mode)?,
+            Expression::Divide(lhs, rhs) => widen("divide", lhs, rhs, columns, mode)?,
+            Expression::Modulo(lhs, rhs) => widen("modulo", lhs, rhs, columns, mode)?,
+            Expression::Exponentiate(lhs, rhs) => {
+                match widen("exponentiate", lhs, rhs, columns, mode)? {
+                    // an integer power turns into a double for a negative exponent or once it
+                    // overflows, so its type is only known from the value
+                    Some(datatype) if datatype.is_integer() => None,
+                    // a float exponent of a float base, otherwise the float is widened into double
+                    Some(DataType::Float)
+                        if lhs.infer_type_with(columns, mode)?
+                            == rhs.infer_type_with(columns, mode)? =>
+                    {
+                        Some(DataType::Float)
+                    }
+                    _ => Some(DataType::Double),
+                }
+            }
+            Expression::Factorial(expr) => match expr.infer_type_with(columns, mode)? {
+                Some(datatype) if !datatype.is_integer() => {
+                    return Err(Error::ValueNotMatch("factorial", datatype.to_string()))
+                }
+                _ => Some(DataType::Bigint),
+            },
+            Expression::Assert(expr) | Expression::Negate(expr) => {
+                match expr.infer_type_with(columns, mode)? {
+                    Some(datatype) if rank(&datatype).is_none() => {
+                        return Err(Error::ValueNotMatch("arithmetic", datatype.to_string()))
+                    }
+                    datatype => datatype,
+                }
+            }
+        })
+    }
+
+    /// Like [`Expression::evaluate`], the mode decides whether booleans compare with integers
+    pub fn evaluate_with(&self, row: Option<&Row>, mode: CastMode) -> SqlResult<Value> {
         match self {
             Expression::Const(value) => Ok(value.clone()),
-            Expression::And(lhs, rhs) => Ok(match (lhs.evaluate()?, rhs.evaluate()?) {
-                (Value::Boolean(lhs), Value::Boolean(rhs)) => Value::Boolean(lhs && rhs),
-                (Value::Null, Value::Null) => Value::Null,
-                (lhs, rhs) => {
-                    return Err(Error::ValuesNotMatch(
-                        "and",
-                        lhs.to_string(),
-                        rhs.to_string(),
-                    ))
-                }
+            Expression::Column(index) => row
+                .and_then(|row| row.get(*index))
+                .cloned()
+                .ok_or(Error::NotFound("column", index.to_string())),
+            Expression::Function(name, args) => {
+                let function =
+                    function::lookup(name).ok_or(Error::NotFound("function", name.clone()))?;
+                #[cfg(test)]
+                tests::FUNCTION_CALLS.with(|calls| calls.set(calls.get() + 1));
+                function(
+                    &args
+                        .iter()
+                        .map(|arg| arg.evaluate_with(row, mode))
+                        .collect::<SqlResult<Vec<_>>>()?,
+                )
+            }
+            Expression::Case { branches, default } => {
+                for (condition, value) in branches {
+                    match condition.evaluate_with(row, mode)? {
+                        Value::Boolean(true) => return value.evaluate_with(row, mode),
+                        Value::Boolean(false) | Value::Null => {}
+                        condition => {
+                            return Err(Error::ValueNotMatch("case", condition.to_string()))
+                        }
+                    }
+                }
+                match default {
+                    Some(default) => default.evaluate_with(row, mode),
+                    None => Ok(Value::Null),
+                }
+            }
+            Expression::Cast(expr, datatype) => {
+                expr.evaluate_with(row, mode)?.cast_explicit(datatype)
+            }
+            // The right side isn't evaluated once the left side decides the result
+            Expression::And(lhs, rhs) => Ok(match lhs.evaluate_with(row, mode)? {
+                Value::Boolean(false) => Value::Boolean(false),
+                lhs => match (lhs, rhs.evaluate_with(row, mode)?) {
+                    (Value::Boolean(lhs), Value::Boolean(rhs)) => Value::Boolean(lhs && rhs),
+                    (Value::Null, Value::Boolean(false)) => Value::Boolean(false),
+                    (Value::Null, Value::Null | Value::Boolean(true))
+                    | (Value::Boolean(true), Value::Null) => Value::Null,
+                    (lhs, rhs) => {
+                        return Err(Error::ValuesNotMatch(
+                            "and",
+                            lhs.to_string(),
+                            rhs.to_string(),
+                        ))
+                    }
+                },
             }),
-            Expression::Or(lhs, rhs) => Ok(match (lhs.evaluate()?, rhs.evaluate()?) {
-                (Value::Boolean(lhs), Value::Boolean(rhs)) => Value::Boolean(lhs || rhs),
-                (Value::Null, Value::Null) => Value::Null,
-                (lhs, rhs) => {
-                    return Err(Error::ValuesNotMatch(
-                        "or",
-                        lhs.to_string(),
-                        rhs.to_string(),
-                    ))
-                }
+            Expression::Or(lhs, rhs) => Ok(match lhs.evaluate_with(row, mode)? {
+                Value::Boolean(true) => Value::Boolean(true),
+                lhs => match (lhs, rhs.evaluate_with(row, mode)?) {
+                    (Value::Boolean(lhs), Value::Boolean(rhs)) => Value::Boolean(lhs || rhs),
+                    (Value::Null, Value::Boolean(true)) => Value::Boolean(true),
+                    (Value::Null, Value::Null | Value::Boolean(false))
+                    | (Value::Boolean(false), Value::Null) => Value::Null,
+                    (lhs, rhs) => {
+                        return Err(Error::ValuesNotMatch(
+                            "or",
+                            lhs.to_string(),
+                            rhs.to_string(),
+                        ))
+                    }
+                },
             }),
-            Expression::Not(expr) => Ok(match expr.evaluate()? {
+            Expression::Not(expr) => Ok(match expr.evaluate_with(row, mode)? {
                 Value::Null => Value::Null,
                 Value::Boolean(expr) => Value::Boolean(!expr),
                 expr => return Err(Error::ValueNotMatch("not", expr.to_string())),
             }),
-            Expression::Equal(lhs, rhs) => Ok(match (lhs.evaluate()?, rhs.evaluate()?) {
-                (Value::Boolean(lhs), Value::Boolean(rhs)) => Value::Boolean(lhs == rhs),
-                (Value::Tinyint(lhs), Value::Tinyint(rhs)) => Value::Boolean(lhs == rhs),
-                (Value::Tinyint(lhs), Value::Smallint(rhs)) => Value::Boolean((lhs as i32) == rhs),
-                (Value::Tinyint(lhs), Value::Integer(rhs)) => Value::Boolean(lhs as i64 == rhs),
-                (Value::Tinyint(lhs), Value::Bigint(rhs)) => Value::Boolean(lhs as i128 == rhs),
-                (Value::Smallint(lhs), Value::Tinyint(rhs)) => Value::Boolean(lhs == rhs as i32),
-                (Value::Smallint(lhs), Value::Smallint(rhs)) => Value::Boolean(lhs == rhs),
-                (Value::Smallint(lhs), Value::Integer(rhs)) => Value::Boolean(lhs as i64 == rhs),
-                (Value::Smallint(lhs), Value::Bigint(rhs)) => Value::Boolean(lhs as i128 == rhs),
-                (Value::Integer(lhs), Value::Tinyint(rhs)) => Value::Boolean(lhs == rhs as i64),
-                (Value::Integer(lhs), Value::Smallint(rhs)) => Value::Boolean(lhs == rhs as i64),
-                (Value::Integer(lhs), Value::Integer(rhs)) => Value::Boolean(lhs == rhs),
-                (Value::Integer(lhs), Value::Bigint(rhs)) => Value::Boolean(lhs as i128 == rhs),
-                (Value::Bigint(lhs), Value::Tinyint(rhs)) => Value::Boolean(lhs == rhs as i128),
-                (Value::Bigint(lhs), Value::Smallint(rhs)) => Value::Boolean(lhs == rhs as i128),
-                (Value::Bigint(lhs), Value::Integer(rhs)) => Value::Boolean(lhs == rhs as i128),
-                (Value::Bigint(lhs), Value::Bigint(rhs)) => Value::Boolean(lhs == rhs),
-                (Value::Float(lhs), Value::Float(rhs)) => Value::Boolean(lhs == rhs),
-                (Value::Float(OrderedFloat(lhs)), Value::Double(rhs)) => {
-                    Value::Boolean(OrderedFloat(lhs as f64) == rhs)
-                }
-                (Value::Double(lhs), Value::Float(OrderedFloat(rhs))) => {
-                    Value::Boolean(lhs == OrderedFloat(rhs as f64))
-                }
-                (Value::Double(lhs), Value::Double(rhs)) => Value::Boolean(lhs == rhs),
-                (Value::String(lhs), Value::String(rhs)) => Value::Boolean(lhs == rhs),
-                (lhs, rhs) => {
-                    return Err(Error::ValuesNotMatch(
-                        "equal",
-                        lhs.to_string(),
-                        rhs.to_string(),
-                    ))
-                }
-            }),
-            Expression::GreaterThan(lhs, rhs) => Ok(match (lhs.evaluate()?, rhs.evaluate()?) {
-                (Value::Boolean(lhs), Value::Boolean(rhs)) => Value::Boolean(lhs & !rhs),
-                (Value::Tinyint(lhs), Value::Tinyint(rhs)) => Value::Boolean(lhs > rhs),
-                (Value::Tinyint(lhs), Value::Smallint(rhs)) => Value::Boolean((lhs as i32) > rhs),
-                (Value::Tinyint(lhs), Value::Integer(rhs)) => Value::Boolean(lhs as i64 > rhs),
-                (Value::Tinyint(lhs), Value::Bigint(rhs)) => Value::Boolean(lhs as i128 > rhs),
-                (Value::Smallint(lhs), Value::Tinyint(rhs)) => Value::Boolean(lhs > rhs as i32),
-                (Value::Smallint(lhs), Value::Smallint(rhs)) => Value::Boolean(lhs > rhs),
-                (Value::Smallint(lhs), Value::Integer(rhs)) => Value::Boolean(lhs as i64 > rhs),
-                (Value::Smallint(lhs), Value::Bigint(rhs)) => Value::Boolean(lhs as i128 > rhs),
-                (Value::Integer(lhs), Value::Tinyint(rhs)) => Value::Boolean(lhs > rhs as i64),
-                (Value::Integer(lhs), Value::Smallint(rhs)) => Value::Boolean(lhs > rhs as i64),
-                (Value::Integer(lhs), Value::Integer(rhs)) => Value::Boolean(lhs > rhs),
-                (Value::Integer(lhs), Value::Bigint(rhs)) => Value::Boolean(lhs as i128 > rhs),
-                (Value::Bigint(lhs), Value::Tinyint(rhs)) => Value::Boolean(lhs > rhs as i128),
-                (Value::Bigint(lhs), Value::Smallint(rhs)) => Value::Boolean(lhs > rhs as i128),
-                (Value::Bigint(lhs), Value::Integer(rhs)) => Value::Boolean(lhs > rhs as i128),
-                (Value::Bigint(lhs), Value::Bigint(rhs)) => Value::Boolean(lhs > rhs),
-                (Value::Float(lhs), Value::Float(rhs)) => Value::Boolean(lhs > rhs),
-                (Value::Float(OrderedFloat(lhs)), Value::Double(rhs)) => {
-                    Value::Boolean(OrderedFloat(lhs as f64) > rhs)
-                }
-                (Value::Double(lhs), Value::Float(OrderedFloat(rhs))) => {
-                    Value::Boolean(lhs > OrderedFloat(rhs as f64))
-                }
-                (Value::Double(lhs), Value::Double(rhs)) => Value::Boolean(lhs > rhs),
-                (Value::String(lhs), Value::String(rhs)) => Value::Boolean(lhs > rhs),
-                (lhs, rhs) => {
-                    return Err(Error::ValuesNotMatch(
-                        "great than",
-                        lhs.to_string(),
-                        rhs.to_string(),
-                    ))
-                }
-            }),
-            Expression::IsNull(expr) => Ok(match expr.evaluate()? {
+            Expression::Equal(lhs, rhs) => Ok(
+                match coerce(
+                    lhs.evaluate_with(row, mode)?,
+                    rhs.evaluate_with(row, mode)?,
+                    mode,
+                )? {
+                    (Value::Null, _) | (_, Value::Null) => Value::Null,
+                    (Value::Boolean(lhs), Value::Boolean(rhs)) => Value::Boolean(lhs == rhs),
+                    (Value::Tinyint(lhs), Value::Tinyint(rhs)) => Value::Boolean(lhs == rhs),
+                    (Value::Tinyint(lhs), Value::Smallint(rhs)) => {
+                        Value::Boolean((lhs as i32) == rhs)
+                    }
+                    (Value::Tinyint(lhs), Value::Integer(rhs)) => Value::Boolean(lhs as i64 == rhs),
+                    (Value::Tinyint(lhs), Value::Bigint(rhs)) => Value::Boolean(lhs as i128 == rhs),
+                    (Value::Smallint(lhs), Value::Tinyint(rhs)) => {
+                        Value::Boolean(lhs == rhs as i32)
+                    }
+                    (Value::Smallint(lhs), Value::Smallint(rhs)) => Value::Boolean(lhs == rhs),
+                    (Value::Smallint(lhs), Value::Integer(rhs)) => {
+                        Value::Boolean(lhs as i64 == rhs)
+                    }
+                    (Value::Smallint(lhs), Value::Bigint(rhs)) => {
+                        Value::Boolean(lhs as i128 == rhs)
+                    }
+                    (Value::Integer(lhs), Value::Tinyint(rhs)) => Value::Boolean(lhs == rhs as i64),
+                    (Value::Integer(lhs), Value::Smallint(rhs)) => {
+                        Value::Boolean(lhs == rhs as i64)
+                    }
+                    (Value::Integer(lhs), Value::Integer(rhs)) => Value::Boolean(lhs == rhs),
+                    (Value::Integer(lhs), Value::Bigint(rhs)) => Value::Boolean(lhs as i128 == rhs),
+                    (Value::Bigint(lhs), Value::Tinyint(rhs)) => Value::Boolean(lhs == rhs as i128),
+                    (Value::Bigint(lhs), Value::Smallint(rhs)) => {
+                        Value::Boolean(lhs == rhs as i128)
+                    }
+                    (Value::Bigint(lhs), Value::Integer(rhs)) => Value::Boolean(lhs == rhs as i128),
+                    (Value::Bigint(lhs), Value::Bigint(rhs)) => Value::Boolean(lhs == rhs),
+                    (Value::Float(lhs), Value::Float(rhs)) => Value::Boolean(lhs == rhs),
+                    (Value::Float(OrderedFloat(lhs)), Value::Double(rhs)) => {
+                        Value::Boolean(OrderedFloat(lhs as f64) == rhs)
+                    }
+                    (Value::Double(lhs), Value::Float(OrderedFloat(rhs))) => {
+                        Value::Boolean(lhs == OrderedFloat(rhs as f64))
+                    }
+                    (Value::Double(lhs), Value::Double(rhs)) => Value::Boolean(lhs == rhs),
+                    (Value::Tinyint(lhs), Value::Float(rhs)) => {
+                        Value::Boolean(OrderedFloat(lhs as f32) == rhs)
+                    }
+                    (Value::Tinyint(lhs), Value::Double(rhs)) => {
+                        Value::Boolean(OrderedFloat(lhs as f64) == rhs)
+                    }
+                    (Value::Float(lhs), Value::Tinyint(rhs)) => {
+                        Value::Boolean(lhs == OrderedFloat(rhs as f32))
+                    }
+                    (Value::Double(lhs), Value::Tinyint(rhs)) => {
+                        Value::Boolean(lhs == OrderedFloat(rhs as f64))
+                    }
+                    (Value::Smallint(lhs), Value::Float(rhs)) => {
+                        Value::Boolean(OrderedFloat(lhs as f32) == rhs)
+                    }
+                    (Value::Float(lhs), Value::Smallint(rhs)) => {
+                        Value::Boolean(lhs == OrderedFloat(rhs as f32))
+                    }
+                    (Value::Smallint(lhs), Value::Double(rhs)) => {
+                        Value::Boolean(OrderedFloat(lhs as f64) == rhs)
+                    }
+                    (Value::Double(lhs), Value::Smallint(rhs)) => {
+                        Value::Boolean(lhs == OrderedFloat(rhs as f64))
+                    }
+                    (Value::Integer(lhs), Value::Float(OrderedFloat(rhs))) => {
+                        Value::Boolean(OrderedFloat(lhs as f64) == OrderedFloat(rhs as f64))
+                    }
+                    (Value::Float(OrderedFloat(lhs)), Value::Integer(rhs)) => {
+                        Value::Boolean(OrderedFloat(lhs as f64) == OrderedFloat(rhs as f64))
+                    }
+                    (Value::Integer(lhs), Value::Double(rhs)) => {
+                        Value::Boolean(OrderedFloat(lhs as f64) == rhs)
+                    }
+                    (Value::Double(lhs), Value::Integer(rhs)) => {
+                        Value::Boolean(lhs == OrderedFloat(rhs as f64))
+                    }
+                    (Value::Bigint(lhs), Value::Float(OrderedFloat(rhs))) => {
+                        Value::Boolean(OrderedFloat(lhs as f64) == OrderedFloat(rhs as f64))
+                    }
+                    (Value::Float(OrderedFloat(lhs)), Value::Bigint(rhs)) => {
+                        Value::Boolean(OrderedFloat(lhs as f64) == OrderedFloat(rhs as f64))
+                    }
+                    (Value::Bigint(lhs), Value::Double(rhs)) => {
+                        Value::Boolean(OrderedFloat(lhs as f64) == rhs)
+                    }
+                    (Value::Double(lhs), Value::Bigint(rhs)) => {
+                        Value::Boolean(lhs == OrderedFloat(rhs as f64))
+                    }
+                    (Value::String(lhs), Value::String(rhs)) => Value::Boolean(lhs == rhs),
+                    (Value::Timestamp(lhs), Value::Timestamp(rhs)) => Value::Boolean(lhs == rhs),
+                    (lhs @ Value::Decimal(_), rhs) | (lhs, rhs @ Value::Decimal(_)) => {
+                        Value::Boolean(compare_decimal("equal", lhs, rhs)? == Ordering::Equal)
+                    }
+                    (lhs, rhs) => {
+                        return Err(Error::ValuesNotMatch(
+                            "equal",
+                            lhs.to_string(),
+                            rhs.to_string(),
+                        ))
+                    }
+                },
+            ),
+            Expression::GreaterThan(lhs, rhs) => Ok(
+                match coerce(
+                    lhs.evaluate_with(row, mode)?,
+                    rhs.evaluate_with(row, mode)?,
+                    mode,
+                )? {
+                    (Value::Null, _) | (_, Value::Null) => Value::Null,
+                    (Value::Boolean(lhs), Value::Boolean(rhs)) => Value::Boolean(lhs & !rhs),
+                    (Value::Tinyint(lhs), Value::Tinyint(rhs)) => Value::Boolean(lhs > rhs),
+                    (Value::Tinyint(lhs), Value::Smallint(rhs)) => {
+                        Value::Boolean((lhs as i32) > rhs)
+                    }
+                    (Value::Tinyint(lhs), Value::Integer(rhs)) => Value::Boolean(lhs as i64 > rhs),
+                    (Value::Tinyint(lhs), Value::Bigint(rhs)) => Value::Boolean(lhs as i128 > rhs),
+                    (Value::Smallint(lhs), Value::Tinyint(rhs)) => Value::Boolean(lhs > rhs as i32),
+                    (Value::Smallint(lhs), Value::Smallint(rhs)) => Value::Boolean(lhs > rhs),
+                    (Value::Smallint(lhs), Value::Integer(rhs)) => Value::Boolean(lhs as i64 > rhs),
+                    (Value::Smallint(lhs), Value::Bigint(rhs)) => Value::Boolean(lhs as i128 > rhs),
+                    (Value::Integer(lhs), Value::Tinyint(rhs)) => Value::Boolean(lhs > rhs as i64),
+                    (Value::Integer(lhs), Value::Smallint(rhs)) => Value::Boolean(lhs > rhs as i64),
+                    (Value::Integer(lhs), Value::Integer(rhs)) => Value::Boolean(lhs > rhs),
+                    (Value::Integer(lhs), Value::Bigint(rhs)) => Value::Boolean(lhs as i128 > rhs),
+                    (Value::Bigint(lhs), Value::Tinyint(rhs)) => Value::Boolean(lhs > rhs as i128),
+                    (Value::Bigint(lhs), Value::Smallint(rhs)) => Value::Boolean(lhs > rhs as i128),
+                    (Value::Bigint(lhs), Value::Integer(rhs)) => Value::Boolean(lhs > rhs as i128),
+                    (Value::Bigint(lhs), Value::Bigint(rhs)) => Value::Boolean(lhs > rhs),
+                    (Value::Float(lhs), Value::Float(rhs)) => Value::Boolean(lhs > rhs),
+                    (Value::Float(OrderedFloat(lhs)), Value::Double(rhs)) => {
+                        Value::Boolean(OrderedFloat(lhs as f64) > rhs)
+                    }
+                    (Value::Double(lhs), Value::Float(OrderedFloat(rhs))) => {
+                        Value::Boolean(lhs > OrderedFloat(rhs as f64))
+                    }
+                    (Value::Double(lhs), Value::Double(rhs)) => Value::Boolean(lhs > rhs),
+                    (Value::Tinyint(lhs), Value::Float(rhs)) => {
+                        Value::Boolean(OrderedFloat(lhs as f32) > rhs)
+                    }
+                    (Value::Tinyint(lhs), Value::Double(rhs)) => {
+                        Value::Boolean(OrderedFloat(lhs as f64) > rhs)
+                    }
+                    (Value::Float(lhs), Value::Tinyint(rhs)) => {
+                        Value::Boolean(lhs > OrderedFloat(rhs as f32))
+                    }
+                    (Value::Double(lhs), Value::Tinyint(rhs)) => {
+                        Value::Boolean(lhs > OrderedFloat(rhs as f64))
+                    }
+                    (Value::Smallint(lhs), Value::Float(rhs)) => {
+                        Value::Boolean(OrderedFloat(lhs as f32) > rhs)
+                    }
+                    (Value::Float(lhs), Value::Smallint(rhs)) => {
+                        Value::Boolean(lhs > OrderedFloat(rhs as f32))
+                    }
+                    (Value::Smallint(lhs), Value::Double(rhs)) => {
+                        Value::Boolean(OrderedFloat(lhs as f64) > rhs)
+                    }
+                    (Value::Double(lhs), Value::Smallint(rhs)) => {
+                        Value::Boolean(lhs > OrderedFloat(rhs as f64))
+                    }
+                    (Value::Integer(lhs), Value::Float(OrderedFloat(rhs))) => {
+                        Value::Boolean(OrderedFloat(lhs as f64) > OrderedFloat(rhs as f64))
+                    }
+                    (Value::Float(OrderedFloat(lhs)), Value::Integer(rhs)) => {
+                        Value::Boolean(OrderedFloat(lhs as f64) > OrderedFloat(rhs as f64))
+                    }
+                    (Value::Integer(lhs), Value::Double(rhs)) => {
+                        Value::Boolean(OrderedFloat(lhs as f64) > rhs)
+                    }
+                    (Value::Double(lhs), Value::Integer(rhs)) => {
+                        Value::Boolean(lhs > OrderedFloat(rhs as f64))
+                    }
+                    (Value::Bigint(lhs), Value::Float(OrderedFloat(rhs))) => {
+                        Value::Boolean(OrderedFloat(lhs as f64) > OrderedFloat(rhs as f64))
+                    }
+                    (Value::Float(OrderedFloat(lhs)), Value::Bigint(rhs)) => {
+                        Value::Boolean(OrderedFloat(lhs as f64) > OrderedFloat(rhs as f64))
+                    }
+                    (Value::Bigint(lhs), Value::Double(rhs)) => {
+                        Value::Boolean(OrderedFloat(lhs as f64) > rhs)
+                    }
+                    (Value::Double(lhs), Value::Bigint(rhs)) => {
+                        Value::Boolean(lhs > OrderedFloat(rhs as f64))
+                    }
+                    (Value::String(lhs), Value::String(rhs)) => Value::Boolean(lhs > rhs),
+                    (Value::Timestamp(lhs), Value::Timestamp(rhs)) => Value::Boolean(lhs > rhs),
+                    (lhs @ Value::Decimal(_), rhs) | (lhs, rhs @ Value::Decimal(_)) => {
+                        Value::Boolean(
+                            compare_decimal("great than", lhs, rhs)? == Ordering::Greater,
+                        )
+                    }
+                    (lhs, rhs) => {
+                        return Err(Error::ValuesNotMatch(
+                            "great than",
+                            lhs.to_string(),
+                            rhs.to_string(),
+                        ))
+                    }
+                },
+            ),
+            Expression::IsDistinctFrom(lhs, rhs) => Ok(
+                match (lhs.evaluate_with(row, mode)?, rhs.evaluate_with(row, mode)?) {
+                    (Value::Null, Value::Null) => Value::Boolean(false),
+                    (Value::Null, _) | (_, Value::Null) => Value::Boolean(true),
+                    (lhs, rhs) => Expression::Not(Box::new(Expression::Equal(
+                        Box::new(Expression::Const(lhs)),
+                        Box::new(Expression::Const(rhs)),
+                    )))
+                    .evaluate_with(None, mode)?,
+                },
+            ),
+            Expression::IsNull(expr) => Ok(match expr.evaluate_with(row, mode)? {
                 Value::Null => Value::Boolean(true),
                 _ => Value::Boolean(false),
             }),
-            Expression::LessThan(lhs, rhs) => Ok(match (lhs.evaluate()?, rhs.evaluate()?) {
-                (Value::Boolean(lhs), Value::Boolean(rhs)) => Value::Boolean(!lhs & rhs),
-                (Value::Tinyint(lhs), Value::Tinyint(rhs)) => Value::Boolean(lhs < rhs),
-                (Value::Tinyint(lhs), Value::Smallint(rhs)) => Value::Boolean((lhs as i32) < rhs),
-                (Value::Tinyint(lhs), Value::Integer(rhs)) => Value::Boolean((lhs as i64) < rhs),
-                (Value::Tinyint(lhs), Value::Bigint(rhs)) => Value::Boolean((lhs as i128) < rhs),
-                (Value::Smallint(lhs), Value::Tinyint(rhs)) => Value::Boolean(lhs < rhs as i32),
-                (Value::Smallint(lhs), Value::Smallint(rhs)) => Value::Boolean(lhs < rhs),
-                (Value::Smallint(lhs), Value::Integer(rhs)) => Value::Boolean((lhs as i64) < rhs),
-                (Value::Smallint(lhs), Value::Bigint(rhs)) => Value::Boolean((lhs as i128) < rhs),
-                (Value::Integer(lhs), Value::Tinyint(rhs)) => Value::Boolean(lhs < rhs as i64),
-                (Value::Integer(lhs), Value::Smallint(rhs)) => Value::Boolean(lhs < rhs as i64),
-                (Value::Integer(lhs), Value::Integer(rhs)) => Value::Boolean(lhs < rhs),
-                (Value::Integer(lhs), Value::Bigint(rhs)) => Value::Boolean((lhs as i128) < rhs),
-                (Value::Bigint(lhs), Value::Tinyint(rhs)) => Value::Boolean(lhs < rhs as i128),
-                (Value::Bigint(lhs), Value::Smallint(rhs)) => Value::Boolean(lhs < rhs as i128),
-                (Value::Bigint(lhs), Value::Integer(rhs)) => Value::Boolean(lhs < rhs as i128),
-                (Value::Bigint(lhs), Value::Bigint(rhs)) => Value::Boolean(lhs < rhs),
-                (Value::Float(lhs), Value::Float(rhs)) => Value::Boolean(lhs < rhs),
-                (Value::Float(OrderedFloat(lhs)), Value::Double(rhs)) => {
-                    Value::Boolean(OrderedFloat(lhs as f64) < rhs)
-                }
-                (Value::Double(lhs), Value::Float(OrderedFloat(rhs))) => {
-                    Value::Boolean(lhs < OrderedFloat(rhs as f64))
-                }
-                (Value::Double(lhs), Value::Double(rhs)) => Value::Boolean(lhs < rhs),
-                (Value::String(lhs), Value::String(rhs)) => Value::Boolean(lhs < rhs),
-                (lhs, rhs) => {
-                    return Err(Error::ValuesNotMatch(
-                        "less than",
-                        lhs.to_string(),
-                        rhs.to_string(),
-                    ))
-                }
-            }),
-            Expression::Add(lhs, rhs) => Ok(match (lhs.evaluate()?, rhs.evaluate()?) {
-                (Value::Null, Value::Null) => Value::Null,
-                (Value::Tinyint(lhs), Value::Tinyint(rhs)) => Value::Tinyint(
-                    lhs.checked_add(rhs)
-                        .ok_or(Error::OutOfBound("Tinyint", "overflow"))?,
-                ),
-                (Value::Tinyint(lhs), Value::Smallint(rhs)) => Value::Smallint(
-                    (lhs as i32)
-                        .checked_add(rhs)
-                        .ok_or(Error::OutOfBound("Smallint", "overflow"))?,
-                ),
-                (Value::Tinyint(lhs), Value::Integer(rhs)) => Value::Integer(
-                    (lhs as i64)
-                        .checked_add(rhs)
-                        .ok_or(Error::OutOfBound("Integer", "overflow"))?,
-                ),
-                (Value::Tinyint(lhs), Value::Bigint(rhs)) => Value::Bigint(
-                    (lhs as i128)
-                        .checked_add(rhs)
-                        .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
-                ),
-                (Value::Tinyint(lhs), Value::Float(OrderedFloat(rhs))) => {
-                    Value::Float(OrderedFloat(lhs as f32 + rhs))
-                }
-                (Value::Tinyint(lhs), Value::Double(OrderedFloat(rhs))) => {
-                    Value::Double(OrderedFloat(lhs as f64 + rhs))
-                }
-                (Value::Smallint(lhs), Value::Tinyint(rhs)) => Value::Smallint(
-                    lhs.checked_add(rhs as i32)
-                        .ok_or(Error::OutOfBound("Smallint", "overflow"))?,
-                ),
-                (Value::Smallint(lhs), Value::Smallint(rhs)) => Value::Smallint(
-                    lhs.checked_add(rhs)
-                        .ok_or(Error::OutOfBound("Smallint", "overflow"))?,
-                ),
-                (Value::Smallint(lhs), Value::Integer(rhs)) => Value::Integer(
-                    (lhs as i64)
-                        .checked_add(rhs)
-                        .ok_or(Error::OutOfBound("Integer", "overflow"))?,
-                ),
-                (Value::Smallint(lhs), Value::Bigint(rhs)) => Value::Bigint(
-                    (lhs as i128)
-                        .checked_add(rhs)
-                        .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
-                ),
-                (Value::Smallint(lhs), Value::Float(rhs)) => {
-                    Value::Float(OrderedFloat(lhs as f32) + rhs)
-                }
-                (Value::Smallint(lhs), Value::Double(rhs)) => {
-                    Value::Double(OrderedFloat(lhs as f64) + rhs)
-                }
-                (Value::Integer(lhs), Value::Tinyint(rhs)) => Value::Integer(
-                    lhs.checked_add(rhs as i64)
-                        .ok_or(Error::OutOfBound("Integer", "overflow"))?,
-                ),
-                (Value::Integer(lhs), Value::Smallint(rhs)) => Value::Integer(
-                    lhs.checked_add(rhs as i64)
-                        .ok_or(Error::OutOfBound("Integer", "overflow"))?,
-                ),
-                (Value::Integer(lhs), Value::Integer(rhs)) => Value::Integer(
-                    lhs.checked_add(rhs)
-                        .ok_or(Error::OutOfBound("Integer", "overflow"))?,
-                ),
-                (Value::Integer(lhs), Value::Bigint(rhs)) => Value::Bigint(
-                    (lhs as i128)
-                        .checked_add(rhs)
-                        .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
-                ),
-                (Value::Integer(lhs), Value::Float(rhs)) => {
-                    Value::Float(OrderedFloat(lhs as f32) + rhs)
-                }
-                (Value::Integer(lhs), Value::Double(rhs)) => {
-                    Value::Double(OrderedFloat(lhs as f64) + rhs)
-                }
-                (Value::Bigint(lhs), Value::Tinyint(rhs)) => Value::Bigint(
-                    lhs.checked_add(rhs as i128)
-                        .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
-                ),
-                (Value::Bigint(lhs), Value::Smallint(rhs)) => Value::Bigint(
-                    lhs.checked_add(rhs as i128)
-                        .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
-                ),
-                (Value::Bigint(lhs), Value::Integer(rhs)) => Value::Bigint(
-                    lhs.checked_add(rhs as i128)
-                        .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
-                ),
-                (Value::Bigint(lhs), Value::Bigint(rhs)) => Value::Bigint(
-                    lhs.checked_add(rhs)
-                        .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
-                ),
-                (Value::Bigint(lhs), Value::Float(rhs)) => {
-                    Value::Float(OrderedFloat(lhs as f32) + rhs)
-                }
-                (Value::Bigint(lhs), Value::Double(rhs)) => {
-                    Value::Double(OrderedFloat(lhs as f64) + rhs)
-                }
-                (Value::Float(lhs), Value::Tinyint(rhs)) => {
-                    Value::Float(lhs + OrderedFloat(rhs as f32))
-                }
-                (Value::Float(lhs), Value::Smallint(rhs)) => {
-                    Value::Float(lhs + OrderedFloat(rhs as f32))
-                }
-                (Value::Float(lhs), Value::Integer(rhs)) => {
-                    Value::Float(lhs + OrderedFloat(rhs as f32))
-                }
-                (Value::Float(lhs), Value::Bigint(rhs)) => {
-                    Value::Float(lhs + OrderedFloat(rhs as f32))
-                }
-                (Value::Float(lhs), Value::Float(rhs)) => Value::Float(lhs + rhs),
-                (Value::Float(OrderedFloat(lhs)), Value::Double(rhs)) => {
-                    Value::Double(OrderedFloat(lhs as f64) + rhs)
-                }
-                (Value::Double(lhs), Value::Tinyint(rhs)) => {
-                    Value::Double(lhs + OrderedFloat(rhs as f64))
-                }
-                (Value::Double(lhs), Value::Smallint(rhs)) => {
-                    Value::Double(lhs + OrderedFloat(rhs as f64))
-                }
-                (Value::Double(lhs), Value::Integer(rhs)) => {
-                    Value::Double(lhs + OrderedFloat(rhs as f64))
-                }
-                (Value::Double(lhs), Value::Bigint(rhs)) => {
-                    Value::Double(lhs + OrderedFloat(rhs as f64))
-                }
-                (Value::Double(lhs), Value::Float(OrderedFloat(rhs))) => {
-                    Value::Double(lhs + OrderedFloat(rhs as f64))
-                }
-                (Value::Double(lhs), Value::Double(rhs)) => Value::Double(lhs + rhs),
-                (lhs, rhs) => {
-                    return Err(Error::ValuesNotMatch(
-                        "add",
-                        lhs.to_string(),
-                        rhs.to_string(),
-                    ))
-                }
-            }),
-            Expression::Assert(expr) => Ok(match expr.evaluate()? {
+            Expression::LessThan(lhs, rhs) => Ok(
+                match coerce(
+                    lhs.evaluate_with(row, mode)?,
+                    rhs.evaluate_with(row, mode)?,
+                    mode,
+                )? {
+                    (Value::Null, _) | (_, Value::Null) => Value::Null,
+                    (Value::Boolean(lhs), Value::Boolean(rhs)) => Value::Boolean(!lhs & rhs),
+                    (Value::Tinyint(lhs), Value::Tinyint(rhs)) => Value::Boolean(lhs < rhs),
+                    (Value::Tinyint(lhs), Value::Smallint(rhs)) => {
+                        Value::Boolean((lhs as i32) < rhs)
+                    }
+                    (Value::Tinyint(lhs), Value::Integer(rhs)) => {
+                        Value::Boolean((lhs as i64) < rhs)
+                    }
+                    (Value::Tinyint(lhs), Value::Bigint(rhs)) => {
+                        Value::Boolean((lhs as i128) < rhs)
+                    }
+                    (Value::Smallint(lhs), Value::Tinyint(rhs)) => Value::Boolean(lhs < rhs as i32),
+                    (Value::Smallint(lhs), Value::Smallint(rhs)) => Value::Boolean(lhs < rhs),
+                    (Value::Smallint(lhs), Value::Integer(rhs)) => {
+                        Value::Boolean((lhs as i64) < rhs)
+                    }
+                    (Value::Smallint(lhs), Value::Bigint(rhs)) => {
+                        Value::Boolean((lhs as i128) < rhs)
+                    }
+                    (Value::Integer(lhs), Value::Tinyint(rhs)) => Value::Boolean(lhs < rhs as i64),
+                    (Value::Integer(lhs), Value::Smallint(rhs)) => Value::Boolean(lhs < rhs as i64),
+                    (Value::Integer(lhs), Value::Integer(rhs)) => Value::Boolean(lhs < rhs),
+                    (Value::Integer(lhs), Value::Bigint(rhs)) => {
+                        Value::Boolean((lhs as i128) < rhs)
+                    }
+                    (Value::Bigint(lhs), Value::Tinyint(rhs)) => Value::Boolean(lhs < rhs as i128),
+                    (Value::Bigint(lhs), Value::Smallint(rhs)) => Value::Boolean(lhs < rhs as i128),
+                    (Value::Bigint(lhs), Value::Integer(rhs)) => Value::Boolean(lhs < rhs as i128),
+                    (Value::Bigint(lhs), Value::Bigint(rhs)) => Value::Boolean(lhs < rhs),
+                    (Value::Float(lhs), Value::Float(rhs)) => Value::Boolean(lhs < rhs),
+                    (Value::Float(OrderedFloat(lhs)), Value::Double(rhs)) => {
+                        Value::Boolean(OrderedFloat(lhs as f64) < rhs)
+                    }
+                    (Value::Double(lhs), Value::Float(OrderedFloat(rhs))) => {
+                        Value::Boolean(lhs < OrderedFloat(rhs as f64))
+                    }
+                    (Value::Double(lhs), Value::Double(rhs)) => Value::Boolean(lhs < rhs),
+                    (Value::Tinyint(lhs), Value::Float(rhs)) => {
+                        Value::Boolean(OrderedFloat(lhs as f32) < rhs)
+                    }
+                    (Value::Tinyint(lhs), Value::Double(rhs)) => {
+                        Value::Boolean(OrderedFloat(lhs as f64) < rhs)
+                    }
+                    (Value::Float(lhs), Value::Tinyint(rhs)) => {
+                        Value::Boolean(lhs < OrderedFloat(rhs as f32))
+                    }
+                    (Value::Double(lhs), Value::Tinyint(rhs)) => {
+                        Value::Boolean(lhs < OrderedFloat(rhs as f64))
+                    }
+                    (Value::Smallint(lhs), Value::Float(rhs)) => {
+                        Value::Boolean(OrderedFloat(lhs as f32) < rhs)
+                    }
+                    (Value::Float(lhs), Value::Smallint(rhs)) => {
+                        Value::Boolean(lhs < OrderedFloat(rhs as f32))
+                    }
+                    (Value::Smallint(lhs), Value::Double(rhs)) => {
+                        Value::Boolean(OrderedFloat(lhs as f64) < rhs)
+                    }
+                    (Value::Double(lhs), Value::Smallint(rhs)) => {
+                        Value::Boolean(lhs < OrderedFloat(rhs as f64))
+                    }
+                    (Value::Integer(lhs), Value::Float(OrderedFloat(rhs))) => {
+                        Value::Boolean(OrderedFloat(lhs as f64) < OrderedFloat(rhs as f64))
+                    }
+                    (Value::Float(OrderedFloat(lhs)), Value::Integer(rhs)) => {
+                        Value::Boolean(OrderedFloat(lhs as f64) < OrderedFloat(rhs as f64))
+                    }
+                    (Value::Integer(lhs), Value::Double(rhs)) => {
+                        Value::Boolean(OrderedFloat(lhs as f64) < rhs)
+                    }
+                    (Value::Double(lhs), Value::Integer(rhs)) => {
+                        Value::Boolean(lhs < OrderedFloat(rhs as f64))
+                    }
+                    (Value::Bigint(lhs), Value::Float(OrderedFloat(rhs))) => {
+                        Value::Boolean(OrderedFloat(lhs as f64) < OrderedFloat(rhs as f64))
+                    }
+                    (Value::Float(OrderedFloat(lhs)), Value::Bigint(rhs)) => {
+                        Value::Boolean(OrderedFloat(lhs as f64) < OrderedFloat(rhs as f64))
+                    }
+                    (Value::Bigint(lhs), Value::Double(rhs)) => {
+                        Value::Boolean(OrderedFloat(lhs as f64) < rhs)
+                    }
+                    (Value::Double(lhs), Value::Bigint(rhs)) => {
+                        Value::Boolean(lhs < OrderedFloat(rhs as f64))
+                    }
+                    (Value::String(lhs), Value::String(rhs)) => Value::Boolean(lhs < rhs),
+                    (Value::Timestamp(lhs), Value::Timestamp(rhs)) => Value::Boolean(lhs < rhs),
+                    (lhs @ Value::Decimal(_), rhs) | (lhs, rhs @ Value::Decimal(_)) => {
+                        Value::Boolean(compare_decimal("less than", lhs, rhs)? == Ordering::Less)
+                    }
+                    (lhs, rhs) => {
+                        return Err(Error::ValuesNotMatch(
+                            "less than",
+                            lhs.to_string(),
+                            rhs.to_string(),
+                        ))
+                    }
+                },
+            ),
+            Expression::Add(lhs, rhs) => Ok(
+                match coerce(
+                    lhs.evaluate_with(row, mode)?,
+                    rhs.evaluate_with(row, mode)?,
+                    mode,
+                )? {
+                    (Value::Null, Value::Null) => Value::Null,
+                    (Value::Tinyint(lhs), Value::Tinyint(rhs)) => Value::Tinyint(
+                        lhs.checked_add(rhs)
+                            .ok_or(Error::OutOfBound("Tinyint", "overflow"))?,
+                    ),
+                    (Value::Tinyint(lhs), Value::Smallint(rhs)) => Value::Smallint(
+                        (lhs as i32)
+                            .checked_add(rhs)
+                            .ok_or(Error::OutOfBound("Smallint", "overflow"))?,
+                    ),
+                    (Value::Tinyint(lhs), Value::Integer(rhs)) => Value::Integer(
+                        (lhs as i64)
+                            .checked_add(rhs)
+                            .ok_or(Error::OutOfBound("Integer", "overflow"))?,
+                    ),
+                    (Value::Tinyint(lhs), Value::Bigint(rhs)) => Value::Bigint(
+                        (lhs as i128)
+                            .checked_add(rhs)
+                            .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
+                    ),
+                    (Value::Tinyint(lhs), Value::Float(OrderedFloat(rhs))) => {
+                        Value::Float(OrderedFloat(lhs as f32 + rhs))
+                    }
+                    (Value::Tinyint(lhs), Value::Double(OrderedFloat(rhs))) => {
+                        Value::Double(OrderedFloat(lhs as f64 + rhs))
+                    }
+                    (Value::Smallint(lhs), Value::Tinyint(rhs)) => Value::Smallint(
+                        lhs.checked_add(rhs as i32)
+                            .ok_or(Error::OutOfBound("Smallint", "overflow"))?,
+                    ),
+                    (Value::Smallint(lhs), Value::Smallint(rhs)) => Value::Smallint(
+                        lhs.checked_add(rhs)
+                            .ok_or(Error::OutOfBound("Smallint", "overflow"))?,
+                    ),
+                    (Value::Smallint(lhs), Value::Integer(rhs)) => Value::Integer(
+                        (lhs as i64)
+                            .checked_add(rhs)
+                            .ok_or(Error::OutOfBound("Integer", "overflow"))?,
+                    ),
+                    (Value::Smallint(lhs), Value::Bigint(rhs)) => Value::Bigint(
+                        (lhs as i128)
+                            .checked_add(rhs)
+                            .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
+                    ),
+                    (Value::Smallint(lhs), Value::Float(rhs)) => {
+                        Value::Float(OrderedFloat(lhs as f32) + rhs)
+                    }
+                    (Value::Smallint(lhs), Value::Double(rhs)) => {
+                        Value::Double(OrderedFloat(lhs as f64) + rhs)
+                    }
+                    (Value::Integer(lhs), Value::Tinyint(rhs)) => Value::Integer(
+                        lhs.checked_add(rhs as i64)
+                            .ok_or(Error::OutOfBound("Integer", "overflow"))?,
+                    ),
+                    (Value::Integer(lhs), Value::Smallint(rhs)) => Value::Integer(
+                        lhs.checked_add(rhs as i64)
+                            .ok_or(Error::OutOfBound("Integer", "overflow"))?,
+                    ),
+                    (Value::Integer(lhs), Value::Integer(rhs)) => Value::Integer(
+                        lhs.checked_add(rhs)
+                            .ok_or(Error::OutOfBound("Integer", "overflow"))?,
+                    ),
+                    (Value::Integer(lhs), Value::Bigint(rhs)) => Value::Bigint(
+                        (lhs as i128)
+                            .checked_add(rhs)
+                            .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
+                    ),
+                    (Value::Integer(lhs), Value::Float(rhs)) => {
+                        Value::Float(OrderedFloat(lhs as f32) + rhs)
+                    }
+                    (Value::Integer(lhs), Value::Double(rhs)) => {
+                        Value::Double(OrderedFloat(lhs as f64) + rhs)
+                    }
+                    (Value::Bigint(lhs), Value::Tinyint(rhs)) => Value::Bigint(
+                        lhs.checked_add(rhs as i128)
+                            .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
+                    ),
+                    (Value::Bigint(lhs), Value::Smallint(rhs)) => Value::Bigint(
+                        lhs.checked_add(rhs as i128)
+                            .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
+                    ),
+                    (Value::Bigint(lhs), Value::Integer(rhs)) => Value::Bigint(
+                        lhs.checked_add(rhs as i128)
+                            .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
+                    ),
+                    (Value::Bigint(lhs), Value::Bigint(rhs)) => Value::Bigint(
+                        lhs.checked_add(rhs)
+                            .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
+                    ),
+                    (Value::Bigint(lhs), Value::Float(rhs)) => {
+                        Value::Float(OrderedFloat(lhs as f32) + rhs)
+                    }
+                    (Value::Bigint(lhs), Value::Double(rhs)) => {
+                        Value::Double(OrderedFloat(lhs as f64) + rhs)
+                    }
+                    (Value::Float(lhs), Value::Tinyint(rhs)) => {
+                        Value::Float(lhs + OrderedFloat(rhs as f32))
+                    }
+                    (Value::Float(lhs), Value::Smallint(rhs)) => {
+                        Value::Float(lhs + OrderedFloat(rhs as f32))
+                    }
+                    (Value::Float(lhs), Value::Integer(rhs)) => {
+                        Value::Float(lhs + OrderedFloat(rhs as f32))
+                    }
+                    (Value::Float(lhs), Value::Bigint(rhs)) => {
+                        Value::Float(lhs + OrderedFloat(rhs as f32))
+                    }
+                    (Value::Float(lhs), Value::Float(rhs)) => Value::Float(lhs + rhs),
+                    (Value::Float(OrderedFloat(lhs)), Value::Double(rhs)) => {
+                        Value::Double(OrderedFloat(lhs as f64) + rhs)
+                    }
+                    (Value::Double(lhs), Value::Tinyint(rhs)) => {
+                        Value::Double(lhs + OrderedFloat(rhs as f64))
+                    }
+                    (Value::Double(lhs), Value::Smallint(rhs)) => {
+                        Value::Double(lhs + OrderedFloat(rhs as f64))
+                    }
+                    (Value::Double(lhs), Value::Integer(rhs)) => {
+                        Value::Double(lhs + OrderedFloat(rhs as f64))
+                    }
+                    (Value::Double(lhs), Value::Bigint(rhs)) => {
+                        Value::Double(lhs + OrderedFloat(rhs as f64))
+                    }
+                    (Value::Double(lhs), Value::Float(OrderedFloat(rhs))) => {
+                        Value::Double(lhs + OrderedFloat(rhs as f64))
+                    }
+                    (Value::Double(lhs), Value::Double(rhs)) => Value::Double(lhs + rhs),
+                    (lhs @ Value::Decimal(_), rhs) | (lhs, rhs @ Value::Decimal(_)) => {
+                        decimal_arithmetic("add", lhs, rhs, Decimal::checked_add, |l, r| l + r)?
+                    }
+                    (lhs, rhs) => {
+                        return Err(Error::ValuesNotMatch(
+                            "add",
+                            lhs.to_string(),
+                            rhs.to_string(),
+                        ))
+                    }
+                },
+            ),
+            Expression::Assert(expr) => Ok(match expr.evaluate_with(row, mode)? {
                 Value::Null => Value::Null,
                 Value::Tinyint(expr) => Value::Tinyint(expr),
                 Value::Smallint(expr) => Value::Smallint(expr),
@@ -314,663 +930,988 @@ impl Expression {
                 Value::Bigint(expr) => Value::Bigint(expr),
                 Value::Float(expr) => Value::Float(expr),
                 Value::Double(expr) => Value::Double(expr),
+                Value::Decimal(expr) => Value::Decimal(expr),
                 expr => return Err(Error::ValueNotMatch("assert", expr.to_string())),
             }),
-            Expression::Factorial(expr) => Ok(match expr.evaluate()? {
+            Expression::Factorial(expr) => Ok(match expr.evaluate_with(row, mode)? {
                 Value::Null => Value::Null,
-                Value::Tinyint(expr) if expr < 0 => {
-                    return Err(Error::ValueNotMatch("factorial", expr.to_string()))
-                }
-                Value::Smallint(expr) if expr < 0 => {
-                    return Err(Error::ValueNotMatch("factorial", expr.to_string()))
-                }
-                Value::Integer(expr) if expr < 0 => {
-                    return Err(Error::ValueNotMatch("factorial", expr.to_string()))
-                }
-                Value::Bigint(expr) if expr < 0 => {
-                    return Err(Error::ValueNotMatch("factorial", expr.to_string()))
-                }
-                Value::Tinyint(expr) => Value::Tinyint((1..expr).product()),
-                Value::Smallint(expr) => Value::Smallint((1..expr).product()),
-                Value::Integer(expr) => Value::Integer((1..expr).product()),
-                Value::Bigint(expr) => Value::Bigint((1..expr).product()),
-                expr => return Err(Error::ValueNotMatch("factorial", expr.to_string())),
+                expr => match integer(&expr) {
+                    Some(n) if n >= 0 => {
+                        let mut product: i128 = 1;
+                        for i in 2..=n {
+                            product = product
+                                .checked_mul(i)
+                                .ok_or(Error::OutOfBound("Bigint", "overflow"))?;
+                        }
+                        Value::Bigint(product)
+                    }
+                    _ => return Err(Error::ValueNotMatch("factorial", expr.to_string())),
+                },
             }),
-            Expression::Modulo(lhs, rhs) => Ok(match (lhs.evaluate()?, rhs.evaluate()?) {
-                // check zero
-                (lhs, rhs) if (lhs.check_int() || lhs.check_float()) && rhs.check_zero() => {
-                    return Err(Error::ValuesNotMatch(
-                        "modulo",
-                        lhs.to_string(),
-                        rhs.to_string(),
-                    ))
-                }
-                (Value::Null, Value::Null) => Value::Null,
-                (Value::Tinyint(lhs), Value::Tinyint(rhs)) => Value::Tinyint(lhs % rhs),
-                (Value::Tinyint(lhs), Value::Smallint(rhs)) => Value::Smallint(lhs as i32 % rhs),
-                (Value::Tinyint(lhs), Value::Integer(rhs)) => Value::Integer((lhs as i64) % rhs),
-                (Value::Tinyint(lhs), Value::Bigint(rhs)) => Value::Bigint((lhs as i128) % rhs),
-                (Value::Tinyint(lhs), Value::Float(OrderedFloat(rhs))) => {
-                    Value::Float(OrderedFloat(lhs as f32 % rhs))
-                }
-                (Value::Tinyint(lhs), Value::Double(OrderedFloat(rhs))) => {
-                    Value::Double(OrderedFloat(lhs as f64 % rhs))
-                }
-                (Value::Smallint(lhs), Value::Tinyint(rhs)) => Value::Smallint(lhs % (rhs as i32)),
-                (Value::Smallint(lhs), Value::Smallint(rhs)) => Value::Smallint(lhs % rhs),
-                (Value::Smallint(lhs), Value::Integer(rhs)) => Value::Integer((lhs as i64) % rhs),
-                (Value::Smallint(lhs), Value::Bigint(rhs)) => Value::Bigint((lhs as i128) % rhs),
-                (Value::Smallint(lhs), Value::Float(rhs)) => {
-                    Value::Float(OrderedFloat(lhs as f32) % rhs)
-                }
-                (Value::Smallint(lhs), Value::Double(rhs)) => {
-                    Value::Double(OrderedFloat(lhs as f64) % rhs)
-                }
-                (Value::Integer(lhs), Value::Tinyint(rhs)) => Value::Integer(lhs % (rhs as i64)),
-                (Value::Integer(lhs), Value::Smallint(rhs)) => Value::Integer(lhs % (rhs as i64)),
-                (Value::Integer(lhs), Value::Integer(rhs)) => Value::Integer(lhs % (rhs)),
-                (Value::Integer(lhs), Value::Bigint(rhs)) => Value::Bigint((lhs as i128) % (rhs)),
-                (Value::Integer(lhs), Value::Float(rhs)) => {
-                    Value::Float(OrderedFloat(lhs as f32) % rhs)
-                }
-                (Value::Integer(lhs), Value::Double(rhs)) => {
-                    Value::Double(OrderedFloat(lhs as f64) % rhs)
-                }
-                (Value::Bigint(lhs), Value::Tinyint(rhs)) => Value::Bigint(lhs % (rhs as i128)),
-                (Value::Bigint(lhs), Value::Smallint(rhs)) => Value::Bigint(lhs % (rhs as i128)),
-                (Value::Bigint(lhs), Value::Integer(rhs)) => Value::Bigint(lhs % (rhs as i128)),
-                (Value::Bigint(lhs), Value::Bigint(rhs)) => Value::Bigint(lhs % rhs),
-                (Value::Bigint(lhs), Value::Float(rhs)) => {
-                    Value::Float(OrderedFloat(lhs as f32) % rhs)
-                }
-                (Value::Bigint(lhs), Value::Double(rhs)) => {
-                    Value::Double(OrderedFloat(lhs as f64) % rhs)
-                }
-                (Value::Float(lhs), Value::Tinyint(rhs)) => {
-                    Value::Float(lhs % OrderedFloat(rhs as f32))
-                }
-                (Value::Float(lhs), Value::Smallint(rhs)) => {
-                    Value::Float(lhs % OrderedFloat(rhs as f32))
-                }
-                (Value::Float(lhs), Value::Integer(rhs)) => {
-                    Value::Float(lhs % OrderedFloat(rhs as f32))
-                }
-                (Value::Float(lhs), Value::Bigint(rhs)) => {
-                    Value::Float(lhs % OrderedFloat(rhs as f32))
-                }
-                (Value::Float(lhs), Value::Float(rhs)) => Value::Float(lhs % rhs),
-                (Value::Float(OrderedFloat(lhs)), Value::Double(rhs)) => {
-                    Value::Double(OrderedFloat(lhs as f64) % rhs)
-                }
-                (Value::Double(lhs), Value::Tinyint(rhs)) => {
-                    Value::Double(lhs % OrderedFloat(rhs as f64))
-                }
-                (Value::Double(lhs), Value::Smallint(rhs)) => {
-                    Value::Double(lhs % OrderedFloat(rhs as f64))
-                }
-                (Value::Double(lhs), Value::Integer(rhs)) => {
-                    Value::Double(lhs % OrderedFloat(rhs as f64))
-                }
-                (Value::Double(lhs), Value::Bigint(rhs)) => {
-                    Value::Double(lhs % OrderedFloat(rhs as f64))
-                }
-                (Value::Double(lhs), Value::Float(OrderedFloat(rhs))) => {
-                    Value::Double(lhs % OrderedFloat(rhs as f64))
-                }
-                (Value::Double(lhs), Value::Double(rhs)) => Value::Double(lhs % rhs),
-                (lhs, rhs) => {
-                    return Err(Error::ValuesNotMatch(
-                        "modulo",
-                        lhs.to_string(),
-                        rhs.to_string(),
-                    ))
-                }
-            }),
-            Expression::Subtract(lhs, rhs) => Ok(match (lhs.evaluate()?, rhs.evaluate()?) {
-                (Value::Null, Value::Null) => Value::Null,
-                (Value::Tinyint(lhs), Value::Tinyint(rhs)) => Value::Tinyint(
-                    lhs.checked_sub(rhs)
-                        .ok_or(Error::OutOfBound("Tinyint", "underflow"))?,
-                ),
-                (Value::Tinyint(lhs), Value::Smallint(rhs)) => Value::Smallint(
-                    (lhs as i32)
-                        .checked_sub(rhs)
-                        .ok_or(Error::OutOfBound("Smallint", "underflow"))?,
-                ),
-                (Value::Tinyint(lhs), Value::Integer(rhs)) => Value::Integer(
-                    (lhs as i64)
-                        .checked_sub(rhs)
-                        .ok_or(Error::OutOfBound("Integer", "underflow"))?,
-                ),
-                (Value::Tinyint(lhs), Value::Bigint(rhs)) => Value::Bigint(
-                    (lhs as i128)
-                        .checked_sub(rhs)
-                        .ok_or(Error::OutOfBound("Bigint", "underflow"))?,
-                ),
-                (Value::Tinyint(lhs), Value::Float(OrderedFloat(rhs))) => {
-                    Value::Float(OrderedFloat(lhs as f32 - rhs))
-                }
-                (Value::Tinyint(lhs), Value::Double(OrderedFloat(rhs))) => {
-                    Value::Double(OrderedFloat(lhs as f64 - rhs))
-                }
-                (Value::Smallint(lhs), Value::Tinyint(rhs)) => Value::Smallint(
-                    lhs.checked_sub(rhs as i32)
-                        .ok_or(Error::OutOfBound("Smallint", "underflow"))?,
-                ),
-                (Value::Smallint(lhs), Value::Smallint(rhs)) => Value::Smallint(
-                    lhs.checked_sub(rhs)
-                        .ok_or(Error::OutOfBound("Smallint", "underflow"))?,
-                ),
-                (Value::Smallint(lhs), Value::Integer(rhs)) => Value::Integer(
-                    (lhs as i64)
-                        .checked_sub(rhs)
-                        .ok_or(Error::OutOfBound("Integer", "underflow"))?,
-                ),
-                (Value::Smallint(lhs), Value::Bigint(rhs)) => Value::Bigint(
-                    (lhs as i128)
-                        .checked_sub(rhs)
-                        .ok_or(Error::OutOfBound("Bigint", "underflow"))?,
-                ),
-                (Value::Smallint(lhs), Value::Float(rhs)) => {
-                    Value::Float(OrderedFloat(lhs as f32) - rhs)
-                }
-                (Value::Smallint(lhs), Value::Double(rhs)) => {
-                    Value::Double(OrderedFloat(lhs as f64) - rhs)
-                }
-                (Value::Integer(lhs), Value::Tinyint(rhs)) => Value::Integer(
-                    lhs.checked_sub(rhs as i64)
-                        .ok_or(Error::OutOfBound("Integer", "underflow"))?,
-                ),
-                (Value::Integer(lhs), Value::Smallint(rhs)) => Value::Integer(
-                    lhs.checked_sub(rhs as i64)
-                        .ok_or(Error::OutOfBound("Integer", "underflow"))?,
-                ),
-                (Value::Integer(lhs), Value::Integer(rhs)) => Value::Integer(
-                    lhs.checked_sub(rhs)
-                        .ok_or(Error::OutOfBound("Integer", "underflow"))?,
-                ),
-                (Value::Integer(lhs), Value::Bigint(rhs)) => Value::Bigint(
-                    (lhs as i128)
-                        .checked_sub(rhs)
-                        .ok_or(Error::OutOfBound("Bigint", "underflow"))?,
-                ),
-                (Value::Integer(lhs), Value::Float(rhs)) => {
-                    Value::Float(OrderedFloat(lhs as f32) - rhs)
-                }
-                (Value::Integer(lhs), Value::Double(rhs)) => {
-                    Value::Double(OrderedFloat(lhs as f64) - rhs)
-                }
-                (Value::Bigint(lhs), Value::Tinyint(rhs)) => Value::Bigint(
-                    lhs.checked_sub(rhs as i128)
-                        .ok_or(Error::OutOfBound("Bigint", "underflow"))?,
-                ),
-                (Value::Bigint(lhs), Value::Smallint(rhs)) => Value::Bigint(
-                    lhs.checked_sub(rhs as i128)
-                        .ok_or(Error::OutOfBound("Bigint", "underflow"))?,
-                ),
-                (Value::Bigint(lhs), Value::Integer(rhs)) => Value::Bigint(
-                    lhs.checked_sub(rhs as i128)
-                        .ok_or(Error::OutOfBound("Bigint", "underflow"))?,
-                ),
-                (Value::Bigint(lhs), Value::Bigint(rhs)) => Value::Bigint(
-                    lhs.checked_sub(rhs)
-                        .ok_or(Error::OutOfBound("Bigint", "underflow"))?,
-                ),
-                (Value::Bigint(lhs), Value::Float(rhs)) => {
-                    Value::Float(OrderedFloat(lhs as f32) - rhs)
-                }
-                (Value::Bigint(lhs), Value::Double(rhs)) => {
-                    Value::Double(OrderedFloat(lhs as f64) - rhs)
-                }
-                (Value::Float(lhs), Value::Tinyint(rhs)) => {
-                    Value::Float(lhs - OrderedFloat(rhs as f32))
-                }
-                (Value::Float(lhs), Value::Smallint(rhs)) => {
-                    Value::Float(lhs - OrderedFloat(rhs as f32))
-                }
-                (Value::Float(lhs), Value::Integer(rhs)) => {
-                    Value::Float(lhs - OrderedFloat(rhs as f32))
-                }
-                (Value::Float(lhs), Value::Bigint(rhs)) => {
-                    Value::Float(lhs - OrderedFloat(rhs as f32))
-                }
-                (Value::Float(lhs), Value::Float(rhs)) => Value::Float(lhs - rhs),
-                (Value::Float(OrderedFloat(lhs)), Value::Double(rhs)) => {
-                    Value::Double(OrderedFloat(lhs as f64) - rhs)
-                }
-                (Value::Double(lhs), Value::Tinyint(rhs)) => {
-                    Value::Double(lhs - OrderedFloat(rhs as f64))
-                }
-                (Value::Double(lhs), Value::Smallint(rhs)) => {
-                    Value::Double(lhs - OrderedFloat(rhs as f64))
-                }
-                (Value::Double(lhs), Value::Integer(rhs)) => {
-                    Value::Double(lhs - OrderedFloat(rhs as f64))
-                }
-                (Value::Double(lhs), Value::Bigint(rhs)) => {
-                    Value::Double(lhs - OrderedFloat(rhs as f64))
-                }
-                (Value::Double(lhs), Value::Float(OrderedFloat(rhs))) => {
-                    Value::Double(lhs - OrderedFloat(rhs as f64))
-                }
-                (Value::Double(lhs), Value::Double(rhs)) => Value::Double(lhs - rhs),
-                (lhs, rhs) => {
-                    return Err(Error::ValuesNotMatch(
-                        "subtract",
-                        lhs.to_string(),
-                        rhs.to_string(),
-                    ))
-                }
-            }),
-            Expression::Multiply(lhs, rhs) => Ok(match (lhs.evaluate()?, rhs.evaluate()?) {
-                (Value::Null, Value::Null) => Value::Null,
-                (Value::Tinyint(lhs), Value::Tinyint(rhs)) => Value::Tinyint(
-                    lhs.checked_mul(rhs)
+            Expression::Modulo(lhs, rhs) => Ok(
+                match coerce(
+                    lhs.evaluate_with(row, mode)?,
+                    rhs.evaluate_with(row, mode)?,
+                    mode,
+                )? {
+                    // check zero
+                    (lhs, rhs) if (lhs.check_int() || lhs.check_float()) && rhs.check_zero() => {
+                        return Err(Error::ValuesNotMatch(
+                            "modulo",
+                            lhs.to_string(),
+                            rhs.to_string(),
+                        ))
+                    }
+                    (Value::Null, _) | (_, Value::Null) => Value::Null,
+                    (Value::Tinyint(lhs), Value::Tinyint(rhs)) => Value::Tinyint(
+                        lhs.checked_rem(rhs)
+                            .ok_or(Error::OutOfBound("Tinyint", "overflow"))?,
+                    ),
+                    (Value::Tinyint(lhs), Value::Smallint(rhs)) => Value::Smallint(
+                        (lhs as i32)
+                            .checked_rem(rhs)
+                            .ok_or(Error::OutOfBound("Smallint", "overflow"))?,
+                    ),
+                    (Value::Tinyint(lhs), Value::Integer(rhs)) => Value::Integer(
+                        (lhs as i64)
+                            .checked_rem(rhs)
+                            .ok_or(Error::OutOfBound("Integer", "overflow"))?,
+                    ),
+                    (Value::Tinyint(lhs), Value::Bigint(rhs)) => Value::Bigint(
+                        (lhs as i128)
+                            .checked_rem(rhs)
+                            .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
+                    ),
+                    (Value::Tinyint(lhs), Value::Float(OrderedFloat(rhs))) => {
+                        Value::Float(OrderedFloat(lhs as f32 % rhs))
+                    }
+                    (Value::Tinyint(lhs), Value::Double(OrderedFloat(rhs))) => {
+                        Value::Double(OrderedFloat(lhs as f64 % rhs))
+                    }
+                    (Value::Smallint(lhs), Value::Tinyint(rhs)) => Value::Smallint(
+                        lhs.checked_rem(rhs as i32)
+                            .ok_or(Error::OutOfBound("Smallint", "overflow"))?,
+                    ),
+                    (Value::Smallint(lhs), Value::Smallint(rhs)) => Value::Smallint(
+                        lhs.checked_rem(rhs)
+                            .ok_or(Error::OutOfBound("Smallint", "overflow"))?,
+                    ),
+                    (Value::Smallint(lhs), Value::Integer(rhs)) => Value::Integer(
+                        (lhs as i64)
+                            .checked_rem(rhs)
+                            .ok_or(Error::OutOfBound("Integer", "overflow"))?,
+                    ),
+                    (Value::Smallint(lhs), Value::Bigint(rhs)) => Value::Bigint(
+                        (lhs as i128)
+                            .checked_rem(rhs)
+                            .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
+                    ),
+                    (Value::Smallint(lhs), Value::Float(rhs)) => {
+                        Value::Float(OrderedFloat(lhs as f32) % rhs)
+                    }
+                    (Value::Smallint(lhs), Value::Double(rhs)) => {
+                        Value::Double(OrderedFloat(lhs as f64) % rhs)
+                    }
+                    (Value::Integer(lhs), Value::Tinyint(rhs)) => Value::Integer(
+                        lhs.checked_rem(rhs as i64)
+                            .ok_or(Error::OutOfBound("Integer", "overflow"))?,
+                    ),
+                    (Value::Integer(lhs), Value::Smallint(rhs)) => Value::Integer(
+                        lhs.checked_rem(rhs as i64)
+                            .ok_or(Error::OutOfBound("Integer", "overflow"))?,
+                    ),
+                    (Value::Integer(lhs), Value::Integer(rhs)) => Value::Integer(
+                        lhs.checked_rem(rhs)
+                            .ok_or(Error::OutOfBound("Integer", "overflow"))?,
+                    ),
+                    (Value::Integer(lhs), Value::Bigint(rhs)) => Value::Bigint(
+                        (lhs as i128)
+                            .checked_rem(rhs)
+                            .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
+                    ),
+                    (Value::Integer(lhs), Value::Float(rhs)) => {
+                        Value::Float(OrderedFloat(lhs as f32) % rhs)
+                    }
+                    (Value::Integer(lhs), Value::Double(rhs)) => {
+                        Value::Double(OrderedFloat(lhs as f64) % rhs)
+                    }
+                    (Value::Bigint(lhs), Value::Tinyint(rhs)) => Value::Bigint(
+                        lhs.checked_rem(rhs as i128)
+                            .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
+                    ),
+                    (Value::Bigint(lhs), Value::Smallint(rhs)) => Value::Bigint(
+                        lhs.checked_rem(rhs as i128)
+                            .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
+                    ),
+                    (Value::Bigint(lhs), Value::Integer(rhs)) => Value::Bigint(
+                        lhs.checked_rem(rhs as i128)
+                            .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
+                    ),
+                    (Value::Bigint(lhs), Value::Bigint(rhs)) => Value::Bigint(
+                        lhs.checked_rem(rhs)
+                            .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
+                    ),
+                    (Value::Bigint(lhs), Value::Float(rhs)) => {
+                        Value::Float(OrderedFloat(lhs as f32) % rhs)
+                    }
+                    (Value::Bigint(lhs), Value::Double(rhs)) => {
+                        Value::Double(OrderedFloat(lhs as f64) % rhs)
+                    }
+                    (Value::Float(lhs), Value::Tinyint(rhs)) => {
+                        Value::Float(lhs % OrderedFloat(rhs as f32))
+                    }
+                    (Value::Float(lhs), Value::Smallint(rhs)) => {
+                        Value::Float(lhs % OrderedFloat(rhs as f32))
+                    }
+                    (Value::Float(lhs), Value::Integer(rhs)) => {
+                        Value::Float(lhs % OrderedFloat(rhs as f32))
+                    }
+                    (Value::Float(lhs), Value::Bigint(rhs)) => {
+                        Value::Float(lhs % OrderedFloat(rhs as f32))
+                    }
+                    (Value::Float(lhs), Value::Float(rhs)) => Value::Float(lhs % rhs),
+                    (Value::Float(OrderedFloat(lhs)), Value::Double(rhs)) => {
+                        Value::Double(OrderedFloat(lhs as f64) % rhs)
+                    }
+                    (Value::Double(lhs), Value::Tinyint(rhs)) => {
+                        Value::Double(lhs % OrderedFloat(rhs as f64))
+                    }
+                    (Value::Double(lhs), Value::Smallint(rhs)) => {
+                        Value::Double(lhs % OrderedFloat(rhs as f64))
+                    }
+                    (Value::Double(lhs), Value::Integer(rhs)) => {
+                        Value::Double(lhs % OrderedFloat(rhs as f64))
+                    }
+                    (Value::Double(lhs), Value::Bigint(rhs)) => {
+                        Value::Double(lhs % OrderedFloat(rhs as f64))
+                    }
+                    (Value::Double(lhs), Value::Float(OrderedFloat(rhs))) => {
+                        Value::Double(lhs % OrderedFloat(rhs as f64))
+                    }
+                    (Value::Double(lhs), Value::Double(rhs)) => Value::Double(lhs % rhs),
+                    (lhs, rhs) => {
+                        return Err(Error::ValuesNotMatch(
+                            "modulo",
+                            lhs.to_string(),
+                            rhs.to_string(),
+                        ))
+                    }
+                },
+            ),
+            Expression::Subtract(lhs, rhs) => Ok(
+                match coerce(
+                    lhs.evaluate_with(row, mode)?,
+                    rhs.evaluate_with(row, mode)?,
+                    mode,
+                )? {
+                    (Value::Null, Value::Null) => Value::Null,
+                    (Value::Tinyint(lhs), Value::Tinyint(rhs)) => Value::Tinyint(
+                        lhs.checked_sub(rhs)
+                            .ok_or(Error::OutOfBound("Tinyint", "underflow"))?,
+                    ),
+                    (Value::Tinyint(lhs), Value::Smallint(rhs)) => Value::Smallint(
+                        (lhs as i32)
+                            .checked_sub(rhs)
+                            .ok_or(Error::OutOfBound("Smallint", "underflow"))?,
+                    ),
+                    (Value::Tinyint(lhs), Value::Integer(rhs)) => Value::Integer(
+                        (lhs as i64)
+                            .checked_sub(rhs)
+                            .ok_or(Error::OutOfBound("Integer", "underflow"))?,
+                    ),
+                    (Value::Tinyint(lhs), Value::Bigint(rhs)) => Value::Bigint(
+                        (lhs as i128)
+                            .checked_sub(rhs)
+                            .ok_or(Error::OutOfBound("Bigint", "underflow"))?,
+                    ),
+                    (Value::Tinyint(lhs), Value::Float(OrderedFloat(rhs))) => {
+                        Value::Float(OrderedFloat(lhs as f32 - rhs))
+                    }
+                    (Value::Tinyint(lhs), Value::Double(OrderedFloat(rhs))) => {
+                        Value::Double(OrderedFloat(lhs as f64 - rhs))
+                    }
+                    (Value::Smallint(lhs), Value::Tinyint(rhs)) => Value::Smallint(
+                        lhs.checked_sub(rhs as i32)
+                            .ok_or(Error::OutOfBound("Smallint", "underflow"))?,
+                    ),
+                    (Value::Smallint(lhs), Value::Smallint(rhs)) => Value::Smallint(
+                        lhs.checked_sub(rhs)
+                            .ok_or(Error::OutOfBound("Smallint", "underflow"))?,
+                    ),
+                    (Value::Smallint(lhs), Value::Integer(rhs)) => Value::Integer(
+                        (lhs as i64)
+                            .checked_sub(rhs)
+                            .ok_or(Error::OutOfBound("Integer", "underflow"))?,
+                    ),
+                    (Value::Smallint(lhs), Value::Bigint(rhs)) => Value::Bigint(
+                        (lhs as i128)
+                            .checked_sub(rhs)
+                            .ok_or(Error::OutOfBound("Bigint", "underflow"))?,
+                    ),
+                    (Value::Smallint(lhs), Value::Float(rhs)) => {
+                        Value::Float(OrderedFloat(lhs as f32) - rhs)
+                    }
+                    (Value::Smallint(lhs), Value::Double(rhs)) => {
+                        Value::Double(OrderedFloat(lhs as f64) - rhs)
+                    }
+                    (Value::Integer(lhs), Value::Tinyint(rhs)) => Value::Integer(
+                        lhs.checked_sub(rhs as i64)
+                            .ok_or(Error::OutOfBound("Integer", "underflow"))?,
+                    ),
+                    (Value::Integer(lhs), Value::Smallint(rhs)) => Value::Integer(
+                        lhs.checked_sub(rhs as i64)
+                            .ok_or(Error::OutOfBound("Integer", "underflow"))?,
+                    ),
+                    (Value::Integer(lhs), Value::Integer(rhs)) => Value::Integer(
+                        lhs.checked_sub(rhs)
+                            .ok_or(Error::OutOfBound("Integer", "underflow"))?,
+                    ),
+                    (Value::Integer(lhs), Value::Bigint(rhs)) => Value::Bigint(
+                        (lhs as i128)
+                            .checked_sub(rhs)
+                            .ok_or(Error::OutOfBound("Bigint", "underflow"))?,
+                    ),
+                    (Value::Integer(lhs), Value::Float(rhs)) => {
+                        Value::Float(OrderedFloat(lhs as f32) - rhs)
+                    }
+                    (Value::Integer(lhs), Value::Double(rhs)) => {
+                        Value::Double(OrderedFloat(lhs as f64) - rhs)
+                    }
+                    (Value::Bigint(lhs), Value::Tinyint(rhs)) => Value::Bigint(
+                        lhs.checked_sub(rhs as i128)
+                            .ok_or(Error::OutOfBound("Bigint", "underflow"))?,
+                    ),
+                    (Value::Bigint(lhs), Value::Smallint(rhs)) => Value::Bigint(
+                        lhs.checked_sub(rhs as i128)
+                            .ok_or(Error::OutOfBound("Bigint", "underflow"))?,
+                    ),
+                    (Value::Bigint(lhs), Value::Integer(rhs)) => Value::Bigint(
+                        lhs.checked_sub(rhs as i128)
+                            .ok_or(Error::OutOfBound("Bigint", "underflow"))?,
+                    ),
+                    (Value::Bigint(lhs), Value::Bigint(rhs)) => Value::Bigint(
+                        lhs.checked_sub(rhs)
+                            .ok_or(Error::OutOfBound("Bigint", "underflow"))?,
+                    ),
+                    (Value::Bigint(lhs), Value::Float(rhs)) => {
+                        Value::Float(OrderedFloat(lhs as f32) - rhs)
+                    }
+                    (Value::Bigint(lhs), Value::Double(rhs)) => {
+                        Value::Double(OrderedFloat(lhs as f64) - rhs)
+                    }
+                    (Value::Float(lhs), Value::Tinyint(rhs)) => {
+                        Value::Float(lhs - OrderedFloat(rhs as f32))
+                    }
+                    (Value::Float(lhs), Value::Smallint(rhs)) => {
+                        Value::Float(lhs - OrderedFloat(rhs as f32))
+                    }
+                    (Value::Float(lhs), Value::Integer(rhs)) => {
+                        Value::Float(lhs - OrderedFloat(rhs as f32))
+                    }
+                    (Value::Float(lhs), Value::Bigint(rhs)) => {
+                        Value::Float(lhs - OrderedFloat(rhs as f32))
+                    }
+                    (Value::Float(lhs), Value::Float(rhs)) => Value::Float(lhs - rhs),
+                    (Value::Float(OrderedFloat(lhs)), Value::Double(rhs)) => {
+                        Value::Double(OrderedFloat(lhs as f64) - rhs)
+                    }
+                    (Value::Double(lhs), Value::Tinyint(rhs)) => {
+                        Value::Double(lhs - OrderedFloat(rhs as f64))
+                    }
+                    (Value::Double(lhs), Value::Smallint(rhs)) => {
+                        Value::Double(lhs - OrderedFloat(rhs as f64))
+                    }
+                    (Value::Double(lhs), Value::Integer(rhs)) => {
+                        Value::Double(lhs - OrderedFloat(rhs as f64))
+                    }
+                    (Value::Double(lhs), Value::Bigint(rhs)) => {
+                        Value::Double(lhs - OrderedFloat(rhs as f64))
+                    }
+                    (Value::Double(lhs), Value::Float(OrderedFloat(rhs))) => {
+                        Value::Double(lhs - OrderedFloat(rhs as f64))
+                    }
+                    (Value::Double(lhs), Value::Double(rhs)) => Value::Double(lhs - rhs),
+                    (lhs @ Value::Decimal(_), rhs) | (lhs, rhs @ Value::Decimal(_)) => {
+                        decimal_arithmetic("subtract", lhs, rhs, Decimal::checked_sub, |l, r| {
+                            l - r
+                        })?
+                    }
+                    (lhs, rhs) => {
+                        return Err(Error::ValuesNotMatch(
+                            "subtract",
+                            lhs.to_string(),
+                            rhs.to_string(),
+                        ))
+                    }
+                },
+            ),
+            Expression::Multiply(lhs, rhs) => Ok(
+                match coerce(
+                    lhs.evaluate_with(row, mode)?,
+                    rhs.evaluate_with(row, mode)?,
+                    mode,
+                )? {
+                    (Value::Null, Value::Null) => Value::Null,
+                    (Value::Tinyint(lhs), Value::Tinyint(rhs)) => Value::Tinyint(
+                        lhs.checked_mul(rhs)
+                            .ok_or(Error::OutOfBound("Tinyint", "overflow"))?,
+                    ),
+                    (Value::Tinyint(lhs), Value::Smallint(rhs)) => Value::Smallint(
+                        (lhs as i32)
+                            .checked_mul(rhs)
+                            .ok_or(Error::OutOfBound("Smallint", "overflow"))?,
+                    ),
+                    (Value::Tinyint(lhs), Value::Integer(rhs)) => Value::Integer(
+                        (lhs as i64)
+                            .checked_mul(rhs)
+                            .ok_or(Error::OutOfBound("Integer", "overflow"))?,
+                    ),
+                    (Value::Tinyint(lhs), Value::Bigint(rhs)) => Value::Bigint(
+                        (lhs as i128)
+                            .checked_mul(rhs)
+                            .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
+                    ),
+                    (Value::Tinyint(lhs), Value::Float(OrderedFloat(rhs))) => {
+                        Value::Float(OrderedFloat(lhs as f32 * rhs))
+                    }
+                    (Value::Tinyint(lhs), Value::Double(OrderedFloat(rhs))) => {
+                        Value::Double(OrderedFloat(lhs as f64 * rhs))
+                    }
+                    (Value::Smallint(lhs), Value::Tinyint(rhs)) => Value::Smallint(
+                        lhs.checked_mul(rhs as i32)
+                            .ok_or(Error::OutOfBound("Smallint", "overflow"))?,
+                    ),
+                    (Value::Smallint(lhs), Value::Smallint(rhs)) => Value::Smallint(
+                        lhs.checked_mul(rhs)
+                            .ok_or(Error::OutOfBound("Smallint", "overflow"))?,
+                    ),
+                    (Value::Smallint(lhs), Value::Integer(rhs)) => Value::Integer(
+                        (lhs as i64)
+                            .checked_mul(rhs)
+                            .ok_or(Error::OutOfBound("Integer", "overflow"))?,
+                    ),
+                    (Value::Smallint(lhs), Value::Bigint(rhs)) => Value::Bigint(
+                        (lhs as i128)
+                            .checked_mul(rhs)
+                            .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
+                    ),
+                    (Value::Smallint(lhs), Value::Float(rhs)) => {
+                        Value::Float(OrderedFloat(lhs as f32) * rhs)
+                    }
+                    (Value::Smallint(lhs), Value::Double(rhs)) => {
+                        Value::Double(OrderedFloat(lhs as f64) * rhs)
+                    }
+                    (Value::Integer(lhs), Value::Tinyint(rhs)) => Value::Integer(
+                        lhs.checked_mul(rhs as i64)
+                            .ok_or(Error::OutOfBound("Integer", "overflow"))?,
+                    ),
+                    (Value::Integer(lhs), Value::Smallint(rhs)) => Value::Integer(
+                        lhs.checked_mul(rhs as i64)
+                            .ok_or(Error::OutOfBound("Integer", "overflow"))?,
+                    ),
+                    (Value::Integer(lhs), Value::Integer(rhs)) => Value::Integer(
+                        lhs.checked_mul(rhs)
+                            .ok_or(Error::OutOfBound("Integer", "overflow"))?,
+                    ),
+                    (Value::Integer(lhs), Value::Bigint(rhs)) => Value::Bigint(
+                        (lhs as i128)
+                            .checked_mul(rhs)
+                            .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
+                    ),
+                    (Value::Integer(lhs), Value::Float(rhs)) => {
+                        Value::Float(OrderedFloat(lhs as f32) * rhs)
+                    }
+                    (Value::Integer(lhs), Value::Double(rhs)) => {
+                        Value::Double(OrderedFloat(lhs as f64) * rhs)
+                    }
+                    (Value::Bigint(lhs), Value::Tinyint(rhs)) => Value::Bigint(
+                        lhs.checked_mul(rhs as i128)
+                            .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
+                    ),
+                    (Value::Bigint(lhs), Value::Smallint(rhs)) => Value::Bigint(
+                        lhs.checked_mul(rhs as i128)
+                            .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
+                    ),
+                    (Value::Bigint(lhs), Value::Integer(rhs)) => Value::Bigint(
+                        lhs.checked_mul(rhs as i128)
+                            .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
+                    ),
+                    (Value::Bigint(lhs), Value::Bigint(rhs)) => Value::Bigint(
+                        lhs.checked_mul(rhs)
+                            .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
+                    ),
+                    (Value::Bigint(lhs), Value::Float(rhs)) => {
+                        Value::Float(OrderedFloat(lhs as f32) * rhs)
+                    }
+                    (Value::Bigint(lhs), Value::Double(rhs)) => {
+                        Value::Double(OrderedFloat(lhs as f64) * rhs)
+                    }
+                    (Value::Float(lhs), Value::Tinyint(rhs)) => {
+                        Value::Float(lhs * OrderedFloat(rhs as f32))
+                    }
+                    (Value::Float(lhs), Value::Smallint(rhs)) => {
+                        Value::Float(lhs * OrderedFloat(rhs as f32))
+                    }
+                    (Value::Float(lhs), Value::Integer(rhs)) => {
+                        Value::Float(lhs * OrderedFloat(rhs as f32))
+                    }
+                    (Value::Float(lhs), Value::Bigint(rhs)) => {
+                        Value::Float(lhs * OrderedFloat(rhs as f32))
+                    }
+                    (Value::Float(lhs), Value::Float(rhs)) => Value::Float(lhs * rhs),
+                    (Value::Float(OrderedFloat(lhs)), Value::Double(rhs)) => {
+                        Value::Double(OrderedFloat(lhs as f64) * rhs)
+                    }
+                    (Value::Double(lhs), Value::Tinyint(rhs)) => {
+                        Value::Double(lhs * OrderedFloat(rhs as f64))
+                    }
+                    (Value::Double(lhs), Value::Smallint(rhs)) => {
+                        Value::Double(lhs * OrderedFloat(rhs as f64))
+                    }
+                    (Value::Double(lhs), Value::Integer(rhs)) => {
+                        Value::Double(lhs * OrderedFloat(rhs as f64))
+                    }
+                    (Value::Double(lhs), Value::Bigint(rhs)) => {
+                        Value::Double(lhs * OrderedFloat(rhs as f64))
+                    }
+                    (Value::Double(lhs), Value::Float(OrderedFloat(rhs))) => {
+                        Value::Double(lhs * OrderedFloat(rhs as f64))
+                    }
+                    (Value::Double(lhs), Value::Double(rhs)) => Value::Double(lhs * rhs),
+                    (lhs @ Value::Decimal(_), rhs) | (lhs, rhs @ Value::Decimal(_)) => {
+                        decimal_arithmetic("multiply", lhs, rhs, Decimal::checked_mul, |l, r| {
+                            l * r
+                        })?
+                    }
+                    (lhs, rhs) => {
+                        return Err(Error::ValuesNotMatch(
+                            "multiply",
+                            lhs.to_string(),
+                            rhs.to_string(),
+                        ))
+                    }
+                },
+            ),
+            Expression::Divide(lhs, rhs) => Ok(
+                match coerce(
+                    lhs.evaluate_with(row, mode)?,
+                    rhs.evaluate_with(row, mode)?,
+                    mode,
+                )? {
+                    // check zero
+                    (lhs, rhs) if (lhs.check_int() || lhs.check_float()) && rhs.check_zero() => {
+                        return Err(Error::ValuesNotMatch(
+                            "divide",
+                            lhs.to_string(),
+                            rhs.to_string(),
+                        ))
+                    }
+                    (Value::Null, Value::Null) => Value::Null,
+                    (Value::Tinyint(lhs), Value::Tinyint(rhs)) => Value::Tinyint(
+                        lhs.checked_div(rhs)
+                            .ok_or(Error::OutOfBound("Tinyint", "underflow"))?,
+                    ),
+                    (Value::Tinyint(lhs), Value::Smallint(rhs)) => Value::Smallint(
+                        (lhs as i32)
+                            .checked_div(rhs)
+                            .ok_or(Error::OutOfBound("Smallint", "underflow"))?,
+                    ),
+                    (Value::Tinyint(lhs), Value::Integer(rhs)) => Value::Integer(
+                        (lhs as i64)
+                            .checked_div(rhs)
+                            .ok_or(Error::OutOfBound("Integer", "underflow"))?,
+                    ),
+                    (Value::Tinyint(lhs), Value::Bigint(rhs)) => Value::Bigint(
+                        (lhs as i128)
+                            .checked_div(rhs)
+                            .ok_or(Error::OutOfBound("Bigint", "underflow"))?,
+                    ),
+                    (Value::Tinyint(lhs), Value::Float(OrderedFloat(rhs))) => {
+                        Value::Float(OrderedFloat(lhs as f32 / rhs))
+                    }
+                    (Value::Tinyint(lhs), Value::Double(OrderedFloat(rhs))) => {
+                        Value::Double(OrderedFloat(lhs as f64 / rhs))
+                    }
+                    (Value::Smallint(lhs), Value::Tinyint(rhs)) => Value::Smallint(
+                        lhs.checked_div(rhs as i32)
+                            .ok_or(Error::OutOfBound("Smallint", "underflow"))?,
+                    ),
+                    (Value::Smallint(lhs), Value::Smallint(rhs)) => Value::Smallint(
+                        lhs.checked_div(rhs)
+                            .ok_or(Error::OutOfBound("Smallint", "underflow"))?,
+                    ),
+                    (Value::Smallint(lhs), Value::Integer(rhs)) => Value::Integer(
+                        (lhs as i64)
+                            .checked_div(rhs)
+                            .ok_or(Error::OutOfBound("Integer", "underflow"))?,
+                    ),
+                    (Value::Smallint(lhs), Value::Bigint(rhs)) => Value::Bigint(
+                        (lhs as i128)
+                            .checked_div(rhs)
+                            .ok_or(Error::OutOfBound("Bigint", "underflow"))?,
+                    ),
+                    (Value::Smallint(lhs), Value::Float(rhs)) => {
+                        Value::Float(OrderedFloat(lhs as f32) / rhs)
+                    }
+                    (Value::Smallint(lhs), Value::Double(rhs)) => {
+                        Value::Double(OrderedFloat(lhs as f64) / rhs)
+                    }
+                    (Value::Integer(lhs), Value::Tinyint(rhs)) => Value::Integer(
+                        lhs.checked_div(rhs as i64)
+                            .ok_or(Error::OutOfBound("Integer", "underflow"))?,
+                    ),
+                    (Value::Integer(lhs), Value::Smallint(rhs)) => Value::Integer(
+                        lhs.checked_div(rhs as i64)
+                            .ok_or(Error::OutOfBound("Integer", "underflow"))?,
+                    ),
+                    (Value::Integer(lhs), Value::Integer(rhs)) => Value::Integer(
+                        lhs.checked_div(rhs)
+                            .ok_or(Error::OutOfBound("Integer", "underflow"))?,
+                    ),
+                    (Value::Integer(lhs), Value::Bigint(rhs)) => Value::Bigint(
+                        (lhs as i128)
+                            .checked_div(rhs)
+                            .ok_or(Error::OutOfBound("Bigint", "underflow"))?,
+                    ),
+                    (Value::Integer(lhs), Value::Float(rhs)) => {
+                        Value::Float(OrderedFloat(lhs as f32) / rhs)
+                    }
+                    (Value::Integer(lhs), Value::Double(rhs)) => {
+                        Value::Double(OrderedFloat(lhs as f64) / rhs)
+                    }
+                    (Value::Bigint(lhs), Value::Tinyint(rhs)) => Value::Bigint(
+                        lhs.checked_div(rhs as i128)
+                            .ok_or(Error::OutOfBound("Bigint", "underflow"))?,
+                    ),
+                    (Value::Bigint(lhs), Value::Smallint(rhs)) => Value::Bigint(
+                        lhs.checked_div(rhs as i128)
+                            .ok_or(Error::OutOfBound("Bigint", "underflow"))?,
+                    ),
+                    (Value::Bigint(lhs), Value::Integer(rhs)) => Value::Bigint(
+                        lhs.checked_div(rhs as i128)
+                            .ok_or(Error::OutOfBound("Bigint", "underflow"))?,
+                    ),
+                    (Value::Bigint(lhs), Value::Bigint(rhs)) => Value::Bigint(
+                        lhs.checked_div(rhs)
+                            .ok_or(Error::OutOfBound("Bigint", "underflow"))?,
+                    ),
+                    (Value::Bigint(lhs), Value::Float(rhs)) => {
+                        Value::Float(OrderedFloat(lhs as f32) / rhs)
+                    }
+                    (Value::Bigint(lhs), Value::Double(rhs)) => {
+                        Value::Double(OrderedFloat(lhs as f64) / rhs)
+                    }
+                    (Value::Float(lhs), Value::Tinyint(rhs)) => {
+                        Value::Float(lhs / OrderedFloat(rhs as f32))
+                    }
+                    (Value::Float(lhs), Value::Smallint(rhs)) => {
+                        Value::Float(lhs / OrderedFloat(rhs as f32))
+                    }
+                    (Value::Float(lhs), Value::Integer(rhs)) => {
+                        Value::Float(lhs / OrderedFloat(rhs as f32))
+                    }
+                    (Value::Float(lhs), Value::Bigint(rhs)) => {
+                        Value::Float(lhs / OrderedFloat(rhs as f32))
+                    }
+                    (Value::Float(lhs), Value::Float(rhs)) => Value::Float(lhs / rhs),
+                    (Value::Float(OrderedFloat(lhs)), Value::Double(rhs)) => {
+                        Value::Double(OrderedFloat(lhs as f64) / rhs)
+                    }
+                    (Value::Double(lhs), Value::Tinyint(rhs)) => {
+                        Value::Double(lhs / OrderedFloat(rhs as f64))
+                    }
+                    (Value::Double(lhs), Value::Smallint(rhs)) => {
+                        Value::Double(lhs / OrderedFloat(rhs as f64))
+                    }
+                    (Value::Double(lhs), Value::Integer(rhs)) => {
+                        Value::Double(lhs / OrderedFloat(rhs as f64))
+                    }
+                    (Value::Double(lhs), Value::Bigint(rhs)) => {
+                        Value::Double(lhs / OrderedFloat(rhs as f64))
+                    }
+                    (Value::Double(lhs), Value::Float(OrderedFloat(rhs))) => {
+                        Value::Double(lhs / OrderedFloat(rhs as f64))
+                    }
+                    (Value::Double(lhs), Value::Double(rhs)) => Value::Double(lhs / rhs),
+                    (lhs, rhs) => {
+                        return Err(Error::ValuesNotMatch(
+                            "subtract",
+                            lhs.to_string(),
+                            rhs.to_string(),
+                        ))
+                    }
+                },
+            ),
+            Expression::Exponentiate(lhs, rhs) => Ok(
+                match coerce(
+                    lhs.evaluate_with(row, mode)?,
+                    rhs.evaluate_with(row, mode)?,
+                    mode,
+                )? {
+                    (Value::Null, _) | (_, Value::Null) => Value::Null,
+                    // Stay in the integer domain unless the exponent is negative or it overflows
+                    (lhs, rhs) if lhs.check_int() && rhs.check_int() => {
+                        let bigint =
+                            matches!(lhs, Value::Bigint(_)) || matches!(rhs, Value::Bigint(_));
+                        let (lhs, rhs) = (integer(&lhs).unwrap(), integer(&rhs).unwrap());
+                        integer_power(lhs, rhs, bigint)
+                            .unwrap_or(Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64))))
+                    }
+                    (Value::Tinyint(lhs), Value::Float(OrderedFloat(rhs))) => {
+                        Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
+                    }
+                    (Value::Tinyint(lhs), Value::Double(OrderedFloat(rhs))) => {
+                        Value::Double(OrderedFloat((lhs as f64).powf(rhs)))
+                    }
+                    (Value::Smallint(lhs), Value::Float(OrderedFloat(rhs))) => {
+                        Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
+                    }
+                    (Value::Smallint(lhs), Value::Double(OrderedFloat(rhs))) => {
+                        Value::Double(OrderedFloat((lhs as f64).powf(rhs)))
+                    }
+                    (Value::Integer(lhs), Value::Float(OrderedFloat(rhs))) => {
+                        Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
+                    }
+                    (Value::Integer(lhs), Value::Double(OrderedFloat(rhs))) => {
+                        Value::Double(OrderedFloat((lhs as f64).powf(rhs)))
+                    }
+                    (Value::Bigint(lhs), Value::Float(OrderedFloat(rhs))) => {
+                        Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
+                    }
+                    (Value::Bigint(lhs), Value::Double(OrderedFloat(rhs))) => {
+                        Value::Double(OrderedFloat((lhs as f64).powf(rhs)))
+                    }
+                    (Value::Float(OrderedFloat(lhs)), Value::Tinyint(rhs)) => {
+                        Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
+                    }
+                    (Value::Float(OrderedFloat(lhs)), Value::Smallint(rhs)) => {
+                        Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
+                    }
+                    (Value::Float(OrderedFloat(lhs)), Value::Integer(rhs)) => {
+                        Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
+                    }
+                    (Value::Float(OrderedFloat(lhs)), Value::Bigint(rhs)) => {
+                        Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
+                    }
+                    (Value::Float(lhs), Value::Float(rhs)) => {
+                        Value::Float(OrderedFloat(lhs.0.powf(rhs.0)))
+                    }
+                    (Value::Float(lhs), Value::Double(rhs)) => {
+                        Value::Double(OrderedFloat((lhs.0 as f64).powf(rhs.0)))
+                    }
+                    (Value::Double(OrderedFloat(lhs)), Value::Tinyint(rhs)) => {
+                        Value::Double(OrderedFloat(lhs.powf(rhs as f64)))
+                    }
+                    (Value::Double(OrderedFloat(lhs)), Value::Smallint(rhs)) => {
+                        Value::Double(OrderedFloat(lhs.powf(rhs as f64)))
+                    }
+                    (Value::Double(OrderedFloat(lhs)), Value::Integer(rhs)) => {
+                        Value::Double(OrderedFloat(lhs.powf(rhs as f64)))
+                    }
+                    (Value::Double(OrderedFloat(lhs)), Value::Bigint(rhs)) => {
+                        Value::Double(OrderedFloat(lhs.powf(rhs as f64)))
+                    }
+                    (Value::Double(lhs), Value::Float(rhs)) => {
+                        Value::Double(OrderedFloat(lhs.powf(rhs.0 as f64)))
+                    }
+                    (Value::Double(lhs), Value::Double(rhs)) => {
+                        Value::Double(OrderedFloat(lhs.powf(rhs.0)))
+                    }
+                    (lhs, rhs) => {
+                        return Err(Error::ValuesNotMatch(
+                            "exponentiate",
+                            lhs.to_string(),
+                            rhs.to_string(),
+                        ))
+                    }
+                },
+            ),
+            Expression::Negate(expr) => Ok(match expr.evaluate_with(row, mode)? {
+                Value::Null => Value::Null,
+                Value::Tinyint(expr) => Value::Tinyint(
+                    expr.checked_neg()
                         .ok_or(Error::OutOfBound("Tinyint", "overflow"))?,
                 ),
-                (Value::Tinyint(lhs), Value::Smallint(rhs)) => Value::Smallint(
-                    (lhs as i32)
-                        .checked_mul(rhs)
+                Value::Smallint(expr) => Value::Smallint(
+                    expr.checked_neg()
                         .ok_or(Error::OutOfBound("Smallint", "overflow"))?,
                 ),
-                (Value::Tinyint(lhs), Value::Integer(rhs)) => Value::Integer(
-                    (lhs as i64)
-                        .checked_mul(rhs)
+                Value::Integer(expr) => Value::Integer(
+                    expr.checked_neg()
                         .ok_or(Error::OutOfBound("Integer", "overflow"))?,
                 ),
-                (Value::Tinyint(lhs), Value::Bigint(rhs)) => Value::Bigint(
-                    (lhs as i128)
-                        .checked_mul(rhs)
+                Value::Bigint(expr) => Value::Bigint(
+                    expr.checked_neg()
                         .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
                 ),
-                (Value::Tinyint(lhs), Value::Float(OrderedFloat(rhs))) => {
-                    Value::Float(OrderedFloat(lhs as f32 * rhs))
-                }
-                (Value::Tinyint(lhs), Value::Double(OrderedFloat(rhs))) => {
-                    Value::Double(OrderedFloat(lhs as f64 * rhs))
-                }
-                (Value::Smallint(lhs), Value::Tinyint(rhs)) => Value::Smallint(
-                    lhs.checked_mul(rhs as i32)
-                        .ok_or(Error::OutOfBound("Smallint", "overflow"))?,
-                ),
-                (Value::Smallint(lhs), Value::Smallint(rhs)) => Value::Smallint(
-                    lhs.checked_mul(rhs)
-                        .ok_or(Error::OutOfBound("Smallint", "overflow"))?,
-                ),
-                (Value::Smallint(lhs), Value::Integer(rhs)) => Value::Integer(
-                    (lhs as i64)
-                        .checked_mul(rhs)
-                        .ok_or(Error::OutOfBound("Integer", "overflow"))?,
-                ),
-                (Value::Smallint(lhs), Value::Bigint(rhs)) => Value::Bigint(
-                    (lhs as i128)
-                        .checked_mul(rhs)
-                        .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
-                ),
-                (Value::Smallint(lhs), Value::Float(rhs)) => {
-                    Value::Float(OrderedFloat(lhs as f32) * rhs)
-                }
-                (Value::Smallint(lhs), Value::Double(rhs)) => {
-                    Value::Double(OrderedFloat(lhs as f64) * rhs)
-                }
-                (Value::Integer(lhs), Value::Tinyint(rhs)) => Value::Integer(
-                    lhs.checked_mul(rhs as i64)
-                        .ok_or(Error::OutOfBound("Integer", "overflow"))?,
-                ),
-                (Value::Integer(lhs), Value::Smallint(rhs)) => Value::Integer(
-                    lhs.checked_mul(rhs as i64)
-                        .ok_or(Error::OutOfBound("Integer", "overflow"))?,
-                ),
-                (Value::Integer(lhs), Value::Integer(rhs)) => Value::Integer(
-                    lhs.checked_mul(rhs)
-                        .ok_or(Error::OutOfBound("Integer", "overflow"))?,
-                ),
-                (Value::Integer(lhs), Value::Bigint(rhs)) => Value::Bigint(
-                    (lhs as i128)
-                        .checked_mul(rhs)
-                        .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
-                ),
-                (Value::Integer(lhs), Value::Float(rhs)) => {
-                    Value::Float(OrderedFloat(lhs as f32) * rhs)
-                }
-                (Value::Integer(lhs), Value::Double(rhs)) => {
-                    Value::Double(OrderedFloat(lhs as f64) * rhs)
-                }
-                (Value::Bigint(lhs), Value::Tinyint(rhs)) => Value::Bigint(
-                    lhs.checked_mul(rhs as i128)
-                        .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
-                ),
-                (Value::Bigint(lhs), Value::Smallint(rhs)) => Value::Bigint(
-                    lhs.checked_mul(rhs as i128)
-                        .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
-                ),
-                (Value::Bigint(lhs), Value::Integer(rhs)) => Value::Bigint(
-                    lhs.checked_mul(rhs as i128)
-                        .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
-                ),
-                (Value::Bigint(lhs), Value::Bigint(rhs)) => Value::Bigint(
-                    lhs.checked_mul(rhs)
-                        .ok_or(Error::OutOfBound("Bigint", "overflow"))?,
-                ),
-                (Value::Bigint(lhs), Value::Float(rhs)) => {
-                    Value::Float(OrderedFloat(lhs as f32) * rhs)
-                }
-                (Value::Bigint(lhs), Value::Double(rhs)) => {
-                    Value::Double(OrderedFloat(lhs as f64) * rhs)
-                }
-                (Value::Float(lhs), Value::Tinyint(rhs)) => {
-                    Value::Float(lhs * OrderedFloat(rhs as f32))
-                }
-                (Value::Float(lhs), Value::Smallint(rhs)) => {
-                    Value::Float(lhs * OrderedFloat(rhs as f32))
-                }
-                (Value::Float(lhs), Value::Integer(rhs)) => {
-                    Value::Float(lhs * OrderedFloat(rhs as f32))
-                }
-                (Value::Float(lhs), Value::Bigint(rhs)) => {
-                    Value::Float(lhs * OrderedFloat(rhs as f32))
-                }
-                (Value::Float(lhs), Value::Float(rhs)) => Value::Float(lhs * rhs),
-                (Value::Float(OrderedFloat(lhs)), Value::Double(rhs)) => {
-                    Value::Double(OrderedFloat(lhs as f64) * rhs)
-                }
-                (Value::Double(lhs), Value::Tinyint(rhs)) => {
-                    Value::Double(lhs * OrderedFloat(rhs as f64))
-                }
-                (Value::Double(lhs), Value::Smallint(rhs)) => {
-                    Value::Double(lhs * OrderedFloat(rhs as f64))
-                }
-                (Value::Double(lhs), Value::Integer(rhs)) => {
-                    Value::Double(lhs * OrderedFloat(rhs as f64))
-                }
-                (Value::Double(lhs), Value::Bigint(rhs)) => {
-                    Value::Double(lhs * OrderedFloat(rhs as f64))
-                }
-                (Value::Double(lhs), Value::Float(OrderedFloat(rhs))) => {
-                    Value::Double(lhs * OrderedFloat(rhs as f64))
-                }
-                (Value::Double(lhs), Value::Double(rhs)) => Value::Double(lhs * rhs),
-                (lhs, rhs) => {
-                    return Err(Error::ValuesNotMatch(
-                        "multiply",
-                        lhs.to_string(),
-                        rhs.to_string(),
-                    ))
-                }
-            }),
-            Expression::Divide(lhs, rhs) => Ok(match (lhs.evaluate()?, rhs.evaluate()?) {
-                // check zero
-                (lhs, rhs) if (lhs.check_int() || lhs.check_float()) && rhs.check_zero() => {
-                    return Err(Error::ValuesNotMatch(
-                        "divide",
-                        lhs.to_string(),
-                        rhs.to_string(),
-                    ))
-                }
-                (Value::Null, Value::Null) => Value::Null,
-                (Value::Tinyint(lhs), Value::Tinyint(rhs)) => Value::Tinyint(
-                    lhs.checked_div(rhs)
-                        .ok_or(Error::OutOfBound("Tinyint", "underflow"))?,
-                ),
-                (Value::Tinyint(lhs), Value::Smallint(rhs)) => Value::Smallint(
-                    (lhs as i32)
-                        .checked_div(rhs)
-                        .ok_or(Error::OutOfBound("Smallint", "underflow"))?,
-                ),
-                (Value::Tinyint(lhs), Value::Integer(rhs)) => Value::Integer(
-                    (lhs as i64)
-                        .checked_div(rhs)
-                        .ok_or(Error::OutOfBound("Integer", "underflow"))?,
-                ),
-                (Value::Tinyint(lhs), Value::Bigint(rhs)) => Value::Bigint(
-                    (lhs as i128)
-                        .checked_div(rhs)
-                        .ok_or(Error::OutOfBound("Bigint", "underflow"))?,
-                ),
-                (Value::Tinyint(lhs), Value::Float(OrderedFloat(rhs))) => {
-                    Value::Float(OrderedFloat(lhs as f32 / rhs))
-                }
-                (Value::Tinyint(lhs), Value::Double(OrderedFloat(rhs))) => {
-                    Value::Double(OrderedFloat(lhs as f64 / rhs))
-                }
-                (Value::Smallint(lhs), Value::Tinyint(rhs)) => Value::Smallint(
-                    lhs.checked_div(rhs as i32)
-                        .ok_or(Error::OutOfBound("Smallint", "underflow"))?,
-                ),
-                (Value::Smallint(lhs), Value::Smallint(rhs)) => Value::Smallint(
-                    lhs.checked_div(rhs)
-                        .ok_or(Error::OutOfBound("Smallint", "underflow"))?,
-                ),
-                (Value::Smallint(lhs), Value::Integer(rhs)) => Value::Integer(
-                    (lhs as i64)
-                        .checked_div(rhs)
-                        .ok_or(Error::OutOfBound("Integer", "underflow"))?,
-                ),
-                (Value::Smallint(lhs), Value::Bigint(rhs)) => Value::Bigint(
-                    (lhs as i128)
-                        .checked_div(rhs)
-                        .ok_or(Error::OutOfBound("Bigint", "underflow"))?,
-                ),
-                (Value::Smallint(lhs), Value::Float(rhs)) => {
-                    Value::Float(OrderedFloat(lhs as f32) / rhs)
-                }
-                (Value::Smallint(lhs), Value::Double(rhs)) => {
-                    Value::Double(OrderedFloat(lhs as f64) / rhs)
-                }
-                (Value::Integer(lhs), Value::Tinyint(rhs)) => Value::Integer(
-                    lhs.checked_div(rhs as i64)
-                        .ok_or(Error::OutOfBound("Integer", "underflow"))?,
-                ),
-                (Value::Integer(lhs), Value::Smallint(rhs)) => Value::Integer(
-                    lhs.checked_div(rhs as i64)
-                        .ok_or(Error::OutOfBound("Integer", "underflow"))?,
-                ),
-                (Value::Integer(lhs), Value::Integer(rhs)) => Value::Integer(
-                    lhs.checked_div(rhs)
-                        .ok_or(Error::OutOfBound("Integer", "underflow"))?,
-                ),
-                (Value::Integer(lhs), Value::Bigint(rhs)) => Value::Bigint(
-                    (lhs as i128)
-                        .checked_div(rhs)
-                        .ok_or(Error::OutOfBound("Bigint", "underflow"))?,
-                ),
-                (Value::Integer(lhs), Value::Float(rhs)) => {
-                    Value::Float(OrderedFloat(lhs as f32) / rhs)
-                }
-                (Value::Integer(lhs), Value::Double(rhs)) => {
-                    Value::Double(OrderedFloat(lhs as f64) / rhs)
-                }
-                (Value::Bigint(lhs), Value::Tinyint(rhs)) => Value::Bigint(
-                    lhs.checked_div(rhs as i128)
-                        .ok_or(Error::OutOfBound("Bigint", "underflow"))?,
-                ),
-                (Value::Bigint(lhs), Value::Smallint(rhs)) => Value::Bigint(
-                    lhs.checked_div(rhs as i128)
-                        .ok_or(Error::OutOfBound("Bigint", "underflow"))?,
-                ),
-                (Value::Bigint(lhs), Value::Integer(rhs)) => Value::Bigint(
-                    lhs.checked_div(rhs as i128)
-                        .ok_or(Error::OutOfBound("Bigint", "underflow"))?,
-                ),
-                (Value::Bigint(lhs), Value::Bigint(rhs)) => Value::Bigint(
-                    lhs.checked_div(rhs)
-                        .ok_or(Error::OutOfBound("Bigint", "underflow"))?,
-                ),
-                (Value::Bigint(lhs), Value::Float(rhs)) => {
-                    Value::Float(OrderedFloat(lhs as f32) / rhs)
-                }
-                (Value::Bigint(lhs), Value::Double(rhs)) => {
-                    Value::Double(OrderedFloat(lhs as f64) / rhs)
-                }
-                (Value::Float(lhs), Value::Tinyint(rhs)) => {
-                    Value::Float(lhs / OrderedFloat(rhs as f32))
-                }
-                (Value::Float(lhs), Value::Smallint(rhs)) => {
-                    Value::Float(lhs / OrderedFloat(rhs as f32))
-                }
-                (Value::Float(lhs), Value::Integer(rhs)) => {
-                    Value::Float(lhs / OrderedFloat(rhs as f32))
-                }
-                (Value::Float(lhs), Value::Bigint(rhs)) => {
-                    Value::Float(lhs / OrderedFloat(rhs as f32))
-                }
-                (Value::Float(lhs), Value::Float(rhs)) => Value::Float(lhs / rhs),
-                (Value::Float(OrderedFloat(lhs)), Value::Double(rhs)) => {
-                    Value::Double(OrderedFloat(lhs as f64) / rhs)
-                }
-                (Value::Double(lhs), Value::Tinyint(rhs)) => {
-                    Value::Double(lhs / OrderedFloat(rhs as f64))
-                }
-                (Value::Double(lhs), Value::Smallint(rhs)) => {
-                    Value::Double(lhs / OrderedFloat(rhs as f64))
-                }
-                (Value::Double(lhs), Value::Integer(rhs)) => {
-                    Value::Double(lhs / OrderedFloat(rhs as f64))
-                }
-                (Value::Double(lhs), Value::Bigint(rhs)) => {
-                    Value::Double(lhs / OrderedFloat(rhs as f64))
-                }
-                (Value::Double(lhs), Value::Float(OrderedFloat(rhs))) => {
-                    Value::Double(lhs / OrderedFloat(rhs as f64))
-                }
-                (Value::Double(lhs), Value::Double(rhs)) => Value::Double(lhs / rhs),
-                (lhs, rhs) => {
-                    return Err(Error::ValuesNotMatch(
-                        "subtract",
-                        lhs.to_string(),
-                        rhs.to_string(),
-                    ))
-                }
-            }),
-            Expression::Exponentiate(lhs, rhs) => Ok(match (lhs.evaluate()?, rhs.evaluate()?) {
-                (Value::Null, Value::Null) => Value::Null,
-                (Value::Tinyint(lhs), Value::Tinyint(rhs)) => {
-                    Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
-                }
-                (Value::Tinyint(lhs), Value::Smallint(rhs)) => {
-                    Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
-                }
-                (Value::Tinyint(lhs), Value::Integer(rhs)) => {
-                    Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
-                }
-                (Value::Tinyint(lhs), Value::Bigint(rhs)) => {
-                    Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
-                }
-                (Value::Tinyint(lhs), Value::Float(OrderedFloat(rhs))) => {
-                    Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
-                }
-                (Value::Tinyint(lhs), Value::Double(OrderedFloat(rhs))) => {
-                    Value::Double(OrderedFloat((lhs as f64).powf(rhs)))
-                }
-                (Value::Smallint(lhs), Value::Tinyint(rhs)) => {
-                    Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
-                }
-                (Value::Smallint(lhs), Value::Smallint(rhs)) => {
-                    Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
-                }
-                (Value::Smallint(lhs), Value::Integer(rhs)) => {
-                    Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
-                }
-                (Value::Smallint(lhs), Value::Bigint(rhs)) => {
-                    Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
-                }
-                (Value::Smallint(lhs), Value::Float(OrderedFloat(rhs))) => {
-                    Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
-                }
-                (Value::Smallint(lhs), Value::Double(OrderedFloat(rhs))) => {
-                    Value::Double(OrderedFloat((lhs as f64).powf(rhs)))
-                }
-                (Value::Integer(lhs), Value::Tinyint(rhs)) => {
-                    Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
-                }
-                (Value::Integer(lhs), Value::Smallint(rhs)) => {
-                    Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
-                }
-                (Value::Integer(lhs), Value::Integer(rhs)) => {
-                    Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
-                }
-                (Value::Integer(lhs), Value::Bigint(rhs)) => {
-                    Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
-                }
-                (Value::Integer(lhs), Value::Float(OrderedFloat(rhs))) => {
-                    Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
-                }
-                (Value::Integer(lhs), Value::Double(OrderedFloat(rhs))) => {
-                    Value::Double(OrderedFloat((lhs as f64).powf(rhs)))
-                }
-                (Value::Bigint(lhs), Value::Tinyint(rhs)) => {
-                    Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
-                }
-                (Value::Bigint(lhs), Value::Smallint(rhs)) => {
-                    Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
-                }
-                (Value::Bigint(lhs), Value::Integer(rhs)) => {
-                    Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
-                }
-                (Value::Bigint(lhs), Value::Bigint(rhs)) => {
-                    Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
-                }
-                (Value::Bigint(lhs), Value::Float(OrderedFloat(rhs))) => {
-                    Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
-                }
-                (Value::Bigint(lhs), Value::Double(OrderedFloat(rhs))) => {
-                    Value::Double(OrderedFloat((lhs as f64).powf(rhs)))
-                }
-                (Value::Float(OrderedFloat(lhs)), Value::Tinyint(rhs)) => {
-                    Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
-                }
-                (Value::Float(OrderedFloat(lhs)), Value::Smallint(rhs)) => {
-                    Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
-                }
-                (Value::Float(OrderedFloat(lhs)), Value::Integer(rhs)) => {
-                    Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
-                }
-                (Value::Float(OrderedFloat(lhs)), Value::Bigint(rhs)) => {
-                    Value::Double(OrderedFloat((lhs as f64).powf(rhs as f64)))
-                }
-                (Value::Float(lhs), Value::Float(rhs)) => {
-                    Value::Float(OrderedFloat(lhs.0.powf(rhs.0)))
-                }
-                (Value::Float(lhs), Value::Double(rhs)) => {
-                    Value::Double(OrderedFloat((lhs.0 as f64).powf(rhs.0)))
-                }
-                (Value::Double(OrderedFloat(lhs)), Value::Tinyint(rhs)) => {
-                    Value::Double(OrderedFloat(lhs.powf(rhs as f64)))
-                }
-                (Value::Double(OrderedFloat(lhs)), Value::Smallint(rhs)) => {
-                    Value::Double(OrderedFloat(lhs.powf(rhs as f64)))
-                }
-                (Value::Double(OrderedFloat(lhs)), Value::Integer(rhs)) => {
-                    Value::Double(OrderedFloat(lhs.powf(rhs as f64)))
-                }
-                (Value::Double(OrderedFloat(lhs)), Value::Bigint(rhs)) => {
-                    Value::Double(OrderedFloat(lhs.powf(rhs as f64)))
-                }
-                (Value::Double(lhs), Value::Float(rhs)) => {
-                    Value::Double(OrderedFloat(lhs.powf(rhs.0 as f64)))
-                }
-                (Value::Double(lhs), Value::Double(rhs)) => {
-                    Value::Double(OrderedFloat(lhs.powf(rhs.0)))
-                }
-                (lhs, rhs) => {
-                    return Err(Error::ValuesNotMatch(
-                        "exponentiate",
-                        lhs.to_string(),
-                        rhs.to_string(),
-                    ))
-                }
-            }),
-            Expression::Negate(expr) => Ok(match expr.evaluate()? {
-                Value::Null => Value::Null,
-                Value::Tinyint(expr) => Value::Tinyint(-expr),
-                Value::Smallint(expr) => Value::Smallint(-expr),
-                Value::Integer(expr) => Value::Integer(-expr),
-                Value::Bigint(expr) => Value::Bigint(-expr),
                 Value::Float(expr) => Value::Float(-expr),
                 Value::Double(expr) => Value::Double(-expr),
+                Value::Decimal(expr) => Value::Decimal(
+                    expr.checked_neg()
+                        .ok_or(Error::OutOfBound("Decimal", "overflow"))?,
+                ),
                 expr => return Err(Error::ValueNotMatch("negate", expr.to_string())),
             }),
-            Expression::Like(_, _) => todo!(),
+            Expression::Like(lhs, rhs) => Ok(
+                match (lhs.evaluate_with(row, mode)?, rhs.evaluate_with(row, mode)?) {
+                    (Value::Null, _) | (_, Value::Null) => Value::Null,
+                    (Value::String(value), Value::String(pattern)) => {
+                        Value::Boolean(like(&value, &pattern))
+                    }
+                    (lhs, rhs) => {
+                        return Err(Error::ValuesNotMatch(
+                            "like",
+                            lhs.to_string(),
+                            rhs.to_string(),
+                        ))
+                    }
+                },
+            ),
+        }
+    }
+}
+
+/// Under [`CastMode::Lenient`], cast boolean to the integer type of the other side, and parse
+/// string as the number type of the other side, when comparing or computing them
+fn coerce(lhs: Value, rhs: Value, mode: CastMode) -> SqlResult<(Value, Value)> {
+    Ok(match (lhs, rhs, mode) {
+        (Value::Boolean(lhs), rhs, CastMode::Lenient) if rhs.check_int() => {
+            let datatype = rhs.datatype().unwrap();
+            (Value::Boolean(lhs).cast_with(&datatype, mode)?, rhs)
+        }
+        (lhs, Value::Boolean(rhs), CastMode::Lenient) if lhs.check_int() => {
+            let datatype = lhs.datatype().unwrap();
+            (lhs, Value::Boolean(rhs).cast_with(&datatype, mode)?)
+        }
+        (Value::String(lhs), rhs, CastMode::Lenient) if number_type(&rhs).is_some() => {
+            let datatype = number_type(&rhs).unwrap();
+            (Value::String(lhs).cast_explicit(&datatype)?, rhs)
+        }
+        (lhs, Value::String(rhs), CastMode::Lenient) if number_type(&lhs).is_some() => {
+            let datatype = number_type(&lhs).unwrap();
+            (lhs, Value::String(rhs).cast_explicit(&datatype)?)
+        }
+        (lhs, rhs, _) => (lhs, rhs),
+    })
+}
+
+/// The datatype of an integer, float or decimal value
+fn number_type(value: &Value) -> Option<DataType> {
+    value.datatype().filter(|datatype| rank(datatype).is_some())
+}
+
+/// The value of a decimal or an integer as a decimal, `None` for other values
+fn decimal(value: &Value) -> Option<Decimal> {
+    match value {
+        Value::Decimal(decimal) => Some(*decimal),
+        value => integer(value).map(Decimal::from),
+    }
+}
+
+/// Compare a decimal with a number. An integer is compared exactly, as a decimal of scale 0,
+/// but a float is compared with the decimal converted into f64, which may round the decimal
+fn compare_decimal(name: &'static str, lhs: Value, rhs: Value) -> SqlResult<Ordering> {
+    if let (Some(lhs), Some(rhs)) = (decimal(&lhs), decimal(&rhs)) {
+        return Ok(lhs.cmp(&rhs));
+    }
+    match (lhs.as_f64(), rhs.as_f64()) {
+        (Some(l), Some(r)) => Ok(OrderedFloat(l).cmp(&OrderedFloat(r))),
+        _ => Err(Error::ValuesNotMatch(
+            name,
+            lhs.to_string(),
+            rhs.to_string(),
+        )),
+    }
+}
+
+/// The arithmetic of a decimal with a number. With an integer the result is an exact decimal,
+/// with a float the decimal is converted into f64 and the result is a float of the float's width
+fn decimal_arithmetic(
+    name: &'static str,
+    lhs: Value,
+    rhs: Value,
+    exact: fn(&Decimal, &Decimal) -> Option<Decimal>,
+    float: fn(f64, f64) -> f64,
+) -> SqlResult<Value> {
+    if let (Some(l), Some(r)) = (decimal(&lhs), decimal(&rhs)) {
+        return Ok(Value::Decimal(
+            exact(&l, &r).ok_or(Error::OutOfBound("Decimal", "overflow"))?,
+        ));
+    }
+    match (&lhs, &rhs, lhs.as_f64(), rhs.as_f64()) {
+        (Value::Float(_), _, Some(l), Some(r)) | (_, Value::Float(_), Some(l), Some(r)) => {
+            Ok(Value::Float(OrderedFloat(float(l, r) as f32)))
+        }
+        (_, _, Some(l), Some(r)) => Ok(Value::Double(OrderedFloat(float(l, r)))),
+        _ => Err(Error::ValuesNotMatch(
+            name,
+            lhs.to_string(),
+            rhs.to_string(),
+        )),
+    }
+}
+
+/// The value of an integer, `None` if the value isn't an integer
+fn integer(value: &Value) -> Option<i128> {
+    match value {
+        Value::Tinyint(value) => Some(*value as i128),
+        Value::Smallint(value) => Some(*value as i128),
+        Value::Integer(value) => Some(*value as i128),
+        Value::Bigint(value) => Some(*value),
+        _ => None,
+    }
+}
+
+/// `base ^ exp` as a [`Value::Bigint`] if `bigint` is set or a [`Value::Integer`] otherwise,
+/// `None` if the exponent is negative or the result overflows
+fn integer_power(base: i128, exp: i128, bigint: bool) -> Option<Value> {
+    let result = base.checked_pow(u32::try_from(exp).ok()?)?;
+    if bigint {
+        Some(Value::Bigint(result))
+    } else {
+        i64::try_from(result).ok().map(Value::Integer)
+    }
+}
+
+/// The position of a numeric datatype in the widening order, `None` for non-numeric datatypes
+fn rank(datatype: &DataType) -> Option<u8> {
+    match datatype {
+        DataType::Tinyint => Some(0),
+        DataType::Smallint => Some(1),
+        DataType::Integer => Some(2),
+        DataType::Bigint => Some(3),
+        DataType::Decimal => Some(4),
+        DataType::Float => Some(5),
+        DataType::Double => Some(6),
+        _ => None,
+    }
+}
+
+/// The datatype of an arithmetic result, integers widen to the wider integer
+/// and any float operand makes the result a float
+fn widen(
+    name: &'static str,
+    lhs: &Expression,
+    rhs: &Expression,
+    columns: &[Option<DataType>],
+    mode: CastMode,
+) -> SqlResult<Option<DataType>> {
+    let (lhs, rhs) = (
+        lhs.infer_type_with(columns, mode)?,
+        rhs.infer_type_with(columns, mode)?,
+    );
+    // a string or boolean operand is coerced into the number on the other side, see coerce
+    let coerced = |datatype: &Option<DataType>, other: &DataType| match (datatype, mode) {
+        (Some(DataType::String), CastMode::Lenient) => rank(other).is_some(),
+        (Some(DataType::Boolean), CastMode::Lenient) => other.is_integer(),
+        _ => false,
+    };
+    let (lhs, rhs) = match (lhs, rhs) {
+        (lhs, Some(rhs)) if coerced(&lhs, &rhs) => (Some(rhs.clone()), Some(rhs)),
+        (Some(lhs), rhs) if coerced(&rhs, &lhs) => (Some(lhs.clone()), Some(lhs)),
+        (lhs, rhs) => (lhs, rhs),
+    };
+    let not_match = |lhs: &Option<DataType>, rhs: &Option<DataType>| {
+        let display = |datatype: &Option<DataType>| match datatype {
+            Some(datatype) => datatype.to_string(),
+            None => "NULL".to_string(),
+        };
+        Error::ValuesNotMatch(name, display(lhs), display(rhs))
+    };
+    Ok(match (&lhs, &rhs) {
+        (Some(l), Some(r)) => match (rank(l), rank(r)) {
+            (Some(l_rank), Some(r_rank)) if l_rank >= r_rank => lhs,
+            (Some(_), Some(_)) => rhs,
+            _ => return Err(not_match(&lhs, &rhs)),
+        },
+        (Some(datatype), None) | (None, Some(datatype)) => match rank(datatype) {
+            Some(_) => Some(datatype.clone()),
+            None => return Err(not_match(&lhs, &rhs)),
+        },
+        (None, None) => None,
+    })
+}
+
+/// A token of a `LIKE` pattern
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LikeToken {
+    /// `%`, any sequence of characters including the empty one
+    Any,
+    /// `_`, exactly one character
+    One,
+    Char(char),
+}
+
+/// Translate a `LIKE` pattern into tokens. A backslash escapes the next character,
+/// so `\%` and `\_` match themselves, and a trailing backslash matches itself
+fn like_tokens(pattern: &str) -> Vec<LikeToken> {
+    let mut tokens = Vec::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(char) = chars.next() {
+        tokens.push(match char {
+            '%' => LikeToken::Any,
+            '_' => LikeToken::One,
+            '\\' => LikeToken::Char(chars.next().unwrap_or('\\')),
+            char => LikeToken::Char(char),
+        });
+    }
+    tokens
+}
+
+/// Whether the value matches the `LIKE` pattern, the characters are compared case-sensitively
+fn like(value: &str, pattern: &str) -> bool {
+    let tokens = like_tokens(pattern);
+    let chars = value.chars().collect::<Vec<_>>();
+    let (mut token, mut char) = (0, 0);
+    // the token after the last `%` and the character it's matched from, when a later token
+    // doesn't match, the `%` takes one more character and the tokens after it are retried
+    let mut retry = None;
+    while char < chars.len() {
+        match tokens.get(token) {
+            Some(LikeToken::Any) => {
+                token += 1;
+                retry = Some((token, char));
+                continue;
+            }
+            Some(LikeToken::One) => {
+                token += 1;
+                char += 1;
+                continue;
+            }
+            Some(LikeToken::Char(expected)) if *expected == chars[char] => {
+                token += 1;
+                char += 1;
+                continue;
+            }
+            _ => {}
+        }
+        match retry {
+            Some((after, from)) => {
+                retry = Some((after, from + 1));
+                (token, char) = (after, from + 1);
+            }
+            None => return false,
+        }
+    }
+    tokens[token..].iter().all(|token| token == &LikeToken::Any)
+}
+
+/// The tighter of two bounds on the same side of a range, `side` is the order of the tighter
+/// value: [`Ordering::Greater`] for the start bounds and [`Ordering::Less`] for the end bounds
+fn tighter(a: Bound<Value>, b: Bound<Value>, side: Ordering) -> Bound<Value> {
+    match (a, b) {
+        (Bound::Unbounded, bound) | (bound, Bound::Unbounded) => bound,
+        (a, b) => {
+            let value = |bound: &Bound<Value>| match bound {
+                Bound::Included(value) | Bound::Excluded(value) => value.clone(),
+                Bound::Unbounded => unreachable!(),
+            };
+            match value(&a).cmp(&value(&b)) {
+                Ordering::Equal if matches!(a, Bound::Excluded(_)) => a,
+                Ordering::Equal => b,
+                order if order == side => a,
+                _ => b,
+            }
         }
     }
 }
@@ -978,6 +1919,13 @@ impl Expression {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sql::types::timestamp;
+    use std::cell::Cell;
+
+    thread_local! {
+        /// The number of evaluated function calls, to observe what is evaluated per row
+        pub(super) static FUNCTION_CALLS: Cell<usize> = const { Cell::new(0) };
+    }
 
     #[test]
     fn evaluate() {
@@ -986,28 +1934,28 @@ mod tests {
                 Box::new(Expression::Const(Value::Integer(1))),
                 Box::new(Expression::Const(Value::Integer(1))),
             );
-            assert_eq!(expression.evaluate().unwrap(), Value::Integer(2))
+            assert_eq!(expression.evaluate(None).unwrap(), Value::Integer(2))
         }
         {
             let expression = Expression::Subtract(
                 Box::new(Expression::Const(Value::Integer(2))),
                 Box::new(Expression::Const(Value::Integer(1))),
             );
-            assert_eq!(expression.evaluate().unwrap(), Value::Integer(1))
+            assert_eq!(expression.evaluate(None).unwrap(), Value::Integer(1))
         }
         {
             let expression = Expression::Multiply(
                 Box::new(Expression::Const(Value::Integer(2))),
                 Box::new(Expression::Const(Value::Integer(2))),
             );
-            assert_eq!(expression.evaluate().unwrap(), Value::Integer(4))
+            assert_eq!(expression.evaluate(None).unwrap(), Value::Integer(4))
         }
         {
             let expression = Expression::Divide(
                 Box::new(Expression::Const(Value::Integer(2))),
                 Box::new(Expression::Const(Value::Integer(1))),
             );
-            assert_eq!(expression.evaluate().unwrap(), Value::Integer(2))
+            assert_eq!(expression.evaluate(None).unwrap(), Value::Integer(2))
         }
         {
             let expression = Expression::Exponentiate(
@@ -1015,8 +1963,8 @@ mod tests {
                 Box::new(Expression::Const(Value::Integer(2))),
             );
             assert_eq!(
-                expression.evaluate().unwrap(),
-                Value::Double(2.0_f64.powf(2.0).into())
+                expression.evaluate(None).unwrap(),
+                Value::Integer(2_i64.pow(2))
             )
         }
         {
@@ -1024,22 +1972,968 @@ mod tests {
                 Box::new(Expression::Const(Value::Integer(2))),
                 Box::new(Expression::Const(Value::Integer(1))),
             );
-            assert_eq!(expression.evaluate().unwrap(), Value::Integer(2 % 1))
+            assert_eq!(expression.evaluate(None).unwrap(), Value::Integer(2 % 1))
         }
         {
             let expression = Expression::Factorial(Box::new(Expression::Const(Value::Integer(2))));
-            assert_eq!(
-                expression.evaluate().unwrap(),
-                Value::Integer((1..2).product())
-            )
+            assert_eq!(expression.evaluate(None).unwrap(), Value::Bigint(2))
         }
         {
             let expression = Expression::Negate(Box::new(Expression::Const(Value::Integer(2))));
-            assert_eq!(expression.evaluate().unwrap(), Value::Integer(-2))
+            assert_eq!(expression.evaluate(None).unwrap(), Value::Integer(-2))
         }
         {
             let expression = Expression::Assert(Box::new(Expression::Const(Value::Integer(-2))));
-            assert_eq!(expression.evaluate().unwrap(), Value::Integer(-2))
+            assert_eq!(expression.evaluate(None).unwrap(), Value::Integer(-2))
+        }
+    }
+
+    #[test]
+    fn assert() {
+        let assert = |value: Value| Expression::Assert(Box::new(Expression::Const(value)));
+        assert_eq!(
+            assert(Value::Integer(5)).evaluate(None).unwrap(),
+            Value::Integer(5)
+        );
+        assert_eq!(
+            assert(Value::Double(OrderedFloat(5.0)))
+                .evaluate(None)
+                .unwrap(),
+            Value::Double(OrderedFloat(5.0))
+        );
+        assert_eq!(assert(Value::Null).evaluate(None).unwrap(), Value::Null);
+        assert!(matches!(
+            assert(Value::String("abc".to_string())).evaluate(None),
+            Err(Error::ValueNotMatch("assert", value)) if value == "abc"
+        ));
+        assert!(matches!(
+            assert(Value::Boolean(true)).evaluate(None),
+            Err(Error::ValueNotMatch("assert", _))
+        ));
+
+        let column = Expression::Assert(Box::new(Expression::Column(0)));
+        assert_eq!(
+            column.infer_type(&[Some(DataType::Float)]).unwrap(),
+            Some(DataType::Float)
+        );
+        assert!(column.infer_type(&[Some(DataType::String)]).is_err());
+        assert!(column.infer_type(&[Some(DataType::Boolean)]).is_err());
+    }
+
+    #[test]
+    fn modulo_negate() {
+        let modulo = |lhs: Value, rhs: Value| {
+            Expression::Modulo(
+                Box::new(Expression::Const(lhs)),
+                Box::new(Expression::Const(rhs)),
+            )
+            .evaluate(None)
+        };
+        let negate =
+            |value: Value| Expression::Negate(Box::new(Expression::Const(value))).evaluate(None);
+        assert_eq!(
+            modulo(Value::Integer(-7), Value::Tinyint(3)).unwrap(),
+            Value::Integer(-1)
+        );
+        assert_eq!(
+            modulo(Value::Double(OrderedFloat(7.5)), Value::Integer(2)).unwrap(),
+            Value::Double(OrderedFloat(1.5))
+        );
+        assert!(matches!(
+            modulo(Value::Integer(1), Value::Integer(0)),
+            Err(Error::ValuesNotMatch("modulo", _, _))
+        ));
+        assert!(matches!(
+            modulo(Value::Bigint(i128::MIN), Value::Bigint(-1)),
+            Err(Error::OutOfBound("Bigint", "overflow"))
+        ));
+        assert!(matches!(
+            modulo(Value::Integer(i64::MIN), Value::Tinyint(-1)),
+            Err(Error::OutOfBound("Integer", "overflow"))
+        ));
+        assert_eq!(modulo(Value::Null, Value::Integer(0)).unwrap(), Value::Null);
+
+        assert_eq!(negate(Value::Smallint(-5)).unwrap(), Value::Smallint(5));
+        assert_eq!(
+            negate(Value::Decimal("1.50".parse().unwrap())).unwrap(),
+            Value::Decimal("-1.5".parse().unwrap())
+        );
+        assert!(matches!(
+            negate(Value::Bigint(i128::MIN)),
+            Err(Error::OutOfBound("Bigint", "overflow"))
+        ));
+        assert!(matches!(
+            negate(Value::String("a".to_string())),
+            Err(Error::ValueNotMatch("negate", _))
+        ));
+    }
+
+    #[test]
+    fn tinyint_float() {
+        let compare =
+            |build: fn(Box<Expression>, Box<Expression>) -> Expression, lhs: Value, rhs: Value| {
+                build(
+                    Box::new(Expression::Const(lhs)),
+                    Box::new(Expression::Const(rhs)),
+                )
+                .evaluate(None)
+                .unwrap()
+            };
+        let double = |f: f64| Value::Double(OrderedFloat(f));
+        let float = |f: f32| Value::Float(OrderedFloat(f));
+        for (lhs, rhs) in [
+            (Value::Tinyint(1), double(1.0)),
+            (double(1.0), Value::Tinyint(1)),
+            (Value::Tinyint(-3), float(-3.0)),
+            (float(-3.0), Value::Tinyint(-3)),
+        ] {
+            assert_eq!(
+                compare(Expression::Equal, lhs.clone(), rhs.clone()),
+                Value::Boolean(true)
+            );
+            assert_eq!(
+                compare(Expression::GreaterThan, lhs.clone(), rhs.clone()),
+                Value::Boolean(false)
+            );
+            assert_eq!(
+                compare(Expression::LessThan, lhs, rhs),
+                Value::Boolean(false)
+            );
         }
+        assert_eq!(
+            compare(Expression::LessThan, Value::Tinyint(1), double(1.5)),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            compare(Expression::GreaterThan, double(1.5), Value::Tinyint(1)),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            compare(Expression::Equal, Value::Tinyint(2), float(1.5)),
+            Value::Boolean(false)
+        );
+        assert_eq!(
+            compare(Expression::GreaterThan, Value::Tinyint(2), float(1.5)),
+            Value::Boolean(true)
+        );
+    }
+
+    #[test]
+    fn decimal() {
+        let evaluate =
+            |build: fn(Box<Expression>, Box<Expression>) -> Expression, lhs: Value, rhs: Value| {
+                build(
+                    Box::new(Expression::Const(lhs)),
+                    Box::new(Expression::Const(rhs)),
+                )
+                .evaluate(None)
+            };
+        let decimal = |s: &str| Value::Decimal(s.parse().unwrap());
+        let double = |f: f64| Value::Double(OrderedFloat(f));
+        let float = |f: f32| Value::Float(OrderedFloat(f));
+        // the scales are aligned before comparing
+        assert_eq!(
+            evaluate(Expression::Equal, decimal("1.50"), decimal("1.5")).unwrap(),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            evaluate(Expression::GreaterThan, decimal("2.00"), Value::Integer(1)).unwrap(),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            evaluate(Expression::LessThan, Value::Tinyint(2), decimal("2.00")).unwrap(),
+            Value::Boolean(false)
+        );
+        assert_eq!(
+            evaluate(Expression::LessThan, decimal("1.5"), double(2.0)).unwrap(),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            evaluate(Expression::Equal, float(0.5), decimal("0.50")).unwrap(),
+            Value::Boolean(true)
+        );
+        assert!(evaluate(
+            Expression::Equal,
+            decimal("1"),
+            Value::String("1".to_string())
+        )
+        .is_err());
+
+        // exact with integers, a float of the float's width with floats
+        let sum = evaluate(Expression::Add, decimal("1.25"), Value::Integer(2)).unwrap();
+        assert_eq!(sum.to_string(), "3.25");
+        assert_eq!(
+            evaluate(Expression::Subtract, Value::Bigint(1), decimal("0.10"))
+                .unwrap()
+                .to_string(),
+            "0.90"
+        );
+        assert_eq!(
+            evaluate(Expression::Multiply, decimal("1.5"), decimal("1.5")).unwrap(),
+            decimal("2.25")
+        );
+        assert_eq!(
+            evaluate(Expression::Add, decimal("0.5"), double(0.25)).unwrap(),
+            double(0.75)
+        );
+        assert_eq!(
+            evaluate(Expression::Multiply, float(2.0), decimal("0.5")).unwrap(),
+            float(1.0)
+        );
+        assert!(evaluate(
+            Expression::Add,
+            Value::Decimal(Decimal::new(i128::MAX, 0)),
+            Value::Integer(1)
+        )
+        .is_err());
+        assert_eq!(
+            Expression::Add(
+                Box::new(Expression::Column(0)),
+                Box::new(Expression::Const(Value::Integer(1)))
+            )
+            .result_type(&[Column::new("d", DataType::Decimal)])
+            .unwrap(),
+            Some(DataType::Decimal)
+        );
+    }
+
+    #[test]
+    fn numeric_comparison_matrix() {
+        let numerics: [fn(i8) -> Value; 6] = [
+            |n| Value::Tinyint(n as i16),
+            |n| Value::Smallint(n as i32),
+            |n| Value::Integer(n as i64),
+            |n| Value::Bigint(n as i128),
+            |n| Value::Float(OrderedFloat(n as f32)),
+            |n| Value::Double(OrderedFloat(n as f64)),
+        ];
+        let compare =
+            |build: fn(Box<Expression>, Box<Expression>) -> Expression, lhs: Value, rhs: Value| {
+                build(
+                    Box::new(Expression::Const(lhs.clone())),
+                    Box::new(Expression::Const(rhs.clone())),
+                )
+                .evaluate(None)
+                .unwrap_or_else(|err| panic!("{:?} with {:?}: {}", lhs, rhs, err))
+            };
+        for lhs in numerics {
+            for rhs in numerics {
+                for (l, r) in [(2, 2), (-3, 2), (2, -3)] {
+                    let (lhs, rhs) = (lhs(l), rhs(r));
+                    assert_eq!(
+                        compare(Expression::Equal, lhs.clone(), rhs.clone()),
+                        Value::Boolean(l == r),
+                        "{:?} = {:?}",
+                        lhs,
+                        rhs
+                    );
+                    assert_eq!(
+                        compare(Expression::GreaterThan, lhs.clone(), rhs.clone()),
+                        Value::Boolean(l > r),
+                        "{:?} > {:?}",
+                        lhs,
+                        rhs
+                    );
+                    assert_eq!(
+                        compare(Expression::LessThan, lhs.clone(), rhs.clone()),
+                        Value::Boolean(l < r),
+                        "{:?} < {:?}",
+                        lhs,
+                        rhs
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn as_index_bound() {
+        use Bound::{Excluded, Included, Unbounded};
+        let column = || Box::new(Expression::Column(1));
+        let constant = |value: i64| Box::new(Expression::Const(Value::Integer(value)));
+        let int = Value::Integer;
+        let ge = |lhs: Box<Expression>, rhs: Box<Expression>| {
+            Expression::Or(
+                Box::new(Expression::Equal(lhs.clone(), rhs.clone())),
+                Box::new(Expression::GreaterThan(lhs, rhs)),
+            )
+        };
+        let le = |lhs: Box<Expression>, rhs: Box<Expression>| {
+            Expression::Or(
+                Box::new(Expression::Equal(lhs.clone(), rhs.clone())),
+                Box::new(Expression::LessThan(lhs, rhs)),
+            )
+        };
+        let cases = [
+            // column on the left
+            (
+                Expression::Equal(column(), constant(3)),
+                (Included(int(3)), Included(int(3))),
+            ),
+            (
+                Expression::GreaterThan(column(), constant(3)),
+                (Excluded(int(3)), Unbounded),
+            ),
+            (ge(column(), constant(3)), (Included(int(3)), Unbounded)),
+            (
+                Expression::LessThan(column(), constant(3)),
+                (Unbounded, Excluded(int(3))),
+            ),
+            (le(column(), constant(3)), (Unbounded, Included(int(3)))),
+            // column on the right
+            (
+                Expression::Equal(constant(3), column()),
+                (Included(int(3)), Included(int(3))),
+            ),
+            (
+                Expression::GreaterThan(constant(3), column()),
+                (Unbounded, Excluded(int(3))),
+            ),
+            (ge(constant(3), column()), (Unbounded, Included(int(3)))),
+            (
+                Expression::LessThan(constant(3), column()),
+                (Excluded(int(3)), Unbounded),
+            ),
+            (le(constant(3), column()), (Included(int(3)), Unbounded)),
+            // BETWEEN 2 AND 5
+            (
+                Expression::And(
+                    Box::new(ge(column(), constant(2))),
+                    Box::new(le(column(), constant(5))),
+                ),
+                (Included(int(2)), Included(int(5))),
+            ),
+            // the tighter bound wins, and excluded is tighter than included
+            (
+                Expression::And(
+                    Box::new(Expression::GreaterThan(column(), constant(2))),
+                    Box::new(ge(constant(1), column())),
+                ),
+                (Excluded(int(2)), Included(int(1))),
+            ),
+            (
+                Expression::And(
+                    Box::new(ge(column(), constant(2))),
+                    Box::new(Expression::GreaterThan(column(), constant(2))),
+                ),
+                (Excluded(int(2)), Unbounded),
+            ),
+        ];
+        for (expression, bound) in cases {
+            assert_eq!(
+                expression.as_index_bound(1),
+                Some(bound),
+                "{:?}",
+                expression
+            );
+        }
+
+        let cases = [
+            // another column
+            Expression::Equal(Box::new(Expression::Column(0)), constant(3)),
+            // not a constant
+            Expression::Equal(column(), Box::new(Expression::Column(0))),
+            Expression::Equal(column(), Box::new(Expression::Const(Value::Null))),
+            Expression::IsNull(column()),
+            Expression::Not(Box::new(Expression::Equal(column(), constant(3)))),
+            Expression::Or(
+                Box::new(Expression::Equal(column(), constant(3))),
+                Box::new(Expression::Equal(column(), constant(4))),
+            ),
+            Expression::And(
+                Box::new(Expression::Equal(column(), constant(3))),
+                Box::new(Expression::IsNull(column())),
+            ),
+        ];
+        for expression in cases {
+            assert_eq!(expression.as_index_bound(1), None, "{:?}", expression);
+        }
+    }
+
+    #[test]
+    fn nan_comparison() {
+        let nan = || Box::new(Expression::Const(Value::Double(OrderedFloat(f64::NAN))));
+        let one = || Box::new(Expression::Const(Value::Double(OrderedFloat(1.0))));
+        // NaN compares structurally, it equals itself and is greater than every other float
+        assert_eq!(
+            Expression::Equal(nan(), nan()).evaluate(None).unwrap(),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            Expression::GreaterThan(nan(), one())
+                .evaluate(None)
+                .unwrap(),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            Expression::Equal(
+                nan(),
+                Box::new(Expression::Const(Value::Float(OrderedFloat(f32::NAN))))
+            )
+            .evaluate(None)
+            .unwrap(),
+            Value::Boolean(true)
+        );
+    }
+
+    #[test]
+    fn modulo_exponentiate() {
+        let modulo = |lhs: Value, rhs: Value| {
+            Expression::Modulo(
+                Box::new(Expression::Const(lhs)),
+                Box::new(Expression::Const(rhs)),
+            )
+            .evaluate(None)
+        };
+        let exponentiate = |lhs: Value, rhs: Value| {
+            Expression::Exponentiate(
+                Box::new(Expression::Const(lhs)),
+                Box::new(Expression::Const(rhs)),
+            )
+            .evaluate(None)
+        };
+        assert!(modulo(Value::Integer(5), Value::Integer(0)).is_err());
+        assert!(modulo(Value::Double(OrderedFloat(5.0)), Value::Tinyint(0)).is_err());
+        assert_eq!(modulo(Value::Null, Value::Integer(0)).unwrap(), Value::Null);
+        assert_eq!(modulo(Value::Integer(5), Value::Null).unwrap(), Value::Null);
+        assert_eq!(
+            exponentiate(Value::Integer(0), Value::Integer(0)).unwrap(),
+            Value::Integer(1)
+        );
+        assert_eq!(
+            exponentiate(Value::Integer(2), Value::Null).unwrap(),
+            Value::Null
+        );
+        assert_eq!(
+            exponentiate(Value::Null, Value::Double(OrderedFloat(2.0))).unwrap(),
+            Value::Null
+        );
+    }
+
+    #[test]
+    fn function() {
+        let expression = Expression::Function(
+            "coalesce".to_string(),
+            vec![
+                Expression::Column(0),
+                Expression::Function("upper".to_string(), vec![Expression::Column(1)]),
+            ],
+        );
+        assert_eq!(
+            expression
+                .evaluate(Some(&vec![Value::Null, Value::String("a".to_string())]))
+                .unwrap(),
+            Value::String("A".to_string())
+        );
+        assert!(Expression::Function("unknown".to_string(), vec![])
+            .evaluate(None)
+            .is_err());
+    }
+
+    #[test]
+    fn case() {
+        let equal = |value: i64| {
+            Expression::Equal(
+                Box::new(Expression::Column(0)),
+                Box::new(Expression::Const(Value::Integer(value))),
+            )
+        };
+        let string = |s: &str| Expression::Const(Value::String(s.to_string()));
+        let expression = Expression::Case {
+            branches: vec![(equal(1), string("one")), (equal(2), string("two"))],
+            default: Some(Box::new(string("many"))),
+        };
+        assert_eq!(
+            expression.evaluate(Some(&vec![Value::Integer(2)])).unwrap(),
+            Value::String("two".to_string())
+        );
+        assert_eq!(
+            expression.evaluate(Some(&vec![Value::Integer(3)])).unwrap(),
+            Value::String("many".to_string())
+        );
+        let expression = Expression::Case {
+            branches: vec![(equal(1), string("one"))],
+            default: None,
+        };
+        assert_eq!(
+            expression.evaluate(Some(&vec![Value::Integer(3)])).unwrap(),
+            Value::Null
+        );
+        let expression = Expression::Case {
+            branches: vec![(Expression::Column(0), string("one"))],
+            default: None,
+        };
+        assert!(expression.evaluate(Some(&vec![Value::Integer(1)])).is_err());
+    }
+
+    #[test]
+    fn distinct_from() {
+        let distinct = |lhs: Value, rhs: Value| {
+            Expression::IsDistinctFrom(
+                Box::new(Expression::Const(lhs)),
+                Box::new(Expression::Const(rhs)),
+            )
+        };
+        for (lhs, rhs, expected) in [
+            (Value::Null, Value::Null, false),
+            (Value::Null, Value::Integer(1), true),
+            (Value::Integer(1), Value::Null, true),
+            (Value::Integer(1), Value::Integer(1), false),
+            (Value::Integer(1), Value::Tinyint(2), true),
+        ] {
+            let expression = distinct(lhs, rhs);
+            assert_eq!(expression.evaluate(None).unwrap(), Value::Boolean(expected));
+            assert_eq!(
+                Expression::Not(Box::new(expression))
+                    .evaluate(None)
+                    .unwrap(),
+                Value::Boolean(!expected)
+            );
+        }
+    }
+
+    #[test]
+    fn normalize() {
+        let column = |index: usize| Box::new(Expression::Column(index));
+        let not = |expr: Expression| Expression::Not(Box::new(expr));
+        let greater = Expression::GreaterThan(column(0), column(1));
+        let less_or_equal = Expression::Or(
+            Box::new(Expression::Equal(column(0), column(1))),
+            Box::new(Expression::LessThan(column(0), column(1))),
+        );
+        assert_eq!(not(greater.clone()).normalize(), less_or_equal);
+        assert_eq!(not(less_or_equal.clone()).normalize(), greater);
+        assert_eq!(not(not(*column(2))).normalize(), *column(2));
+        assert_eq!(
+            not(Expression::Equal(column(0), column(1))).normalize(),
+            not(Expression::Equal(column(0), column(1)))
+        );
+
+        let expression = not(Expression::And(
+            Box::new(not(not(*column(2)))),
+            Box::new(not(Expression::LessThan(column(0), column(1)))),
+        ));
+        let normalized = expression.clone().normalize();
+        assert_eq!(
+            normalized,
+            Expression::Or(
+                Box::new(not(*column(2))),
+                Box::new(Expression::LessThan(column(0), column(1))),
+            )
+        );
+        for (row, expected) in [
+            (
+                vec![Value::Integer(1), Value::Integer(2), Value::Boolean(true)],
+                Value::Boolean(true),
+            ),
+            (
+                vec![Value::Integer(2), Value::Integer(1), Value::Boolean(false)],
+                Value::Boolean(true),
+            ),
+            (
+                vec![Value::Integer(1), Value::Integer(1), Value::Boolean(true)],
+                Value::Boolean(false),
+            ),
+            (vec![Value::Null, Value::Null, Value::Null], Value::Null),
+            (
+                vec![Value::Integer(1), Value::Null, Value::Boolean(true)],
+                Value::Null,
+            ),
+            (
+                vec![Value::Integer(1), Value::Null, Value::Boolean(false)],
+                Value::Boolean(true),
+            ),
+        ] {
+            assert_eq!(expression.evaluate(Some(&row)).unwrap(), expected);
+            assert_eq!(normalized.evaluate(Some(&row)).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn lenient_comparison() {
+        let flag = Box::new(Expression::Const(Value::Boolean(true)));
+        let one = Box::new(Expression::Const(Value::Tinyint(1)));
+        let expression = Expression::Equal(flag.clone(), one.clone());
+        assert!(expression.evaluate(None).is_err());
+        assert_eq!(
+            expression.evaluate_with(None, CastMode::Lenient).unwrap(),
+            Value::Boolean(true)
+        );
+        let expression =
+            Expression::LessThan(Box::new(Expression::Const(Value::Integer(0))), flag.clone());
+        assert_eq!(
+            expression.evaluate_with(None, CastMode::Lenient).unwrap(),
+            Value::Boolean(true)
+        );
+        let expression = Expression::GreaterThan(flag, one);
+        assert_eq!(
+            expression.evaluate_with(None, CastMode::Lenient).unwrap(),
+            Value::Boolean(false)
+        );
+    }
+
+    #[test]
+    fn result_type() {
+        let columns = vec![
+            Column::new("id", DataType::Integer),
+            Column::new("price", DataType::Double),
+            Column::new("name", DataType::String),
+        ];
+        let column = |index| Box::new(Expression::Column(index));
+        let constant = |value| Box::new(Expression::Const(value));
+        let result_type = |expression: Expression| expression.result_type(&columns);
+
+        assert_eq!(result_type(*column(0)).unwrap(), Some(DataType::Integer));
+        assert_eq!(result_type(*column(2)).unwrap(), Some(DataType::String));
+        assert!(matches!(
+            result_type(*column(3)),
+            Err(Error::NotFound("column", _))
+        ));
+        assert_eq!(result_type(*constant(Value::Null)).unwrap(), None);
+
+        assert_eq!(
+            result_type(Expression::Add(column(0), column(1))).unwrap(),
+            Some(DataType::Double)
+        );
+        assert_eq!(
+            result_type(Expression::Multiply(column(0), constant(Value::Tinyint(2)))).unwrap(),
+            Some(DataType::Integer)
+        );
+        assert_eq!(
+            result_type(Expression::Subtract(constant(Value::Null), column(0))).unwrap(),
+            Some(DataType::Integer)
+        );
+        assert_eq!(
+            result_type(Expression::Exponentiate(column(0), column(1))).unwrap(),
+            Some(DataType::Double)
+        );
+        assert_eq!(
+            result_type(Expression::Exponentiate(
+                column(0),
+                constant(Value::Tinyint(2))
+            ))
+            .unwrap(),
+            None
+        );
+        assert!(result_type(Expression::Add(column(0), column(2))).is_err());
+        assert!(result_type(Expression::Negate(column(2))).is_err());
+
+        assert_eq!(
+            result_type(Expression::Equal(column(2), constant(Value::Null))).unwrap(),
+            Some(DataType::Boolean)
+        );
+        assert_eq!(
+            result_type(Expression::LessThan(column(0), column(1))).unwrap(),
+            Some(DataType::Boolean)
+        );
+        assert_eq!(
+            result_type(Expression::Function(
+                "length".to_string(),
+                vec![Expression::Column(2)]
+            ))
+            .unwrap(),
+            Some(DataType::Integer)
+        );
+        assert!(result_type(Expression::Function("reverse".to_string(), vec![])).is_err());
+    }
+
+    #[test]
+    fn short_circuit() {
+        let constant = |value| Box::new(Expression::Const(value));
+        // 1 / 0 = 1
+        let division = Box::new(Expression::Equal(
+            Box::new(Expression::Divide(
+                constant(Value::Integer(1)),
+                constant(Value::Integer(0)),
+            )),
+            constant(Value::Integer(1)),
+        ));
+        assert!(division.evaluate(None).is_err());
+        assert_eq!(
+            Expression::And(constant(Value::Boolean(false)), division.clone())
+                .evaluate(None)
+                .unwrap(),
+            Value::Boolean(false)
+        );
+        assert_eq!(
+            Expression::Or(constant(Value::Boolean(true)), division.clone())
+                .evaluate(None)
+                .unwrap(),
+            Value::Boolean(true)
+        );
+        assert!(
+            Expression::And(constant(Value::Boolean(true)), division.clone())
+                .evaluate(None)
+                .is_err()
+        );
+        // the column is only evaluated when the left side doesn't decide the result
+        let column = Box::new(Expression::Column(0));
+        assert_eq!(
+            Expression::And(constant(Value::Boolean(false)), column.clone())
+                .evaluate(None)
+                .unwrap(),
+            Value::Boolean(false)
+        );
+        assert!(Expression::Or(constant(Value::Boolean(false)), column)
+            .evaluate(None)
+            .is_err());
+    }
+
+    #[test]
+    fn three_valued_logic() {
+        let constant = |value| Box::new(Expression::Const(value));
+        let (t, f, null) = (Value::Boolean(true), Value::Boolean(false), Value::Null);
+        for (lhs, rhs, and, or) in [
+            (t.clone(), t.clone(), t.clone(), t.clone()),
+            (t.clone(), f.clone(), f.clone(), t.clone()),
+            (f.clone(), f.clone(), f.clone(), f.clone()),
+            (t.clone(), null.clone(), null.clone(), t.clone()),
+            (f.clone(), null.clone(), f.clone(), null.clone()),
+            (null.clone(), null.clone(), null.clone(), null.clone()),
+        ] {
+            // both operand orders give the same result
+            for (lhs, rhs) in [(lhs.clone(), rhs.clone()), (rhs, lhs)] {
+                assert_eq!(
+                    Expression::And(constant(lhs.clone()), constant(rhs.clone()))
+                        .evaluate(None)
+                        .unwrap(),
+                    and
+                );
+                assert_eq!(
+                    Expression::Or(constant(lhs), constant(rhs))
+                        .evaluate(None)
+                        .unwrap(),
+                    or
+                );
+            }
+        }
+        assert!(
+            Expression::And(constant(null.clone()), constant(Value::Integer(1)))
+                .evaluate(None)
+                .is_err()
+        );
+        assert!(Expression::Or(constant(Value::Integer(1)), constant(null))
+            .evaluate(None)
+            .is_err());
+    }
+
+    #[test]
+    fn factorial_power() {
+        let factorial =
+            |value: Value| Expression::Factorial(Box::new(Expression::Const(value))).evaluate(None);
+        let exponentiate = |lhs: Value, rhs: Value| {
+            Expression::Exponentiate(
+                Box::new(Expression::Const(lhs)),
+                Box::new(Expression::Const(rhs)),
+            )
+            .evaluate(None)
+        };
+        assert_eq!(
+            factorial(Value::Tinyint(20)).unwrap(),
+            Value::Bigint(2432902008176640000)
+        );
+        assert_eq!(factorial(Value::Integer(0)).unwrap(), Value::Bigint(1));
+        assert_eq!(factorial(Value::Null).unwrap(), Value::Null);
+        assert!(factorial(Value::Integer(-1)).is_err());
+        assert!(matches!(
+            factorial(Value::Integer(34)),
+            Err(Error::OutOfBound("Bigint", _))
+        ));
+
+        assert_eq!(
+            exponentiate(Value::Tinyint(2), Value::Tinyint(10)).unwrap(),
+            Value::Integer(1024)
+        );
+        assert_eq!(
+            exponentiate(Value::Bigint(2), Value::Tinyint(100)).unwrap(),
+            Value::Bigint(1 << 100)
+        );
+        assert_eq!(
+            exponentiate(Value::Tinyint(2), Value::Double(OrderedFloat(0.5))).unwrap(),
+            Value::Double(OrderedFloat(2f64.powf(0.5)))
+        );
+        assert_eq!(
+            exponentiate(Value::Tinyint(2), Value::Tinyint(-1)).unwrap(),
+            Value::Double(OrderedFloat(0.5))
+        );
+        assert_eq!(
+            exponentiate(Value::Integer(2), Value::Integer(64)).unwrap(),
+            Value::Double(OrderedFloat(2f64.powi(64)))
+        );
+    }
+
+    #[test]
+    fn precompute() -> SqlResult<()> {
+        let upper = |arg: Expression| Expression::Function("UPPER".to_string(), vec![arg]);
+        let string = |s: &str| Expression::Const(Value::String(s.to_string()));
+        // name = UPPER('a') OR (id > 1 - 2 AND UPPER(name) = UPPER('b'))
+        let filter = Expression::Or(
+            Box::new(Expression::Equal(
+                Box::new(Expression::Column(1)),
+                Box::new(upper(string("a"))),
+            )),
+            Box::new(Expression::And(
+                Box::new(Expression::GreaterThan(
+                    Box::new(Expression::Column(0)),
+                    Box::new(Expression::Subtract(
+                        Box::new(Expression::Const(Value::Integer(1))),
+                        Box::new(Expression::Const(Value::Integer(2))),
+                    )),
+                )),
+                Box::new(Expression::Equal(
+                    Box::new(upper(Expression::Column(1))),
+                    Box::new(upper(string("b"))),
+                )),
+            )),
+        );
+        let rows = (0..100)
+            .map(|i| {
+                vec![
+                    Value::Integer(i),
+                    Value::String(["A", "b", "c"][i as usize % 3].to_string()),
+                ]
+            })
+            .collect::<Vec<_>>();
+        let evaluate = |filter: &Expression| {
+            FUNCTION_CALLS.with(|calls| calls.set(0));
+            let values = rows
+                .iter()
+                .map(|row| filter.evaluate(Some(row)))
+                .collect::<SqlResult<Vec<_>>>();
+            (values, FUNCTION_CALLS.with(|calls| calls.get()))
+        };
+
+        let (expected, calls) = evaluate(&filter);
+        assert_eq!(calls, 100 + 2 * 66);
+        FUNCTION_CALLS.with(|calls| calls.set(0));
+        let precomputed = filter.clone().precompute();
+        assert_eq!(FUNCTION_CALLS.with(|calls| calls.get()), 2);
+        assert_eq!(
+            precomputed,
+            Expression::Or(
+                Box::new(Expression::Equal(
+                    Box::new(Expression::Column(1)),
+                    Box::new(string("A")),
+                )),
+                Box::new(Expression::And(
+                    Box::new(Expression::GreaterThan(
+                        Box::new(Expression::Column(0)),
+                        Box::new(Expression::Const(Value::Integer(-1))),
+                    )),
+                    Box::new(Expression::Equal(
+                        Box::new(upper(Expression::Column(1))),
+                        Box::new(string("B")),
+                    )),
+                )),
+            )
+        );
+        // only the function reading the row is left
+        let (values, calls) = evaluate(&precomputed);
+        assert_eq!(values?, expected?);
+        assert_eq!(calls, 66);
+
+        // the error of a constant is still raised per row
+        let failing = Expression::Equal(
+            Box::new(Expression::Column(0)),
+            Box::new(upper(Expression::Const(Value::Integer(1)))),
+        );
+        assert_eq!(failing.clone().precompute(), failing);
+        Ok(())
+    }
+
+    #[test]
+    fn timestamp() {
+        let compare =
+            |build: fn(Box<Expression>, Box<Expression>) -> Expression, lhs: &str, rhs: &str| {
+                let timestamp = |s: &str| {
+                    Box::new(Expression::Const(Value::Timestamp(
+                        timestamp::parse(s).unwrap(),
+                    )))
+                };
+                build(timestamp(lhs), timestamp(rhs))
+                    .evaluate(None)
+                    .unwrap()
+            };
+        let (earlier, later) = ("1999-12-31 23:59:59.999999", "2000-01-01");
+        assert_eq!(
+            compare(Expression::LessThan, earlier, later),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            compare(Expression::GreaterThan, earlier, later),
+            Value::Boolean(false)
+        );
+        assert_eq!(
+            compare(Expression::GreaterThan, later, earlier),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            compare(Expression::Equal, later, "2000-01-01T00:00:00"),
+            Value::Boolean(true)
+        );
+        assert!(matches!(
+            Expression::Equal(
+                Box::new(Expression::Const(Value::Timestamp(0))),
+                Box::new(Expression::Const(Value::Integer(0))),
+            )
+            .evaluate(None),
+            Err(Error::ValuesNotMatch("equal", _, _))
+        ));
+    }
+
+    #[test]
+    fn like() {
+        let like = |value: Value, pattern: &str| {
+            Expression::Like(
+                Box::new(Expression::Const(value)),
+                Box::new(Expression::Const(Value::String(pattern.to_string()))),
+            )
+            .evaluate(None)
+        };
+        let matches = |value: &str, pattern: &str| {
+            like(Value::String(value.to_string()), pattern).unwrap() == Value::Boolean(true)
+        };
+        // prefix
+        assert!(matches("abcdef", "abc%"));
+        assert!(matches("abc", "abc%"));
+        assert!(!matches("xabc", "abc%"));
+        // suffix
+        assert!(matches("wxyz", "%xyz"));
+        assert!(matches("xyz", "%xyz"));
+        assert!(!matches("xyzw", "%xyz"));
+        // single character
+        assert!(matches("abc", "a_c"));
+        assert!(!matches("ac", "a_c"));
+        assert!(!matches("abbc", "a_c"));
+        assert!(matches("a\u{e9}c", "a_c"));
+        // the `%` is retried after a partial match
+        assert!(matches("aXbXc", "a%b%c"));
+        assert!(matches("abab", "%ab"));
+        assert!(!matches("abcd", "a%e%"));
+        assert!(matches("", "%"));
+        assert!(!matches("", "_"));
+        assert!(!matches("ABC", "abc"));
+        // escaped
+        assert!(matches("100%", "100\\%"));
+        assert!(!matches("1000", "100\\%"));
+        assert!(matches("a_b", "a\\_b"));
+        assert!(!matches("axb", "a\\_b"));
+        assert!(matches("a\\b", "a\\\\b"));
+        assert!(matches("a\\", "a\\"));
+
+        assert_eq!(like(Value::Null, "a%").unwrap(), Value::Null);
+        assert_eq!(
+            Expression::Like(
+                Box::new(Expression::Const(Value::String("a".to_string()))),
+                Box::new(Expression::Const(Value::Null)),
+            )
+            .evaluate(None)
+            .unwrap(),
+            Value::Null
+        );
+        assert!(matches!(
+            like(Value::Integer(1), "1"),
+            Err(Error::ValuesNotMatch("like", _, _))
+        ));
     }
 }
@@ -0,0 +1,152 @@
+use crate::sql::types::{DataType, Value};
+use crate::sql::{Error, SqlResult};
+
+/// The implementation of a scalar function, which takes the evaluated arguments
+pub type ScalarFunction = fn(&[Value]) -> SqlResult<Value>;
+
+/// The registry of scalar functions, the names are matched case-insensitively
+const FUNCTIONS: &[(&str, ScalarFunction)] = &[
+    ("ABS", abs),
+    ("COALESCE", coalesce),
+    ("LENGTH", length),
+    ("LOWER", lower),
+    ("UPPER", upper),
+];
+
+/// Find the scalar function by its name
+pub fn lookup(name: &str) -> Option<ScalarFunction> {
+    FUNCTIONS
+        .iter()
+        .find(|(function, _)| function.eq_ignore_ascii_case(name))
+        .map(|(_, function)| *function)
+}
+
+/// The datatype returned by the scalar function, given the datatypes of its arguments
+pub fn result_type(name: &str, args: &[Option<DataType>]) -> Option<DataType> {
+    match name.to_ascii_uppercase().as_str() {
+        "ABS" => args.first().cloned().flatten(),
+        "COALESCE" => args.iter().find_map(|arg| arg.clone()),
+        "LENGTH" => Some(DataType::Integer),
+        "LOWER" | "UPPER" => Some(DataType::String),
+        _ => None,
+    }
+}
+
+fn unary<'a>(name: &'static str, args: &'a [Value]) -> SqlResult<&'a Value> {
+    match args {
+        [arg] => Ok(arg),
+        _ => Err(Error::Value(format!(
+            "{} expects 1 argument, but got {}",
+            name,
+            args.len()
+        ))),
+    }
+}
+
+fn abs(args: &[Value]) -> SqlResult<Value> {
+    let overflow = || Error::OutOfBound("abs", "the integer overflows");
+    Ok(match unary("ABS", args)? {
+        Value::Null => Value::Null,
+        Value::Tinyint(i) => Value::Tinyint(i.checked_abs().ok_or_else(overflow)?),
+        Value::Smallint(i) => Value::Smallint(i.checked_abs().ok_or_else(overflow)?),
+        Value::Integer(i) => Value::Integer(i.checked_abs().ok_or_else(overflow)?),
+        Value::Bigint(i) => Value::Bigint(i.checked_abs().ok_or_else(overflow)?),
+        Value::Float(f) => Value::Float(f.abs().into()),
+        Value::Double(f) => Value::Double(f.abs().into()),
+        value => return Err(Error::ValueNotMatch("abs", value.to_string())),
+    })
+}
+
+fn coalesce(args: &[Value]) -> SqlResult<Value> {
+    if args.is_empty() {
+        return Err(Error::Value(
+            "COALESCE expects at least 1 argument".to_string(),
+        ));
+    }
+    Ok(args
+        .iter()
+        .find(|arg| !matches!(arg, Value::Null))
+        .cloned()
+        .unwrap_or(Value::Null))
+}
+
+fn length(args: &[Value]) -> SqlResult<Value> {
+    Ok(match unary("LENGTH", args)? {
+        Value::Null => Value::Null,
+        Value::String(s) => Value::Integer(s.chars().count() as i64),
+        value => return Err(Error::ValueNotMatch("length", value.to_string())),
+    })
+}
+
+fn lower(args: &[Value]) -> SqlResult<Value> {
+    Ok(match unary("LOWER", args)? {
+        Value::Null => Value::Null,
+        Value::String(s) => Value::String(s.to_lowercase()),
+        value => return Err(Error::ValueNotMatch("lower", value.to_string())),
+    })
+}
+
+fn upper(args: &[Value]) -> SqlResult<Value> {
+    Ok(match unary("UPPER", args)? {
+        Value::Null => Value::Null,
+        Value::String(s) => Value::String(s.to_uppercase()),
+        value => return Err(Error::ValueNotMatch("upper", value.to_string())),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ordered_float::OrderedFloat;
+
+    fn call(name: &str, args: &[Value]) -> SqlResult<Value> {
+        lookup(name).unwrap()(args)
+    }
+
+    #[test]
+    fn functions() {
+        let string = |s: &str| Value::String(s.to_string());
+        assert!(lookup("unknown").is_none());
+
+        assert_eq!(call("upper", &[string("John")]).unwrap(), string("JOHN"));
+        assert_eq!(call("UPPER", &[Value::Null]).unwrap(), Value::Null);
+        assert!(call("upper", &[Value::Integer(1)]).is_err());
+
+        assert_eq!(call("Lower", &[string("John")]).unwrap(), string("john"));
+        assert_eq!(call("lower", &[Value::Null]).unwrap(), Value::Null);
+        assert!(call("lower", &[string("a"), string("b")]).is_err());
+
+        assert_eq!(
+            call("length", &[string("John")]).unwrap(),
+            Value::Integer(4)
+        );
+        assert_eq!(call("length", &[Value::Null]).unwrap(), Value::Null);
+        assert!(call("length", &[]).is_err());
+
+        assert_eq!(
+            call("abs", &[Value::Tinyint(-3)]).unwrap(),
+            Value::Tinyint(3)
+        );
+        assert_eq!(
+            call("abs", &[Value::Double(OrderedFloat(-1.5))]).unwrap(),
+            Value::Double(OrderedFloat(1.5))
+        );
+        assert_eq!(call("abs", &[Value::Null]).unwrap(), Value::Null);
+        assert!(call("abs", &[Value::Integer(i64::MIN)]).is_err());
+        assert!(call("abs", &[string("John")]).is_err());
+
+        assert_eq!(
+            call(
+                "coalesce",
+                &[Value::Null, Value::Integer(1), Value::Integer(2)]
+            )
+            .unwrap(),
+            Value::Integer(1)
+        );
+        assert_eq!(
+            call("coalesce", &[Value::Null, Value::Null]).unwrap(),
+            Value::Null
+        );
+        assert!(call("coalesce", &[]).is_err());
+    }
+}
@@ -1,7 +1,12 @@
+pub mod decimal;
 pub(crate) mod expression;
+pub(crate) mod function;
+pub mod timestamp;
 
 use crate::sql::catalog::Column;
-use crate::sql::Error;
+use crate::sql::parser::expression::Literal;
+use crate::sql::types::decimal::Decimal;
+use crate::sql::{Error, SqlResult};
 use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
@@ -18,26 +23,40 @@ pub enum DataType {
     Float,
     Double,
     String,
+    Decimal,
+    Timestamp,
 }
 
 impl FromStr for DataType {
     type Err = Error;
 
+    /// The aliases `INT`, `VARCHAR`, `TEXT`, `REAL`, `DOUBLE PRECISION` and `BOOL` are accepted,
+    /// regardless of case
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(match s {
-            "BOOLEAN" => Self::Boolean,
+        Ok(match s.to_ascii_uppercase().as_str() {
+            "BOOLEAN" | "BOOL" => Self::Boolean,
             "TINYINT" => Self::Tinyint,
             "SMALLINT" => Self::Smallint,
-            "INTEGER" => Self::Integer,
-            "BIGINT" => Self::Float,
-            "FLOAT" => Self::Double,
-            "STRING" => Self::String,
+            "INTEGER" | "INT" => Self::Integer,
+            "BIGINT" => Self::Bigint,
+            "FLOAT" => Self::Float,
+            "DOUBLE" | "REAL" | "DOUBLE PRECISION" => Self::Double,
+            "STRING" | "VARCHAR" | "TEXT" => Self::String,
+            "DECIMAL" | "NUMERIC" => Self::Decimal,
+            "TIMESTAMP" => Self::Timestamp,
             _ => return Err(Error::FromStr(format!("Can't convert {} to DataType", s))),
         })
     }
 }
 impl DataType {
-    pub fn as_str(&self) -> &str {
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self,
+            DataType::Tinyint | DataType::Smallint | DataType::Integer | DataType::Bigint
+        )
+    }
+
+    pub fn as_str(&self) -> &'static str {
         match self {
             DataType::Boolean => "BOOLEAN",
             DataType::Tinyint => "TINYINT",
@@ -47,6 +66,8 @@ impl DataType {
             DataType::Float => "FLOAT",
             DataType::Double => "DOUBLE",
             DataType::String => "STRING",
+            DataType::Decimal => "DECIMAL",
+            DataType::Timestamp => "TIMESTAMP",
         }
     }
 }
@@ -57,17 +78,42 @@ impl std::fmt::Display for DataType {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+/// How strict the implicit conversions between values are
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CastMode {
+    /// Only 0 and 1 can be cast to boolean, and booleans never compare with integers
+    #[default]
+    Strict,
+    /// Every nonzero integer is cast to true, booleans compare with integers as 0 and 1,
+    /// and strings are parsed as the numbers they're compared or computed with
+    Lenient,
+}
+
+/// The integers are stored wider than their sql widths, so the arithmetic on them doesn't overflow.
+/// The sql widths are enforced when a value is cast into a column, see [`Value::cast`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum Value {
     Null,
     Boolean(bool),
+    /// A 8-bit integer, in the range of [`i8`]
     Tinyint(i16),
+    /// A 16-bit integer, in the range of [`i16`]
     Smallint(i32),
+    /// A 64-bit integer
     Integer(i64),
+    /// A 128-bit integer
     Bigint(i128),
+    /// Floats compare and sort as [`OrderedFloat`] does, NaN equals itself and sorts after every
+    /// other float. Columns never store NaN or infinity, see [`Column::check_value`]
+    ///
+    /// [`Column::check_value`]: crate::sql::catalog::Column::check_value
     Float(OrderedFloat<f32>),
     Double(OrderedFloat<f64>),
     String(String),
+    /// An exact decimal, see [`Decimal`]
+    Decimal(Decimal),
+    /// The microseconds since `1970-01-01 00:00:00`, see [`timestamp`]
+    Timestamp(i64),
 }
 
 impl Default for Value {
@@ -76,6 +122,23 @@ impl Default for Value {
     }
 }
 
+impl From<Literal> for Value {
+    fn from(literal: Literal) -> Self {
+        match literal {
+            Literal::Null => Value::Null,
+            Literal::Boolean(boolean) => Value::Boolean(boolean),
+            Literal::Tinyint(integer) => Value::Tinyint(integer),
+            Literal::Smallint(integer) => Value::Smallint(integer),
+            Literal::Integer(integer) => Value::Integer(integer),
+            Literal::Bigint(integer) => Value::Bigint(integer),
+            Literal::Float(float) => Value::Float(OrderedFloat(float)),
+            Literal::Double(float) => Value::Double(OrderedFloat(float)),
+            Literal::String(string) => Value::String(string),
+            Literal::Timestamp(micros) => Value::Timestamp(micros),
+        }
+    }
+}
+
 impl std::fmt::Display for Value {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.write_str(
@@ -90,6 +153,8 @@ impl std::fmt::Display for Value {
                 Value::Float(f) => Cow::Owned(f.0.to_string()),
                 Value::Double(f) => Cow::Owned(f.0.to_string()),
                 Value::String(s) => Cow::Borrowed(s.as_str()),
+                Value::Decimal(d) => Cow::Owned(d.to_string()),
+                Value::Timestamp(t) => Cow::Owned(timestamp::format(*t)),
             }
             .as_ref(),
         )
@@ -108,9 +173,177 @@ impl Value {
             Value::Float(_) => DataType::Float,
             Value::Double(_) => DataType::Double,
             Value::String(_) => DataType::String,
+            Value::Decimal(_) => DataType::Decimal,
+            Value::Timestamp(_) => DataType::Timestamp,
         })
     }
 
+    /// Cast the value into the given datatype, integers are range checked against the sql width of datatype
+    pub fn cast(self, datatype: &DataType) -> SqlResult<Value> {
+        self.cast_with(datatype, CastMode::Strict)
+    }
+
+    /// Like [`Value::cast`], the mode decides how integers other than 0 and 1 are cast to boolean
+    pub fn cast_with(self, datatype: &DataType, mode: CastMode) -> SqlResult<Value> {
+        if self == Value::Null {
+            return Ok(self);
+        }
+        let integer = match self {
+            value if value.datatype().as_ref() == Some(datatype) && !datatype.is_integer() => {
+                return Ok(value)
+            }
+            Value::Boolean(b) if datatype.is_integer() => b as i128,
+            Value::Tinyint(i) => i as i128,
+            Value::Smallint(i) => i as i128,
+            Value::Integer(i) => i as i128,
+            Value::Bigint(i) => i,
+            Value::Float(f) if datatype == &DataType::Double => {
+                return Ok(Value::Double(OrderedFloat(f.0 as f64)))
+            }
+            Value::Double(f) if datatype == &DataType::Float => {
+                return Ok(Value::Float(OrderedFloat(f.0 as f32)))
+            }
+            // the shortest representation of the float which reads back as the same float
+            Value::Float(OrderedFloat(f)) if datatype == &DataType::Decimal => {
+                return Ok(Value::Decimal(f.to_string().parse()?))
+            }
+            Value::Double(OrderedFloat(f)) if datatype == &DataType::Decimal => {
+                return Ok(Value::Decimal(f.to_string().parse()?))
+            }
+            Value::Decimal(d) if datatype == &DataType::Float => {
+                return Ok(Value::Float(OrderedFloat(d.to_f64() as f32)))
+            }
+            Value::Decimal(d) if datatype == &DataType::Double => {
+                return Ok(Value::Double(OrderedFloat(d.to_f64())))
+            }
+            Value::String(s) if datatype == &DataType::Timestamp => {
+                return Ok(Value::Timestamp(timestamp::parse(&s)?))
+            }
+            value => {
+                return Err(Error::ValueNotMatch(
+                    "cast",
+                    format!("{} to {}", value, datatype),
+                ))
+            }
+        };
+        Ok(match datatype {
+            DataType::Tinyint => Value::Tinyint(
+                i8::try_from(integer).map_err(|_| Error::OutOfBound("Tinyint", "overflow"))? as i16,
+            ),
+            DataType::Smallint => Value::Smallint(
+                i16::try_from(integer).map_err(|_| Error::OutOfBound("Smallint", "overflow"))?
+                    as i32,
+            ),
+            DataType::Integer => Value::Integer(
+                i64::try_from(integer).map_err(|_| Error::OutOfBound("Integer", "overflow"))?,
+            ),
+            DataType::Bigint => Value::Bigint(integer),
+            DataType::Boolean => match (integer, mode) {
+                (0, _) => Value::Boolean(false),
+                (1, _) | (_, CastMode::Lenient) => Value::Boolean(true),
+                (integer, CastMode::Strict) => {
+                    return Err(Error::ValueNotMatch(
+                        "cast",
+                        format!("{} to {}", integer, datatype),
+                    ))
+                }
+            },
+            DataType::Float => Value::Float(OrderedFloat(integer as f32)),
+            DataType::Double => Value::Double(OrderedFloat(integer as f64)),
+            DataType::Decimal => Value::Decimal(Decimal::from(integer)),
+            datatype => {
+                return Err(Error::ValueNotMatch(
+                    "cast",
+                    format!("{} to {}", integer, datatype),
+                ))
+            }
+        })
+    }
+
+    /// Cast the value as `CAST(value AS datatype)` does. Unlike [`Value::cast`], strings are parsed
+    /// into the datatype, any value is formatted into a string, and floats and decimals are rounded
+    /// into integers, halves away from zero
+    pub fn cast_explicit(self, datatype: &DataType) -> SqlResult<Value> {
+        let rounded = match self {
+            Value::Null => return Ok(Value::Null),
+            value if datatype == &DataType::String => return Ok(Value::String(value.to_string())),
+            Value::String(s) => {
+                let invalid = || Error::FromStr(format!("Can't convert {} to {}", s, datatype));
+                let text = s.trim();
+                return match datatype {
+                    DataType::Boolean => match text.to_ascii_uppercase().as_str() {
+                        "TRUE" => Ok(Value::Boolean(true)),
+                        "FALSE" => Ok(Value::Boolean(false)),
+                        _ => Err(invalid()),
+                    },
+                    DataType::Tinyint
+                    | DataType::Smallint
+                    | DataType::Integer
+                    | DataType::Bigint => {
+                        Value::Bigint(text.parse().map_err(|_| invalid())?).cast(datatype)
+                    }
+                    DataType::Float | DataType::Double => {
+                        Value::Double(OrderedFloat(text.parse().map_err(|_| invalid())?))
+                            .cast(datatype)
+                    }
+                    DataType::Decimal => Ok(Value::Decimal(text.parse().map_err(|_| invalid())?)),
+                    DataType::Timestamp => Ok(Value::Timestamp(timestamp::parse(text)?)),
+                    DataType::String => unreachable!(),
+                };
+            }
+            Value::Float(OrderedFloat(f)) if datatype.is_integer() => f as f64,
+            Value::Double(OrderedFloat(f)) if datatype.is_integer() => f,
+            Value::Decimal(d) if datatype.is_integer() => {
+                return Value::Bigint(d.round()).cast(datatype)
+            }
+            value => return value.cast(datatype),
+        };
+        // 2^127 is the first float out of range of i128
+        if !rounded.is_finite() || rounded.round().abs() >= i128::MAX as f64 {
+            return Err(Error::OutOfBound("Bigint", "overflow"));
+        }
+        Value::Bigint(rounded.round() as i128).cast(datatype)
+    }
+
+    /// The value of any integer variant, `None` for other values or a Bigint out of range of i64
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Tinyint(i) => Some(*i as i64),
+            Value::Smallint(i) => Some(*i as i64),
+            Value::Integer(i) => Some(*i),
+            Value::Bigint(i) => i64::try_from(*i).ok(),
+            _ => None,
+        }
+    }
+
+    /// The value of a float, integers are widened into f64 and decimals are rounded into it
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Decimal(d) => Some(d.to_f64()),
+            Value::Float(f) => Some(f.0 as f64),
+            Value::Double(f) => Some(f.0),
+            Value::Tinyint(i) => Some(*i as f64),
+            Value::Smallint(i) => Some(*i as f64),
+            Value::Integer(i) => Some(*i as f64),
+            Value::Bigint(i) => Some(*i as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
     pub fn check_int(&self) -> bool {
         matches!(
             self,
@@ -138,3 +371,274 @@ impl Value {
 pub type Row = Vec<Value>;
 
 pub type Columns = Vec<Column>;
+
+pub trait Project {
+    /// Build a new row from the values at the given positions, in the order of positions.
+    /// Every position must be in range of the row.
+    fn project(&self, indices: &[usize]) -> Row;
+}
+
+impl Project for Row {
+    fn project(&self, indices: &[usize]) -> Row {
+        debug_assert!(
+            indices.iter().all(|index| *index < self.len()),
+            "project {:?} out of row with {} values",
+            indices,
+            self.len()
+        );
+        indices.iter().map(|index| self[*index].clone()).collect()
+    }
+}
+
+/// Returns the positions of the named columns in schema
+pub fn column_indices<S: AsRef<str>>(columns: &[Column], names: &[S]) -> SqlResult<Vec<usize>> {
+    names
+        .iter()
+        .map(|name| {
+            columns
+                .iter()
+                .position(|column| column.name == name.as_ref())
+                .ok_or(Error::NotFound("column", name.as_ref().to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn datatype_from_str() {
+        for datatype in [
+            DataType::Boolean,
+            DataType::Tinyint,
+            DataType::Smallint,
+            DataType::Integer,
+            DataType::Bigint,
+            DataType::Float,
+            DataType::Double,
+            DataType::String,
+            DataType::Decimal,
+            DataType::Timestamp,
+        ] {
+            assert_eq!(DataType::from_str(datatype.as_str()).unwrap(), datatype);
+            assert_eq!(
+                DataType::from_str(&datatype.as_str().to_lowercase()).unwrap(),
+                datatype
+            );
+        }
+        assert_eq!(DataType::from_str("bigint").unwrap(), DataType::Bigint);
+        assert_eq!(
+            DataType::from_str("Double Precision").unwrap(),
+            DataType::Double
+        );
+        assert!(matches!(DataType::from_str("BLOB"), Err(Error::FromStr(_))));
+    }
+
+    #[test]
+    fn accessors() {
+        assert_eq!(Value::Tinyint(-1).as_i64(), Some(-1));
+        assert_eq!(Value::Smallint(2).as_i64(), Some(2));
+        assert_eq!(Value::Integer(i64::MIN).as_i64(), Some(i64::MIN));
+        assert_eq!(Value::Bigint(3).as_i64(), Some(3));
+        assert_eq!(Value::Bigint(i64::MAX as i128 + 1).as_i64(), None);
+        assert_eq!(Value::Double(OrderedFloat(1.0)).as_i64(), None);
+        assert_eq!(Value::String("1".to_string()).as_i64(), None);
+        assert_eq!(Value::Null.as_i64(), None);
+
+        assert_eq!(Value::Float(OrderedFloat(1.5)).as_f64(), Some(1.5));
+        assert_eq!(Value::Double(OrderedFloat(-2.5)).as_f64(), Some(-2.5));
+        assert_eq!(Value::Integer(3).as_f64(), Some(3.0));
+        assert_eq!(Value::Boolean(true).as_f64(), None);
+        assert_eq!(Value::Null.as_f64(), None);
+
+        assert_eq!(Value::String("abc".to_string()).as_str(), Some("abc"));
+        assert_eq!(Value::Integer(1).as_str(), None);
+        assert_eq!(Value::Null.as_str(), None);
+
+        assert_eq!(Value::Boolean(false).as_bool(), Some(false));
+        assert_eq!(Value::Integer(1).as_bool(), None);
+        assert_eq!(Value::Null.as_bool(), None);
+    }
+
+    #[test]
+    fn cast() {
+        assert_eq!(
+            Value::Tinyint(1).cast(&DataType::Bigint).unwrap(),
+            Value::Bigint(1)
+        );
+        assert_eq!(
+            Value::Integer(2).cast(&DataType::Double).unwrap(),
+            Value::Double(OrderedFloat(2.0))
+        );
+        assert_eq!(
+            Value::Float(OrderedFloat(1.5))
+                .cast(&DataType::Double)
+                .unwrap(),
+            Value::Double(OrderedFloat(1.5))
+        );
+        assert_eq!(Value::Null.cast(&DataType::Integer).unwrap(), Value::Null);
+        assert!(Value::Integer(i64::MAX).cast(&DataType::Tinyint).is_err());
+        assert!(Value::String("1".into()).cast(&DataType::Integer).is_err());
+        assert!(Value::Double(OrderedFloat(1.5))
+            .cast(&DataType::Integer)
+            .is_err());
+    }
+
+    #[test]
+    fn cast_explicit() {
+        let cast = |value: Value, datatype: DataType| value.cast_explicit(&datatype);
+        let string = |s: &str| Value::String(s.to_string());
+        // widening
+        assert_eq!(
+            cast(Value::Tinyint(-1), DataType::Bigint).unwrap(),
+            Value::Bigint(-1)
+        );
+        assert_eq!(
+            cast(Value::Integer(3), DataType::Float).unwrap(),
+            Value::Float(OrderedFloat(3.0))
+        );
+        // narrowing
+        assert!(matches!(
+            cast(Value::Integer(i64::MAX), DataType::Tinyint),
+            Err(Error::OutOfBound("Tinyint", "overflow"))
+        ));
+        assert!(matches!(
+            cast(Value::Double(OrderedFloat(1e40)), DataType::Bigint),
+            Err(Error::OutOfBound("Bigint", "overflow"))
+        ));
+        assert!(matches!(
+            cast(Value::Double(OrderedFloat(f64::NAN)), DataType::Integer),
+            Err(Error::OutOfBound(..))
+        ));
+        assert_eq!(
+            cast(Value::Double(OrderedFloat(-2.5)), DataType::Integer).unwrap(),
+            Value::Integer(-3)
+        );
+        assert_eq!(
+            cast(Value::Decimal("1.49".parse().unwrap()), DataType::Smallint).unwrap(),
+            Value::Smallint(1)
+        );
+        // strings
+        assert_eq!(
+            cast(string(" 42 "), DataType::Integer).unwrap(),
+            Value::Integer(42)
+        );
+        assert!(matches!(
+            cast(string("300"), DataType::Tinyint),
+            Err(Error::OutOfBound("Tinyint", "overflow"))
+        ));
+        assert!(matches!(
+            cast(string("4x"), DataType::Integer),
+            Err(Error::FromStr(_))
+        ));
+        assert_eq!(
+            cast(string("true"), DataType::Boolean).unwrap(),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            cast(string("-1.50"), DataType::Decimal)
+                .unwrap()
+                .to_string(),
+            "-1.50"
+        );
+        assert_eq!(
+            cast(Value::Integer(-7), DataType::String).unwrap(),
+            string("-7")
+        );
+        assert_eq!(
+            cast(Value::Timestamp(0), DataType::String).unwrap(),
+            string("1970-01-01T00:00:00")
+        );
+        assert_eq!(cast(Value::Null, DataType::Tinyint).unwrap(), Value::Null);
+    }
+
+    #[test]
+    fn cast_boolean() {
+        assert_eq!(
+            Value::Boolean(true).cast(&DataType::Integer).unwrap(),
+            Value::Integer(1)
+        );
+        assert_eq!(
+            Value::Boolean(false).cast(&DataType::Tinyint).unwrap(),
+            Value::Tinyint(0)
+        );
+        assert_eq!(
+            Value::Tinyint(0).cast(&DataType::Boolean).unwrap(),
+            Value::Boolean(false)
+        );
+        assert_eq!(
+            Value::Bigint(1).cast(&DataType::Boolean).unwrap(),
+            Value::Boolean(true)
+        );
+        assert!(Value::Integer(2).cast(&DataType::Boolean).is_err());
+        assert_eq!(
+            Value::Integer(-2)
+                .cast_with(&DataType::Boolean, CastMode::Lenient)
+                .unwrap(),
+            Value::Boolean(true)
+        );
+        assert!(Value::Boolean(true).cast(&DataType::Double).is_err());
+    }
+
+    #[test]
+    fn cast_range() {
+        assert_eq!(
+            Value::Tinyint(127).cast(&DataType::Tinyint).unwrap(),
+            Value::Tinyint(127)
+        );
+        assert_eq!(
+            Value::Integer(-128).cast(&DataType::Tinyint).unwrap(),
+            Value::Tinyint(-128)
+        );
+        assert!(Value::Tinyint(128).cast(&DataType::Tinyint).is_err());
+        assert!(Value::Smallint(-129).cast(&DataType::Tinyint).is_err());
+        assert_eq!(
+            Value::Smallint(32767).cast(&DataType::Smallint).unwrap(),
+            Value::Smallint(32767)
+        );
+        assert!(Value::Integer(32768).cast(&DataType::Smallint).is_err());
+    }
+
+    #[test]
+    fn from_literal() {
+        for (literal, value) in [
+            (Literal::Null, Value::Null),
+            (Literal::Boolean(true), Value::Boolean(true)),
+            (Literal::Tinyint(-1), Value::Tinyint(-1)),
+            (Literal::Smallint(300), Value::Smallint(300)),
+            (Literal::Integer(70000), Value::Integer(70000)),
+            (Literal::Bigint(1 << 70), Value::Bigint(1 << 70)),
+            (Literal::Float(1.5), Value::Float(OrderedFloat(1.5))),
+            (Literal::Double(2.5), Value::Double(OrderedFloat(2.5))),
+            (
+                Literal::String("a".to_string()),
+                Value::String("a".to_string()),
+            ),
+        ] {
+            assert_eq!(Value::from(literal), value);
+        }
+    }
+
+    #[test]
+    fn project() {
+        let columns: Columns = vec![
+            Column::new("a", DataType::Integer),
+            Column::new("b", DataType::String),
+            Column::new("c", DataType::Boolean),
+        ];
+        let row: Row = vec![
+            Value::Integer(1),
+            Value::String("b".to_string()),
+            Value::Boolean(true),
+        ];
+        let indices = column_indices(&columns, &["c", "b", "a"]).unwrap();
+        assert_eq!(indices, vec![2, 1, 0]);
+        assert_eq!(
+            row.project(&indices),
+            row.iter().rev().cloned().collect::<Row>()
+        );
+        assert_eq!(row.project(&[0, 0]), vec![Value::Integer(1); 2]);
+        assert!(column_indices(&columns, &["d"]).is_err());
+    }
+}
@@ -0,0 +1,152 @@
+//! A timestamp is stored as the microseconds since `1970-01-01 00:00:00`, without a time zone.
+//! The dates are in the proleptic Gregorian calendar.
+
+use crate::sql::{Error, SqlResult};
+
+const MICROS_PER_SECOND: i64 = 1_000_000;
+const MICROS_PER_DAY: i64 = 86_400 * MICROS_PER_SECOND;
+
+/// The days since 1970-01-01 of a date
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    // the year starts in March, so the leap day is the last day of the year
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The year, month and day of the days since 1970-01-01, the inverse of [`days_from_civil`]
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = era * 400 + year_of_era + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// The number of exactly `len` digits
+fn digits(s: &str, len: usize) -> Option<i64> {
+    if s.len() != len || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+/// Parse a timestamp like `2024-01-01 12:30:00.5`. The time may be separated by `T` instead,
+/// it may have up to 6 digits of the fraction, and a date alone is at midnight
+pub fn parse(s: &str) -> SqlResult<i64> {
+    parse_micros(s).ok_or_else(|| Error::FromStr(format!("Can't convert {} to Timestamp", s)))
+}
+
+fn parse_micros(s: &str) -> Option<i64> {
+    let (date, time) = match s.split_once([' ', 'T']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (s, None),
+    };
+    let mut date = date.split('-');
+    let year = digits(date.next()?, 4)?;
+    let month = digits(date.next()?, 2)?;
+    let day = digits(date.next()?, 2)?;
+    if date.next().is_some()
+        || !(1..=12).contains(&month)
+        || !(1..=days_in_month(year, month)).contains(&day)
+    {
+        return None;
+    }
+    let mut micros = days_from_civil(year, month, day) * MICROS_PER_DAY;
+    if let Some(time) = time {
+        let (time, fraction) = match time.split_once('.') {
+            Some((time, fraction)) if (1..=6).contains(&fraction.len()) => (
+                time,
+                digits(fraction, fraction.len())? * 10i64.pow(6 - fraction.len() as u32),
+            ),
+            Some(_) => return None,
+            None => (time, 0),
+        };
+        let mut time = time.split(':');
+        let hour = digits(time.next()?, 2)?;
+        let minute = digits(time.next()?, 2)?;
+        let second = digits(time.next()?, 2)?;
+        if time.next().is_some() || hour > 23 || minute > 59 || second > 59 {
+            return None;
+        }
+        micros += (hour * 3600 + minute * 60 + second) * MICROS_PER_SECOND + fraction;
+    }
+    Some(micros)
+}
+
+/// Format a timestamp in ISO-8601, like `2024-01-01T12:30:00.500000`.
+/// The fraction is left out when it's zero
+pub fn format(micros: i64) -> String {
+    let (year, month, day) = civil_from_days(micros.div_euclid(MICROS_PER_DAY));
+    let time = micros.rem_euclid(MICROS_PER_DAY);
+    let seconds = time / MICROS_PER_SECOND;
+    let fraction = time % MICROS_PER_SECOND;
+    let mut formatted = format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+    if fraction != 0 {
+        formatted.push_str(&format!(".{:06}", fraction));
+    }
+    formatted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_format() {
+        assert_eq!(parse("1970-01-01").unwrap(), 0);
+        assert_eq!(parse("1970-01-02 00:00:01").unwrap(), 86_401_000_000);
+        assert_eq!(parse("1969-12-31T23:59:59.5").unwrap(), -500_000);
+        for (s, formatted) in [
+            ("2024-01-01 00:00:00", "2024-01-01T00:00:00"),
+            ("2024-02-29", "2024-02-29T00:00:00"),
+            ("2000-03-01T12:30:05.000250", "2000-03-01T12:30:05.000250"),
+            ("1900-12-31 23:59:59", "1900-12-31T23:59:59"),
+            ("0001-01-01", "0001-01-01T00:00:00"),
+            ("9999-12-31 23:59:59.999999", "9999-12-31T23:59:59.999999"),
+        ] {
+            assert_eq!(format(parse(s).unwrap()), formatted);
+            assert_eq!(parse(formatted).unwrap(), parse(s).unwrap());
+        }
+        for s in [
+            "",
+            "2024",
+            "2024-1-01",
+            "2023-02-29",
+            "2024-13-01",
+            "2024-01-01 24:00:00",
+            "2024-01-01 00:00",
+            "2024-01-01 00:00:00.",
+            "2024-01-01 00:00:00.1234567",
+            "2024-01-01-01",
+        ] {
+            assert!(parse(s).is_err(), "{}", s);
+        }
+    }
+}
@@ -1,11 +1,32 @@
 use crate::storage::{PageId, PAGE_SIZE};
 use std::io::SeekFrom;
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 use tokio::sync::RwLock;
 
 pub struct DiskManager {
     db_file: RwLock<tokio::fs::File>,
+    durability: DurabilityMode,
+    page_size: usize,
+    syncs: AtomicUsize,
+    #[cfg(test)]
+    failing_writes: AtomicUsize,
+}
+
+/// When the written pages are synced to the disk. Only a synced write survives a power loss,
+/// but syncing waits for the disk, so a stricter mode trades write throughput for durability
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DurabilityMode {
+    /// The file is only synced when the buffer pool shuts down,
+    /// committed transactions may be lost on a power loss
+    #[default]
+    None,
+    /// Every commit writes the modified pages and syncs the file once
+    PerCommit,
+    /// Every page write is synced, and commits write the modified pages as well.
+    /// It's the safest and the slowest mode
+    PerWrite,
 }
 
 impl DiskManager {
@@ -19,19 +40,68 @@ impl DiskManager {
             .await?;
         Ok(DiskManager {
             db_file: RwLock::new(db_file),
+            durability: DurabilityMode::default(),
+            page_size: PAGE_SIZE,
+            syncs: Default::default(),
+            #[cfg(test)]
+            failing_writes: Default::default(),
         })
     }
 
+    pub fn with_durability(mut self, durability: DurabilityMode) -> Self {
+        self.durability = durability;
+        self
+    }
+
+    pub fn durability(&self) -> DurabilityMode {
+        self.durability
+    }
+
+    /// The size of the pages in the file, it's [`PAGE_SIZE`] by default
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    /// The number of times the file has been synced
+    pub fn syncs(&self) -> usize {
+        self.syncs.load(Ordering::Relaxed)
+    }
+
+    /// Make the next `count` page writes fail, to test how the failures are handled
+    #[cfg(test)]
+    pub(crate) fn fail_writes(&self, count: usize) {
+        self.failing_writes.store(count, Ordering::SeqCst);
+    }
+
     pub async fn write_page(
         &self,
         page_id: PageId,
         page_data: &[u8],
     ) -> Result<(), std::io::Error> {
-        let offset = PAGE_SIZE as u64 * page_id as u64;
+        #[cfg(test)]
+        if self
+            .failing_writes
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+                count.checked_sub(1)
+            })
+            .is_ok()
+        {
+            return Err(std::io::Error::other("injected write failure"));
+        }
+        let offset = self.page_size as u64 * page_id as u64;
         let mut db_file = self.db_file.write().await;
         db_file.seek(SeekFrom::Start(offset)).await?;
         db_file.write_all(page_data).await?;
         db_file.flush().await?;
+        if self.durability == DurabilityMode::PerWrite {
+            db_file.sync_data().await?;
+            self.syncs.fetch_add(1, Ordering::Relaxed);
+        }
         Ok(())
     }
     pub async fn read_page(
@@ -39,10 +109,110 @@ impl DiskManager {
         page_id: PageId,
         page_data: &mut [u8],
     ) -> Result<(), std::io::Error> {
-        let offset = PAGE_SIZE as u64 * page_id as u64;
+        let offset = self.page_size as u64 * page_id as u64;
         let mut db_file = self.db_file.write().await;
         db_file.seek(SeekFrom::Start(offset)).await?;
         db_file.read_exact(page_data).await?;
         Ok(())
     }
+
+    /// Write contiguous pages starting from `start` with a single write,
+    /// `page_data` must hold exactly `count` pages
+    pub async fn write_pages(
+        &self,
+        start: PageId,
+        count: usize,
+        page_data: &[u8],
+    ) -> Result<(), std::io::Error> {
+        check_len(count, self.page_size, page_data.len())?;
+        self.write_page(start, page_data).await
+    }
+
+    /// Read contiguous pages starting from `start` with a single read,
+    /// `page_data` must hold exactly `count` pages
+    pub async fn read_pages(
+        &self,
+        start: PageId,
+        count: usize,
+        page_data: &mut [u8],
+    ) -> Result<(), std::io::Error> {
+        check_len(count, self.page_size, page_data.len())?;
+        self.read_page(start, page_data).await
+    }
+
+    /// Make sure the written pages reach the disk
+    pub async fn sync(&self) -> Result<(), std::io::Error> {
+        self.db_file.write().await.sync_all().await?;
+        self.syncs.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// The number of pages the database file can hold, a partially written tail page counts as one
+    pub async fn num_pages(&self) -> Result<usize, std::io::Error> {
+        let len = self.db_file.read().await.metadata().await?.len() as usize;
+        Ok(len.div_ceil(self.page_size))
+    }
+}
+
+fn check_len(count: usize, page_size: usize, len: usize) -> Result<(), std::io::Error> {
+    if count.checked_mul(page_size) != Some(len) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("buffer of {} bytes can't hold exactly {} pages", len, count),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn contiguous_pages() -> Result<(), std::io::Error> {
+        let file = tempfile::NamedTempFile::new()?;
+        let disk_manager = DiskManager::new(file.path()).await?;
+        let mut data = vec![0u8; 4 * PAGE_SIZE];
+        for (i, page) in data.chunks_mut(PAGE_SIZE).enumerate() {
+            page.fill(i as u8 + 1);
+        }
+        disk_manager.write_pages(2, 4, &data).await?;
+        assert_eq!(disk_manager.num_pages().await?, 6);
+
+        let mut read = vec![0u8; 4 * PAGE_SIZE];
+        disk_manager.read_pages(2, 4, &mut read).await?;
+        assert_eq!(read, data);
+        let mut page = vec![0u8; PAGE_SIZE];
+        disk_manager.read_page(4, &mut page).await?;
+        assert_eq!(page, vec![3u8; PAGE_SIZE]);
+
+        assert!(disk_manager.write_pages(0, 3, &data).await.is_err());
+        assert!(disk_manager
+            .read_pages(0, 2, &mut read[..PAGE_SIZE])
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn durability() -> Result<(), std::io::Error> {
+        let file = tempfile::NamedTempFile::new()?;
+        let page = vec![1u8; PAGE_SIZE];
+        for (durability, syncs) in [
+            (DurabilityMode::None, 0),
+            (DurabilityMode::PerCommit, 0),
+            (DurabilityMode::PerWrite, 3),
+        ] {
+            let disk_manager = DiskManager::new(file.path())
+                .await?
+                .with_durability(durability);
+            for page_id in 0..3 {
+                disk_manager.write_page(page_id, &page).await?;
+            }
+            assert_eq!(disk_manager.syncs(), syncs);
+            disk_manager.sync().await?;
+            assert_eq!(disk_manager.syncs(), syncs + 1);
+        }
+        Ok(())
+    }
 }
@@ -1,21 +1,57 @@
+use crate::buffer;
 use crate::buffer::buffer_pool_manager::BufferPoolManager;
-use crate::sql::types::Value;
-use crate::storage::index::Index;
+use crate::encoding::Decoder;
+use crate::sql::types::{DataType, Value};
 use crate::storage::page::column::Column;
-use crate::storage::page::table::{Tuple, Tuples};
+use crate::storage::page::meta::Meta;
+use crate::storage::page::table::{TableIndex, Tuple, Tuples};
+use crate::storage::page::{PageEncoding, PageTrait};
 use crate::storage::table::Table;
-use crate::storage::{Error, PageId, Storage, StorageResult};
+use crate::storage::value_index::{CompositeIndex, SecondaryIndex, ValueIndex};
+use crate::storage::{
+    Error, OnScanError, PageId, RecordId, ScanOptions, Storage, StorageResult, CHECKSUM_SIZE,
+};
 use async_stream::try_stream;
 use futures::Stream;
-use std::collections::BTreeMap;
-use std::ops::RangeBounds;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::{Bound, RangeBounds};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+/// The page which stores [`Meta`], it's always the first page of the database file
+pub const META_PAGE: PageId = 0;
+
 type TableKey = String;
-type TableValue = (PageId, Arc<Index<Value>>); // table page id , index
+// table page id, index, secondaries, composites
+type TableValue = (PageId, Arc<ValueIndex>, Arc<Secondaries>, Arc<Composites>);
+type Secondaries = BTreeMap<usize, SecondaryIndex>; // column position, index
+type Composites = BTreeMap<String, Arc<CompositeIndex>>; // index name, index
+
+/// The statistics of a table collected by [`Engine::analyze`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TableStats {
+    /// The number of live tuples
+    pub rows: usize,
+    /// The number of pages in the table heap
+    pub pages: usize,
+}
+
+/// An index entry which disagrees with the table heap, found by [`Engine::check_index`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Divergence {
+    /// The record of the entry holds no tuple
+    Dangling { key: Value, record_id: RecordId },
+    /// The tuple of the record holds another value in the indexed column
+    Mismatch {
+        key: Value,
+        record_id: RecordId,
+        found: Value,
+    },
+}
+
 pub struct Engine {
     tables: RwLock<BTreeMap<TableKey, TableValue>>,
+    stats: RwLock<BTreeMap<TableKey, TableStats>>,
     buffer_pool: Arc<BufferPoolManager>,
 }
 
@@ -28,13 +64,36 @@ impl Storage for Engine {
         for column in columns.iter() {
             column.validate()?;
         }
-        let index =
-            Index::new(self.buffer_pool.clone(), Self::evaluate_tree_size(&columns)).await?;
+        let mut tables = self.tables.write().await;
+        if tables.contains_key(&name.clone().into()) {
+            return Err(Error::Value(format!(
+                "Table {} already exists",
+                name.into()
+            )));
+        }
+        let index = ValueIndex::new(
+            self.buffer_pool.clone(),
+            Self::evaluate_tree_size(&columns),
+            Self::primary_datatype(&columns)?,
+        )
+        .await?;
+        let secondaries = Self::new_secondaries(self.buffer_pool.clone(), &columns).await?;
         let table = Table::new(name, columns, self.buffer_pool.clone()).await?;
-        self.tables
-            .write()
-            .await
-            .insert(table.name().to_string(), (table.page_id(), Arc::new(index)));
+        table.set_primary(index.root_page_id().await).await?;
+        table
+            .set_index_roots(Self::secondary_roots(&secondaries).await, Vec::new())
+            .await?;
+        self.write_meta(|meta| meta.push_table(table.page_id()))
+            .await?;
+        tables.insert(
+            table.name().to_string(),
+            (
+                table.page_id(),
+                Arc::new(index),
+                Arc::new(secondaries),
+                Default::default(),
+            ),
+        );
         Ok(table)
     }
 
@@ -47,82 +106,166 @@ impl Storage for Engine {
 
     async fn drop_table(&self, name: &str) -> StorageResult<Option<Table>> {
         // todo delete table and index actually
+        self.stats.write().await.remove(name);
         Ok(match self.tables.write().await.remove(name) {
             None => None,
-            Some((table_page_id, _)) => {
+            Some((table_page_id, _, _, _)) => {
+                self.write_meta(|meta| meta.remove_table(table_page_id))
+                    .await?;
                 Some(Table::try_from(table_page_id, self.buffer_pool.clone()).await?)
             }
         })
     }
 
     async fn insert(&self, name: &str, tuples: Tuples) -> StorageResult<usize> {
-        let primary = self
-            .read_primary(name)
-            .await
-            .ok_or(Error::NotFound("table", name.to_string()))?;
-        let table = self
-            .read_table(name)
-            .await?
-            .ok_or(Error::NotFound("table", name.to_string()))?;
+        let (table, primary, secondaries, composites) = self.resolve(name).await?;
         let primary_position = table.primary_position().await?;
+        let auto_increment = table
+            .columns()
+            .await?
+            .iter()
+            .position(|column| column.auto_increment);
         let mut count = 0;
         for tuple in tuples {
             let key = tuple
                 .field(primary_position)
                 .ok_or(Error::NotFound("column", String::from("primary key")))?;
+            if let Some(value) = auto_increment
+                .and_then(|position| tuple.field(position))
+                .and_then(|value| sequence_value(&value))
+            {
+                table.advance_sequence(value).await?;
+            }
+            let fields = secondaries
+                .keys()
+                .map(|position| tuple.field(*position).unwrap_or(Value::Null))
+                .collect::<Vec<_>>();
+            let keys = composites
+                .values()
+                .map(|index| index.key(&tuple))
+                .collect::<Vec<_>>();
             let record_id = table.insert(tuple).await?;
             primary.insert(key, record_id).await?;
+            for (index, field) in secondaries.values().zip(fields) {
+                index.insert(field, record_id).await?;
+            }
+            for (index, key) in composites.values().zip(keys) {
+                index.insert(key, record_id).await?;
+            }
             count += 1
         }
         Ok(count)
     }
 
     async fn read(&self, name: &str, key: &Value) -> StorageResult<Option<Tuple>> {
-        let primary = self
-            .read_primary(name)
-            .await
-            .ok_or(Error::NotFound("table", name.to_string()))?;
-        let table = self
-            .read_table(name)
-            .await?
-            .ok_or(Error::NotFound("table", name.to_string()))?;
+        let (table, primary, _, _) = self.resolve(name).await?;
         Ok(match primary.search(key).await? {
             None => None,
             Some(record_id) => table.read_tuple(record_id).await?,
         })
     }
 
-    async fn delete(&self, name: &str, key: &Value) -> StorageResult<Option<Tuple>> {
-        let primary = self
-            .read_primary(name)
+    async fn contains(&self, name: &str, key: &Value) -> StorageResult<bool> {
+        self.read_primary(name)
             .await
-            .ok_or(Error::NotFound("table", name.to_string()))?;
-        let table = self
-            .read_table(name)
-            .await?
-            .ok_or(Error::NotFound("table", name.to_string()))?;
+            .ok_or(Error::NotFound("table", name.to_string()))?
+            .contains(key)
+            .await
+    }
+
+    async fn delete(&self, name: &str, key: &Value) -> StorageResult<Option<Tuple>> {
+        let (table, primary, _, _) = self.resolve(name).await?;
         Ok(match primary.delete(key).await? {
             None => None,
-            Some((_, record_id)) => Some(table.delete(record_id).await?),
+            // the tuple may be soft-deleted already
+            Some((_, record_id)) => match table.read_tuple(record_id).await? {
+                None => None,
+                Some(_) => Some(table.delete(record_id).await?),
+            },
         })
     }
 
-    async fn update(&self, name: &str, tuple: Tuple) -> StorageResult<Option<()>> {
-        let primary = self
-            .read_primary(name)
-            .await
-            .ok_or(Error::NotFound("table", name.to_string()))?;
-        let table = self
-            .read_table(name)
-            .await?
-            .ok_or(Error::NotFound("table", name.to_string()))?;
-        let key = table.primary_key(&tuple).await?;
-        Ok(match primary.search(&key).await? {
+    async fn soft_delete(&self, name: &str, key: &Value) -> StorageResult<Option<Tuple>> {
+        let (table, primary, _, _) = self.resolve(name).await?;
+        Ok(match primary.search(key).await? {
             None => None,
-            Some(record_id) => table.update_tuple(record_id, tuple).await?,
+            Some(record_id) => match table.read_tuple(record_id).await? {
+                None => None,
+                Some(_) => Some(table.delete(record_id).await?),
+            },
         })
     }
 
+    async fn vacuum(&self, name: &str) -> StorageResult<usize> {
+        let (table, primary, secondaries, composites) = self.resolve(name).await?;
+        let vacuumed = table.vacuum().await?;
+        // the keys of hard-deleted tuples are gone or reused, only soft-deleted keys remain
+        for (record_id, tuple) in vacuumed.removed.iter() {
+            let key = table.primary_key(tuple).await?;
+            if primary.search(&key).await? == Some(*record_id) {
+                primary.delete(&key).await?;
+            }
+            for (position, index) in secondaries.iter() {
+                let field = tuple.field(*position).unwrap_or(Value::Null);
+                index.delete(field, *record_id).await?;
+            }
+            for index in composites.values() {
+                index.delete(index.key(tuple), *record_id).await?;
+            }
+        }
+        for (old, record_id, tuple) in vacuumed.moved {
+            for (position, index) in secondaries.iter() {
+                let field = tuple.field(*position).unwrap_or(Value::Null);
+                index.delete(field.clone(), old).await?;
+                index.insert(field, record_id).await?;
+            }
+            for index in composites.values() {
+                index.delete(index.key(&tuple), old).await?;
+                index.insert(index.key(&tuple), record_id).await?;
+            }
+            let key = table.primary_key(&tuple).await?;
+            primary.delete(&key).await?;
+            primary.insert(key, record_id).await?;
+        }
+        Ok(vacuumed.removed.len())
+    }
+
+    async fn update(&self, name: &str, tuple: Tuple) -> StorageResult<Option<()>> {
+        let (table, primary, secondaries, composites) = self.resolve(name).await?;
+        let key = table.primary_key(&tuple).await?;
+        let record_id = match primary.search(&key).await? {
+            None => return Ok(None),
+            Some(record_id) => record_id,
+        };
+        let old = match table.read_tuple(record_id).await? {
+            None => return Ok(None),
+            Some(old) => old,
+        };
+        for (position, index) in secondaries.iter() {
+            let (old, new) = (old.field(*position), tuple.field(*position));
+            if old != new {
+                index.delete(old.unwrap_or(Value::Null), record_id).await?;
+                index.insert(new.unwrap_or(Value::Null), record_id).await?;
+            }
+        }
+        for index in composites.values() {
+            let (old, new) = (index.key(&old), index.key(&tuple));
+            if old != new {
+                index.delete(old, record_id).await?;
+                index.insert(new, record_id).await?;
+            }
+        }
+        table.update_tuple(record_id, tuple).await
+    }
+
+    async fn next_sequence(&self, name: &str) -> StorageResult<i64> {
+        self.read_table(name)
+            .await?
+            .ok_or(Error::NotFound("table", name.to_string()))?
+            .next_sequence()
+            .await
+    }
+
     async fn scan<'a, R>(
         &self,
         name: &str,
@@ -132,19 +275,39 @@ impl Storage for Engine {
         R: RangeBounds<&'a Value>,
         Value: 'a,
     {
-        let primary = self
-            .read_primary(name)
-            .await
-            .ok_or(Error::NotFound("table", name.to_string()))?;
-        let table = self
-            .read_table(name)
-            .await?
-            .ok_or(Error::NotFound("table", name.to_string()))?;
+        self.scan_with(name, range, ScanOptions::default()).await
+    }
+
+    async fn scan_with<'a, R>(
+        &self,
+        name: &str,
+        range: R,
+        options: ScanOptions,
+    ) -> StorageResult<impl Stream<Item = StorageResult<Tuple>>>
+    where
+        R: RangeBounds<&'a Value>,
+        Value: 'a,
+    {
+        let (table, primary, _, _) = self.resolve(name).await?;
         let record_ids = primary.search_range(range).await?;
         let stream = try_stream! {
+            let mut skipped = BTreeSet::new();
             for record_id in record_ids{
-                yield table.read_tuple(record_id).await?
+                if skipped.contains(&record_id.page_id) {
+                    continue;
+                }
+                let tuple = match (table.read_record(record_id).await, &options.on_error) {
+                    (Err(err), OnScanError::SkipAndReport(report)) if is_decode_error(&err) => {
+                        skipped.insert(record_id.page_id);
+                        report.record(record_id.page_id);
+                        continue;
+                    }
+                    (result, _) => result?,
+                }
                 .ok_or(Error::NotFound("tuple",format!("page: {} slot: {}",record_id.page_id,record_id.slot_num)))?;
+                if options.include_deleted || !tuple.deleted {
+                    yield tuple;
+                }
             }
         };
         Ok(stream)
@@ -152,22 +315,584 @@ impl Storage for Engine {
 }
 
 impl Engine {
-    pub fn new(buffer_pool: Arc<BufferPoolManager>) -> Self {
-        Self {
+    /// Create a storage engine on an empty database file, the first page is taken by [`Meta`]
+    pub async fn new(buffer_pool: Arc<BufferPoolManager>) -> StorageResult<Self> {
+        let mut meta = Meta {
+            page_size: buffer_pool.page_size(),
+            ..Default::default()
+        };
+        let mut page = buffer_pool.new_page_write_owned(&mut meta).await?;
+        if meta.page_id() != META_PAGE {
+            return Err(Error::Value(format!(
+                "Meta must be stored in page {}, but got page {}",
+                META_PAGE,
+                meta.page_id()
+            )));
+        }
+        page.write_meta_back(&meta)?;
+        Ok(Self {
             tables: Default::default(),
+            stats: Default::default(),
+            buffer_pool,
+        })
+    }
+
+    /// Open a storage engine from an existing database file. Every index of every table is
+    /// opened at the root recorded in the table page.
+    /// Fails if the buffer pool's page size isn't the one the database file is created with.
+    pub async fn open(buffer_pool: Arc<BufferPoolManager>) -> StorageResult<Self> {
+        let page_size = Self::read_page_size(&buffer_pool).await?;
+        if page_size != buffer_pool.page_size() {
+            return Err(Error::Value(format!(
+                "The database file has pages of {} bytes, but it's opened with pages of {} bytes",
+                page_size,
+                buffer_pool.page_size()
+            )));
+        }
+        let meta = buffer_pool.fetch_page_read_owned(META_PAGE).await?.meta()?;
+        let mut tables = BTreeMap::new();
+        for page_id in meta.tables() {
+            let table = Table::try_from(*page_id, buffer_pool.clone()).await?;
+            let columns = table.columns().await?;
+            let index = ValueIndex::open(
+                buffer_pool.clone(),
+                table.primary().await?,
+                Self::evaluate_tree_size(&columns),
+                Self::primary_datatype(&columns)?,
+            )
+            .await?;
+            let (secondary_roots, composite_roots) = table.index_roots().await?;
+            let secondaries =
+                Self::open_secondaries(buffer_pool.clone(), &columns, &secondary_roots).await?;
+            let indexes = table.indexes().await?;
+            if indexes.len() != composite_roots.len() {
+                return Err(Error::Value(format!(
+                    "Table {} records {} roots for {} indexes",
+                    table.name(),
+                    composite_roots.len(),
+                    indexes.len()
+                )));
+            }
+            let mut composites = Composites::new();
+            for (index, root) in indexes.into_iter().zip(composite_roots) {
+                let composite = CompositeIndex::open(
+                    buffer_pool.clone(),
+                    root,
+                    Self::evaluate_tree_size(&columns),
+                    index.columns().to_vec(),
+                    Self::datatypes(&columns, index.columns()),
+                )
+                .await?;
+                composites.insert(index.name().to_string(), Arc::new(composite));
+            }
+            tables.insert(
+                table.name().to_string(),
+                (
+                    table.page_id(),
+                    Arc::new(index),
+                    Arc::new(secondaries),
+                    Arc::new(composites),
+                ),
+            );
+        }
+        Ok(Self {
+            tables: RwLock::new(tables),
+            stats: Default::default(),
             buffer_pool,
+        })
+    }
+
+    /// Read the page size recorded in [`Meta`] from the start of the file, the meta page
+    /// isn't fetched since it doesn't match its checksum if it's read with another page size
+    async fn read_page_size(buffer_pool: &BufferPoolManager) -> StorageResult<usize> {
+        let mut data = [0; CHECKSUM_SIZE + Meta::PREFIX_SIZE];
+        buffer_pool
+            .disk_manager()
+            .read_page(META_PAGE, &mut data)
+            .await?;
+        let mut buf = &data[CHECKSUM_SIZE..];
+        PageId::decode(&mut buf)?;
+        Ok(usize::decode(&mut buf)?)
+    }
+
+    /// Make sure every modified page is written to disk
+    pub async fn flush(&self) -> StorageResult<()> {
+        self.write_roots().await?;
+        self.buffer_pool.flush_page_all().await?;
+        Ok(())
+    }
+
+    /// Make the changes of a committed transaction durable, see [`BufferPoolManager::sync_commit`]
+    pub async fn commit(&self) -> StorageResult<()> {
+        self.write_roots().await?;
+        self.buffer_pool.sync_commit().await?;
+        Ok(())
+    }
+
+    /// Record the roots of the indexes in the table pages, a root moves as its tree grows or
+    /// shrinks. A table page is only written if one of its roots moved
+    async fn write_roots(&self) -> StorageResult<()> {
+        for (page_id, primary, secondaries, composites) in self.tables.read().await.values() {
+            let table = Table::try_from(*page_id, self.buffer_pool.clone()).await?;
+            table.set_primary(primary.root_page_id().await).await?;
+            let mut composite_roots = Vec::with_capacity(composites.len());
+            for index in table.indexes().await? {
+                let composite = composites
+                    .get(index.name())
+                    .ok_or(Error::NotFound("index", index.name().to_string()))?;
+                composite_roots.push(composite.root_page_id().await);
+            }
+            table
+                .set_index_roots(Self::secondary_roots(secondaries).await, composite_roots)
+                .await?;
         }
+        Ok(())
+    }
+
+    /// The roots of the secondary indexes in the order of their columns
+    async fn secondary_roots(secondaries: &Secondaries) -> Vec<PageId> {
+        let mut roots = Vec::with_capacity(secondaries.len());
+        for index in secondaries.values() {
+            roots.push(index.root_page_id().await);
+        }
+        roots
+    }
+
+    pub fn buffer_pool(&self) -> &Arc<BufferPoolManager> {
+        &self.buffer_pool
+    }
+
+    pub async fn table_names(&self) -> Vec<String> {
+        self.tables.read().await.keys().cloned().collect()
+    }
+
+    async fn write_meta(&self, f: impl FnOnce(&mut Meta)) -> StorageResult<()> {
+        let mut page = self.buffer_pool.fetch_page_write_owned(META_PAGE).await?;
+        let mut meta = page.meta()?;
+        f(&mut meta);
+        page.write_meta_back(&meta)?;
+        Ok(())
     }
     pub fn evaluate_tree_size(_columns: &[Column]) -> usize {
         64
     }
 
-    pub async fn read_primary(&self, name: &str) -> Option<Arc<Index<Value>>> {
+    /// An index for every indexed column but the primary key
+    async fn new_secondaries(
+        buffer_pool: Arc<BufferPoolManager>,
+        columns: &[Column],
+    ) -> StorageResult<Secondaries> {
+        let mut secondaries = BTreeMap::new();
+        for (position, column) in columns.iter().enumerate() {
+            if column.index && !column.primary() {
+                let index = SecondaryIndex::new(
+                    buffer_pool.clone(),
+                    Self::evaluate_tree_size(columns),
+                    column.datatype.clone(),
+                )
+                .await?;
+                secondaries.insert(position, index);
+            }
+        }
+        Ok(secondaries)
+    }
+
+    /// Open the index of every indexed column but the primary key at the recorded roots
+    async fn open_secondaries(
+        buffer_pool: Arc<BufferPoolManager>,
+        columns: &[Column],
+        roots: &[PageId],
+    ) -> StorageResult<Secondaries> {
+        let positions = columns
+            .iter()
+            .enumerate()
+            .filter(|(_, column)| column.index && !column.primary())
+            .map(|(position, _)| position)
+            .collect::<Vec<_>>();
+        if positions.len() != roots.len() {
+            return Err(Error::Value(format!(
+                "{} roots are recorded for {} indexed columns",
+                roots.len(),
+                positions.len()
+            )));
+        }
+        let mut secondaries = BTreeMap::new();
+        for (position, root) in positions.into_iter().zip(roots) {
+            let index = SecondaryIndex::open(
+                buffer_pool.clone(),
+                *root,
+                Self::evaluate_tree_size(columns),
+                columns[position].datatype.clone(),
+            )
+            .await?;
+            secondaries.insert(position, index);
+        }
+        Ok(secondaries)
+    }
+
+    async fn new_composite(
+        buffer_pool: Arc<BufferPoolManager>,
+        columns: &[Column],
+        positions: Vec<usize>,
+    ) -> StorageResult<CompositeIndex> {
+        let datatypes = Self::datatypes(columns, &positions);
+        CompositeIndex::new(
+            buffer_pool,
+            Self::evaluate_tree_size(columns),
+            positions,
+            datatypes,
+        )
+        .await
+    }
+
+    /// The datatypes of the columns at the positions
+    fn datatypes(columns: &[Column], positions: &[usize]) -> Vec<DataType> {
+        positions
+            .iter()
+            .map(|position| columns[*position].datatype.clone())
+            .collect()
+    }
+
+    fn primary_datatype(columns: &[Column]) -> StorageResult<DataType> {
+        columns
+            .iter()
+            .find(|column| column.primary())
+            .map(|column| column.datatype.clone())
+            .ok_or(Error::NotFound("column", String::from("primary key")))
+    }
+
+    /// Rebuild the primary, secondary and composite indexes of the table, returns the number
+    /// of tuples in the primary index
+    pub async fn reindex(&self, name: &str) -> StorageResult<usize> {
+        let (_, primary, secondaries, composites) = self
+            .tables
+            .read()
+            .await
+            .get(name)
+            .cloned()
+            .ok_or(Error::NotFound("table", name.to_string()))?;
+        let len = primary.reindex().await?;
+        for index in secondaries.values() {
+            index.reindex().await?;
+        }
+        for index in composites.values() {
+            index.reindex().await?;
+        }
+        Ok(len)
+    }
+
+    /// Collect the statistics of the table, they are kept until the next analyze
+    pub async fn analyze(&self, name: &str) -> StorageResult<TableStats> {
+        let table = self
+            .read_table(name)
+            .await?
+            .ok_or(Error::NotFound("table", name.to_string()))?;
+        let stats = TableStats {
+            rows: table.records().await?.count(),
+            pages: table.page_count().await?,
+        };
+        self.stats.write().await.insert(name.to_string(), stats);
+        Ok(stats)
+    }
+
+    /// The statistics collected by the last [`Engine::analyze`] of the table
+    pub async fn stats(&self, name: &str) -> Option<TableStats> {
+        self.stats.read().await.get(name).copied()
+    }
+
+    pub async fn read_primary(&self, name: &str) -> Option<Arc<ValueIndex>> {
         self.tables
             .read()
             .await
             .get(name)
-            .map(|(_, index)| index.clone())
+            .map(|(_, index, _, _)| index.clone())
+    }
+
+    async fn read_secondaries(&self, name: &str) -> Option<Arc<Secondaries>> {
+        self.tables
+            .read()
+            .await
+            .get(name)
+            .map(|(_, _, secondaries, _)| secondaries.clone())
+    }
+
+    /// Resolve the table and its indexes by name, a missing table is `NotFound` before
+    /// any page is read
+    async fn resolve(
+        &self,
+        name: &str,
+    ) -> StorageResult<(Table, Arc<ValueIndex>, Arc<Secondaries>, Arc<Composites>)> {
+        let (page_id, primary, secondaries, composites) = self
+            .tables
+            .read()
+            .await
+            .get(name)
+            .cloned()
+            .ok_or(Error::NotFound("table", name.to_string()))?;
+        let table = Table::try_from(page_id, self.buffer_pool.clone()).await?;
+        Ok((table, primary, secondaries, composites))
+    }
+
+    async fn read_composites(&self, name: &str) -> Option<Arc<Composites>> {
+        self.tables
+            .read()
+            .await
+            .get(name)
+            .map(|(_, _, _, composites)| composites.clone())
+    }
+
+    /// Create an index named `index` on the columns of the table at the positions, the index
+    /// is filled with the tuples of the table and persisted in the table page.
+    /// The name must be unique among the indexes of the table.
+    pub async fn create_index(
+        &self,
+        name: &str,
+        index: &str,
+        columns: Vec<usize>,
+    ) -> StorageResult<()> {
+        // holding the tables blocks the writers from missing the index while it's filled
+        let mut tables = self.tables.write().await;
+        let (page_id, _, _, composites) = tables
+            .get_mut(name)
+            .ok_or(Error::NotFound("table", name.to_string()))?;
+        if composites.contains_key(index) {
+            return Err(Error::Value(format!(
+                "Index {} already exists on table {}",
+                index, name
+            )));
+        }
+        let table = Table::try_from(*page_id, self.buffer_pool.clone()).await?;
+        let schema = table.columns().await?;
+        let mut positions = BTreeSet::new();
+        for position in columns.iter() {
+            if *position >= schema.len() {
+                return Err(Error::Value(format!(
+                    "Column {} is out of the {} columns of table {}",
+                    position,
+                    schema.len(),
+                    name
+                )));
+            }
+            if !positions.insert(*position) {
+                return Err(Error::Value(format!(
+                    "Column {} is indexed more than once",
+                    schema[*position].name
+                )));
+            }
+        }
+        let composite =
+            Self::new_composite(self.buffer_pool.clone(), &schema, columns.clone()).await?;
+        for (record_id, tuple) in table.records().await? {
+            composite.insert(composite.key(&tuple), record_id).await?;
+        }
+        table
+            .push_index(
+                TableIndex::new(index, columns),
+                composite.root_page_id().await,
+            )
+            .await?;
+        let mut indexes = composites.as_ref().clone();
+        indexes.insert(index.to_string(), Arc::new(composite));
+        *composites = Arc::new(indexes);
+        Ok(())
+    }
+
+    /// The records whose leading columns of the index equal the prefix.
+    /// Returns `None` if the table has no such index, the records may be deleted already.
+    pub async fn search_composite_index(
+        &self,
+        name: &str,
+        index: &str,
+        prefix: &[Value],
+    ) -> StorageResult<Option<Vec<RecordId>>> {
+        let composites = self
+            .read_composites(name)
+            .await
+            .ok_or(Error::NotFound("table", name.to_string()))?;
+        Ok(match composites.get(index) {
+            None => None,
+            Some(index) => Some(index.search(prefix).await?),
+        })
+    }
+
+    /// The records whose column equals the key, looked up in the secondary index of the column.
+    /// Returns `None` if the column isn't indexed, the records may be deleted already.
+    pub async fn search_index(
+        &self,
+        name: &str,
+        column: usize,
+        key: &Value,
+    ) -> StorageResult<Option<Vec<RecordId>>> {
+        let secondaries = self
+            .read_secondaries(name)
+            .await
+            .ok_or(Error::NotFound("table", name.to_string()))?;
+        Ok(match secondaries.get(&column) {
+            None => None,
+            Some(index) => Some(index.search(key).await?),
+        })
+    }
+
+    /// Delete the tuples whose primary key is within the range, their keys are removed from
+    /// the primary index at once by [`ValueIndex::delete_range`] instead of one at a time.
+    /// Returns the deleted tuples in key order, the soft-deleted ones are skipped.
+    pub async fn delete_range(
+        &self,
+        name: &str,
+        start: Bound<&Value>,
+        end: Bound<&Value>,
+    ) -> StorageResult<Vec<Tuple>> {
+        let (table, primary, _, _) = self.resolve(name).await?;
+        let record_ids = primary.search_range((start, end)).await?;
+        primary.delete_range((start, end)).await?;
+        let mut tuples = Vec::with_capacity(record_ids.len());
+        for record_id in record_ids {
+            if table.read_tuple(record_id).await?.is_some() {
+                tuples.push(table.delete(record_id).await?);
+            }
+        }
+        Ok(tuples)
+    }
+
+    /// Like [`Engine::search_index`], but the records whose column is within the range
+    pub async fn search_index_range(
+        &self,
+        name: &str,
+        column: usize,
+        start: Bound<&Value>,
+        end: Bound<&Value>,
+    ) -> StorageResult<Option<Vec<RecordId>>> {
+        let secondaries = self
+            .read_secondaries(name)
+            .await
+            .ok_or(Error::NotFound("table", name.to_string()))?;
+        Ok(match secondaries.get(&column) {
+            None => None,
+            Some(index) => Some(index.search_range(start, end).await?),
+        })
+    }
+
+    /// The smallest or largest non-null value of the column read from its primary or secondary
+    /// index, NULL if there is none. Returns `None` if the column isn't indexed or the extreme
+    /// entry belongs to a deleted tuple, then the caller has to scan the table.
+    pub async fn index_extreme(
+        &self,
+        name: &str,
+        column: usize,
+        largest: bool,
+    ) -> StorageResult<Option<Value>> {
+        let table = self
+            .read_table(name)
+            .await?
+            .ok_or(Error::NotFound("table", name.to_string()))?;
+        let entry = if column == table.primary_position().await? {
+            let primary = self
+                .read_primary(name)
+                .await
+                .ok_or(Error::NotFound("table", name.to_string()))?;
+            match largest {
+                true => primary.last().await?,
+                false => primary.first().await?,
+            }
+        } else {
+            let secondaries = self
+                .read_secondaries(name)
+                .await
+                .ok_or(Error::NotFound("table", name.to_string()))?;
+            match (secondaries.get(&column), largest) {
+                (None, _) => return Ok(None),
+                (Some(index), true) => index.last().await?,
+                (Some(index), false) => index.first().await?,
+            }
+        };
+        Ok(match entry {
+            None => Some(Value::Null),
+            Some((key, record_id)) => table.read_tuple(record_id).await?.map(|_| key),
+        })
+    }
+
+    /// Verify every entry of the index on the column against the table heap, every entry whose
+    /// tuple is missing or holds another value is reported. The entries of deleted tuples are
+    /// expected until vacuum, so they are only checked for their value.
+    pub async fn check_index(&self, name: &str, column: usize) -> StorageResult<Vec<Divergence>> {
+        let table = self
+            .read_table(name)
+            .await?
+            .ok_or(Error::NotFound("table", name.to_string()))?;
+        let entries = if column == table.primary_position().await? {
+            self.read_primary(name)
+                .await
+                .ok_or(Error::NotFound("table", name.to_string()))?
+                .entries()
+                .await?
+        } else {
+            self.read_secondaries(name)
+                .await
+                .ok_or(Error::NotFound("table", name.to_string()))?
+                .get(&column)
+                .ok_or(Error::NotFound(
+                    "index",
+                    format!("{} column {}", name, column),
+                ))?
+                .entries()
+                .await?
+        };
+        let mut divergences = Vec::new();
+        for (key, record_id) in entries {
+            match table.read_record(record_id).await? {
+                None => divergences.push(Divergence::Dangling { key, record_id }),
+                Some(tuple) => {
+                    let found = tuple.field(column).unwrap_or(Value::Null);
+                    if found != key {
+                        divergences.push(Divergence::Mismatch {
+                            key,
+                            record_id,
+                            found,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(divergences)
+    }
+
+    /// Read the live tuples of the records, the deleted ones are skipped
+    pub async fn read_records(
+        &self,
+        name: &str,
+        record_ids: &[RecordId],
+    ) -> StorageResult<Vec<Tuple>> {
+        let table = self
+            .read_table(name)
+            .await?
+            .ok_or(Error::NotFound("table", name.to_string()))?;
+        let mut tuples = Vec::with_capacity(record_ids.len());
+        for record_id in record_ids {
+            if let Some(tuple) = table.read_tuple(*record_id).await? {
+                tuples.push(tuple);
+            }
+        }
+        Ok(tuples)
+    }
+}
+
+/// Whether the error is a page which fails to decode
+fn is_decode_error(err: &Error) -> bool {
+    matches!(
+        err,
+        Error::Encoding(_)
+            | Error::Buffer(buffer::Error::Encoding(_) | buffer::Error::Quarantined(_))
+    )
+}
+
+/// The value of an auto-increment column as a sequence value, `None` if it isn't an integer
+fn sequence_value(value: &Value) -> Option<i64> {
+    match value {
+        Value::Tinyint(value) => Some(*value as i64),
+        Value::Smallint(value) => Some(*value as i64),
+        Value::Integer(value) => Some(*value),
+        Value::Bigint(value) => Some((*value).clamp(i64::MIN as i128, i64::MAX as i128) as i64),
+        _ => None,
     }
 }
 
@@ -175,15 +900,18 @@ impl Engine {
 mod tests {
 
     use super::*;
+    use crate::encoding::Encoder;
     use crate::sql::types::DataType;
     use crate::storage::disk::disk_manager::DiskManager;
+    use crate::storage::page::table::TableNode;
+    use crate::storage::ScanReport;
     use futures::stream::StreamExt;
 
     async fn new_engine() -> StorageResult<Engine> {
         let f = tempfile::NamedTempFile::new()?;
         let disk_manager = DiskManager::new(f.path()).await?;
         let buffer_pool = BufferPoolManager::new(128, 2, disk_manager).await?;
-        let engine = Engine::new(Arc::new(buffer_pool));
+        let engine = Engine::new(Arc::new(buffer_pool)).await?;
         let column_id = Column::new("id", DataType::Bigint)
             .with_primary(true)
             .with_unique(true);
@@ -307,4 +1035,396 @@ mod tests {
         }
         Ok(())
     }
+
+    #[tokio::test]
+    async fn reopen() -> StorageResult<()> {
+        let f = tempfile::NamedTempFile::new()?;
+        let root = {
+            let disk_manager = DiskManager::new(f.path()).await?;
+            let buffer_pool = BufferPoolManager::new(16, 2, disk_manager).await?;
+            let engine = Engine::new(Arc::new(buffer_pool)).await?;
+            engine
+                .create_table(
+                    "user",
+                    vec![
+                        Column::new("id", DataType::Bigint)
+                            .with_primary(true)
+                            .with_unique(true),
+                        Column::new("name", DataType::String).with_index(true),
+                    ],
+                )
+                .await?;
+            engine.create_index("user", "name_id", vec![1, 0]).await?;
+            let tuples = (0..1024)
+                .map(|id| {
+                    Tuple::new(
+                        vec![Value::Bigint(id), Value::String("Mike".to_string())],
+                        0,
+                    )
+                })
+                .collect::<Vec<_>>();
+            engine.insert("user", tuples).await?;
+            engine.delete("user", &Value::Bigint(0)).await?;
+            engine.flush().await?;
+            engine
+                .read_primary("user")
+                .await
+                .unwrap()
+                .root_page_id()
+                .await
+        };
+        // the file can't be opened with another page size
+        let disk_manager = DiskManager::new(f.path()).await?;
+        let buffer_pool = BufferPoolManager::with_page_size(16, 2, disk_manager, 8192).await?;
+        assert!(matches!(
+            Engine::open(Arc::new(buffer_pool)).await,
+            Err(Error::Value(_))
+        ));
+
+        let disk_manager = DiskManager::new(f.path()).await?;
+        let buffer_pool = BufferPoolManager::new(16, 2, disk_manager).await?;
+        let engine = Engine::open(Arc::new(buffer_pool)).await?;
+        assert_eq!(engine.table_names().await, vec!["user".to_string()]);
+        // the primary index is opened at the recorded root instead of rebuilt
+        assert_eq!(
+            engine
+                .read_primary("user")
+                .await
+                .unwrap()
+                .root_page_id()
+                .await,
+            root
+        );
+        assert!(engine.read("user", &Value::Bigint(0)).await?.is_none());
+        for id in 1..1024 {
+            assert_eq!(
+                engine.read("user", &Value::Bigint(id)).await?,
+                Some(Tuple::new(
+                    vec![Value::Bigint(id), Value::String("Mike".to_string())],
+                    0
+                ))
+            );
+        }
+        let mike = Value::String("Mike".to_string());
+        // the entry of the deleted tuple stays in the secondary index until vacuum
+        let found = engine.search_index("user", 1, &mike).await?.unwrap();
+        assert_eq!(found.len(), 1024);
+        let found = engine
+            .search_composite_index("user", "name_id", &[mike.clone(), Value::Bigint(7)])
+            .await?
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        // the indexes aren't rebuilt into new pages, so reopening doesn't grow the file
+        engine.flush().await?;
+        drop(engine);
+        let len = std::fs::metadata(f.path())?.len();
+        for _ in 0..2 {
+            let disk_manager = DiskManager::new(f.path()).await?;
+            let buffer_pool = BufferPoolManager::new(16, 2, disk_manager).await?;
+            let engine = Engine::open(Arc::new(buffer_pool)).await?;
+            assert_eq!(
+                engine.search_index("user", 1, &mike).await?.unwrap().len(),
+                1024
+            );
+            engine.flush().await?;
+        }
+        assert_eq!(std::fs::metadata(f.path())?.len(), len);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_by_primary() -> StorageResult<()> {
+        let engine = new_engine().await?;
+        let len = 5000;
+        let tuples = (0..len)
+            .map(|id| {
+                Tuple::new(
+                    vec![Value::Bigint(id), Value::String("Mike".to_string())],
+                    0,
+                )
+            })
+            .collect::<Vec<_>>();
+        engine.insert("user", tuples).await?;
+        let table = engine.read_table("user").await?.unwrap();
+        let pages = table
+            .records()
+            .await?
+            .map(|(record_id, _)| record_id.page_id)
+            .collect::<std::collections::BTreeSet<_>>()
+            .len();
+        assert!(pages > 20);
+        // the table page, the path from root to leaf, and the page of tuple
+        for id in [0, len / 2, len - 1] {
+            let stats = engine.buffer_pool.stats();
+            assert!(engine.read("user", &Value::Bigint(id)).await?.is_some());
+            assert!(engine.buffer_pool.stats().accesses() - stats.accesses() <= 6);
+
+            let stats = engine.buffer_pool.stats();
+            assert!(engine.delete("user", &Value::Bigint(id)).await?.is_some());
+            // deleting may also fetch the siblings to rebalance the leaf
+            assert!(engine.buffer_pool.stats().accesses() - stats.accesses() <= 12);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn soft_delete() -> StorageResult<()> {
+        let engine = new_engine().await?;
+        let tuple = |id: i128| {
+            Tuple::new(
+                vec![Value::Bigint(id), Value::String("Mike".to_string())],
+                0,
+            )
+        };
+        engine.insert("user", (0..10).map(tuple).collect()).await?;
+        let engine = &engine;
+        let scan = |options: ScanOptions| async move {
+            engine
+                .scan_with("user", .., options)
+                .await?
+                .collect::<Vec<_>>()
+                .await
+                .into_iter()
+                .collect::<StorageResult<Vec<_>>>()
+        };
+
+        assert_eq!(
+            engine
+                .soft_delete("user", &Value::Bigint(3))
+                .await?
+                .map(|tuple| tuple.values),
+            Some(tuple(3).values)
+        );
+        assert!(engine
+            .soft_delete("user", &Value::Bigint(3))
+            .await?
+            .is_none());
+        assert!(engine.read("user", &Value::Bigint(3)).await?.is_none());
+        assert!(engine.contains("user", &Value::Bigint(3)).await?);
+        assert!(engine.delete("user", &Value::Bigint(5)).await?.is_some());
+
+        let visible = scan(ScanOptions::default()).await?;
+        assert_eq!(visible.len(), 8);
+        assert!(visible.iter().all(|tuple| !tuple.deleted));
+        let all = scan(ScanOptions::default().with_include_deleted(true)).await?;
+        assert_eq!(all.len(), 9);
+        assert!(all[3].deleted);
+        assert_eq!(all[3].values, tuple(3).values);
+
+        // both the soft-deleted and the deleted tuple are removed
+        assert_eq!(engine.vacuum("user").await?, 2);
+        assert!(!engine.contains("user", &Value::Bigint(3)).await?);
+        assert_eq!(
+            scan(ScanOptions::default().with_include_deleted(true))
+                .await?
+                .len(),
+            8
+        );
+        for id in [0, 1, 2, 4, 6, 7, 8, 9] {
+            assert_eq!(
+                engine.read("user", &Value::Bigint(id)).await?,
+                Some(tuple(id))
+            );
+        }
+        engine.insert("user", vec![tuple(3)]).await?;
+        assert_eq!(
+            engine.read("user", &Value::Bigint(3)).await?,
+            Some(tuple(3))
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn skip_corrupt_pages() -> StorageResult<()> {
+        let engine = new_engine().await?;
+        let tuple = |id: i128| {
+            Tuple::new(
+                vec![Value::Bigint(id), Value::String("Mike".to_string())],
+                0,
+            )
+        };
+        engine
+            .insert("user", (0..1000).map(tuple).collect())
+            .await?;
+        let record_ids = engine
+            .read_primary("user")
+            .await
+            .unwrap()
+            .search_range(..)
+            .await?;
+        let corrupt = record_ids[500].page_id;
+        {
+            // the deleted flag of the first tuple follows the header of the node and the timestamp
+            let mut header = Vec::new();
+            TableNode::new(corrupt, Vec::new()).encode(&mut header)?;
+            let mut page = engine.buffer_pool.fetch_page_write_owned(corrupt).await?;
+            page[CHECKSUM_SIZE + header.len() + 8] = 2;
+        }
+        let scan = |options: ScanOptions| {
+            let engine = &engine;
+            async move {
+                engine
+                    .scan_with("user", .., options)
+                    .await?
+                    .collect::<Vec<_>>()
+                    .await
+                    .into_iter()
+                    .collect::<StorageResult<Vec<_>>>()
+            }
+        };
+        assert!(scan(ScanOptions::default()).await.is_err());
+
+        let report = ScanReport::default();
+        let tuples =
+            scan(ScanOptions::default().with_on_error(OnScanError::SkipAndReport(report.clone())))
+                .await?;
+        let expected = record_ids
+            .iter()
+            .enumerate()
+            .filter(|(_, record_id)| record_id.page_id != corrupt)
+            .map(|(id, _)| tuple(id as i128))
+            .collect::<Vec<_>>();
+        assert!(expected.len() < 1000);
+        assert_eq!(tuples, expected);
+        assert_eq!(report.failed_pages(), vec![corrupt]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn secondary_index() -> StorageResult<()> {
+        let engine = new_engine().await?;
+        engine
+            .create_table(
+                "pet",
+                vec![
+                    Column::new("id", DataType::Bigint)
+                        .with_primary(true)
+                        .with_unique(true),
+                    Column::new("owner", DataType::Bigint).with_index(true),
+                ],
+            )
+            .await?;
+        let tuple = |id: i128, owner: Value| Tuple::new(vec![Value::Bigint(id), owner], 0);
+        engine
+            .insert(
+                "pet",
+                (0..10)
+                    .map(|id| tuple(id, Value::Bigint(id % 3)))
+                    .chain([tuple(10, Value::Null)])
+                    .collect(),
+            )
+            .await?;
+        let owned = |owner: i128| {
+            let engine = &engine;
+            async move {
+                let record_ids = engine
+                    .search_index("pet", 1, &Value::Bigint(owner))
+                    .await?
+                    .unwrap();
+                Ok::<_, Error>(
+                    engine
+                        .read_records("pet", &record_ids)
+                        .await?
+                        .into_iter()
+                        .map(|tuple| tuple.values[0].clone())
+                        .collect::<Vec<_>>(),
+                )
+            }
+        };
+        let ids = |ids: &[i128]| ids.iter().map(|id| Value::Bigint(*id)).collect::<Vec<_>>();
+        assert_eq!(owned(1).await?, ids(&[1, 4, 7]));
+        assert!(engine
+            .search_index("pet", 0, &Value::Bigint(1))
+            .await?
+            .is_none());
+        assert!(engine
+            .search_index("user", 1, &Value::Bigint(1))
+            .await?
+            .is_none());
+
+        engine.update("pet", tuple(4, Value::Bigint(2))).await?;
+        assert_eq!(owned(1).await?, ids(&[1, 7]));
+        assert_eq!(owned(2).await?, ids(&[2, 4, 5, 8]));
+        engine.delete("pet", &Value::Bigint(2)).await?;
+        engine.soft_delete("pet", &Value::Bigint(5)).await?;
+        assert_eq!(owned(2).await?, ids(&[4, 8]));
+        // the tuples after the removed ones move, so do their entries
+        assert_eq!(engine.vacuum("pet").await?, 2);
+        assert_eq!(owned(2).await?, ids(&[4, 8]));
+        assert_eq!(owned(0).await?, ids(&[0, 3, 6, 9]));
+        engine
+            .insert("pet", vec![tuple(11, Value::Bigint(2))])
+            .await?;
+        assert_eq!(owned(2).await?, ids(&[4, 8, 11]));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn check_index() -> StorageResult<()> {
+        let engine = new_engine().await?;
+        let tuple = |id: i128| {
+            Tuple::new(
+                vec![Value::Bigint(id), Value::String("hello".to_string())],
+                0,
+            )
+        };
+        engine.insert("user", (0..10).map(tuple).collect()).await?;
+        // soft-deleted tuples keep their entries until vacuum
+        engine.soft_delete("user", &Value::Bigint(3)).await?;
+        assert!(engine.check_index("user", 0).await?.is_empty());
+
+        let primary = engine.read_primary("user").await.unwrap();
+        let record_id = primary.search(&Value::Bigint(1)).await?.unwrap();
+        let dangling = RecordId::new(record_id.page_id, 999);
+        primary.insert(Value::Bigint(100), dangling).await?;
+        primary.insert(Value::Bigint(101), record_id).await?;
+        assert_eq!(
+            engine.check_index("user", 0).await?,
+            vec![
+                Divergence::Dangling {
+                    key: Value::Bigint(100),
+                    record_id: dangling,
+                },
+                Divergence::Mismatch {
+                    key: Value::Bigint(101),
+                    record_id,
+                    found: Value::Bigint(1),
+                },
+            ]
+        );
+        assert!(matches!(
+            engine.check_index("user", 1).await,
+            Err(Error::NotFound("index", _))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn missing_table() -> StorageResult<()> {
+        fn is_not_found<T>(result: StorageResult<T>, name: &str) -> bool {
+            matches!(result, Err(Error::NotFound("table", table)) if table == name)
+        }
+        let engine = new_engine().await?;
+        let tuple = Tuple::new(vec![Value::Bigint(1), Value::String("Mike".to_string())], 0);
+        engine.insert("user", vec![tuple.clone()]).await?;
+        engine.drop_table("user").await?;
+        let accesses = engine.buffer_pool.stats().accesses();
+        // a dropped table and one which never existed
+        for name in ["user", "pet"] {
+            let key = Value::Bigint(1);
+            assert!(is_not_found(
+                engine.insert(name, vec![tuple.clone()]).await,
+                name
+            ));
+            assert!(is_not_found(engine.read(name, &key).await, name));
+            assert!(is_not_found(engine.delete(name, &key).await, name));
+            assert!(is_not_found(engine.soft_delete(name, &key).await, name));
+            assert!(is_not_found(engine.update(name, tuple.clone()).await, name));
+            assert!(is_not_found(engine.scan(name, ..).await, name));
+            assert!(is_not_found(engine.vacuum(name).await, name));
+        }
+        // the table is resolved before any page is read
+        assert_eq!(engine.buffer_pool.stats().accesses(), accesses);
+        Ok(())
+    }
 }
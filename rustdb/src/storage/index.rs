@@ -2,166 +2,1242 @@ use crate::buffer;
 use crate::buffer::buffer_pool_manager::{
     BufferPoolManager, OwnedPageDataReadGuard, OwnedPageDataWriteGuard,
 };
+use crate::encoding::encoded_size::EncodedSize;
 use crate::encoding::{Decoder, Encoder};
+use crate::sql::types::Value;
 use crate::storage::page::index::{Header, Internal, Leaf, Node};
 use crate::storage::page::{PageEncoding, PageTrait};
-use crate::storage::{PageId, RecordId, StorageResult};
+use crate::storage::{Error, PageId, RecordId, StorageResult, CHECKSUM_SIZE, PAGE_SIZE};
+use async_stream::try_stream;
+use futures::{Stream, TryStreamExt};
 use indexmap::IndexMap;
-use std::collections::Bound;
+use std::cmp::Ordering;
+use std::collections::{Bound, VecDeque};
+use std::future::Future;
 use std::marker::PhantomData;
-use std::ops::{Deref, RangeBounds};
+use std::ops::{Deref, RangeBounds, RangeFull};
+use std::sync::atomic::{self, AtomicU64};
 use std::sync::Arc;
-use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::Duration;
+use tokio::sync::{Notify, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// The order of keys stored in [`Index`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+impl SortOrder {
+    pub fn compare<K: Ord>(&self, a: &K, b: &K) -> Ordering {
+        match self {
+            SortOrder::Ascending => a.cmp(b),
+            SortOrder::Descending => b.cmp(a),
+        }
+    }
+
+    /// Returns the (first, last) bounds of range in this order
+    fn bounds<'r, T, R>(&self, range: &'r R) -> (Bound<&'r T>, Bound<&'r T>)
+    where
+        R: RangeBounds<T>,
+    {
+        match self {
+            SortOrder::Ascending => (range.start_bound(), range.end_bound()),
+            SortOrder::Descending => (range.end_bound(), range.start_bound()),
+        }
+    }
+}
+
+/// Where NULL keys are stored in [`Index`]. It's the position in the order of index,
+/// so NULLs are scanned first with [`NullOrder::First`] whatever the [`SortOrder`] is.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NullOrder {
+    #[default]
+    First,
+    Last,
+}
+
+/// A key of [`Index`], the NULL keys are placed by the [`NullOrder`] of index
+/// instead of their [`Ord`]. The encoded size of keys decides when a node is too large for its page
+pub trait IndexKey: Ord + EncodedSize {
+    fn is_null(&self) -> bool;
+}
+
+macro_rules! impl_index_key_never_null {
+    ($($ty:ty);+$(;)?) => {
+        $(impl IndexKey for $ty {
+            fn is_null(&self) -> bool {
+                false
+            }
+        })+
+    };
+}
+
+impl_index_key_never_null! {
+    u8;
+    u16;
+    u32;
+    u64;
+    u128;
+    i8;
+    i16;
+    i32;
+    i64;
+    i128;
+    String;
+}
+
+impl<T: Ord> IndexKey for Option<T>
+where
+    Option<T>: EncodedSize,
+{
+    fn is_null(&self) -> bool {
+        self.is_none()
+    }
+}
+
+impl IndexKey for Value {
+    fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+}
+
+/// A composite key is never NULL, its NULL fields are ordered by [`Ord`]
+impl<T: Ord + EncodedSize> IndexKey for Vec<T> {
+    fn is_null(&self) -> bool {
+        false
+    }
+}
+
+/// The number of leaves loaded ahead when scanning a range
+pub const DEFAULT_READ_AHEAD: usize = 2;
+
+/// The max number of entries in a node of a [`PAGE_SIZE`] page when it's not given
+pub const DEFAULT_MAX_SIZE: usize = 64;
+
+/// How many times [`Index::reindex`] restarts for concurrent writes before it latches the root
+pub const REINDEX_RETRIES: usize = 3;
+
+/// The max number of entries in a node when it's not given, it grows with the page size
+pub fn default_max_size(page_size: usize) -> usize {
+    (DEFAULT_MAX_SIZE * page_size / PAGE_SIZE).max(4)
+}
+
+/// The options to construct an [`Index`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexOptions {
+    max_size: Option<usize>,
+    order: SortOrder,
+    nulls: NullOrder,
+    read_ahead: usize,
+    timeout: Option<Duration>,
+    unique: bool,
+}
+
+impl Default for IndexOptions {
+    fn default() -> Self {
+        Self {
+            max_size: None,
+            order: SortOrder::default(),
+            nulls: NullOrder::default(),
+            read_ahead: DEFAULT_READ_AHEAD,
+            timeout: None,
+            unique: true,
+        }
+    }
+}
+
+impl IndexOptions {
+    /// The max number of entries in a node, a node splits when it grows beyond it.
+    /// It's derived from the page size of the buffer pool if not given, see [`default_max_size`]
+    pub fn with_max_size(mut self, max_size: usize) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// See [`Index::new_with_order`]
+    pub fn with_order(mut self, order: SortOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// See [`NullOrder`]
+    pub fn with_nulls(mut self, nulls: NullOrder) -> Self {
+        self.nulls = nulls;
+        self
+    }
+
+    /// See [`Index::with_read_ahead`]
+    pub fn with_read_ahead(mut self, read_ahead: usize) -> Self {
+        self.read_ahead = read_ahead;
+        self
+    }
+
+    /// See [`Index::with_timeout`]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Whether an insert of an equal key overwrites the entry, it's true by default.
+    /// A non-unique index keeps every record id of a key, see [`Index::delete_entry`]
+    pub fn with_unique(mut self, unique: bool) -> Self {
+        self.unique = unique;
+        self
+    }
+}
 
 /// A concurrency BPlus Tree, use [`Latch`] to lock every node.
 pub struct Index<K> {
     buffer_pool: Arc<BufferPoolManager>,
     root: RwLock<PageId>,
     max_size: usize,
+    order: SortOrder,
+    nulls: NullOrder,
+    read_ahead: usize,
+    timeout: Option<Duration>,
+    unique: bool,
+    /// Bumped whenever a write lands, so [`Index::reindex`] detects concurrent writes
+    version: AtomicU64,
+    /// The writers which latched the root and haven't landed their writes yet
+    writers: AtomicU64,
+    /// Notified when the last writer lands its write
+    idle: Notify,
     _data: PhantomData<K>,
 }
 
 impl<'a, K> Index<K> {
     pub async fn new(buffer_pool: Arc<BufferPoolManager>, max_size: usize) -> StorageResult<Self>
     where
-        K: Encoder,
+        K: Encoder,
+    {
+        Self::new_with_order(buffer_pool, max_size, SortOrder::Ascending).await
+    }
+
+    /// Create an index whose keys are stored in the given order,
+    /// so scanning a [`SortOrder::Descending`] index yields the largest key first.
+    pub async fn new_with_order(
+        buffer_pool: Arc<BufferPoolManager>,
+        max_size: usize,
+        order: SortOrder,
+    ) -> StorageResult<Self>
+    where
+        K: Encoder,
+    {
+        Self::with_options(
+            buffer_pool,
+            IndexOptions::default()
+                .with_max_size(max_size)
+                .with_order(order),
+        )
+        .await
+    }
+
+    pub async fn with_options(
+        buffer_pool: Arc<BufferPoolManager>,
+        options: IndexOptions,
+    ) -> StorageResult<Self>
+    where
+        K: Encoder,
+    {
+        let IndexOptions {
+            max_size,
+            order,
+            nulls,
+            read_ahead,
+            timeout,
+            unique,
+        } = options;
+        let max_size = max_size.unwrap_or_else(|| default_max_size(buffer_pool.page_size()));
+        let mut node = Node::Leaf(Leaf::<K> {
+            header: Header {
+                size: 0,
+                max_size,
+                parent: None,
+                page_id: 0,
+                next: None,
+                prev: None,
+            },
+            kv: Vec::new(),
+        });
+        buffer_pool.new_page_node(&mut node).await?;
+        Ok(Self {
+            buffer_pool,
+            root: RwLock::new(node.page_id()),
+            max_size,
+            order,
+            nulls,
+            read_ahead,
+            timeout,
+            unique,
+            version: AtomicU64::new(0),
+            writers: AtomicU64::new(0),
+            idle: Notify::new(),
+            _data: Default::default(),
+        })
+    }
+
+    /// Open the index whose tree is at the root page, like [`Index::new`] it's ascending.
+    /// See [`Index::root_page_id`]
+    pub async fn open(
+        buffer_pool: Arc<BufferPoolManager>,
+        root_page_id: PageId,
+        max_size: usize,
+    ) -> StorageResult<Self>
+    where
+        K: Decoder,
+    {
+        Self::open_with_options(
+            buffer_pool,
+            root_page_id,
+            IndexOptions::default().with_max_size(max_size),
+        )
+        .await
+    }
+
+    /// Open the index whose tree is at the root page, the options must be the ones it was
+    /// created with. The root page is decoded first, so a page which isn't a root fails it
+    pub async fn open_with_options(
+        buffer_pool: Arc<BufferPoolManager>,
+        root_page_id: PageId,
+        options: IndexOptions,
+    ) -> StorageResult<Self>
+    where
+        K: Decoder,
+    {
+        let (_, mut node) = buffer_pool.fetch_page_node::<K>(root_page_id).await?;
+        if node.parent().is_some() {
+            return Err(Error::Value(format!(
+                "page {} isn't the root of an index",
+                root_page_id
+            )));
+        }
+        let IndexOptions {
+            max_size,
+            order,
+            nulls,
+            read_ahead,
+            timeout,
+            unique,
+        } = options;
+        // the nodes keep the max size the index is created with
+        let max_size = max_size.unwrap_or_else(|| node.max_size());
+        Ok(Self {
+            buffer_pool,
+            root: RwLock::new(root_page_id),
+            max_size,
+            order,
+            nulls,
+            read_ahead,
+            timeout,
+            unique,
+            version: AtomicU64::new(0),
+            writers: AtomicU64::new(0),
+            idle: Notify::new(),
+            _data: Default::default(),
+        })
+    }
+
+    /// The page id of the root, which changes as the tree grows or shrinks.
+    /// Persist it after the writes to [`Index::open`] the index again
+    pub async fn root_page_id(&self) -> PageId {
+        *self.root.read().await
+    }
+
+    /// Build an index from entries sorted in the order of index, which is much faster than
+    /// inserting them one by one. See [`Index::bulk_load_with_options`]
+    pub async fn bulk_load<I>(
+        buffer_pool: Arc<BufferPoolManager>,
+        max_size: usize,
+        entries: I,
+    ) -> StorageResult<Self>
+    where
+        K: Decoder + Encoder + IndexKey + Default + Clone,
+        I: IntoIterator<Item = (K, RecordId)>,
+    {
+        Self::bulk_load_with_options(
+            buffer_pool,
+            IndexOptions::default().with_max_size(max_size),
+            entries,
+        )
+        .await
+    }
+
+    /// Build an index from entries sorted in the order of index, see [`Index::bulk_load_stream`]
+    pub async fn bulk_load_with_options<I>(
+        buffer_pool: Arc<BufferPoolManager>,
+        options: IndexOptions,
+        entries: I,
+    ) -> StorageResult<Self>
+    where
+        K: Decoder + Encoder + IndexKey + Default + Clone,
+        I: IntoIterator<Item = (K, RecordId)>,
+    {
+        Self::bulk_load_stream(
+            buffer_pool,
+            options,
+            futures::stream::iter(entries.into_iter().map(Ok)),
+        )
+        .await
+    }
+
+    /// Build an index from a stream of entries sorted in the order of index, only a leaf of
+    /// them is kept in memory. The leaves are filled up to `max_size - 1` entries or their page,
+    /// and the internal nodes are built on them level by level, the last two nodes of a level
+    /// share their entries so that neither underflows.
+    /// The keys must be strictly increasing, or only not decreasing if the index isn't unique,
+    /// else the pages written so far are freed and it fails
+    pub async fn bulk_load_stream<S>(
+        buffer_pool: Arc<BufferPoolManager>,
+        options: IndexOptions,
+        entries: S,
+    ) -> StorageResult<Self>
+    where
+        K: Decoder + Encoder + IndexKey + Default + Clone,
+        S: Stream<Item = StorageResult<(K, RecordId)>>,
+    {
+        let mut index = Self::with_options(buffer_pool, options).await?;
+        let empty = *index.root.get_mut();
+        // the first key and the page id of every node in the level
+        let mut level = Vec::new();
+        if let Err(err) = index.bulk_leaves(entries, &mut level).await {
+            for (_, page_id) in level {
+                index.buffer_pool.delete_page(page_id).await?;
+            }
+            index.free_tree(empty).await?;
+            return Err(err);
+        }
+        if level.is_empty() {
+            return Ok(index);
+        }
+        while level.len() > 1 {
+            level = index.bulk_internals(level).await?;
+        }
+        *index.root.get_mut() = level[0].1;
+        index.free_tree(empty).await?;
+        Ok(index)
+    }
+
+    /// Write the entries into the leaves of [`Index::bulk_load_stream`], the first key and
+    /// the page id of a leaf are pushed to the level once its page is allocated
+    async fn bulk_leaves<S>(&self, entries: S, level: &mut Vec<(K, PageId)>) -> StorageResult<()>
+    where
+        K: Decoder + Encoder + IndexKey + Clone,
+        S: Stream<Item = StorageResult<(K, RecordId)>>,
+    {
+        let mut entries = std::pin::pin!(entries);
+        // a leaf is written back once the page of the next leaf is known
+        let mut previous: Option<(OwnedPageDataWriteGuard, Leaf<K>)> = None;
+        let mut kv: Vec<(K, RecordId)> = Vec::new();
+        while let Some((key, record_id)) = entries.try_next().await? {
+            if key.encoded_size() > self.max_key_size() {
+                return Err(Error::Value(format!(
+                    "key of {} bytes exceeds the max key size {}",
+                    key.encoded_size(),
+                    self.max_key_size()
+                )));
+            }
+            let last = kv
+                .last()
+                .or_else(|| previous.as_ref().and_then(|(_, leaf)| leaf.kv.last()));
+            let sorted = last.is_none_or(|(last, _)| match self.compare(last, &key) {
+                Ordering::Less => true,
+                Ordering::Equal => !self.unique,
+                Ordering::Greater => false,
+            });
+            if !sorted {
+                return Err(Error::Value(
+                    "the keys of bulk load aren't in the order of index".to_string(),
+                ));
+            }
+            kv.push((key, record_id));
+            if kv.len() < self.max_size && self.entries_fit(&kv) {
+                continue;
+            }
+            // the entry which doesn't fit starts the next leaf
+            let next = kv.pop().unwrap();
+            let full = std::mem::replace(&mut kv, vec![next]);
+            self.bulk_leaf(full, &mut previous, level).await?;
+        }
+        if kv.is_empty() {
+            return Ok(());
+        }
+        if let Some((_, leaf)) = previous.as_mut() {
+            if kv.len() < self.max_size / 2 {
+                self.share(&mut leaf.kv, &mut kv);
+                leaf.header.size = leaf.kv.len();
+            }
+        }
+        self.bulk_leaf(kv, &mut previous, level).await?;
+        if let Some((mut latch, leaf)) = previous {
+            latch.write_node_back(&Node::Leaf(leaf))?;
+        }
+        Ok(())
+    }
+
+    /// Allocate the page of a leaf, link it after the previous leaf and write that one back
+    async fn bulk_leaf(
+        &self,
+        kv: Vec<(K, RecordId)>,
+        previous: &mut Option<(OwnedPageDataWriteGuard, Leaf<K>)>,
+        level: &mut Vec<(K, PageId)>,
+    ) -> StorageResult<()>
+    where
+        K: Decoder + Encoder + Clone,
+    {
+        let first = kv[0].0.clone();
+        let mut node = Node::Leaf(Leaf {
+            header: Header {
+                size: kv.len(),
+                max_size: self.max_size,
+                parent: None,
+                page_id: 0,
+                next: None,
+                prev: None,
+            },
+            kv,
+        });
+        let latch = self.buffer_pool.new_page_write_owned(&mut node).await?;
+        level.push((first, node.page_id()));
+        let mut leaf = node.assume_leaf();
+        if let Some((mut previous_latch, mut previous_leaf)) = previous.take() {
+            previous_leaf.header.next = Some(leaf.page_id());
+            leaf.header.prev = Some(previous_leaf.page_id());
+            previous_latch.write_node_back(&Node::Leaf(previous_leaf))?;
+        }
+        *previous = Some((latch, leaf));
+        Ok(())
+    }
+
+    /// Build the level of internal nodes above the level of [`Index::bulk_load_stream`],
+    /// a node takes up to `max_size` children or its page
+    async fn bulk_internals(&self, level: Vec<(K, PageId)>) -> StorageResult<Vec<(K, PageId)>>
+    where
+        K: Decoder + Encoder + IndexKey + Default,
+    {
+        let mut nodes = Vec::new();
+        let mut kv = Vec::new();
+        for child in level {
+            kv.push(child);
+            if kv.len() <= self.max_size && self.entries_fit(&kv) {
+                continue;
+            }
+            let next = kv.pop().unwrap();
+            nodes.push(std::mem::replace(&mut kv, vec![next]));
+        }
+        if let Some(previous) = nodes.last_mut() {
+            if kv.len() < self.max_size / 2 {
+                self.share(previous, &mut kv);
+            }
+        }
+        nodes.push(kv);
+        let mut parents = Vec::new();
+        for mut kv in nodes {
+            // the first key of internal node is a placeholder
+            let first = std::mem::take(&mut kv[0].0);
+            let mut node = Node::Internal(Internal {
+                header: Header {
+                    size: kv.len() - 1,
+                    max_size: self.max_size,
+                    parent: None,
+                    page_id: 0,
+                    next: None,
+                    prev: None,
+                },
+                kv,
+            });
+            let mut latch = self.buffer_pool.new_page_write_owned(&mut node).await?;
+            for (_, child) in node.assume_internal_ref().kv.iter() {
+                let mut child_latch = self.buffer_pool.fetch_page_write_owned(*child).await?;
+                let mut child_node = child_latch.node::<K>()?;
+                child_node.set_parent(node.page_id());
+                child_latch.write_node_back(&child_node)?;
+            }
+            latch.write_node_back(&node)?;
+            parents.push((first, node.page_id()));
+        }
+        Ok(parents)
+    }
+
+    /// Whether a node of the entries fits in a page, the links of its header are counted
+    fn entries_fit<V: EncodedSize>(&self, kv: &[(K, V)]) -> bool
+    where
+        K: EncodedSize,
+    {
+        let header = Header {
+            size: kv.len(),
+            max_size: self.max_size,
+            parent: Some(0),
+            page_id: 0,
+            next: Some(0),
+            prev: Some(0),
+        };
+        let size = kv
+            .iter()
+            .fold(size_of::<u8>() + header.encoded_size(), |size, (k, v)| {
+                size + k.encoded_size() + v.encoded_size()
+            });
+        size < self.capacity()
+    }
+
+    /// Move the entries at the end of the previous node to the front of the last one, until
+    /// they hold about as many entries or the last one is full
+    fn share<V: EncodedSize>(&self, previous: &mut Vec<(K, V)>, last: &mut Vec<(K, V)>)
+    where
+        K: EncodedSize,
+    {
+        let half = (previous.len() + last.len()) / 2;
+        while last.len() < half {
+            let Some(entry) = previous.pop() else {
+                break;
+            };
+            last.insert(0, entry);
+            if !self.entries_fit(last) {
+                previous.push(last.remove(0));
+                break;
+            }
+        }
+    }
+
+    /// Set how many leaves are loaded into the buffer pool ahead of a range scan, 0 disables it
+    pub fn with_read_ahead(mut self, read_ahead: usize) -> Self {
+        self.read_ahead = read_ahead;
+        self
+    }
+
+    /// Bound how long an operation waits for the latches from the root to the leaf,
+    /// it fails with [`Error::Timeout`] instead of waiting forever
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn order(&self) -> SortOrder {
+        self.order
+    }
+
+    pub fn nulls(&self) -> NullOrder {
+        self.nulls
+    }
+
+    pub fn unique(&self) -> bool {
+        self.unique
+    }
+
+    /// The bytes of a page which a node is encoded into
+    fn capacity(&self) -> usize {
+        self.buffer_pool.page_size() - CHECKSUM_SIZE
+    }
+
+    /// Whether the node fits in a page after the type byte of node
+    fn fits(&self, node: &impl EncodedSize) -> bool {
+        node.encoded_size() < self.capacity()
+    }
+
+    /// The max encoded size of a key, a longer key fails to insert. It's a quarter of the page,
+    /// so a node split in the middle of its bytes has room for the key moved up from a child
+    pub fn max_key_size(&self) -> usize {
+        self.capacity() / 4
+    }
+
+    /// The max encoded size of an entry of a leaf or an internal node
+    fn max_entry_size(&self) -> usize {
+        self.max_key_size() + std::mem::size_of::<PageId>() + std::mem::size_of::<u32>()
+    }
+
+    /// Compare keys in the order of index, every lookup and write of the index goes through it
+    fn compare(&self, a: &K, b: &K) -> Ordering
+    where
+        K: IndexKey,
+    {
+        match (a.is_null(), b.is_null(), self.nulls) {
+            (true, true, _) => Ordering::Equal,
+            (true, false, NullOrder::First) | (false, true, NullOrder::Last) => Ordering::Less,
+            (true, false, NullOrder::Last) | (false, true, NullOrder::First) => Ordering::Greater,
+            (false, false, _) => self.order.compare(a, b),
+        }
+    }
+
+    /// The leaf to scan the keys from the bound. The equal keys of a non-unique index may
+    /// span several leaves, so an included key is sought in the leftmost leaf which may hold it
+    fn seek_start<'k>(&self, bound: Bound<&'k K>) -> KeyCondition<'k, &'k K> {
+        match bound {
+            Bound::Included(key) if !self.unique => KeyCondition::First(key),
+            Bound::Included(key) | Bound::Excluded(key) => KeyCondition::Equal(key),
+            Bound::Unbounded => KeyCondition::Min,
+        }
+    }
+
+    /// The leaf to scan the keys backward from the bound, like [`Index::seek_start`]
+    /// an included key is sought in the rightmost leaf which may hold it
+    fn seek_end<'k>(&self, bound: Bound<&'k K>) -> KeyCondition<'k, &'k K> {
+        match bound {
+            Bound::Included(key) if !self.unique => KeyCondition::Last(key),
+            Bound::Included(key) | Bound::Excluded(key) => KeyCondition::Equal(key),
+            Bound::Unbounded => KeyCondition::Max,
+        }
+    }
+
+    /// The index of the first entry of the leaf which doesn't sort before the start bound
+    fn start_index(&self, leaf: &Leaf<K>, bound: Bound<&K>) -> usize
+    where
+        K: IndexKey,
+    {
+        match bound {
+            Bound::Included(key) => leaf
+                .kv
+                .partition_point(|(k, _)| self.compare(k, key) == Ordering::Less),
+            Bound::Excluded(key) => leaf
+                .kv
+                .partition_point(|(k, _)| self.compare(k, key) != Ordering::Greater),
+            Bound::Unbounded => 0,
+        }
+    }
+
+    /// The index after the last entry of the leaf which doesn't sort after the end bound
+    fn end_index(&self, leaf: &Leaf<K>, bound: Bound<&K>) -> usize
+    where
+        K: IndexKey,
+    {
+        match bound {
+            Bound::Included(key) => leaf
+                .kv
+                .partition_point(|(k, _)| self.compare(k, key) != Ordering::Greater),
+            Bound::Excluded(key) => leaf
+                .kv
+                .partition_point(|(k, _)| self.compare(k, key) == Ordering::Less),
+            Bound::Unbounded => leaf.kv.len(),
+        }
+    }
+
+    /// Wait for the latch, no longer than the timeout of the index
+    async fn latch<F: Future>(&self, latch: F) -> StorageResult<F::Output> {
+        match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, latch)
+                .await
+                .map_err(|_| Error::Timeout("latch")),
+            None => Ok(latch.await),
+        }
+    }
+
+    /// Returns the first entry in the order of index
+    pub async fn first(&self) -> StorageResult<Option<(K, RecordId)>>
+    where
+        K: Decoder + Encoder + IndexKey + Clone,
+    {
+        let mut route = Route::new(RouteOption::default());
+        let page_id = self.find_route(KeyCondition::Min, &mut route).await?;
+        let leaf = route
+            .nodes
+            .shift_remove(&page_id)
+            .unwrap()
+            .latch
+            .node::<K>()?
+            .assume_leaf();
+        Ok(leaf.kv.first().cloned())
+    }
+
+    /// Returns the last entry in the order of index
+    pub async fn last(&self) -> StorageResult<Option<(K, RecordId)>>
+    where
+        K: Decoder + Encoder + IndexKey + Clone,
+    {
+        let mut route = Route::new(RouteOption::default());
+        let page_id = self.find_route(KeyCondition::Max, &mut route).await?;
+        let leaf = route
+            .nodes
+            .shift_remove(&page_id)
+            .unwrap()
+            .latch
+            .node::<K>()?
+            .assume_leaf();
+        Ok(leaf.kv.last().cloned())
+    }
+
+    /// Returns the record id of the key, any one of them if the index isn't unique
+    pub async fn search(&self, key: &K) -> StorageResult<Option<RecordId>>
+    where
+        K: Decoder + Encoder + IndexKey,
+    {
+        if !self.unique {
+            // the equal keys may begin in a leaf after the one which the key routes to
+            return Ok(self.multi_search(std::slice::from_ref(key)).await?[0]);
+        }
+        let mut route = Route::new(RouteOption::default());
+        let page_id = self
+            .find_route(KeyCondition::Equal(key), &mut route)
+            .await?;
+        match route.nodes.get(&page_id).unwrap().latch {
+            Latch::Read(ref guard) => {
+                let leaf = guard.node::<K>()?.assume_leaf();
+                Ok(leaf.search_by(key, |a, b| self.compare(a, b)))
+            }
+            Latch::Write(ref _guard) => {
+                unreachable!()
+            }
+        }
+    }
+
+    /// Search every key in one pass, returns the record ids in the order of keys.
+    /// The keys are searched in the order of index, so the tree is descended once and the
+    /// leaves are walked from the first key to the last. Like a scan, it descends again if
+    /// the next leaf is latched by a writer.
+    pub async fn multi_search(&self, keys: &[K]) -> StorageResult<Vec<Option<RecordId>>>
+    where
+        K: Decoder + Encoder + IndexKey,
+    {
+        let mut order = (0..keys.len()).collect::<Vec<_>>();
+        order.sort_by(|a, b| self.compare(&keys[*a], &keys[*b]));
+        let mut result = vec![None; keys.len()];
+        let mut current: Option<(OwnedPageDataReadGuard, Leaf<K>)> = None;
+        for position in order {
+            let key = &keys[position];
+            loop {
+                let Some((_, leaf)) = &current else {
+                    let mut route = Route::new(RouteOption::default());
+                    let page_id = self
+                        .find_route(self.seek_start(Bound::Included(key)), &mut route)
+                        .await?;
+                    let latch = route
+                        .nodes
+                        .shift_remove(&page_id)
+                        .unwrap()
+                        .latch
+                        .assume_read();
+                    let leaf = latch.node::<K>()?.assume_leaf();
+                    current = Some((latch, leaf));
+                    // the equal keys of a non-unique index may begin in a later leaf
+                    if self.unique {
+                        break;
+                    }
+                    continue;
+                };
+                let beyond = matches!(
+                    leaf.kv.last(),
+                    Some((last, _)) if self.compare(key, last) == Ordering::Greater
+                );
+                let next_id = match leaf.next() {
+                    Some(next_id) if beyond => next_id,
+                    _ => break,
+                };
+                // the latch of current leaf is held until the next leaf is latched
+                match self.buffer_pool.try_fetch_page_read_owned(next_id).await {
+                    Ok(latch) => {
+                        let leaf = latch.node::<K>()?.assume_leaf();
+                        current = Some((latch, leaf));
+                    }
+                    Err(buffer::Error::TryLock(_)) => current = None,
+                    Err(err) => return Err(err.into()),
+                }
+            }
+            let (_, leaf) = current.as_ref().unwrap();
+            result[position] = leaf.search_by(key, |a, b| self.compare(a, b));
+        }
+        Ok(result)
+    }
+
+    /// Whether the key is in the index, use it instead of [`Index::search`] when the record isn't needed
+    pub async fn contains(&self, key: &K) -> StorageResult<bool>
+    where
+        K: Decoder + Encoder + IndexKey,
+    {
+        if !self.unique {
+            return Ok(self.search(key).await?.is_some());
+        }
+        let mut route = Route::new(RouteOption::default());
+        let page_id = self
+            .find_route(KeyCondition::Equal(key), &mut route)
+            .await?;
+        let leaf = route
+            .nodes
+            .get(&page_id)
+            .unwrap()
+            .latch
+            .assume_read_ref()
+            .node::<K>()?
+            .assume_leaf();
+        Ok(leaf
+            .kv
+            .binary_search_by(|(k, _)| self.compare(k, key))
+            .is_ok())
+    }
+
+    pub async fn search_range<'r, R>(&self, range: R) -> StorageResult<Vec<RecordId>>
+    where
+        K: Decoder + Encoder + IndexKey + Clone + 'r,
+        R: RangeBounds<&'r K>,
+    {
+        Ok(self
+            .range_entries(range)
+            .await?
+            .into_iter()
+            .map(|(_, record_id)| record_id)
+            .collect())
+    }
+
+    /// Returns every record id of the key, in the order they were inserted into a non-unique index
+    pub async fn search_all(&self, key: &K) -> StorageResult<Vec<RecordId>>
+    where
+        K: Decoder + Encoder + IndexKey + Clone,
+    {
+        self.search_range(key..=key).await
+    }
+
+    /// Like [`Index::search_range`], but the search stops at the first key in range which
+    /// `keep` rejects, so the range can end at a key which can't be written as a bound
+    pub async fn search_range_while<'r, R>(
+        &self,
+        range: R,
+        keep: impl Fn(&K) -> bool,
+    ) -> StorageResult<Vec<RecordId>>
+    where
+        K: Decoder + Encoder + IndexKey + Clone + 'r,
+        R: RangeBounds<&'r K>,
+    {
+        Ok(self
+            .range_entries_while(range, keep)
+            .await?
+            .into_iter()
+            .map(|(_, record_id)| record_id)
+            .collect())
+    }
+
+    /// Like [`Index::search_range`], but the record ids are in the reverse order of index,
+    /// so an ascending index returns them in descending key order
+    pub async fn search_range_rev<'r, R>(&self, range: R) -> StorageResult<Vec<RecordId>>
+    where
+        K: Decoder + Encoder + IndexKey + Clone + 'r,
+        R: RangeBounds<&'r K>,
+    {
+        Ok(self
+            .range_entries_rev(range)
+            .await?
+            .into_iter()
+            .map(|(_, record_id)| record_id)
+            .collect())
+    }
+
+    /// Count the keys in range, like `search_range(range).len()` without collecting the
+    /// record ids. The keys of a leaf in range are counted by their positions, so a leaf
+    /// wholly in range is counted by its size. Like [`Index::range_entries`], the scan seeks
+    /// again from the root after the last counted key if the next leaf is latched
+    pub async fn count_range<'r, R>(&self, range: R) -> StorageResult<usize>
+    where
+        K: Decoder + Encoder + IndexKey + Clone + 'r,
+        R: RangeBounds<&'r K>,
+    {
+        let (start_bound, end_bound) = self.order.bounds(&range);
+        let (start_bound, end_bound) = (start_bound.map(|key| *key), end_bound.map(|key| *key));
+        if let (
+            Bound::Included(start) | Bound::Excluded(start),
+            Bound::Included(end) | Bound::Excluded(end),
+        ) = (start_bound, end_bound)
+        {
+            // an empty range, it ends before it starts
+            if self.compare(start, end) == Ordering::Greater {
+                return Ok(0);
+            }
+        }
+        let mut count = 0;
+        // the last counted key, and its counted record ids if the index isn't unique
+        let mut resume: Option<(K, Vec<RecordId>)> = None;
+        'seek: loop {
+            let from = resume.clone();
+            let (start_bound, skip) = match &from {
+                Some((key, _)) if self.unique => (Bound::Excluded(key), &[][..]),
+                Some((key, skip)) => (Bound::Included(key), skip.as_slice()),
+                None => (start_bound, &[][..]),
+            };
+            let mut route = Route::new(RouteOption::default());
+            let page_id = self
+                .find_route(self.seek_start(start_bound), &mut route)
+                .await?;
+            let mut latch = route
+                .nodes
+                .shift_remove(&page_id)
+                .unwrap()
+                .latch
+                .assume_read();
+            drop(route);
+            loop {
+                let leaf = latch.node::<K>()?.assume_leaf();
+                let start_index = self.start_index(&leaf, start_bound);
+                let end_index = self.end_index(&leaf, end_bound).max(start_index);
+                let counted = &leaf.kv[start_index..end_index];
+                count += counted.len();
+                if !skip.is_empty() {
+                    count -= counted
+                        .iter()
+                        .filter(|(k, v)| {
+                            skip.contains(v)
+                                && matches!(start_bound, Bound::Included(key) if self.compare(k, key) == Ordering::Equal)
+                        })
+                        .count();
+                }
+                if let Some((last, _)) = counted.last() {
+                    let mut skip = match resume.take() {
+                        Some((key, skip)) if self.compare(&key, last) == Ordering::Equal => skip,
+                        _ => Vec::new(),
+                    };
+                    if !self.unique {
+                        skip.extend(
+                            counted
+                                .iter()
+                                .rev()
+                                .take_while(|(k, _)| self.compare(k, last) == Ordering::Equal)
+                                .map(|(_, record_id)| *record_id),
+                        );
+                    }
+                    resume = Some((last.clone(), skip));
+                }
+                let next_id = match leaf.next() {
+                    Some(next_id) if end_index == leaf.kv.len() => next_id,
+                    _ => return Ok(count),
+                };
+                latch = match self.buffer_pool.try_fetch_page_read_owned(next_id).await {
+                    Ok(latch) => latch,
+                    Err(buffer::Error::TryLock(_)) => continue 'seek,
+                    Err(err) => return Err(err.into()),
+                };
+            }
+        }
+    }
+
+    /// The number of keys in the index
+    pub async fn len(&self) -> StorageResult<usize>
+    where
+        K: Decoder + Encoder + IndexKey + Clone,
+    {
+        self.count_range::<RangeFull>(..).await
+    }
+
+    /// Like [`Index::search_range`], but the record ids are read leaf by leaf as the stream is
+    /// polled. No latch is held while the stream is suspended, the next leaf is sought again
+    /// from the root after the last key it yielded, so the stream can be dropped at any time.
+    pub fn scan_stream<R>(&self, range: R) -> impl Stream<Item = StorageResult<RecordId>> + '_
+    where
+        K: Decoder + Encoder + IndexKey + Clone,
+        R: RangeBounds<K>,
+    {
+        self.entry_stream(range).map_ok(|(_, record_id)| record_id)
+    }
+
+    /// Like [`Index::scan_stream`], but the key-value pairs are yielded
+    fn entry_stream<R>(&self, range: R) -> impl Stream<Item = StorageResult<(K, RecordId)>> + '_
+    where
+        K: Decoder + Encoder + IndexKey + Clone,
+        R: RangeBounds<K>,
+    {
+        let range = (range.start_bound().cloned(), range.end_bound().cloned());
+        try_stream! {
+            let (start_bound, end_bound) = self.order.bounds(&range);
+            let empty = match (start_bound, end_bound) {
+                (
+                    Bound::Included(start) | Bound::Excluded(start),
+                    Bound::Included(end) | Bound::Excluded(end),
+                ) => self.compare(start, end) == Ordering::Greater,
+                _ => false,
+            };
+            // the last key yielded, and the record ids yielded of it if the index isn't unique
+            let mut resume: Option<(K, Vec<RecordId>)> = None;
+            let mut done = empty;
+            while !done {
+                let (start_bound, skip) = match &resume {
+                    Some((key, _)) if self.unique => (Bound::Excluded(key), &[][..]),
+                    Some((key, skip)) => (Bound::Included(key), skip.as_slice()),
+                    None => (start_bound, &[][..]),
+                };
+                let entries;
+                (entries, done) = self.leaf_entries(start_bound, end_bound, skip).await?;
+                if let Some((last, _)) = entries.last() {
+                    let mut skip = match resume.take() {
+                        Some((key, skip)) if self.compare(&key, last) == Ordering::Equal => skip,
+                        _ => Vec::new(),
+                    };
+                    if !self.unique {
+                        skip.extend(
+                            entries
+                                .iter()
+                                .rev()
+                                .take_while(|(k, _)| self.compare(k, last) == Ordering::Equal)
+                                .map(|(_, record_id)| *record_id),
+                        );
+                    }
+                    resume = Some((last.clone(), skip));
+                }
+                for entry in entries {
+                    yield entry;
+                }
+            }
+        }
+    }
+
+    /// The entries in range of the first leaf which has any, and whether the range ends in it.
+    /// The entries of the start key whose record ids are in `skip` are left out.
+    /// Like [`Index::range_entries`], the next leaf is only tried, and if it's latched the
+    /// leaf is sought again from the root. Every latch is released when it returns
+    async fn leaf_entries(
+        &self,
+        start_bound: Bound<&K>,
+        end_bound: Bound<&K>,
+        skip: &[RecordId],
+    ) -> StorageResult<(Vec<(K, RecordId)>, bool)>
+    where
+        K: Decoder + Encoder + IndexKey + Clone,
+    {
+        'seek: loop {
+            let mut route = Route::new(RouteOption::default());
+            let page_id = self
+                .find_route(self.seek_start(start_bound), &mut route)
+                .await?;
+            let mut latch = route
+                .nodes
+                .shift_remove(&page_id)
+                .unwrap()
+                .latch
+                .assume_read();
+            drop(route);
+            loop {
+                let leaf = latch.node::<K>()?.assume_leaf();
+                let start_index = self.start_index(&leaf, start_bound);
+                let mut entries = Vec::new();
+                for (k, v) in leaf.kv[start_index..].iter() {
+                    let beyond_end = match end_bound {
+                        Bound::Included(key) => self.compare(k, key) == Ordering::Greater,
+                        Bound::Excluded(key) => self.compare(k, key) != Ordering::Less,
+                        Bound::Unbounded => false,
+                    };
+                    if beyond_end {
+                        return Ok((entries, true));
+                    }
+                    let skipped = skip.contains(v)
+                        && matches!(start_bound, Bound::Included(key) if self.compare(k, key) == Ordering::Equal);
+                    if skipped {
+                        continue;
+                    }
+                    entries.push((k.clone(), *v));
+                }
+                let Some(next_id) = leaf.next() else {
+                    return Ok((entries, true));
+                };
+                if !entries.is_empty() {
+                    return Ok((entries, false));
+                }
+                latch = match self.buffer_pool.try_fetch_page_read_owned(next_id).await {
+                    Ok(latch) => latch,
+                    Err(buffer::Error::TryLock(_)) => continue 'seek,
+                    Err(err) => return Err(err.into()),
+                };
+            }
+        }
+    }
+
+    /// Returns every key-value pair, in the order of index
+    pub async fn entries(&self) -> StorageResult<Vec<(K, RecordId)>>
+    where
+        K: Decoder + Encoder + IndexKey + Clone,
     {
-        let mut node = Node::Leaf(Leaf::<K> {
-            header: Header {
-                size: 0,
-                max_size,
-                parent: None,
-                page_id: 0,
-                next: None,
-                prev: None,
-            },
-            kv: Vec::new(),
-        });
-        buffer_pool.new_page_node(&mut node).await?;
-        Ok(Self {
-            buffer_pool,
-            root: RwLock::new(node.page_id()),
-            max_size,
-            _data: Default::default(),
-        })
+        self.range_entries(..).await
     }
-    pub async fn search(&self, key: &K) -> StorageResult<Option<RecordId>>
+
+    /// Returns the key-value pairs in range, in the order of index.
+    ///
+    /// The scan holds the read latch of a leaf until the latch of the next leaf is taken, so a
+    /// writer can't split or merge the leaves around it. Writers latch a leaf and then either of
+    /// its neighbours, so the next leaf is only tried, and if it's latched the scan releases its
+    /// leaf and seeks again from the root, after the last key it returned.
+    /// Every key which stays in the index during the scan is returned exactly once, a key
+    /// inserted or deleted meanwhile may or may not be returned.
+    async fn range_entries<'r, R>(&self, range: R) -> StorageResult<Vec<(K, RecordId)>>
     where
-        K: Decoder + Encoder + Ord,
+        K: Decoder + Encoder + IndexKey + Clone + 'r,
+        R: RangeBounds<&'r K>,
     {
-        let mut route = Route::new(RouteOption::default());
-        let page_id = self
-            .find_route(KeyCondition::Equal(key), &mut route)
-            .await?;
-        match route.nodes.get(&page_id).unwrap().latch {
-            Latch::Read(ref guard) => {
-                let leaf = guard.node::<K>()?.assume_leaf();
-                Ok(leaf.search(key))
-            }
-            Latch::Write(ref _guard) => {
-                unreachable!()
-            }
-        }
+        self.range_entries_while(range, |_| true).await
     }
 
-    pub async fn search_range<'r, R>(&self, range: R) -> StorageResult<Vec<RecordId>>
+    /// Like [`Index::range_entries`], but the scan stops at the first key which `keep` rejects
+    async fn range_entries_while<'r, R>(
+        &self,
+        range: R,
+        keep: impl Fn(&K) -> bool,
+    ) -> StorageResult<Vec<(K, RecordId)>>
     where
-        K: Decoder + Encoder + Ord + 'r,
+        K: Decoder + Encoder + IndexKey + Clone + 'r,
         R: RangeBounds<&'r K>,
     {
+        if let (
+            Bound::Included(start) | Bound::Excluded(start),
+            Bound::Included(end) | Bound::Excluded(end),
+        ) = self.order.bounds(&range)
+        {
+            // an empty range, it ends before it starts
+            if self.compare(start, end) == Ordering::Greater {
+                return Ok(Vec::new());
+            }
+        }
+        let mut result: Vec<(K, RecordId)> = Vec::new();
         let output = 'output: loop {
-            let mut result = Vec::new();
             let mut route = Route::new(RouteOption::default());
-            let mut excluded = Vec::with_capacity(2);
-            if let Bound::Excluded(key) = range.start_bound() {
-                excluded.push(key);
-            }
-            if let Bound::Excluded(key) = range.end_bound() {
-                excluded.push(key);
-            }
-            let page_id = match range.start_bound() {
-                Bound::Included(key) | Bound::Excluded(key) => {
-                    self.find_route(KeyCondition::Equal(key), &mut route)
-                        .await?
+            let (start_bound, end_bound) = self.order.bounds(&range);
+            let (mut start_bound, end_bound) =
+                (start_bound.map(|key| *key), end_bound.map(|key| *key));
+            // resume after the returned keys, the leaves may have changed since
+            let resume = result.last().map(|(key, _)| key.clone());
+            let resume = resume.as_ref();
+            // a non-unique index resumes at the last key, and skips its returned record ids
+            let mut skip = Vec::new();
+            if let Some(key) = resume {
+                if self.unique {
+                    start_bound = Bound::Excluded(key);
+                } else {
+                    start_bound = Bound::Included(key);
+                    skip.extend(
+                        result
+                            .iter()
+                            .rev()
+                            .take_while(|(k, _)| self.compare(k, key) == Ordering::Equal)
+                            .map(|(_, record_id)| *record_id),
+                    );
                 }
-                Bound::Unbounded => self.find_route(KeyCondition::Min, &mut route).await?,
-            };
+            }
+            let page_id = self
+                .find_route(self.seek_start(start_bound), &mut route)
+                .await?;
             let mut latch = route
                 .nodes
                 .shift_remove(&page_id)
                 .unwrap()
                 .latch
                 .assume_read();
+            let mut ahead = VecDeque::new();
             'search: loop {
                 let leaf = latch.node::<K>()?.assume_leaf();
-                let start = match range.start_bound() {
-                    Bound::Included(key) | Bound::Excluded(key) => {
-                        leaf.kv.binary_search_by(|(k, _)| k.cmp(key))
-                    }
-                    Bound::Unbounded => Ok(0),
-                };
-                let end = match range.end_bound() {
-                    Bound::Included(key) | Bound::Excluded(key) => {
-                        leaf.kv.binary_search_by(|(k, _)| k.cmp(key))
-                    }
-                    Bound::Unbounded => Ok(leaf.kv.len() - 1),
-                };
-                match (start, end) {
-                    (Ok(start_index), Ok(end_index)) => {
-                        for (k, v) in leaf.kv[start_index..=end_index].iter() {
-                            if !excluded.contains(&&k) {
-                                result.push(*v);
-                            }
-                        }
-                        if end_index < leaf.kv.len() - 1 {
-                            break 'output Ok(result);
-                        }
-                    }
-                    (Ok(start_index), Err(end_index)) => {
-                        if end_index < leaf.kv.len() {
-                            for (k, v) in leaf.kv[start_index..=end_index].iter() {
-                                if !excluded.contains(&&k) {
-                                    result.push(*v);
-                                }
-                            }
-                            break 'output Ok(result);
-                        } else {
-                            for (k, v) in leaf.kv[start_index..].iter() {
-                                if !excluded.contains(&&k) {
-                                    result.push(*v);
-                                }
-                            }
-                        }
-                    }
-                    (Err(start_index), Ok(end_index)) => {
-                        for (k, v) in leaf.kv[start_index..=end_index].iter() {
-                            if !excluded.contains(&&k) {
-                                result.push(*v);
-                            }
-                        }
-                        if end_index < leaf.kv.len() - 1 {
-                            break 'output Ok(result);
-                        }
+                self.read_ahead(&leaf, &mut ahead).await;
+                // only the root leaf of an empty tree can be empty
+                if leaf.kv.is_empty() {
+                    break 'output Ok(result);
+                }
+                // the start key may sort after every key of this leaf,
+                // then the range begins at the next leaf
+                let start_index = self.start_index(&leaf, start_bound);
+                // the range ends in this leaf unless it covers the last key
+                let end_index = self.end_index(&leaf, end_bound).max(start_index);
+                let done = end_index < leaf.kv.len();
+                for (k, v) in leaf.kv[start_index..end_index].iter() {
+                    if !keep(k) {
+                        break 'output Ok(result);
                     }
-                    (Err(start_index), Err(end_index)) => {
-                        if end_index < leaf.kv.len() {
-                            for (k, v) in leaf.kv[start_index..=end_index].iter() {
-                                if !excluded.contains(&&k) {
-                                    result.push(*v);
-                                }
-                            }
-                            break 'output Ok(result);
-                        } else if start_index < leaf.kv.len() {
-                            for (k, v) in leaf.kv[start_index..].iter() {
-                                if !excluded.contains(&&k) {
-                                    result.push(*v);
-                                }
-                            }
-                        } else {
-                            break 'output Ok(result);
-                        }
+                    let skipped = skip.contains(v)
+                        && matches!(resume, Some(key) if self.compare(k, key) == Ordering::Equal);
+                    if !skipped {
+                        result.push((k.clone(), *v));
                     }
                 }
+                if done {
+                    break 'output Ok(result);
+                }
                 match leaf.next() {
                     None => break 'output Ok(result),
                     Some(next_id) => {
@@ -179,28 +1255,609 @@ impl<'a, K> Index<K> {
         Ok(output)
     }
 
+    /// Like [`Index::range_entries`], but the leaves are walked backward from the last key in
+    /// range. The previous leaf is only tried as well, and if it's latched the scan seeks again
+    /// from the root, before the last key it returned.
+    async fn range_entries_rev<'r, R>(&self, range: R) -> StorageResult<Vec<(K, RecordId)>>
+    where
+        K: Decoder + Encoder + IndexKey + Clone + 'r,
+        R: RangeBounds<&'r K>,
+    {
+        let (start_bound, end_bound) = self.order.bounds(&range);
+        let (start_bound, end_bound) = (start_bound.map(|key| *key), end_bound.map(|key| *key));
+        if let (
+            Bound::Included(start) | Bound::Excluded(start),
+            Bound::Included(end) | Bound::Excluded(end),
+        ) = (start_bound, end_bound)
+        {
+            // an empty range, it ends before it starts
+            if self.compare(start, end) == Ordering::Greater {
+                return Ok(Vec::new());
+            }
+        }
+        let mut result: Vec<(K, RecordId)> = Vec::new();
+        'output: loop {
+            let mut route = Route::new(RouteOption::default());
+            // resume before the returned keys, the leaves may have changed since
+            let resume = result.last().map(|(key, _)| key.clone());
+            let resume = resume.as_ref();
+            // a non-unique index resumes at the last key, and skips its returned record ids
+            let mut skip = Vec::new();
+            let end_bound = match resume {
+                Some(key) if self.unique => Bound::Excluded(key),
+                Some(key) => {
+                    skip.extend(
+                        result
+                            .iter()
+                            .rev()
+                            .take_while(|(k, _)| self.compare(k, key) == Ordering::Equal)
+                            .map(|(_, record_id)| *record_id),
+                    );
+                    Bound::Included(key)
+                }
+                None => end_bound,
+            };
+            let page_id = self
+                .find_route(self.seek_end(end_bound), &mut route)
+                .await?;
+            let mut latch = route
+                .nodes
+                .shift_remove(&page_id)
+                .unwrap()
+                .latch
+                .assume_read();
+            loop {
+                let leaf = latch.node::<K>()?.assume_leaf();
+                let end_index = self.end_index(&leaf, end_bound);
+                for (k, v) in leaf.kv[..end_index].iter().rev() {
+                    let before_start = match start_bound {
+                        Bound::Included(key) => self.compare(k, key) == Ordering::Less,
+                        Bound::Excluded(key) => self.compare(k, key) != Ordering::Greater,
+                        Bound::Unbounded => false,
+                    };
+                    if before_start {
+                        return Ok(result);
+                    }
+                    let skipped = skip.contains(v)
+                        && matches!(resume, Some(key) if self.compare(k, key) == Ordering::Equal);
+                    if !skipped {
+                        result.push((k.clone(), *v));
+                    }
+                }
+                match leaf.prev() {
+                    None => return Ok(result),
+                    Some(prev_id) => {
+                        latch = match self.buffer_pool.try_fetch_page_read_owned(prev_id).await {
+                            Ok(latch) => latch,
+                            Err(buffer::Error::TryLock(_)) => continue 'output,
+                            Err(err) => return Err(err.into()),
+                        };
+                    }
+                }
+            }
+        }
+    }
+
     pub async fn insert(&self, key: K, value: RecordId) -> StorageResult<()>
     where
-        K: Decoder + Encoder + Ord + Default + Clone,
+        K: Decoder + Encoder + IndexKey + Default + Clone,
     {
+        if key.encoded_size() > self.max_key_size() {
+            return Err(Error::Value(format!(
+                "key of {} bytes exceeds the max key size {}",
+                key.encoded_size(),
+                self.max_key_size()
+            )));
+        }
         let option = RouteOption::default().with_action(RouteAction::Insert);
         let mut route = Route::new(option);
+        // a duplicate key is appended after the equal keys in the rightmost leaf of them
         let page_id = self
-            .find_route(KeyCondition::Equal(&key), &mut route)
+            .find_route(self.seek_end(Bound::Included(&key)), &mut route)
             .await?;
         self.insert_inner(page_id, route, key, value).await
     }
 
+    /// Delete the key, any one of its entries if the index isn't unique
     pub async fn delete(&self, key: &K) -> StorageResult<Option<(K, RecordId)>>
     where
-        K: Decoder + Encoder + Ord + Clone,
+        K: Decoder + Encoder + IndexKey + Clone,
     {
+        if !self.unique {
+            while let Some(record_id) = self.search(key).await? {
+                if self.delete_entry(key, record_id).await? {
+                    return Ok(Some((key.clone(), record_id)));
+                }
+            }
+            return Ok(None);
+        }
         let option = RouteOption::default().with_action(RouteAction::Delete);
         let mut route = Route::new(option);
         let page_id = self
             .find_route(KeyCondition::Equal(key), &mut route)
             .await?;
-        self.delete_inner(page_id, route, key).await
+        self.delete_inner(page_id, route, |leaf| {
+            leaf.remove_by(key, |a, b| self.compare(a, b))
+        })
+        .await
+    }
+
+    /// Delete the entry of the key and record id, the other entries of the key are kept.
+    /// Returns whether the entry was in the index
+    pub async fn delete_entry(&self, key: &K, record_id: RecordId) -> StorageResult<bool>
+    where
+        K: Decoder + Encoder + IndexKey + Clone,
+    {
+        loop {
+            let Some(path) = self.entry_path(key, record_id).await? else {
+                return Ok(false);
+            };
+            let option = RouteOption::default().with_action(RouteAction::Delete);
+            let mut route = Route::new(option);
+            let page_id = self
+                .find_route(KeyCondition::Path(key, &path), &mut route)
+                .await?;
+            // the tree may have changed since the path was found, then it's found again
+            let deleted = self
+                .delete_inner(page_id, route, |leaf| {
+                    leaf.remove_entry_by(key, record_id, |a, b| self.compare(a, b))
+                })
+                .await?;
+            if deleted.is_some() {
+                return Ok(true);
+            }
+        }
+    }
+
+    /// The pages from the leaf which holds the entry up to the root, `None` if there's no entry.
+    /// The equal keys may span several leaves, so they're scanned from the leftmost one
+    async fn entry_path(&self, key: &K, record_id: RecordId) -> StorageResult<Option<Vec<PageId>>>
+    where
+        K: Decoder + Encoder + IndexKey,
+    {
+        let leaf = 'seek: loop {
+            let mut route = Route::new(RouteOption::default());
+            let page_id = self
+                .find_route(KeyCondition::First(key), &mut route)
+                .await?;
+            let mut latch = route
+                .nodes
+                .shift_remove(&page_id)
+                .unwrap()
+                .latch
+                .assume_read();
+            drop(route);
+            loop {
+                let leaf = latch.node::<K>()?.assume_leaf();
+                let found = leaf
+                    .kv
+                    .iter()
+                    .any(|(k, v)| *v == record_id && self.compare(k, key) == Ordering::Equal);
+                if found {
+                    break 'seek leaf;
+                }
+                let beyond = matches!(
+                    leaf.kv.last(),
+                    Some((last, _)) if self.compare(last, key) == Ordering::Greater
+                );
+                let next_id = match leaf.next() {
+                    Some(next_id) if !beyond => next_id,
+                    _ => return Ok(None),
+                };
+                latch = match self.buffer_pool.try_fetch_page_read_owned(next_id).await {
+                    Ok(latch) => latch,
+                    Err(buffer::Error::TryLock(_)) => continue 'seek,
+                    Err(err) => return Err(err.into()),
+                };
+            }
+        };
+        // the ancestors are read after the leaf is released, a writer latches them first
+        let mut path = vec![leaf.page_id()];
+        let mut parent = leaf.parent();
+        while let Some(page_id) = parent {
+            path.push(page_id);
+            let (_, node) = self.buffer_pool.fetch_page_node::<K>(page_id).await?;
+            parent = node.parent();
+        }
+        Ok(Some(path))
+    }
+
+    /// Rebuild the tree into new pages, swap the root and free the pages of the old tree.
+    /// Readers keep using the old tree during the rebuild and writers only wait for the swap,
+    /// the rebuild restarts if a write lands meanwhile or is still landing when it's done.
+    /// After [`REINDEX_RETRIES`] restarts it's rebuilt once more with the root latched, once
+    /// the writers below the root have landed their writes.
+    /// Returns the number of entries.
+    pub async fn reindex(&self) -> StorageResult<usize>
+    where
+        K: Decoder + Encoder + IndexKey + Default + Clone,
+    {
+        let options = IndexOptions::default()
+            .with_max_size(self.max_size)
+            .with_order(self.order)
+            .with_nulls(self.nulls)
+            .with_unique(self.unique);
+        for _ in 0..REINDEX_RETRIES {
+            let version = self.version.load(atomic::Ordering::SeqCst);
+            // the scan stops at the last key, a writer appending after it can't keep it going
+            let end = self
+                .last()
+                .await?
+                .map_or(Bound::Unbounded, |(last, _)| Bound::Included(last));
+            let range = match self.order {
+                SortOrder::Ascending => (Bound::Unbounded, end),
+                SortOrder::Descending => (end, Bound::Unbounded),
+            };
+            let len = AtomicU64::new(0);
+            let entries = self.entry_stream(range).inspect_ok(|_| {
+                len.fetch_add(1, atomic::Ordering::Relaxed);
+            });
+            let rebuilt =
+                Index::<K>::bulk_load_stream(self.buffer_pool.clone(), options, entries).await?;
+            let rebuilt = rebuilt.root.into_inner();
+            let mut root = self.latch(self.root.write()).await?;
+            // a writer below the root may not have landed its write in the scanned leaves
+            if self.version.load(atomic::Ordering::SeqCst) != version
+                || self.writers.load(atomic::Ordering::SeqCst) != 0
+            {
+                drop(root);
+                self.free_tree(rebuilt).await?;
+                continue;
+            }
+            let old = std::mem::replace(&mut *root, rebuilt);
+            drop(root);
+            self.free_tree(old).await?;
+            return Ok(len.into_inner() as usize);
+        }
+        let mut root = self.latch(self.root.write()).await?;
+        // no writer can latch the root, so the ones below it are waited for
+        loop {
+            let idle = self.idle.notified();
+            if self.writers.load(atomic::Ordering::SeqCst) == 0 {
+                break;
+            }
+            self.latch(idle).await?;
+        }
+        let len = AtomicU64::new(0);
+        let entries = self.tree_entries(*root).inspect_ok(|_| {
+            len.fetch_add(1, atomic::Ordering::Relaxed);
+        });
+        let rebuilt =
+            Index::<K>::bulk_load_stream(self.buffer_pool.clone(), options, entries).await?;
+        let old = std::mem::replace(&mut *root, rebuilt.root.into_inner());
+        drop(root);
+        self.free_tree(old).await?;
+        Ok(len.into_inner() as usize)
+    }
+
+    /// Every key-value pair of the tree at the root, the nodes are read breadth-first so the
+    /// leaves come in the order of index. The root isn't latched, the caller holds its write
+    /// latch and no writer is in the tree
+    fn tree_entries(&self, root: PageId) -> impl Stream<Item = StorageResult<(K, RecordId)>> + '_
+    where
+        K: Decoder,
+    {
+        try_stream! {
+            let mut pages = VecDeque::from([root]);
+            while let Some(page_id) = pages.pop_front() {
+                let latch = self
+                    .latch(self.buffer_pool.fetch_page_read_owned(page_id))
+                    .await??;
+                let node = latch.node::<K>()?;
+                drop(latch);
+                match node {
+                    Node::Internal(internal) => {
+                        pages.extend(internal.kv.into_iter().map(|(_, child)| child))
+                    }
+                    Node::Leaf(leaf) => {
+                        for entry in leaf.kv {
+                            yield entry;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Rewrite the `next` and `prev` links of every leaf to follow the key order of the tree.
+    /// The leaves are found through the internal nodes, so a broken chain is repaired.
+    /// New operations wait for the root latch until the links are rebuilt.
+    pub async fn rebuild_links(&self) -> StorageResult<()>
+    where
+        K: Decoder + Encoder,
+    {
+        let root = self.latch(self.root.write()).await?;
+        // the leaves are all at the same depth, so breadth-first visits them in key order
+        let mut leaves = Vec::new();
+        let mut pages = VecDeque::from([*root]);
+        while let Some(page_id) = pages.pop_front() {
+            let latch = self
+                .latch(self.buffer_pool.fetch_page_read_owned(page_id))
+                .await??;
+            match latch.node::<K>()? {
+                Node::Internal(internal) => {
+                    pages.extend(internal.kv.iter().map(|(_, child)| *child))
+                }
+                Node::Leaf(_) => leaves.push(page_id),
+            }
+        }
+        for (i, page_id) in leaves.iter().enumerate() {
+            let mut latch = self
+                .latch(self.buffer_pool.fetch_page_write_owned(*page_id))
+                .await??;
+            let mut node = latch.node::<K>()?;
+            let leaf = node.assume_leaf_mut();
+            leaf.header.prev = i.checked_sub(1).map(|prev| leaves[prev]);
+            leaf.header.next = leaves.get(i + 1).copied();
+            latch.write_node_back(&node)?;
+        }
+        drop(root);
+        Ok(())
+    }
+
+    /// Merge every pair of adjacent leaves under the same parent whose keys fit in one leaf
+    /// and its page, the right leaf is merged into the left one and its page is deleted.
+    /// A pair is skipped if removing the right leaf would underflow the parent, and a root left
+    /// with one child is replaced by the merged leaf. New operations wait for the root latch
+    /// until it's done.
+    /// Returns the number of merged leaves.
+    pub async fn coalesce(&self) -> StorageResult<usize>
+    where
+        K: Decoder + Encoder + EncodedSize,
+    {
+        let mut root = self.latch(self.root.write()).await?;
+        self.version.fetch_add(1, atomic::Ordering::SeqCst);
+        let mut page_id = *root;
+        while let (_, Node::Internal(internal)) =
+            self.buffer_pool.fetch_page_node::<K>(page_id).await?
+        {
+            page_id = internal.kv[0].1;
+        }
+        let mut merged = 0;
+        loop {
+            let (_, node) = self.buffer_pool.fetch_page_node::<K>(page_id).await?;
+            let leaf = node.assume_leaf();
+            let (Some(parent_id), Some(next_id)) = (leaf.parent(), leaf.next()) else {
+                break;
+            };
+            // latch top-down like the readers do, the tree can't change meanwhile
+            let mut parent_latch = self
+                .latch(self.buffer_pool.fetch_page_write_owned(parent_id))
+                .await??;
+            let mut left_latch = self
+                .latch(self.buffer_pool.fetch_page_write_owned(page_id))
+                .await??;
+            let right_latch = self
+                .latch(self.buffer_pool.fetch_page_write_owned(next_id))
+                .await??;
+            let mut parent = parent_latch.node::<K>()?.assume_internal();
+            let mut left = left_latch.node::<K>()?.assume_leaf();
+            let mut right = right_latch.node::<K>()?.assume_leaf();
+            let right_index = parent.kv.iter().position(|(_, child)| *child == next_id);
+            let merged_size =
+                left.encoded_size() + right.encoded_size() - right.header.encoded_size();
+            let (Some(right_index), true, true) = (
+                right_index,
+                left.kv.len() + right.kv.len() < self.max_size && merged_size < self.capacity(),
+                parent.allow_delete(),
+            ) else {
+                page_id = next_id;
+                continue;
+            };
+            left.merge(&mut right);
+            parent.kv.remove(right_index);
+            parent.header.size -= 1;
+            if let Some(next) = left.next() {
+                let mut next_latch = self
+                    .latch(self.buffer_pool.fetch_page_write_owned(next))
+                    .await??;
+                let mut next = next_latch.node::<K>()?;
+                next.set_prev(page_id);
+                next_latch.write_node_back(&next)?;
+            }
+            let collapsed = parent.header.size == 0 && parent.parent().is_none();
+            if collapsed {
+                *root = page_id;
+                left.header.parent = None;
+            } else {
+                parent_latch.write_node_back(&Node::Internal(parent))?;
+            }
+            left_latch.write_node_back(&Node::Leaf(left))?;
+            drop((parent_latch, left_latch, right_latch));
+            // a pinned page is left to the buffer pool
+            self.buffer_pool.delete_page(next_id).await?;
+            if collapsed {
+                self.buffer_pool.delete_page(parent_id).await?;
+            }
+            merged += 1;
+        }
+        drop(root);
+        Ok(merged)
+    }
+
+    /// Delete the pages of a tree which is no longer reachable from the root.
+    /// Every page is write latched first, so the readers still in it move on before it's deleted.
+    /// The internal nodes go first, so the readers left are all in the leaves. A reader moves
+    /// from a leaf to a neighbour by only trying its latch, so a leaf is deleted while the next
+    /// one is latched: neither neighbour of a deleted leaf can be entered, and a reader which
+    /// fails to try them seeks again from the new root.
+    async fn free_tree(&self, root: PageId) -> StorageResult<()>
+    where
+        K: Decoder,
+    {
+        // the leaves are all at the same depth, so breadth-first visits them in key order
+        let mut pages = VecDeque::from([root]);
+        let mut previous: Option<(PageId, OwnedPageDataWriteGuard)> = None;
+        while let Some(page_id) = pages.pop_front() {
+            let latch = self
+                .latch(self.buffer_pool.fetch_page_write_owned(page_id))
+                .await??;
+            let leaf = match latch.node::<K>()? {
+                Node::Internal(internal) => {
+                    pages.extend(internal.kv.iter().map(|(_, child)| *child));
+                    false
+                }
+                Node::Leaf(_) => true,
+            };
+            if !leaf {
+                drop(latch);
+                // a pinned page is left to the buffer pool
+                self.buffer_pool.delete_page(page_id).await?;
+                continue;
+            }
+            if let Some((previous_id, previous_latch)) = previous.replace((page_id, latch)) {
+                drop(previous_latch);
+                self.buffer_pool.delete_page(previous_id).await?;
+            }
+        }
+        if let Some((page_id, latch)) = previous {
+            drop(latch);
+            self.buffer_pool.delete_page(page_id).await?;
+        }
+        Ok(())
+    }
+
+    /// Load the leaves after the given leaf into the buffer pool, until [`Index::with_read_ahead`] leaves are ahead.
+    /// `ahead` holds the (page id, next page id) of loaded leaves, so each leaf is only loaded once.
+    /// It's best-effort, a leaf which is latched or can't be decoded stops reading ahead.
+    async fn read_ahead(&self, leaf: &Leaf<K>, ahead: &mut VecDeque<(PageId, Option<PageId>)>)
+    where
+        K: Decoder,
+    {
+        match ahead.front() {
+            Some((page_id, _)) if *page_id == leaf.page_id() => {
+                ahead.pop_front();
+            }
+            _ => ahead.clear(),
+        }
+        let mut next = ahead.back().map_or(leaf.next(), |(_, next)| *next);
+        while ahead.len() < self.read_ahead {
+            let Some(page_id) = next else {
+                break;
+            };
+            let Ok(latch) = self.buffer_pool.try_fetch_page_read_owned(page_id).await else {
+                break;
+            };
+            next = match latch.node::<K>() {
+                Ok(Node::Leaf(leaf)) => leaf.next(),
+                _ => break,
+            };
+            ahead.push_back((page_id, next));
+        }
+    }
+
+    /// Delete all the keys in range, returns the number of deleted keys.
+    /// Keys in the same leaf are removed together, and the leaf is rebalanced once.
+    pub async fn delete_range<'r, R>(&self, range: R) -> StorageResult<usize>
+    where
+        K: Decoder + Encoder + IndexKey + Clone + 'r,
+        R: RangeBounds<&'r K>,
+    {
+        if !self.unique {
+            // the equal keys may span several leaves, so each entry is deleted on its own
+            let mut count = 0;
+            for (key, record_id) in self.range_entries(range).await? {
+                if self.delete_entry(&key, record_id).await? {
+                    count += 1;
+                }
+            }
+            return Ok(count);
+        }
+        let keys = self
+            .range_entries(range)
+            .await?
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect::<Vec<_>>();
+        let mut keys = keys.as_slice();
+        let mut count = 0;
+        while let Some(first) = keys.first() {
+            let option = RouteOption::default().with_action(RouteAction::DeleteRange);
+            let mut route = Route::new(option);
+            let page_id = self
+                .find_route(KeyCondition::Equal(first), &mut route)
+                .await?;
+            let (deleted, consumed) = self.delete_range_inner(page_id, route, keys).await?;
+            count += deleted;
+            keys = &keys[consumed..];
+        }
+        Ok(count)
+    }
+
+    /// Delete the leading keys which belong to the leaf, then rebalance the tree.
+    /// Returns the number of deleted keys and the number of consumed keys.
+    async fn delete_range_inner(
+        &self,
+        page_id: PageId,
+        mut route: Route<'_>,
+        keys: &[K],
+    ) -> StorageResult<(usize, usize)>
+    where
+        K: Decoder + Encoder + IndexKey + Clone,
+    {
+        let route_node = route.nodes.shift_remove(&page_id).unwrap();
+        let mut parent_index = route_node.parent_index;
+        let mut latch = route_node.latch.assume_write();
+        let mut leaf = latch.node::<K>()?.assume_leaf();
+        let consumed = match (leaf.next(), leaf.kv.last()) {
+            (Some(_), Some((last, _))) => keys
+                .iter()
+                .take_while(|key| self.compare(*key, last) != Ordering::Greater)
+                .count()
+                .max(1),
+            _ => keys.len(),
+        };
+        let deleted = keys[..consumed]
+            .iter()
+            .filter_map(|key| leaf.remove_by(key, |a, b| self.compare(a, b)))
+            .count();
+        if let (true, Some(parent_id)) = (leaf.is_underflow(), leaf.parent()) {
+            self.check_siblings(
+                route
+                    .nodes
+                    .get(&parent_id)
+                    .unwrap()
+                    .latch
+                    .assume_write_ref(),
+                parent_index,
+            )
+            .await?;
+        }
+        latch.write_node_back(&Node::Leaf(leaf))?;
+        loop {
+            let node = latch.node::<K>()?;
+            if !node.is_underflow() {
+                break;
+            }
+            let parent_id = match node.parent() {
+                None => break,
+                Some(parent_id) => parent_id,
+            };
+            let parent_latch = route
+                .nodes
+                .get_mut(&parent_id)
+                .unwrap()
+                .latch
+                .assume_write_mut();
+            // The node may lose several keys, so keep stealing until it's not underflow
+            if self
+                .steal(parent_latch, &mut latch, parent_index)
+                .await?
+                .is_some()
+            {
+                continue;
+            }
+            if self
+                .merge(parent_latch, latch, &mut route.root_latch, parent_index)
+                .await?
+            {
+                break;
+            }
+            let route_node = route.nodes.shift_remove(&parent_id).unwrap();
+            parent_index = route_node.parent_index;
+            latch = route_node.latch.assume_write();
+        }
+        Ok((deleted, consumed))
     }
 
     async fn insert_inner(
@@ -211,61 +1868,116 @@ impl<'a, K> Index<K> {
         value: RecordId,
     ) -> StorageResult<()>
     where
-        K: Decoder + Encoder + Ord + Default + Clone,
+        K: Decoder + Encoder + IndexKey + Default + Clone,
     {
+        // the parent which took the median key, it's written back once it's known to fit
+        let mut parent = None;
         loop {
-            let mut latch = route
-                .nodes
-                .shift_remove(&page_id)
-                .unwrap()
-                .latch
-                .assume_write();
-            let mut node: Node<K> = latch.node()?;
+            let route_node = route.nodes.shift_remove(&page_id).unwrap();
+            let mut latch = route_node.latch.assume_write();
+            let mut node: Node<K> = match parent.take() {
+                Some(parent) => parent,
+                None => latch.node()?,
+            };
             match node {
-                Node::Internal(ref mut _internal) => {}
-                Node::Leaf(ref mut leaf) => {
-                    match leaf.kv.binary_search_by(|(k, _)| k.cmp(&key)) {
+                Node::Leaf(ref mut leaf) if self.unique => {
+                    match leaf.kv.binary_search_by(|(k, _)| self.compare(k, &key)) {
                         Ok(index) => leaf.kv[index] = (key.clone(), value),
                         Err(index) => leaf.insert(index, key.clone(), value),
                     };
-                    latch.write_node_back(&node)?;
                 }
+                Node::Leaf(ref mut leaf) => {
+                    let index = leaf
+                        .kv
+                        .partition_point(|(k, _)| self.compare(k, &key) != Ordering::Greater);
+                    leaf.insert(index, key.clone(), value);
+                }
+                Node::Internal(_) => {}
             }
-            if !node.is_overflow() {
+            if !node.is_overflow(self.capacity()) {
+                latch.write_node_back(&node)?;
                 return Ok(());
             }
-            let (median_key, mut sibling) = node.split();
-            let mut sibling_latch = self.buffer_pool.new_page_write_owned(&mut sibling).await?;
-            let sibling_page_id = sibling.page_id();
-            if let Node::Internal(ref mut internal) = sibling {
-                for (_, child) in internal.kv.iter() {
-                    let mut child_latch = self.buffer_pool.fetch_page_write_owned(*child).await?;
-                    let mut child_node = child_latch.node::<K>()?;
-                    child_node.set_parent(sibling_page_id);
-                    child_latch.write_node_back(&child_node)?;
-                }
-            }
-            node.set_next(sibling.page_id());
-            sibling.set_prev(node.page_id());
-            if let Some(parent_id) = node.parent() {
+            if let (Node::Leaf(_), Some(parent_id)) = (&node, node.parent()) {
                 let parent_latch = route
                     .nodes
                     .get_mut(&parent_id)
                     .unwrap()
                     .latch
                     .assume_write_mut();
-                let mut parent_node = parent_latch.node::<K>()?;
+                if self
+                    .redistribute(parent_latch, &mut latch, &mut node, route_node.parent_index)
+                    .await?
+                {
+                    return Ok(());
+                }
+            }
+            let (median_key, mut sibling) = node.split(self.capacity());
+            // Decode every page changed by the split before writing any of them back,
+            // so a corrupt page fails the split without leaving it half done
+            let mut children = Vec::new();
+            if let Node::Internal(ref internal) = sibling {
+                for (_, child) in internal.kv.iter() {
+                    let child_latch = self.buffer_pool.fetch_page_write_owned(*child).await?;
+                    let child_node = child_latch.node::<K>()?;
+                    children.push((child_latch, child_node));
+                }
+            }
+            // the leaf after the split one links back to the sibling
+            let next = match &sibling {
+                Node::Leaf(leaf) => match leaf.next() {
+                    Some(next_id) => {
+                        let next_latch = self.buffer_pool.fetch_page_write_owned(next_id).await?;
+                        let next_node = next_latch.node::<K>()?;
+                        Some((next_latch, next_node))
+                    }
+                    None => None,
+                },
+                Node::Internal(_) => None,
+            };
+            let parent_node = match node.parent() {
+                Some(parent_id) => Some(
+                    route
+                        .nodes
+                        .get(&parent_id)
+                        .unwrap()
+                        .latch
+                        .assume_write_ref()
+                        .node::<K>()?,
+                ),
+                None => None,
+            };
+            let mut sibling_latch = self.buffer_pool.new_page_write_owned(&mut sibling).await?;
+            let sibling_page_id = sibling.page_id();
+            for (mut child_latch, mut child_node) in children {
+                child_node.set_parent(sibling_page_id);
+                child_latch.write_node_back(&child_node)?;
+            }
+            node.set_next(sibling.page_id());
+            sibling.set_prev(node.page_id());
+            if let Some((mut next_latch, mut next_node)) = next {
+                next_node.set_prev(sibling_page_id);
+                next_latch.write_node_back(&next_node)?;
+            }
+            if let Some(mut parent_node) = parent_node {
                 let internal = parent_node.assume_internal_mut();
-                let index = internal
-                    .kv
-                    .binary_search_by(|(k, _)| k.cmp(&median_key))
-                    .unwrap_or_else(|index| index);
+                // the first key of internal node is a placeholder, skip it.
+                // The separators of a non-unique index may be equal, so the sibling is placed
+                // right after the split node instead
+                let index = if self.unique {
+                    internal.kv[1..]
+                        .binary_search_by(|(k, _)| self.compare(k, &median_key))
+                        .unwrap_or_else(|index| index)
+                        + 1
+                } else {
+                    route_node.parent_index + 1
+                };
                 internal.insert(index, median_key.clone(), sibling_page_id);
 
-                parent_latch.write_node_back(&parent_node)?;
                 sibling_latch.write_node_back(&sibling)?;
                 latch.write_node_back(&node)?;
-                page_id = parent_latch.page_id();
+                page_id = parent_node.page_id();
+                parent = Some(parent_node);
             } else {
                 let mut parent_node = Node::Internal(Internal {
                     header: Header {
@@ -304,15 +2016,17 @@ impl<'a, K> Index<K> {
         }
     }
 
+    /// Remove an entry from the leaf by `remove`, then rebalance the tree
     async fn delete_inner(
         &self,
         mut page_id: PageId,
         mut route: Route<'_>,
-        key: &K,
+        remove: impl FnOnce(&mut Leaf<K>) -> Option<(K, RecordId)>,
     ) -> StorageResult<Option<(K, RecordId)>>
     where
-        K: Decoder + Encoder + Ord + Clone,
+        K: Decoder + Encoder + IndexKey + Clone,
     {
+        let mut remove = Some(remove);
         let mut res = None;
         loop {
             let route_node = route.nodes.shift_remove(&page_id).unwrap();
@@ -321,12 +2035,24 @@ impl<'a, K> Index<K> {
             match node {
                 Node::Internal(ref mut _internal) => {}
                 Node::Leaf(ref mut leaf) => {
-                    res = match leaf.remove(key) {
+                    res = match remove.take().unwrap()(leaf) {
                         None => return Ok(None),
                         other => other,
                     };
                 }
             }
+            if let (true, Some(parent_id)) = (node.is_underflow(), node.parent()) {
+                self.check_siblings(
+                    route
+                        .nodes
+                        .get(&parent_id)
+                        .unwrap()
+                        .latch
+                        .assume_write_ref(),
+                    route_node.parent_index,
+                )
+                .await?;
+            }
             latch.write_node_back(&node)?;
             if !node.is_underflow() {
                 break;
@@ -365,9 +2091,31 @@ impl<'a, K> Index<K> {
         Ok(res)
     }
 
+    /// Decode the parent and the siblings which rebalancing the node may touch,
+    /// so a corrupt page is found before the node is written back
+    async fn check_siblings(
+        &self,
+        parent_latch: &OwnedPageDataWriteGuard,
+        index: usize,
+    ) -> StorageResult<()>
+    where
+        K: Decoder,
+    {
+        let parent = parent_latch.node::<K>()?.assume_internal();
+        for sibling in [index.checked_sub(1), index.checked_add(1)]
+            .into_iter()
+            .flatten()
+        {
+            if let Some((_, page_id)) = parent.kv.get(sibling) {
+                self.buffer_pool.fetch_page_node::<K>(*page_id).await?;
+            }
+        }
+        Ok(())
+    }
+
     /// Try to steal key-value from it's sibling node.
     /// If steal successfully, return [`Some`]
-    /// else, return [`None`]
+    /// else, return [`None`], also when the nodes of long keys wouldn't fit in their pages
     async fn steal(
         &self,
         parent_latch: &mut OwnedPageDataWriteGuard,
@@ -375,7 +2123,7 @@ impl<'a, K> Index<K> {
         index: usize,
     ) -> StorageResult<Option<()>>
     where
-        K: Decoder + Encoder + Ord + Clone,
+        K: Decoder + Encoder + IndexKey + Clone,
     {
         let mut parent: Internal<K> = parent_latch.node()?.assume_internal();
         let node: Node<K> = latch.node()?;
@@ -400,18 +2148,23 @@ impl<'a, K> Index<K> {
                         let right_index = index;
                         internal.push_front(parent.kv[right_index].0.clone(), steal.1);
                         parent.kv[right_index].0 = steal.0;
-                        // change child parent pointer
-                        let (child_page, mut child) =
-                            self.buffer_pool.fetch_page_node::<K>(steal.1).await?;
-                        child.set_parent(internal.page_id());
-                        child_page.data_write().await.write_node_back(&child)?;
-                        prev_page
-                            .data_write()
-                            .await
-                            .write_node_back(&Node::Internal(prev_node))?;
-                        latch.write_node_back(&Node::Internal(internal))?;
-                        parent_latch.write_node_back(&Node::Internal(parent))?;
-                        return Ok(Some(()));
+                        if self.fits(&internal) && self.fits(&parent) {
+                            // change child parent pointer
+                            let (child_page, mut child) =
+                                self.buffer_pool.fetch_page_node::<K>(steal.1).await?;
+                            child.set_parent(internal.page_id());
+                            child_page.data_write().await.write_node_back(&child)?;
+                            prev_page
+                                .data_write()
+                                .await
+                                .write_node_back(&Node::Internal(prev_node))?;
+                            latch.write_node_back(&Node::Internal(internal))?;
+                            parent_latch.write_node_back(&Node::Internal(parent))?;
+                            return Ok(Some(()));
+                        }
+                        // the longer separators don't fit, undo the steal
+                        internal = latch.node()?.assume_internal();
+                        parent = parent_latch.node()?.assume_internal();
                     }
                 }
                 if let Some(next_index) = next {
@@ -424,18 +2177,20 @@ impl<'a, K> Index<K> {
                         let right_index = next_index;
                         internal.push_back(parent.kv[right_index].0.clone(), steal.1);
                         parent.kv[right_index].0 = steal.0;
-                        // change child parent pointer
-                        let (child_page, mut child) =
-                            self.buffer_pool.fetch_page_node::<K>(steal.1).await?;
-                        child.set_parent(internal.page_id());
-                        child_page.data_write().await.write_node_back(&child)?;
-                        next_page
-                            .data_write()
-                            .await
-                            .write_node_back(&Node::Internal(next_node))?;
-                        latch.write_node_back(&Node::Internal(internal))?;
-                        parent_latch.write_node_back(&Node::Internal(parent))?;
-                        return Ok(Some(()));
+                        if self.fits(&internal) && self.fits(&parent) {
+                            // change child parent pointer
+                            let (child_page, mut child) =
+                                self.buffer_pool.fetch_page_node::<K>(steal.1).await?;
+                            child.set_parent(internal.page_id());
+                            child_page.data_write().await.write_node_back(&child)?;
+                            next_page
+                                .data_write()
+                                .await
+                                .write_node_back(&Node::Internal(next_node))?;
+                            latch.write_node_back(&Node::Internal(internal))?;
+                            parent_latch.write_node_back(&Node::Internal(parent))?;
+                            return Ok(Some(()));
+                        }
                     }
                 }
             }
@@ -450,13 +2205,18 @@ impl<'a, K> Index<K> {
                         parent.kv[right_index].0 = steal.0.clone();
                         let (key, value) = steal;
                         leaf.push_front(key, value);
-                        prev_page
-                            .data_write()
-                            .await
-                            .write_node_back(&Node::Leaf(prev_node))?;
-                        latch.write_node_back(&Node::Leaf(leaf))?;
-                        parent_latch.write_node_back(&Node::Internal(parent))?;
-                        return Ok(Some(()));
+                        if self.fits(&leaf) && self.fits(&parent) {
+                            prev_page
+                                .data_write()
+                                .await
+                                .write_node_back(&Node::Leaf(prev_node))?;
+                            latch.write_node_back(&Node::Leaf(leaf))?;
+                            parent_latch.write_node_back(&Node::Internal(parent))?;
+                            return Ok(Some(()));
+                        }
+                        // the longer key doesn't fit, undo the steal
+                        leaf = latch.node()?.assume_leaf();
+                        parent = parent_latch.node()?.assume_internal();
                     }
                 }
                 if let Some(next_index) = next {
@@ -470,13 +2230,15 @@ impl<'a, K> Index<K> {
                         parent.kv[right_index].0 = next_node.kv[0].0.clone();
                         let (key, value) = steal;
                         leaf.push_back(key, value);
-                        next_page
-                            .data_write()
-                            .await
-                            .write_node_back(&Node::Leaf(next_node))?;
-                        latch.write_node_back(&Node::Leaf(leaf))?;
-                        parent_latch.write_node_back(&Node::Internal(parent))?;
-                        return Ok(Some(()));
+                        if self.fits(&leaf) && self.fits(&parent) {
+                            next_page
+                                .data_write()
+                                .await
+                                .write_node_back(&Node::Leaf(next_node))?;
+                            latch.write_node_back(&Node::Leaf(leaf))?;
+                            parent_latch.write_node_back(&Node::Internal(parent))?;
+                            return Ok(Some(()));
+                        }
                     }
                 }
             }
@@ -485,8 +2247,86 @@ impl<'a, K> Index<K> {
         Ok(None)
     }
 
+    /// Shift a key of the overflowing leaf to a sibling which has room for it, so the leaf
+    /// doesn't have to be split. It's the inverse of [`Index::steal`].
+    /// Returns whether the key is shifted, the leaf is only written back if it is
+    async fn redistribute(
+        &self,
+        parent_latch: &mut OwnedPageDataWriteGuard,
+        latch: &mut OwnedPageDataWriteGuard,
+        node: &mut Node<K>,
+        index: usize,
+    ) -> StorageResult<bool>
+    where
+        K: Decoder + Encoder + IndexKey + Clone,
+    {
+        let capacity = self.capacity();
+        // a leaf too large for its page may not fit after shifting a key, it's split instead
+        if node.encoded_size() > capacity {
+            return Ok(false);
+        }
+        let mut parent: Internal<K> = parent_latch.node()?.assume_internal();
+        let leaf = node.assume_leaf_mut();
+        // the sibling takes the entry, and the parent replaces the separator.
+        // Both are encoded after the type byte of node, hence `<`
+        let fits =
+            |sibling: &Leaf<K>, entry: &(K, RecordId), parent: &Internal<K>, old: &K, new: &K| {
+                sibling.encoded_size() + entry.0.encoded_size() + entry.1.encoded_size() < capacity
+                    && parent.encoded_size() + new.encoded_size() < capacity + old.encoded_size()
+            };
+        if index > 0 {
+            let prev_id = parent.kv[index - 1].1;
+            let (prev_page, prev_node) = self.buffer_pool.fetch_page_node::<K>(prev_id).await?;
+            let mut prev_node = prev_node.assume_leaf();
+            if prev_node.allow_insert()
+                && fits(
+                    &prev_node,
+                    &leaf.kv[0],
+                    &parent,
+                    &parent.kv[index].0,
+                    &leaf.kv[1].0,
+                )
+            {
+                // shift the first key to prev node and change parent
+                let (key, value) = leaf.steal_first().unwrap();
+                prev_node.push_back(key, value);
+                parent.kv[index].0 = leaf.kv[0].0.clone();
+                prev_page
+                    .data_write()
+                    .await
+                    .write_node_back(&Node::Leaf(prev_node))?;
+                latch.write_node_back(node)?;
+                parent_latch.write_node_back(&Node::Internal(parent))?;
+                return Ok(true);
+            }
+        }
+        if index + 1 < parent.kv.len() {
+            let next_id = parent.kv[index + 1].1;
+            let (next_page, next_node) = self.buffer_pool.fetch_page_node::<K>(next_id).await?;
+            let mut next_node = next_node.assume_leaf();
+            let last = leaf.kv.last().unwrap();
+            if next_node.allow_insert()
+                && fits(&next_node, last, &parent, &parent.kv[index + 1].0, &last.0)
+            {
+                // shift the last key to next node and change parent
+                let (key, value) = leaf.steal_last().unwrap();
+                parent.kv[index + 1].0 = key.clone();
+                next_node.push_front(key, value);
+                next_page
+                    .data_write()
+                    .await
+                    .write_node_back(&Node::Leaf(next_node))?;
+                latch.write_node_back(node)?;
+                parent_latch.write_node_back(&Node::Internal(parent))?;
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
     /// merge this node and it's prev node or next node
-    /// return true if the node which been merged become the root
+    /// return true if the node which been merged become the root,
+    /// or if the nodes of long keys don't fit in one page and are left as they are
     async fn merge(
         &self,
         parent_latch: &mut OwnedPageDataWriteGuard,
@@ -495,7 +2335,7 @@ impl<'a, K> Index<K> {
         index: usize,
     ) -> StorageResult<bool>
     where
-        K: Encoder + Decoder + Clone + Ord,
+        K: Encoder + Decoder + Clone + IndexKey,
     {
         let mut parent: Internal<K> = parent_latch.node()?.assume_internal();
         let node: Node<K> = latch.node()?;
@@ -528,10 +2368,15 @@ impl<'a, K> Index<K> {
                 let (key, _) = parent.kv.remove(right_index);
                 parent.header.size -= 1;
                 left_node.merge(key, &mut right_node);
-                // change the children's parent id
+                if !self.fits(&left_node) {
+                    return Ok(true);
+                }
+                // change the children's parent id, decode all of them before writing any back
+                let mut children = Vec::with_capacity(changed_children.len());
                 for child_id in changed_children {
-                    let (child_page, mut child) =
-                        self.buffer_pool.fetch_page_node::<K>(child_id).await?;
+                    children.push(self.buffer_pool.fetch_page_node::<K>(child_id).await?);
+                }
+                for (child_page, mut child) in children {
                     child.set_parent(left_node.page_id());
                     child_page.data_write().await.write_node_back(&child)?;
                 }
@@ -568,8 +2413,17 @@ impl<'a, K> Index<K> {
                     }
                 };
                 left_node.merge(&mut right_node);
+                if !self.fits(&left_node) {
+                    return Ok(true);
+                }
                 parent.kv.remove(right_index);
                 parent.header.size -= 1;
+                if let Some(next_id) = left_node.next() {
+                    let mut next_latch = self.buffer_pool.fetch_page_write_owned(next_id).await?;
+                    let mut next_node = next_latch.node::<K>()?;
+                    next_node.set_prev(left_node.page_id());
+                    next_latch.write_node_back(&next_node)?;
+                }
 
                 if parent.header.size == 0 && parent.parent().is_none() {
                     //change root node
@@ -595,19 +2449,25 @@ impl<'a, K> Index<K> {
     /// If current node is unsafe, then take parent latch
     async fn find_route(
         &'a self,
-        key: KeyCondition<&K>,
+        key: KeyCondition<'_, &K>,
         route: &mut Route<'a>,
     ) -> StorageResult<PageId>
     where
-        K: Decoder + Encoder + Ord,
+        K: Decoder + Encoder + IndexKey,
     {
         let root_latch = match route.option.action {
             RouteAction::Search => {
-                let root_guard = self.root.read().await;
+                let root_guard = self.latch(self.root.read()).await?;
                 RootLatch::Read(root_guard)
             }
-            RouteAction::Insert | RouteAction::Delete => {
-                let root_guard = self.root.write().await;
+            RouteAction::Insert | RouteAction::Delete | RouteAction::DeleteRange => {
+                let root_guard = self.latch(self.root.write()).await?;
+                self.writers.fetch_add(1, atomic::Ordering::SeqCst);
+                route.writer = Some(Writer {
+                    version: &self.version,
+                    writers: &self.writers,
+                    idle: &self.idle,
+                });
                 RootLatch::Write(root_guard)
             }
         };
@@ -622,12 +2482,12 @@ impl<'a, K> Index<K> {
                 .ok_or(buffer::Error::BufferInsufficient)?;
             let (latch, node) = match route.option.action {
                 RouteAction::Search => {
-                    let read_guard = page.data_read_owned().await;
+                    let read_guard = self.latch(page.data_read_owned()).await?;
                     let node = read_guard.node::<K>()?;
                     (Latch::Read(read_guard), node)
                 }
-                RouteAction::Insert | RouteAction::Delete => {
-                    let write_guard = page.data_write_owned().await;
+                RouteAction::Insert | RouteAction::Delete | RouteAction::DeleteRange => {
+                    let write_guard = self.latch(page.data_write_owned()).await?;
                     let node = write_guard.node::<K>()?;
                     (Latch::Write(write_guard), node)
                 }
@@ -638,7 +2498,7 @@ impl<'a, K> Index<K> {
                         route.pop_front_until(page_id);
                     }
                     RouteAction::Insert => {
-                        if node.allow_insert() {
+                        if node.allow_insert(self.capacity(), self.max_entry_size()) {
                             route.pop_front_until(page_id);
                         }
                     }
@@ -647,6 +2507,8 @@ impl<'a, K> Index<K> {
                             route.pop_front_until(page_id);
                         }
                     }
+                    // A leaf may lose many keys at once, so every ancestor is kept
+                    RouteAction::DeleteRange => {}
                 }
             }
             match node {
@@ -656,7 +2518,27 @@ impl<'a, K> Index<K> {
                         KeyCondition::Max => {
                             (internal.kv.len() - 1, internal.kv[internal.kv.len() - 1].1)
                         }
-                        KeyCondition::Equal(key) => internal.search(key),
+                        KeyCondition::Equal(key) => {
+                            internal.search_by(key, |a, b| self.compare(a, b))
+                        }
+                        KeyCondition::First(key) => self.first_child(internal, key),
+                        KeyCondition::Last(key) => {
+                            // the first key of internal node is a placeholder, skip it
+                            let index = internal.kv[1..].partition_point(|(k, _)| {
+                                self.compare(k, key) != Ordering::Greater
+                            });
+                            (index, internal.kv[index].1)
+                        }
+                        KeyCondition::Path(key, path) => {
+                            match internal
+                                .kv
+                                .iter()
+                                .position(|(_, child)| path.contains(child))
+                            {
+                                Some(index) => (index, internal.kv[index].1),
+                                None => self.first_child(internal, key),
+                            }
+                        }
                     };
                     let node = RouteNode::new(latch, parent_index);
                     route.insert(page_id, node);
@@ -672,6 +2554,17 @@ impl<'a, K> Index<K> {
         }
     }
 
+    /// The leftmost child which may hold the key, the separators before it sort before the key
+    fn first_child(&self, internal: &Internal<K>, key: &K) -> (usize, PageId)
+    where
+        K: IndexKey,
+    {
+        // the first key of internal node is a placeholder, skip it
+        let index =
+            internal.kv[1..].partition_point(|(k, _)| self.compare(k, key) == Ordering::Less);
+        (index, internal.kv[index].1)
+    }
+
     #[cfg(test)]
     pub(crate) async fn print(&self) -> StorageResult<()>
     where
@@ -722,16 +2615,24 @@ impl<'a, K> Index<K> {
     }
 }
 
-enum KeyCondition<K> {
+enum KeyCondition<'p, K> {
     Min,
     Max,
     Equal(K),
+    /// The leftmost leaf which may hold the key
+    First(K),
+    /// The rightmost leaf which may hold the key
+    Last(K),
+    /// Through the pages of the path where they're children, else like [`KeyCondition::First`]
+    Path(K, &'p [PageId]),
 }
 
 struct Route<'a> {
     nodes: IndexMap<PageId, RouteNode>,
     root_latch: Option<RootLatch<'a>>,
     option: RouteOption,
+    /// Dropped after the latches, the route of a write is dropped once the write has landed
+    writer: Option<Writer<'a>>,
 }
 
 impl Route<'_> {
@@ -740,6 +2641,7 @@ impl Route<'_> {
             nodes: Default::default(),
             root_latch: None,
             option,
+            writer: None,
         }
     }
 
@@ -758,10 +2660,28 @@ impl Route<'_> {
     }
 }
 
+/// A writer counted in [`Index::writers`] from when it latches the root, it bumps
+/// [`Index::version`] when it's dropped
+struct Writer<'a> {
+    version: &'a AtomicU64,
+    writers: &'a AtomicU64,
+    idle: &'a Notify,
+}
+
+impl Drop for Writer<'_> {
+    fn drop(&mut self) {
+        self.version.fetch_add(1, atomic::Ordering::SeqCst);
+        if self.writers.fetch_sub(1, atomic::Ordering::SeqCst) == 1 {
+            self.idle.notify_waiters();
+        }
+    }
+}
+
 enum RouteAction {
     Search,
     Insert,
     Delete,
+    DeleteRange,
 }
 
 struct RouteOption {
@@ -878,7 +2798,9 @@ impl<'a> RootLatch<'a> {
 mod tests {
     use super::*;
     use crate::storage::disk::disk_manager::DiskManager;
-    use crate::storage::Error;
+    use crate::storage::{Error, PAGE_PAYLOAD_SIZE};
+    use futures::{pin_mut, StreamExt};
+    use std::collections::HashSet;
     use std::ops::RangeFull;
 
     async fn test_index() -> StorageResult<Index<u32>> {
@@ -965,50 +2887,562 @@ mod tests {
         Ok(())
     }
     #[tokio::test]
-    async fn search_range() -> StorageResult<()> {
+    async fn search_range() -> StorageResult<()> {
+        let index = test_index().await?;
+        let keys = (1..1000).collect::<Vec<_>>();
+        insert_inner(&index, &keys.iter().rev().copied().collect::<Vec<_>>()).await?;
+        let range = index
+            .search_range((Bound::Unbounded, Bound::Included(&1000)))
+            .await?;
+        assert_eq!(range.len(), 999);
+        let range = index.search_range(&100..).await?;
+        assert_eq!(range.len(), 900);
+        let range = index.search_range(..=&800).await?;
+        assert_eq!(range.len(), 800);
+        let range = index.search_range::<_>(RangeFull).await?;
+        assert_eq!(range.len(), 999);
+        let range = index.search_range(&0..&900).await?;
+        assert_eq!(range.len(), 899);
+        let range = index
+            .search_range((Bound::Excluded(&100), Bound::Included(&1000)))
+            .await?;
+        assert_eq!(range.len(), 899);
+        let range = index.search_range(&1..=&1000).await?;
+        assert_eq!(range.len(), 999);
+        for (index, record) in range.into_iter().enumerate() {
+            assert_eq!(index + 1, record.page_id);
+        }
+
+        let range = index.search_range(&801..=&900).await?;
+        for (index, record) in range.into_iter().enumerate() {
+            assert_eq!(index + 801, record.page_id);
+        }
+
+        let range = index.search_range(&800..=&1200).await?;
+        assert_eq!(range.len(), 200);
+        for (index, record) in range.into_iter().enumerate() {
+            assert_eq!(index + 800, record.page_id);
+        }
+
+        let range = index.search_range(&0..=&1200).await?;
+        assert_eq!(range.len(), 999);
+        for (index, record) in range.into_iter().enumerate() {
+            assert_eq!(index + 1, record.page_id);
+        }
+        // a range which ends before it starts is empty
+        assert!(index.search_range(&500..&100).await?.is_empty());
+        assert!(index.search_range(&500..=&499).await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn search_range_rev() -> StorageResult<()> {
+        let index = test_index().await?;
+        assert!(index.search_range_rev(..).await?.is_empty());
+        let keys = (1..1000).collect::<Vec<_>>();
+        insert_inner(&index, &keys).await?;
+        let page_ids = |range: Vec<RecordId>| {
+            range
+                .into_iter()
+                .map(|record| record.page_id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            page_ids(index.search_range_rev::<_>(RangeFull).await?),
+            (1..1000).rev().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            page_ids(index.search_range_rev(&100..).await?),
+            (100..1000).rev().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            page_ids(index.search_range_rev(..=&800).await?),
+            (1..=800).rev().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            page_ids(index.search_range_rev(&0..&900).await?),
+            (1..900).rev().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            page_ids(
+                index
+                    .search_range_rev((Bound::Excluded(&100), Bound::Excluded(&200)))
+                    .await?
+            ),
+            (101..200).rev().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            page_ids(index.search_range_rev(&800..=&1200).await?),
+            (800..1000).rev().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            page_ids(index.search_range_rev(&500..=&500).await?),
+            vec![500]
+        );
+        assert!(index.search_range_rev(&500..&100).await?.is_empty());
+        assert!(index.search_range_rev(&500..=&499).await?.is_empty());
+        assert!(index.search_range_rev(&1000..).await?.is_empty());
+        // the leaves merged by deletes still link back in order
+        for key in (200..700).step_by(3).chain(300..600) {
+            index.delete(&key).await?;
+        }
+        let mut forward = page_ids(index.search_range::<_>(RangeFull).await?);
+        forward.reverse();
+        assert_eq!(
+            page_ids(index.search_range_rev::<_>(RangeFull).await?),
+            forward
+        );
+
+        // the reverse of a descending index is in ascending key order
+        let f = tempfile::NamedTempFile::new()?;
+        let disk_manager = DiskManager::new(f.path()).await?;
+        let buffer_pool_manager = Arc::new(BufferPoolManager::new(100, 2, disk_manager).await?);
+        let index = Index::new_with_order(buffer_pool_manager, 4, SortOrder::Descending).await?;
+        insert_inner(&index, &keys).await?;
+        assert_eq!(
+            page_ids(index.search_range_rev(&100..=&200).await?),
+            (100..=200).collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+    #[tokio::test]
+    async fn scan_stream() -> StorageResult<()> {
+        let index = test_index().await?;
+        assert!(Box::pin(index.scan_stream(..)).next().await.is_none());
+        let keys = (1..1000).collect::<Vec<_>>();
+        insert_inner(&index, &keys).await?;
+        for range in [
+            (Bound::Unbounded, Bound::Unbounded),
+            (Bound::Included(100), Bound::Excluded(900)),
+            (Bound::Excluded(100), Bound::Included(900)),
+            (Bound::Included(0), Bound::Included(1200)),
+            (Bound::Included(500), Bound::Excluded(100)),
+        ] {
+            let streamed = index
+                .scan_stream(range)
+                .collect::<Vec<_>>()
+                .await
+                .into_iter()
+                .collect::<StorageResult<Vec<_>>>()?;
+            let (start, end) = (range.0.as_ref(), range.1.as_ref());
+            assert_eq!(streamed, index.search_range((start, end)).await?);
+        }
+
+        // a partially consumed stream holds no latch, so writers go on while it's alive
+        let stream = index.scan_stream(100..);
+        pin_mut!(stream);
+        for key in 100..105 {
+            assert_eq!(stream.next().await.unwrap()?.page_id, key);
+        }
+        tokio::time::timeout(Duration::from_secs(10), async {
+            for key in 200..300 {
+                index.delete(&key).await?;
+            }
+            insert_inner(&index, &[1000, 1001]).await
+        })
+        .await
+        .expect("writers blocked by the stream")?;
+        let rest = stream
+            .map(|record| record.map(|record| record.page_id))
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<StorageResult<Vec<_>>>()?;
+        assert_eq!(rest, (105..200).chain(300..1002).collect::<Vec<_>>());
+
+        // a dropped stream doesn't block the writers either
+        let mut stream = Box::pin(index.scan_stream(..));
+        stream.next().await.unwrap()?;
+        drop(stream);
+        tokio::time::timeout(Duration::from_secs(10), index.delete(&1))
+            .await
+            .expect("writers blocked by the dropped stream")?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn bulk_load() -> StorageResult<()> {
+        let f = tempfile::NamedTempFile::new()?;
+        let disk_manager = DiskManager::new(f.path()).await?;
+        let buffer_pool_manager = Arc::new(BufferPoolManager::new(100, 2, disk_manager).await?);
+        let len = 10000;
+        let record = |key: u32| RecordId {
+            page_id: key as PageId,
+            slot_num: 0,
+        };
+        let index = Index::bulk_load(
+            buffer_pool_manager.clone(),
+            4,
+            (0..len).map(|key| (key, record(key))),
+        )
+        .await?;
+        index.print().await?;
+        for key in 0..len {
+            assert_eq!(index.search(&key).await?, Some(record(key)));
+        }
+        assert_eq!(index.search_range_rev::<_>(RangeFull).await?.len(), 10000);
+        // every leaf is at the same depth, and no node overflows or underflows
+        let mut depths = Vec::new();
+        let mut pages = VecDeque::from([(*index.root.read().await, None, 0)]);
+        while let Some((page_id, parent, depth)) = pages.pop_front() {
+            let (_, node) = index.buffer_pool.fetch_page_node::<u32>(page_id).await?;
+            assert_eq!(node.parent(), parent);
+            assert!(!node.is_overflow(PAGE_PAYLOAD_SIZE) && !node.is_underflow());
+            match node {
+                Node::Internal(internal) => pages.extend(
+                    internal
+                        .kv
+                        .iter()
+                        .map(|(_, child)| (*child, Some(page_id), depth + 1)),
+                ),
+                Node::Leaf(_) => depths.push(depth),
+            }
+        }
+        assert!(depths.iter().all(|depth| *depth == depths[0]));
+        // the tree takes writes like an inserted one
+        for key in (0..len).step_by(2) {
+            assert!(index.delete(&key).await?.is_some());
+        }
+        index.insert(len, record(len)).await?;
+        assert_eq!(index.len().await?, 5001);
+
+        let index = Index::<u32>::bulk_load(buffer_pool_manager.clone(), 4, Vec::new()).await?;
+        assert!(index.entries().await?.is_empty());
+        for entries in [
+            vec![(2, record(2)), (1, record(1))],
+            vec![(1, record(1)); 2],
+        ] {
+            assert!(matches!(
+                Index::bulk_load(buffer_pool_manager.clone(), 4, entries).await,
+                Err(Error::Value(_))
+            ));
+        }
+        // equal keys are taken by a non-unique index
+        let index = Index::bulk_load_with_options(
+            buffer_pool_manager.clone(),
+            IndexOptions::default().with_max_size(4).with_unique(false),
+            vec![(1, record(1)); 10],
+        )
+        .await?;
+        assert_eq!(index.search_all(&1).await?.len(), 10);
+
+        // the nodes of long keys are cut at their page
+        let keys = (0..200)
+            .map(|key| format!("{:0>1000}", key))
+            .collect::<Vec<_>>();
+        let index = Index::bulk_load(
+            buffer_pool_manager,
+            DEFAULT_MAX_SIZE,
+            keys.iter().cloned().zip((0..200).map(record)),
+        )
+        .await?;
+        let mut pages = VecDeque::from([*index.root.read().await]);
+        while let Some(page_id) = pages.pop_front() {
+            let (_, node) = index.buffer_pool.fetch_page_node::<String>(page_id).await?;
+            assert!(index.fits(&node));
+            if let Node::Internal(internal) = node {
+                pages.extend(internal.kv.iter().map(|(_, child)| *child));
+            }
+        }
+        for (key, i) in keys.iter().zip(0..200) {
+            assert_eq!(index.search(key).await?, Some(record(i)));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn open() -> StorageResult<()> {
+        let f = tempfile::NamedTempFile::new()?;
+        let disk_manager = DiskManager::new(f.path()).await?;
+        let buffer_pool_manager = Arc::new(BufferPoolManager::new(100, 2, disk_manager).await?);
+        let index = Index::new(buffer_pool_manager.clone(), 4).await?;
+        let first_root = index.root_page_id().await;
+        let keys = (0..1000).collect::<Vec<_>>();
+        insert_inner(&index, &keys).await?;
+        // the root moves up as the tree grows
+        let root = index.root_page_id().await;
+        assert_ne!(root, first_root);
+        drop(index);
+
+        let index = Index::<u32>::open(buffer_pool_manager.clone(), root, 4).await?;
+        assert_eq!(index.root_page_id().await, root);
+        for key in keys.iter() {
+            assert_eq!(index.search(key).await?.unwrap().page_id, *key as PageId);
+        }
+        insert_inner(&index, &[1000]).await?;
+        assert_eq!(index.len().await?, 1001);
+        // a page under the root isn't a root
+        let (_, node) = buffer_pool_manager.fetch_page_node::<u32>(root).await?;
+        let child = node.assume_internal().kv[0].1;
+        assert!(matches!(
+            Index::<u32>::open(buffer_pool_manager, child, 4).await,
+            Err(Error::Value(_))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn page_size() -> StorageResult<()> {
+        let f = tempfile::NamedTempFile::new()?;
+        let disk_manager = DiskManager::new(f.path()).await?;
+        let buffer_pool_manager =
+            Arc::new(BufferPoolManager::with_page_size(4, 2, disk_manager, 8192).await?);
+        let index =
+            Index::<Vec<i64>>::with_options(buffer_pool_manager.clone(), IndexOptions::default())
+                .await?;
+        assert_eq!(index.max_size, 2 * DEFAULT_MAX_SIZE);
+        let key = |i: i64| vec![i; 16];
+        for i in 0..40 {
+            index
+                .insert(
+                    key(i),
+                    RecordId {
+                        page_id: i as PageId,
+                        slot_num: 0,
+                    },
+                )
+                .await?;
+        }
+        // the only leaf is larger than a page of the default size
+        let root = index.root_page_id().await;
+        {
+            let (_, node) = buffer_pool_manager
+                .fetch_page_node::<Vec<i64>>(root)
+                .await?;
+            assert!(matches!(node, Node::Leaf(_)));
+            let mut data = Vec::new();
+            node.encode(&mut data)?;
+            assert!(data.len() > PAGE_PAYLOAD_SIZE);
+        }
+        drop(index);
+
+        // evict the leaf, so it's read back from disk
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let mut pages = Vec::new();
+        for _ in 0..4 {
+            pages.push(buffer_pool_manager.new_page_ref().await?.unwrap());
+        }
+        assert!(!buffer_pool_manager.is_resident(root).await);
+        drop(pages);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let index = Index::<Vec<i64>>::open_with_options(
+            buffer_pool_manager,
+            root,
+            IndexOptions::default(),
+        )
+        .await?;
+        assert_eq!(index.max_size, 2 * DEFAULT_MAX_SIZE);
+        for i in 0..40 {
+            assert_eq!(index.search(&key(i)).await?.unwrap().page_id, i as PageId);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn long_keys() -> StorageResult<()> {
+        let f = tempfile::NamedTempFile::new()?;
+        let disk_manager = DiskManager::new(f.path()).await?;
+        let buffer_pool_manager = Arc::new(BufferPoolManager::new(100, 2, disk_manager).await?);
+        let index = Index::<String>::new(buffer_pool_manager.clone(), DEFAULT_MAX_SIZE).await?;
+        // a full node of these keys is several times larger than a page
+        let key = |i: usize| format!("{:0200}", i);
+        assert!(DEFAULT_MAX_SIZE * key(0).encoded_size() > PAGE_PAYLOAD_SIZE);
+        // 7919 is coprime to 2000, so every key is inserted once out of order
+        for i in (0..2000).map(|i| i * 7919 % 2000) {
+            index
+                .insert(
+                    key(i),
+                    RecordId {
+                        page_id: i,
+                        slot_num: 0,
+                    },
+                )
+                .await?;
+        }
+        // every node fits in its page, though it has fewer entries than max size
+        let mut pages = VecDeque::from([*index.root.read().await]);
+        while let Some(page_id) = pages.pop_front() {
+            let (_, node) = buffer_pool_manager
+                .fetch_page_node::<String>(page_id)
+                .await?;
+            assert!(!node.is_overflow(PAGE_PAYLOAD_SIZE));
+            if let Node::Internal(internal) = node {
+                pages.extend(internal.kv.iter().map(|(_, child)| *child));
+            }
+        }
+        for i in 0..2000 {
+            assert_eq!(index.search(&key(i)).await?.unwrap().page_id, i);
+        }
+        assert_eq!(
+            index.search_range(..).await?,
+            (0..2000)
+                .map(|i| RecordId {
+                    page_id: i,
+                    slot_num: 0
+                })
+                .collect::<Vec<_>>()
+        );
+
+        let long = "a".repeat(index.max_key_size());
+        assert!(matches!(
+            index
+                .insert(
+                    long,
+                    RecordId {
+                        page_id: 0,
+                        slot_num: 0
+                    }
+                )
+                .await,
+            Err(Error::Value(_))
+        ));
+        Ok(())
+    }
+
+    /// Distinct strings of 1 to 300 letters, generated by xorshift so every run is the same.
+    /// The long ones are large enough to split a node before it has max size entries
+    fn random_strings(len: usize) -> Vec<String> {
+        let mut state = 0x2545f4914f6cdd1d_u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        let mut seen = HashSet::new();
+        let mut strings = Vec::with_capacity(len);
+        while strings.len() < len {
+            let length = 1 + next() as usize % 300;
+            let string = (0..length)
+                .map(|_| (b'a' + (next() % 26) as u8) as char)
+                .collect::<String>();
+            if seen.insert(string.clone()) {
+                strings.push(string);
+            }
+        }
+        strings
+    }
+
+    #[tokio::test]
+    async fn string_keys() -> StorageResult<()> {
+        let strings = random_strings(3000);
+        let record = |i: usize| RecordId {
+            page_id: i,
+            slot_num: 0,
+        };
+        for max_size in [4, DEFAULT_MAX_SIZE] {
+            let f = tempfile::NamedTempFile::new()?;
+            let disk_manager = DiskManager::new(f.path()).await?;
+            let buffer_pool_manager = Arc::new(BufferPoolManager::new(100, 2, disk_manager).await?);
+            let index = Index::<String>::new(buffer_pool_manager, max_size).await?;
+            for (i, string) in strings.iter().enumerate() {
+                index.insert(string.clone(), record(i)).await?;
+            }
+            for (i, string) in strings.iter().enumerate() {
+                assert_eq!(index.search(string).await?, Some(record(i)));
+            }
+            assert_eq!(index.search(&"0".to_string()).await?, None);
+
+            let mut sorted = strings.iter().enumerate().collect::<Vec<_>>();
+            sorted.sort_by_key(|(_, string)| *string);
+            let (start, end) = ("g".to_string(), "p".to_string());
+            assert_eq!(
+                index.search_range(&start..&end).await?,
+                sorted
+                    .iter()
+                    .filter(|(_, string)| (&start..&end).contains(string))
+                    .map(|(i, _)| record(*i))
+                    .collect::<Vec<_>>()
+            );
+            assert_eq!(
+                index.search_range(..).await?,
+                sorted.iter().map(|(i, _)| record(*i)).collect::<Vec<_>>()
+            );
+
+            // delete in the order of the reversed strings, which is unrelated to their order
+            let mut deleting = strings.iter().enumerate().collect::<Vec<_>>();
+            deleting.sort_by_key(|(_, string)| string.chars().rev().collect::<String>());
+            for (n, (i, string)) in deleting.iter().enumerate() {
+                assert_eq!(
+                    index.delete(string).await?,
+                    Some(((*string).clone(), record(*i)))
+                );
+                if n % 500 == 0 {
+                    for (i, string) in deleting[n + 1..].iter() {
+                        assert_eq!(index.search(string).await?, Some(record(*i)));
+                    }
+                }
+            }
+            assert_eq!(index.len().await?, 0);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn count_range() -> StorageResult<()> {
         let index = test_index().await?;
+        assert_eq!(index.len().await?, 0);
+        assert_eq!(index.count_range(&1..&10).await?, 0);
         let keys = (1..1000).collect::<Vec<_>>();
-        insert_inner(&index, &keys.iter().rev().copied().collect::<Vec<_>>()).await?;
-        let range = index
-            .search_range((Bound::Unbounded, Bound::Included(&1000)))
-            .await?;
-        assert_eq!(range.len(), 999);
-        let range = index.search_range(&100..).await?;
-        assert_eq!(range.len(), 900);
-        let range = index.search_range(..=&800).await?;
-        assert_eq!(range.len(), 800);
-        let range = index.search_range::<_>(RangeFull).await?;
-        assert_eq!(range.len(), 999);
-        let range = index.search_range(&0..&900).await?;
-        assert_eq!(range.len(), 899);
-        let range = index
-            .search_range((Bound::Excluded(&100), Bound::Included(&1000)))
-            .await?;
-        assert_eq!(range.len(), 899);
-        let range = index.search_range(&1..=&1000).await?;
-        assert_eq!(range.len(), 999);
-        for (index, record) in range.into_iter().enumerate() {
-            assert_eq!(index + 1, record.page_id);
-        }
-
-        let range = index.search_range(&801..=&900).await?;
-        for (index, record) in range.into_iter().enumerate() {
-            assert_eq!(index + 801, record.page_id);
+        insert_inner(&index, &keys).await?;
+        for key in (200..700).step_by(3) {
+            index.delete(&key).await?;
         }
-
-        let range = index.search_range(&800..=&1200).await?;
-        assert_eq!(range.len(), 200);
-        for (index, record) in range.into_iter().enumerate() {
-            assert_eq!(index + 800, record.page_id);
+        let ranges: Vec<(Bound<&u32>, Bound<&u32>)> = vec![
+            (Bound::Unbounded, Bound::Unbounded),
+            (Bound::Included(&100), Bound::Unbounded),
+            (Bound::Unbounded, Bound::Included(&800)),
+            (Bound::Included(&0), Bound::Excluded(&900)),
+            (Bound::Excluded(&100), Bound::Included(&1000)),
+            (Bound::Excluded(&200), Bound::Excluded(&203)),
+            (Bound::Included(&500), Bound::Included(&500)),
+            (Bound::Included(&501), Bound::Included(&501)),
+            (Bound::Included(&500), Bound::Excluded(&100)),
+            (Bound::Included(&1000), Bound::Unbounded),
+        ];
+        for range in ranges {
+            assert_eq!(
+                index.count_range(range).await?,
+                index.search_range(range).await?.len(),
+                "{:?}",
+                range
+            );
         }
+        assert_eq!(index.len().await?, index.entries().await?.len());
 
-        let range = index.search_range(&0..=&1200).await?;
-        assert_eq!(range.len(), 999);
-        for (index, record) in range.into_iter().enumerate() {
-            assert_eq!(index + 1, record.page_id);
+        // the equal keys of a non-unique index are all counted
+        let f = tempfile::NamedTempFile::new()?;
+        let disk_manager = DiskManager::new(f.path()).await?;
+        let buffer_pool_manager = Arc::new(BufferPoolManager::new(100, 2, disk_manager).await?);
+        let index = Index::with_options(
+            buffer_pool_manager,
+            IndexOptions::default()
+                .with_max_size(4)
+                .with_order(SortOrder::Descending)
+                .with_unique(false),
+        )
+        .await?;
+        for slot_num in 0..10 {
+            for key in 0..10u32 {
+                index
+                    .insert(
+                        key,
+                        RecordId {
+                            page_id: key as PageId,
+                            slot_num,
+                        },
+                    )
+                    .await?;
+            }
         }
+        assert_eq!(index.len().await?, 100);
+        assert_eq!(index.count_range(&3..=&5).await?, 30);
+        assert_eq!(index.count_range(&3..&5).await?, 20);
+        assert_eq!(index.count_range(&9..).await?, 10);
+        assert_eq!(index.count_range(&10..).await?, 0);
         Ok(())
     }
+
     #[tokio::test]
     async fn insert() -> StorageResult<()> {
         let keys: Vec<u32> = (1..100).collect::<Vec<_>>();
@@ -1048,6 +3482,103 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn duplicate_keys() -> StorageResult<()> {
+        let f = tempfile::NamedTempFile::new()?;
+        let disk_manager = DiskManager::new(f.path()).await?;
+        let buffer_pool_manager = Arc::new(BufferPoolManager::new(100, 2, disk_manager).await?);
+        let index = Index::with_options(
+            buffer_pool_manager,
+            IndexOptions::default().with_max_size(4).with_unique(false),
+        )
+        .await?;
+        assert!(!index.unique());
+        let record = |key: u32, slot_num: u32| RecordId {
+            page_id: key as PageId,
+            slot_num,
+        };
+        // the equal keys of each key span several leaves
+        for slot_num in 0..20 {
+            for key in (0..10).rev() {
+                index.insert(key, record(key, slot_num)).await?;
+            }
+        }
+        for key in 0..10 {
+            assert_eq!(
+                index.search_all(&key).await?,
+                (0..20)
+                    .map(|slot_num| record(key, slot_num))
+                    .collect::<Vec<_>>()
+            );
+            assert_eq!(index.search(&key).await?.unwrap().page_id, key as PageId);
+        }
+        assert!(index.search_all(&10).await?.is_empty());
+        assert!(!index.contains(&10).await?);
+        let expected = (3..=5)
+            .flat_map(|key| (0..20).map(move |slot_num| record(key, slot_num)))
+            .collect::<Vec<_>>();
+        assert_eq!(index.search_range(&3..=&5).await?, expected);
+        let streamed = index
+            .scan_stream(3..=5)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<StorageResult<Vec<_>>>()?;
+        assert_eq!(streamed, expected);
+        let mut reversed = expected.clone();
+        reversed.reverse();
+        assert_eq!(index.search_range_rev(&3..=&5).await?, reversed);
+        assert_eq!(index.search_range(&2..&3).await?.len(), 20);
+        assert_eq!(
+            index
+                .search_range((Bound::Excluded(&3), Bound::Excluded(&5)))
+                .await?,
+            (0..20)
+                .map(|slot_num| record(4, slot_num))
+                .collect::<Vec<_>>()
+        );
+
+        // only the requested entry is deleted
+        assert!(index.delete_entry(&4, record(4, 7)).await?);
+        assert!(!index.delete_entry(&4, record(4, 7)).await?);
+        assert!(!index.delete_entry(&3, record(4, 8)).await?);
+        assert_eq!(
+            index.search_all(&4).await?,
+            (0..20)
+                .filter(|slot_num| *slot_num != 7)
+                .map(|slot_num| record(4, slot_num))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(index.search_all(&3).await?.len(), 20);
+        assert_eq!(index.search_all(&5).await?.len(), 20);
+        assert_eq!(index.delete(&4).await?.unwrap().0, 4);
+        assert_eq!(index.search_all(&4).await?.len(), 18);
+        for slot_num in [
+            19, 0, 10, 5, 1, 18, 2, 17, 3, 16, 4, 15, 6, 14, 7, 13, 8, 12, 9, 11,
+        ] {
+            assert!(index.delete_entry(&5, record(5, slot_num)).await?);
+        }
+        assert!(index.search_all(&5).await?.is_empty());
+        assert!(!index.contains(&5).await?);
+        assert_eq!(index.delete_range(&2..=&3).await?, 40);
+        assert_eq!(index.entries().await?.len(), 20 * 6 + 18);
+        assert_eq!(index.reindex().await?, 20 * 6 + 18);
+        assert_eq!(index.search_all(&4).await?.len(), 18);
+        assert_eq!(
+            index.search_all(&9).await?,
+            (0..20)
+                .map(|slot_num| record(9, slot_num))
+                .collect::<Vec<_>>()
+        );
+
+        // a unique index overwrites the equal key
+        let index = test_index().await?;
+        index.insert(1, record(1, 0)).await?;
+        index.insert(1, record(1, 1)).await?;
+        assert_eq!(index.search_all(&1).await?, vec![record(1, 1)]);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn search_concurrency() -> StorageResult<()> {
         let index = Arc::new(test_index().await?);
@@ -1165,6 +3696,193 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn reindex() -> StorageResult<()> {
+        let len = 1000;
+        let index = Arc::new(test_index().await?);
+        insert_concurrency_inner(index.clone(), len, 1).await?;
+        let done = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let readers = (0..4)
+            .map(|reader| {
+                let index = index.clone();
+                let done = done.clone();
+                tokio::spawn(async move {
+                    let mut rounds = 0;
+                    while !done.load(atomic::Ordering::SeqCst) || rounds == 0 {
+                        for i in (reader..len).step_by(7) {
+                            let val = index.search(&(i as u32)).await?;
+                            assert_eq!(val.map(|record_id| record_id.page_id), Some(i));
+                        }
+                        assert_eq!(index.search_range(..).await?.len(), len);
+                        // the reverse scans follow the prev links of the old tree
+                        assert_eq!(index.search_range_rev(..).await?.len(), len);
+                        rounds += 1;
+                    }
+                    Ok::<_, Error>(())
+                })
+            })
+            .collect::<Vec<_>>();
+        for _ in 0..3 {
+            let root = *index.root.read().await;
+            assert_eq!(index.reindex().await?, len);
+            assert_ne!(*index.root.read().await, root);
+        }
+        done.store(true, atomic::Ordering::SeqCst);
+        for reader in readers {
+            reader.await.unwrap()?;
+        }
+
+        // the writes during the rebuild aren't lost
+        let writer = {
+            let index = index.clone();
+            tokio::spawn(async move {
+                for i in len..len + 500 {
+                    index.insert(i as u32, RecordId::new(i, 0)).await?;
+                }
+                Ok::<_, Error>(())
+            })
+        };
+        index.reindex().await?;
+        writer.await.unwrap()?;
+        for i in 0..len + 500 {
+            assert_eq!(index.search(&(i as u32)).await?, Some(RecordId::new(i, 0)));
+        }
+        assert_eq!(index.reindex().await?, len + 500);
+
+        // a writer which never stops makes it rebuild with the root latched, it keeps the
+        // last 50 keys it inserted
+        let done = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let writer = {
+            let index = index.clone();
+            let done = done.clone();
+            tokio::spawn(async move {
+                let mut i = len + 500;
+                while !done.load(atomic::Ordering::SeqCst) || i < len + 550 {
+                    index.insert(i as u32, RecordId::new(i, 0)).await?;
+                    if i >= len + 550 {
+                        assert!(index.delete(&(i as u32 - 50)).await?.is_some());
+                    }
+                    i += 1;
+                    tokio::task::yield_now().await;
+                }
+                Ok::<_, Error>(i)
+            })
+        };
+        let reindexed = index.reindex().await?;
+        done.store(true, atomic::Ordering::SeqCst);
+        let written = writer.await.unwrap()?;
+        // an inserted key may not be deleted yet
+        assert!((len + 500..=len + 551).contains(&reindexed));
+        for i in (0..len + 500).chain(written - 50..written) {
+            assert_eq!(index.search(&(i as u32)).await?, Some(RecordId::new(i, 0)));
+        }
+        assert_eq!(index.len().await?, len + 550);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn reindex_writer_below_root() -> StorageResult<()> {
+        let f = tempfile::NamedTempFile::new()?;
+        let disk_manager = DiskManager::new(f.path()).await?;
+        let buffer_pool_manager = Arc::new(BufferPoolManager::new(100, 2, disk_manager).await?);
+        // the nodes split by sequential inserts are half full, so a writer releases the root
+        let index = Arc::new(Index::new(buffer_pool_manager.clone(), 8).await?);
+        for i in (0..2000).step_by(2) {
+            index.insert(i as u32, RecordId::new(i, 0)).await?;
+        }
+        let path = index
+            .entry_path(&1000, RecordId::new(1000, 0))
+            .await?
+            .unwrap();
+        assert!(path.len() > 3);
+        let (_, leaf) = buffer_pool_manager.fetch_page_node::<u32>(path[0]).await?;
+        assert!(leaf
+            .assume_leaf_ref()
+            .kv
+            .iter()
+            .any(|(key, _)| *key == 1002));
+        // the writer stops at the parent of its leaf, after it has latched the root
+        let parent = buffer_pool_manager.fetch_page_write_owned(path[1]).await?;
+        let writer = {
+            let index = index.clone();
+            tokio::spawn(async move { index.insert(1001, RecordId::new(1001, 0)).await })
+        };
+        let inside = async {
+            while index.writers.load(atomic::Ordering::SeqCst) == 0
+                || index.root.try_read().is_err()
+            {
+                tokio::task::yield_now().await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(5), inside)
+            .await
+            .unwrap();
+        // the rebuild starts while the writer is below the root, the root isn't swapped until
+        // the write has landed
+        let reindex = {
+            let index = index.clone();
+            tokio::spawn(async move { index.reindex().await })
+        };
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        drop(parent);
+        writer.await.unwrap()?;
+        assert_eq!(reindex.await.unwrap()?, 1001);
+        assert_eq!(index.search(&1001).await?, Some(RecordId::new(1001, 0)));
+        assert_eq!(index.len().await?, 1001);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn scan_consistency() -> StorageResult<()> {
+        let len = 1000;
+        let index = Arc::new(test_index().await?);
+        // the even keys stay, the odd keys come and go
+        for i in (0..len).step_by(2) {
+            index.insert(i as u32, RecordId::new(i, 0)).await?;
+        }
+        let done = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let writers = (0..2)
+            .map(|writer| {
+                let index = index.clone();
+                let done = done.clone();
+                tokio::spawn(async move {
+                    let odd = (writer * 2 + 1..len).step_by(4).collect::<Vec<_>>();
+                    while !done.load(atomic::Ordering::SeqCst) {
+                        // the inserts split the leaves, and the deletes merge them back
+                        for i in &odd {
+                            index.insert(*i as u32, RecordId::new(*i, 0)).await?;
+                        }
+                        for i in &odd {
+                            assert!(index.delete(&(*i as u32)).await?.is_some());
+                        }
+                    }
+                    Ok::<_, Error>(())
+                })
+            })
+            .collect::<Vec<_>>();
+        let expected = (0..len as u32).step_by(2).collect::<Vec<_>>();
+        for _ in 0..50 {
+            let keys = index
+                .entries()
+                .await?
+                .into_iter()
+                .map(|(key, _)| key)
+                .collect::<Vec<_>>();
+            // no key is returned twice
+            assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+            let stable = keys
+                .into_iter()
+                .filter(|key| key % 2 == 0)
+                .collect::<Vec<_>>();
+            assert_eq!(stable, expected);
+        }
+        done.store(true, atomic::Ordering::SeqCst);
+        for writer in writers {
+            writer.await.unwrap()?;
+        }
+        Ok(())
+    }
+
     #[tokio::test]
     async fn multiple_index() -> StorageResult<()> {
         let f = tempfile::NamedTempFile::new()?;
@@ -1190,4 +3908,494 @@ mod tests {
         assert!(index2.search(&101).await?.is_none());
         Ok(())
     }
+
+    #[tokio::test]
+    async fn descending() -> StorageResult<()> {
+        let f = tempfile::NamedTempFile::new()?;
+        let disk_manager = DiskManager::new(f.path()).await?;
+        let buffer_pool_manager = Arc::new(BufferPoolManager::new(100, 2, disk_manager).await?);
+        let index = Index::new_with_order(buffer_pool_manager, 4, SortOrder::Descending).await?;
+        assert!(index.first().await?.is_none());
+        assert!(index.last().await?.is_none());
+        let keys: Vec<u32> = (1..1000).collect::<Vec<_>>();
+        insert_inner(&index, &keys).await?;
+        let (key, record_id) = index.first().await?.unwrap();
+        assert_eq!(key, 999);
+        assert_eq!(record_id.page_id, 999);
+        assert_eq!(index.last().await?.unwrap().0, 1);
+        for i in &keys {
+            assert_eq!(index.search(i).await?.unwrap().page_id as u32, *i);
+        }
+        let range = index.search_range(&100..=&200).await?;
+        assert_eq!(
+            range
+                .iter()
+                .map(|record| record.page_id)
+                .collect::<Vec<_>>(),
+            (100..=200).rev().collect::<Vec<_>>()
+        );
+        let range = index.search_range(&900..).await?;
+        assert_eq!(range.len(), 100);
+        assert_eq!(range[0].page_id, 999);
+        for key in (500..1000).rev() {
+            assert!(index.delete(&key).await?.is_some());
+        }
+        assert_eq!(index.first().await?.unwrap().0, 499);
+        assert_eq!(index.search_range::<_>(RangeFull).await?.len(), 499);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn negative_keys() -> StorageResult<()> {
+        let f = tempfile::NamedTempFile::new()?;
+        let disk_manager = DiskManager::new(f.path()).await?;
+        let buffer_pool_manager = Arc::new(BufferPoolManager::new(100, 2, disk_manager).await?);
+        let index = Index::<i64>::new(buffer_pool_manager, 4).await?;
+        // the placeholder key of an internal node is the default 0, which sorts after every
+        // separator here, so the separators must be searched without it
+        let len = 500;
+        for i in 0..len {
+            let key = -((i * 7919) % len) - 1;
+            index
+                .insert(
+                    key,
+                    RecordId {
+                        page_id: -key as PageId,
+                        slot_num: 0,
+                    },
+                )
+                .await?;
+        }
+        for key in -len..0 {
+            assert_eq!(index.search(&key).await?.unwrap().page_id as i64, -key);
+        }
+        let range = index.search_range::<_>(RangeFull).await?;
+        assert_eq!(
+            range
+                .iter()
+                .map(|record| record.page_id)
+                .collect::<Vec<_>>(),
+            (1..=len as PageId).rev().collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn delete_range() -> StorageResult<()> {
+        let index = test_index().await?;
+        let keys: Vec<u32> = (1..1000).collect::<Vec<_>>();
+        insert_inner(&index, &keys).await?;
+        assert_eq!(index.delete_range(&200..=&700).await?, 501);
+        assert_eq!(index.delete_range(&200..=&700).await?, 0);
+        for i in &keys {
+            let val = index.search(i).await?;
+            assert_eq!(val.is_none(), (200..=700).contains(i));
+        }
+        let range = index.search_range::<_>(RangeFull).await?;
+        assert_eq!(
+            range
+                .iter()
+                .map(|record| record.page_id as u32)
+                .collect::<Vec<_>>(),
+            keys.iter()
+                .copied()
+                .filter(|i| !(200..=700).contains(i))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            index
+                .delete_range((Bound::Excluded(&100), Bound::Excluded(&800)))
+                .await?,
+            198
+        );
+        assert!(index.search(&100).await?.is_some());
+        assert!(index.search(&800).await?.is_some());
+        insert_inner(&index, &(101..800).collect::<Vec<_>>()).await?;
+        assert_eq!(index.search_range::<_>(RangeFull).await?.len(), 999);
+        assert_eq!(index.delete_range::<_>(RangeFull).await?, 999);
+        assert!(index.search_range::<_>(RangeFull).await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn corrupt_page() -> StorageResult<()> {
+        let index = test_index().await?;
+        let keys: Vec<u32> = (1..1000).collect::<Vec<_>>();
+        insert_inner(&index, &keys).await?;
+        let mut route = Route::new(RouteOption::default());
+        let page_id = index
+            .find_route(KeyCondition::Equal(&500), &mut route)
+            .await?;
+        drop(route);
+        let (_, node) = index.buffer_pool.fetch_page_node::<u32>(page_id).await?;
+        let corrupted = node
+            .assume_leaf()
+            .kv
+            .iter()
+            .map(|(key, _)| *key)
+            .collect::<Vec<_>>();
+        index
+            .buffer_pool
+            .fetch_page_write_owned(page_id)
+            .await?
+            .fill(u8::MAX);
+
+        assert!(index.search(&500).await.is_err());
+        assert!(index.insert(500, RecordId::new(500, 0)).await.is_err());
+        assert!(index.delete(&500).await.is_err());
+        // every latch is released, so the operations on other pages still work
+        tokio::time::timeout(std::time::Duration::from_secs(10), async {
+            for i in keys.iter().filter(|i| !corrupted.contains(i)).step_by(7) {
+                assert_eq!(index.search(i).await?, Some(RecordId::new(*i as PageId, 0)));
+            }
+            for i in 1..20 {
+                assert!(index.delete(&i).await?.is_some());
+            }
+            insert_inner(&index, &(1000..1100).collect::<Vec<_>>()).await?;
+            for i in 1000..1100 {
+                assert!(index.search(&i).await?.is_some());
+            }
+            Ok::<_, Error>(())
+        })
+        .await
+        .expect("latches are not released")?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rebuild_links() -> StorageResult<()> {
+        let index = test_index().await?;
+        let keys = (1..200).collect::<Vec<_>>();
+        insert_inner(&index, &keys).await?;
+        let mut route = Route::new(RouteOption::default());
+        let page_id = index
+            .find_route(KeyCondition::Equal(&50), &mut route)
+            .await?;
+        drop(route);
+        // skip the leaf after the one of 50
+        let (_, node) = index.buffer_pool.fetch_page_node::<u32>(page_id).await?;
+        let next = node.assume_leaf().next().unwrap();
+        let (_, next) = index.buffer_pool.fetch_page_node::<u32>(next).await?;
+        let skipped = next.assume_leaf_ref().kv.len();
+        {
+            let mut latch = index.buffer_pool.fetch_page_write_owned(page_id).await?;
+            let mut node = latch.node::<u32>()?;
+            node.assume_leaf_mut().header.next = next.assume_leaf().next();
+            latch.write_node_back(&node)?;
+        }
+        let range = index.search_range(&1..&200).await?;
+        assert_eq!(range.len(), 199 - skipped);
+
+        index.rebuild_links().await?;
+        let range = index.search_range(&1..&200).await?;
+        assert_eq!(
+            range,
+            keys.iter()
+                .map(|i| RecordId::new(*i as PageId, 0))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(index.search_range(&120..=&130).await?.len(), 11);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn redistribute() -> StorageResult<()> {
+        let index = test_index().await?;
+        let keys = (1..200).collect::<Vec<_>>();
+        insert_inner(&index, &keys).await?;
+        let mut route = Route::new(RouteOption::default());
+        let mut page_id = Some(index.find_route(KeyCondition::Min, &mut route).await?);
+        drop(route);
+        let mut leaves = Vec::new();
+        while let Some(id) = page_id {
+            let (_, node) = index.buffer_pool.fetch_page_node::<u32>(id).await?;
+            let leaf = node.assume_leaf();
+            leaves.push(leaf.kv.len());
+            page_id = leaf.next();
+        }
+        // splitting alone leaves every leaf of sequential inserts half full,
+        // the left leaves are filled up before splitting the last one
+        assert!(leaves.len() < keys.len() / 2);
+        assert!(leaves[..leaves.len() - 2].iter().all(|len| *len == 3));
+        assert_eq!(leaves.iter().sum::<usize>(), keys.len());
+        assert_eq!(
+            index.search_range(..).await?,
+            keys.iter()
+                .map(|i| RecordId::new(*i as PageId, 0))
+                .collect::<Vec<_>>()
+        );
+        // inserting before a full leaf shifts its last key to the next leaf
+        insert_inner(&index, &[0]).await?;
+        assert_eq!(index.search_range(&0..&4).await?.len(), 4);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn coalesce() -> StorageResult<()> {
+        let f = tempfile::NamedTempFile::new()?;
+        let disk_manager = DiskManager::new(f.path()).await?;
+        let buffer_pool_manager = BufferPoolManager::new(100, 2, disk_manager).await?;
+        // a leaf holds at most 8 keys and underflows below 4
+        let index = Index::<u32>::new(Arc::new(buffer_pool_manager), 9).await?;
+        insert_inner(&index, &(1..500).collect::<Vec<_>>()).await?;
+        let leaves = |index: &Index<u32>| {
+            let buffer_pool = index.buffer_pool.clone();
+            let root = *index.root.try_read().unwrap();
+            async move {
+                let mut page_id = root;
+                while let Node::Internal(internal) =
+                    buffer_pool.fetch_page_node::<u32>(page_id).await?.1
+                {
+                    page_id = internal.kv[0].1;
+                }
+                let (mut leaves, mut prev, mut page_id) = (Vec::new(), None, Some(page_id));
+                while let Some(id) = page_id {
+                    let leaf = buffer_pool
+                        .fetch_page_node::<u32>(id)
+                        .await?
+                        .1
+                        .assume_leaf();
+                    assert_eq!(leaf.prev(), prev);
+                    assert!(!leaf.is_overflow() && !leaf.is_underflow());
+                    leaves.push(leaf.kv.iter().map(|(key, _)| *key).collect::<Vec<_>>());
+                    (prev, page_id) = (Some(id), leaf.next());
+                }
+                Ok::<_, Error>(leaves)
+            }
+        };
+        // leave every leaf just above underflow
+        for leaf in leaves(&index).await? {
+            for key in leaf.iter().skip(4) {
+                index.delete(key).await?;
+            }
+        }
+        let before = leaves(&index).await?;
+        assert!(before.iter().all(|leaf| leaf.len() == 4));
+        let keys = before.concat();
+
+        let merged = index.coalesce().await?;
+        let after = leaves(&index).await?;
+        assert_eq!(after.len(), before.len() - merged);
+        assert!(after.len() < before.len());
+        assert_eq!(after.concat(), keys);
+        for key in keys.iter() {
+            assert_eq!(
+                index.search(key).await?,
+                Some(RecordId::new(*key as PageId, 0))
+            );
+        }
+        assert_eq!(index.search_range(..).await?.len(), keys.len());
+        // the merged tree takes writes as before
+        index.delete(&keys[0]).await?;
+        insert_inner(&index, &[1000]).await?;
+        assert_eq!(index.search_range(..).await?.len(), keys.len());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_ahead() -> StorageResult<()> {
+        let index = test_index().await?.with_read_ahead(3);
+        let keys: Vec<u32> = (1..1000).collect::<Vec<_>>();
+        insert_inner(&index, &keys).await?;
+        let mut route = Route::new(RouteOption::default());
+        let mut next = Some(index.find_route(KeyCondition::Min, &mut route).await?);
+        drop(route);
+        let mut leaves = Vec::new();
+        while let (Some(page_id), true) = (next, leaves.len() < 5) {
+            leaves.push(page_id);
+            next = index
+                .buffer_pool
+                .fetch_page_node::<u32>(page_id)
+                .await?
+                .1
+                .assume_leaf()
+                .next();
+        }
+        // evict the first leaves by scanning the others
+        index.search_range(&500..).await?;
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        for page_id in &leaves[1..] {
+            assert!(!index.buffer_pool.is_resident(*page_id).await);
+        }
+        index.search_range(&1..=&1).await?;
+        for page_id in &leaves[1..4] {
+            assert!(index.buffer_pool.is_resident(*page_id).await);
+        }
+        assert!(!index.buffer_pool.is_resident(leaves[4]).await);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn multi_search() -> StorageResult<()> {
+        let index = test_index().await?;
+        let len = 1000;
+        for i in (0..len).step_by(2) {
+            index.insert(i, RecordId::new(i as PageId, 0)).await?;
+        }
+        // scattered keys in no order, some missing and some repeated
+        let keys = (0..100u32)
+            .map(|i| (i * 397 + 11) % (len + 10))
+            .chain([4, 4, len + 100])
+            .collect::<Vec<_>>();
+        let accesses = || index.buffer_pool.stats().accesses();
+        let before = accesses();
+        let found = index.multi_search(&keys).await?;
+        let multi_accesses = accesses() - before;
+
+        let before = accesses();
+        let mut expected = Vec::with_capacity(keys.len());
+        for key in &keys {
+            expected.push(index.search(key).await?);
+        }
+        let single_accesses = accesses() - before;
+        assert_eq!(found, expected);
+        assert!(found.iter().any(Option::is_some));
+        assert!(found.iter().any(Option::is_none));
+        assert!(multi_accesses < single_accesses);
+
+        assert!(index.multi_search(&[]).await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn contains() -> StorageResult<()> {
+        let index = test_index().await?;
+        let keys: Vec<u32> = (0..500).map(|i| i * 2).collect::<Vec<_>>();
+        insert_inner(&index, &keys).await?;
+        for i in 0..1000 {
+            assert_eq!(index.contains(&i).await?, i % 2 == 0);
+        }
+        assert!(!index.contains(&1000).await?);
+        index.delete(&10).await?;
+        assert!(!index.contains(&10).await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn timeout() -> StorageResult<()> {
+        let index = test_index().await?.with_timeout(Duration::from_millis(100));
+        insert_inner(&index, &[1, 2, 3]).await?;
+        let root = *index.root.read().await;
+        let latch = index.buffer_pool.fetch_page_write_owned(root).await?;
+        let operations = async {
+            assert!(matches!(index.search(&1).await, Err(Error::Timeout(_))));
+            assert!(matches!(
+                index.insert(4, RecordId::new(4, 4)).await,
+                Err(Error::Timeout(_))
+            ));
+            assert!(matches!(index.delete(&1).await, Err(Error::Timeout(_))));
+        };
+        tokio::time::timeout(Duration::from_secs(10), operations)
+            .await
+            .expect("the operations should time out instead of blocking");
+        drop(latch);
+        assert_eq!(index.search(&1).await?, Some(RecordId::new(1, 0)));
+        index.insert(4, RecordId::new(4, 4)).await?;
+        assert!(index.contains(&4).await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn options() -> StorageResult<()> {
+        let f = tempfile::NamedTempFile::new()?;
+        let disk_manager = DiskManager::new(f.path()).await?;
+        let buffer_pool = Arc::new(BufferPoolManager::new(100, 2, disk_manager).await?);
+        let options = IndexOptions::default()
+            .with_max_size(4)
+            .with_order(SortOrder::Descending)
+            .with_read_ahead(0)
+            .with_timeout(Duration::from_secs(1));
+        let index = Index::<u32>::with_options(buffer_pool, options).await?;
+        assert_eq!(index.max_size, 4);
+        assert_eq!(index.order(), SortOrder::Descending);
+        assert_eq!(index.read_ahead, 0);
+        assert_eq!(index.timeout, Some(Duration::from_secs(1)));
+        insert_inner(&index, &(0..100).collect::<Vec<_>>()).await?;
+        assert_eq!(index.first().await?, Some((99, RecordId::new(99, 0))));
+        assert_eq!(
+            index.search_range(&5..=&10).await?,
+            (5..=10)
+                .rev()
+                .map(|i| RecordId::new(i, 0))
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn null_order() -> StorageResult<()> {
+        let f = tempfile::NamedTempFile::new()?;
+        let disk_manager = DiskManager::new(f.path()).await?;
+        let buffer_pool_manager = Arc::new(BufferPoolManager::new(100, 2, disk_manager).await?);
+        let keys = |range: Vec<RecordId>| {
+            range
+                .into_iter()
+                .map(|record_id| record_id.page_id)
+                .collect::<Vec<_>>()
+        };
+        // the NULL key points to page 100
+        for (order, nulls) in [
+            (SortOrder::Ascending, NullOrder::First),
+            (SortOrder::Ascending, NullOrder::Last),
+            (SortOrder::Descending, NullOrder::First),
+            (SortOrder::Descending, NullOrder::Last),
+        ] {
+            let index = Index::<Value>::with_options(
+                buffer_pool_manager.clone(),
+                IndexOptions::default()
+                    .with_max_size(4)
+                    .with_order(order)
+                    .with_nulls(nulls),
+            )
+            .await?;
+            assert_eq!(index.nulls(), nulls);
+            for i in (0..50).rev() {
+                index
+                    .insert(Value::Integer(i), RecordId::new(i as usize, 0))
+                    .await?;
+                if i == 25 {
+                    index.insert(Value::Null, RecordId::new(100, 0)).await?;
+                }
+            }
+            assert_eq!(
+                index.search(&Value::Null).await?,
+                Some(RecordId::new(100, 0))
+            );
+            let mut non_null = (0..50).collect::<Vec<_>>();
+            if order == SortOrder::Descending {
+                non_null.reverse();
+            }
+            let mut all = non_null.clone();
+            match nulls {
+                NullOrder::First => all.insert(0, 100),
+                NullOrder::Last => all.push(100),
+            }
+            assert_eq!(keys(index.search_range(..).await?), all);
+            assert_eq!(index.first().await?.unwrap().1.page_id, all[0]);
+            // NULL is only in the range when the range is unbounded at its side
+            let from_smallest = keys(index.search_range(&Value::Integer(0)..).await?);
+            let to_largest = keys(index.search_range(..=&Value::Integer(49)).await?);
+            let null_after_smallest = match order {
+                SortOrder::Ascending => nulls == NullOrder::Last,
+                SortOrder::Descending => nulls == NullOrder::First,
+            };
+            let (with_null, without_null) = match null_after_smallest {
+                true => (from_smallest, to_largest),
+                false => (to_largest, from_smallest),
+            };
+            assert_eq!(with_null, all);
+            assert_eq!(without_null, non_null);
+            assert_eq!(
+                keys(
+                    index
+                        .search_range(&Value::Integer(0)..=&Value::Integer(49))
+                        .await?
+                ),
+                non_null
+            );
+            assert!(index.delete(&Value::Null).await?.is_some());
+            assert_eq!(keys(index.search_range(..).await?), non_null);
+        }
+        Ok(())
+    }
 }
@@ -2,20 +2,29 @@ use crate::sql::types::Value;
 use crate::storage::page::column::Column;
 use crate::storage::page::table::{Tuple, Tuples};
 use crate::storage::table::Table;
-use crate::{buffer, encoding};
+use crate::{buffer, catalog, encoding};
 use futures::Stream;
+use std::collections::BTreeSet;
 use std::future::Future;
 use std::ops::RangeBounds;
 use std::sync::atomic::{AtomicU64, AtomicUsize};
+use std::sync::Arc;
 use thiserror::Error;
 
 pub mod disk;
-mod engine;
+pub mod engine;
 mod index;
 pub mod page;
 pub mod table;
+mod value_index;
 
+/// The size of a page unless the buffer pool is created with another one,
+/// see [`BufferPoolManager::with_page_size`](crate::buffer::buffer_pool_manager::BufferPoolManager::with_page_size)
 pub const PAGE_SIZE: usize = 4096;
+/// The bytes at the start of every page which hold the CRC32 checksum of the rest of it
+pub const CHECKSUM_SIZE: usize = 4;
+/// The bytes of a page of [`PAGE_SIZE`] which its content is encoded into
+pub const PAGE_PAYLOAD_SIZE: usize = PAGE_SIZE - CHECKSUM_SIZE;
 pub type PageId = usize;
 
 pub type AtomicPageId = AtomicUsize;
@@ -23,7 +32,7 @@ pub const NULL_PAGE: PageId = PageId::MAX;
 pub type TimeStamp = u64;
 pub type AtomicTimeStamp = AtomicU64;
 
-#[derive(Debug, Copy, Clone, PartialOrd, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct RecordId {
     pub page_id: PageId,
     pub slot_num: u32,
@@ -42,12 +51,16 @@ pub enum Error {
     Buffer(#[from] buffer::Error),
     #[error("encoding error {0}")]
     Encoding(#[from] encoding::error::Error),
+    #[error("catalog error {0}")]
+    Catalog(#[from] catalog::error::Error),
     #[error("io error: {0}")]
     IO(#[from] std::io::Error),
     #[error("{0} {1} not found")]
     NotFound(&'static str, String),
     #[error("{0}")]
     Value(String),
+    #[error("timed out waiting for {0}")]
+    Timeout(&'static str),
 }
 
 pub trait Storage {
@@ -65,11 +78,29 @@ pub trait Storage {
 
     fn read(&self, name: &str, key: &Value) -> impl Future<Output = StorageResult<Option<Tuple>>>;
 
+    /// Whether the table has a tuple with the primary key, without reading the tuple
+    fn contains(&self, name: &str, key: &Value) -> impl Future<Output = StorageResult<bool>>;
+
     fn delete(&self, name: &str, key: &Value)
         -> impl Future<Output = StorageResult<Option<Tuple>>>;
 
+    /// Mark the tuple deleted but keep it in the table, its key stays taken until [`Storage::vacuum`].
+    /// Returns the tuple, or None if there's no tuple with the key or it's already deleted.
+    fn soft_delete(
+        &self,
+        name: &str,
+        key: &Value,
+    ) -> impl Future<Output = StorageResult<Option<Tuple>>>;
+
+    /// Physically remove the deleted tuples of the table, returns the number of removed tuples
+    fn vacuum(&self, name: &str) -> impl Future<Output = StorageResult<usize>>;
+
     fn update(&self, name: &str, tuple: Tuple) -> impl Future<Output = StorageResult<Option<()>>>;
 
+    /// Hand out the next value of the table's auto-increment sequence.
+    /// Inserting a tuple moves the sequence past the value of its auto-increment column.
+    fn next_sequence(&self, name: &str) -> impl Future<Output = StorageResult<i64>>;
+
     fn scan<'a, R>(
         &self,
         name: &str,
@@ -78,4 +109,62 @@ pub trait Storage {
     where
         R: RangeBounds<&'a Value>,
         Value: 'a;
+
+    /// Like [`Storage::scan`], with the options deciding which tuples are returned
+    fn scan_with<'a, R>(
+        &self,
+        name: &str,
+        range: R,
+        options: ScanOptions,
+    ) -> impl Future<Output = StorageResult<impl Stream<Item = StorageResult<Tuple>>>>
+    where
+        R: RangeBounds<&'a Value>,
+        Value: 'a;
+}
+
+/// The options of [`Storage::scan_with`]
+#[derive(Debug, Default, Clone)]
+pub struct ScanOptions {
+    /// Also return the soft-deleted tuples
+    pub include_deleted: bool,
+    pub on_error: OnScanError,
+}
+
+impl ScanOptions {
+    pub fn with_include_deleted(mut self, include_deleted: bool) -> Self {
+        self.include_deleted = include_deleted;
+        self
+    }
+
+    pub fn with_on_error(mut self, on_error: OnScanError) -> Self {
+        self.on_error = on_error;
+        self
+    }
+}
+
+/// What a scan does with a page of the table which fails to decode
+#[derive(Debug, Default, Clone)]
+pub enum OnScanError {
+    /// Fail the scan with the error
+    #[default]
+    Abort,
+    /// Skip the tuples of the page, and record the page in the report
+    SkipAndReport(ScanReport),
+}
+
+/// The pages skipped by scans, the clones of a report share the same pages
+#[derive(Debug, Default, Clone)]
+pub struct ScanReport {
+    failed_pages: Arc<std::sync::Mutex<BTreeSet<PageId>>>,
+}
+
+impl ScanReport {
+    /// The ids of the pages which failed to decode, in ascending order
+    pub fn failed_pages(&self) -> Vec<PageId> {
+        self.failed_pages.lock().unwrap().iter().copied().collect()
+    }
+
+    fn record(&self, page_id: PageId) {
+        self.failed_pages.lock().unwrap().insert(page_id);
+    }
 }
@@ -6,6 +6,7 @@ pub struct Column {
     pub name: String,
     pub datatype: DataType,
     pub primary_key: bool,
+    pub auto_increment: bool,
     pub nullable: Option<bool>,
     pub default: Option<Value>,
     pub unique: bool,
@@ -19,6 +20,7 @@ impl Column {
             name: name.into(),
             datatype,
             primary_key: false,
+            auto_increment: false,
             nullable: None,
             default: None,
             unique: false,
@@ -37,6 +39,11 @@ impl Column {
         self
     }
 
+    pub fn with_auto_increment(mut self, auto_increment: bool) -> Self {
+        self.auto_increment = auto_increment;
+        self
+    }
+
     pub fn with_nullable(mut self, nullable: bool) -> Self {
         self.nullable = Some(nullable);
         self
@@ -85,6 +92,24 @@ impl Column {
             )));
         }
 
+        if self.auto_increment {
+            if !matches!(
+                self.datatype,
+                DataType::Tinyint | DataType::Smallint | DataType::Integer | DataType::Bigint
+            ) {
+                return Err(Error::Value(format!(
+                    "Auto-increment column {} must be an integer",
+                    self.name
+                )));
+            }
+            if matches!(&self.default, Some(default) if default.datatype().is_some()) {
+                return Err(Error::Value(format!(
+                    "Auto-increment column {} can't have a default value",
+                    self.name
+                )));
+            }
+        }
+
         // Validate default value
         if let Some(default) = &self.default {
             if let Some(datatype) = default.datatype() {
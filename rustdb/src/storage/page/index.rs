@@ -1,7 +1,9 @@
+use crate::encoding::encoded_size::EncodedSize;
 use crate::storage::page::PageTrait;
 use crate::storage::{PageId, RecordId};
 use std::cmp::Ordering;
 use std::fmt::Debug;
+use std::ops::RangeInclusive;
 use std::{mem, ptr};
 
 #[derive(Debug, PartialEq)]
@@ -26,11 +28,16 @@ impl<K> PageTrait for Node<K> {
     }
 }
 impl<K> Node<K> {
-    pub fn is_overflow(&self) -> bool {
-        match self {
+    /// Whether the node has too many entries, or is too large for a page payload of `capacity`
+    pub fn is_overflow(&self, capacity: usize) -> bool
+    where
+        K: EncodedSize,
+    {
+        let overflow = match self {
             Node::Internal(internal) => internal.is_overflow(),
             Node::Leaf(leaf) => leaf.is_overflow(),
-        }
+        };
+        overflow || self.encoded_size() > capacity
     }
     pub fn is_underflow(&self) -> bool {
         match self {
@@ -39,11 +46,16 @@ impl<K> Node<K> {
         }
     }
 
-    pub fn allow_insert(&self) -> bool {
-        match self {
+    /// Whether an entry of up to `entry_size` bytes can be inserted without splitting the node
+    pub fn allow_insert(&self, capacity: usize, entry_size: usize) -> bool
+    where
+        K: EncodedSize,
+    {
+        let allow = match self {
             Node::Internal(internal) => internal.allow_insert(),
             Node::Leaf(leaf) => leaf.allow_insert(),
-        }
+        };
+        allow && self.encoded_size() + entry_size <= capacity
     }
 
     pub fn allow_delete(&self) -> bool {
@@ -127,18 +139,40 @@ impl<K> Node<K> {
         }
     }
 
-    pub fn split(&mut self) -> (K, Node<K>)
+    /// Split the node in the middle of its entries. A node of long keys may be too large
+    /// for the page payload of `capacity` before it has too many entries, then it's split
+    /// in the middle of its bytes so both halves fit
+    pub fn split(&mut self, capacity: usize) -> (K, Node<K>)
     where
-        K: Default + Clone,
+        K: Default + Clone + EncodedSize,
     {
+        let by_size = self.encoded_size() > capacity;
         match self {
             Node::Internal(ref mut internal) => {
-                let (median_key, sibling) = internal.split();
+                let (median_key, sibling) = match by_size {
+                    // both halves keep two children at least
+                    true => internal.split_at(byte_median(
+                        internal
+                            .kv
+                            .iter()
+                            .map(|(k, v)| k.encoded_size() + v.encoded_size()),
+                        2..=internal.kv.len() - 2,
+                    )),
+                    false => internal.split(),
+                };
                 assert_eq!(sibling.header.size, sibling.kv.len() - 1);
                 (median_key, Node::Internal(sibling))
             }
             Node::Leaf(ref mut leaf) => {
-                let (median_key, sibling) = leaf.split();
+                let (median_key, sibling) = match by_size {
+                    true => leaf.split_at(byte_median(
+                        leaf.kv
+                            .iter()
+                            .map(|(k, v)| k.encoded_size() + v.encoded_size()),
+                        1..=leaf.kv.len() - 1,
+                    )),
+                    false => leaf.split(),
+                };
                 assert_eq!(sibling.header.size, sibling.kv.len());
                 (median_key, Node::Leaf(sibling))
             }
@@ -146,6 +180,24 @@ impl<K> Node<K> {
     }
 }
 
+/// The index within `range` which splits the entries of the sizes into two halves
+/// whose larger one is the smallest
+fn byte_median(sizes: impl Iterator<Item = usize>, range: RangeInclusive<usize>) -> usize {
+    let prefix = sizes
+        .scan(0, |sum, size| {
+            *sum += size;
+            Some(*sum)
+        })
+        .collect::<Vec<_>>();
+    let total = prefix.last().copied().unwrap_or_default();
+    range
+        .min_by_key(|at| {
+            let left = prefix[at - 1];
+            left.max(total - left)
+        })
+        .unwrap()
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Header {
     pub size: usize,
@@ -180,11 +232,19 @@ impl<K> Internal<K> {
     pub fn search(&self, key: &K) -> (usize, PageId)
     where
         K: Ord,
+    {
+        self.search_by(key, K::cmp)
+    }
+
+    /// Like [`Internal::search`], but keys are ordered by `compare`
+    pub fn search_by<F>(&self, key: &K, compare: F) -> (usize, PageId)
+    where
+        F: Fn(&K, &K) -> Ordering,
     {
         let (mut start, mut end) = (1, self.header.size);
         while start < end {
             let mid = (start + end) / 2;
-            match self.kv[mid].0.cmp(key) {
+            match compare(&self.kv[mid].0, key) {
                 Ordering::Less => {
                     start = mid + 1;
                 }
@@ -194,7 +254,7 @@ impl<K> Internal<K> {
                 }
             }
         }
-        match key.cmp(&self.kv[start].0) {
+        match compare(key, &self.kv[start].0) {
             Ordering::Less => (start - 1, self.kv[start - 1].1),
             _ => (start, self.kv[start].1),
         }
@@ -258,9 +318,15 @@ impl<K> Internal<K> {
         K: Default,
     {
         // index 0 is ignored, so we split kv from max_size/2 +1
-        let spilt_at = self.header.max_size / 2 + 1;
+        self.split_at(self.header.max_size / 2 + 1)
+    }
 
-        let mut sibling_kv = self.kv.split_off(spilt_at);
+    /// Move the entries from `at` to a new sibling, the key at `at` is the median moved up
+    pub fn split_at(&mut self, at: usize) -> (K, Internal<K>)
+    where
+        K: Default,
+    {
+        let mut sibling_kv = self.kv.split_off(at);
         let median_key = mem::take(&mut sibling_kv[0].0);
         let mut sibling_header = self.header.clone();
         self.header.size = self.kv.len() - 1;
@@ -350,7 +416,15 @@ impl<K> Leaf<K> {
     where
         K: Ord,
     {
-        match self.kv.binary_search_by(|(k, _)| k.cmp(key)) {
+        self.search_by(key, K::cmp)
+    }
+
+    /// Like [`Leaf::search`], but keys are ordered by `compare`
+    pub fn search_by<F>(&self, key: &K, compare: F) -> Option<RecordId>
+    where
+        F: Fn(&K, &K) -> Ordering,
+    {
+        match self.kv.binary_search_by(|(k, _)| compare(k, key)) {
             Ok(index) => Some(self.kv[index].1),
             Err(_) => None,
         }
@@ -411,7 +485,15 @@ impl<K> Leaf<K> {
     where
         K: Ord,
     {
-        match self.kv.binary_search_by(|(k, _)| k.cmp(key)) {
+        self.remove_by(key, K::cmp)
+    }
+
+    /// Like [`Leaf::remove`], but keys are ordered by `compare`
+    pub fn remove_by<F>(&mut self, key: &K, compare: F) -> Option<(K, RecordId)>
+    where
+        F: Fn(&K, &K) -> Ordering,
+    {
+        match self.kv.binary_search_by(|(k, _)| compare(k, key)) {
             Ok(index) => {
                 self.header.size -= 1;
                 let val = self.kv.remove(index);
@@ -421,12 +503,41 @@ impl<K> Leaf<K> {
         }
     }
 
+    /// Like [`Leaf::remove_by`], but only the entry of the record id is removed of the equal keys
+    pub fn remove_entry_by<F>(
+        &mut self,
+        key: &K,
+        record_id: RecordId,
+        compare: F,
+    ) -> Option<(K, RecordId)>
+    where
+        F: Fn(&K, &K) -> Ordering,
+    {
+        let start = self
+            .kv
+            .partition_point(|(k, _)| compare(k, key) == Ordering::Less);
+        let index = start
+            + self.kv[start..]
+                .iter()
+                .take_while(|(k, _)| compare(k, key) == Ordering::Equal)
+                .position(|(_, v)| *v == record_id)?;
+        self.header.size -= 1;
+        Some(self.kv.remove(index))
+    }
+
     pub fn split(&mut self) -> (K, Leaf<K>)
     where
         K: Clone,
     {
-        let spilt_at = self.header.max_size / 2;
-        let sibling_kv = self.kv.split_off(spilt_at);
+        self.split_at(self.header.max_size / 2)
+    }
+
+    /// Move the entries from `at` to a new sibling, whose first key is the median
+    pub fn split_at(&mut self, at: usize) -> (K, Leaf<K>)
+    where
+        K: Clone,
+    {
+        let sibling_kv = self.kv.split_off(at);
         let median_key = sibling_kv[0].0.clone();
         let mut sibling_header = self.header.clone();
         self.header.size = self.kv.len();
@@ -485,9 +596,11 @@ impl<K> Leaf<K> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::buffer;
     use crate::encoding;
     use crate::encoding::{Decoder, Encoder};
-    use crate::storage::{StorageResult, PAGE_SIZE};
+    use crate::storage::page::PageEncoding;
+    use crate::storage::{StorageResult, PAGE_PAYLOAD_SIZE, PAGE_SIZE};
     use bytes::{Buf, BufMut};
 
     #[derive(PartialEq, Debug)]
@@ -526,7 +639,7 @@ mod tests {
             next: Some(5),
             prev: Some(6),
         };
-        let mut buffer = [0; PAGE_SIZE];
+        let mut buffer = [0; PAGE_PAYLOAD_SIZE];
         header.encode(&mut buffer.as_mut())?;
         let new_header1 = Header::decode(&mut buffer.as_ref())?;
         let new_header2 = Header::decode(&mut buffer.as_ref())?;
@@ -554,7 +667,7 @@ mod tests {
             kv,
         });
 
-        let mut buffer = [0; PAGE_SIZE];
+        let mut buffer = [0; PAGE_PAYLOAD_SIZE];
         tree.encode(&mut buffer.as_mut())?;
         let new_tree: Node<Key> = Node::decode(&mut buffer.as_ref())?;
         assert_eq!(new_tree, tree);
@@ -589,7 +702,7 @@ mod tests {
             kv,
         });
 
-        let mut buffer = [0; PAGE_SIZE];
+        let mut buffer = [0; PAGE_PAYLOAD_SIZE];
         tree.encode(&mut buffer.as_mut())?;
         let new_tree: Node<Key> = Node::decode(&mut buffer.as_ref())?;
         assert_eq!(new_tree, tree);
@@ -599,6 +712,44 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn oversized_node() -> StorageResult<()> {
+        let len = 1000;
+        let kv = (0..len)
+            .map(|i| {
+                (
+                    Key { data: i as u32 },
+                    RecordId {
+                        page_id: i,
+                        slot_num: 0,
+                    },
+                )
+            })
+            .collect();
+        let tree = Node::Leaf(Leaf {
+            header: Header {
+                size: len,
+                max_size: len + 1,
+                parent: None,
+                page_id: 3,
+                next: None,
+                prev: None,
+            },
+            kv,
+        });
+        let mut page = [0; PAGE_SIZE];
+        assert!(matches!(
+            page.write_node_back(&tree),
+            Err(buffer::Error::PageOverflow {
+                capacity: PAGE_PAYLOAD_SIZE,
+                ..
+            })
+        ));
+        // nothing is written into the page
+        assert!(page.iter().all(|byte| *byte == 0));
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_simple_encode() {
         let data: [u8; 4096] = [
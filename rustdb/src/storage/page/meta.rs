@@ -0,0 +1,41 @@
+use crate::storage::page::PageTrait;
+use crate::storage::PageId;
+
+/// The first page of a database file, it records where every table lives so that
+/// the database can be reopened
+#[derive(Debug, Default, PartialEq)]
+pub struct Meta {
+    /// This Meta's page_id
+    pub(crate) page_id: PageId,
+    /// The size of every page of the database file
+    pub(crate) page_size: usize,
+    /// Every table's [`crate::storage::page::table::Table`] page_id
+    pub(crate) tables: Vec<PageId>,
+}
+
+impl PageTrait for Meta {
+    fn page_id(&self) -> PageId {
+        self.page_id
+    }
+
+    fn set_page_id(&mut self, page_id: PageId) {
+        self.page_id = page_id
+    }
+}
+
+impl Meta {
+    /// The encoded bytes of page_id and page_size, which lead the encoded Meta
+    pub const PREFIX_SIZE: usize = 2 * size_of::<u64>();
+
+    pub fn tables(&self) -> &[PageId] {
+        self.tables.as_slice()
+    }
+
+    pub fn push_table(&mut self, page_id: PageId) {
+        self.tables.push(page_id)
+    }
+
+    pub fn remove_table(&mut self, page_id: PageId) {
+        self.tables.retain(|table| *table != page_id)
+    }
+}
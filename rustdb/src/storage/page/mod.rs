@@ -1,19 +1,54 @@
 pub mod column;
 /// This mod contain a bunch of structures which represent the page's layout
 pub mod index;
+pub mod meta;
 pub mod table;
 
 use crate::buffer;
 use crate::buffer::Error;
 use crate::encoding::{Decoder, Encoder};
 use crate::storage::page::index::Node;
+use crate::storage::page::meta::Meta;
 use crate::storage::page::table::{Table, TableNode};
-use crate::storage::{page, AtomicPageId, PageId, PAGE_SIZE};
+use crate::storage::{page, AtomicPageId, PageId, CHECKSUM_SIZE};
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
-pub type PageData = Arc<RwLock<[u8; PAGE_SIZE]>>;
+pub type PageData = Arc<RwLock<Box<[u8]>>>;
+
+/// Write the checksum of the page's payload into its first bytes, before it's written to disk
+pub fn seal(data: &mut [u8]) {
+    let checksum = crc32fast::hash(&data[CHECKSUM_SIZE..]);
+    data[..CHECKSUM_SIZE].copy_from_slice(&checksum.to_be_bytes());
+}
+
+/// Whether the checksum of the page read from disk matches its payload.
+/// A page of zeros has never been written, so it's taken as well
+pub fn verify(data: &[u8]) -> bool {
+    let checksum = crc32fast::hash(&data[CHECKSUM_SIZE..]);
+    data[..CHECKSUM_SIZE] == checksum.to_be_bytes() || data.iter().all(|byte| *byte == 0)
+}
+
+/// Encode into the payload of the page, a value which doesn't fit fails
+/// rather than overflowing the page
+fn encode_payload<T>(t: &T, data: &mut [u8]) -> Result<(), Error>
+where
+    T: Encoder,
+{
+    let mut encoded = Vec::new();
+    t.encode(&mut encoded)?;
+    let payload = &mut data[CHECKSUM_SIZE..];
+    if encoded.len() > payload.len() {
+        return Err(Error::PageOverflow {
+            size: encoded.len(),
+            capacity: payload.len(),
+        });
+    }
+    payload[..encoded.len()].copy_from_slice(&encoded);
+    Ok(())
+}
+
 pub struct Page {
     page_id: AtomicPageId,
     data: PageData,
@@ -28,10 +63,10 @@ pub trait PageTrait {
 }
 
 impl Page {
-    pub fn new(page_id: PageId) -> Self {
+    pub fn new(page_id: PageId, page_size: usize) -> Self {
         Self {
             page_id: AtomicPageId::new(page_id),
-            data: Arc::new(RwLock::new([0; PAGE_SIZE])),
+            data: Arc::new(RwLock::new(vec![0; page_size].into_boxed_slice())),
             pin_count: AtomicU32::new(0),
             is_dirty: AtomicBool::new(false),
         }
@@ -46,10 +81,7 @@ impl Page {
 
     pub async fn reset(&self) {
         self.page_id.store(0, Ordering::Relaxed);
-        {
-            let mut data = self.data.write().await;
-            *data = [0; PAGE_SIZE];
-        }
+        self.data.write().await.fill(0);
         self.pin_count.store(0, Ordering::Relaxed);
         self.is_dirty.store(false, Ordering::Relaxed);
     }
@@ -103,7 +135,9 @@ impl Page {
         T: Encoder,
     {
         let mut data = self.data_ref().write().await;
-        t.encode(&mut data.as_mut()).map_err(Into::into)
+        encode_payload(t, &mut data)?;
+        self.set_dirty(true);
+        Ok(())
     }
 
     async fn decode<T>(&self) -> Result<T, buffer::Error>
@@ -111,7 +145,7 @@ impl Page {
         T: Decoder,
     {
         let data = self.data_ref().read().await;
-        T::decode(&mut data.as_ref()).map_err(Into::into)
+        T::decode(&mut &data[CHECKSUM_SIZE..]).map_err(Into::into)
     }
 }
 
@@ -130,36 +164,48 @@ pub trait PageEncoding {
     fn table_node(&self) -> Result<page::table::TableNode, Error>;
 
     fn write_table_node_back(&mut self, node: &page::table::TableNode) -> Result<(), Error>;
+
+    fn meta(&self) -> Result<Meta, Error>;
+
+    fn write_meta_back(&mut self, meta: &Meta) -> Result<(), Error>;
 }
 
-impl PageEncoding for [u8; PAGE_SIZE] {
+impl PageEncoding for [u8] {
     fn node<K>(&self) -> Result<Node<K>, Error>
     where
         K: Decoder,
     {
-        Node::decode(&mut self.as_ref()).map_err(Into::into)
+        Node::decode(&mut &self[CHECKSUM_SIZE..]).map_err(Into::into)
     }
 
     fn write_node_back<K>(&mut self, node: &Node<K>) -> Result<(), Error>
     where
         K: Encoder,
     {
-        node.encode(&mut self.as_mut()).map_err(Into::into)
+        encode_payload(node, self)
     }
 
     fn table(&self) -> Result<Table, Error> {
-        Table::decode(&mut self.as_ref()).map_err(Into::into)
+        Table::decode(&mut &self[CHECKSUM_SIZE..]).map_err(Into::into)
     }
 
     fn write_table_back(&mut self, table: &Table) -> Result<(), Error> {
-        table.encode(&mut self.as_mut()).map_err(Into::into)
+        encode_payload(table, self)
     }
 
     fn table_node(&self) -> Result<TableNode, Error> {
-        TableNode::decode(&mut self.as_ref()).map_err(Into::into)
+        TableNode::decode(&mut &self[CHECKSUM_SIZE..]).map_err(Into::into)
     }
 
     fn write_table_node_back(&mut self, node: &TableNode) -> Result<(), Error> {
-        node.encode(&mut self.as_mut()).map_err(Into::into)
+        encode_payload(node, self)
+    }
+
+    fn meta(&self) -> Result<Meta, Error> {
+        Meta::decode(&mut &self[CHECKSUM_SIZE..]).map_err(Into::into)
+    }
+
+    fn write_meta_back(&mut self, meta: &Meta) -> Result<(), Error> {
+        encode_payload(meta, self)
     }
 }
@@ -14,8 +14,43 @@ pub struct Table {
     pub(crate) start: PageId,
     /// Last TableNode's page_id
     pub(crate) end: PageId,
+    /// The last value of the auto-increment sequence, 0 if none was handed out
+    pub(crate) sequence: i64,
+    /// The root page of the primary index
+    pub(crate) primary: PageId,
+    /// The root pages of the secondary indexes, in the order of their columns
+    pub(crate) secondaries: Vec<PageId>,
+    /// The root pages of the indexes created on several columns, in the order of `indexes`
+    pub(crate) composites: Vec<PageId>,
     /// Columns
     pub(crate) columns: Vec<Column>,
+    /// The indexes created on several columns
+    pub(crate) indexes: Vec<TableIndex>,
+}
+
+/// An index of several columns, which is created after the table
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableIndex {
+    pub(crate) name: String,
+    /// The positions of the indexed columns, in the order of the index key
+    pub(crate) columns: Vec<usize>,
+}
+
+impl TableIndex {
+    pub fn new(name: impl Into<String>, columns: Vec<usize>) -> Self {
+        Self {
+            name: name.into(),
+            columns,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn columns(&self) -> &[usize] {
+        &self.columns
+    }
 }
 
 impl PageTrait for Table {
@@ -40,7 +75,12 @@ impl Table {
             page_id,
             start: node_page_id,
             end: node_page_id,
+            sequence: 0,
+            primary: 0,
+            secondaries: Vec::new(),
+            composites: Vec::new(),
             columns,
+            indexes: Vec::new(),
         }
     }
 
@@ -52,6 +92,36 @@ impl Table {
         self.end = page_id
     }
 
+    /// Advance the sequence and return the new value
+    pub fn next_sequence(&mut self) -> i64 {
+        self.sequence += 1;
+        self.sequence
+    }
+
+    /// Move the sequence forward to `value`, so it never hands out a value which is used.
+    /// Returns false if the sequence is already at or past `value`.
+    pub fn advance_sequence(&mut self, value: i64) -> bool {
+        if value <= self.sequence {
+            return false;
+        }
+        self.sequence = value;
+        true
+    }
+
+    pub fn set_primary(&mut self, page_id: PageId) {
+        self.primary = page_id
+    }
+
+    /// Returns false if the roots are recorded already
+    pub fn set_index_roots(&mut self, secondaries: Vec<PageId>, composites: Vec<PageId>) -> bool {
+        if self.secondaries == secondaries && self.composites == composites {
+            return false;
+        }
+        self.secondaries = secondaries;
+        self.composites = composites;
+        true
+    }
+
     pub fn push_column(&mut self, column: Column) {
         self.columns.push(column);
     }
@@ -60,9 +130,28 @@ impl Table {
         self.columns.insert(index, column);
     }
 
+    /// Returns false if there's no column named `from`
+    pub fn rename_column(&mut self, from: &str, to: impl Into<String>) -> bool {
+        match self.columns.iter_mut().find(|column| column.name == from) {
+            None => false,
+            Some(column) => {
+                column.name = to.into();
+                true
+            }
+        }
+    }
+
     pub fn columns(&self) -> &[Column] {
         self.columns.as_slice()
     }
+
+    pub fn push_index(&mut self, index: TableIndex) {
+        self.indexes.push(index);
+    }
+
+    pub fn indexes(&self) -> &[TableIndex] {
+        self.indexes.as_slice()
+    }
 }
 #[derive(Debug, PartialEq)]
 pub struct TableNode {
@@ -110,6 +199,36 @@ impl TableNode {
         self.tuples.push(tuple);
         RecordId::new(self.page_id, slot_num)
     }
+
+    /// Remove the deleted tuples to reclaim their space, the live tuples after them move forward
+    pub fn compact(&mut self) -> Compacted {
+        let mut removed = Vec::new();
+        let mut moved = Vec::new();
+        let mut tuples = Vec::with_capacity(self.tuples.len());
+        for (slot_num, tuple) in std::mem::take(&mut self.tuples).into_iter().enumerate() {
+            let slot_num = slot_num as u32;
+            if tuple.deleted {
+                removed.push((slot_num, tuple));
+                continue;
+            }
+            let new_slot_num = tuples.len() as u32;
+            if new_slot_num != slot_num {
+                moved.push((slot_num, new_slot_num));
+            }
+            tuples.push(tuple);
+        }
+        self.tuples = tuples;
+        Compacted { removed, moved }
+    }
+}
+
+/// The slots changed by [`TableNode::compact`]
+#[derive(Debug, Default, PartialEq)]
+pub struct Compacted {
+    /// The removed tuples with their slots
+    pub removed: Vec<(u32, Tuple)>,
+    /// The (old, new) slots of the moved tuples
+    pub moved: Vec<(u32, u32)>,
 }
 #[derive(Debug, PartialEq, Clone)]
 pub struct Tuple {
@@ -1,14 +1,77 @@
 use crate::buffer::buffer_pool_manager::{
     BufferPoolManager, OwnedPageDataReadGuard, OwnedPageDataWriteGuard, PageRef,
 };
+use crate::catalog::table::TableCatalog;
+use crate::catalog::TableId;
 use crate::encoding::encoded_size::EncodedSize;
 use crate::sql::types::Value;
 use crate::storage::page::column::Column;
-use crate::storage::page::table::{TableNode, Tuple};
+use crate::storage::page::table::{Compacted, TableIndex, TableNode, Tuple};
 use crate::storage::page::{PageEncoding, PageTrait};
 use crate::storage::{page, Error, PageId, RecordId, StorageResult};
 use std::sync::Arc;
 
+/// The tuples changed by [`Table::vacuum`]
+#[derive(Debug, Default)]
+pub struct Vacuumed {
+    /// The removed tuples, with the record ids they had
+    pub removed: Vec<(RecordId, Tuple)>,
+    /// The live tuples which moved, with their old and new record ids
+    pub moved: Vec<(RecordId, RecordId, Tuple)>,
+}
+
+/// A resumable position in the table heap, which pages through the live tuples in the order
+/// of their [`RecordId`]s without rescanning the pages before the position.
+///
+/// Tuples are only appended to the heap, so the tuples inserted while paging are returned by
+/// a later batch, and the tuples deleted before they are reached are skipped. The position is
+/// a [`RecordId`], which is invalidated by [`Table::vacuum`] since tuples move in their pages,
+/// resuming after a vacuum may skip or repeat tuples.
+pub struct HeapCursor<'a> {
+    table: &'a Table,
+    position: RecordId,
+}
+
+impl HeapCursor<'_> {
+    /// The position of the next tuple, pass it to [`Table::cursor_at`] to resume later
+    pub fn position(&self) -> RecordId {
+        self.position
+    }
+
+    /// Read at most `limit` live tuples from the position and move past them.
+    /// Fewer tuples mean the end of the heap is reached, but the cursor stays valid
+    /// and returns the tuples appended later.
+    pub async fn next_batch(&mut self, limit: usize) -> StorageResult<Vec<(RecordId, Tuple)>> {
+        let mut output = Vec::new();
+        while output.len() < limit {
+            let RecordId { page_id, slot_num } = self.position;
+            let page = self
+                .table
+                .buffer_pool
+                .fetch_page_read_owned(page_id)
+                .await?;
+            let node = page.table_node()?;
+            for (slot_num, tuple) in node.tuples.iter().enumerate().skip(slot_num as usize) {
+                if output.len() == limit {
+                    break;
+                }
+                self.position = RecordId::new(page_id, slot_num as u32 + 1);
+                if !tuple.deleted {
+                    output.push((RecordId::new(page_id, slot_num as u32), tuple.clone()));
+                }
+            }
+            if output.len() == limit {
+                break;
+            }
+            match node.next() {
+                None => break,
+                Some(next) => self.position = RecordId::new(next, 0),
+            }
+        }
+        Ok(output)
+    }
+}
+
 /// The wrapper of physical table in [`page::table::Table`]
 pub struct Table {
     name: String,
@@ -50,6 +113,19 @@ impl Table {
         })
     }
 
+    /// Reconstruct the schema persisted in the table page, the root page id is used as the table id
+    pub async fn load_schema(
+        buffer_pool: &BufferPoolManager,
+        root: PageId,
+    ) -> StorageResult<TableCatalog> {
+        let table = buffer_pool.fetch_page_table(root).await?.1;
+        Ok(TableCatalog::new(
+            root as TableId,
+            table.name,
+            table.columns.into_iter().map(Into::into).collect(),
+        )?)
+    }
+
     pub fn page_id(&self) -> PageId {
         self.root
     }
@@ -76,6 +152,86 @@ impl Table {
         Ok(())
     }
 
+    pub async fn indexes(&self) -> StorageResult<Vec<TableIndex>> {
+        Ok(self.table_read().await?.1.indexes)
+    }
+
+    /// Record the index along with the root page of its tree
+    pub async fn push_index(&self, index: TableIndex, root: PageId) -> StorageResult<()> {
+        let (mut page, mut table) = self.table_write().await?;
+        table.push_index(index);
+        table.composites.push(root);
+        page.write_table_back(&table)?;
+        Ok(())
+    }
+
+    pub async fn rename_column(&self, from: &str, to: impl Into<String>) -> StorageResult<()> {
+        let (mut page, mut table) = self.table_write().await?;
+        if !table.rename_column(from, to) {
+            return Err(Error::NotFound("column", from.to_string()));
+        }
+        page.write_table_back(&table)?;
+        Ok(())
+    }
+
+    /// The root page of the primary index recorded by [`Table::set_primary`]
+    pub async fn primary(&self) -> StorageResult<PageId> {
+        Ok(self.table_read().await?.1.primary)
+    }
+
+    /// Record the root page of the primary index, so it's opened instead of rebuilt
+    pub async fn set_primary(&self, page_id: PageId) -> StorageResult<()> {
+        let (mut page, mut table) = self.table_write().await?;
+        if table.primary != page_id {
+            table.set_primary(page_id);
+            page.write_table_back(&table)?;
+        }
+        Ok(())
+    }
+
+    /// The root pages of the secondary and composite indexes recorded by
+    /// [`Table::set_index_roots`]
+    pub async fn index_roots(&self) -> StorageResult<(Vec<PageId>, Vec<PageId>)> {
+        let table = self.table_read().await?.1;
+        Ok((table.secondaries, table.composites))
+    }
+
+    /// Record the root pages of the secondary indexes in the order of their columns and the ones
+    /// of the composite indexes in the order they're created, so they're opened instead of rebuilt
+    pub async fn set_index_roots(
+        &self,
+        secondaries: Vec<PageId>,
+        composites: Vec<PageId>,
+    ) -> StorageResult<()> {
+        let (mut page, mut table) = self.table_write().await?;
+        if table.set_index_roots(secondaries, composites) {
+            page.write_table_back(&table)?;
+        }
+        Ok(())
+    }
+
+    /// The last value handed out by the auto-increment sequence
+    pub async fn sequence(&self) -> StorageResult<i64> {
+        Ok(self.table_read().await?.1.sequence)
+    }
+
+    /// Hand out the next value of the auto-increment sequence, which is persisted in the table page
+    pub async fn next_sequence(&self) -> StorageResult<i64> {
+        let (mut page, mut table) = self.table_write().await?;
+        let value = table.next_sequence();
+        page.write_table_back(&table)?;
+        Ok(value)
+    }
+
+    /// Make sure the sequence never hands out `value` or anything before it
+    pub async fn advance_sequence(&self, value: i64) -> StorageResult<()> {
+        let (mut page, mut table) = self.table_write().await?;
+        if table.advance_sequence(value) {
+            page.write_table_back(&table)?;
+        }
+        Ok(())
+    }
+
     pub async fn table_read(&self) -> StorageResult<(OwnedPageDataReadGuard, page::table::Table)> {
         let page = self.buffer_pool.fetch_page_read_owned(self.root).await?;
         let table = page.table()?;
@@ -149,14 +305,50 @@ impl Table {
     }
 
     pub async fn read_tuple(&self, record_id: RecordId) -> StorageResult<Option<Tuple>> {
+        Ok(self
+            .read_record(record_id)
+            .await?
+            .filter(|tuple| !tuple.deleted))
+    }
+
+    /// Like [`Table::read_tuple`], but deleted tuples are returned too
+    pub async fn read_record(&self, record_id: RecordId) -> StorageResult<Option<Tuple>> {
         let RecordId { page_id, slot_num } = record_id;
         let page = self.buffer_pool.fetch_page_read_owned(page_id).await?;
         let node = page.table_node()?;
-        Ok(node
-            .tuples
-            .get(slot_num as usize)
-            .filter(|tuple| !tuple.deleted)
-            .cloned())
+        Ok(node.tuples.get(slot_num as usize).cloned())
+    }
+
+    /// Remove the deleted tuples from every page, the live tuples after them move forward
+    /// in the same page, so the caller must fix the record ids it keeps
+    pub async fn vacuum(&self) -> StorageResult<Vacuumed> {
+        let mut vacuumed = Vacuumed::default();
+        let mut page_id = self.table_read().await?.1.start;
+        loop {
+            let mut page = self.buffer_pool.fetch_page_write_owned(page_id).await?;
+            let mut node = page.table_node()?;
+            let Compacted { removed, moved } = node.compact();
+            if !removed.is_empty() {
+                page.write_table_node_back(&node)?;
+            }
+            vacuumed.removed.extend(
+                removed
+                    .into_iter()
+                    .map(|(slot_num, tuple)| (RecordId::new(page_id, slot_num), tuple)),
+            );
+            vacuumed.moved.extend(moved.into_iter().map(|(old, new)| {
+                (
+                    RecordId::new(page_id, old),
+                    RecordId::new(page_id, new),
+                    node.tuples[new as usize].clone(),
+                )
+            }));
+            match node.next() {
+                None => break,
+                Some(next) => page_id = next,
+            }
+        }
+        Ok(vacuumed)
     }
 
     pub async fn update_tuple(
@@ -198,6 +390,63 @@ impl Table {
         Ok(output.into_iter())
     }
 
+    /// Like [`Table::tuples`], but also returns the [`RecordId`] of every tuple
+    pub async fn records(
+        &self,
+    ) -> StorageResult<impl DoubleEndedIterator<Item = (RecordId, Tuple)>> {
+        let mut page_id = self.table_read().await?.1.start;
+        let mut output = Vec::new();
+        let mut latches = Vec::new();
+        loop {
+            let page = self.buffer_pool.fetch_page_read_owned(page_id).await?;
+            let node = page.table_node()?;
+            let next = node.next();
+            output.extend(
+                node.tuples
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(_, tuple)| !tuple.deleted)
+                    .map(|(slot_num, tuple)| (RecordId::new(page_id, slot_num as u32), tuple)),
+            );
+            latches.push(page);
+            match next {
+                None => break,
+                Some(next) => page_id = next,
+            }
+        }
+        Ok(output.into_iter())
+    }
+
+    /// The number of pages in the table heap
+    pub async fn page_count(&self) -> StorageResult<usize> {
+        let mut page_id = self.table_read().await?.1.start;
+        let mut count = 1;
+        while let Some(next) = self
+            .buffer_pool
+            .fetch_page_read_owned(page_id)
+            .await?
+            .table_node()?
+            .next()
+        {
+            page_id = next;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// A [`HeapCursor`] at the first tuple of the heap
+    pub async fn cursor(&self) -> StorageResult<HeapCursor<'_>> {
+        Ok(self.cursor_at(RecordId::new(self.table_read().await?.1.start, 0)))
+    }
+
+    /// Resume a [`HeapCursor`] from a position returned by [`HeapCursor::position`]
+    pub fn cursor_at(&self, position: RecordId) -> HeapCursor<'_> {
+        HeapCursor {
+            table: self,
+            position,
+        }
+    }
+
     async fn add_node(&self) -> StorageResult<(OwnedPageDataWriteGuard, TableNode)> {
         let mut heap_page = self.buffer_pool.fetch_page_write_owned(self.root).await?;
         let mut table_heap = heap_page.table()?;
@@ -293,6 +542,106 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn load_schema() -> StorageResult<()> {
+        let f = tempfile::NamedTempFile::new()?;
+        let columns = vec![
+            Column::new("id", DataType::Bigint)
+                .with_primary(true)
+                .with_unique(true)
+                .with_auto_increment(true),
+            Column::new("name", DataType::String)
+                .with_nullable(true)
+                .with_default(Value::String("hello".to_string())),
+            Column::new("owner", DataType::Bigint)
+                .with_index(true)
+                .with_references("user"),
+        ];
+        let buffer_pool =
+            Arc::new(BufferPoolManager::new(16, 2, DiskManager::new(f.path()).await?).await?);
+        let root = Table::new("pet", columns.clone(), buffer_pool.clone())
+            .await?
+            .page_id();
+        buffer_pool.flush_page_all().await?;
+
+        let buffer_pool = BufferPoolManager::new(16, 2, DiskManager::new(f.path()).await?).await?;
+        let schema = Table::load_schema(&buffer_pool, root).await?;
+        assert_eq!(schema.name(), "pet");
+        assert_eq!(
+            schema.read_column("name").unwrap().default,
+            Some(Value::String("hello".to_string()))
+        );
+        assert_eq!(schema.read_column("name").unwrap().nullable, Some(true));
+        assert!(schema.read_column("id").unwrap().auto_increment);
+        assert_eq!(
+            schema.read_column("owner").unwrap().references.as_deref(),
+            Some("user")
+        );
+        assert_eq!(
+            schema.primary_keys(),
+            [schema.read_id_by_name("id").unwrap()]
+        );
+        assert_eq!(
+            schema,
+            TableCatalog::new(
+                root as TableId,
+                "pet",
+                columns.into_iter().map(Into::into).collect()
+            )?
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn heap_cursor() -> StorageResult<()> {
+        let buffer_manager = Arc::new(new_buffer_pool().await?);
+        let columns = vec![
+            Column::new("id", DataType::Bigint).with_primary(true),
+            Column::new("name", DataType::String),
+        ];
+        let table = Table::new("user", columns, buffer_manager).await?;
+        let tuple = |i: i128| Tuple::new(vec![Value::Bigint(i), Value::String("a".repeat(100))], 0);
+        for i in 0..95 {
+            table.insert(tuple(i)).await?;
+        }
+
+        let mut records = Vec::new();
+        let mut position = table.cursor().await?.position();
+        loop {
+            let mut cursor = table.cursor_at(position);
+            let batch = cursor.next_batch(10).await?;
+            position = cursor.position();
+            records.extend(batch.iter().cloned());
+            if batch.len() < 10 {
+                assert_eq!(batch.len(), 5);
+                break;
+            }
+        }
+        assert_eq!(records, table.records().await?.collect::<Vec<_>>());
+        // the batches cross the pages of the heap
+        assert_ne!(records[0].0.page_id, records[94].0.page_id);
+
+        // the deleted tuples are skipped and the appended tuples are returned after the end
+        let mut cursor = table.cursor().await?;
+        assert_eq!(cursor.next_batch(10).await?.len(), 10);
+        table.delete(records[10].0).await?;
+        table.insert(tuple(95)).await?;
+        let rest = cursor.next_batch(100).await?;
+        assert_eq!(
+            rest.iter()
+                .map(|(_, tuple)| tuple.values[0].clone())
+                .collect::<Vec<_>>(),
+            (11..96).map(Value::Bigint).collect::<Vec<_>>()
+        );
+        assert!(cursor.next_batch(10).await?.is_empty());
+        table.insert(tuple(96)).await?;
+        assert_eq!(
+            cursor.next_batch(10).await?,
+            vec![(table.records().await?.last().unwrap().0, tuple(96))]
+        );
+        Ok(())
+    }
+
     #[tokio::test]
     async fn delete_tuple() -> StorageResult<()> {
         let buffer_manager = Arc::new(new_buffer_pool().await?);
@@ -333,4 +682,39 @@ mod tests {
         );
         Ok(())
     }
+
+    #[tokio::test]
+    async fn compact() -> StorageResult<()> {
+        let buffer_manager = Arc::new(new_buffer_pool().await?);
+        let column_id = Column::new("id", DataType::Bigint).with_primary(true);
+        let table = Table::new("user", vec![column_id], buffer_manager.clone()).await?;
+        let mut record_ids = Vec::new();
+        for id in 0..20 {
+            record_ids.push(table.insert(Tuple::new(vec![Value::Bigint(id)], 0)).await?);
+        }
+        let page_id = record_ids[0].page_id;
+        assert!(record_ids.iter().all(|record| record.page_id == page_id));
+        for record_id in record_ids.iter().step_by(2) {
+            table.delete(*record_id).await?;
+        }
+        let used = |buffer_manager: Arc<BufferPoolManager>| async move {
+            let page = buffer_manager.fetch_page_read_owned(page_id).await?;
+            Ok::<_, Error>(page.table_node()?.encoded_size())
+        };
+        let before = used(buffer_manager.clone()).await?;
+
+        let vacuumed = table.vacuum().await?;
+        assert_eq!(vacuumed.removed.len(), 10);
+        assert_eq!(vacuumed.moved.len(), 10);
+        assert!(used(buffer_manager.clone()).await? < before);
+        for (old, new, tuple) in vacuumed.moved {
+            assert_eq!(
+                old,
+                record_ids[tuple.values[0].to_string().parse::<usize>().unwrap()]
+            );
+            assert_eq!(table.read_tuple(new).await?, Some(tuple));
+        }
+        assert_eq!(table.tuples().await?.count(), 10);
+        Ok(())
+    }
 }
@@ -0,0 +1,609 @@
+use crate::buffer::buffer_pool_manager::BufferPoolManager;
+use crate::sql::types::{DataType, Value};
+use crate::storage::index::{Index, IndexOptions, SortOrder};
+use crate::storage::page::table::Tuple;
+use crate::storage::{Error, PageId, RecordId, StorageResult};
+use std::ops::{Bound, RangeBounds};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// An [`Index`] keyed by [`Value`], so the key type of the column can be chosen at runtime.
+/// Values of different types don't have a meaningful order,
+/// so every key of the index must be a non-null value of the same [`DataType`].
+pub struct ValueIndex {
+    index: Index<Value>,
+    datatype: DataType,
+}
+
+impl ValueIndex {
+    pub async fn new(
+        buffer_pool: Arc<BufferPoolManager>,
+        max_size: usize,
+        datatype: DataType,
+    ) -> StorageResult<Self> {
+        Self::new_with_order(buffer_pool, max_size, datatype, SortOrder::Ascending).await
+    }
+
+    pub async fn new_with_order(
+        buffer_pool: Arc<BufferPoolManager>,
+        max_size: usize,
+        datatype: DataType,
+        order: SortOrder,
+    ) -> StorageResult<Self> {
+        Self::with_options(
+            buffer_pool,
+            datatype,
+            IndexOptions::default()
+                .with_max_size(max_size)
+                .with_order(order),
+        )
+        .await
+    }
+
+    pub async fn with_options(
+        buffer_pool: Arc<BufferPoolManager>,
+        datatype: DataType,
+        options: IndexOptions,
+    ) -> StorageResult<Self> {
+        Ok(Self {
+            index: Index::with_options(buffer_pool, options).await?,
+            datatype,
+        })
+    }
+
+    /// Open the ascending index whose tree is at the root page, see [`Index::open`]
+    pub async fn open(
+        buffer_pool: Arc<BufferPoolManager>,
+        root_page_id: PageId,
+        max_size: usize,
+        datatype: DataType,
+    ) -> StorageResult<Self> {
+        Ok(Self {
+            index: Index::open(buffer_pool, root_page_id, max_size).await?,
+            datatype,
+        })
+    }
+
+    /// See [`Index::root_page_id`]
+    pub async fn root_page_id(&self) -> PageId {
+        self.index.root_page_id().await
+    }
+
+    /// See [`Index::with_read_ahead`]
+    pub fn with_read_ahead(mut self, read_ahead: usize) -> Self {
+        self.index = self.index.with_read_ahead(read_ahead);
+        self
+    }
+
+    /// See [`Index::with_timeout`]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.index = self.index.with_timeout(timeout);
+        self
+    }
+
+    pub fn datatype(&self) -> &DataType {
+        &self.datatype
+    }
+
+    pub fn order(&self) -> SortOrder {
+        self.index.order()
+    }
+
+    pub async fn first(&self) -> StorageResult<Option<(Value, RecordId)>> {
+        self.index.first().await
+    }
+
+    pub async fn last(&self) -> StorageResult<Option<(Value, RecordId)>> {
+        self.index.last().await
+    }
+
+    pub async fn search(&self, key: &Value) -> StorageResult<Option<RecordId>> {
+        self.check(key)?;
+        self.index.search(key).await
+    }
+
+    pub async fn contains(&self, key: &Value) -> StorageResult<bool> {
+        self.check(key)?;
+        self.index.contains(key).await
+    }
+
+    pub async fn search_range<'r, R>(&self, range: R) -> StorageResult<Vec<RecordId>>
+    where
+        R: RangeBounds<&'r Value>,
+    {
+        self.check_range(&range)?;
+        self.index.search_range(range).await
+    }
+
+    /// See [`Index::entries`]
+    pub async fn entries(&self) -> StorageResult<Vec<(Value, RecordId)>> {
+        self.index.entries().await
+    }
+
+    pub async fn insert(&self, key: Value, value: RecordId) -> StorageResult<()> {
+        self.check(&key)?;
+        self.index.insert(key, value).await
+    }
+
+    pub async fn delete(&self, key: &Value) -> StorageResult<Option<(Value, RecordId)>> {
+        self.check(key)?;
+        self.index.delete(key).await
+    }
+
+    pub async fn delete_range<'r, R>(&self, range: R) -> StorageResult<usize>
+    where
+        R: RangeBounds<&'r Value>,
+    {
+        self.check_range(&range)?;
+        self.index.delete_range(range).await
+    }
+
+    /// See [`Index::reindex`]
+    pub async fn reindex(&self) -> StorageResult<usize> {
+        self.index.reindex().await
+    }
+
+    fn check(&self, key: &Value) -> StorageResult<()> {
+        match key.datatype() {
+            Some(datatype) if datatype == self.datatype => Ok(()),
+            _ => Err(Error::Value(format!(
+                "Can't use {} as the key of {} index",
+                key, self.datatype
+            ))),
+        }
+    }
+
+    fn check_range<'r, R>(&self, range: &R) -> StorageResult<()>
+    where
+        R: RangeBounds<&'r Value>,
+    {
+        for bound in [range.start_bound(), range.end_bound()] {
+            if let Bound::Included(key) | Bound::Excluded(key) = bound {
+                self.check(key)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An index of a column whose values may repeat. Every entry is keyed by the value and the
+/// record id, so the records of a value are a range of the index. NULLs aren't indexed.
+pub struct SecondaryIndex {
+    index: Index<Vec<Value>>,
+    datatype: DataType,
+}
+
+impl SecondaryIndex {
+    pub async fn new(
+        buffer_pool: Arc<BufferPoolManager>,
+        max_size: usize,
+        datatype: DataType,
+    ) -> StorageResult<Self> {
+        Ok(Self {
+            index: Index::new(buffer_pool, max_size).await?,
+            datatype,
+        })
+    }
+
+    /// Open the index whose tree is at the root page, see [`Index::open`]
+    pub async fn open(
+        buffer_pool: Arc<BufferPoolManager>,
+        root_page_id: PageId,
+        max_size: usize,
+        datatype: DataType,
+    ) -> StorageResult<Self> {
+        Ok(Self {
+            index: Index::open(buffer_pool, root_page_id, max_size).await?,
+            datatype,
+        })
+    }
+
+    /// See [`Index::root_page_id`]
+    pub async fn root_page_id(&self) -> PageId {
+        self.index.root_page_id().await
+    }
+
+    /// The smallest value and its first record
+    pub async fn first(&self) -> StorageResult<Option<(Value, RecordId)>> {
+        Ok(self
+            .index
+            .first()
+            .await?
+            .map(|(mut entry, record_id)| (entry.swap_remove(0), record_id)))
+    }
+
+    /// The largest value and its last record
+    pub async fn last(&self) -> StorageResult<Option<(Value, RecordId)>> {
+        Ok(self
+            .index
+            .last()
+            .await?
+            .map(|(mut entry, record_id)| (entry.swap_remove(0), record_id)))
+    }
+
+    /// The records whose value equals the key, in the order of record id
+    pub async fn search(&self, key: &Value) -> StorageResult<Vec<RecordId>> {
+        if key == &Value::Null {
+            return Ok(Vec::new());
+        }
+        self.check(key)?;
+        let start = vec![key.clone(), Value::Bigint(i128::MIN)];
+        let end = vec![key.clone(), Value::Bigint(i128::MAX)];
+        self.index.search_range(&start..=&end).await
+    }
+
+    /// The records whose value is within the range, in the order of value and record id
+    pub async fn search_range(
+        &self,
+        start: Bound<&Value>,
+        end: Bound<&Value>,
+    ) -> StorageResult<Vec<RecordId>> {
+        for key in [start, end] {
+            if let Bound::Included(key) | Bound::Excluded(key) = key {
+                self.check(key)?;
+            }
+        }
+        // the entries of a value are ordered by record id, so a bound on the value
+        // becomes the smallest or the largest entry of the value
+        let entry = |key: &Value, last: bool| {
+            let record_id = if last { i128::MAX } else { i128::MIN };
+            vec![key.clone(), Value::Bigint(record_id)]
+        };
+        let start = match start {
+            Bound::Included(key) => Bound::Included(entry(key, false)),
+            Bound::Excluded(key) => Bound::Excluded(entry(key, true)),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        let end = match end {
+            Bound::Included(key) => Bound::Included(entry(key, true)),
+            Bound::Excluded(key) => Bound::Excluded(entry(key, false)),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        self.index
+            .search_range((start.as_ref(), end.as_ref()))
+            .await
+    }
+
+    /// Every value and its record, in the order of value and record id
+    pub async fn entries(&self) -> StorageResult<Vec<(Value, RecordId)>> {
+        Ok(self
+            .index
+            .entries()
+            .await?
+            .into_iter()
+            .map(|(mut entry, record_id)| (entry.swap_remove(0), record_id))
+            .collect())
+    }
+
+    pub async fn insert(&self, key: Value, record_id: RecordId) -> StorageResult<()> {
+        if key == Value::Null {
+            return Ok(());
+        }
+        self.check(&key)?;
+        self.index
+            .insert(Self::entry(key, record_id), record_id)
+            .await
+    }
+
+    pub async fn delete(&self, key: Value, record_id: RecordId) -> StorageResult<bool> {
+        if key == Value::Null {
+            return Ok(false);
+        }
+        self.check(&key)?;
+        Ok(self
+            .index
+            .delete(&Self::entry(key, record_id))
+            .await?
+            .is_some())
+    }
+
+    /// Rebuild the index, returns the number of indexed records
+    pub async fn reindex(&self) -> StorageResult<usize> {
+        self.index.reindex().await
+    }
+
+    fn entry(key: Value, record_id: RecordId) -> Vec<Value> {
+        let RecordId { page_id, slot_num } = record_id;
+        vec![
+            key,
+            Value::Bigint(((page_id as i128) << 32) | slot_num as i128),
+        ]
+    }
+
+    fn check(&self, key: &Value) -> StorageResult<()> {
+        match key.datatype() {
+            Some(datatype) if datatype == self.datatype => Ok(()),
+            _ => Err(Error::Value(format!(
+                "Can't use {} as the key of {} index",
+                key, self.datatype
+            ))),
+        }
+    }
+}
+
+/// An index of several columns of a table. Every entry is keyed by the values of the columns
+/// and the record id, so the records whose leading columns equal a prefix are a range of the
+/// index. Unlike [`SecondaryIndex`], the NULLs are indexed, so a prefix finds the records whose
+/// other columns are NULL.
+pub struct CompositeIndex {
+    index: Index<Vec<Value>>,
+    columns: Vec<usize>,
+    datatypes: Vec<DataType>,
+}
+
+impl CompositeIndex {
+    /// Index the columns at the positions, whose datatypes are in the same order
+    pub async fn new(
+        buffer_pool: Arc<BufferPoolManager>,
+        max_size: usize,
+        columns: Vec<usize>,
+        datatypes: Vec<DataType>,
+    ) -> StorageResult<Self> {
+        assert_eq!(columns.len(), datatypes.len());
+        Ok(Self {
+            index: Index::new(buffer_pool, max_size).await?,
+            columns,
+            datatypes,
+        })
+    }
+
+    /// Open the index whose tree is at the root page, see [`Index::open`]
+    pub async fn open(
+        buffer_pool: Arc<BufferPoolManager>,
+        root_page_id: PageId,
+        max_size: usize,
+        columns: Vec<usize>,
+        datatypes: Vec<DataType>,
+    ) -> StorageResult<Self> {
+        assert_eq!(columns.len(), datatypes.len());
+        Ok(Self {
+            index: Index::open(buffer_pool, root_page_id, max_size).await?,
+            columns,
+            datatypes,
+        })
+    }
+
+    /// See [`Index::root_page_id`]
+    pub async fn root_page_id(&self) -> PageId {
+        self.index.root_page_id().await
+    }
+
+    /// The values of the indexed columns in the tuple
+    pub fn key(&self, tuple: &Tuple) -> Vec<Value> {
+        self.columns
+            .iter()
+            .map(|position| tuple.field(*position).unwrap_or(Value::Null))
+            .collect()
+    }
+
+    /// The records whose leading columns equal the prefix, in the order of the key and record id
+    pub async fn search(&self, prefix: &[Value]) -> StorageResult<Vec<RecordId>> {
+        if prefix.len() > self.datatypes.len() {
+            return Err(Error::Value(format!(
+                "Can't use {} values as the prefix of {} columns",
+                prefix.len(),
+                self.datatypes.len()
+            )));
+        }
+        self.check(prefix)?;
+        // a prefix sorts before every entry it's a prefix of
+        let start = prefix.to_vec();
+        self.index
+            .search_range_while(&start.., |entry| entry.starts_with(prefix))
+            .await
+    }
+
+    pub async fn insert(&self, key: Vec<Value>, record_id: RecordId) -> StorageResult<()> {
+        self.check(&key)?;
+        self.index
+            .insert(Self::entry(key, record_id), record_id)
+            .await
+    }
+
+    pub async fn delete(&self, key: Vec<Value>, record_id: RecordId) -> StorageResult<bool> {
+        self.check(&key)?;
+        Ok(self
+            .index
+            .delete(&Self::entry(key, record_id))
+            .await?
+            .is_some())
+    }
+
+    /// Rebuild the index, returns the number of indexed records
+    pub async fn reindex(&self) -> StorageResult<usize> {
+        self.index.reindex().await
+    }
+
+    fn entry(mut key: Vec<Value>, record_id: RecordId) -> Vec<Value> {
+        let RecordId { page_id, slot_num } = record_id;
+        key.push(Value::Bigint(((page_id as i128) << 32) | slot_num as i128));
+        key
+    }
+
+    fn check(&self, key: &[Value]) -> StorageResult<()> {
+        for (value, datatype) in key.iter().zip(self.datatypes.iter()) {
+            match value.datatype() {
+                None => {}
+                Some(found) if &found == datatype => {}
+                _ => {
+                    return Err(Error::Value(format!(
+                        "Can't use {} as the key of {} index",
+                        value, datatype
+                    )))
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::buffer_pool_manager::BufferPoolManager;
+    use crate::storage::disk::disk_manager::DiskManager;
+
+    async fn buffer_pool(file: &tempfile::NamedTempFile) -> Arc<BufferPoolManager> {
+        let disk_manager = DiskManager::new(file.path()).await.unwrap();
+        Arc::new(BufferPoolManager::new(100, 2, disk_manager).await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn integer_keys() -> StorageResult<()> {
+        let file = tempfile::NamedTempFile::new()?;
+        let index = ValueIndex::new(buffer_pool(&file).await, 4, DataType::Integer).await?;
+        for i in (0..100).rev() {
+            index
+                .insert(Value::Integer(i), RecordId::new(i as usize, 0))
+                .await?;
+        }
+        assert_eq!(
+            index.search(&Value::Integer(42)).await?,
+            Some(RecordId::new(42, 0))
+        );
+        assert_eq!(
+            index
+                .search_range(&Value::Integer(10)..&Value::Integer(15))
+                .await?,
+            (10..15).map(|i| RecordId::new(i, 0)).collect::<Vec<_>>()
+        );
+        assert!(index
+            .insert(Value::Tinyint(1), RecordId::new(1, 0))
+            .await
+            .is_err());
+        assert!(index
+            .insert(Value::Null, RecordId::new(1, 0))
+            .await
+            .is_err());
+        assert!(index.search(&Value::String("a".to_string())).await.is_err());
+        assert!(index.search_range(..&Value::Bigint(1)).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn string_keys() -> StorageResult<()> {
+        let file = tempfile::NamedTempFile::new()?;
+        let index = ValueIndex::new(buffer_pool(&file).await, 4, DataType::String).await?;
+        let names = ["mike", "alice", "john", "bob", "carol", "dave", "eve"];
+        for (i, name) in names.iter().enumerate() {
+            index
+                .insert(Value::String(name.to_string()), RecordId::new(i, 0))
+                .await?;
+        }
+        assert_eq!(
+            index.first().await?,
+            Some((Value::String("alice".to_string()), RecordId::new(1, 0)))
+        );
+        assert_eq!(
+            index
+                .search_range(&Value::String("b".to_string())..&Value::String("e".to_string()))
+                .await?,
+            vec![
+                RecordId::new(3, 0),
+                RecordId::new(4, 0),
+                RecordId::new(5, 0)
+            ]
+        );
+        assert_eq!(
+            index.delete(&Value::String("john".to_string())).await?,
+            Some((Value::String("john".to_string()), RecordId::new(2, 0)))
+        );
+        assert_eq!(
+            index.search(&Value::String("john".to_string())).await?,
+            None
+        );
+        assert!(index.search(&Value::Integer(1)).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn secondary_keys() -> StorageResult<()> {
+        let file = tempfile::NamedTempFile::new()?;
+        let index = SecondaryIndex::new(buffer_pool(&file).await, 4, DataType::Integer).await?;
+        for i in 0..100 {
+            index
+                .insert(Value::Integer(i % 10), RecordId::new(i as usize, 1))
+                .await?;
+        }
+        index.insert(Value::Null, RecordId::new(100, 0)).await?;
+        assert_eq!(
+            index.search(&Value::Integer(3)).await?,
+            (0..10)
+                .map(|i| RecordId::new(i * 10 + 3, 1))
+                .collect::<Vec<_>>()
+        );
+        assert!(index.search(&Value::Null).await?.is_empty());
+        assert!(index.search(&Value::Integer(10)).await?.is_empty());
+        assert!(
+            index
+                .delete(Value::Integer(3), RecordId::new(13, 1))
+                .await?
+        );
+        assert!(
+            !index
+                .delete(Value::Integer(3), RecordId::new(13, 1))
+                .await?
+        );
+        assert_eq!(index.search(&Value::Integer(3)).await?.len(), 9);
+        assert!(index.search(&Value::Bigint(3)).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn composite_keys() -> StorageResult<()> {
+        let file = tempfile::NamedTempFile::new()?;
+        let index = CompositeIndex::new(
+            buffer_pool(&file).await,
+            4,
+            vec![0, 1],
+            vec![DataType::Integer, DataType::String],
+        )
+        .await?;
+        let name = |i: usize| match i % 3 {
+            0 => Value::Null,
+            i => Value::String(i.to_string()),
+        };
+        for i in 0..60 {
+            index
+                .insert(
+                    vec![Value::Integer((i % 5) as i64), name(i)],
+                    RecordId::new(i, 0),
+                )
+                .await?;
+        }
+        assert_eq!(
+            index.search(&[Value::Integer(2)]).await?,
+            [12, 27, 42, 57, 7, 22, 37, 52, 2, 17, 32, 47]
+                .into_iter()
+                .map(|i| RecordId::new(i, 0))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            index
+                .search(&[Value::Integer(2), Value::String("1".to_string())])
+                .await?,
+            [7, 22, 37, 52]
+                .into_iter()
+                .map(|i| RecordId::new(i, 0))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            index.search(&[Value::Integer(2), Value::Null]).await?.len(),
+            4
+        );
+        assert!(index.search(&[Value::Integer(5)]).await?.is_empty());
+        assert_eq!(index.search(&[]).await?.len(), 60);
+        assert!(
+            index
+                .delete(vec![Value::Integer(2), name(7)], RecordId::new(7, 0))
+                .await?
+        );
+        assert_eq!(index.search(&[Value::Integer(2)]).await?.len(), 11);
+        assert!(index.search(&[Value::Bigint(2)]).await.is_err());
+        assert!(index
+            .search(&[Value::Integer(2), Value::Null, Value::Null])
+            .await
+            .is_err());
+        Ok(())
+    }
+}